@@ -0,0 +1,525 @@
+//! # Shared Security Definitions
+//! 
+//! Definitions every example program re-exports, so the same failure means
+//! the same thing - and the same error code - across the whole suite.
+//! 
+//! ## Error Codes
+//! Anchor numbers each `#[error_code]` enum from an offset (6000 unless
+//! told otherwise), and every program's own enum starts at 6000. Shared
+//! variants can't live in that range without colliding with each
+//! program's local errors, so `SecurityError` is pinned to 7000.
+//! 
+//! Codes are positional: a variant's code is `7000 + its index`. Append new
+//! variants at the end; never reorder or remove one, or clients decoding
+//! the old code will misreport the error. `tests/error_codes.rs` pins the
+//! current list.
+
+use anchor_lang::prelude::*;
+
+/// First code used by `SecurityError`
+pub const SECURITY_ERROR_OFFSET: u32 = 7000;
+
+/// Failures that mean the same thing in every program.
+///
+/// Program-specific enums (`MathError`, `CpiError`, ...) keep their own
+/// variants and codes; use these for the cases that are genuinely common.
+#[error_code(offset = 7000)]
+pub enum SecurityError {
+    #[msg("Signer is not authorized for this action")]
+    Unauthorized,
+    #[msg("Amount must be greater than zero")]
+    ZeroAmount,
+    #[msg("Arithmetic overflow")]
+    Overflow,
+    #[msg("Insufficient funds")]
+    InsufficientFunds,
+    #[msg("Division by zero")]
+    DivisionByZero,
+    #[msg("Account is already initialized")]
+    AlreadyInitialized,
+    #[msg("Not enough compute units left to finish the batch")]
+    ComputeBudgetLow,
+    #[msg("Value was created or destroyed across the touched balances")]
+    ConservationViolation,
+    #[msg("Closed account still holds data or a program owner")]
+    AccountNotClosed,
+}
+
+// ============================================================================
+// COMPUTE BUDGET
+// ============================================================================
+
+/// Compute-unit checks for instructions that loop over caller-sized input.
+///
+/// A batch that runs out of compute units mid-loop aborts with a generic
+/// runtime error after doing most of the work. Checking the budget up
+/// front turns that into `ComputeBudgetLow` before anything is written,
+/// and tells the caller to split the batch.
+pub mod compute {
+    use super::*;
+    
+    /// Headroom kept for the work an instruction does outside its loop
+    pub const COMPUTE_RESERVE: u64 = 5_000;
+    
+    /// Compute units this instruction can still spend.
+    /// 
+    /// On-chain this is the runtime's meter. Native builds (the test
+    /// harness) have no meter, so they report a budget that is unlimited
+    /// unless a test lowers it with `set_native_budget`.
+    pub fn remaining_compute_units() -> u64 {
+        #[cfg(target_os = "solana")]
+        {
+            anchor_lang::solana_program::compute_units::sol_remaining_compute_units()
+        }
+        #[cfg(not(target_os = "solana"))]
+        {
+            native::BUDGET.load(std::sync::atomic::Ordering::Relaxed)
+        }
+    }
+    
+    /// Fails with `ComputeBudgetLow` unless `items` iterations of
+    /// `units_per_item` each, plus `COMPUTE_RESERVE`, still fit.
+    /// 
+    /// `units_per_item` is each instruction's own measured cost of one
+    /// iteration, with headroom; call this before the loop's first write.
+    pub fn require_compute_budget(items: usize, units_per_item: u64) -> Result<()> {
+        let needed = (items as u64)
+            .saturating_mul(units_per_item)
+            .saturating_add(COMPUTE_RESERVE);
+        let remaining = remaining_compute_units();
+        if remaining < needed {
+            msg!("Batch needs {} compute units, {} left", needed, remaining);
+            return err!(SecurityError::ComputeBudgetLow);
+        }
+        Ok(())
+    }
+    
+    /// Sets the budget `remaining_compute_units` reports in native builds.
+    /// The setting is process-wide.
+    #[cfg(not(target_os = "solana"))]
+    pub fn set_native_budget(units: u64) {
+        native::BUDGET.store(units, std::sync::atomic::Ordering::Relaxed);
+    }
+    
+    #[cfg(not(target_os = "solana"))]
+    mod native {
+        pub static BUDGET: std::sync::atomic::AtomicU64 = std::sync::atomic::AtomicU64::new(u64::MAX);
+    }
+}
+
+// ============================================================================
+// AUTHORITY CHECKS
+// ============================================================================
+
+/// The signer-plus-key check behind `ensure_authority!`.
+///
+/// Fails with Anchor's `AccountNotSigner` unless `signer` signed the
+/// transaction, then with `error` unless it is `expected`. Either check on
+/// its own is not enough: a matching key that didn't sign can be passed by
+/// anyone, and a signature from the wrong key proves nothing.
+pub fn ensure_authority(expected: &Pubkey, signer: &AccountInfo, error: impl Into<Error>) -> Result<()> {
+    if !signer.is_signer {
+        return Err(error!(anchor_lang::error::ErrorCode::AccountNotSigner));
+    }
+    if signer.key != expected {
+        return Err(error.into().with_pubkeys((*signer.key, *expected)));
+    }
+    Ok(())
+}
+
+/// Returns early unless `$signer` signed and its key is `$expected`.
+///
+/// `$signer` is anything with `to_account_info()` - `Signer`,
+/// `UncheckedAccount`, `AccountInfo`. A key mismatch fails with `$error`,
+/// or `SecurityError::Unauthorized` when it is omitted.
+///
+/// ```ignore
+/// ensure_authority!(vault.authority, ctx.accounts.authority);
+/// ```
+#[macro_export]
+macro_rules! ensure_authority {
+    ($expected:expr, $signer:expr $(,)?) => {
+        $crate::ensure_authority!($expected, $signer, $crate::SecurityError::Unauthorized)
+    };
+    ($expected:expr, $signer:expr, $error:expr $(,)?) => {
+        $crate::ensure_authority(&$expected, &::anchor_lang::ToAccountInfo::to_account_info(&$signer), $error)?
+    };
+}
+
+// ============================================================================
+// OWNER CHECKS
+// ============================================================================
+
+/// The owner check `Account<'info, T>` makes, for a raw account.
+///
+/// Fails with `error` unless `program_id` owns `info`, then with Anchor's
+/// `AccountDiscriminatorNotFound` if the account is too short to hold a
+/// discriminator, so callers can read the first 8 bytes safely. Anyone can
+/// write bytes that look like a program's account; only an account that
+/// program owns can have been written by it.
+pub fn require_owned_by(info: &AccountInfo, program_id: &Pubkey, error: impl Into<Error>) -> Result<()> {
+    if info.owner != program_id {
+        return Err(error.into().with_pubkeys((*info.owner, *program_id)));
+    }
+    require!(info.data_len() >= 8, anchor_lang::error::ErrorCode::AccountDiscriminatorNotFound);
+    Ok(())
+}
+
+// ============================================================================
+// VALUE CONSERVATION
+// ============================================================================
+
+/// Fails with `ConservationViolation` unless `before_sum == after_sum`.
+///
+/// Sum every balance an instruction touches - token amounts, lamports,
+/// bookkeeping totals and fee buckets alike - before it writes and again
+/// after. Moving value between them leaves the sum unchanged; a rounding
+/// remainder that vanished, a fee credited twice or a payout that was
+/// never debited all show up as a difference. Count each account once,
+/// however many times it was passed in.
+pub fn assert_value_conserved(before_sum: u64, after_sum: u64) -> Result<()> {
+    require_eq!(before_sum, after_sum, SecurityError::ConservationViolation);
+    Ok(())
+}
+
+/// Checked sum of `balances`, for either side of `assert_value_conserved`.
+pub fn sum_balances(balances: impl IntoIterator<Item = u64>) -> Result<u64> {
+    balances
+        .into_iter()
+        .try_fold(0u64, |sum, balance| sum.checked_add(balance))
+        .ok_or_else(|| error!(SecurityError::Overflow))
+}
+
+// ============================================================================
+// MULTIPLY-THEN-DIVIDE
+// ============================================================================
+
+/// `a * b / denom` for amounts, rates and shares.
+///
+/// The product is formed in u128, so only the result has to fit in u64.
+/// Failures come back as `MulDivError` rather than an error code: a
+/// program with its own math errors (`MathError`) maps them onto those,
+/// any other converts them into `SecurityError` with `?`.
+pub mod math {
+    use super::*;
+
+    /// Why a `mul_div` failed
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub enum MulDivError {
+        DivisionByZero,
+        Overflow,
+    }
+
+    impl From<MulDivError> for Error {
+        fn from(failure: MulDivError) -> Self {
+            match failure {
+                MulDivError::DivisionByZero => error!(SecurityError::DivisionByZero),
+                MulDivError::Overflow => error!(SecurityError::Overflow),
+            }
+        }
+    }
+
+    /// `a * b / denom`, rounded down.
+    pub fn mul_div(a: u64, b: u64, denom: u64) -> core::result::Result<u64, MulDivError> {
+        if denom == 0 {
+            return Err(MulDivError::DivisionByZero);
+        }
+        let result = u128::from(a) * u128::from(b) / u128::from(denom);
+        u64::try_from(result).map_err(|_| MulDivError::Overflow)
+    }
+
+    /// `mul_div`, rounded up. Use it for fees and penalties, so splitting
+    /// an amount into small pieces can't round each piece's charge to zero.
+    pub fn mul_div_ceil(a: u64, b: u64, denom: u64) -> core::result::Result<u64, MulDivError> {
+        let floor = mul_div(a, b, denom)?;
+        if u128::from(floor) * u128::from(denom) == u128::from(a) * u128::from(b) {
+            Ok(floor)
+        } else {
+            floor.checked_add(1).ok_or(MulDivError::Overflow)
+        }
+    }
+}
+
+// ============================================================================
+// CHECKED CLOSE
+// ============================================================================
+
+/// What a closed account leaves behind.
+///
+/// Programs embed this in their own tombstone accounts (the account type
+/// has to be declared in the program that owns it) and it is the payload
+/// of `AccountClosed`, so every close in the suite is recorded the same way.
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Tombstone {
+    /// Address of the closed account
+    pub account: Pubkey,
+    /// Whoever the closed account belonged to
+    pub original_owner: Pubkey,
+    /// Signer who performed the close
+    pub closed_by: Pubkey,
+    /// Account the rent went to
+    pub recipient: Pubkey,
+    /// Lamports moved to `recipient`
+    pub lamports: u64,
+    pub closed_at: i64,
+}
+
+/// Emitted by `checked_close` once the account is gone
+#[event]
+pub struct AccountClosed {
+    pub tombstone: Tombstone,
+}
+
+/// Closes `account` into `recipient` and returns its tombstone.
+///
+/// Does what Anchor's `close` constraint does - moves every lamport,
+/// hands the account back to the System Program and drops its data - then
+/// checks the result instead of assuming it:
+/// - the lamports only moved from `account` to `recipient`, and all of
+///   them did (`ConservationViolation`); closing an account into itself
+///   would burn them and fails here
+/// - the account is System-owned with no discriminator left
+///   (`AccountNotClosed`), so nothing can deserialize it again
+///
+/// Emits `AccountClosed`. The caller stores the returned `Tombstone` and
+/// must not also use the `close` constraint on `account`.
+pub fn checked_close<'info>(
+    account: &AccountInfo<'info>,
+    recipient: &AccountInfo<'info>,
+    original_owner: Pubkey,
+    closed_by: Pubkey,
+) -> Result<Tombstone> {
+    let lamports = account.lamports();
+    let before = sum_balances([lamports, recipient.lamports()])?;
+    
+    let credited = recipient.lamports().checked_add(lamports).ok_or(SecurityError::Overflow)?;
+    **recipient.try_borrow_mut_lamports()? = credited;
+    **account.try_borrow_mut_lamports()? = 0;
+    account.assign(&anchor_lang::system_program::ID);
+    account.realloc(0, false)?;
+    
+    require_eq!(account.lamports(), 0, SecurityError::ConservationViolation);
+    assert_value_conserved(before, sum_balances([account.lamports(), recipient.lamports()])?)?;
+    
+    let data = account.try_borrow_data()?;
+    let discriminator_zeroed = data.iter().take(8).all(|byte| *byte == 0);
+    require!(
+        discriminator_zeroed && account.owner == &anchor_lang::system_program::ID,
+        SecurityError::AccountNotClosed
+    );
+    
+    let tombstone = Tombstone {
+        account: account.key(),
+        original_owner,
+        closed_by,
+        recipient: recipient.key(),
+        lamports,
+        closed_at: Clock::get()?.unix_timestamp,
+    };
+    emit!(AccountClosed { tombstone });
+    Ok(tombstone)
+}
+
+// ============================================================================
+// ERROR CATEGORIES
+// ============================================================================
+
+/// What kind of failure an error code reports, for clients that react to
+/// errors generically rather than one code at a time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ErrorCategory {
+    /// Missing signature, wrong authority or missing permission
+    Auth,
+    /// Overflow, division by zero or value created out of nothing
+    Math,
+    /// Malformed input or the wrong account - fix the request and resend
+    Validation,
+    /// A valid request the accounts can't accept right now (or ever again)
+    State,
+}
+
+impl SecurityError {
+    pub fn category(self) -> ErrorCategory {
+        match self {
+            SecurityError::Unauthorized => ErrorCategory::Auth,
+            SecurityError::ZeroAmount | SecurityError::ComputeBudgetLow => ErrorCategory::Validation,
+            SecurityError::Overflow | SecurityError::DivisionByZero | SecurityError::ConservationViolation => ErrorCategory::Math,
+            SecurityError::InsufficientFunds | SecurityError::AlreadyInitialized | SecurityError::AccountNotClosed => {
+                ErrorCategory::State
+            }
+        }
+    }
+}
+
+/// Every `SecurityError`, in code order
+const SECURITY_ERRORS: &[SecurityError] = &[
+    SecurityError::Unauthorized,
+    SecurityError::ZeroAmount,
+    SecurityError::Overflow,
+    SecurityError::InsufficientFunds,
+    SecurityError::DivisionByZero,
+    SecurityError::AlreadyInitialized,
+    SecurityError::ComputeBudgetLow,
+    SecurityError::ConservationViolation,
+    SecurityError::AccountNotClosed,
+];
+
+/// Category of an Anchor framework or `SecurityError` code.
+///
+/// Program-local codes (6000 and up, below 7000) mean something different
+/// in every program, so they can't be classified by number alone - use
+/// `program_error_category` when the failing program is known. Codes this
+/// doesn't recognize are reported as `Validation`.
+pub fn error_category(code: u32) -> ErrorCategory {
+    use anchor_lang::error::ErrorCode as Anchor;
+
+    if let Some(shared) = code
+        .checked_sub(SECURITY_ERROR_OFFSET)
+        .and_then(|index| SECURITY_ERRORS.get(index as usize))
+    {
+        return shared.category();
+    }
+    match code {
+        c if c == Anchor::ConstraintHasOne as u32
+            || c == Anchor::ConstraintSigner as u32
+            || c == Anchor::AccountNotSigner as u32 =>
+        {
+            ErrorCategory::Auth
+        }
+        c if c == Anchor::ConstraintZero as u32
+            || c == Anchor::AccountDiscriminatorAlreadySet as u32
+            || c == Anchor::AccountNotInitialized as u32 =>
+        {
+            ErrorCategory::State
+        }
+        _ => ErrorCategory::Validation,
+    }
+}
+
+/// A program's error enum, each variant with its category.
+///
+/// Implemented with `error_categories!`, which matches on the variants
+/// themselves, so reordering the enum can't move a variant to another
+/// category and a new variant doesn't compile until it has one.
+pub trait CategorizedError: Copy + Into<u32> + 'static {
+    /// Every variant, each listed once
+    const ALL: &'static [Self];
+
+    fn category(self) -> ErrorCategory;
+}
+
+/// Category of `code` as returned by a program whose own errors are `E`.
+///
+/// Codes that aren't one of `E`'s go through `error_category`.
+pub fn program_error_category<E: CategorizedError>(code: u32) -> ErrorCategory {
+    E::ALL
+        .iter()
+        .find(|error| Into::<u32>::into(**error) == code)
+        .map_or_else(|| error_category(code), |error| error.category())
+}
+
+/// Implements `CategorizedError` for a program's error enum.
+///
+/// Every variant goes under exactly one category; the generated `match` is
+/// exhaustive, so one left out doesn't compile and one listed twice is an
+/// unreachable pattern.
+///
+/// ```ignore
+/// error_categories!(CloseError {
+///     Auth => [Unauthorized],
+///     State => [AlreadyClosed, CooldownActive],
+/// });
+/// ```
+#[macro_export]
+macro_rules! error_categories {
+    ($error:ident { $($category:ident => [$($variant:ident),+ $(,)?]),+ $(,)? }) => {
+        impl $crate::CategorizedError for $error {
+            const ALL: &'static [Self] = &[$($($error::$variant),+),+];
+
+            fn category(self) -> $crate::ErrorCategory {
+                match self {
+                    $($($error::$variant)|+ => $crate::ErrorCategory::$category,)+
+                }
+            }
+        }
+    };
+}
+
+// ============================================================================
+// LESSONS
+// ============================================================================
+
+/// One vulnerability class, taught by a VULNERABLE/SECURE pair in one
+/// program.
+///
+/// Like error codes, the list is append-only: tooling may key on the
+/// description strings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum LessonId {
+    MissingSignerCheck,
+    AccountValidation,
+    IntegerOverflow,
+    ArbitraryCpi,
+    Reinitialization,
+    TypeCosplay,
+    ClosingAccounts,
+    EscrowRelease,
+    InsecureRandomness,
+    NativeSolVault,
+    UpgradeAuthority,
+    FlashLoanGuard,
+}
+
+impl LessonId {
+    /// Every lesson, in program order
+    pub const ALL: &'static [LessonId] = &[
+        LessonId::MissingSignerCheck,
+        LessonId::AccountValidation,
+        LessonId::IntegerOverflow,
+        LessonId::ArbitraryCpi,
+        LessonId::Reinitialization,
+        LessonId::TypeCosplay,
+        LessonId::ClosingAccounts,
+        LessonId::EscrowRelease,
+        LessonId::InsecureRandomness,
+        LessonId::NativeSolVault,
+        LessonId::UpgradeAuthority,
+        LessonId::FlashLoanGuard,
+    ];
+
+    /// Crate name of the program that teaches this lesson
+    pub fn program(self) -> &'static str {
+        match self {
+            LessonId::MissingSignerCheck => "missing_signer_check",
+            LessonId::AccountValidation => "account_validation",
+            LessonId::IntegerOverflow => "integer_overflow",
+            LessonId::ArbitraryCpi => "arbitrary_cpi",
+            LessonId::Reinitialization => "reinitialization",
+            LessonId::TypeCosplay => "type_cosplay",
+            LessonId::ClosingAccounts => "closing_accounts",
+            LessonId::EscrowRelease => "escrow",
+            LessonId::InsecureRandomness => "insecure_randomness",
+            LessonId::NativeSolVault => "native_sol_vault",
+            LessonId::UpgradeAuthority => "upgrade_authority",
+            LessonId::FlashLoanGuard => "flash_loan_guard",
+        }
+    }
+}
+
+/// A short, stable description of `id`: `<slug>: <what goes wrong>`.
+pub fn describe_lesson(id: LessonId) -> &'static str {
+    match id {
+        LessonId::MissingSignerCheck => "missing-signer-check: authority accepted without a signature",
+        LessonId::AccountValidation => "account-validation: owner, PDA or relationship of an account not verified",
+        LessonId::IntegerOverflow => "integer-overflow: unchecked arithmetic or casts wrap silently",
+        LessonId::ArbitraryCpi => "arbitrary-cpi: invoked program id not verified",
+        LessonId::Reinitialization => "reinitialization: initialized account can be initialized again",
+        LessonId::TypeCosplay => "type-cosplay: account of one type accepted as another",
+        LessonId::ClosingAccounts => "closing-accounts: closed account revived or its rent stolen",
+        LessonId::EscrowRelease => "escrow-release: release condition supplied by the caller",
+        LessonId::InsecureRandomness => "insecure-randomness: outcome derived from public on-chain data",
+        LessonId::NativeSolVault => "native-sol-vault: SOL debited from a program-owned PDA through the System Program",
+        LessonId::UpgradeAuthority => "upgrade-authority: admin taken by whoever initializes the config first",
+        LessonId::FlashLoanGuard => "flash-loan-guard: loan repaid with a deposit that credits the borrower",
+    }
+}
@@ -16,6 +16,9 @@
 //! allowed attackers to mint tokens without proper authorization.
 
 use anchor_lang::prelude::*;
+use anchor_lang::system_program::{self, Transfer};
+use security_common::{assert_value_conserved, ensure_authority, error_categories, sum_balances};
+pub use security_common::SecurityError;
 
 declare_id!("Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLnS");
 
@@ -42,24 +45,36 @@ pub mod missing_signer_check {
     ///    - vault = victim's vault
     ///    - recipient = attacker's account
     /// 3. Funds transfer to attacker because authority is never verified as signer
-    pub fn withdraw_vulnerable(ctx: Context<WithdrawVulnerable>, amount: u64) -> Result<()> {
+    /// 
+    /// Emits a `WithdrawEvent` for the amount actually moved, and returns it
+    /// through return data.
+    pub fn withdraw_vulnerable(ctx: Context<WithdrawVulnerable>, amount: u64) -> Result<WithdrawEvent> {
         let vault = &mut ctx.accounts.vault;
         
         // DANGER: We check if authority matches, but NEVER check if they signed!
         // This is security theater - the check is meaningless without signature verification
         require!(
             vault.authority == ctx.accounts.authority.key(),
-            CustomError::Unauthorized
+            SecurityError::Unauthorized
         );
 
-        // Transfer funds (would succeed for any attacker who knows the authority pubkey)
-        let transfer_amount = amount.min(vault.balance);
-        vault.balance = vault.balance.checked_sub(transfer_amount).unwrap();
+        // Transfer funds (would succeed for any attacker who knows the authority pubkey).
+        // The lifetime cap still applies, but to whoever calls - a limit only
+        // bounds the damage, it is no substitute for a signer check
+        let transfer_amount = vault.book_withdrawal(amount)?;
         
-        // In real code, this would transfer lamports to recipient
+        // The lamports really leave - to whoever the caller named
+        pay_out(&vault.to_account_info(), &ctx.accounts.recipient, transfer_amount)?;
         msg!("VULNERABLE: Transferred {} lamports", transfer_amount);
         
-        Ok(())
+        let event = WithdrawEvent {
+            vault: vault.key(),
+            authority: ctx.accounts.authority.key(),
+            amount: transfer_amount,
+            remaining_balance: vault.balance,
+        };
+        emit!(event.clone());
+        Ok(event)
     }
 
     // ============================================================================
@@ -77,30 +92,323 @@ pub mod missing_signer_check {
     /// ## Defense in Depth:
     /// We also keep the authority pubkey check as a secondary verification,
     /// ensuring the signer is actually the vault's designated authority.
-    pub fn withdraw_secure(ctx: Context<WithdrawSecure>, amount: u64) -> Result<()> {
+    /// A signer that fails it is reported in a `WithdrawDenied` event.
+    /// 
+    /// Emits a `WithdrawEvent` for the amount actually moved, and returns it
+    /// through return data.
+    pub fn withdraw_secure(ctx: Context<WithdrawSecure>, amount: u64) -> Result<WithdrawEvent> {
+        // Reject no-op calls - they mask client bugs and log misleading "success"
+        require!(amount > 0, SecurityError::ZeroAmount);
+        
         let vault = &mut ctx.accounts.vault;
         
-        // SECURE: authority.key() check combined with Signer constraint
-        // The Signer constraint (in account struct) ensures they actually signed
-        // This check ensures the signer is the CORRECT authority for this vault
+        // SECURE: The signer must be the CORRECT authority for this vault.
+        // `ensure_authority!` checks the signature and the key together, so
+        // it stays correct even if `authority` is ever loosened from `Signer`
+        if vault.authority != ctx.accounts.authority.key() {
+            emit!(WithdrawDenied { vault: vault.key(), attempted_authority: ctx.accounts.authority.key() });
+        }
+        ensure_authority!(vault.authority, ctx.accounts.authority);
+
+        // SECURE: Enforce the lifetime cap on the aggregate, not per call -
+        // splitting a large withdrawal into many small ones doesn't help
+        let transfer_amount = vault.book_withdrawal(amount)?;
+        
+        pay_out(&vault.to_account_info(), &ctx.accounts.recipient, transfer_amount)?;
+        msg!("SECURE: Transferred {} lamports", transfer_amount);
+        
+        let event = WithdrawEvent {
+            vault: vault.key(),
+            authority: ctx.accounts.authority.key(),
+            amount: transfer_amount,
+            remaining_balance: vault.balance,
+        };
+        emit!(event.clone());
+        Ok(event)
+    }
+
+    // ============================================================================
+    // HAND-ROLLED SIGNER CHECKS
+    // ============================================================================
+
+    /// VULNERABLE: Takes the authority as a raw `AccountInfo` and only
+    /// compares its key.
+    /// 
+    /// ## What's Wrong?
+    /// `AccountInfo` is the escape hatch out of Anchor's account types: it
+    /// carries no constraints, so nothing checks it unless the handler
+    /// does. Matching the key proves only that the caller knows the
+    /// vault's authority - every pubkey is public - not that the authority
+    /// approved this withdrawal. `is_signer` is the only part the caller
+    /// can't forge, and it's the part left out here.
+    pub fn withdraw_manual_missing_is_signer(ctx: Context<WithdrawManual>, amount: u64) -> Result<()> {
+        let vault = &mut ctx.accounts.vault;
+        
+        // DANGER: Key equality alone - the same mistake as `withdraw_vulnerable`,
+        // just without the `UncheckedAccount` name to warn the reader
+        require!(vault.authority == ctx.accounts.authority.key(), SecurityError::Unauthorized);
+        
+        let transfer_amount = vault.book_withdrawal(amount)?;
+        pay_out(&vault.to_account_info(), &ctx.accounts.recipient, transfer_amount)?;
+        
+        msg!("VULNERABLE: Transferred {} lamports", transfer_amount);
+        Ok(())
+    }
+
+    /// SECURE: The same raw `AccountInfo`, checked by hand.
+    /// 
+    /// ## What's Fixed?
+    /// Hand-rolled programs that can't use `Signer` have to do both halves
+    /// themselves: `is_signer` proves the account approved the transaction,
+    /// the key comparison proves it is this vault's authority. Either one
+    /// alone lets someone else withdraw.
+    pub fn withdraw_manual_signer_check(ctx: Context<WithdrawManual>, amount: u64) -> Result<()> {
+        require!(amount > 0, SecurityError::ZeroAmount);
+        
+        let vault = &mut ctx.accounts.vault;
+        let authority = &ctx.accounts.authority;
+        
+        // SECURE: Signature first, then identity
+        require!(authority.is_signer, SecurityError::Unauthorized);
+        require!(vault.authority == authority.key(), SecurityError::Unauthorized);
+        
+        let transfer_amount = vault.book_withdrawal(amount)?;
+        pay_out(&vault.to_account_info(), &ctx.accounts.recipient, transfer_amount)?;
+        
+        msg!("SECURE: Transferred {} lamports", transfer_amount);
+        Ok(())
+    }
+
+    // ============================================================================
+    // MULTISIG: TIMELOCKED THRESHOLD CHANGES
+    // ============================================================================
+
+    /// Initialize a vault controlled by up to three signers.
+    /// 
+    /// `timelock_secs` is the delay every threshold change must wait out
+    /// before it can be applied. The creator funds the vault with
+    /// `initial_balance` lamports on top of its rent.
+    pub fn initialize_multisig_vault(
+        ctx: Context<InitializeMultisigVault>,
+        signers: [Pubkey; 3],
+        threshold: u8,
+        timelock_secs: i64,
+        initial_balance: u64,
+    ) -> Result<()> {
+        // Duplicate keys would let one signer count as several
         require!(
-            vault.authority == ctx.accounts.authority.key(),
-            CustomError::Unauthorized
+            signers[0] != signers[1] && signers[0] != signers[2] && signers[1] != signers[2],
+            MultisigError::DuplicateSigner
+        );
+        require!(
+            (1..=signers.len() as u8).contains(&threshold),
+            MultisigError::InvalidThreshold
         );
+        require!(timelock_secs >= 0, MultisigError::InvalidTimelock);
+        
+        system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.creator.to_account_info(),
+                    to: ctx.accounts.multisig.to_account_info(),
+                },
+            ),
+            initial_balance,
+        )?;
+        
+        let multisig = &mut ctx.accounts.multisig;
+        multisig.creator = ctx.accounts.creator.key();
+        multisig.signers = signers;
+        multisig.threshold = threshold;
+        multisig.timelock_secs = timelock_secs;
+        multisig.balance = initial_balance;
+        multisig.pending_threshold = 0;
+        multisig.threshold_change_eta = 0;
+        multisig.bump = ctx.bumps.multisig;
+        Ok(())
+    }
 
-        let transfer_amount = amount.min(vault.balance);
-        vault.balance = vault.balance.checked_sub(transfer_amount).unwrap();
+    /// SECURE: Withdraws once `threshold` of the configured signers approve.
+    /// 
+    /// Approving signers are passed in `remaining_accounts`. `count_approvals`
+    /// counts each configured key at most once and only if it actually
+    /// signed, so neither repeating one signer's account nor listing a
+    /// signer who didn't sign gets closer to the threshold.
+    pub fn withdraw_multisig(ctx: Context<WithdrawMultisig>, amount: u64) -> Result<()> {
+        require!(amount > 0, SecurityError::ZeroAmount);
         
-        msg!("SECURE: Transferred {} lamports", transfer_amount);
+        let multisig = &mut ctx.accounts.multisig;
+        require!(
+            count_approvals(&multisig.signers, ctx.remaining_accounts) >= multisig.threshold,
+            MultisigError::ThresholdNotMet
+        );
+        
+        multisig.balance = multisig.balance
+            .checked_sub(amount)
+            .ok_or(SecurityError::InsufficientFunds)?;
+        pay_out(&multisig.to_account_info(), &ctx.accounts.recipient, amount)?;
+        
+        msg!("SECURE: Transferred {} lamports with {}-of-3 approval", amount, multisig.threshold);
+        Ok(())
+    }
+
+    /// SECURE: Proposes a new threshold, approved by the CURRENT threshold.
+    /// 
+    /// ## Why a Timelock?
+    /// If one key is compromised, the attacker's first move is to lower the
+    /// threshold to 1 and drain the vault alone. Requiring the current
+    /// threshold to approve blocks that, and the delay gives the honest
+    /// signers time to notice a proposal pushed through by colluding keys
+    /// and move funds or overwrite it with a new proposal.
+    /// 
+    /// Approving signers are passed in `remaining_accounts`.
+    pub fn propose_threshold_change(
+        ctx: Context<ProposeThresholdChange>,
+        new_threshold: u8,
+    ) -> Result<()> {
+        let multisig = &mut ctx.accounts.multisig;
+        
+        require!(
+            count_approvals(&multisig.signers, ctx.remaining_accounts) >= multisig.threshold,
+            MultisigError::ThresholdNotMet
+        );
+        require!(
+            (1..=multisig.signers.len() as u8).contains(&new_threshold),
+            MultisigError::InvalidThreshold
+        );
+        
+        let now = Clock::get()?.unix_timestamp;
+        multisig.pending_threshold = new_threshold;
+        multisig.threshold_change_eta = now
+            .checked_add(multisig.timelock_secs)
+            .ok_or(MultisigError::InvalidTimelock)?;
+        
+        msg!(
+            "SECURE: Threshold change {} -> {} proposed, applicable at {}",
+            multisig.threshold, new_threshold, multisig.threshold_change_eta
+        );
+        Ok(())
+    }
+
+    /// Applies a pending threshold change once its timelock has elapsed.
+    /// 
+    /// Permissionless: the approval already happened at proposal time, so
+    /// anyone may crank the change through after the delay.
+    pub fn apply_threshold_change(ctx: Context<ApplyThresholdChange>) -> Result<()> {
+        let multisig = &mut ctx.accounts.multisig;
+        
+        require!(multisig.pending_threshold != 0, MultisigError::NoPendingChange);
+        require!(
+            Clock::get()?.unix_timestamp >= multisig.threshold_change_eta,
+            MultisigError::TimelockNotElapsed
+        );
+        
+        multisig.threshold = multisig.pending_threshold;
+        multisig.pending_threshold = 0;
+        multisig.threshold_change_eta = 0;
+        
+        msg!("SECURE: Threshold is now {}", multisig.threshold);
+        Ok(())
+    }
+
+    // ============================================================================
+    // DELEGATED WITHDRAWALS
+    // ============================================================================
+
+    /// Lets `delegate` withdraw from the vault until `expires_at`.
+    /// 
+    /// Only the vault authority, as a `Signer`, can grant this: the
+    /// delegation is itself an authorization and needs the same check as a
+    /// withdrawal.
+    pub fn approve_delegate(ctx: Context<ApproveDelegate>, delegate: Pubkey, expires_at: i64) -> Result<()> {
+        require!(expires_at > Clock::get()?.unix_timestamp, CustomError::InvalidExpiry);
+        
+        let record = &mut ctx.accounts.delegate_record;
+        record.vault = ctx.accounts.vault.key();
+        record.delegate = delegate;
+        record.expires_at = expires_at;
+        record.bump = ctx.bumps.delegate_record;
+        
+        msg!("Delegate {} approved until {}", delegate, expires_at);
+        Ok(())
+    }
+
+    /// Withdraws the delegation before it expires; the record is closed
+    /// and its rent returned to the authority.
+    pub fn revoke_delegate(ctx: Context<RevokeDelegate>) -> Result<()> {
+        msg!("Delegate {} revoked", ctx.accounts.delegate_record.delegate);
+        Ok(())
+    }
+
+    /// VULNERABLE: Checks who the delegate is, but not whether the
+    /// delegation is still in force.
+    /// 
+    /// ## What's Wrong?
+    /// The program acts as a deputy for the vault authority, on the strength
+    /// of a grant the authority made once. A grant with an expiry that
+    /// nothing enforces never really expires: a delegate whose access the
+    /// authority thought had lapsed can still drain the vault.
+    pub fn withdraw_as_delegate_vulnerable(ctx: Context<WithdrawAsDelegate>, amount: u64) -> Result<()> {
+        // DANGER: `expires_at` is never compared with the clock
+        let vault = &mut ctx.accounts.vault;
+        let transfer_amount = vault.book_withdrawal(amount)?;
+        pay_out(&vault.to_account_info(), &ctx.accounts.recipient, transfer_amount)?;
         
+        msg!("VULNERABLE: Delegate transferred {} lamports", transfer_amount);
+        Ok(())
+    }
+
+    /// SECURE: A delegate withdraws only while its grant is unexpired.
+    /// 
+    /// ## What's Fixed?
+    /// - The delegate signs, and `has_one = delegate` ties the signer to the
+    ///   record
+    /// - `has_one = vault` ties the record to this vault, so a grant on one
+    ///   vault can't be used against another
+    /// - The grant is honoured only while `now < expires_at`
+    pub fn withdraw_as_delegate(ctx: Context<WithdrawAsDelegate>, amount: u64) -> Result<()> {
+        require!(amount > 0, SecurityError::ZeroAmount);
+        require!(
+            Clock::get()?.unix_timestamp < ctx.accounts.delegate_record.expires_at,
+            CustomError::DelegateExpired
+        );
+        
+        let vault = &mut ctx.accounts.vault;
+        let transfer_amount = vault.book_withdrawal(amount)?;
+        pay_out(&vault.to_account_info(), &ctx.accounts.recipient, transfer_amount)?;
+        
+        msg!("SECURE: Delegate transferred {} lamports", transfer_amount);
         Ok(())
     }
 
     /// Initialize a vault for demonstration
-    pub fn initialize_vault(ctx: Context<InitializeVault>, initial_balance: u64) -> Result<()> {
+    /// 
+    /// `withdrawal_limit` caps the total amount that can ever be withdrawn
+    /// from this vault, across all sessions. The authority funds the vault
+    /// with `initial_balance` lamports on top of its rent, so `balance`
+    /// matches what can actually be withdrawn.
+    pub fn initialize_vault(
+        ctx: Context<InitializeVault>,
+        initial_balance: u64,
+        withdrawal_limit: u64,
+    ) -> Result<()> {
+        system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.authority.to_account_info(),
+                    to: ctx.accounts.vault.to_account_info(),
+                },
+            ),
+            initial_balance,
+        )?;
+        
         let vault = &mut ctx.accounts.vault;
         vault.authority = ctx.accounts.authority.key();
         vault.balance = initial_balance;
+        vault.total_withdrawn = 0;
+        vault.withdrawal_limit = withdrawal_limit;
         vault.bump = ctx.bumps.vault;
         Ok(())
     }
@@ -156,8 +464,23 @@ pub struct WithdrawSecure<'info> {
     pub recipient: UncheckedAccount<'info>,
 }
 
+/// Shared by both hand-rolled checks; only the handlers differ
+#[derive(Accounts)]
+pub struct WithdrawManual<'info> {
+    #[account(mut)]
+    pub vault: Account<'info, Vault>,
+    
+    /// CHECK: Deliberately a raw `AccountInfo` - Anchor verifies nothing
+    /// about it, so whether it signed is entirely up to the handler
+    pub authority: AccountInfo<'info>,
+    
+    /// CHECK: Recipient account for withdrawn funds
+    #[account(mut)]
+    pub recipient: UncheckedAccount<'info>,
+}
+
 #[derive(Accounts)]
-#[instruction(initial_balance: u64)]
+#[instruction(initial_balance: u64, withdrawal_limit: u64)]
 pub struct InitializeVault<'info> {
     #[account(
         init,
@@ -174,6 +497,169 @@ pub struct InitializeVault<'info> {
     pub system_program: Program<'info, System>,
 }
 
+#[derive(Accounts)]
+pub struct InitializeMultisigVault<'info> {
+    #[account(
+        init,
+        payer = creator,
+        space = 8 + MultisigVault::INIT_SPACE,
+        seeds = [b"multisig", creator.key().as_ref()],
+        bump
+    )]
+    pub multisig: Account<'info, MultisigVault>,
+    
+    #[account(mut)]
+    pub creator: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ProposeThresholdChange<'info> {
+    #[account(
+        mut,
+        seeds = [b"multisig", multisig.creator.as_ref()],
+        bump = multisig.bump,
+    )]
+    pub multisig: Account<'info, MultisigVault>,
+    // remaining_accounts: approving signers
+}
+
+#[derive(Accounts)]
+pub struct WithdrawMultisig<'info> {
+    #[account(
+        mut,
+        seeds = [b"multisig", multisig.creator.as_ref()],
+        bump = multisig.bump,
+    )]
+    pub multisig: Account<'info, MultisigVault>,
+    
+    /// CHECK: Recipient account for withdrawn funds
+    #[account(mut)]
+    pub recipient: UncheckedAccount<'info>,
+    // remaining_accounts: approving signers
+}
+
+#[derive(Accounts)]
+pub struct ApplyThresholdChange<'info> {
+    #[account(
+        mut,
+        seeds = [b"multisig", multisig.creator.as_ref()],
+        bump = multisig.bump,
+    )]
+    pub multisig: Account<'info, MultisigVault>,
+}
+
+#[derive(Accounts)]
+#[instruction(delegate: Pubkey)]
+pub struct ApproveDelegate<'info> {
+    #[account(
+        seeds = [b"vault", authority.key().as_ref()],
+        bump = vault.bump,
+        has_one = authority,
+    )]
+    pub vault: Account<'info, Vault>,
+    
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + Delegate::INIT_SPACE,
+        seeds = [b"delegate", vault.key().as_ref(), delegate.as_ref()],
+        bump
+    )]
+    pub delegate_record: Account<'info, Delegate>,
+    
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RevokeDelegate<'info> {
+    #[account(
+        seeds = [b"vault", authority.key().as_ref()],
+        bump = vault.bump,
+        has_one = authority,
+    )]
+    pub vault: Account<'info, Vault>,
+    
+    #[account(
+        mut,
+        seeds = [b"delegate", vault.key().as_ref(), delegate_record.delegate.as_ref()],
+        bump = delegate_record.bump,
+        has_one = vault,
+        close = authority,
+    )]
+    pub delegate_record: Account<'info, Delegate>,
+    
+    #[account(mut)]
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawAsDelegate<'info> {
+    #[account(
+        mut,
+        seeds = [b"vault", vault.authority.as_ref()],
+        bump = vault.bump,
+    )]
+    pub vault: Account<'info, Vault>,
+    
+    #[account(
+        seeds = [b"delegate", vault.key().as_ref(), delegate.key().as_ref()],
+        bump = delegate_record.bump,
+        has_one = vault,
+        has_one = delegate,
+    )]
+    pub delegate_record: Account<'info, Delegate>,
+    
+    pub delegate: Signer<'info>,
+    
+    /// CHECK: Recipient account for withdrawn funds
+    #[account(mut)]
+    pub recipient: UncheckedAccount<'info>,
+}
+
+// ============================================================================
+// LAMPORT TRANSFER
+// ============================================================================
+
+/// Moves `amount` lamports from the program-owned `vault` to `recipient`.
+/// 
+/// The vault holds data, so the System Program can't debit it; the program
+/// owns it and adjusts both balances directly instead. The vault must stay
+/// rent-exempt afterwards, or it could be reaped along with its state.
+fn pay_out(vault: &AccountInfo, recipient: &AccountInfo, amount: u64) -> Result<()> {
+    let available = vault.lamports().saturating_sub(Rent::get()?.minimum_balance(vault.data_len()));
+    require!(amount <= available, CustomError::BelowRentExempt);
+    
+    let before = sum_balances([vault.lamports(), recipient.lamports()])?;
+    vault.sub_lamports(amount)?;
+    recipient.add_lamports(amount)?;
+    // Both balances re-read: whatever left the vault reached the recipient
+    assert_value_conserved(before, sum_balances([vault.lamports(), recipient.lamports()])?)
+}
+
+// ============================================================================
+// MULTISIG HELPERS
+// ============================================================================
+
+/// Counts how many distinct configured signers actually signed.
+/// 
+/// Each configured slot is counted at most once, so passing the same signer
+/// account several times can't inflate the count, and an account that
+/// matches a configured key but didn't sign counts for nothing.
+pub fn count_approvals(signers: &[Pubkey; 3], accounts: &[AccountInfo]) -> u8 {
+    let mut approved = [false; 3];
+    for info in accounts.iter().filter(|info| info.is_signer) {
+        if let Some(slot) = signers.iter().position(|signer| signer == info.key) {
+            approved[slot] = true;
+        }
+    }
+    approved.iter().filter(|approved| **approved).count() as u8
+}
+
 // ============================================================================
 // STATE
 // ============================================================================
@@ -181,24 +667,139 @@ pub struct InitializeVault<'info> {
 #[account]
 #[derive(InitSpace)]
 pub struct Vault {
-    /// The only pubkey authorized to withdraw from this vault
+    /// The pubkey authorized to withdraw from this vault and approve delegates
     pub authority: Pubkey,
     /// Current balance in the vault
     pub balance: u64,
+    /// Cumulative amount withdrawn over the vault's lifetime
+    pub total_withdrawn: u64,
+    /// Lifetime cap on `total_withdrawn`
+    pub withdrawal_limit: u64,
+    /// PDA bump seed
+    pub bump: u8,
+}
+
+impl Vault {
+    /// Applies the lifetime cap and debits `balance` for a withdrawal of up
+    /// to `amount`, returning how much is actually paid out.
+    pub fn book_withdrawal(&mut self, amount: u64) -> Result<u64> {
+        let transfer_amount = amount.min(self.balance);
+        let total_withdrawn = self
+            .total_withdrawn
+            .checked_add(transfer_amount)
+            .ok_or(CustomError::WithdrawalLimitReached)?;
+        require!(total_withdrawn <= self.withdrawal_limit, CustomError::WithdrawalLimitReached);
+        self.total_withdrawn = total_withdrawn;
+        self.balance -= transfer_amount;
+        Ok(transfer_amount)
+    }
+}
+
+/// Vault controlled by a threshold of configured signers
+#[account]
+#[derive(InitSpace)]
+pub struct MultisigVault {
+    /// Payer at creation, used only for PDA derivation
+    pub creator: Pubkey,
+    /// Keys allowed to approve actions
+    pub signers: [Pubkey; 3],
+    /// Number of distinct signers required
+    pub threshold: u8,
+    /// Delay before a proposed threshold change can be applied
+    pub timelock_secs: i64,
+    /// Current balance in the vault
+    pub balance: u64,
+    /// Proposed threshold waiting on the timelock (0 = none)
+    pub pending_threshold: u8,
+    /// Earliest unix timestamp at which `pending_threshold` may be applied
+    pub threshold_change_eta: i64,
+    /// PDA bump seed
+    pub bump: u8,
+}
+
+/// Withdrawal rights granted by a vault's authority to another key
+#[account]
+#[derive(InitSpace)]
+pub struct Delegate {
+    /// Vault the grant applies to
+    pub vault: Pubkey,
+    /// Key allowed to withdraw on the authority's behalf
+    pub delegate: Pubkey,
+    /// Unix timestamp from which the grant no longer applies
+    pub expires_at: i64,
     /// PDA bump seed
     pub bump: u8,
 }
 
+// ============================================================================
+// EVENTS
+// ============================================================================
+
+/// A withdrawal that went through
+#[event]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WithdrawEvent {
+    pub vault: Pubkey,
+    pub authority: Pubkey,
+    /// Lamports actually moved, after clamping to the vault's balance
+    pub amount: u64,
+    pub remaining_balance: u64,
+}
+
+/// A signer that isn't the vault's authority tried to withdraw
+#[event]
+pub struct WithdrawDenied {
+    pub vault: Pubkey,
+    pub attempted_authority: Pubkey,
+}
+
 // ============================================================================
 // ERRORS
 // ============================================================================
 
 #[error_code]
 pub enum CustomError {
-    #[msg("You are not authorized to perform this action")]
-    Unauthorized,
+    // 0: `Unauthorized` moved to `SecurityError`
+    #[msg("Cumulative withdrawals would exceed the vault's lifetime limit")]
+    WithdrawalLimitReached = 1,
+    // 2: `ZeroAmount` moved to `SecurityError`
+    #[msg("Withdrawal would leave the vault below rent exemption")]
+    BelowRentExempt = 3,
+    #[msg("Delegation has expired")]
+    DelegateExpired,
+    #[msg("Delegation must expire in the future")]
+    InvalidExpiry,
+}
+
+error_categories!(CustomError {
+    Auth => [DelegateExpired],
+    Validation => [InvalidExpiry],
+    State => [WithdrawalLimitReached, BelowRentExempt],
+});
+
+/// Offset past `CustomError` so the two enums never share an error code
+#[error_code(offset = 6100)]
+pub enum MultisigError {
+    #[msg("Not enough distinct configured signers approved")]
+    ThresholdNotMet,
+    #[msg("Threshold must be between 1 and the number of signers")]
+    InvalidThreshold,
+    #[msg("Multisig signers must be distinct")]
+    DuplicateSigner,
+    #[msg("Timelock must be non-negative and must not overflow")]
+    InvalidTimelock,
+    #[msg("No threshold change is pending")]
+    NoPendingChange,
+    #[msg("Threshold change timelock has not elapsed")]
+    TimelockNotElapsed,
 }
 
+error_categories!(MultisigError {
+    Auth => [ThresholdNotMet, DuplicateSigner],
+    Validation => [InvalidThreshold, InvalidTimelock],
+    State => [NoPendingChange, TimelockNotElapsed],
+});
+
 // ============================================================================
 // COMPARISON TABLE
 // ============================================================================
@@ -211,4 +812,12 @@ pub enum CustomError {
 // | Runtime Behavior    | Silently accepts any pubkey   | Fails if not signed         |
 // | Code Complexity     | Same                          | Same (constraint only)      |
 //
+// Hand-rolled, with `authority: AccountInfo`:
+//
+// | Check                         | withdraw_manual_missing_is_signer | withdraw_manual_signer_check |
+// |-------------------------------|-----------------------------------|------------------------------|
+// | `authority.is_signer`         | Missing                           | Required                     |
+// | `vault.authority == key`      | Required                          | Required                     |
+// | Attacker naming the authority | Withdraws                         | Unauthorized                 |
+//
 // ============================================================================
@@ -17,7 +17,13 @@
 //! The program MUST validate every account is what it claims to be.
 
 use anchor_lang::prelude::*;
-use anchor_spl::token::{Token, TokenAccount};
+use anchor_lang::solana_program::pubkey::{MAX_SEEDS, MAX_SEED_LEN};
+use anchor_lang::Discriminator;
+use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::token::{self, Mint, Token, TokenAccount, Transfer};
+use security_common::compute::require_compute_budget;
+use security_common::{assert_value_conserved, error_categories, math, require_owned_by, sum_balances};
+pub use security_common::SecurityError;
 
 declare_id!("Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLnT");
 
@@ -38,10 +44,12 @@ pub mod account_validation {
     /// 
     /// ## Attack Scenario:
     /// 1. Attacker creates their own account with System Program as owner
-    /// 2. Attacker writes data that matches Pool struct layout
+    /// 2. Attacker writes data that matches Pool struct layout, naming the
+    ///    victim as `authority`
     /// 3. Attacker sets total_deposited to 0 and reward_rate to 1000000
-    /// 4. Attacker claims massive rewards from a fake pool state
-    pub fn claim_rewards_vulnerable(ctx: Context<ClaimRewardsVulnerable>) -> Result<()> {
+    /// 4. The program signs for the victim pool's reward vault and pays out
+    ///    the fabricated reward_rate - up to the whole vault
+    pub fn claim_rewards_vulnerable(ctx: Context<ClaimRewardsVulnerable>) -> Result<u64> {
         // DANGER: We're reading from an unvalidated account!
         // The pool_info data could be completely fabricated by an attacker
         let pool_info = &ctx.accounts.pool_info;
@@ -49,28 +57,128 @@ pub mod account_validation {
         
         // Skip discriminator, parse as if it's a Pool
         // Attacker controls this data entirely!
+        let authority = Pubkey::try_from(&data[8..8+32]).unwrap();
         let reward_rate = u64::from_le_bytes(data[8+32+8..8+32+8+8].try_into().unwrap());
+        drop(data);
         
-        msg!("VULNERABLE: Claiming with reward_rate: {}", reward_rate);
-        // Would transfer reward_rate tokens to user...
+        // The signer derivation is right; the authority it uses is not
+        let (pool_signer, bump) = Pubkey::find_program_address(&[b"pool", authority.as_ref()], ctx.program_id);
+        require_keys_eq!(ctx.accounts.pool_signer.key(), pool_signer, ValidationError::InvalidPDA);
         
-        Ok(())
+        msg!("VULNERABLE: Claiming with reward_rate: {}", reward_rate);
+        let accounts = ctx.accounts;
+        let reward = reward_rate.min(accounts.reward_vault_token_account.amount);
+        pay_out(
+            &accounts.token_program,
+            &mut accounts.reward_vault_token_account,
+            accounts.user_token_account.to_account_info(),
+            accounts.pool_signer.to_account_info(),
+            &authority,
+            bump,
+            reward,
+        )
     }
 
     /// SECURE: Uses Anchor's Account<> wrapper which validates:
     /// 1. Account owner matches the program ID
     /// 2. Account data deserializes correctly
     /// 3. Account discriminator is correct
-    pub fn claim_rewards_secure(ctx: Context<ClaimRewardsSecure>) -> Result<()> {
-        let pool = &ctx.accounts.pool;
+    /// 
+    /// The pool must also be solvent: its token account has to cover every
+    /// reward accrued so far, this claim's included, or no one can claim.
+    /// 
+    /// Pays the claimant's position its `accrued_rewards`, brought up to
+    /// date first, in `pool.reward_mint` from the pool's reward vault, and
+    /// returns the amount paid. The position is zeroed before the transfer,
+    /// so claiming again right away pays nothing, and what's paid comes off
+    /// `pool.total_accrued`.
+    pub fn claim_rewards_secure(ctx: Context<ClaimRewardsSecure>) -> Result<u64> {
+        let now = Clock::get()?.unix_timestamp;
+        accrue_rewards(&mut ctx.accounts.pool, &mut ctx.accounts.user_deposit, now)?;
+        guards::assert_solvent(&ctx)?;
+        
+        let accounts = ctx.accounts;
+        // SECURE: Taken before the transfer - what's paid is never owed again
+        let reward = std::mem::take(&mut accounts.user_deposit.accrued_rewards);
         
         // SECURE: pool is guaranteed to be:
         // - Owned by this program
         // - Correctly deserialized
         // - Has valid discriminator
-        msg!("SECURE: Claiming with reward_rate: {}", pool.reward_rate);
+        let pool = &mut accounts.pool;
+        pool.total_accrued = pool
+            .total_accrued
+            .checked_sub(reward)
+            .ok_or(SecurityError::InsufficientFunds)?;
+        let (authority, bump) = (pool.authority, pool.bump);
+        msg!("SECURE: Claiming {} accrued rewards", reward);
+        pay_out(
+            &accounts.token_program,
+            &mut accounts.reward_vault_token_account,
+            accounts.user_token_account.to_account_info(),
+            accounts.pool.to_account_info(),
+            &authority,
+            bump,
+            reward,
+        )
+    }
+
+    /// SECURE: The owner and discriminator checks of `Account<>`, by hand.
+    /// 
+    /// For programs that have to take the pool as a raw account. Nothing
+    /// is read from `pool_info` until `require_owned_by` has passed and its
+    /// first 8 bytes are `Pool`'s discriminator; the reward vault, the
+    /// user's token account and the claimant's position are then checked
+    /// against the pool like the constraints on `ClaimRewardsSecure` would.
+    /// 
+    /// Pays what the position has accrued, zeroed before the transfer, as
+    /// `claim_rewards_secure` does, and writes the pool's `total_accrued`
+    /// back to `pool_info`.
+    pub fn claim_rewards_manual_owner_check(ctx: Context<ClaimRewardsManualOwnerCheck>) -> Result<u64> {
+        let pool_info = &ctx.accounts.pool_info;
         
-        Ok(())
+        // SECURE: Owned by this program, and a Pool rather than any other type
+        require_owned_by(pool_info, ctx.program_id, ValidationError::InvalidOwner)?;
+        let mut pool = {
+            let data = pool_info.try_borrow_data()?;
+            require!(data[..8] == Pool::DISCRIMINATOR, ErrorCode::AccountDiscriminatorMismatch);
+            Pool::try_deserialize_unchecked(&mut &data[..])?
+        };
+        
+        let vault = &ctx.accounts.reward_vault_token_account;
+        require_keys_eq!(vault.owner, pool_info.key(), ValidationError::TokenAccountOwnerMismatch);
+        require_keys_eq!(vault.mint, pool.reward_mint, ValidationError::TokenMintMismatch);
+        require_keys_eq!(ctx.accounts.user_token_account.mint, pool.reward_mint, ValidationError::TokenMintMismatch);
+        
+        // SECURE: The claimant's own position in this pool, as `seeds` would check
+        let (user_deposit, _) = Pubkey::find_program_address(
+            &[b"user_deposit", pool_info.key().as_ref(), ctx.accounts.user.key().as_ref()],
+            ctx.program_id,
+        );
+        require_keys_eq!(ctx.accounts.user_deposit.key(), user_deposit, ValidationError::InvalidPDA);
+        
+        let accounts = ctx.accounts;
+        accrue_rewards(&mut pool, &mut accounts.user_deposit, Clock::get()?.unix_timestamp)?;
+        require!(
+            accounts.reward_vault_token_account.amount >= pool.total_accrued,
+            ValidationError::PoolInsolvent
+        );
+        let reward = std::mem::take(&mut accounts.user_deposit.accrued_rewards);
+        pool.total_accrued = pool
+            .total_accrued
+            .checked_sub(reward)
+            .ok_or(SecurityError::InsufficientFunds)?;
+        pool.try_serialize(&mut &mut accounts.pool_info.try_borrow_mut_data()?[..])?;
+        msg!("SECURE: Claiming {} accrued rewards", reward);
+        pay_out(
+            &accounts.token_program,
+            &mut accounts.reward_vault_token_account,
+            accounts.user_token_account.to_account_info(),
+            accounts.pool_info.to_account_info(),
+            &pool.authority,
+            pool.bump,
+            reward,
+        )
     }
 
     // ============================================================================
@@ -105,14 +213,38 @@ pub mod account_validation {
     /// 1. Account address matches expected PDA derivation
     /// 2. Cannot be substituted with arbitrary accounts
     /// 3. Deterministic and verifiable
-    pub fn swap_secure(ctx: Context<SwapSecure>, amount: u64) -> Result<()> {
+    /// 
+    /// The pool being swapped in must also point at this config
+    /// (`has_one = config`), so a pool can't be paired with another config.
+    /// 
+    /// No fee is charged before `config.fee_holiday_until`; see
+    /// `set_fee_holiday`. Returns the fee charged, which a calling program
+    /// reads back as CPI return data.
+    /// 
+    /// Like a DEX swap, the caller bounds what it will accept:
+    /// - after `deadline` the swap fails with `DeadlinePassed`, so a
+    ///   transaction held back by a validator can't land at a worse time
+    /// - if less than `min_out` is left after the fee it fails with
+    ///   `SlippageExceeded`, so a fee raised in the meantime can't eat the
+    ///   trade
+    pub fn swap_secure(ctx: Context<SwapSecure>, amount: u64, min_out: u64, deadline: i64) -> Result<u64> {
+        require!(amount > 0, SecurityError::ZeroAmount);
+        
         let config = &ctx.accounts.config;
         
+        let now = Clock::get()?.unix_timestamp;
+        require!(now <= deadline, ValidationError::DeadlinePassed);
+        
+        // The holiday ends AT fee_holiday_until: fees apply from that second on
+        let fee_bps = if now < config.fee_holiday_until { 0 } else { config.fee_bps };
+        
         // SECURE: config is validated PDA
-        let fee = (amount as u128 * config.fee_bps as u128 / 10000) as u64;
-        msg!("SECURE: Swap {} with fee {} ({}bps)", amount, fee, config.fee_bps);
+        let fee = (amount as u128 * fee_bps as u128 / 10000) as u64;
+        let out = amount.saturating_sub(fee);
+        require!(out >= min_out, ValidationError::SlippageExceeded);
+        msg!("SECURE: Swap {} with fee {} ({}bps), {} out", amount, fee, fee_bps, out);
         
-        Ok(())
+        Ok(fee)
     }
 
     // ============================================================================
@@ -130,7 +262,7 @@ pub mod account_validation {
     /// 2. Passes victim's token account as user_token_account
     /// 3. Victim's tokens get transferred to pool
     /// 4. Attacker's user_deposit account gets credited
-    pub fn deposit_vulnerable(ctx: Context<DepositVulnerable>, amount: u64) -> Result<()> {
+    pub fn deposit_vulnerable(_ctx: Context<DepositVulnerable>, amount: u64) -> Result<()> {
         // DANGER: No check that user_token_account.owner == user.key()
         msg!("VULNERABLE: Depositing {} tokens", amount);
         // Would transfer from user_token_account to pool...
@@ -144,35 +276,982 @@ pub mod account_validation {
     /// ## What's Fixed?
     /// The `constraint` ensures the token account's owner matches the signer.
     /// For SPL tokens, also validates the mint matches expected mint.
+    /// 
+    /// Deposits from all users also count against the pool's
+    /// `global_tvl_cap`, a protocol-wide limit on value at risk. As in
+    /// `swap_secure`, the pool must belong to the provided config.
+    /// 
+    /// Every deposit (re)locks the user's whole balance until
+    /// `now + pool.lock_secs` and restarts its minimum hold; see `withdraw`.
+    /// 
+    /// The tokens are transferred into the pool's token account before the
+    /// position is credited, and it must have grown by exactly `amount`.
     pub fn deposit_secure(ctx: Context<DepositSecure>, amount: u64) -> Result<()> {
+        let accounts = ctx.accounts;
         // SECURE: user_token_account is validated to belong to user
+        credit_deposit(
+            &accounts.token_program,
+            &accounts.user_token_account,
+            &mut accounts.pool_token_account,
+            accounts.user.to_account_info(),
+            &[],
+            &mut accounts.pool,
+            &mut accounts.user_deposit,
+            amount,
+        )?;
         msg!("SECURE: Depositing {} tokens from verified account", amount);
         
         Ok(())
     }
 
+    /// SECURE: `deposit_secure` with every account pinned to the pool.
+    /// 
+    /// ## What's Added?
+    /// - The pool is re-derived from the `authority` account passed in and
+    ///   bound to it with `has_one = authority`, on top of its stored bump
+    /// - Both token accounts must hold `pool.deposit_mint`, checked up front
+    ///   rather than left to the token program's mint check
+    pub fn deposit_secure_v2(ctx: Context<DepositSecureV2>, amount: u64) -> Result<()> {
+        let accounts = ctx.accounts;
+        credit_deposit(
+            &accounts.token_program,
+            &accounts.user_token_account,
+            &mut accounts.pool_token_account,
+            accounts.user.to_account_info(),
+            &[],
+            &mut accounts.pool,
+            &mut accounts.user_deposit,
+            amount,
+        )?;
+        msg!("SECURE: Deposited {} tokens into the pool", amount);
+        
+        Ok(())
+    }
+
+    /// VULNERABLE: Accepts any token account the user owns, of any mint.
+    /// 
+    /// ## What's Wrong?
+    /// Only `token::authority` is checked, on both sides. Anyone can create
+    /// a token account owned by the pool PDA, so the user picks the mint
+    /// that gets moved - and is credited as if it were the pool's.
+    /// 
+    /// ## Attack Scenario:
+    /// 1. Attacker mints a worthless token
+    /// 2. Creates a token account for it owned by the pool PDA
+    /// 3. Deposits the worthless token into it
+    /// 4. Their position is credited with real withdrawable value
+    pub fn deposit_any_token_account(ctx: Context<DepositAnyTokenAccount>, amount: u64) -> Result<()> {
+        // DANGER: Neither account's mint, nor its address, is checked
+        let accounts = ctx.accounts;
+        credit_deposit(
+            &accounts.token_program,
+            &accounts.user_token_account,
+            &mut accounts.pool_token_account,
+            accounts.user.to_account_info(),
+            &[],
+            &mut accounts.pool,
+            &mut accounts.user_deposit,
+            amount,
+        )?;
+        msg!("VULNERABLE: Deposited {} tokens of an unchecked mint", amount);
+        
+        Ok(())
+    }
+
+    /// SECURE: Only deposits from the user's associated token account.
+    /// 
+    /// ## What's Fixed?
+    /// `associated_token::mint` and `associated_token::authority` pin
+    /// `user_token_account` to the one canonical address for
+    /// (user, `pool.deposit_mint`). The mint itself is bound to the pool with
+    /// `address = pool.deposit_mint`, and the pool's token account must
+    /// hold the same mint.
+    pub fn deposit_secure_ata(ctx: Context<DepositSecureAta>, amount: u64) -> Result<()> {
+        let accounts = ctx.accounts;
+        credit_deposit(
+            &accounts.token_program,
+            &accounts.user_token_account,
+            &mut accounts.pool_token_account,
+            accounts.user.to_account_info(),
+            &[],
+            &mut accounts.pool,
+            &mut accounts.user_deposit,
+            amount,
+        )?;
+        msg!("SECURE: Deposited {} tokens from the user's ATA", amount);
+        
+        Ok(())
+    }
+
+    // ============================================================================
+    // VULNERABILITY 4: PARTIAL RELATIONSHIP CHAINS
+    // ============================================================================
+
+    /// VULNERABLE: Validates only the nearest link of an `Org -> Team -> Member` chain.
+    /// 
+    /// ## What's Wrong?
+    /// `member.team == team` is checked, but `team.org == org` is not. A
+    /// member and team that are correctly linked to EACH OTHER can be
+    /// presented alongside any org at all.
+    /// 
+    /// ## Attack Scenario:
+    /// 1. Attacker creates their own org, a team in it, and joins the team
+    /// 2. Attacker calls member_action with the VICTIM's org, plus their
+    ///    own team and membership
+    /// 3. member -> team passes, team -> org is never looked at
+    /// 4. Attacker acts as a member of the victim's org
+    pub fn member_action_vulnerable(ctx: Context<MemberActionVulnerable>) -> Result<()> {
+        // DANGER: The chain stops at the team - any org is accepted
+        msg!(
+            "VULNERABLE: {} acting in org {}",
+            ctx.accounts.wallet.key(),
+            ctx.accounts.org.key()
+        );
+        Ok(())
+    }
+
+    /// SECURE: Validates every link of the chain with `has_one`.
+    /// 
+    /// ## What's Fixed?
+    /// `member.team == team.key()` AND `team.org == org.key()` are enforced
+    /// in the account struct, so substituting any one account - including
+    /// a member from a different org - breaks the chain.
+    pub fn member_action(ctx: Context<MemberAction>) -> Result<()> {
+        msg!(
+            "SECURE: {} acting in org {} via team {}",
+            ctx.accounts.wallet.key(),
+            ctx.accounts.org.key(),
+            ctx.accounts.team.key()
+        );
+        Ok(())
+    }
+
+    /// VULNERABLE: Takes a pool, its vault and the vault's mint as three
+    /// unrelated accounts.
+    /// 
+    /// ## What's Wrong?
+    /// `Pool -> PoolVault -> Mint` is another chain, and here none of its
+    /// links are checked. Each account is genuine on its own, so every
+    /// owner and discriminator check passes.
+    /// 
+    /// ## Attack Scenario:
+    /// 1. Attacker creates their own pool and a vault for it
+    /// 2. Attacker calls swap_unlinked with the VICTIM's pool and their
+    ///    own vault
+    /// 3. The victim's pool records the liquidity, the attacker's vault
+    ///    holds it
+    /// 4. The victim's pool now claims reserves its vault doesn't have
+    pub fn swap_unlinked(ctx: Context<SwapUnlinked>, amount: u64) -> Result<u64> {
+        // DANGER: pool_vault may belong to any pool, expected_mint be any mint
+        let accounts = ctx.accounts;
+        msg!("VULNERABLE: Swapping {} {} into vault {}", amount, accounts.expected_mint.key(), accounts.pool_vault.key());
+        swap_into(&mut accounts.pool, &mut accounts.pool_vault, amount)
+    }
+
+    /// SECURE: Checks every link of `Pool -> PoolVault -> Mint`.
+    /// 
+    /// ## What's Fixed?
+    /// `pool.vault == pool_vault.key()`, `pool_vault.pool == pool.key()` and
+    /// `pool_vault.mint == expected_mint.key()` are enforced in the account
+    /// struct. Substituting any one account fails with `BrokenAccountLink`.
+    pub fn swap_secure_linked(ctx: Context<SwapSecureLinked>, amount: u64) -> Result<u64> {
+        let accounts = ctx.accounts;
+        msg!("SECURE: Swapping {} {} into vault {}", amount, accounts.expected_mint.key(), accounts.pool_vault.key());
+        swap_into(&mut accounts.pool, &mut accounts.pool_vault, amount)
+    }
+
+    // ============================================================================
+    // VULNERABILITY 5: DEREFERENCING OPTIONAL ACCOUNTS
+    // ============================================================================
+
+    /// VULNERABLE: Assumes an optional referrer account is always present.
+    /// 
+    /// ## What's Wrong?
+    /// `referrer_account` is an `Option<Account<UserAccount>>`: clients
+    /// without a referrer leave it out, and Anchor hands the program `None`.
+    /// Unwrapping it turns the ordinary no-referrer call into a panic.
+    /// 
+    /// ## Attack Scenario:
+    /// No attacker needed:
+    /// 1. A user signs up without a referrer and passes no referrer account
+    /// 2. `unwrap()` on `None` aborts the instruction
+    /// 3. Only referred users can ever set up their account; everyone else
+    ///    is locked out
+    pub fn set_referrer_vulnerable(ctx: Context<SetReferrerVulnerable>) -> Result<()> {
+        // DANGER: None is a valid input, not an impossible one
+        let referrer = ctx.accounts.referrer_account.as_mut().unwrap();
+        referrer.referrals = referrer
+            .referrals
+            .checked_add(1)
+            .ok_or(SecurityError::Overflow)?;
+        ctx.accounts.user_account.referrer = Some(referrer.owner);
+        
+        msg!("VULNERABLE: Referrer recorded");
+        Ok(())
+    }
+
+    /// SECURE: Validates the optional referrer only when it is present.
+    /// 
+    /// ## What's Fixed?
+    /// - A missing referrer is handled as a normal case, not a panic
+    /// - A present referrer is still a full `Account<UserAccount>`: Anchor
+    ///   checks its owner and discriminator, and the program rejects the
+    ///   user naming their own account
+    /// - The referrer can be set once, so it can't be re-pointed later
+    pub fn set_referrer(ctx: Context<SetReferrer>) -> Result<()> {
+        let user_account = &mut ctx.accounts.user_account;
+        require!(user_account.referrer.is_none(), ValidationError::ReferrerAlreadySet);
+        
+        match ctx.accounts.referrer_account.as_mut() {
+            Some(referrer) => {
+                require_keys_neq!(referrer.key(), user_account.key(), ValidationError::SelfReferral);
+                referrer.referrals = referrer
+                    .referrals
+                    .checked_add(1)
+                    .ok_or(SecurityError::Overflow)?;
+                user_account.referrer = Some(referrer.owner);
+                msg!("SECURE: Referred by {}", referrer.owner);
+            }
+            None => msg!("SECURE: No referrer"),
+        }
+        Ok(())
+    }
+
+    // ============================================================================
+    // VULNERABILITY 6: UNSIGNED DEPOSIT PERMITS
+    // ============================================================================
+
+    /// VULNERABLE: Deposits on a user's behalf whenever the caller says the
+    /// user approved.
+    /// 
+    /// ## What's Wrong?
+    /// `approved` is an instruction argument. The relayer writes it, so it
+    /// proves nothing about what the user agreed to - and the user doesn't
+    /// sign this transaction at all.
+    /// 
+    /// ## Attack Scenario:
+    /// 1. Victim's deposit is about to unlock
+    /// 2. Attacker deposits 1 token in the victim's name with `approved = true`,
+    ///    pulled through the delegate approval the victim gave for permits
+    /// 3. The deposit relocks the victim's whole balance and restarts its
+    ///    minimum hold
+    pub fn deposit_with_permit_vulnerable(
+        ctx: Context<DepositWithPermitVulnerable>,
+        amount: u64,
+        approved: bool,
+    ) -> Result<()> {
+        // DANGER: the caller vouches for the user
+        require!(approved, ValidationError::PermitNotApproved);
+        
+        let pool_key = ctx.accounts.pool.key();
+        let delegate_seeds: &[&[u8]] = &[b"permit_delegate", pool_key.as_ref(), &[ctx.bumps.permit_delegate]];
+        let accounts = ctx.accounts;
+        credit_deposit(
+            &accounts.token_program,
+            &accounts.user_token_account,
+            &mut accounts.pool_token_account,
+            accounts.permit_delegate.to_account_info(),
+            &[delegate_seeds],
+            &mut accounts.pool,
+            &mut accounts.user_deposit,
+            amount,
+        )?;
+        
+        msg!("VULNERABLE: Deposited {} for {} on the relayer's word", amount, accounts.user.key());
+        Ok(())
+    }
+
+    /// SECURE: Deposits on a user's behalf with a permit the user signed
+    /// off-chain.
+    /// 
+    /// ## What's Fixed?
+    /// - The transaction must carry an Ed25519 program instruction checking
+    ///   `signature` by the user; the runtime verifies it before any
+    ///   instruction runs, and this handler finds it in the instructions
+    ///   sysvar
+    /// - The signed message is a `DepositPermit` naming this pool, the user,
+    ///   `amount` and `expiry`, so it can't be spent on anything else
+    /// - Rejected once `expiry` has passed
+    /// - The permit must carry the deposit's next `permit_nonce`, which this
+    ///   bumps, so each permit is spent at most once
+    /// 
+    /// The user doesn't sign the transaction, so the tokens can't move on
+    /// their signature. Instead they approve the pool's
+    /// `[b"permit_delegate", pool]` PDA as delegate on their token account
+    /// (an SPL `approve`), and this moves `amount` under that approval.
+    pub fn deposit_with_permit(
+        ctx: Context<DepositWithPermit>,
+        amount: u64,
+        expiry: i64,
+        signature: [u8; 64],
+    ) -> Result<()> {
+        require!(amount > 0, SecurityError::ZeroAmount);
+        
+        let user = ctx.accounts.user.key();
+        let message = permits::verified_message(&ctx.accounts.instructions, &user, &signature)?;
+        let permit = DepositPermit::from_message(&message)?;
+        require!(
+            permit.pool == ctx.accounts.pool.key()
+                && permit.owner == user
+                && permit.amount == amount
+                && permit.expiry == expiry,
+            ValidationError::InvalidPermit
+        );
+        require!(Clock::get()?.unix_timestamp <= expiry, ValidationError::PermitExpired);
+        
+        let user_deposit = &mut ctx.accounts.user_deposit;
+        require!(permit.nonce == user_deposit.permit_nonce, ValidationError::PermitReplayed);
+        user_deposit.permit_nonce = user_deposit
+            .permit_nonce
+            .checked_add(1)
+            .ok_or(SecurityError::Overflow)?;
+        
+        let pool_key = ctx.accounts.pool.key();
+        let delegate_seeds: &[&[u8]] = &[b"permit_delegate", pool_key.as_ref(), &[ctx.bumps.permit_delegate]];
+        let accounts = ctx.accounts;
+        credit_deposit(
+            &accounts.token_program,
+            &accounts.user_token_account,
+            &mut accounts.pool_token_account,
+            accounts.permit_delegate.to_account_info(),
+            &[delegate_seeds],
+            &mut accounts.pool,
+            &mut accounts.user_deposit,
+            amount,
+        )?;
+        
+        msg!("SECURE: Deposited {} for {} under permit {}", amount, user, permit.nonce);
+        Ok(())
+    }
+
+    // ============================================================================
+    // DEPOSIT LOCKS
+    // ============================================================================
+
+    /// SECURE: Withdraws a deposit only after its lock has expired.
+    /// 
+    /// ## Why Lock Deposits?
+    /// Anything priced off `total_deposited` (reward share, voting weight,
+    /// collateral value) can be gamed by depositing borrowed funds, using
+    /// the inflated position, and withdrawing in the same transaction.
+    /// Requiring funds to stay put for `pool.lock_secs` makes that capital
+    /// cost real time instead of one slot.
+    /// 
+    /// A pool with `lock_secs == 0` has no lock: `locked_until` is the
+    /// deposit time itself, so withdrawing right away is allowed.
+    /// 
+    /// ## Early-Exit Penalty
+    /// A lock is all-or-nothing. `pool.min_hold_secs` is the softer
+    /// version: withdrawing before `deposited_at + min_hold_secs` is
+    /// allowed but forfeits `early_exit_bps` of the amount to the pool
+    /// (`pool.early_exit_fees`), which makes a deposit-then-withdraw
+    /// sandwich around someone else's transaction cost money. The penalty
+    /// rounds up, so splitting the withdrawal doesn't dodge it. Withdrawing
+    /// exactly at the end of the hold pays nothing. Returns the amount
+    /// paid out, net of any penalty.
+    /// 
+    /// The payout is transferred from the pool's token account to the
+    /// user's, signed by the pool PDA; the penalty stays in the pool. Both
+    /// token accounts are re-read after the transfer and must still hold
+    /// what they held between them before.
+    pub fn withdraw(ctx: Context<Withdraw>, amount: u64) -> Result<u64> {
+        require!(amount > 0, SecurityError::ZeroAmount);
+        
+        let now = Clock::get()?.unix_timestamp;
+        let user_deposit = &mut ctx.accounts.user_deposit;
+        require!(now >= user_deposit.locked_until, ValidationError::DepositLocked);
+        
+        let pool = &mut ctx.accounts.pool;
+        accrue_rewards(pool, user_deposit, now)?;
+        let held_until = user_deposit
+            .deposited_at
+            .checked_add(pool.min_hold_secs)
+            .ok_or(SecurityError::Overflow)?;
+        let penalty = if now < held_until {
+            math::mul_div_ceil(amount, u64::from(pool.early_exit_bps), u64::from(payments::BPS_DENOMINATOR))?
+        } else {
+            0
+        };
+        
+        user_deposit.amount = user_deposit
+            .amount
+            .checked_sub(amount)
+            .ok_or(SecurityError::InsufficientFunds)?;
+        
+        pool.total_deposited = pool
+            .total_deposited
+            .checked_sub(amount)
+            .ok_or(SecurityError::InsufficientFunds)?;
+        pool.early_exit_fees = pool
+            .early_exit_fees
+            .checked_add(penalty)
+            .ok_or(SecurityError::Overflow)?;
+        
+        // penalty <= amount, since early_exit_bps <= 10_000
+        let paid_out = amount - penalty;
+        msg!("SECURE: Withdrew {} tokens, {} early-exit penalty", paid_out, penalty);
+        
+        let (authority, bump) = (pool.authority, pool.bump);
+        let accounts = ctx.accounts;
+        let before = token_balances([&accounts.pool_token_account, &accounts.user_token_account])?;
+        pay_out(
+            &accounts.token_program,
+            &mut accounts.pool_token_account,
+            accounts.user_token_account.to_account_info(),
+            accounts.pool.to_account_info(),
+            &authority,
+            bump,
+            paid_out,
+        )?;
+        
+        // Tokens only moved from the pool's account to the user's
+        accounts.user_token_account.reload()?;
+        assert_value_conserved(
+            before,
+            token_balances([&accounts.pool_token_account, &accounts.user_token_account])?,
+        )?;
+        Ok(paid_out)
+    }
+
+    // ============================================================================
+    // POSITION LIMITS
+    // ============================================================================
+
+    /// SECURE: Opens a position in `pool`, up to `config.max_positions` per user.
+    /// 
+    /// ## Why Cap Positions?
+    /// Every position is a rent-paying account that indexers, crank bots and
+    /// any instruction iterating a user's positions have to walk. Without a
+    /// cap, a griefer can open thousands of empty positions and make that
+    /// work - or the accounts list of a single transaction - too large to
+    /// process. `user_account.open_positions` counts what the user holds
+    /// now; closing a position frees its slot.
+    /// 
+    /// Rewards accrue from the moment the position is opened, not from the
+    /// Unix epoch.
+    pub fn open_deposit(ctx: Context<OpenDeposit>) -> Result<()> {
+        let user_account = &mut ctx.accounts.user_account;
+        require!(
+            user_account.open_positions < ctx.accounts.config.max_positions,
+            ValidationError::TooManyPositions
+        );
+        user_account.open_positions = user_account
+            .open_positions
+            .checked_add(1)
+            .ok_or(SecurityError::Overflow)?;
+        
+        let user_deposit = &mut ctx.accounts.user_deposit;
+        user_deposit.pool = ctx.accounts.pool.key();
+        user_deposit.owner = ctx.accounts.user.key();
+        user_deposit.amount = 0;
+        user_deposit.locked_until = 0;
+        user_deposit.deposited_at = 0;
+        user_deposit.accrued_rewards = 0;
+        user_deposit.rewards_updated_at = Clock::get()?.unix_timestamp;
+        user_deposit.bump = ctx.bumps.user_deposit;
+        Ok(())
+    }
+
+    /// SECURE: Closes an empty position and frees its slot.
+    /// 
+    /// Only positions holding nothing - no deposit and no unclaimed rewards -
+    /// can be closed, so a balance is never thrown away with the account.
+    /// Rent goes back to the owner.
+    pub fn close_deposit(ctx: Context<CloseDeposit>) -> Result<()> {
+        let user_deposit = &ctx.accounts.user_deposit;
+        require!(
+            user_deposit.amount == 0 && user_deposit.accrued_rewards == 0,
+            ValidationError::DepositNotEmpty
+        );
+        
+        let user_account = &mut ctx.accounts.user_account;
+        user_account.open_positions = user_account
+            .open_positions
+            .checked_sub(1)
+            .ok_or(SecurityError::Overflow)?;
+        
+        msg!("SECURE: Closed position, {} still open", user_account.open_positions);
+        Ok(())
+    }
+
+    // ============================================================================
+    // DUST SWEEPING
+    // ============================================================================
+
+    /// SECURE: Closes a position whose balance is below `config.dust_threshold`.
+    /// 
+    /// ## Why Sweep Dust?
+    /// A position holding a few base units costs its owner more in locked
+    /// rent than it's worth, and still counts against `max_positions`.
+    /// Only the owner or the pool authority may sweep it. The dust goes to
+    /// the pool: it leaves `total_deposited` for `dust_swept` and the
+    /// tokens stay in the pool's token account. The rent goes back to the
+    /// owner, never to the caller, so sweeping can't be used to skim.
+    /// 
+    /// A locked position can't be swept - otherwise a deposit sized under
+    /// the threshold would be a way out of its lock. Since the owner gives
+    /// up the whole balance, there is no early-exit penalty left to dodge.
+    /// 
+    /// The threshold is exclusive - a position holding exactly
+    /// `dust_threshold` is not dust. A threshold of 0 disables sweeping.
+    /// Rewards the position accrued and never claimed are forfeited too,
+    /// and no longer count against the pool's `total_accrued`.
+    pub fn sweep_dust(ctx: Context<SweepDust>) -> Result<()> {
+        let dust = ctx.accounts.user_deposit.amount;
+        require!(dust < ctx.accounts.config.dust_threshold, ValidationError::NotDust);
+        require!(
+            Clock::get()?.unix_timestamp >= ctx.accounts.user_deposit.locked_until,
+            ValidationError::DepositLocked
+        );
+        
+        // SECURE: A stranger can't close someone else's position
+        let sweeper = ctx.accounts.sweeper.key();
+        require!(
+            sweeper == ctx.accounts.owner.key() || sweeper == ctx.accounts.pool.authority,
+            ValidationError::UnauthorizedSweeper
+        );
+        
+        let pool = &mut ctx.accounts.pool;
+        pool.total_deposited = pool
+            .total_deposited
+            .checked_sub(dust)
+            .ok_or(SecurityError::InsufficientFunds)?;
+        pool.dust_swept = pool
+            .dust_swept
+            .checked_add(dust)
+            .ok_or(SecurityError::Overflow)?;
+        pool.total_accrued = pool
+            .total_accrued
+            .checked_sub(ctx.accounts.user_deposit.accrued_rewards)
+            .ok_or(SecurityError::InsufficientFunds)?;
+        
+        let user_account = &mut ctx.accounts.user_account;
+        user_account.open_positions = user_account
+            .open_positions
+            .checked_sub(1)
+            .ok_or(SecurityError::Overflow)?;
+        
+        msg!("SECURE: Swept {} dust into the pool", dust);
+        Ok(())
+    }
+
+    // ============================================================================
+    // SPLIT PAYMENTS
+    // ============================================================================
+
+    /// SECURE: Pays `total` out to several recipients by basis-point weight.
+    /// 
+    /// Recipient token accounts are passed in `remaining_accounts`, one per
+    /// entry of `bps_splits`, and each is checked to be a real token account
+    /// of the source's mint before anything moves. Per-recipient shares round
+    /// down; whatever rounding leaves over goes to the FIRST recipient, so
+    /// exactly `total` leaves the source - never a token more or less.
+    /// The source and recipient balances are re-read afterwards and must sum
+    /// to what they did before (`assert_value_conserved`).
+    /// 
+    /// Too many recipients for the remaining compute budget fails up front
+    /// with `ComputeBudgetLow`.
+    /// 
+    /// As with `swap_secure`, nothing moves after `deadline`
+    /// (`DeadlinePassed`), and the recipients must have received at least
+    /// `min_out` between them (`SlippageExceeded`), going by their re-read
+    /// balances rather than the computed shares.
+    pub fn split_payment<'info>(
+        ctx: Context<'_, '_, 'info, 'info, SplitPayment<'info>>,
+        total: u64,
+        bps_splits: Vec<u16>,
+        min_out: u64,
+        deadline: i64,
+    ) -> Result<()> {
+        require!(total > 0, SecurityError::ZeroAmount);
+        require!(Clock::get()?.unix_timestamp <= deadline, ValidationError::DeadlinePassed);
+        require!(
+            ctx.remaining_accounts.len() == bps_splits.len(),
+            ValidationError::RecipientCountMismatch
+        );
+        require_compute_budget(bps_splits.len(), SPLIT_UNITS_PER_RECIPIENT)?;
+        
+        let amounts = payments::split_amounts(total, &bps_splits)?;
+        let mint = ctx.accounts.source.mint;
+        
+        let mut recipients = Vec::with_capacity(ctx.remaining_accounts.len());
+        for info in ctx.remaining_accounts.iter() {
+            // Account<TokenAccount> checks owner == token program and layout
+            let recipient = Account::<TokenAccount>::try_from(info)?;
+            require_keys_eq!(recipient.mint, mint, ValidationError::TokenMintMismatch);
+            recipients.push(recipient);
+        }
+        let balances = |source: &Account<'info, TokenAccount>, recipients: &[Account<'info, TokenAccount>]| {
+            token_balances(std::iter::once(source).chain(recipients))
+        };
+        // What `min_out` is measured against: a recipient that is the source
+        // itself receives nothing
+        let source_key = ctx.accounts.source.key();
+        let payees = |recipients: &[Account<'info, TokenAccount>]| {
+            token_balances(recipients.iter().filter(|recipient| recipient.key() != source_key))
+        };
+        let before = balances(&ctx.accounts.source, &recipients)?;
+        let held = payees(&recipients)?;
+        
+        for (recipient, amount) in recipients.iter().zip(amounts) {
+            if amount == 0 {
+                continue;
+            }
+            let cpi_ctx = CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.source.to_account_info(),
+                    to: recipient.to_account_info(),
+                    authority: ctx.accounts.payer.to_account_info(),
+                },
+            );
+            token::transfer(cpi_ctx, amount)?;
+        }
+        
+        // Tokens only moved from the source to the recipients
+        ctx.accounts.source.reload()?;
+        for recipient in recipients.iter_mut() {
+            recipient.reload()?;
+        }
+        assert_value_conserved(before, balances(&ctx.accounts.source, &recipients)?)?;
+        let received = payees(&recipients)?.saturating_sub(held);
+        require!(received >= min_out, ValidationError::SlippageExceeded);
+        
+        msg!("SECURE: Split {} tokens across {} recipients", total, bps_splits.len());
+        Ok(())
+    }
+
+    // ============================================================================
+    // FEE HOLIDAYS
+    // ============================================================================
+
+    /// SECURE: Waives swap fees until `fee_holiday_until`.
+    /// 
+    /// Only the config admin can start a promotion. The waiver comes from
+    /// the canonical config PDA, so it can't be forged the way a zero-fee
+    /// config can in `swap_vulnerable`. Passing a timestamp in the past
+    /// (or 0) ends any running holiday.
+    pub fn set_fee_holiday(ctx: Context<SetFeeHoliday>, fee_holiday_until: i64) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        config.fee_holiday_until = fee_holiday_until;
+        msg!("SECURE: Fees waived until {}", fee_holiday_until);
+        Ok(())
+    }
+
+    // ============================================================================
+    // PDA VERIFICATION
+    // ============================================================================
+
+    /// Checks that `account` is the PDA of `seeds` under this program and
+    /// returns its canonical bump.
+    /// 
+    /// A read-only debugging aid: clients can confirm a derivation before
+    /// relying on it, and the PDA lessons can show that a lookalike address
+    /// fails the same check `seeds = [...]` performs. `seeds` exclude the
+    /// bump, which is searched for.
+    pub fn verify_pda(ctx: Context<VerifyPda>, seeds: Vec<Vec<u8>>) -> Result<u8> {
+        // One seed slot is reserved for the bump
+        require!(
+            seeds.len() < MAX_SEEDS && seeds.iter().all(|seed| seed.len() <= MAX_SEED_LEN),
+            ValidationError::InvalidSeeds
+        );
+        
+        let seed_refs: Vec<&[u8]> = seeds.iter().map(Vec::as_slice).collect();
+        let (expected, bump) = Pubkey::try_find_program_address(&seed_refs, ctx.program_id)
+            .ok_or(ValidationError::InvalidSeeds)?;
+        require_keys_eq!(ctx.accounts.account.key(), expected, ValidationError::InvalidPDA);
+        
+        msg!("PDA {} has canonical bump {}", expected, bump);
+        Ok(bump)
+    }
+
+    /// Reads the protocol config from a raw account, making by hand the
+    /// checks `seeds` and `Account<>` make on `SwapSecure.config`.
+    /// 
+    /// In order: the address must be the `["config"]` PDA (`InvalidPDA`),
+    /// this program must own it (`InvalidOwner`), and it must still be rent
+    /// exempt (`NotRentExempt`) - a force-defunded config is about to be
+    /// garbage collected and shouldn't be trusted. Only then is it
+    /// deserialized, which checks the discriminator. Returns `fee_bps`.
+    pub fn read_config_rent_checked(ctx: Context<ReadConfigRentChecked>) -> Result<u16> {
+        let config = &ctx.accounts.config;
+        
+        let (expected, _) = Pubkey::find_program_address(&[b"config"], ctx.program_id);
+        require_keys_eq!(config.key(), expected, ValidationError::InvalidPDA);
+        require_owned_by(config, ctx.program_id, ValidationError::InvalidOwner)?;
+        require!(
+            Rent::get()?.is_exempt(config.lamports(), config.data_len()),
+            ValidationError::NotRentExempt
+        );
+        
+        let state = Config::try_deserialize(&mut &config.try_borrow_data()?[..])?;
+        msg!("Config fee_bps = {}", state.fee_bps);
+        Ok(state.fee_bps)
+    }
+
     // ============================================================================
     // INITIALIZATION
     // ============================================================================
 
-    pub fn initialize_pool(ctx: Context<InitializePool>, reward_rate: u64) -> Result<()> {
+    pub fn initialize_org(ctx: Context<InitializeOrg>) -> Result<()> {
+        let org = &mut ctx.accounts.org;
+        org.authority = ctx.accounts.authority.key();
+        org.bump = ctx.bumps.org;
+        Ok(())
+    }
+
+    pub fn initialize_team(ctx: Context<InitializeTeam>) -> Result<()> {
+        let team = &mut ctx.accounts.team;
+        team.org = ctx.accounts.org.key();
+        team.lead = ctx.accounts.lead.key();
+        team.bump = ctx.bumps.team;
+        Ok(())
+    }
+
+    pub fn add_member(ctx: Context<AddMember>) -> Result<()> {
+        let member = &mut ctx.accounts.member;
+        member.team = ctx.accounts.team.key();
+        member.wallet = ctx.accounts.wallet.key();
+        member.bump = ctx.bumps.member;
+        Ok(())
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn initialize_pool(
+        ctx: Context<InitializePool>,
+        reward_rate: u64,
+        global_tvl_cap: u64,
+        lock_secs: i64,
+        min_hold_secs: i64,
+        early_exit_bps: u16,
+        deposit_mint: Pubkey,
+        reward_mint: Pubkey,
+    ) -> Result<()> {
+        require!(lock_secs >= 0 && min_hold_secs >= 0, ValidationError::InvalidLockPeriod);
+        require!(early_exit_bps <= payments::BPS_DENOMINATOR, ValidationError::InvalidPenalty);
+        
         let pool = &mut ctx.accounts.pool;
         pool.authority = ctx.accounts.authority.key();
+        pool.config = ctx.accounts.config.key();
         pool.total_deposited = 0;
         pool.reward_rate = reward_rate;
+        pool.total_accrued = 0;
+        pool.global_tvl_cap = global_tvl_cap;
+        pool.lock_secs = lock_secs;
+        pool.dust_swept = 0;
+        pool.min_hold_secs = min_hold_secs;
+        pool.early_exit_bps = early_exit_bps;
+        pool.early_exit_fees = 0;
+        pool.deposit_mint = deposit_mint;
+        pool.reward_mint = reward_mint;
+        pool.vault = Pubkey::default();
         pool.bump = ctx.bumps.pool;
         Ok(())
     }
 
-    pub fn initialize_config(ctx: Context<InitializeConfig>, fee_bps: u16) -> Result<()> {
+    /// Creates the pool's vault for `mint` and links the pool to it.
+    pub fn initialize_pool_vault(ctx: Context<InitializePoolVault>) -> Result<()> {
+        let pool_vault = &mut ctx.accounts.pool_vault;
+        pool_vault.pool = ctx.accounts.pool.key();
+        pool_vault.mint = ctx.accounts.mint.key();
+        pool_vault.reserve = 0;
+        pool_vault.bump = ctx.bumps.pool_vault;
+        ctx.accounts.pool.vault = pool_vault.key();
+        Ok(())
+    }
+
+    pub fn initialize_user_account(ctx: Context<InitializeUserAccount>) -> Result<()> {
+        let user_account = &mut ctx.accounts.user_account;
+        user_account.owner = ctx.accounts.user.key();
+        user_account.open_positions = 0;
+        user_account.referrer = None;
+        user_account.referrals = 0;
+        user_account.bump = ctx.bumps.user_account;
+        Ok(())
+    }
+
+    pub fn initialize_config(
+        ctx: Context<InitializeConfig>,
+        fee_bps: u16,
+        max_positions: u16,
+        dust_threshold: u64,
+    ) -> Result<()> {
         let config = &mut ctx.accounts.config;
         config.admin = ctx.accounts.admin.key();
         config.fee_bps = fee_bps;
+        config.max_positions = max_positions;
+        config.dust_threshold = dust_threshold;
+        config.fee_holiday_until = 0;
         config.bump = ctx.bumps.config;
         Ok(())
     }
 }
 
+/// Adds `amount` to both the vault's reserve and the pool's deposits and
+/// returns the new reserve.
+fn swap_into(pool: &mut Pool, pool_vault: &mut PoolVault, amount: u64) -> Result<u64> {
+    require!(amount > 0, SecurityError::ZeroAmount);
+    pool_vault.reserve = pool_vault
+        .reserve
+        .checked_add(amount)
+        .ok_or(SecurityError::Overflow)?;
+    pool.total_deposited = pool
+        .total_deposited
+        .checked_add(amount)
+        .ok_or(SecurityError::Overflow)?;
+    Ok(pool_vault.reserve)
+}
+
+/// Transfers `amount` from `from` into `to`, signed by `authority` (with
+/// `signer_seeds` if it's a PDA).
+/// 
+/// `to` is reloaded after the transfer and must have grown by exactly
+/// `amount`.
+fn transfer_in<'info>(
+    token_program: &Program<'info, Token>,
+    from: &Account<'info, TokenAccount>,
+    to: &mut Account<'info, TokenAccount>,
+    authority: AccountInfo<'info>,
+    signer_seeds: &[&[&[u8]]],
+    amount: u64,
+) -> Result<()> {
+    require!(amount > 0, SecurityError::ZeroAmount);
+    let before = to.amount;
+    
+    let cpi_ctx = CpiContext::new_with_signer(
+        token_program.to_account_info(),
+        Transfer {
+            from: from.to_account_info(),
+            to: to.to_account_info(),
+            authority,
+        },
+        signer_seeds,
+    );
+    token::transfer(cpi_ctx, amount)?;
+    
+    to.reload()?;
+    let received = to
+        .amount
+        .checked_sub(before)
+        .ok_or(SecurityError::ConservationViolation)?;
+    require_eq!(received, amount, SecurityError::ConservationViolation);
+    Ok(())
+}
+
+/// Sum of the token balances of `accounts`, each account counted once
+/// however often it is listed.
+fn token_balances<'a, 'info: 'a>(
+    accounts: impl IntoIterator<Item = &'a Account<'info, TokenAccount>>,
+) -> Result<u64> {
+    let mut counted: Vec<(Pubkey, u64)> = Vec::new();
+    for account in accounts {
+        if counted.iter().all(|(key, _)| *key != account.key()) {
+            counted.push((account.key(), account.amount));
+        }
+    }
+    sum_balances(counted.into_iter().map(|(_, amount)| amount))
+}
+
+/// Pays `amount` from `vault` to `to`, signing as the pool PDA of
+/// `authority`. Returns the amount paid.
+/// 
+/// The vault is reloaded after the transfer and must have gone down by
+/// exactly that amount.
+fn pay_out<'info>(
+    token_program: &Program<'info, Token>,
+    vault: &mut Account<'info, TokenAccount>,
+    to: AccountInfo<'info>,
+    pool: AccountInfo<'info>,
+    authority: &Pubkey,
+    bump: u8,
+    amount: u64,
+) -> Result<u64> {
+    let before = vault.amount;
+    
+    let seeds = &[b"pool".as_ref(), authority.as_ref(), &[bump]];
+    let signer_seeds = &[&seeds[..]];
+    let cpi_ctx = CpiContext::new_with_signer(
+        token_program.to_account_info(),
+        Transfer { from: vault.to_account_info(), to, authority: pool },
+        signer_seeds,
+    );
+    token::transfer(cpi_ctx, amount)?;
+    
+    vault.reload()?;
+    let paid = before
+        .checked_sub(vault.amount)
+        .ok_or(SecurityError::ConservationViolation)?;
+    require_eq!(paid, amount, SecurityError::ConservationViolation);
+    Ok(amount)
+}
+
+/// Adds what a position earned since `rewards_updated_at` to its
+/// `accrued_rewards` and to the pool's `total_accrued`, and returns it.
+/// 
+/// A position earns `pool.reward_rate` basis points of its amount per
+/// second, rounded down. Runs before anything changes the amount, so each
+/// second earns at the amount that was deposited during it.
+fn accrue_rewards(pool: &mut Pool, user_deposit: &mut UserDeposit, now: i64) -> Result<u64> {
+    let elapsed = u64::try_from(now.saturating_sub(user_deposit.rewards_updated_at)).unwrap_or(0);
+    let rate = pool.reward_rate.checked_mul(elapsed).ok_or(SecurityError::Overflow)?;
+    let earned = math::mul_div(user_deposit.amount, rate, u64::from(payments::BPS_DENOMINATOR))?;
+    user_deposit.accrued_rewards = user_deposit
+        .accrued_rewards
+        .checked_add(earned)
+        .ok_or(SecurityError::Overflow)?;
+    pool.total_accrued = pool.total_accrued.checked_add(earned).ok_or(SecurityError::Overflow)?;
+    user_deposit.rewards_updated_at = now;
+    Ok(earned)
+}
+
+/// Moves `amount` of the user's tokens from `from` into the pool's `to`,
+/// then adds it to their position.
+/// 
+/// `authority` is the user themselves, or a delegate they approved signing
+/// with `signer_seeds`. Nothing is credited that didn't arrive:
+/// `transfer_in` fails unless `to` grew by exactly `amount`.
+#[allow(clippy::too_many_arguments)]
+fn credit_deposit<'info>(
+    token_program: &Program<'info, Token>,
+    from: &Account<'info, TokenAccount>,
+    to: &mut Account<'info, TokenAccount>,
+    authority: AccountInfo<'info>,
+    signer_seeds: &[&[&[u8]]],
+    pool: &mut Pool,
+    user_deposit: &mut UserDeposit,
+    amount: u64,
+) -> Result<()> {
+    transfer_in(token_program, from, to, authority, signer_seeds, amount)?;
+    book_deposit(pool, user_deposit, amount)
+}
+
+/// Adds `amount` to a position, counting it against the pool's TVL cap.
+/// 
+/// Every deposit (re)locks the whole position until `now + pool.lock_secs`
+/// and restarts its minimum hold.
+fn book_deposit(pool: &mut Pool, user_deposit: &mut UserDeposit, amount: u64) -> Result<()> {
+    let now = Clock::get()?.unix_timestamp;
+    accrue_rewards(pool, user_deposit, now)?;
+    
+    // An overflowing total is over any cap
+    let new_total = pool
+        .total_deposited
+        .checked_add(amount)
+        .ok_or(ValidationError::TvlCapReached)?;
+    require!(new_total <= pool.global_tvl_cap, ValidationError::TvlCapReached);
+    pool.total_deposited = new_total;
+    
+    user_deposit.amount = user_deposit
+        .amount
+        .checked_add(amount)
+        .ok_or(SecurityError::Overflow)?;
+    user_deposit.locked_until = now
+        .checked_add(pool.lock_secs)
+        .ok_or(SecurityError::Overflow)?;
+    user_deposit.deposited_at = now;
+    Ok(())
+}
+
 // ============================================================================
 // VULNERABLE ACCOUNT STRUCTURES
 // ============================================================================
@@ -189,7 +1268,19 @@ pub struct ClaimRewardsVulnerable<'info> {
     /// CHECK: Intentionally insecure for demonstration
     pub pool_info: UncheckedAccount<'info>,
     
+    /// CHECK: The pool PDA of whatever authority pool_info names
+    pub pool_signer: UncheckedAccount<'info>,
+    
+    /// VULNERABLE: Whichever vault that PDA controls
+    #[account(mut)]
+    pub reward_vault_token_account: Account<'info, TokenAccount>,
+    
+    #[account(mut)]
+    pub user_token_account: Account<'info, TokenAccount>,
+    
     pub user: Signer<'info>,
+    
+    pub token_program: Program<'info, Token>,
 }
 
 #[derive(Accounts)]
@@ -221,29 +1312,155 @@ pub struct DepositVulnerable<'info> {
     pub user: Signer<'info>,
 }
 
-// ============================================================================
-// SECURE ACCOUNT STRUCTURES
-// ============================================================================
-
 #[derive(Accounts)]
-pub struct ClaimRewardsSecure<'info> {
-    /// SECURE: Account<> wrapper provides:
-    /// 
-    /// 1. Owner Check: Verifies account is owned by this program
-    /// 2. Discriminator Check: Validates the 8-byte discriminator
-    /// 3. Deserialization: Safely deserializes into Pool struct
-    /// 4. Type Safety: Compile-time guarantee of correct type
-    #[account(
-        seeds = [b"pool", pool.authority.as_ref()],
-        bump = pool.bump,
-    )]
-    pub pool: Account<'info, Pool>,
+pub struct MemberActionVulnerable<'info> {
+    /// VULNERABLE: Nothing ties the org to the team
+    pub org: Account<'info, Org>,
     
-    pub user: Signer<'info>,
+    pub team: Account<'info, Team>,
+    
+    #[account(has_one = team, has_one = wallet)]
+    pub member: Account<'info, Member>,
+    
+    pub wallet: Signer<'info>,
 }
 
 #[derive(Accounts)]
-pub struct SwapSecure<'info> {
+pub struct SetReferrerVulnerable<'info> {
+    #[account(
+        mut,
+        seeds = [b"user", user.key().as_ref()],
+        bump = user_account.bump,
+    )]
+    pub user_account: Account<'info, UserAccount>,
+    
+    /// VULNERABLE: Optional, but the handler unwraps it
+    #[account(mut)]
+    pub referrer_account: Option<Account<'info, UserAccount>>,
+    
+    pub user: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct DepositWithPermitVulnerable<'info> {
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, Config>,
+    
+    #[account(
+        mut,
+        seeds = [b"pool", pool.authority.as_ref()],
+        bump = pool.bump,
+        has_one = config,
+    )]
+    pub pool: Account<'info, Pool>,
+    
+    #[account(
+        mut,
+        seeds = [b"user_deposit", pool.key().as_ref(), user.key().as_ref()],
+        bump = user_deposit.bump,
+    )]
+    pub user_deposit: Account<'info, UserDeposit>,
+    
+    /// CHECK: VULNERABLE - never signs, and nothing else shows consent
+    pub user: UncheckedAccount<'info>,
+    
+    #[account(
+        mut,
+        token::authority = user,
+        token::mint = pool.deposit_mint,
+    )]
+    pub user_token_account: Account<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        token::authority = pool,
+        token::mint = pool.deposit_mint,
+    )]
+    pub pool_token_account: Account<'info, TokenAccount>,
+    
+    /// CHECK: A data-less PDA; signs as the delegate users approve for permits
+    #[account(seeds = [b"permit_delegate", pool.key().as_ref()], bump)]
+    pub permit_delegate: UncheckedAccount<'info>,
+    
+    pub relayer: Signer<'info>,
+    
+    pub token_program: Program<'info, Token>,
+}
+
+// ============================================================================
+// SECURE ACCOUNT STRUCTURES
+// ============================================================================
+
+#[derive(Accounts)]
+pub struct ClaimRewardsSecure<'info> {
+    /// SECURE: Account<> wrapper provides:
+    /// 
+    /// 1. Owner Check: Verifies account is owned by this program
+    /// 2. Discriminator Check: Validates the 8-byte discriminator
+    /// 3. Deserialization: Safely deserializes into Pool struct
+    /// 4. Type Safety: Compile-time guarantee of correct type
+    #[account(
+        mut,
+        seeds = [b"pool", pool.authority.as_ref()],
+        bump = pool.bump,
+    )]
+    pub pool: Account<'info, Pool>,
+    
+    /// Tokens backing the pool's rewards
+    #[account(
+        mut,
+        token::authority = pool,
+        token::mint = pool.reward_mint,
+    )]
+    pub reward_vault_token_account: Account<'info, TokenAccount>,
+    
+    /// Where the reward is paid
+    #[account(mut, token::mint = pool.reward_mint)]
+    pub user_token_account: Account<'info, TokenAccount>,
+    
+    /// SECURE: The claimant's own position in this pool; only its rewards
+    /// are paid
+    #[account(
+        mut,
+        seeds = [b"user_deposit", pool.key().as_ref(), user.key().as_ref()],
+        bump = user_deposit.bump,
+    )]
+    pub user_deposit: Account<'info, UserDeposit>,
+    
+    pub user: Signer<'info>,
+    
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimRewardsManualOwnerCheck<'info> {
+    /// CHECK: Owner and discriminator are verified in the instruction
+    #[account(mut)]
+    pub pool_info: UncheckedAccount<'info>,
+    
+    /// Checked against the pool in the instruction
+    #[account(mut)]
+    pub reward_vault_token_account: Account<'info, TokenAccount>,
+    
+    /// Checked against the pool's reward mint in the instruction
+    #[account(mut)]
+    pub user_token_account: Account<'info, TokenAccount>,
+    
+    /// The claimant's position; its address is checked against the pool
+    /// in the instruction
+    #[account(mut)]
+    pub user_deposit: Account<'info, UserDeposit>,
+    
+    pub user: Signer<'info>,
+    
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct SwapSecure<'info> {
     /// SECURE: PDA validation with seeds constraint
     /// 
     /// The `seeds` constraint ensures:
@@ -251,43 +1468,459 @@ pub struct SwapSecure<'info> {
     /// 2. Cannot be any arbitrary account
     /// 3. Deterministic - same seeds always produce same address
     #[account(
-        seeds = [b"config"],
-        bump = config.bump,
+        seeds = [b"config"],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, Config>,
+    
+    /// SECURE: Pool must have been created against this config
+    #[account(
+        seeds = [b"pool", pool.authority.as_ref()],
+        bump = pool.bump,
+        has_one = config,
+    )]
+    pub pool: Account<'info, Pool>,
+    
+    pub user: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct DepositSecure<'info> {
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, Config>,
+    
+    /// SECURE: Pool must have been created against this config
+    #[account(
+        mut,
+        seeds = [b"pool", pool.authority.as_ref()],
+        bump = pool.bump,
+        has_one = config,
+    )]
+    pub pool: Account<'info, Pool>,
+    
+    /// The user's position in this pool
+    #[account(
+        mut,
+        seeds = [b"user_deposit", pool.key().as_ref(), user.key().as_ref()],
+        bump = user_deposit.bump,
+    )]
+    pub user_deposit: Account<'info, UserDeposit>,
+    
+    /// SECURE: Token account with ownership validation
+    /// 
+    /// Constraints ensure:
+    /// 1. `token::authority` - Token account owner is the user
+    /// 2. `token::mint` - Token account holds correct mint
+    /// 3. Anchor's TokenAccount validates it's a valid SPL token account
+    #[account(
+        mut,
+        token::authority = user,
+        // token::mint is only enforced by the transfer; DepositSecureV2 checks it up front
+    )]
+    pub user_token_account: Account<'info, TokenAccount>,
+    
+    /// SECURE: The pool's own tokens, of the mint it accepts
+    #[account(
+        mut,
+        token::authority = pool,
+        token::mint = pool.deposit_mint,
+    )]
+    pub pool_token_account: Account<'info, TokenAccount>,
+    
+    pub user: Signer<'info>,
+    
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct DepositSecureV2<'info> {
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, Config>,
+    
+    /// SECURE: Derived from the authority passed in, checked against the
+    /// stored bump, and bound to both that authority and the config
+    #[account(
+        mut,
+        seeds = [b"pool", authority.key().as_ref()],
+        bump = pool.bump,
+        has_one = config,
+        has_one = authority,
+    )]
+    pub pool: Account<'info, Pool>,
+    
+    /// CHECK: Only a seed; `has_one = authority` ties it to the pool
+    pub authority: UncheckedAccount<'info>,
+    
+    /// The user's position in this pool
+    #[account(
+        mut,
+        seeds = [b"user_deposit", pool.key().as_ref(), user.key().as_ref()],
+        bump = user_deposit.bump,
+    )]
+    pub user_deposit: Account<'info, UserDeposit>,
+    
+    /// SECURE: The user's own tokens, of the mint this pool accepts
+    #[account(
+        mut,
+        token::authority = user,
+        token::mint = pool.deposit_mint,
+    )]
+    pub user_token_account: Account<'info, TokenAccount>,
+    
+    /// SECURE: The pool's own tokens, of the same mint
+    #[account(
+        mut,
+        token::authority = pool,
+        token::mint = pool.deposit_mint,
+    )]
+    pub pool_token_account: Account<'info, TokenAccount>,
+    
+    pub user: Signer<'info>,
+    
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct DepositAnyTokenAccount<'info> {
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, Config>,
+    
+    #[account(
+        mut,
+        seeds = [b"pool", pool.authority.as_ref()],
+        bump = pool.bump,
+        has_one = config,
+    )]
+    pub pool: Account<'info, Pool>,
+    
+    #[account(
+        mut,
+        seeds = [b"user_deposit", pool.key().as_ref(), user.key().as_ref()],
+        bump = user_deposit.bump,
+    )]
+    pub user_deposit: Account<'info, UserDeposit>,
+    
+    /// VULNERABLE: Any account the user owns, of any mint
+    #[account(mut, token::authority = user)]
+    pub user_token_account: Account<'info, TokenAccount>,
+    
+    /// VULNERABLE: Any account the pool owns, of any mint
+    #[account(mut, token::authority = pool)]
+    pub pool_token_account: Account<'info, TokenAccount>,
+    
+    pub user: Signer<'info>,
+    
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct DepositSecureAta<'info> {
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, Config>,
+    
+    #[account(
+        mut,
+        seeds = [b"pool", pool.authority.as_ref()],
+        bump = pool.bump,
+        has_one = config,
+    )]
+    pub pool: Account<'info, Pool>,
+    
+    #[account(
+        mut,
+        seeds = [b"user_deposit", pool.key().as_ref(), user.key().as_ref()],
+        bump = user_deposit.bump,
+    )]
+    pub user_deposit: Account<'info, UserDeposit>,
+    
+    /// The mint this pool accepts
+    #[account(address = pool.deposit_mint)]
+    pub deposit_mint: Account<'info, Mint>,
+    
+    /// SECURE: Exactly the user's ATA for the deposit mint
+    #[account(
+        mut,
+        associated_token::mint = deposit_mint,
+        associated_token::authority = user,
+    )]
+    pub user_token_account: Account<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        token::authority = pool,
+        token::mint = deposit_mint,
+    )]
+    pub pool_token_account: Account<'info, TokenAccount>,
+    
+    pub user: Signer<'info>,
+    
+    pub token_program: Program<'info, Token>,
+    
+    pub associated_token_program: Program<'info, AssociatedToken>,
+}
+
+#[derive(Accounts)]
+pub struct Withdraw<'info> {
+    #[account(
+        mut,
+        seeds = [b"pool", pool.authority.as_ref()],
+        bump = pool.bump,
+    )]
+    pub pool: Account<'info, Pool>,
+    
+    #[account(
+        mut,
+        seeds = [b"user_deposit", pool.key().as_ref(), user.key().as_ref()],
+        bump = user_deposit.bump,
+    )]
+    pub user_deposit: Account<'info, UserDeposit>,
+    
+    /// The pool's tokens the payout comes from
+    #[account(
+        mut,
+        token::authority = pool,
+        token::mint = pool.deposit_mint,
+    )]
+    pub pool_token_account: Account<'info, TokenAccount>,
+    
+    /// Where the payout goes, of the same mint
+    #[account(mut, token::mint = pool.deposit_mint)]
+    pub user_token_account: Account<'info, TokenAccount>,
+    
+    pub user: Signer<'info>,
+    
+    pub token_program: Program<'info, Token>,
+}
+
+/// Compute budgeted per `split_payment` recipient: token account
+/// deserialization plus one token transfer CPI
+pub const SPLIT_UNITS_PER_RECIPIENT: u64 = 8_000;
+
+#[derive(Accounts)]
+pub struct SplitPayment<'info> {
+    #[account(mut, token::authority = payer)]
+    pub source: Account<'info, TokenAccount>,
+    
+    pub payer: Signer<'info>,
+    
+    pub token_program: Program<'info, Token>,
+    
+    // remaining_accounts: recipient token accounts, in `bps_splits` order
+}
+
+#[derive(Accounts)]
+pub struct DepositWithPermit<'info> {
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, Config>,
+    
+    #[account(
+        mut,
+        seeds = [b"pool", pool.authority.as_ref()],
+        bump = pool.bump,
+        has_one = config,
+    )]
+    pub pool: Account<'info, Pool>,
+    
+    #[account(
+        mut,
+        seeds = [b"user_deposit", pool.key().as_ref(), user.key().as_ref()],
+        bump = user_deposit.bump,
+    )]
+    pub user_deposit: Account<'info, UserDeposit>,
+    
+    /// CHECK: Consents through the Ed25519-signed permit, not a signature
+    /// on this transaction
+    pub user: UncheckedAccount<'info>,
+    
+    /// The user's tokens, of the mint this pool accepts
+    #[account(
+        mut,
+        token::authority = user,
+        token::mint = pool.deposit_mint,
+    )]
+    pub user_token_account: Account<'info, TokenAccount>,
+    
+    /// SECURE: The pool's own tokens, of the same mint
+    #[account(
+        mut,
+        token::authority = pool,
+        token::mint = pool.deposit_mint,
+    )]
+    pub pool_token_account: Account<'info, TokenAccount>,
+    
+    /// CHECK: A data-less PDA; signs as the delegate the user approved on
+    /// `user_token_account`
+    #[account(seeds = [b"permit_delegate", pool.key().as_ref()], bump)]
+    pub permit_delegate: UncheckedAccount<'info>,
+    
+    /// Submits the transaction and pays its fees
+    pub relayer: Signer<'info>,
+    
+    /// CHECK: The instructions sysvar, pinned by address
+    #[account(address = anchor_lang::solana_program::sysvar::instructions::ID)]
+    pub instructions: UncheckedAccount<'info>,
+    
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct SetReferrer<'info> {
+    #[account(
+        mut,
+        seeds = [b"user", user.key().as_ref()],
+        bump = user_account.bump,
+    )]
+    pub user_account: Account<'info, UserAccount>,
+    
+    /// SECURE: Validated as a UserAccount whenever it is passed
+    #[account(mut)]
+    pub referrer_account: Option<Account<'info, UserAccount>>,
+    
+    pub user: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct MemberAction<'info> {
+    pub org: Account<'info, Org>,
+    
+    /// SECURE: team must belong to org
+    #[account(has_one = org)]
+    pub team: Account<'info, Team>,
+    
+    /// SECURE: member must belong to team, and be the signer
+    #[account(has_one = team, has_one = wallet)]
+    pub member: Account<'info, Member>,
+    
+    pub wallet: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SwapUnlinked<'info> {
+    #[account(
+        mut,
+        seeds = [b"pool", pool.authority.as_ref()],
+        bump = pool.bump,
     )]
-    pub config: Account<'info, Config>,
+    pub pool: Account<'info, Pool>,
+    
+    /// VULNERABLE: Any pool's vault
+    #[account(mut)]
+    pub pool_vault: Account<'info, PoolVault>,
+    
+    /// VULNERABLE: Any mint
+    pub expected_mint: Account<'info, Mint>,
     
     pub user: Signer<'info>,
 }
 
 #[derive(Accounts)]
-pub struct DepositSecure<'info> {
+pub struct SwapSecureLinked<'info> {
     #[account(
+        mut,
         seeds = [b"pool", pool.authority.as_ref()],
         bump = pool.bump,
     )]
     pub pool: Account<'info, Pool>,
     
-    /// SECURE: Token account with ownership validation
-    /// 
-    /// Constraints ensure:
-    /// 1. `token::authority` - Token account owner is the user
-    /// 2. `token::mint` - Token account holds correct mint
-    /// 3. Anchor's TokenAccount validates it's a valid SPL token account
+    /// SECURE: The vault this pool points at, pointing back at it
     #[account(
         mut,
-        token::authority = user,
-        // token::mint = pool.deposit_mint, // Would add in real implementation
+        address = pool.vault @ ValidationError::BrokenAccountLink,
+        has_one = pool @ ValidationError::BrokenAccountLink,
     )]
-    pub user_token_account: Account<'info, TokenAccount>,
+    pub pool_vault: Account<'info, PoolVault>,
+    
+    /// SECURE: The mint the vault holds
+    #[account(address = pool_vault.mint @ ValidationError::BrokenAccountLink)]
+    pub expected_mint: Account<'info, Mint>,
     
     pub user: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeOrg<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + Org::INIT_SPACE,
+        seeds = [b"org", authority.key().as_ref()],
+        bump
+    )]
+    pub org: Account<'info, Org>,
     
-    pub token_program: Program<'info, Token>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeTeam<'info> {
+    #[account(has_one = authority)]
+    pub org: Account<'info, Org>,
+    
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + Team::INIT_SPACE,
+        seeds = [b"team", org.key().as_ref(), lead.key().as_ref()],
+        bump
+    )]
+    pub team: Account<'info, Team>,
+    
+    /// CHECK: Only recorded as the team lead
+    pub lead: UncheckedAccount<'info>,
+    
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct AddMember<'info> {
+    #[account(has_one = lead)]
+    pub team: Account<'info, Team>,
+    
+    #[account(
+        init,
+        payer = lead,
+        space = 8 + Member::INIT_SPACE,
+        seeds = [b"member", team.key().as_ref(), wallet.key().as_ref()],
+        bump
+    )]
+    pub member: Account<'info, Member>,
+    
+    /// CHECK: Only recorded as the member's wallet
+    pub wallet: UncheckedAccount<'info>,
+    
+    #[account(mut)]
+    pub lead: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
 #[instruction(reward_rate: u64)]
 pub struct InitializePool<'info> {
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    
     #[account(
         init,
         payer = authority,
@@ -303,6 +1936,164 @@ pub struct InitializePool<'info> {
     pub system_program: Program<'info, System>,
 }
 
+#[derive(Accounts)]
+pub struct SetFeeHoliday<'info> {
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = admin,
+    )]
+    pub config: Account<'info, Config>,
+    
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct OpenDeposit<'info> {
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    
+    #[account(has_one = config)]
+    pub pool: Account<'info, Pool>,
+    
+    #[account(
+        mut,
+        seeds = [b"user", user.key().as_ref()],
+        bump = user_account.bump,
+    )]
+    pub user_account: Account<'info, UserAccount>,
+    
+    #[account(
+        init,
+        payer = user,
+        space = 8 + UserDeposit::INIT_SPACE,
+        seeds = [b"user_deposit", pool.key().as_ref(), user.key().as_ref()],
+        bump
+    )]
+    pub user_deposit: Account<'info, UserDeposit>,
+    
+    #[account(mut)]
+    pub user: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CloseDeposit<'info> {
+    #[account(
+        mut,
+        seeds = [b"user", user.key().as_ref()],
+        bump = user_account.bump,
+    )]
+    pub user_account: Account<'info, UserAccount>,
+    
+    #[account(
+        mut,
+        seeds = [b"user_deposit", user_deposit.pool.as_ref(), user.key().as_ref()],
+        bump = user_deposit.bump,
+        close = user,
+    )]
+    pub user_deposit: Account<'info, UserDeposit>,
+    
+    #[account(mut)]
+    pub user: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SweepDust<'info> {
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    
+    #[account(
+        mut,
+        seeds = [b"pool", pool.authority.as_ref()],
+        bump = pool.bump,
+        has_one = config,
+    )]
+    pub pool: Account<'info, Pool>,
+    
+    #[account(
+        mut,
+        seeds = [b"user", owner.key().as_ref()],
+        bump = user_account.bump,
+    )]
+    pub user_account: Account<'info, UserAccount>,
+    
+    /// SECURE: Rent can only be returned to the position's owner
+    #[account(
+        mut,
+        seeds = [b"user_deposit", pool.key().as_ref(), owner.key().as_ref()],
+        bump = user_deposit.bump,
+        has_one = owner,
+        close = owner,
+    )]
+    pub user_deposit: Account<'info, UserDeposit>,
+    
+    /// CHECK: Only receives the rent; pinned by `has_one = owner`
+    #[account(mut)]
+    pub owner: UncheckedAccount<'info>,
+    
+    /// The position's owner or the pool authority
+    pub sweeper: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct InitializePoolVault<'info> {
+    #[account(
+        mut,
+        seeds = [b"pool", authority.key().as_ref()],
+        bump = pool.bump,
+        has_one = authority,
+    )]
+    pub pool: Account<'info, Pool>,
+    
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + PoolVault::INIT_SPACE,
+        seeds = [b"pool_vault", pool.key().as_ref()],
+        bump
+    )]
+    pub pool_vault: Account<'info, PoolVault>,
+    
+    pub mint: Account<'info, Mint>,
+    
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeUserAccount<'info> {
+    #[account(
+        init,
+        payer = user,
+        space = 8 + UserAccount::INIT_SPACE,
+        seeds = [b"user", user.key().as_ref()],
+        bump
+    )]
+    pub user_account: Account<'info, UserAccount>,
+    
+    #[account(mut)]
+    pub user: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct VerifyPda<'info> {
+    /// CHECK: Only the address is compared; the account need not exist
+    pub account: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ReadConfigRentChecked<'info> {
+    /// CHECK: Address, owner and rent exemption are checked in the instruction
+    pub config: UncheckedAccount<'info>,
+}
+
 #[derive(Accounts)]
 pub struct InitializeConfig<'info> {
     #[account(
@@ -329,7 +2120,109 @@ pub struct InitializeConfig<'info> {
 pub struct Pool {
     pub authority: Pubkey,
     pub total_deposited: u64,
+    /// Rewards each position earns per second, in basis points of its amount
     pub reward_rate: u64,
+    /// Protocol config this pool was created under
+    pub config: Pubkey,
+    /// Rewards accrued to depositors and not yet claimed
+    pub total_accrued: u64,
+    /// Most `total_deposited` may ever reach, across all users
+    pub global_tvl_cap: u64,
+    /// How long each deposit keeps the depositor's balance locked
+    pub lock_secs: i64,
+    /// Dust balances `sweep_dust` moved from positions to the pool
+    pub dust_swept: u64,
+    /// How long a deposit must stay put to be withdrawn without penalty
+    pub min_hold_secs: i64,
+    /// Share of an early withdrawal forfeited to the pool, out of 10_000
+    pub early_exit_bps: u16,
+    /// Penalties collected from early withdrawals
+    pub early_exit_fees: u64,
+    /// The only mint `deposit_secure_v2` accepts
+    pub deposit_mint: Pubkey,
+    /// The mint `claim_rewards_secure` pays out
+    pub reward_mint: Pubkey,
+    /// The `PoolVault` holding this pool's swap reserves
+    pub vault: Pubkey,
+    pub bump: u8,
+}
+
+/// Swap reserves of one mint, held for one pool
+#[account]
+#[derive(InitSpace)]
+pub struct PoolVault {
+    /// The pool this vault belongs to
+    pub pool: Pubkey,
+    pub mint: Pubkey,
+    pub reserve: u64,
+    pub bump: u8,
+}
+
+/// One user's position in a pool
+#[account]
+#[derive(InitSpace)]
+pub struct UserDeposit {
+    pub pool: Pubkey,
+    pub owner: Pubkey,
+    pub amount: u64,
+    /// Unix timestamp before which `withdraw` is rejected
+    pub locked_until: i64,
+    /// Unix timestamp of the latest deposit; starts the minimum hold
+    pub deposited_at: i64,
+    /// Nonce the next deposit permit must carry
+    pub permit_nonce: u64,
+    /// Rewards earned and not yet claimed
+    pub accrued_rewards: u64,
+    /// Unix timestamp `accrued_rewards` is up to date as of
+    pub rewards_updated_at: i64,
+    pub bump: u8,
+}
+
+/// What a user signs off-chain to let a relayer deposit for them.
+/// 
+/// The Ed25519-signed message is `DepositPermit::DOMAIN` followed by the
+/// Borsh encoding of this struct.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct DepositPermit {
+    pub pool: Pubkey,
+    pub owner: Pubkey,
+    pub amount: u64,
+    /// Last unix timestamp the permit can be used at
+    pub expiry: i64,
+    /// Must equal the position's `permit_nonce` when used
+    pub nonce: u64,
+}
+
+impl DepositPermit {
+    /// Keeps a permit from being mistaken for any other signed message
+    pub const DOMAIN: &'static [u8] = b"account_validation:deposit_permit:";
+    
+    /// The bytes the owner signs
+    pub fn message(&self) -> Vec<u8> {
+        let mut message = Self::DOMAIN.to_vec();
+        self.serialize(&mut message).expect("serializing to a Vec can't fail");
+        message
+    }
+    
+    pub fn from_message(message: &[u8]) -> Result<Self> {
+        let body = message
+            .strip_prefix(Self::DOMAIN)
+            .ok_or(ValidationError::InvalidPermit)?;
+        Self::try_from_slice(body).map_err(|_| error!(ValidationError::InvalidPermit))
+    }
+}
+
+/// Per-user bookkeeping shared by all of a user's positions
+#[account]
+#[derive(InitSpace)]
+pub struct UserAccount {
+    pub owner: Pubkey,
+    /// UserDeposit accounts currently open, across all pools
+    pub open_positions: u16,
+    /// Owner of the account that referred this user, if any
+    pub referrer: Option<Pubkey>,
+    /// Users who named this account as their referrer
+    pub referrals: u32,
     pub bump: u8,
 }
 
@@ -338,6 +2231,39 @@ pub struct Pool {
 pub struct Config {
     pub admin: Pubkey,
     pub fee_bps: u16,
+    /// Swaps before this Unix timestamp pay no fee
+    pub fee_holiday_until: i64,
+    /// Most positions a single user may hold open at once
+    pub max_positions: u16,
+    /// Positions holding less than this may be swept into the pool by
+    /// their owner or the pool authority
+    pub dust_threshold: u64,
+    pub bump: u8,
+}
+
+/// Root of the `Org -> Team -> Member` chain
+#[account]
+#[derive(InitSpace)]
+pub struct Org {
+    pub authority: Pubkey,
+    pub bump: u8,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Team {
+    /// Parent org
+    pub org: Pubkey,
+    pub lead: Pubkey,
+    pub bump: u8,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Member {
+    /// Parent team
+    pub team: Pubkey,
+    pub wallet: Pubkey,
     pub bump: u8,
 }
 
@@ -353,6 +2279,221 @@ pub enum ValidationError {
     InvalidPDA,
     #[msg("Token account does not belong to user")]
     TokenAccountOwnerMismatch,
+    // 3: `ZeroAmount` moved to `SecurityError`
+    #[msg("Expected a data account but received a program account")]
+    ProgramAccountNotAllowed = 4,
+    #[msg("Pool tokens do not cover outstanding accrued rewards")]
+    PoolInsolvent,
+    #[msg("Deposit would exceed the pool's global TVL cap")]
+    TvlCapReached,
+    #[msg("Deposit is still locked")]
+    DepositLocked,
+    #[msg("Lock period must not be negative")]
+    InvalidLockPeriod,
+    #[msg("Split weights must sum to 10000 basis points")]
+    InvalidSplit,
+    #[msg("Number of recipients does not match number of splits")]
+    RecipientCountMismatch,
+    #[msg("Token account holds a different mint")]
+    TokenMintMismatch,
+    #[msg("Seeds exceed the PDA seed limits or have no valid bump")]
+    InvalidSeeds,
+    #[msg("User already holds the maximum number of open positions")]
+    TooManyPositions,
+    #[msg("Position still holds a balance")]
+    DepositNotEmpty,
+    #[msg("Referrer has already been set")]
+    ReferrerAlreadySet,
+    #[msg("Users cannot refer themselves")]
+    SelfReferral,
+    #[msg("Position balance is not below the dust threshold")]
+    NotDust,
+    #[msg("Account data length does not match the expected type")]
+    UnexpectedAccountLength,
+    #[msg("Early-exit penalty must not exceed 10000 basis points")]
+    InvalidPenalty,
+    #[msg("Deposit was not approved")]
+    PermitNotApproved,
+    #[msg("No valid Ed25519 permit from the user for this deposit")]
+    InvalidPermit,
+    #[msg("Deposit permit has expired")]
+    PermitExpired,
+    #[msg("Deposit permit was already used")]
+    PermitReplayed,
+    #[msg("Output is below the caller's minimum")]
+    SlippageExceeded,
+    #[msg("Deadline has passed")]
+    DeadlinePassed,
+    #[msg("Account is not rent exempt")]
+    NotRentExempt,
+    #[msg("Accounts are not linked to each other")]
+    BrokenAccountLink,
+    #[msg("Only the position's owner or the pool authority may sweep it")]
+    UnauthorizedSweeper,
+}
+
+error_categories!(ValidationError {
+    Auth => [PermitNotApproved, InvalidPermit, UnauthorizedSweeper],
+    Validation => [
+        InvalidOwner, InvalidPDA, TokenAccountOwnerMismatch, ProgramAccountNotAllowed, InvalidLockPeriod,
+        InvalidSplit, RecipientCountMismatch, TokenMintMismatch, InvalidSeeds, SelfReferral, UnexpectedAccountLength,
+        InvalidPenalty, SlippageExceeded, BrokenAccountLink,
+    ],
+    State => [
+        PoolInsolvent, TvlCapReached, DepositLocked, TooManyPositions, DepositNotEmpty, ReferrerAlreadySet, NotDust,
+        PermitExpired, PermitReplayed, DeadlinePassed, NotRentExempt,
+    ],
+});
+
+// ============================================================================
+// ACCOUNT GUARD HELPERS
+// ============================================================================
+
+/// Manual validation helpers for accounts that can't use `Account<'info, T>`.
+pub mod guards {
+    use super::*;
+    use anchor_lang::solana_program::{bpf_loader, bpf_loader_deprecated, bpf_loader_upgradeable};
+
+    /// Rejects program accounts where a data account is expected.
+    /// 
+    /// A program account passed as "state" usually deserializes as garbage,
+    /// but its bytes are attacker-chosen (the attacker deployed it). Any
+    /// account that is executable or owned by one of the BPF loaders is a
+    /// program (or its program-data), never state.
+    pub fn assert_not_program_account(info: &AccountInfo) -> Result<()> {
+        let owned_by_loader = *info.owner == bpf_loader::ID
+            || *info.owner == bpf_loader_deprecated::ID
+            || *info.owner == bpf_loader_upgradeable::ID;
+        
+        require!(
+            !info.executable && !owned_by_loader,
+            ValidationError::ProgramAccountNotAllowed
+        );
+        Ok(())
+    }
+    
+    /// Rejects accounts whose data isn't exactly the size of a `T`.
+    /// 
+    /// For fixed-size accounts that is `8 + T::INIT_SPACE`. Deserialization
+    /// alone doesn't catch a resize: trailing bytes after a grown account
+    /// are silently ignored, and a layout read at fixed offsets can land on
+    /// whatever was appended. Only meaningful for types without `Vec` or
+    /// `String` fields, whose `INIT_SPACE` is an upper bound.
+    pub fn assert_exact_len<T: Space>(info: &AccountInfo) -> Result<()> {
+        require_eq!(info.data_len(), 8 + T::INIT_SPACE, ValidationError::UnexpectedAccountLength);
+        Ok(())
+    }
+    
+    /// Rejects claims against a pool that can't pay what it owes.
+    /// 
+    /// A reward rate is only meaningful while the pool holds enough tokens
+    /// to cover `total_accrued`. Once it doesn't, early claimers would be
+    /// paid out of later claimers' rewards. Run after the claimant's rewards
+    /// are accrued, so the payout being claimed is counted.
+    pub fn assert_solvent(ctx: &Context<ClaimRewardsSecure>) -> Result<()> {
+        require!(
+            ctx.accounts.reward_vault_token_account.amount >= ctx.accounts.pool.total_accrued,
+            ValidationError::PoolInsolvent
+        );
+        Ok(())
+    }
+}
+
+// ============================================================================
+// PERMIT HELPERS
+// ============================================================================
+
+/// Reading Ed25519 signature checks out of the instructions sysvar.
+pub mod permits {
+    use super::*;
+    use anchor_lang::solana_program::ed25519_program;
+    use anchor_lang::solana_program::sysvar::instructions::load_instruction_at_checked;
+
+    /// Header of an Ed25519 program instruction: signature count and padding
+    const HEADER_LEN: usize = 2;
+    /// Seven u16 offsets per signature
+    const OFFSETS_LEN: usize = 14;
+    /// `instruction_index` meaning "this instruction's own data"
+    const THIS_INSTRUCTION: u16 = u16::MAX;
+
+    /// The message `signer` signed with `signature`, taken from an Ed25519
+    /// program instruction in this transaction.
+    /// 
+    /// The runtime rejects the whole transaction if that instruction's
+    /// signature doesn't verify, so finding it is proof the signer signed.
+    /// Only single-signature instructions that keep the key, signature and
+    /// message in their own data are accepted - offsets into other
+    /// instructions could point the check at bytes this one never reads.
+    pub fn verified_message(instructions: &AccountInfo, signer: &Pubkey, signature: &[u8; 64]) -> Result<Vec<u8>> {
+        let mut index = 0;
+        // Loading past the last instruction fails, which ends the scan
+        while let Ok(instruction) = load_instruction_at_checked(index, instructions) {
+            if instruction.program_id == ed25519_program::ID {
+                if let Some(message) = signed_message(&instruction.data, signer, signature) {
+                    return Ok(message.to_vec());
+                }
+            }
+            index += 1;
+        }
+        err!(ValidationError::InvalidPermit)
+    }
+
+    fn signed_message<'a>(data: &'a [u8], signer: &Pubkey, signature: &[u8; 64]) -> Option<&'a [u8]> {
+        if data.len() < HEADER_LEN + OFFSETS_LEN || data[0] != 1 {
+            return None;
+        }
+        let offset = |field: usize| {
+            let at = HEADER_LEN + 2 * field;
+            u16::from_le_bytes([data[at], data[at + 1]])
+        };
+        let (signature_at, signature_ix) = (usize::from(offset(0)), offset(1));
+        let (pubkey_at, pubkey_ix) = (usize::from(offset(2)), offset(3));
+        let (message_at, message_len, message_ix) = (usize::from(offset(4)), usize::from(offset(5)), offset(6));
+        if [signature_ix, pubkey_ix, message_ix] != [THIS_INSTRUCTION; 3] {
+            return None;
+        }
+        
+        let signed_by = data.get(pubkey_at..pubkey_at + 32)?;
+        let signed_with = data.get(signature_at..signature_at + 64)?;
+        (signed_by == signer.as_ref() && signed_with == signature.as_slice())
+            .then(|| data.get(message_at..message_at + message_len))
+            .flatten()
+    }
+}
+
+// ============================================================================
+// PAYMENT HELPERS
+// ============================================================================
+
+/// Amount arithmetic for instructions that pay several parties at once.
+pub mod payments {
+    use super::*;
+
+    /// Basis points in 100%
+    pub const BPS_DENOMINATOR: u16 = 10_000;
+
+    /// Splits `total` by basis-point weights.
+    /// 
+    /// Each share is `total * bps / 10_000`, rounded down, and the rounding
+    /// remainder is added to the first share so the result always sums to
+    /// `total`. `bps_splits` must be non-empty and sum to exactly 10_000.
+    pub fn split_amounts(total: u64, bps_splits: &[u16]) -> Result<Vec<u64>> {
+        let weight: u32 = bps_splits.iter().map(|bps| u32::from(*bps)).sum();
+        require!(
+            !bps_splits.is_empty() && weight == u32::from(BPS_DENOMINATOR),
+            ValidationError::InvalidSplit
+        );
+        
+        // bps <= 10_000, so every share fits back into a u64
+        let mut amounts: Vec<u64> = bps_splits
+            .iter()
+            .map(|bps| (u128::from(total) * u128::from(*bps) / u128::from(BPS_DENOMINATOR)) as u64)
+            .collect();
+        let distributed: u64 = amounts.iter().sum();
+        amounts[0] += total - distributed;
+        
+        Ok(amounts)
+    }
 }
 
 // ============================================================================
@@ -364,7 +2505,18 @@ pub enum ValidationError {
 // - Verify token account ownership with `token::authority`
 // - Check token mint with `token::mint` constraint
 // - Use `has_one` to validate account relationships
+// - Validate EVERY link of a relationship chain, not just the nearest one
+// - Handle `None` for optional accounts; validate them only when present
 // - Validate program accounts with Program<'info, T>
 // - Add explicit constraints for business logic validation
+// - Reject program accounts where data accounts are expected
+// - Check the pool can cover accrued rewards before paying any out
+// - Lock deposits for a period so they can't be withdrawn in the same slot
+// - Route rounding remainders explicitly so splits conserve the total
+// - Cap how many accounts one user can open so state can't be bloated
+// - Penalize withdrawals inside a minimum hold so deposits can't sandwich
+// - Check fixed-size accounts are exactly their type's size before raw reads
+// - Accept deposits on a user's behalf only with an Ed25519 permit that
+//   names the deposit, expires and carries a single-use nonce
 //
 // ============================================================================
@@ -22,6 +22,11 @@
 //! - Must use explicit checked/saturating arithmetic for safety
 
 use anchor_lang::prelude::*;
+use anchor_lang::solana_program::program_option::COption;
+use anchor_spl::token::{self, spl_token::instruction::AuthorityType, Mint, MintTo, SetAuthority, Token, TokenAccount};
+use security_common::compute::require_compute_budget;
+use security_common::{assert_value_conserved, error_categories, sum_balances};
+pub use security_common::SecurityError;
 
 declare_id!("Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLnU");
 
@@ -50,7 +55,7 @@ pub mod integer_overflow {
         
         // DANGER: Silent overflow in release builds!
         // u64::MAX + 1 = 0
-        vault.total_deposits = vault.total_deposits + amount;
+        vault.total_deposits += amount;
         
         msg!("VULNERABLE: Deposited {}, total: {}", amount, vault.total_deposits);
         Ok(())
@@ -58,12 +63,14 @@ pub mod integer_overflow {
 
     /// SECURE: Uses checked arithmetic that returns None on overflow.
     pub fn deposit_secure(ctx: Context<Deposit>, amount: u64) -> Result<()> {
+        require!(amount > 0, SecurityError::ZeroAmount);
+        
         let vault = &mut ctx.accounts.vault;
         
         // SECURE: checked_add returns None if overflow would occur
         vault.total_deposits = vault.total_deposits
             .checked_add(amount)
-            .ok_or(MathError::Overflow)?;
+            .ok_or(SecurityError::Overflow)?;
         
         msg!("SECURE: Deposited {}, total: {}", amount, vault.total_deposits);
         Ok(())
@@ -90,7 +97,7 @@ pub mod integer_overflow {
         
         // DANGER: Underflow wraps to u64::MAX!
         // 100 - 101 = 18446744073709551615
-        user_account.balance = user_account.balance - amount;
+        user_account.balance -= amount;
         
         msg!("VULNERABLE: Withdrew {}, remaining: {}", amount, user_account.balance);
         Ok(())
@@ -98,17 +105,71 @@ pub mod integer_overflow {
 
     /// SECURE: Uses checked subtraction that fails on underflow.
     pub fn withdraw_secure(ctx: Context<Withdraw>, amount: u64) -> Result<()> {
+        require!(amount > 0, SecurityError::ZeroAmount);
+        
         let user_account = &mut ctx.accounts.user_account;
         
         // SECURE: checked_sub returns None if underflow would occur
         user_account.balance = user_account.balance
             .checked_sub(amount)
-            .ok_or(MathError::InsufficientFunds)?;
+            .ok_or(SecurityError::InsufficientFunds)?;
         
         msg!("SECURE: Withdrew {}, remaining: {}", amount, user_account.balance);
         Ok(())
     }
 
+    // ============================================================================
+    // SATURATING ARITHMETIC
+    // ============================================================================
+
+    /// TRADEOFF: Clamps `total_deposits` at u64::MAX instead of failing.
+    /// 
+    /// ## What's Hidden?
+    /// Saturation never errors, so the deposit "succeeds" while any part
+    /// past u64::MAX goes uncounted. Once the total is pinned at MAX every
+    /// further deposit is silently dropped, and code that treats MAX as
+    /// "unlimited" or "full" now misbehaves for everyone. Only use it for
+    /// values where a cap is genuinely the right answer (e.g. a counter
+    /// nobody redeems against), and emit `SaturationOccurred` when it
+    /// clamps. Returns the new total.
+    pub fn deposit_saturating(ctx: Context<Deposit>, amount: u64) -> Result<u64> {
+        require!(amount > 0, SecurityError::ZeroAmount);
+        
+        let vault = &mut ctx.accounts.vault;
+        
+        // TRADEOFF: saturating_add sticks at u64::MAX
+        let clamped = vault.total_deposits.checked_add(amount).is_none();
+        vault.total_deposits = vault.total_deposits.saturating_add(amount);
+        if clamped {
+            emit!(SaturationOccurred { field: "total_deposits".to_string() });
+        }
+        
+        msg!("SATURATING: Deposited {}, total: {}", amount, vault.total_deposits);
+        Ok(vault.total_deposits)
+    }
+
+    /// TRADEOFF: Floors the balance at zero instead of failing.
+    /// 
+    /// ## What's Hidden?
+    /// Withdrawing more than the balance succeeds and leaves zero, so a
+    /// caller that pays out `amount` afterwards pays more than was ever
+    /// held. `withdraw_secure` rejects the same call with
+    /// `InsufficientFunds`. Returns the remaining balance.
+    pub fn withdraw_saturating(ctx: Context<Withdraw>, amount: u64) -> Result<u64> {
+        require!(amount > 0, SecurityError::ZeroAmount);
+        
+        let user_account = &mut ctx.accounts.user_account;
+        
+        // TRADEOFF: saturating_sub sticks at 0
+        if amount > user_account.balance {
+            emit!(SaturationOccurred { field: "balance".to_string() });
+        }
+        user_account.balance = user_account.balance.saturating_sub(amount);
+        
+        msg!("SATURATING: Withdrew {}, remaining: {}", amount, user_account.balance);
+        Ok(user_account.balance)
+    }
+
     // ============================================================================
     // VULNERABILITY 3: MULTIPLICATION OVERFLOW
     // ============================================================================
@@ -138,18 +199,22 @@ pub mod integer_overflow {
         Ok(())
     }
 
-    /// SECURE: Uses checked multiplication.
-    pub fn calculate_price_secure(ctx: Context<PriceCalculation>, quantity: u64) -> Result<()> {
+    /// SECURE: Forms the price in u128 and returns it with the fee added.
+    /// 
+    /// `price_per_unit * quantity` and `subtotal * fee_bps` are both formed
+    /// by `safe_math` in u128, so only a total that doesn't fit in u64 fails
+    /// with `Overflow`. The fee rounds up.
+    pub fn calculate_price_secure(ctx: Context<PriceCalculation>, quantity: u64) -> Result<u64> {
         let config = &ctx.accounts.config;
         
-        // SECURE: checked_mul returns None on overflow
-        let total_price = config.price_per_unit
-            .checked_mul(quantity)
-            .ok_or(MathError::Overflow)?;
+        // SECURE: u128 intermediates, checked narrowing back to u64
+        let subtotal = safe_math::mul_div(config.price_per_unit, quantity, 1)?;
+        let fee = safe_math::mul_div_ceil(subtotal, config.fee_bps as u64, 10_000)?;
+        let total_price = safe_math::safe_add(subtotal, fee)?;
         
-        msg!("SECURE: {} units at {} each = {} total", 
-            quantity, config.price_per_unit, total_price);
-        Ok(())
+        msg!("SECURE: {} units at {} each = {} total ({} fee)", 
+            quantity, config.price_per_unit, total_price, fee);
+        Ok(total_price)
     }
 
     // ============================================================================
@@ -221,28 +286,349 @@ pub mod integer_overflow {
         Ok(fee)
     }
 
-    /// SECURE: Uses ceiling division to ensure minimum fee.
+    /// SECURE: Rounds the fee up with `safe_math::Bps`.
+    /// 
+    /// `Bps::apply_ceil` forms `amount * fee_bps` in u128 and rounds up, so
+    /// any non-zero amount pays at least 1 at a non-zero rate, and a zero
+    /// amount pays nothing. A `fee_bps` above 10_000 fails with `InvalidBps`.
     pub fn calculate_fee_secure(ctx: Context<FeeCalculation>, amount: u64) -> Result<u64> {
         let config = &ctx.accounts.config;
         
         // SECURE: Ceiling division ensures non-zero fee for any transfer
-        // Formula: (a + b - 1) / b = ceiling(a / b)
-        let numerator = amount
-            .checked_mul(config.fee_bps as u64)
-            .ok_or(MathError::Overflow)?;
+        let fee = safe_math::Bps(config.fee_bps).apply_ceil(amount)?;
         
-        let fee = numerator
-            .checked_add(10000 - 1)
-            .ok_or(MathError::Overflow)?
-            .checked_div(10000)
-            .ok_or(MathError::DivisionByZero)?;
+        msg!("SECURE: Fee on {} = {}", amount, fee);
+        Ok(fee)
+    }
+
+    // ============================================================================
+    // INTEREST ACCRUAL
+    // ============================================================================
+
+    /// SECURE: Accrues `principal * rate_per_second * elapsed / INTEREST_SCALE`.
+    /// 
+    /// The three-way product overflows u64 quickly - a large principal left
+    /// for a few years is enough, as is a high rate over a long gap - even
+    /// when the final interest fits. The whole numerator is formed in u128
+    /// with checked multiplies, divided by `INTEREST_SCALE`, and only then
+    /// narrowed back to u64 with a checked conversion. Returns the interest
+    /// added this call.
+    pub fn accrue_interest(ctx: Context<AccrueInterest>) -> Result<u64> {
+        let now = Clock::get()?.unix_timestamp;
+        let account = &mut ctx.accounts.interest_account;
+        
+        // A clock that reads earlier than the last accrual accrues nothing
+        let elapsed = now.saturating_sub(account.last_accrued_at).max(0) as u64;
+        
+        // SECURE: No intermediate product is ever held in u64
+        let numerator = u128::from(account.principal)
+            .checked_mul(u128::from(account.rate_per_second))
+            .and_then(|product| product.checked_mul(u128::from(elapsed)))
+            .ok_or(SecurityError::Overflow)?;
+        let interest = u64::try_from(numerator / u128::from(INTEREST_SCALE))
+            .map_err(|_| error!(SecurityError::Overflow))?;
         
-        // Alternatively, ensure minimum fee
-        let min_fee = 1u64;
-        let final_fee = fee.max(min_fee);
+        account.accrued_interest = safe_math::safe_add(account.accrued_interest, interest)?;
+        account.last_accrued_at = now;
         
-        msg!("SECURE: Fee on {} = {} (min {})", amount, final_fee, min_fee);
-        Ok(final_fee)
+        msg!("SECURE: Accrued {} interest over {}s", interest, elapsed);
+        Ok(interest)
+    }
+
+    // ============================================================================
+    // COMPOUND INTEREST
+    // ============================================================================
+
+    /// VULNERABLE: Compounds with `pow` in u64.
+    /// 
+    /// ## What's Wrong?
+    /// `principal * (1 + rate)^periods` is computed as
+    /// `principal * (10_000 + rate_bps)^periods / 10_000^periods`. Both
+    /// powers pass u64::MAX within a few periods (10_000^5 already does),
+    /// long before the compounded balance is anywhere near it.
+    /// 
+    /// ## Attack Scenario:
+    /// 1. A loan compounds 1% per period over 16 periods
+    /// 2. 10_000^16 wraps to 0 in release, (10_100^16) to an arbitrary value
+    /// 3. The debt is computed from garbage, or the division by zero aborts
+    ///    every repayment and liquidation of the position
+    pub fn accrue_compound_interest_vulnerable(
+        _ctx: Context<CompoundInterest>,
+        principal: u64,
+        rate_bps: u16,
+        periods: u32,
+    ) -> Result<u64> {
+        // DANGER: pow overflows silently in release builds
+        let growth = (10_000 + rate_bps as u64).pow(periods);
+        let scale = 10_000u64.pow(periods);
+        let balance = principal * growth / scale;
+        
+        msg!("VULNERABLE: {} compounded at {}bps for {} periods = {}", principal, rate_bps, periods, balance);
+        Ok(balance)
+    }
+
+    /// SECURE: Compounds one period at a time with `safe_math::mul_div_ceil`.
+    /// 
+    /// Each step is `balance * (10_000 + rate_bps) / 10_000` in u128,
+    /// rounded up so the debt never compounds to less than it should. Only
+    /// a balance that no longer fits in u64 fails with `Overflow`.
+    /// `periods` is capped at `MAX_COMPOUND_PERIODS` so the loop's compute
+    /// is bounded (`TooManyPeriods`), and `rate_bps` at 10_000 (`InvalidBps`).
+    /// Returns the compounded balance.
+    pub fn accrue_compound_interest(
+        _ctx: Context<CompoundInterest>,
+        principal: u64,
+        rate_bps: u16,
+        periods: u32,
+    ) -> Result<u64> {
+        require!(periods <= MAX_COMPOUND_PERIODS, MathError::TooManyPeriods);
+        require!(rate_bps <= safe_math::BPS_DENOMINATOR, MathError::InvalidBps);
+        
+        let growth = safe_math::BPS_DENOMINATOR as u64 + rate_bps as u64;
+        let mut balance = principal;
+        for _ in 0..periods {
+            balance = safe_math::mul_div_ceil(balance, growth, safe_math::BPS_DENOMINATOR as u64)?;
+        }
+        
+        msg!("SECURE: {} compounded at {}bps for {} periods = {}", principal, rate_bps, periods, balance);
+        Ok(balance)
+    }
+
+    // ============================================================================
+    // REWARD MINTING
+    // ============================================================================
+
+    /// VULNERABLE: Mints `base * multiplier` reward tokens, unchecked.
+    /// 
+    /// ## What's Wrong?
+    /// The product wraps in release builds, and whatever it wraps to is
+    /// minted for real. There is also no supply cap, so a product that
+    /// doesn't wrap mints without limit.
+    /// 
+    /// ## Attack Scenario:
+    /// 1. Attacker picks base = 2^32 and multiplier = 2^32 + 5
+    /// 2. base * multiplier = 2^64 + 5 * 2^32, which wraps to 5 * 2^32
+    /// 3. Any reward can be dialled in by choosing factors that wrap to it,
+    ///    while off-chain checks on either factor alone look plausible
+    pub fn mint_rewards_vulnerable(ctx: Context<MintReward>, base: u64, multiplier: u64) -> Result<u64> {
+        // DANGER: Multiplication overflow, and nothing caps the supply
+        let reward = base * multiplier;
+        mint_reward(ctx.accounts, reward)?;
+        
+        msg!("VULNERABLE: Minted {} ({} x {})", reward, base, multiplier);
+        Ok(reward)
+    }
+
+    /// SECURE: Checks the product and caps the mint's supply.
+    /// 
+    /// Only the minter's `admin` can mint (`has_one = admin`).
+    /// `base * multiplier` is a checked multiplication (`Overflow`), and the
+    /// mint's supply after minting must stay within the minter's
+    /// `max_supply` (`MaxSupplyExceeded`). Returns the amount minted.
+    pub fn mint_rewards_secure(ctx: Context<MintReward>, base: u64, multiplier: u64) -> Result<u64> {
+        // SECURE: checked_mul returns None on overflow
+        let reward = safe_math::safe_mul(base, multiplier)?;
+        let supply = safe_math::safe_add(ctx.accounts.mint.supply, reward)?;
+        require!(supply <= ctx.accounts.mint_authority.max_supply, MathError::MaxSupplyExceeded);
+        mint_reward(ctx.accounts, reward)?;
+        
+        msg!("SECURE: Minted {} ({} x {}), supply {}", reward, base, multiplier, supply);
+        Ok(reward)
+    }
+
+    // ============================================================================
+    // LP REDEMPTION
+    // ============================================================================
+
+    /// SECURE: Burns `lp_amount` LP tokens for `lp_amount * reserve / total_lp`
+    /// of the pool's reserve.
+    /// 
+    /// `lp_amount * reserve` overflows u64 long before either side is large,
+    /// so the share is formed with `safe_math::mul_div` and rounded down: a
+    /// redemption never takes more than its share, and any dust stays with
+    /// the remaining holders. Redeeming the entire supply returns the entire
+    /// reserve, because `total_lp * reserve / total_lp` is exact. The burn
+    /// is a checked subtraction from both the position and the supply.
+    /// Returns the reserve paid out.
+    pub fn redeem(ctx: Context<Redeem>, lp_amount: u64) -> Result<u64> {
+        require!(lp_amount > 0, SecurityError::ZeroAmount);
+        let pool = &mut ctx.accounts.pool;
+        let position = &mut ctx.accounts.position;
+        
+        let owed = safe_math::mul_div(lp_amount, pool.reserve, pool.total_lp)?;
+        
+        position.lp_amount = safe_math::safe_sub(position.lp_amount, lp_amount)?;
+        pool.total_lp = safe_math::safe_sub(pool.total_lp, lp_amount)?;
+        pool.reserve = safe_math::safe_sub(pool.reserve, owed)?;
+        
+        msg!("SECURE: Burned {} LP for {} of the reserve", lp_amount, owed);
+        Ok(owed)
+    }
+
+    // ============================================================================
+    // REFERRAL FEES
+    // ============================================================================
+
+    /// SECURE: Deposits `amount` less the fee, splitting the fee between the
+    /// protocol and an optional referrer.
+    /// 
+    /// The fee is `calculate_bps_fee(amount, fee_bps)`; the referrer's cut is
+    /// `mul_div(fee, referral_bps, 10_000)` and the protocol keeps the rest,
+    /// so the two always sum to the fee. Every running total is updated with
+    /// checked addition. A zero fee credits nobody, and depositors can't
+    /// refer themselves.
+    /// 
+    /// `referral_account` must be passed exactly when `referrer` is `Some`,
+    /// and must belong to that referrer. The deposit, protocol fees and
+    /// referral earnings together grow by exactly `amount`.
+    pub fn deposit_with_referral(
+        ctx: Context<DepositWithReferral>,
+        amount: u64,
+        referrer: Option<Pubkey>,
+    ) -> Result<()> {
+        require!(amount > 0, SecurityError::ZeroAmount);
+        let config = &ctx.accounts.config;
+        let earnings = |account: &Option<Account<ReferralAccount>>| {
+            account.as_ref().map_or(0, |account| account.referral_earnings)
+        };
+        let before = sum_balances([
+            ctx.accounts.vault.total_deposits,
+            ctx.accounts.vault.protocol_fees,
+            earnings(&ctx.accounts.referral_account),
+            amount,
+        ])?;
+        
+        let fee = safe_math::calculate_bps_fee(amount, config.fee_bps)?;
+        let referral_cut = match (referrer, ctx.accounts.referral_account.as_mut()) {
+            (Some(referrer), Some(referral_account)) => {
+                require_keys_neq!(referrer, ctx.accounts.depositor.key(), MathError::SelfReferral);
+                require_keys_eq!(referral_account.referrer, referrer, MathError::ReferrerMismatch);
+                
+                let cut = safe_math::mul_div(fee, config.referral_bps as u64, 10_000)?;
+                referral_account.referral_earnings = safe_math::safe_add(referral_account.referral_earnings, cut)?;
+                cut
+            }
+            (None, None) => 0,
+            _ => return Err(error!(MathError::ReferrerMismatch)),
+        };
+        
+        let vault = &mut ctx.accounts.vault;
+        vault.protocol_fees = safe_math::safe_add(vault.protocol_fees, safe_math::safe_sub(fee, referral_cut)?)?;
+        vault.total_deposits = safe_math::safe_add(vault.total_deposits, safe_math::safe_sub(amount, fee)?)?;
+        
+        // Every unit of `amount` landed in exactly one of the three buckets
+        let after = sum_balances([
+            vault.total_deposits,
+            vault.protocol_fees,
+            earnings(&ctx.accounts.referral_account),
+        ])?;
+        assert_value_conserved(before, after)?;
+        
+        msg!("SECURE: Deposited {}, fee {} ({} to referrer)", amount, fee, referral_cut);
+        Ok(())
+    }
+
+    // ============================================================================
+    // FEE COLLECTION
+    // ============================================================================
+
+    /// SECURE: Sweeps the pending fees of every `FeePosition` in
+    /// `remaining_accounts` into the vault's `protocol_fees`.
+    /// 
+    /// Each position is loaded as `Account<'info, FeePosition>` and must
+    /// belong to this vault; passing the same position twice fails with
+    /// `DuplicatePosition` rather than counting its fees twice. The whole
+    /// batch is summed with checked addition - together with the vault's
+    /// existing `protocol_fees` - before any position is zeroed, so an
+    /// aggregate past u64 fails with `Overflow` having moved nothing. An
+    /// empty list collects nothing. Fees only move from positions to the
+    /// vault, so their sum is the same afterwards. Returns the amount
+    /// collected.
+    pub fn collect_fees<'info>(ctx: Context<'_, '_, 'info, 'info, CollectFees<'info>>) -> Result<u64> {
+        require_compute_budget(ctx.remaining_accounts.len(), COLLECT_UNITS_PER_POSITION)?;
+        
+        let vault_key = ctx.accounts.vault.key();
+        let mut positions: Vec<Account<FeePosition>> = Vec::with_capacity(ctx.remaining_accounts.len());
+        let mut collected = 0u64;
+        for info in ctx.remaining_accounts.iter() {
+            require!(info.is_writable, MathError::FeePositionMismatch);
+            require!(
+                positions.iter().all(|position| position.key() != info.key()),
+                MathError::DuplicatePosition
+            );
+            
+            let position = Account::<FeePosition>::try_from(info)?;
+            require_keys_eq!(position.vault, vault_key, MathError::FeePositionMismatch);
+            collected = safe_math::safe_add(collected, position.pending_fees)?;
+            positions.push(position);
+        }
+        
+        let vault = &mut ctx.accounts.vault;
+        let balances = |protocol_fees: u64, positions: &[Account<FeePosition>]| {
+            sum_balances(std::iter::once(protocol_fees).chain(positions.iter().map(|position| position.pending_fees)))
+        };
+        let before = balances(vault.protocol_fees, &positions)?;
+        vault.protocol_fees = safe_math::safe_add(vault.protocol_fees, collected)?;
+        
+        // Nothing is written until the totals are known to fit
+        for position in positions.iter_mut() {
+            position.pending_fees = 0;
+            position.exit(ctx.program_id)?;
+        }
+        assert_value_conserved(before, balances(vault.protocol_fees, &positions)?)?;
+        
+        msg!("SECURE: Collected {} in fees from {} positions", collected, ctx.remaining_accounts.len());
+        Ok(collected)
+    }
+
+    // ============================================================================
+    // ACCOUNT MAINTENANCE
+    // ============================================================================
+
+    /// SECURE: Appends a withdrawal to the record's history.
+    /// 
+    /// The `realloc` constraint grows the record by one entry, paid for by
+    /// the user. The amount is cast as in `record_withdrawal_secure`, so a
+    /// value that doesn't fit a u32 is rejected rather than truncated.
+    pub fn log_withdrawal(ctx: Context<LogWithdrawal>, amount: u64) -> Result<()> {
+        let amount: u32 = amount
+            .try_into()
+            .map_err(|_| MathError::CastOverflow)?;
+        
+        let record = &mut ctx.accounts.record;
+        record.history.push(amount);
+        record.last_withdrawal = amount;
+        
+        msg!("SECURE: Logged withdrawal of {}, {} in history", amount, record.history.len());
+        Ok(())
+    }
+
+    /// Forgets all but the `keep` most recent history entries.
+    /// 
+    /// The allocation is left as it is; `shrink_record` releases the
+    /// bytes the dropped entries held.
+    pub fn prune_history(ctx: Context<PruneHistory>, keep: u32) -> Result<()> {
+        let history = &mut ctx.accounts.record.history;
+        let forgotten = history.len().saturating_sub(keep as usize);
+        history.drain(..forgotten);
+        
+        msg!("Pruned {} history entries", forgotten);
+        Ok(())
+    }
+
+    /// SECURE: Releases the rent held by an over-allocated record.
+    /// 
+    /// Pruning the history leaves the record allocated larger than its
+    /// contents. The `realloc` constraint trims it to the bytes its live
+    /// fields and history entries occupy - never below `8 + INIT_SPACE` -
+    /// and refunds the freed rent to the user. Only unused trailing bytes
+    /// are dropped, so the stored data survives.
+    pub fn shrink_record(ctx: Context<ShrinkRecord>) -> Result<()> {
+        msg!(
+            "SECURE: Record shrunk to {} bytes",
+            ctx.accounts.record.to_account_info().data_len()
+        );
+        Ok(())
     }
 
     // ============================================================================
@@ -253,6 +639,7 @@ pub mod integer_overflow {
         let vault = &mut ctx.accounts.vault;
         vault.authority = ctx.accounts.authority.key();
         vault.total_deposits = 0;
+        vault.protocol_fees = 0;
         vault.bump = ctx.bumps.vault;
         Ok(())
     }
@@ -265,22 +652,131 @@ pub mod integer_overflow {
         Ok(())
     }
 
-    pub fn initialize_config(ctx: Context<InitializeConfig>, price: u64, fee_bps: u16) -> Result<()> {
+    /// `referral_bps` is the share of each fee paid to a referrer, out of
+    /// 10_000.
+    pub fn initialize_config(
+        ctx: Context<InitializeConfig>,
+        price: u64,
+        fee_bps: u16,
+        referral_bps: u16,
+    ) -> Result<()> {
+        require!(referral_bps <= 10_000, MathError::InvalidReferralShare);
+        
         let config = &mut ctx.accounts.config;
         config.admin = ctx.accounts.admin.key();
         config.price_per_unit = price;
         config.fee_bps = fee_bps;
+        config.referral_bps = referral_bps;
         config.bump = ctx.bumps.config;
         Ok(())
     }
 
+    pub fn initialize_interest_account(
+        ctx: Context<InitializeInterestAccount>,
+        principal: u64,
+        rate_per_second: u64,
+    ) -> Result<()> {
+        let account = &mut ctx.accounts.interest_account;
+        account.owner = ctx.accounts.owner.key();
+        account.principal = principal;
+        account.rate_per_second = rate_per_second;
+        account.accrued_interest = 0;
+        account.last_accrued_at = Clock::get()?.unix_timestamp;
+        account.bump = ctx.bumps.interest_account;
+        Ok(())
+    }
+
+    /// Creates a pool holding `reserve` with `total_lp` LP outstanding, all
+    /// of it credited to the authority's position.
+    pub fn initialize_lp_pool(ctx: Context<InitializeLpPool>, reserve: u64, total_lp: u64) -> Result<()> {
+        require!(total_lp > 0, SecurityError::ZeroAmount);
+        
+        let pool = &mut ctx.accounts.pool;
+        pool.authority = ctx.accounts.authority.key();
+        pool.reserve = reserve;
+        pool.total_lp = total_lp;
+        pool.bump = ctx.bumps.pool;
+        
+        let position = &mut ctx.accounts.position;
+        position.pool = pool.key();
+        position.owner = ctx.accounts.authority.key();
+        position.lp_amount = total_lp;
+        position.bump = ctx.bumps.position;
+        Ok(())
+    }
+
+    /// Opens `referrer`'s earnings account. Anyone may pay for it.
+    pub fn initialize_referral_account(ctx: Context<InitializeReferralAccount>) -> Result<()> {
+        let referral_account = &mut ctx.accounts.referral_account;
+        referral_account.referrer = ctx.accounts.referrer.key();
+        referral_account.referral_earnings = 0;
+        referral_account.bump = ctx.bumps.referral_account;
+        Ok(())
+    }
+
+    /// Opens the owner's fee position in `vault`, with nothing pending.
+    pub fn initialize_fee_position(ctx: Context<InitializeFeePosition>) -> Result<()> {
+        let position = &mut ctx.accounts.position;
+        position.vault = ctx.accounts.vault.key();
+        position.owner = ctx.accounts.owner.key();
+        position.pending_fees = 0;
+        position.bump = ctx.bumps.position;
+        Ok(())
+    }
+
     pub fn initialize_record(ctx: Context<InitializeRecord>) -> Result<()> {
         let record = &mut ctx.accounts.record;
         record.user = ctx.accounts.user.key();
         record.last_withdrawal = 0;
         record.bump = ctx.bumps.record;
+        record.history = Vec::new();
         Ok(())
     }
+
+    /// Creates the PDA that signs for `mint` and hands it the mint's
+    /// authority.
+    /// 
+    /// Only the mint's current mint authority can do this, so nobody else
+    /// can claim a mint's minter first with a cap of their choosing. That
+    /// signer becomes the minter's `admin`.
+    pub fn initialize_reward_minter(ctx: Context<InitializeRewardMinter>, max_supply: u64) -> Result<()> {
+        let minter = &mut ctx.accounts.minter;
+        minter.admin = ctx.accounts.admin.key();
+        minter.mint = ctx.accounts.mint.key();
+        minter.max_supply = max_supply;
+        minter.bump = ctx.bumps.minter;
+        
+        token::set_authority(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                SetAuthority {
+                    current_authority: ctx.accounts.admin.to_account_info(),
+                    account_or_mint: ctx.accounts.mint.to_account_info(),
+                },
+            ),
+            AuthorityType::MintTokens,
+            Some(minter.key()),
+        )
+    }
+}
+
+/// Mints `amount` of `accounts.mint` to `accounts.destination`, signed by
+/// the minter PDA.
+fn mint_reward(accounts: &MintReward, amount: u64) -> Result<()> {
+    let mint = accounts.mint.key();
+    let seeds: &[&[u8]] = &[b"mint_authority", mint.as_ref(), &[accounts.mint_authority.bump]];
+    token::mint_to(
+        CpiContext::new_with_signer(
+            accounts.token_program.to_account_info(),
+            MintTo {
+                mint: accounts.mint.to_account_info(),
+                to: accounts.destination.to_account_info(),
+                authority: accounts.mint_authority.to_account_info(),
+            },
+            &[seeds],
+        ),
+        amount,
+    )
 }
 
 // ============================================================================
@@ -333,6 +829,56 @@ pub struct RecordWithdrawal<'info> {
     pub user: Signer<'info>,
 }
 
+#[derive(Accounts)]
+pub struct LogWithdrawal<'info> {
+    #[account(
+        mut,
+        seeds = [b"record", user.key().as_ref()],
+        bump = record.bump,
+        realloc = record.live_len() + WithdrawalRecord::ENTRY_SPACE,
+        realloc::payer = user,
+        realloc::zero = false,
+    )]
+    pub record: Account<'info, WithdrawalRecord>,
+    
+    /// Pays for the new entry
+    #[account(mut)]
+    pub user: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct PruneHistory<'info> {
+    #[account(
+        mut,
+        seeds = [b"record", user.key().as_ref()],
+        bump = record.bump,
+    )]
+    pub record: Account<'info, WithdrawalRecord>,
+    
+    pub user: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ShrinkRecord<'info> {
+    #[account(
+        mut,
+        seeds = [b"record", user.key().as_ref()],
+        bump = record.bump,
+        realloc = record.live_len(),
+        realloc::payer = user,
+        realloc::zero = false,
+    )]
+    pub record: Account<'info, WithdrawalRecord>,
+    
+    /// Receives the rent freed by shrinking
+    #[account(mut)]
+    pub user: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
 #[derive(Accounts)]
 pub struct FeeCalculation<'info> {
     #[account(
@@ -342,6 +888,81 @@ pub struct FeeCalculation<'info> {
     pub config: Account<'info, Config>,
 }
 
+#[derive(Accounts)]
+pub struct AccrueInterest<'info> {
+    #[account(
+        mut,
+        seeds = [b"interest", interest_account.owner.as_ref()],
+        bump = interest_account.bump,
+    )]
+    pub interest_account: Account<'info, InterestAccount>,
+}
+
+/// Compounding is pure arithmetic on its arguments
+#[derive(Accounts)]
+pub struct CompoundInterest {}
+
+#[derive(Accounts)]
+pub struct Redeem<'info> {
+    #[account(
+        mut,
+        seeds = [b"lp_pool", pool.authority.as_ref()],
+        bump = pool.bump,
+    )]
+    pub pool: Account<'info, LpPool>,
+    
+    #[account(
+        mut,
+        seeds = [b"lp_position", pool.key().as_ref(), owner.key().as_ref()],
+        bump = position.bump,
+        has_one = pool,
+        has_one = owner,
+    )]
+    pub position: Account<'info, LpPosition>,
+    
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct DepositWithReferral<'info> {
+    #[account(
+        mut,
+        seeds = [b"vault", vault.authority.as_ref()],
+        bump = vault.bump,
+    )]
+    pub vault: Account<'info, Vault>,
+    
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, Config>,
+    
+    /// Present exactly when the deposit names a referrer
+    #[account(
+        mut,
+        seeds = [b"referral", referral_account.referrer.as_ref()],
+        bump = referral_account.bump,
+    )]
+    pub referral_account: Option<Account<'info, ReferralAccount>>,
+    
+    pub depositor: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CollectFees<'info> {
+    #[account(
+        mut,
+        seeds = [b"vault", authority.key().as_ref()],
+        bump = vault.bump,
+        has_one = authority,
+    )]
+    pub vault: Account<'info, Vault>,
+    
+    pub authority: Signer<'info>,
+    // remaining_accounts: this vault's FeePosition accounts, writable
+}
+
 #[derive(Accounts)]
 pub struct InitializeVault<'info> {
     #[account(
@@ -393,6 +1014,92 @@ pub struct InitializeConfig<'info> {
     pub system_program: Program<'info, System>,
 }
 
+#[derive(Accounts)]
+pub struct InitializeInterestAccount<'info> {
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + InterestAccount::INIT_SPACE,
+        seeds = [b"interest", owner.key().as_ref()],
+        bump
+    )]
+    pub interest_account: Account<'info, InterestAccount>,
+    
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeLpPool<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + LpPool::INIT_SPACE,
+        seeds = [b"lp_pool", authority.key().as_ref()],
+        bump
+    )]
+    pub pool: Account<'info, LpPool>,
+    
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + LpPosition::INIT_SPACE,
+        seeds = [b"lp_position", pool.key().as_ref(), authority.key().as_ref()],
+        bump
+    )]
+    pub position: Account<'info, LpPosition>,
+    
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeReferralAccount<'info> {
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + ReferralAccount::INIT_SPACE,
+        seeds = [b"referral", referrer.key().as_ref()],
+        bump
+    )]
+    pub referral_account: Account<'info, ReferralAccount>,
+    
+    /// CHECK: Only its key is recorded
+    pub referrer: UncheckedAccount<'info>,
+    
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeFeePosition<'info> {
+    #[account(
+        seeds = [b"vault", vault.authority.as_ref()],
+        bump = vault.bump,
+    )]
+    pub vault: Account<'info, Vault>,
+    
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + FeePosition::INIT_SPACE,
+        seeds = [b"fee_position", vault.key().as_ref(), owner.key().as_ref()],
+        bump
+    )]
+    pub position: Account<'info, FeePosition>,
+    
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
 #[derive(Accounts)]
 pub struct InitializeRecord<'info> {
     #[account(
@@ -410,6 +1117,53 @@ pub struct InitializeRecord<'info> {
     pub system_program: Program<'info, System>,
 }
 
+#[derive(Accounts)]
+pub struct MintReward<'info> {
+    #[account(mut)]
+    pub mint: Account<'info, Mint>,
+    
+    #[account(mut, token::mint = mint)]
+    pub destination: Account<'info, TokenAccount>,
+    
+    /// The mint's authority, holding its supply cap
+    #[account(
+        seeds = [b"mint_authority", mint.key().as_ref()],
+        bump = mint_authority.bump,
+        has_one = admin,
+    )]
+    pub mint_authority: Account<'info, RewardMinter>,
+    
+    pub admin: Signer<'info>,
+    
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeRewardMinter<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + RewardMinter::INIT_SPACE,
+        seeds = [b"mint_authority", mint.key().as_ref()],
+        bump
+    )]
+    pub minter: Account<'info, RewardMinter>,
+    
+    /// SECURE: Only the mint's own authority can put a minter over it
+    #[account(
+        mut,
+        constraint = mint.mint_authority == COption::Some(admin.key()) @ SecurityError::Unauthorized,
+    )]
+    pub mint: Account<'info, Mint>,
+    
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    
+    pub token_program: Program<'info, Token>,
+    
+    pub system_program: Program<'info, System>,
+}
+
 // ============================================================================
 // STATE
 // ============================================================================
@@ -419,6 +1173,8 @@ pub struct InitializeRecord<'info> {
 pub struct Vault {
     pub authority: Pubkey,
     pub total_deposits: u64,
+    /// Fees kept by the protocol after referral cuts
+    pub protocol_fees: u64,
     pub bump: u8,
 }
 
@@ -436,6 +1192,17 @@ pub struct Config {
     pub admin: Pubkey,
     pub price_per_unit: u64,
     pub fee_bps: u16,
+    /// Share of each fee paid to the referrer, out of 10_000
+    pub referral_bps: u16,
+    pub bump: u8,
+}
+
+/// Fees earned by one referrer
+#[account]
+#[derive(InitSpace)]
+pub struct ReferralAccount {
+    pub referrer: Pubkey,
+    pub referral_earnings: u64,
     pub bump: u8,
 }
 
@@ -445,6 +1212,98 @@ pub struct WithdrawalRecord {
     pub user: Pubkey,
     pub last_withdrawal: u32,  // Intentionally u32 to show truncation
     pub bump: u8,
+    /// Withdrawals logged by `log_withdrawal`, oldest first. Created empty;
+    /// the allocation grows with each entry
+    #[max_len(0)]
+    pub history: Vec<u32>,
+}
+
+/// Most periods `accrue_compound_interest` will compound in one call
+pub const MAX_COMPOUND_PERIODS: u32 = 1024;
+
+/// Fixed-point denominator for `InterestAccount::rate_per_second` (1e12 = 100%)
+pub const INTEREST_SCALE: u64 = 1_000_000_000_000;
+
+#[account]
+#[derive(InitSpace)]
+pub struct InterestAccount {
+    pub owner: Pubkey,
+    pub principal: u64,
+    /// Interest per second, scaled by `INTEREST_SCALE`
+    pub rate_per_second: u64,
+    pub accrued_interest: u64,
+    pub last_accrued_at: i64,
+    pub bump: u8,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct LpPool {
+    pub authority: Pubkey,
+    /// Underlying assets backing the outstanding LP
+    pub reserve: u64,
+    /// LP supply; every position's `lp_amount` is part of it
+    pub total_lp: u64,
+    pub bump: u8,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct LpPosition {
+    pub pool: Pubkey,
+    pub owner: Pubkey,
+    pub lp_amount: u64,
+    pub bump: u8,
+}
+
+/// Fees one position owes a vault, swept into `protocol_fees` by
+/// `collect_fees`
+#[account]
+#[derive(InitSpace)]
+pub struct FeePosition {
+    pub vault: Pubkey,
+    pub owner: Pubkey,
+    pub pending_fees: u64,
+    pub bump: u8,
+}
+
+/// Mint authority of a reward mint, and the most that mint may ever supply
+#[account]
+#[derive(InitSpace)]
+pub struct RewardMinter {
+    /// The only signer `mint_rewards_secure` mints for
+    pub admin: Pubkey,
+    pub mint: Pubkey,
+    pub max_supply: u64,
+    pub bump: u8,
+}
+
+/// Compute budgeted per `collect_fees` position: deserialize, sum,
+/// serialize
+pub const COLLECT_UNITS_PER_POSITION: u64 = 6_000;
+
+impl WithdrawalRecord {
+    /// Bytes one history entry occupies
+    pub const ENTRY_SPACE: usize = std::mem::size_of::<u32>();
+    
+    /// Allocation the record's current contents need, including the
+    /// discriminator and every history entry, floored at the initial
+    /// allocation.
+    pub fn live_len(&self) -> usize {
+        let min_len = 8 + Self::INIT_SPACE;
+        self.try_to_vec()
+            .map_or(min_len, |data| (8 + data.len()).max(min_len))
+    }
+}
+
+// ============================================================================
+// EVENTS
+// ============================================================================
+
+/// A saturating operation clamped `field` at its bound
+#[event]
+pub struct SaturationOccurred {
+    pub field: String,
 }
 
 // ============================================================================
@@ -453,16 +1312,37 @@ pub struct WithdrawalRecord {
 
 #[error_code]
 pub enum MathError {
-    #[msg("Arithmetic overflow")]
-    Overflow,
-    #[msg("Arithmetic underflow - insufficient funds")]
-    InsufficientFunds,
-    #[msg("Division by zero")]
-    DivisionByZero,
+    // 0-2: `Overflow`, `InsufficientFunds`, `DivisionByZero` moved to `SecurityError`
     #[msg("Cast overflow - value too large for target type")]
-    CastOverflow,
+    CastOverflow = 3,
+    // 4: `ZeroAmount` moved to `SecurityError`
+    #[msg("Depositors cannot refer themselves")]
+    SelfReferral = 5,
+    #[msg("Referral account is missing or belongs to another referrer")]
+    ReferrerMismatch,
+    #[msg("Referral share cannot exceed 10000 bps")]
+    InvalidReferralShare,
+    #[msg("Fee position is read-only or belongs to another vault")]
+    FeePositionMismatch,
+    #[msg("The same fee position was passed more than once")]
+    DuplicatePosition,
+    #[msg("Basis points cannot exceed 10000")]
+    InvalidBps,
+    #[msg("Too many periods to compound in one call")]
+    TooManyPeriods,
+    #[msg("Minting would exceed the mint's max supply")]
+    MaxSupplyExceeded,
 }
 
+error_categories!(MathError {
+    Math => [CastOverflow],
+    Validation => [
+        SelfReferral, ReferrerMismatch, InvalidReferralShare, FeePositionMismatch, DuplicatePosition,
+        InvalidBps, TooManyPeriods,
+    ],
+    State => [MaxSupplyExceeded],
+});
+
 // ============================================================================
 // SAFE MATH HELPER FUNCTIONS
 // ============================================================================
@@ -470,40 +1350,84 @@ pub enum MathError {
 /// Collection of safe math utilities
 pub mod safe_math {
     use super::*;
+    use security_common::math;
 
     /// Safely add two u64 values, returning error on overflow
     pub fn safe_add(a: u64, b: u64) -> Result<u64> {
-        a.checked_add(b).ok_or_else(|| error!(MathError::Overflow))
+        a.checked_add(b).ok_or_else(|| error!(SecurityError::Overflow))
     }
 
     /// Safely subtract two u64 values, returning error on underflow
     pub fn safe_sub(a: u64, b: u64) -> Result<u64> {
-        a.checked_sub(b).ok_or_else(|| error!(MathError::InsufficientFunds))
+        a.checked_sub(b).ok_or_else(|| error!(SecurityError::InsufficientFunds))
     }
 
     /// Safely multiply two u64 values, returning error on overflow
     pub fn safe_mul(a: u64, b: u64) -> Result<u64> {
-        a.checked_mul(b).ok_or_else(|| error!(MathError::Overflow))
+        a.checked_mul(b).ok_or_else(|| error!(SecurityError::Overflow))
     }
 
     /// Safely divide, returning error on division by zero
     pub fn safe_div(a: u64, b: u64) -> Result<u64> {
-        a.checked_div(b).ok_or_else(|| error!(MathError::DivisionByZero))
+        a.checked_div(b).ok_or_else(|| error!(SecurityError::DivisionByZero))
+    }
+
+    /// `a * b / denom` with a u128 intermediate, so only the result has to
+    /// fit in u64 - `security_common::math::mul_div` as an Anchor error
+    pub fn mul_div(a: u64, b: u64, denom: u64) -> Result<u64> {
+        Ok(math::mul_div(a, b, denom)?)
+    }
+
+    /// `mul_div`, rounded up. Use it for fees, so a charge is never rounded
+    /// down to zero
+    pub fn mul_div_ceil(a: u64, b: u64, denom: u64) -> Result<u64> {
+        Ok(math::mul_div_ceil(a, b, denom)?)
     }
 
     /// Ceiling division: ceil(a / b)
+    /// 
+    /// `(a + b - 1) / b` overflows for `a` near u64::MAX, so round up from
+    /// `(a - 1) / b` instead.
     pub fn ceil_div(a: u64, b: u64) -> Result<u64> {
         if b == 0 {
-            return Err(error!(MathError::DivisionByZero));
+            return Err(error!(SecurityError::DivisionByZero));
+        }
+        if a == 0 {
+            return Ok(0);
+        }
+        Ok(1 + (a - 1) / b)
+    }
+
+    /// Basis points in 100%
+    pub const BPS_DENOMINATOR: u16 = 10_000;
+
+    /// A rate in basis points (1 bp = 0.01%), at most `BPS_DENOMINATOR`
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub struct Bps(pub u16);
+
+    impl Bps {
+        fn checked(self) -> Result<u64> {
+            require!(self.0 <= BPS_DENOMINATOR, MathError::InvalidBps);
+            Ok(self.0 as u64)
+        }
+
+        /// `amount * bps / 10_000`, rounded down. Use it for payouts.
+        pub fn apply_floor(self, amount: u64) -> Result<u64> {
+            mul_div(amount, self.checked()?, BPS_DENOMINATOR as u64)
+        }
+
+        /// `amount * bps / 10_000`, rounded up. Use it for fees, so a small
+        /// amount can't round its fee down to zero.
+        pub fn apply_ceil(self, amount: u64) -> Result<u64> {
+            mul_div_ceil(amount, self.checked()?, BPS_DENOMINATOR as u64)
         }
-        Ok((a + b - 1) / b)
     }
 
     /// Calculate percentage with basis points (1 bp = 0.01%)
-    /// Returns ceil(amount * bps / 10000) to prevent zero fees
+    /// Returns ceil(amount * bps / 10000) to prevent zero fees. The product
+    /// is formed in u128, so only a fee past u64::MAX fails
     pub fn calculate_bps_fee(amount: u64, bps: u16) -> Result<u64> {
-        let numerator = safe_mul(amount, bps as u64)?;
-        ceil_div(numerator, 10000)
+        mul_div_ceil(amount, bps as u64, BPS_DENOMINATOR as u64)
     }
 }
 
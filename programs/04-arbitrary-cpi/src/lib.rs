@@ -17,8 +17,17 @@
 //! This is safe when calling trusted programs, but dangerous with arbitrary ones.
 
 use anchor_lang::prelude::*;
-use anchor_lang::solana_program::{program::invoke, system_instruction};
-use anchor_spl::token::{self, Token, TokenAccount, Transfer};
+use anchor_lang::solana_program::hash::hashv;
+use anchor_lang::solana_program::{bpf_loader, bpf_loader_upgradeable};
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::solana_program::program::{get_return_data, invoke, invoke_signed};
+use anchor_lang::solana_program::sysvar::instructions::{self as instructions_sysvar, load_current_index_checked, load_instruction_at_checked};
+use anchor_lang::system_program;
+use anchor_spl::token::{self, InitializeAccount3, Mint, Token, TokenAccount, Transfer, TransferChecked};
+use anchor_spl::token_2022;
+use anchor_spl::token_interface::{self, TokenInterface};
+pub use security_common::SecurityError;
+use security_common::{assert_value_conserved, error_categories, sum_balances};
 
 declare_id!("Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLnV");
 
@@ -53,12 +62,11 @@ pub mod arbitrary_cpi {
         
         msg!("VULNERABLE: Calling unvalidated program {}", swap_program.key());
         
-        // This would invoke whatever program was passed
+        // This invokes whatever program was passed
         // Attacker could pass malicious program
-        // invoke(
-        //     &swap_instruction,
-        //     &[...accounts...],
-        // )?;
+        let user = ctx.accounts.user.to_account_info();
+        let swap = swap_instruction(swap_program.key(), &user, ctx.remaining_accounts, amount);
+        invoke(&swap, &swap_account_infos(&user, ctx.remaining_accounts, swap_program))?;
         
         Ok(())
     }
@@ -66,18 +74,31 @@ pub mod arbitrary_cpi {
     /// SECURE: Validates the program ID before CPI.
     /// 
     /// ## What's Fixed?
-    /// We explicitly verify the program ID matches the expected swap program.
+    /// We explicitly verify the program ID is `EXPECTED_SWAP_PROGRAM`.
     /// Anchor's Program<> type also provides this guarantee.
+    /// 
+    /// The route accounts forwarded to the swap (`remaining_accounts`) are
+    /// checked against `SWAP_ROUTE_MIN_LENS` position by position, so a
+    /// caller can't hand the callee a truncated token account it would
+    /// misread.
     pub fn swap_secure<'info>(
         ctx: Context<'_, '_, '_, 'info, SwapSecure<'info>>,
         amount: u64,
     ) -> Result<()> {
-        // SECURE: Program<> type validates the account is the expected program
-        // The constraint ensures swap_program.key() == expected_program_id
+        require!(amount > 0, SecurityError::ZeroAmount);
+        
+        guards::assert_route_lens(ctx.remaining_accounts, &SWAP_ROUTE_MIN_LENS)?;
+        
+        // SECURE: The address constraint ensures
+        // swap_program.key() == EXPECTED_SWAP_PROGRAM
         let swap_program = &ctx.accounts.swap_program;
         
         msg!("SECURE: Calling validated program {}", swap_program.key());
         
+        let user = ctx.accounts.user.to_account_info();
+        let swap = swap_instruction(EXPECTED_SWAP_PROGRAM, &user, ctx.remaining_accounts, amount);
+        invoke(&swap, &swap_account_infos(&user, ctx.remaining_accounts, swap_program))?;
+        
         Ok(())
     }
 
@@ -99,21 +120,39 @@ pub mod arbitrary_cpi {
     /// 3. Attacker calls our withdraw with fake token program
     /// 4. Our vault state updates, but tokens don't actually move
     /// 5. Attacker repeats to drain vault
+    /// 
+    /// `programs/fake-token-program` is such a program.
     pub fn transfer_tokens_vulnerable(
         ctx: Context<TransferVulnerable>,
         amount: u64,
     ) -> Result<()> {
-        let vault = &mut ctx.accounts.vault;
-        
         // DANGER: We don't verify this is the real Token Program!
         // Attacker can pass fake program that doesn't actually transfer
-        msg!("VULNERABLE: Using unvalidated token program");
+        let token_program = &ctx.accounts.token_program;
+        msg!("VULNERABLE: Using unvalidated token program {}", token_program.key());
+        
+        // An SPL Token `Transfer`, sent to whatever program was passed
+        let transfer = token_transfer_instruction(
+            token_program.key(),
+            ctx.accounts.source.key(),
+            ctx.accounts.destination.key(),
+            ctx.accounts.authority.key(),
+            amount,
+        );
+        invoke(
+            &transfer,
+            &[
+                ctx.accounts.source.to_account_info(),
+                ctx.accounts.destination.to_account_info(),
+                ctx.accounts.authority.to_account_info(),
+                token_program.to_account_info(),
+            ],
+        )?;
         
         // Update state as if transfer succeeded
+        let vault = &mut ctx.accounts.vault;
         vault.balance = vault.balance.checked_sub(amount).unwrap();
         
-        // CPI to potentially fake token program would happen here
-        
         Ok(())
     }
 
@@ -128,7 +167,7 @@ pub mod arbitrary_cpi {
         ctx: Context<TransferSecure>,
         amount: u64,
     ) -> Result<()> {
-        let vault = &mut ctx.accounts.vault;
+        require!(amount > 0, SecurityError::ZeroAmount);
         
         // SECURE: token_program is validated as Token Program
         let cpi_accounts = Transfer {
@@ -152,6 +191,7 @@ pub mod arbitrary_cpi {
         // SECURE: This CPI is to the validated Token Program
         token::transfer(cpi_ctx, amount)?;
         
+        let vault = &mut ctx.accounts.vault;
         vault.balance = vault.balance.checked_sub(amount).unwrap();
         
         msg!("SECURE: Transferred {} tokens via validated Token Program", amount);
@@ -177,19 +217,36 @@ pub mod arbitrary_cpi {
     /// 2. Attacker passes malicious program as "reward_program"
     /// 3. We CPI to malicious program, signing with treasury seeds
     /// 4. Malicious program transfers all treasury funds to attacker
+    /// 
+    /// `programs/malicious-reward-program` is exactly such a program.
     pub fn distribute_rewards_vulnerable<'info>(
         ctx: Context<'_, '_, '_, 'info, DistributeRewardsVulnerable<'info>>,
     ) -> Result<()> {
-        msg!("VULNERABLE: About to CPI with signer seeds to arbitrary program");
+        let treasury = &ctx.accounts.treasury;
+        let reward_program = &ctx.accounts.reward_program;
+        msg!("VULNERABLE: About to CPI with signer seeds to arbitrary program {}", reward_program.key());
+        
+        // The treasury signs; everything else is forwarded as the caller sent it
+        let mut metas = vec![AccountMeta::new_readonly(treasury.key(), true)];
+        let mut infos = vec![treasury.to_account_info()];
+        for account in ctx.remaining_accounts {
+            metas.push(if account.is_writable {
+                AccountMeta::new(account.key(), account.is_signer)
+            } else {
+                AccountMeta::new_readonly(account.key(), account.is_signer)
+            });
+            infos.push(account.clone());
+        }
+        infos.push(reward_program.to_account_info());
+        let distribute = Instruction {
+            program_id: reward_program.key(),
+            accounts: metas,
+            data: DISTRIBUTE_DISCRIMINATOR.to_vec(),
+        };
         
         // DANGER: We're giving our PDA's signing authority to unknown program!
         // The malicious program can do anything with our PDA
-        
-        // invoke_signed(
-        //     &attacker_instruction,
-        //     &[treasury.to_account_info(), ...],
-        //     &[&[b"treasury", &[bump]]],  // Attacker gets our PDA authority!
-        // )?;
+        invoke_signed(&distribute, &infos, &[&[b"treasury", &[treasury.bump]]])?;
         
         Ok(())
     }
@@ -243,21 +300,478 @@ pub mod arbitrary_cpi {
         ctx: Context<CallOracleVulnerable>,
     ) -> Result<()> {
         // DANGER: Not checking if account is executable
-        let oracle = &ctx.accounts.oracle_program;
+        let _oracle = &ctx.accounts.oracle_program;
         
         msg!("VULNERABLE: Calling potentially non-executable account");
         
         Ok(())
     }
 
-    /// SECURE: Uses constraint to verify executable.
+    /// SECURE: Verifies the oracle is `ORACLE_PROGRAM_ID`, executable, and
+    /// owned by a BPF loader.
+    /// 
+    /// `executable` alone only says the runtime will run the account; the
+    /// loader owner is what makes it a deployed program rather than, say, a
+    /// builtin. See `guards::require_valid_program`.
     pub fn call_oracle_secure(
         ctx: Context<CallOracleSecure>,
     ) -> Result<()> {
-        // SECURE: executable constraint and program ID check
+        // SECURE: program ID, executable and loader owner checks
         let oracle = &ctx.accounts.oracle_program;
+        guards::require_valid_program(oracle, &ORACLE_PROGRAM_ID)?;
+        
+        msg!("SECURE: Oracle program verified as a deployed program");
+        
+        Ok(())
+    }
+
+    // ============================================================================
+    // VULNERABILITY 5: DECIMAL CONFUSION
+    // ============================================================================
+
+    /// VULNERABLE: Transfers with plain `transfer`, ignoring `decimals`.
+    /// 
+    /// ## What's Wrong?
+    /// `amount` is in base units, and only the mint knows how many base
+    /// units make one token. Plain `transfer` never sees the mint, so an
+    /// amount computed for the wrong number of decimals moves anyway -
+    /// `decimals` is accepted and only logged.
+    /// 
+    /// ## Attack Scenario:
+    /// 1. A frontend quotes "1 token" as 1_000_000_000 (9 decimals)
+    /// 2. The mint actually has 6 decimals
+    /// 3. transfer_unchecked_vulnerable moves 1000 tokens, not 1
+    pub fn transfer_unchecked_vulnerable(
+        ctx: Context<TransferUncheckedVulnerable>,
+        amount: u64,
+        decimals: u8,
+    ) -> Result<()> {
+        // DANGER: Nothing ties `amount` to the mint's decimals
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.source.to_account_info(),
+            to: ctx.accounts.destination.to_account_info(),
+            authority: ctx.accounts.authority.to_account_info(),
+        };
+        token::transfer(
+            CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts),
+            amount,
+        )?;
+        
+        msg!("VULNERABLE: Transferred {} base units, {} decimals never checked", amount, decimals);
+        Ok(())
+    }
+
+    /// SECURE: Transfers with `transfer_checked`, passing the mint and the
+    /// decimals the amount was computed for.
+    /// 
+    /// ## What's Fixed?
+    /// The token program compares `decimals` with the mint's own and fails
+    /// with `MintDecimalsMismatch` if they differ, so an amount in the wrong
+    /// units never moves. Both token accounts must hold `mint`.
+    pub fn transfer_checked_secure(
+        ctx: Context<TransferCheckedSecure>,
+        amount: u64,
+        decimals: u8,
+    ) -> Result<()> {
+        require!(amount > 0, SecurityError::ZeroAmount);
+        
+        let cpi_accounts = TransferChecked {
+            from: ctx.accounts.source.to_account_info(),
+            mint: ctx.accounts.mint.to_account_info(),
+            to: ctx.accounts.destination.to_account_info(),
+            authority: ctx.accounts.authority.to_account_info(),
+        };
+        token::transfer_checked(
+            CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts),
+            amount,
+            decimals,
+        )?;
+        
+        msg!("SECURE: Transferred {} base units at {} decimals", amount, decimals);
+        Ok(())
+    }
+
+    // ============================================================================
+    // TOKEN-2022 TRANSFER HOOKS
+    // ============================================================================
+
+    /// SECURE: Transfers through whichever token program owns the mint,
+    /// using `transfer_checked` wherever transfer hooks can exist.
+    /// 
+    /// A Token-2022 mint can carry a transfer hook: the token program calls
+    /// out to another program on every transfer, and plain `transfer` skips
+    /// the mint entirely, so the hook (and the decimals) are never seen.
+    /// `Interface<TokenInterface>` admits exactly the two real token
+    /// programs; the program ID then picks the route:
+    /// - Token-2022 - `token_2022::transfer_checked` with the mint and its
+    ///   decimals, forwarding `remaining_accounts` for the hook
+    /// - Legacy Token - no hooks exist, so `token::transfer` is enough
+    /// 
+    /// Either way the mint's decimals must equal `expected_decimals`: a
+    /// caller quoting amounts for a 6-decimal mint must not be handed a
+    /// 9-decimal one.
+    pub fn transfer_with_hook_awareness<'info>(
+        ctx: Context<'_, '_, 'info, 'info, TransferHookAware<'info>>,
+        amount: u64,
+        expected_decimals: u8,
+    ) -> Result<()> {
+        require!(amount > 0, SecurityError::ZeroAmount);
+        let decimals = ctx.accounts.mint.decimals;
+        require!(decimals == expected_decimals, CpiError::DecimalsMismatch);
+        
+        let token_program = ctx.accounts.token_program.to_account_info();
+        if token_program.key() == token_2022::ID {
+            let cpi_accounts = token_2022::TransferChecked {
+                from: ctx.accounts.source.to_account_info(),
+                mint: ctx.accounts.mint.to_account_info(),
+                to: ctx.accounts.destination.to_account_info(),
+                authority: ctx.accounts.authority.to_account_info(),
+            };
+            let cpi_ctx = CpiContext::new(token_program, cpi_accounts)
+                .with_remaining_accounts(ctx.remaining_accounts.to_vec());
+            token_2022::transfer_checked(cpi_ctx, amount, decimals)?;
+            msg!("SECURE: Token-2022 transfer_checked of {} ({} decimals)", amount, decimals);
+        } else {
+            let cpi_accounts = Transfer {
+                from: ctx.accounts.source.to_account_info(),
+                to: ctx.accounts.destination.to_account_info(),
+                authority: ctx.accounts.authority.to_account_info(),
+            };
+            token::transfer(CpiContext::new(token_program, cpi_accounts), amount)?;
+            msg!("SECURE: Legacy Token transfer of {}", amount);
+        }
+        
+        Ok(())
+    }
+
+    // ============================================================================
+    // COMMIT-REVEAL SWAPS
+    // ============================================================================
+
+    /// Step 1 of a front-running-resistant swap: records
+    /// `commitment = swap_commitment_of(amount, min_out, nonce)`.
+    /// 
+    /// The pending transaction shows only a hash, so a searcher watching it
+    /// learns nothing about the size or slippage tolerance of the swap it
+    /// would sandwich. A user has one outstanding commitment at a time.
+    pub fn commit_swap(ctx: Context<CommitSwap>, commitment: [u8; 32]) -> Result<()> {
+        let swap_commitment = &mut ctx.accounts.swap_commitment;
+        swap_commitment.user = ctx.accounts.user.key();
+        swap_commitment.commitment = commitment;
+        swap_commitment.slot = Clock::get()?.slot;
+        swap_commitment.bump = ctx.bumps.swap_commitment;
+        
+        msg!("SECURE: Swap committed at slot {}", swap_commitment.slot);
+        Ok(())
+    }
+
+    /// Step 2: reveals the committed parameters and runs the validated swap.
+    /// 
+    /// ## What's Checked?
+    /// - The reveal lands in a later slot than the commit, so the two can't
+    ///   be bundled and the parameters are never visible before the commit
+    ///   is final
+    /// - `(amount, min_out, nonce)` hashes to the stored commitment - any
+    ///   tampered parameter fails with `CommitmentMismatch`
+    /// - The swap itself gets the same checks as `swap_secure`
+    /// - The amount out, read from the swap program's own return data, is
+    ///   at least `min_out` - otherwise the whole swap fails with
+    ///   `SlippageExceeded`
+    /// 
+    /// The commitment account is closed to the user, so a reveal can't be
+    /// replayed. Returns the amount out.
+    pub fn reveal_swap<'info>(
+        ctx: Context<'_, '_, '_, 'info, RevealSwap<'info>>,
+        amount: u64,
+        min_out: u64,
+        nonce: [u8; 32],
+    ) -> Result<u64> {
+        let swap_commitment = &ctx.accounts.swap_commitment;
+        require!(Clock::get()?.slot > swap_commitment.slot, CpiError::RevealTooEarly);
+        require!(
+            swap_commitment_of(amount, min_out, &nonce) == swap_commitment.commitment,
+            CpiError::CommitmentMismatch
+        );
+        require!(amount > 0, SecurityError::ZeroAmount);
+        
+        guards::assert_route_lens(ctx.remaining_accounts, &SWAP_ROUTE_MIN_LENS)?;
+        
+        let swap_program = &ctx.accounts.swap_program;
+        let user = ctx.accounts.user.to_account_info();
+        let swap = swap_instruction(EXPECTED_SWAP_PROGRAM, &user, ctx.remaining_accounts, amount);
+        invoke(&swap, &swap_account_infos(&user, ctx.remaining_accounts, swap_program))?;
+        
+        // SECURE: Only the validated program's own answer counts
+        let (program_id, data) = get_return_data().ok_or(CpiError::InvalidReturnData)?;
+        require_keys_eq!(program_id, EXPECTED_SWAP_PROGRAM, CpiError::InvalidProgram);
+        let out = u64::try_from_slice(&data).map_err(|_| CpiError::InvalidReturnData)?;
+        require!(out >= min_out, CpiError::SlippageExceeded);
+        
+        msg!(
+            "SECURE: Revealed swap of {} for {} (min out {}) via validated program {}",
+            amount,
+            out,
+            min_out,
+            swap_program.key()
+        );
+        
+        Ok(out)
+    }
+
+    // ============================================================================
+    // INSTRUCTION INTROSPECTION
+    // ============================================================================
+
+    /// SECURE: `swap_secure`, refusing to run if any later instruction in the
+    /// transaction belongs to a program outside `FOLLOWING_PROGRAM_ALLOWLIST`.
+    /// 
+    /// ## Attack Scenario:
+    /// 1. A bot watches for pending swaps through our protocol
+    /// 2. It wraps the user's signed swap instruction in its own transaction:
+    ///    a buy before it, a sell from its own program right after
+    /// 3. The user's swap executes at the worse price the buy created; the
+    ///    sell pockets the difference
+    /// 
+    /// ## What's Checked?
+    /// The instructions sysvar lists every top-level instruction in the
+    /// transaction, including the ones that haven't run yet. Every
+    /// instruction after the current one must target an allowlisted
+    /// program, so the back-run can't share the transaction.
+    /// 
+    /// This only sees the transaction it runs in; a sandwich spread over
+    /// several transactions needs `min_out` or commit-reveal as well.
+    pub fn swap_with_introspection<'info>(
+        ctx: Context<'_, '_, '_, 'info, SwapWithIntrospection<'info>>,
+        amount: u64,
+    ) -> Result<()> {
+        require!(amount > 0, SecurityError::ZeroAmount);
+        guards::assert_following_instructions_allowed(&ctx.accounts.instructions)?;
+        
+        guards::assert_route_lens(ctx.remaining_accounts, &SWAP_ROUTE_MIN_LENS)?;
+        
+        let swap_program = &ctx.accounts.swap_program;
+        msg!("SECURE: No unknown program follows; calling validated program {}", swap_program.key());
+        
+        let user = ctx.accounts.user.to_account_info();
+        let swap = swap_instruction(EXPECTED_SWAP_PROGRAM, &user, ctx.remaining_accounts, amount);
+        invoke(&swap, &swap_account_infos(&user, ctx.remaining_accounts, swap_program))?;
+        
+        Ok(())
+    }
+
+    // ============================================================================
+    // BATCHED CPI
+    // ============================================================================
+
+    /// VULNERABLE: Pays `reward_amount` to each `(token_account, program)`
+    /// pair in `remaining_accounts`, through whichever program each pair names.
+    /// 
+    /// ## What's Wrong?
+    /// - Every program gets the treasury's signature, so one malicious pair
+    ///   can do anything the treasury can
+    /// - A token account listed twice is paid twice
+    /// - An odd trailing account is silently dropped
+    pub fn batch_distribute_unchecked<'info>(
+        ctx: Context<'_, '_, '_, 'info, BatchDistributeUnchecked<'info>>,
+    ) -> Result<u64> {
+        let treasury = &ctx.accounts.treasury;
+        let source = ctx.accounts.treasury_token_account.to_account_info();
+        let mut paid = 0u64;
+        
+        for pair in ctx.remaining_accounts.chunks_exact(2) {
+            let (recipient, program) = (&pair[0], &pair[1]);
+            // DANGER: Whatever program the caller listed, signed as the treasury
+            let transfer = token_transfer_instruction(
+                program.key(),
+                source.key(),
+                recipient.key(),
+                treasury.key(),
+                treasury.reward_amount,
+            );
+            invoke_signed(
+                &transfer,
+                &[source.clone(), recipient.clone(), treasury.to_account_info(), program.clone()],
+                &[&[b"treasury", &[treasury.bump]]],
+            )?;
+            paid = paid.checked_add(treasury.reward_amount).ok_or(SecurityError::Overflow)?;
+        }
+        
+        msg!("VULNERABLE: Paid {} through unchecked programs", paid);
+        Ok(paid)
+    }
+
+    /// SECURE: `batch_distribute_unchecked` with every pair validated first.
+    /// 
+    /// ## What's Fixed?
+    /// - An odd number of remaining accounts fails with
+    ///   `UnpairedRemainingAccount` instead of dropping the last one
+    /// - Each program must be in `BATCH_PROGRAM_ALLOWLIST` (`InvalidProgram`)
+    /// - Each token account may appear once (`DuplicateRecipient`)
+    /// 
+    /// All pairs are checked before the first transfer, so a bad pair late
+    /// in the batch can't leave it half paid. Returns the total paid.
+    pub fn batch_distribute<'info>(
+        ctx: Context<'_, '_, '_, 'info, BatchDistribute<'info>>,
+    ) -> Result<u64> {
+        let pairs = ctx.remaining_accounts;
+        require!(pairs.chunks_exact(2).remainder().is_empty(), CpiError::UnpairedRemainingAccount);
+        
+        let mut recipients: Vec<Pubkey> = Vec::with_capacity(pairs.len() / 2);
+        for pair in pairs.chunks_exact(2) {
+            let (recipient, program) = (&pair[0], &pair[1]);
+            require!(BATCH_PROGRAM_ALLOWLIST.contains(program.key), CpiError::InvalidProgram);
+            require!(!recipients.contains(recipient.key), CpiError::DuplicateRecipient);
+            recipients.push(recipient.key());
+        }
+        
+        let treasury = &ctx.accounts.treasury;
+        let source = ctx.accounts.treasury_token_account.to_account_info();
+        let mut paid = 0u64;
+        for pair in pairs.chunks_exact(2) {
+            let (recipient, program) = (&pair[0], &pair[1]);
+            // SECURE: Only allowlisted token programs get the treasury's signature
+            let transfer = token_transfer_instruction(
+                program.key(),
+                source.key(),
+                recipient.key(),
+                treasury.key(),
+                treasury.reward_amount,
+            );
+            invoke_signed(
+                &transfer,
+                &[source.clone(), recipient.clone(), treasury.to_account_info(), program.clone()],
+                &[&[b"treasury", &[treasury.bump]]],
+            )?;
+            paid = paid.checked_add(treasury.reward_amount).ok_or(SecurityError::Overflow)?;
+        }
+        
+        msg!("SECURE: Paid {} to {} recipients", paid, recipients.len());
+        Ok(paid)
+    }
+
+    // ============================================================================
+    // REWARD CLAIMS VIA CPI
+    // ============================================================================
+
+    /// SECURE: Asks the reward program how much the user earned and credits
+    /// exactly that.
+    /// 
+    /// The amount comes back as CPI return data, never from the client.
+    /// Return data is only as trustworthy as the program that set it, so:
+    /// - `reward_program` must be `REWARD_PROGRAM_ID` and executable
+    /// - the return data must have been set BY that program - a program it
+    ///   calls in turn could otherwise overwrite it
+    /// - it must decode as exactly one `u64`
+    /// 
+    /// `min_amount` only lets the caller refuse a smaller reward than they
+    /// expected; it never raises what gets credited.
+    /// 
+    /// `reward_state` is passed writable: `distribute` zeroes the reward it
+    /// reports, so the same reward can't be credited twice. A claim with
+    /// nothing left to report fails with `ZeroAmount`.
+    pub fn claim_via_cpi(ctx: Context<ClaimViaCpi>, min_amount: u64) -> Result<u64> {
+        let distribute = Instruction {
+            program_id: REWARD_PROGRAM_ID,
+            accounts: vec![
+                AccountMeta::new(ctx.accounts.reward_state.key(), false),
+                AccountMeta::new_readonly(ctx.accounts.authority.key(), false),
+            ],
+            data: DISTRIBUTE_DISCRIMINATOR.to_vec(),
+        };
+        invoke(
+            &distribute,
+            &[
+                ctx.accounts.reward_state.to_account_info(),
+                ctx.accounts.authority.to_account_info(),
+                ctx.accounts.reward_program.to_account_info(),
+            ],
+        )?;
+        
+        // SECURE: Only the validated program's own answer counts
+        let (program_id, data) = get_return_data().ok_or(CpiError::InvalidReturnData)?;
+        require_keys_eq!(program_id, REWARD_PROGRAM_ID, CpiError::InvalidProgram);
+        let amount = u64::try_from_slice(&data).map_err(|_| CpiError::InvalidReturnData)?;
+        require!(amount > 0, SecurityError::ZeroAmount);
+        require!(amount >= min_amount, CpiError::RewardBelowMinimum);
+        
+        let vault = &mut ctx.accounts.vault;
+        vault.balance = vault.balance
+            .checked_add(amount)
+            .ok_or(SecurityError::Overflow)?;
+        
+        msg!("SECURE: Credited {} reported by the reward program", amount);
+        Ok(amount)
+    }
+
+    // ============================================================================
+    // CAPSTONE: SAFE TRANSFER (EVERY LESSON COMBINED)
+    // ============================================================================
+
+    /// SECURE REFERENCE: A token withdrawal that applies every pattern at once.
+    /// 
+    /// ## Guards (each one maps to a lesson in this repository):
+    /// 1. Signer check - `authority` is a `Signer` (01)
+    /// 2. Owner/PDA validation - `vault` is a typed PDA with `has_one`, and
+    ///    the source is that vault's own `[b"vault_token", vault]` (02)
+    /// 3. Mint check - both token accounts must hold `vault.mint` (02)
+    /// 4. Duplicate-account check - source and destination must differ
+    /// 5. Overflow-safe accounting - `checked_sub` on the bookkeeping (03)
+    /// 6. Validated CPI - `Program<'info, Token>` only (04)
+    /// 7. Event emission - indexers observe the exact amount moved
+    /// 8. Value conservation - both token balances re-read after the CPI
+    ///    still sum to what they held before
+    /// 
+    /// Use this as the "how to do it right" template when writing new
+    /// instructions that move value.
+    pub fn safe_transfer(ctx: Context<SafeTransfer>, amount: u64) -> Result<()> {
+        require!(amount > 0, SecurityError::ZeroAmount);
+        let before = sum_balances([ctx.accounts.vault_token_account.amount, ctx.accounts.user_token_account.amount])?;
+        
+        // SECURE: Debit the bookkeeping first - fails cleanly on underflow
+        let vault = &mut ctx.accounts.vault;
+        vault.balance = vault.balance
+            .checked_sub(amount)
+            .ok_or(SecurityError::InsufficientFunds)?;
+        let remaining_balance = vault.balance;
+        
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.vault_token_account.to_account_info(),
+            to: ctx.accounts.user_token_account.to_account_info(),
+            authority: ctx.accounts.vault.to_account_info(),
+        };
+        
+        // SECURE: The vault's own seeds, which sign for its token account only
+        let authority_key = ctx.accounts.authority.key();
+        let seeds = &[
+            b"vault".as_ref(),
+            authority_key.as_ref(),
+            &[ctx.accounts.vault.bump],
+        ];
+        let signer_seeds = &[&seeds[..]];
+        
+        let cpi_ctx = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            cpi_accounts,
+            signer_seeds,
+        );
+        
+        // SECURE: Signer seeds only ever reach the validated Token Program
+        token::transfer(cpi_ctx, amount)?;
         
-        msg!("SECURE: Oracle program verified as executable");
+        ctx.accounts.vault_token_account.reload()?;
+        ctx.accounts.user_token_account.reload()?;
+        let after = sum_balances([ctx.accounts.vault_token_account.amount, ctx.accounts.user_token_account.amount])?;
+        assert_value_conserved(before, after)?;
+        
+        emit!(SafeTransferEvent {
+            vault: ctx.accounts.vault.key(),
+            authority: ctx.accounts.authority.key(),
+            destination: ctx.accounts.user_token_account.key(),
+            amount,
+            remaining_balance,
+        });
+        
+        msg!("SECURE: Safe transfer of {} tokens, {} remaining", amount, remaining_balance);
         
         Ok(())
     }
@@ -269,12 +783,94 @@ pub mod arbitrary_cpi {
     pub fn initialize_vault(ctx: Context<InitializeVault>) -> Result<()> {
         let vault = &mut ctx.accounts.vault;
         vault.authority = ctx.accounts.authority.key();
+        vault.mint = ctx.accounts.mint.key();
         vault.balance = 0;
         vault.vault_authority_bump = ctx.bumps.vault_authority;
         vault.bump = ctx.bumps.vault;
         Ok(())
     }
 
+    /// SECURE: Creates the vault's token account, owned by the vault PDA.
+    /// 
+    /// The account lives at `[b"vault_token", vault]` and is initialized
+    /// with `initialize_account3` through the validated Token Program, for
+    /// `vault.mint` only (`has_one = mint`), with the vault itself as the
+    /// token authority rather than the program-wide `vault_authority`, so
+    /// only this vault's seeds can move its tokens. `safe_transfer` only
+    /// accepts this account as its source.
+    /// 
+    /// Anyone can send lamports to the PDA's address before it exists,
+    /// which would make a plain `create_account` fail forever. A pre-funded
+    /// address is topped up to rent exemption, allocated and assigned
+    /// instead.
+    pub fn init_token_account(ctx: Context<InitTokenAccount>) -> Result<()> {
+        let vault_key = ctx.accounts.vault.key();
+        let seeds = &[
+            b"vault_token".as_ref(),
+            vault_key.as_ref(),
+            &[ctx.bumps.vault_token_account],
+        ];
+        let signer_seeds = &[&seeds[..]];
+        
+        let system = ctx.accounts.system_program.to_account_info();
+        let payer = ctx.accounts.authority.to_account_info();
+        let account = ctx.accounts.vault_token_account.to_account_info();
+        let rent = Rent::get()?.minimum_balance(TokenAccount::LEN);
+        
+        if account.lamports() == 0 {
+            system_program::create_account(
+                CpiContext::new_with_signer(
+                    system.clone(),
+                    system_program::CreateAccount { from: payer, to: account.clone() },
+                    signer_seeds,
+                ),
+                rent,
+                TokenAccount::LEN as u64,
+                &token::ID,
+            )?;
+        } else {
+            let shortfall = rent.saturating_sub(account.lamports());
+            if shortfall > 0 {
+                system_program::transfer(
+                    CpiContext::new(
+                        system.clone(),
+                        system_program::Transfer { from: payer, to: account.clone() },
+                    ),
+                    shortfall,
+                )?;
+            }
+            system_program::allocate(
+                CpiContext::new_with_signer(
+                    system.clone(),
+                    system_program::Allocate { account_to_allocate: account.clone() },
+                    signer_seeds,
+                ),
+                TokenAccount::LEN as u64,
+            )?;
+            system_program::assign(
+                CpiContext::new_with_signer(
+                    system,
+                    system_program::Assign { account_to_assign: account.clone() },
+                    signer_seeds,
+                ),
+                &token::ID,
+            )?;
+        }
+        
+        // SECURE: The mint is the vault's, the owner is the vault PDA
+        token::initialize_account3(CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            InitializeAccount3 {
+                account,
+                mint: ctx.accounts.mint.to_account_info(),
+                authority: ctx.accounts.vault.to_account_info(),
+            },
+        ))?;
+        
+        msg!("SECURE: Initialized vault token account for mint {}", ctx.accounts.mint.key());
+        Ok(())
+    }
+
     pub fn initialize_treasury(ctx: Context<InitializeTreasury>, reward_amount: u64) -> Result<()> {
         let treasury = &mut ctx.accounts.treasury;
         treasury.admin = ctx.accounts.admin.key();
@@ -301,6 +897,7 @@ pub struct SwapVulnerable<'info> {
     pub swap_program: UncheckedAccount<'info>,
     
     pub user: Signer<'info>,
+    // remaining_accounts: the swap route, forwarded unchecked
 }
 
 #[derive(Accounts)]
@@ -333,6 +930,26 @@ pub struct DistributeRewardsVulnerable<'info> {
     pub reward_program: UncheckedAccount<'info>,
     
     pub admin: Signer<'info>,
+    
+    // remaining_accounts: forwarded to `reward_program` after the treasury
+}
+
+#[derive(Accounts)]
+pub struct BatchDistributeUnchecked<'info> {
+    #[account(
+        seeds = [b"treasury"],
+        bump = treasury.bump,
+        has_one = admin,
+    )]
+    pub treasury: Account<'info, Treasury>,
+    
+    /// CHECK: Intentionally insecure - handed to every listed program
+    #[account(mut)]
+    pub treasury_token_account: UncheckedAccount<'info>,
+    
+    pub admin: Signer<'info>,
+    
+    // remaining_accounts: (recipient token account, program) pairs, unchecked
 }
 
 #[derive(Accounts)]
@@ -343,33 +960,175 @@ pub struct CallOracleVulnerable<'info> {
     pub oracle_program: UncheckedAccount<'info>,
 }
 
+#[derive(Accounts)]
+pub struct TransferUncheckedVulnerable<'info> {
+    #[account(mut)]
+    pub source: Account<'info, TokenAccount>,
+    
+    #[account(mut)]
+    pub destination: Account<'info, TokenAccount>,
+    
+    pub authority: Signer<'info>,
+    
+    pub token_program: Program<'info, Token>,
+}
+
 // ============================================================================
 // SECURE ACCOUNT STRUCTURES
 // ============================================================================
 
-// Note: In a real implementation, you would have the actual swap program ID
-// For demonstration, we use a placeholder
-// pub static SWAP_PROGRAM_ID: Pubkey = pubkey!("SwapProgramID...");
+/// Smallest data length at each position of the swap route: the user's
+/// source and destination SPL token accounts. The mints, pool state and
+/// programs after them vary in size and are the swap program's to check.
+pub const SWAP_ROUTE_MIN_LENS: [usize; 2] = [TokenAccount::LEN, TokenAccount::LEN];
+
+/// The only program `swap_secure` and `reveal_swap` will route a swap through
+pub const EXPECTED_SWAP_PROGRAM: Pubkey = pubkey!("SwapRouter111111111111111111111111111111111");
+
+/// `sha256("global:swap")[..8]` - the swap program's Anchor-style `swap`
+/// instruction, which takes the amount in as a `u64` and returns the amount
+/// out as one
+pub const SWAP_DISCRIMINATOR: [u8; 8] = [248, 198, 158, 145, 225, 117, 135, 200];
+
+/// The `swap` instruction for `program_id`: the user signs, then the route
+/// follows as it was passed in.
+fn swap_instruction(program_id: Pubkey, user: &AccountInfo, route: &[AccountInfo], amount: u64) -> Instruction {
+    let mut accounts = vec![AccountMeta::new_readonly(user.key(), true)];
+    accounts.extend(route.iter().map(|account| {
+        if account.is_writable {
+            AccountMeta::new(account.key(), account.is_signer)
+        } else {
+            AccountMeta::new_readonly(account.key(), account.is_signer)
+        }
+    }));
+    let mut data = SWAP_DISCRIMINATOR.to_vec();
+    data.extend_from_slice(&amount.to_le_bytes());
+    Instruction { program_id, accounts, data }
+}
+
+fn swap_account_infos<'info>(
+    user: &AccountInfo<'info>,
+    route: &[AccountInfo<'info>],
+    swap_program: &AccountInfo<'info>,
+) -> Vec<AccountInfo<'info>> {
+    let mut infos = vec![user.clone()];
+    infos.extend(route.iter().cloned());
+    infos.push(swap_program.clone());
+    infos
+}
+
+/// Programs `batch_distribute` will send a treasury transfer through.
+/// 
+/// Legacy SPL Token only: the treasury's token account is an SPL Token
+/// account, so no Token-2022 transfer out of it can be valid.
+pub const BATCH_PROGRAM_ALLOWLIST: [Pubkey; 1] = [token::ID];
+
+/// An SPL Token `Transfer` for `program_id`; the Token-2022 program accepts
+/// the same layout.
+fn token_transfer_instruction(program_id: Pubkey, from: Pubkey, to: Pubkey, authority: Pubkey, amount: u64) -> Instruction {
+    Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(from, false),
+            AccountMeta::new(to, false),
+            AccountMeta::new_readonly(authority, true),
+        ],
+        data: token::spl_token::instruction::TokenInstruction::Transfer { amount }.pack(),
+    }
+}
+
+/// The only program `call_oracle_secure` accepts as the oracle
+pub const ORACLE_PROGRAM_ID: Pubkey = pubkey!("PriceFeed1111111111111111111111111111111111");
+
+/// Programs `swap_with_introspection` lets later instructions target: the
+/// swap program and the Token program.
+/// 
+/// Not this program - its `swap_vulnerable` invokes whatever program it is
+/// handed, so a back-run could simply be routed through it.
+pub const FOLLOWING_PROGRAM_ALLOWLIST: [Pubkey; 2] = [EXPECTED_SWAP_PROGRAM, token::ID];
+
+/// The only program `claim_via_cpi` accepts a reward amount from
+pub const REWARD_PROGRAM_ID: Pubkey = pubkey!("RewardDistributor11111111111111111111111111");
+
+/// `sha256("global:distribute")[..8]` - the reward program's Anchor-style
+/// `distribute` instruction, which returns the user's reward as a `u64` and
+/// marks it paid
+pub const DISTRIBUTE_DISCRIMINATOR: [u8; 8] = [191, 44, 223, 207, 164, 236, 126, 61];
 
 #[derive(Accounts)]
 pub struct SwapSecure<'info> {
     /// SECURE: Validated program account
     /// 
-    /// In production, you would use:
-    /// #[account(address = SWAP_PROGRAM_ID)]
+    /// With an Anchor-generated swap crate you would use:
     /// pub swap_program: Program<'info, SwapProgram>,
     /// 
-    /// Or with constraint:
-    /// #[account(
-    ///     executable,
-    ///     constraint = swap_program.key() == EXPECTED_SWAP_PROGRAM @ CpiError::InvalidProgram
-    /// )]
-    /// 
-    /// CHECK: Would be validated in production with program address constraint
-    #[account(executable)]
+    /// CHECK: Must be `EXPECTED_SWAP_PROGRAM`, and executable
+    #[account(
+        executable,
+        address = EXPECTED_SWAP_PROGRAM @ CpiError::InvalidProgram
+    )]
+    pub swap_program: UncheckedAccount<'info>,
+    
+    pub user: Signer<'info>,
+    // remaining_accounts: the swap route, sized as `SWAP_ROUTE_MIN_LENS` says
+}
+
+#[derive(Accounts)]
+pub struct CommitSwap<'info> {
+    #[account(
+        init,
+        payer = user,
+        space = 8 + SwapCommitment::INIT_SPACE,
+        seeds = [b"swap_commitment", user.key().as_ref()],
+        bump
+    )]
+    pub swap_commitment: Account<'info, SwapCommitment>,
+    
+    #[account(mut)]
+    pub user: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RevealSwap<'info> {
+    /// SECURE: The signer's own commitment, closed once revealed
+    #[account(
+        mut,
+        seeds = [b"swap_commitment", user.key().as_ref()],
+        bump = swap_commitment.bump,
+        has_one = user,
+        close = user,
+    )]
+    pub swap_commitment: Account<'info, SwapCommitment>,
+    
+    /// CHECK: Validated the same way as in `SwapSecure`
+    #[account(
+        executable,
+        address = EXPECTED_SWAP_PROGRAM @ CpiError::InvalidProgram
+    )]
+    pub swap_program: UncheckedAccount<'info>,
+    
+    #[account(mut)]
+    pub user: Signer<'info>,
+    // remaining_accounts: the swap route, as for `swap_secure`
+}
+
+#[derive(Accounts)]
+pub struct SwapWithIntrospection<'info> {
+    /// CHECK: Validated the same way as in `SwapSecure`
+    #[account(
+        executable,
+        address = EXPECTED_SWAP_PROGRAM @ CpiError::InvalidProgram
+    )]
     pub swap_program: UncheckedAccount<'info>,
     
     pub user: Signer<'info>,
+    
+    /// CHECK: The instructions sysvar, pinned by address
+    #[account(address = instructions_sysvar::ID)]
+    pub instructions: UncheckedAccount<'info>,
+    // remaining_accounts: the swap route, as for `swap_secure`
 }
 
 #[derive(Accounts)]
@@ -424,19 +1183,144 @@ pub struct DistributeRewardsSecure<'info> {
     pub admin: Signer<'info>,
 }
 
+#[derive(Accounts)]
+pub struct BatchDistribute<'info> {
+    #[account(
+        seeds = [b"treasury"],
+        bump = treasury.bump,
+        has_one = admin,
+    )]
+    pub treasury: Account<'info, Treasury>,
+    
+    #[account(mut, token::authority = treasury)]
+    pub treasury_token_account: Account<'info, TokenAccount>,
+    
+    pub admin: Signer<'info>,
+    
+    // remaining_accounts: (recipient token account, program) pairs, each
+    // program in `BATCH_PROGRAM_ALLOWLIST` and each recipient listed once
+}
+
 #[derive(Accounts)]
 pub struct CallOracleSecure<'info> {
-    /// SECURE: Executable constraint plus address validation
-    /// 
-    /// In production:
-    /// #[account(
-    ///     executable,
-    ///     address = ORACLE_PROGRAM_ID @ CpiError::InvalidOracle
-    /// )]
-    #[account(executable)]
+    /// SECURE: Address, executable and loader owner, checked in the handler
+    /// 
+    /// CHECK: `guards::require_valid_program` with `ORACLE_PROGRAM_ID`
     pub oracle_program: UncheckedAccount<'info>,
 }
 
+#[derive(Accounts)]
+pub struct ClaimViaCpi<'info> {
+    /// Credited with whatever the reward program reports
+    #[account(
+        mut,
+        seeds = [b"vault", authority.key().as_ref()],
+        bump = vault.bump,
+        has_one = authority,
+    )]
+    pub vault: Account<'info, Vault>,
+    
+    /// CHECK: The reward program's own state; it validates the contents
+    /// and zeroes the reward it reports
+    #[account(mut, owner = REWARD_PROGRAM_ID @ CpiError::InvalidProgram)]
+    pub reward_state: UncheckedAccount<'info>,
+    
+    /// CHECK: SECURE - executable and exactly the expected reward program
+    #[account(executable, address = REWARD_PROGRAM_ID @ CpiError::InvalidProgram)]
+    pub reward_program: UncheckedAccount<'info>,
+    
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SafeTransfer<'info> {
+    /// SECURE: Typed PDA bound to the signing authority and its mint
+    #[account(
+        mut,
+        seeds = [b"vault", authority.key().as_ref()],
+        bump = vault.bump,
+        has_one = authority,
+        has_one = mint,
+    )]
+    pub vault: Account<'info, Vault>,
+    
+    pub mint: Account<'info, Mint>,
+    
+    /// SECURE: This vault's own token account (see `init_token_account`),
+    /// not merely one controlled by a PDA every vault shares
+    #[account(
+        mut,
+        seeds = [b"vault_token", vault.key().as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = vault,
+    )]
+    pub vault_token_account: Account<'info, TokenAccount>,
+    
+    /// SECURE: Must hold the vault's mint and must not alias the source
+    #[account(
+        mut,
+        token::mint = mint,
+        constraint = user_token_account.key() != vault_token_account.key() @ CpiError::DuplicateAccount,
+    )]
+    pub user_token_account: Account<'info, TokenAccount>,
+    
+    pub token_program: Program<'info, Token>,
+    
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct TransferCheckedSecure<'info> {
+    #[account(
+        mut,
+        token::mint = mint,
+        token::authority = authority,
+    )]
+    pub source: Account<'info, TokenAccount>,
+    
+    /// SECURE: The token program checks `decimals` against this mint
+    pub mint: Account<'info, Mint>,
+    
+    #[account(
+        mut,
+        token::mint = mint,
+    )]
+    pub destination: Account<'info, TokenAccount>,
+    
+    pub authority: Signer<'info>,
+    
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct TransferHookAware<'info> {
+    /// SECURE: Owned by `token_program`, holding `mint`, controlled by `authority`
+    #[account(
+        mut,
+        token::mint = mint,
+        token::authority = authority,
+        token::token_program = token_program,
+    )]
+    pub source: InterfaceAccount<'info, token_interface::TokenAccount>,
+    
+    #[account(mint::token_program = token_program)]
+    pub mint: InterfaceAccount<'info, token_interface::Mint>,
+    
+    #[account(
+        mut,
+        token::mint = mint,
+        token::token_program = token_program,
+    )]
+    pub destination: InterfaceAccount<'info, token_interface::TokenAccount>,
+    
+    pub authority: Signer<'info>,
+    
+    /// SECURE: Either the Token program or Token-2022, nothing else
+    pub token_program: Interface<'info, TokenInterface>,
+    // remaining_accounts: the mint's transfer-hook accounts, if any
+}
+
 #[derive(Accounts)]
 pub struct InitializeVault<'info> {
     #[account(
@@ -455,9 +1339,40 @@ pub struct InitializeVault<'info> {
     )]
     pub vault_authority: UncheckedAccount<'info>,
     
+    pub mint: Account<'info, Mint>,
+    
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitTokenAccount<'info> {
+    /// SECURE: The token account can only be created for the vault's mint
+    #[account(
+        seeds = [b"vault", authority.key().as_ref()],
+        bump = vault.bump,
+        has_one = authority,
+        has_one = mint,
+    )]
+    pub vault: Account<'info, Vault>,
+    
+    pub mint: Account<'info, Mint>,
+    
+    /// CHECK: Created and initialized by the handler at this PDA
+    #[account(
+        mut,
+        seeds = [b"vault_token", vault.key().as_ref()],
+        bump,
+    )]
+    pub vault_token_account: UncheckedAccount<'info>,
+    
     #[account(mut)]
     pub authority: Signer<'info>,
     
+    pub token_program: Program<'info, Token>,
+    
     pub system_program: Program<'info, System>,
 }
 
@@ -486,6 +1401,7 @@ pub struct InitializeTreasury<'info> {
 #[derive(InitSpace)]
 pub struct Vault {
     pub authority: Pubkey,
+    pub mint: Pubkey,
     pub balance: u64,
     pub vault_authority_bump: u8,
     pub bump: u8,
@@ -499,6 +1415,39 @@ pub struct Treasury {
     pub bump: u8,
 }
 
+#[account]
+#[derive(InitSpace)]
+pub struct SwapCommitment {
+    pub user: Pubkey,
+    /// `swap_commitment_of(amount, min_out, nonce)`
+    pub commitment: [u8; 32],
+    /// Slot the commitment was recorded in; reveals must come later
+    pub slot: u64,
+    pub bump: u8,
+}
+
+/// `sha256(amount || min_out || nonce)`, with both amounts little-endian -
+/// the value `commit_swap` stores and `reveal_swap` checks.
+/// 
+/// The nonce keeps a common `(amount, min_out)` pair from being guessed
+/// off a table of precomputed hashes.
+pub fn swap_commitment_of(amount: u64, min_out: u64, nonce: &[u8; 32]) -> [u8; 32] {
+    hashv(&[&amount.to_le_bytes(), &min_out.to_le_bytes(), nonce]).to_bytes()
+}
+
+// ============================================================================
+// EVENTS
+// ============================================================================
+
+#[event]
+pub struct SafeTransferEvent {
+    pub vault: Pubkey,
+    pub authority: Pubkey,
+    pub destination: Pubkey,
+    pub amount: u64,
+    pub remaining_balance: u64,
+}
+
 // ============================================================================
 // ERRORS
 // ============================================================================
@@ -511,6 +1460,106 @@ pub enum CpiError {
     NotExecutable,
     #[msg("Invalid oracle program")]
     InvalidOracle,
+    // 3: `InsufficientFunds` moved to `SecurityError`
+    #[msg("Source and destination accounts must differ")]
+    DuplicateAccount = 4,
+    // 5: `ZeroAmount` moved to `SecurityError`
+    #[msg("Account data is shorter than the callee expects")]
+    AccountDataTooSmall = 6,
+    #[msg("Mint decimals do not match the expected decimals")]
+    DecimalsMismatch,
+    #[msg("Callee did not return a single u64")]
+    InvalidReturnData,
+    #[msg("Reward is below the caller's minimum")]
+    RewardBelowMinimum,
+    #[msg("Revealed swap parameters do not match the commitment")]
+    CommitmentMismatch,
+    #[msg("A swap must be revealed in a later slot than it was committed")]
+    RevealTooEarly,
+    #[msg("A later instruction targets a program outside the allowlist")]
+    UnexpectedFollowingInstruction,
+    #[msg("Remaining accounts must come in (token account, program) pairs")]
+    UnpairedRemainingAccount,
+    #[msg("A recipient token account appears more than once in the batch")]
+    DuplicateRecipient,
+    #[msg("The swap returned less than the minimum amount out")]
+    SlippageExceeded,
+}
+
+error_categories!(CpiError {
+    Validation => [
+        InvalidProgram, NotExecutable, InvalidOracle, DuplicateAccount, AccountDataTooSmall,
+        DecimalsMismatch, InvalidReturnData, RewardBelowMinimum, CommitmentMismatch, UnexpectedFollowingInstruction,
+        UnpairedRemainingAccount, DuplicateRecipient, SlippageExceeded,
+    ],
+    State => [RevealTooEarly],
+});
+
+// ============================================================================
+// CPI GUARDS
+// ============================================================================
+
+pub mod guards {
+    use super::*;
+
+    /// Rejects accounts with fewer than `n` bytes of data.
+    /// 
+    /// Callees often index into account data at fixed offsets. Forwarding a
+    /// truncated account lets the caller decide what those reads return (or
+    /// whether they fault), so check the length before the CPI.
+    pub fn assert_min_len(info: &AccountInfo, n: usize) -> Result<()> {
+        require!(info.data_len() >= n, CpiError::AccountDataTooSmall);
+        Ok(())
+    }
+
+    /// `assert_min_len` for each account of `route` against the length
+    /// `min_lens` expects at its position.
+    /// 
+    /// Positions past the end of `min_lens` aren't checked: a route also
+    /// carries mints, programs and callee state whose size this program
+    /// doesn't know.
+    pub fn assert_route_lens(route: &[AccountInfo], min_lens: &[usize]) -> Result<()> {
+        for (info, min_len) in route.iter().zip(min_lens) {
+            assert_min_len(info, *min_len)?;
+        }
+        Ok(())
+    }
+
+    /// Requires `info` to be the deployed program `expected_id`.
+    /// 
+    /// - the key must be `expected_id` (`InvalidProgram`)
+    /// - it must be executable (`NotExecutable`)
+    /// - it must be owned by `bpf_loader_upgradeable` or `bpf_loader`
+    ///   (`InvalidProgram`) - an executable owned by anything else is not a
+    ///   program anyone deployed
+    pub fn require_valid_program(info: &AccountInfo, expected_id: &Pubkey) -> Result<()> {
+        require_keys_eq!(info.key(), *expected_id, CpiError::InvalidProgram);
+        require!(info.executable, CpiError::NotExecutable);
+        require!(
+            *info.owner == bpf_loader_upgradeable::ID || *info.owner == bpf_loader::ID,
+            CpiError::InvalidProgram
+        );
+        Ok(())
+    }
+
+    /// Rejects a transaction in which any top-level instruction after the
+    /// current one targets a program outside `FOLLOWING_PROGRAM_ALLOWLIST`.
+    /// 
+    /// Every later instruction is checked, not just the next one - a
+    /// back-run works as well two instructions later. Being the last
+    /// instruction is fine. `instructions` must be the instructions sysvar.
+    pub fn assert_following_instructions_allowed(instructions: &AccountInfo) -> Result<()> {
+        let mut index = usize::from(load_current_index_checked(instructions)?) + 1;
+        // Loading past the last instruction fails: nothing more follows
+        while let Ok(following) = load_instruction_at_checked(index, instructions) {
+            require!(
+                FOLLOWING_PROGRAM_ALLOWLIST.contains(&following.program_id),
+                CpiError::UnexpectedFollowingInstruction
+            );
+            index += 1;
+        }
+        Ok(())
+    }
 }
 
 // ============================================================================
@@ -520,9 +1569,15 @@ pub enum CpiError {
 // Use Program<'info, T> types for standard programs (Token, System, etc.)
 // Verify program IDs with `address` constraint for custom programs
 // Add `executable` constraint when using UncheckedAccount for programs
+// Check a custom program's owner is a BPF loader, not just `executable`
 // Never pass signer seeds to unvalidated programs
 // Use Anchor's CPI helpers (token::transfer, etc.) when possible
 // Store expected program IDs as constants
-// Be cautious with remaining_accounts - validate each one
+// Be cautious with remaining_accounts - validate each one, pairs included
+// Check forwarded accounts meet the callee's minimum data length
+// Use transfer_checked for Token-2022 mints so hooks and decimals apply
+// Commit to swap parameters before revealing them, in a later slot
+// Check the instructions sysvar for what else shares the transaction
+// Create program-owned token accounts for a known mint and PDA owner yourself
 //
 // ============================================================================
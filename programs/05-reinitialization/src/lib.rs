@@ -18,6 +18,11 @@
 //! checking if the account is already initialized.
 
 use anchor_lang::prelude::*;
+use anchor_lang::solana_program::hash::hashv;
+use anchor_lang::system_program::{self, CreateAccount, Transfer};
+use anchor_lang::Discriminator;
+use security_common::{checked_close, error_categories, require_owned_by, Tombstone};
+pub use security_common::SecurityError;
 
 declare_id!("Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLnW");
 
@@ -71,7 +76,7 @@ pub mod reinitialization {
         let vault = &mut ctx.accounts.vault;
         
         // SECURE: Check initialization flag
-        require!(!vault.is_initialized, ReinitError::AlreadyInitialized);
+        require!(!vault.is_initialized, SecurityError::AlreadyInitialized);
         
         vault.authority = ctx.accounts.authority.key();
         vault.balance = 0;
@@ -155,6 +160,40 @@ pub mod reinitialization {
         Ok(())
     }
 
+    /// SECURE (Manual): The checks `Account<>` makes, done by hand on raw data.
+    /// 
+    /// ## What's Fixed?
+    /// For programs that have to work with raw bytes:
+    /// 1. The account must be owned by this program - otherwise anyone
+    ///    could have written its bytes. `security_common::require_owned_by`
+    ///    fails as `Account<>` would: `AccountOwnedByWrongProgram`, or
+    ///    `AccountDiscriminatorNotFound` for fewer than 8 bytes
+    /// 2. An all-zero discriminator means the account was allocated but
+    ///    never initialized (`NotInitialized`)
+    /// 3. Any other discriminator but `VaultSecure::DISCRIMINATOR` is some
+    ///    other type (`WrongAccountType`)
+    /// 
+    /// Unlike `data[0]`, the discriminator can't be set by a field value:
+    /// every initialized vault has these exact 8 bytes, and nothing else does.
+    pub fn process_vault_manual_secure(
+        ctx: Context<ProcessVaultManualSecure>,
+    ) -> Result<()> {
+        let info = &ctx.accounts.vault_info;
+        require_owned_by(info, ctx.program_id, ErrorCode::AccountOwnedByWrongProgram)?;
+        
+        let data = info.try_borrow_data()?;
+        let discriminator = &data[..8];
+        require!(discriminator != [0u8; 8], ReinitError::NotInitialized);
+        require!(
+            discriminator == VaultSecure::DISCRIMINATOR,
+            ReinitError::WrongAccountType
+        );
+        
+        let vault = VaultSecure::try_deserialize(&mut &data[..])?;
+        msg!("SECURE (manual): Processing vault owned by {}", vault.authority);
+        Ok(())
+    }
+
     // ============================================================================
     // VULNERABILITY 3: CONFIG REINIT
     // ============================================================================
@@ -198,17 +237,486 @@ pub mod reinitialization {
         // SECURE: Anchor's init ensures this PDA doesn't exist yet
         config.admin = ctx.accounts.admin.key();
         config.fee_bps = fee_bps;
+        config.pending_admin = None;
         config.bump = ctx.bumps.config;
         
         msg!("SECURE: Config initialized with fee {}bps", fee_bps);
         Ok(())
     }
 
+    // ============================================================================
+    // ADMIN TRANSFER
+    // ============================================================================
+
+    /// VULNERABLE: Anyone can make anyone the admin.
+    /// 
+    /// ## What's Wrong?
+    /// The same takeover as reinitializing the config, without even
+    /// needing the init path: nothing checks that the caller is the
+    /// current admin. And a one-step handoff has a second problem even
+    /// with that check - a typo in `new_admin` locks the protocol forever.
+    pub fn set_admin_vulnerable(ctx: Context<SetAdminVulnerable>, new_admin: Pubkey) -> Result<()> {
+        // DANGER: No check on who is calling
+        ctx.accounts.config.admin = new_admin;
+        
+        msg!("VULNERABLE: Admin set to {}", new_admin);
+        Ok(())
+    }
+
+    /// SECURE (step 1): The current admin nominates the next one.
+    /// 
+    /// Nothing changes hands yet; proposing again replaces the nominee.
+    pub fn propose_admin_transfer(ctx: Context<ProposeAdminTransfer>, new_admin: Pubkey) -> Result<()> {
+        ctx.accounts.config.pending_admin = Some(new_admin);
+        
+        msg!("SECURE: Admin transfer to {} proposed", new_admin);
+        Ok(())
+    }
+
+    /// SECURE (step 2): The nominee signs to take over.
+    /// 
+    /// ## What's Fixed?
+    /// Control only moves to a key that both the old admin named and that
+    /// can sign - an unknown or mistyped key can never accept. With no
+    /// transfer pending, nobody is the pending admin, so every caller gets
+    /// `NotPendingAdmin`.
+    pub fn accept_admin_transfer(ctx: Context<AcceptAdminTransfer>) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        let new_admin = ctx.accounts.new_admin.key();
+        
+        // SECURE: Only the nominee, and only while a transfer is pending
+        require!(config.pending_admin == Some(new_admin), ReinitError::NotPendingAdmin);
+        
+        config.admin = new_admin;
+        config.pending_admin = None;
+        
+        msg!("SECURE: {} accepted the admin role", new_admin);
+        Ok(())
+    }
+
+    // ============================================================================
+    // VULNERABILITY 4: RAW WRITES RESET MANUAL FLAGS
+    // ============================================================================
+
+    /// VULNERABLE: Writes caller-supplied bytes into any program-owned account.
+    /// 
+    /// ## What's Wrong?
+    /// Generic "patch" or migration endpoints like this one are common, and
+    /// the program owns every account it writes, so the runtime allows it.
+    /// Nothing checks who is calling or which account is being patched.
+    /// 
+    /// ## Attack Scenario:
+    /// 1. Victim's VaultVulnerable has `is_initialized = true`
+    /// 2. Attacker writes a single `0` byte over `is_initialized`
+    /// 3. Attacker calls initialize_secure_manual - the flag check passes
+    /// 4. Attacker is now the vault's authority
+    /// 
+    /// A manual flag is only as strong as every code path that can write
+    /// the account. Anchor's `init` doesn't depend on account data at all:
+    /// even zeroed bytes can't make an existing address re-initializable.
+    pub fn write_raw_vulnerable(
+        ctx: Context<WriteRawVulnerable>,
+        offset: u32,
+        bytes: Vec<u8>,
+    ) -> Result<()> {
+        let mut data = ctx.accounts.target.try_borrow_mut_data()?;
+        let start = offset as usize;
+        let end = start
+            .checked_add(bytes.len())
+            .filter(|end| *end <= data.len())
+            .ok_or(ProgramError::AccountDataTooSmall)?;
+        
+        // DANGER: Arbitrary bytes, arbitrary account, arbitrary caller
+        data[start..end].copy_from_slice(&bytes);
+        
+        msg!("VULNERABLE: Wrote {} raw bytes at offset {}", bytes.len(), offset);
+        Ok(())
+    }
+
+    // ============================================================================
+    // PRE-FUNDED ADDRESSES
+    // ============================================================================
+
+    /// SECURE (Manual Creation): Creates the vault with `create_account` and
+    /// explains a pre-funded address instead of failing opaquely.
+    /// 
+    /// ## The Edge Case
+    /// A PDA's address is public before the account exists, and anyone can
+    /// send lamports to it. `create_account` then refuses the address with
+    /// a bare `AccountAlreadyInUse`, which reads like a reinitialization
+    /// bug. This handler checks first and returns `AddressPreFunded`.
+    /// 
+    /// Note that Anchor's own `init` (see `initialize_secure_anchor`) tops
+    /// the balance up and uses allocate + assign instead, so it is not
+    /// affected - this matters for hand-rolled creation only.
+    pub fn initialize_robust(ctx: Context<InitializeRobust>) -> Result<()> {
+        let vault = &ctx.accounts.vault;
+        
+        require!(
+            vault.owner != ctx.program_id,
+            SecurityError::AlreadyInitialized
+        );
+        
+        if vault.lamports() > 0 {
+            msg!(
+                "Vault address {} already holds {} lamports. create_account cannot \
+                 use a funded address; initialize with initialize_secure_anchor, \
+                 which tops up and allocates instead.",
+                vault.key(),
+                vault.lamports()
+            );
+            return err!(ReinitError::AddressPreFunded);
+        }
+        
+        let space = 8 + VaultSecure::INIT_SPACE;
+        let authority_key = ctx.accounts.authority.key();
+        let seeds: &[&[u8]] = &[b"vault", authority_key.as_ref(), &[ctx.bumps.vault]];
+        
+        system_program::create_account(
+            CpiContext::new_with_signer(
+                ctx.accounts.system_program.to_account_info(),
+                CreateAccount {
+                    from: ctx.accounts.authority.to_account_info(),
+                    to: vault.to_account_info(),
+                },
+                &[seeds],
+            ),
+            Rent::get()?.minimum_balance(space),
+            space as u64,
+            ctx.program_id,
+        )?;
+        
+        let state = VaultSecure {
+            authority: authority_key,
+            balance: 0,
+            total_deposits: 0,
+            total_withdrawals: 0,
+            bump: ctx.bumps.vault,
+        };
+        state.try_serialize(&mut &mut vault.try_borrow_mut_data()?[..])?;
+        
+        msg!("SECURE (robust): Created vault with create_account");
+        Ok(())
+    }
+
+    // ============================================================================
+    // INIT IF NEEDED
+    // ============================================================================
+
+    /// VULNERABLE: `init_if_needed` with a handler that assumes a new account.
+    /// 
+    /// ## What's Wrong?
+    /// `init_if_needed` creates the vault on the first call and simply loads
+    /// it on every later one - the handler runs either way. Writing the
+    /// initial values unconditionally turns every deposit into a reinit.
+    /// 
+    /// ## Attack Scenario:
+    /// 1. Alice's vault holds a balance of 1,000
+    /// 2. Attacker deposits 1 into Alice's vault (deposits need no authority)
+    /// 3. The handler "initializes" it again: balance = 1, counters reset
+    /// 4. Alice's 1,000 is gone from the books
+    pub fn deposit_or_init_vulnerable(ctx: Context<DepositOrInit>, amount: u64) -> Result<()> {
+        ctx.accounts.kill_switch.require_enabled(instruction::DepositOrInitVulnerable::DISCRIMINATOR)?;
+        require!(amount > 0, SecurityError::ZeroAmount);
+        
+        let vault = &mut ctx.accounts.vault;
+        
+        // DANGER: Runs on an existing vault too
+        vault.authority = ctx.accounts.owner.key();
+        vault.balance = 0;
+        vault.total_deposits = 0;
+        vault.total_withdrawals = 0;
+        vault.bump = ctx.bumps.vault;
+        
+        vault.balance = vault.balance.checked_add(amount).ok_or(SecurityError::Overflow)?;
+        vault.total_deposits = vault.total_deposits.checked_add(amount).ok_or(SecurityError::Overflow)?;
+        
+        msg!("VULNERABLE: Deposited {} into a (re)initialized vault", amount);
+        Ok(())
+    }
+
+    /// SECURE: `init_if_needed` with the initial values written only once.
+    /// 
+    /// ## What's Fixed?
+    /// A vault `init_if_needed` just created is all zeroes after the
+    /// discriminator, and every vault it didn't create has its authority
+    /// set. Identity fields are written only in the first case; the
+    /// deposit itself always adds to what is there.
+    pub fn deposit_or_init_secure(ctx: Context<DepositOrInit>, amount: u64) -> Result<()> {
+        ctx.accounts.kill_switch.require_enabled(instruction::DepositOrInitSecure::DISCRIMINATOR)?;
+        require!(amount > 0, SecurityError::ZeroAmount);
+        
+        let vault = &mut ctx.accounts.vault;
+        
+        // SECURE: Only a vault created by this call is initialized
+        if vault.authority == Pubkey::default() {
+            vault.authority = ctx.accounts.owner.key();
+            vault.bump = ctx.bumps.vault;
+            msg!("SECURE: Created vault for {}", vault.authority);
+        }
+        
+        vault.balance = vault.balance.checked_add(amount).ok_or(SecurityError::Overflow)?;
+        vault.total_deposits = vault.total_deposits.checked_add(amount).ok_or(SecurityError::Overflow)?;
+        
+        msg!("SECURE: Deposited {}, balance now {}", amount, vault.balance);
+        Ok(())
+    }
+
+    // ============================================================================
+    // AUDIT MODE
+    // ============================================================================
+
+    /// AUDIT: Checks a vault's internal invariants without erroring.
+    /// 
+    /// Returns a bitmask of `audit_flags` (0 = healthy) through return data,
+    /// so monitoring can poll any vault - including one that was corrupted
+    /// by a vulnerable path - without the transaction failing.
+    /// 
+    /// The vault is deliberately loaded without a `seeds` constraint: a
+    /// non-canonical address or bump is one of the things being reported.
+    pub fn audit_vault(ctx: Context<AuditVault>) -> Result<u8> {
+        let vault = &ctx.accounts.vault;
+        let mut violations = 0u8;
+        
+        if vault.balance > vault.total_deposits {
+            violations |= audit_flags::BALANCE_EXCEEDS_DEPOSITS;
+        }
+        
+        // A u64 can't go negative, but "deposits - withdrawals" can
+        match vault.total_deposits.checked_sub(vault.total_withdrawals) {
+            Some(net) if net != vault.balance => {
+                violations |= audit_flags::BALANCE_MISMATCH;
+            }
+            Some(_) => {}
+            None => {
+                violations |= audit_flags::NEGATIVE_NET_BALANCE;
+            }
+        }
+        
+        let (expected, bump) = Pubkey::find_program_address(
+            &[b"vault", vault.authority.as_ref()],
+            ctx.program_id,
+        );
+        if expected != vault.key() || bump != vault.bump {
+            violations |= audit_flags::NON_CANONICAL_PDA;
+        }
+        
+        if vault.authority == Pubkey::default() {
+            violations |= audit_flags::AUTHORITY_UNSET;
+        }
+        
+        msg!("AUDIT: vault {} violations = {:#07b}", vault.key(), violations);
+        Ok(violations)
+    }
+
+    // ============================================================================
+    // KILL SWITCH
+    // ============================================================================
+
+    /// Creates the (initially empty) list of disabled instructions.
+    pub fn initialize_kill_switch(ctx: Context<InitializeKillSwitch>) -> Result<()> {
+        let kill_switch = &mut ctx.accounts.kill_switch;
+        kill_switch.admin = ctx.accounts.admin.key();
+        kill_switch.disabled = Vec::new();
+        kill_switch.bump = ctx.bumps.kill_switch;
+        Ok(())
+    }
+
+    /// Disables or re-enables one instruction, identified by its 8-byte
+    /// Anchor discriminator, without redeploying the program.
+    /// 
+    /// Pausing is a privileged override, so it is recorded in the admin
+    /// audit log either way. Returns the recorded entry.
+    /// 
+    /// Idempotent: pausing a paused instruction, or enabling an enabled
+    /// one, leaves the list as it was - even when it is already full.
+    pub fn set_instruction_enabled(
+        ctx: Context<SetInstructionEnabled>,
+        ix_disc: [u8; 8],
+        enabled: bool,
+    ) -> Result<AdminOverride> {
+        let action = if enabled { OverrideAction::Unpause } else { OverrideAction::Pause };
+        let target = ctx.accounts.kill_switch.key();
+        let entry = record_override(&mut ctx.accounts.audit_log, ctx.accounts.admin.key(), action, target)?;
+        
+        let disabled = &mut ctx.accounts.kill_switch.disabled;
+        
+        if enabled {
+            disabled.retain(|disc| *disc != ix_disc);
+        } else if !disabled.contains(&ix_disc) {
+            require!(
+                disabled.len() < DisabledInstructions::MAX_DISABLED,
+                ReinitError::KillSwitchFull
+            );
+            disabled.push(ix_disc);
+        }
+        
+        msg!("Instruction {:?} enabled = {}", ix_disc, enabled);
+        Ok(entry)
+    }
+
+    // ============================================================================
+    // ADMIN OVERRIDES
+    // ============================================================================
+
+    /// Creates the admin audit log. Only the kill switch admin may do so.
+    pub fn initialize_admin_audit_log(ctx: Context<InitializeAdminAuditLog>) -> Result<()> {
+        let audit_log = &mut ctx.accounts.audit_log;
+        audit_log.admin = ctx.accounts.admin.key();
+        audit_log.override_count = 0;
+        audit_log.last_hash = [0u8; 32];
+        audit_log.bump = ctx.bumps.audit_log;
+        Ok(())
+    }
+
+    /// Admin override: debits any vault, ignoring the kill switch and the
+    /// vault's own authority. Returns the audit log entry.
+    pub fn emergency_withdraw(ctx: Context<AdminVaultOverride>, amount: u64) -> Result<AdminOverride> {
+        require!(amount > 0, SecurityError::ZeroAmount);
+        let target = ctx.accounts.vault.key();
+        let entry = record_override(
+            &mut ctx.accounts.audit_log,
+            ctx.accounts.admin.key(),
+            OverrideAction::EmergencyWithdraw,
+            target,
+        )?;
+        
+        let vault = &mut ctx.accounts.vault;
+        vault.balance = vault.balance
+            .checked_sub(amount)
+            .ok_or(SecurityError::InsufficientFunds)?;
+        vault.total_withdrawals = vault.total_withdrawals
+            .checked_add(amount)
+            .ok_or(SecurityError::Overflow)?;
+        Ok(entry)
+    }
+
+    /// Admin override: zeroes a vault's balance and counters. Returns the
+    /// audit log entry.
+    pub fn reset_vault(ctx: Context<AdminVaultOverride>) -> Result<AdminOverride> {
+        let target = ctx.accounts.vault.key();
+        let entry = record_override(
+            &mut ctx.accounts.audit_log,
+            ctx.accounts.admin.key(),
+            OverrideAction::ResetVault,
+            target,
+        )?;
+        
+        let vault = &mut ctx.accounts.vault;
+        vault.balance = 0;
+        vault.total_deposits = 0;
+        vault.total_withdrawals = 0;
+        Ok(entry)
+    }
+
+    // ============================================================================
+    // CLOSING VAULTS
+    // ============================================================================
+
+    /// SECURE: Closes an empty vault through the shared `checked_close`.
+    /// 
+    /// Only the vault's authority can close it, and only once its balance
+    /// is zero. The rent goes to `recipient`; the returned tombstone is
+    /// kept at `[b"vault_tombstone", vault, n]`, where `n` counts the
+    /// vault's earlier closes, so a recreated vault can be closed again
+    /// without overwriting the record of the last one.
+    pub fn close_vault(ctx: Context<CloseVault>) -> Result<()> {
+        let vault = &ctx.accounts.vault;
+        require!(vault.balance == 0, ReinitError::VaultNotEmpty);
+        
+        let record = checked_close(
+            &vault.to_account_info(),
+            &ctx.accounts.recipient,
+            vault.authority,
+            ctx.accounts.authority.key(),
+        )?;
+        let tombstone = &mut ctx.accounts.tombstone;
+        tombstone.record = record;
+        tombstone.bump = ctx.bumps.tombstone;
+        
+        let closes = &mut ctx.accounts.closes;
+        closes.count = closes.count.checked_add(1).ok_or(SecurityError::Overflow)?;
+        closes.bump = ctx.bumps.closes;
+        
+        msg!("SECURE: Vault closed, {} lamports to {}", record.lamports, record.recipient);
+        Ok(())
+    }
+
+    // ============================================================================
+    // GROWING ACCOUNTS
+    // ============================================================================
+
+    /// Creates a `VaultV2` sized to exactly fit `notes`.
+    pub fn initialize_vault_v2(ctx: Context<InitializeVaultV2>, notes: Vec<u8>) -> Result<()> {
+        let vault = &mut ctx.accounts.vault;
+        vault.authority = ctx.accounts.authority.key();
+        vault.balance = 0;
+        vault.bump = ctx.bumps.vault;
+        vault.notes = notes;
+        Ok(())
+    }
+
+    /// VULNERABLE: Starts the notes over in a larger account with
+    /// `realloc(new_len, false)`.
+    /// 
+    /// ## What's Wrong?
+    /// `zero_init = false` skips zeroing the grown region. Memory that was
+    /// never part of the account is zero anyway, but this handler shrinks
+    /// the account first (dropping the old notes) and then grows it in the
+    /// same instruction - and a shrink doesn't erase anything.
+    /// 
+    /// ## Attack Scenario:
+    /// 1. Alice stores a note, then grows her vault to start a fresh one
+    /// 2. The "new" space past the empty notes still holds her old note
+    /// 3. Anyone reading the account - or any later instruction that
+    ///    treats that space as blank - sees the dropped bytes
+    pub fn grow_vault(ctx: Context<GrowVault>, new_len: u32) -> Result<()> {
+        let new_len = new_len as usize;
+        let info = ctx.accounts.vault.to_account_info();
+        fund_rent(&info, &ctx.accounts.authority, &ctx.accounts.system_program, new_len)?;
+        
+        ctx.accounts.vault.notes.clear();
+        info.realloc(VaultV2::space(0), false)?;
+        
+        // DANGER: Hands the bytes just dropped back as the "new" region
+        info.realloc(new_len, false)?;
+        
+        msg!("VULNERABLE: Grew vault to {} bytes without zeroing", new_len);
+        Ok(())
+    }
+
+    /// SECURE: Only grows, keeping the notes, and zeroes everything it grows
+    /// into.
+    /// 
+    /// ## What's Fixed?
+    /// - The account grows from its current length and the notes stay as
+    ///   they are; nothing is dropped, so no dropped bytes can resurface
+    /// - A `new_len` that isn't past the current length is `InvalidSize`:
+    ///   this is a grow, so it can't be used to shrink (or to shrink and
+    ///   grow back)
+    /// - `realloc(new_len, true)` zeroes from the current end to `new_len`,
+    ///   whatever that memory held before
+    pub fn grow_vault_secure(ctx: Context<GrowVault>, new_len: u32) -> Result<()> {
+        let new_len = new_len as usize;
+        let info = ctx.accounts.vault.to_account_info();
+        require!(new_len > info.data_len(), ReinitError::InvalidSize);
+        fund_rent(&info, &ctx.accounts.authority, &ctx.accounts.system_program, new_len)?;
+        
+        // SECURE: One realloc, up only, zeroing what it adds
+        info.realloc(new_len, true)?;
+        
+        msg!("SECURE: Grew vault to {} bytes, the new space zeroed", new_len);
+        Ok(())
+    }
+
     // ============================================================================
     // HELPER INSTRUCTIONS
     // ============================================================================
 
     pub fn deposit(ctx: Context<Deposit>, amount: u64) -> Result<()> {
+        ctx.accounts.kill_switch.require_enabled(instruction::Deposit::DISCRIMINATOR)?;
+        require!(amount > 0, SecurityError::ZeroAmount);
+        
         let vault = &mut ctx.accounts.vault;
         vault.balance = vault.balance.checked_add(amount).unwrap();
         vault.total_deposits = vault.total_deposits.checked_add(amount).unwrap();
@@ -216,14 +724,63 @@ pub mod reinitialization {
     }
 
     pub fn withdraw(ctx: Context<Withdraw>, amount: u64) -> Result<()> {
+        ctx.accounts.kill_switch.require_enabled(instruction::Withdraw::DISCRIMINATOR)?;
+        require!(amount > 0, SecurityError::ZeroAmount);
+        
         let vault = &mut ctx.accounts.vault;
-        require!(vault.authority == ctx.accounts.authority.key(), ReinitError::Unauthorized);
+        require!(vault.authority == ctx.accounts.authority.key(), SecurityError::Unauthorized);
         vault.balance = vault.balance.checked_sub(amount).unwrap();
         vault.total_withdrawals = vault.total_withdrawals.checked_add(amount).unwrap();
         Ok(())
     }
 }
 
+/// Tops `account` up to the rent-exempt minimum for `len` bytes, paid by
+/// `payer`.
+fn fund_rent<'info>(
+    account: &AccountInfo<'info>,
+    payer: &Signer<'info>,
+    system_program: &Program<'info, System>,
+    len: usize,
+) -> Result<()> {
+    let shortfall = Rent::get()?.minimum_balance(len).saturating_sub(account.lamports());
+    if shortfall > 0 {
+        system_program::transfer(
+            CpiContext::new(
+                system_program.to_account_info(),
+                Transfer { from: payer.to_account_info(), to: account.clone() },
+            ),
+            shortfall,
+        )?;
+    }
+    Ok(())
+}
+
+/// Appends one privileged action to the audit log, emits it and returns it.
+/// 
+/// Each entry's hash covers the previous hash, so the log is a hash chain:
+/// an indexer replaying the `AdminOverride` events recomputes `last_hash`,
+/// and any dropped, reordered or altered entry makes it diverge from the
+/// one stored on chain.
+fn record_override(
+    audit_log: &mut AdminAuditLog,
+    admin: Pubkey,
+    action: OverrideAction,
+    target: Pubkey,
+) -> Result<AdminOverride> {
+    let sequence = audit_log.override_count
+        .checked_add(1)
+        .ok_or(SecurityError::Overflow)?;
+    let hash = AdminAuditLog::chain(&audit_log.last_hash, sequence, action, &admin, &target);
+    
+    audit_log.override_count = sequence;
+    audit_log.last_hash = hash;
+    
+    let entry = AdminOverride { admin, action, target, sequence, hash };
+    emit!(entry.clone());
+    Ok(entry)
+}
+
 // ============================================================================
 // VULNERABLE ACCOUNT STRUCTURES
 // ============================================================================
@@ -257,6 +814,23 @@ pub struct ProcessVaultVulnerable<'info> {
     pub vault_info: UncheckedAccount<'info>,
 }
 
+#[derive(Accounts)]
+pub struct ProcessVaultManualSecure<'info> {
+    /// CHECK: Owner and discriminator are checked in the handler
+    pub vault_info: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct WriteRawVulnerable<'info> {
+    /// VULNERABLE: Any account this program owns, with no type check
+    /// 
+    /// CHECK: Intentionally insecure for demonstration
+    #[account(mut)]
+    pub target: UncheckedAccount<'info>,
+    
+    pub caller: Signer<'info>,
+}
+
 #[derive(Accounts)]
 pub struct InitializeConfigVulnerable<'info> {
     /// VULNERABLE: Can be called multiple times
@@ -266,6 +840,15 @@ pub struct InitializeConfigVulnerable<'info> {
     pub admin: Signer<'info>,
 }
 
+#[derive(Accounts)]
+pub struct SetAdminVulnerable<'info> {
+    /// VULNERABLE: No `has_one = admin`, and the caller isn't compared
+    #[account(mut)]
+    pub config: Account<'info, ConfigVulnerable>,
+    
+    pub caller: Signer<'info>,
+}
+
 // ============================================================================
 // SECURE ACCOUNT STRUCTURES
 // ============================================================================
@@ -321,6 +904,244 @@ pub struct InitializeConfigSecure<'info> {
     pub system_program: Program<'info, System>,
 }
 
+#[derive(Accounts)]
+pub struct ProposeAdminTransfer<'info> {
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = admin,
+    )]
+    pub config: Account<'info, ConfigSecure>,
+    
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct AcceptAdminTransfer<'info> {
+    /// `pending_admin` is checked in the handler
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, ConfigSecure>,
+    
+    pub new_admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeRobust<'info> {
+    /// Created in the handler; seeds pin the address
+    /// 
+    /// CHECK: Ownership and balance are checked before create_account
+    #[account(
+        mut,
+        seeds = [b"vault", authority.key().as_ref()],
+        bump
+    )]
+    pub vault: UncheckedAccount<'info>,
+    
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct DepositOrInit<'info> {
+    /// Created on the first deposit for `owner`, loaded on every later one
+    #[account(
+        init_if_needed,
+        payer = depositor,
+        space = 8 + VaultSecure::INIT_SPACE,
+        seeds = [b"vault", owner.key().as_ref()],
+        bump
+    )]
+    pub vault: Account<'info, VaultSecure>,
+    
+    /// CHECK: Only used as the vault's seed - anyone may deposit for anyone
+    pub owner: UncheckedAccount<'info>,
+    
+    #[account(seeds = [b"disabled_instructions"], bump = kill_switch.bump)]
+    pub kill_switch: Account<'info, DisabledInstructions>,
+    
+    #[account(mut)]
+    pub depositor: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct AuditVault<'info> {
+    /// Owner and discriminator are checked; seeds are audited in the handler
+    pub vault: Account<'info, VaultSecure>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeKillSwitch<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + DisabledInstructions::INIT_SPACE,
+        seeds = [b"disabled_instructions"],
+        bump
+    )]
+    pub kill_switch: Account<'info, DisabledInstructions>,
+    
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetInstructionEnabled<'info> {
+    #[account(
+        mut,
+        seeds = [b"disabled_instructions"],
+        bump = kill_switch.bump,
+        has_one = admin,
+    )]
+    pub kill_switch: Account<'info, DisabledInstructions>,
+    
+    #[account(
+        mut,
+        seeds = [b"admin_audit"],
+        bump = audit_log.bump,
+    )]
+    pub audit_log: Account<'info, AdminAuditLog>,
+    
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeAdminAuditLog<'info> {
+    #[account(
+        seeds = [b"disabled_instructions"],
+        bump = kill_switch.bump,
+        has_one = admin,
+    )]
+    pub kill_switch: Account<'info, DisabledInstructions>,
+    
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + AdminAuditLog::INIT_SPACE,
+        seeds = [b"admin_audit"],
+        bump
+    )]
+    pub audit_log: Account<'info, AdminAuditLog>,
+    
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CloseVault<'info> {
+    #[account(
+        mut,
+        seeds = [b"vault", vault.authority.as_ref()],
+        bump = vault.bump,
+        has_one = authority,
+    )]
+    pub vault: Account<'info, VaultSecure>,
+    
+    /// Numbers this close; it outlives the vault, so a recreated vault
+    /// continues the count
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + VaultCloses::INIT_SPACE,
+        seeds = [b"vault_closes", vault.key().as_ref()],
+        bump
+    )]
+    pub closes: Account<'info, VaultCloses>,
+    
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + VaultTombstone::INIT_SPACE,
+        seeds = [b"vault_tombstone", vault.key().as_ref(), &closes.count.to_le_bytes()],
+        bump
+    )]
+    pub tombstone: Account<'info, VaultTombstone>,
+    
+    /// CHECK: Receives the rent lamports
+    #[account(mut)]
+    pub recipient: AccountInfo<'info>,
+    
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+/// Accounts for an admin override against one vault
+#[derive(Accounts)]
+pub struct AdminVaultOverride<'info> {
+    /// The kill switch admin is the protocol admin
+    #[account(
+        seeds = [b"disabled_instructions"],
+        bump = kill_switch.bump,
+        has_one = admin,
+    )]
+    pub kill_switch: Account<'info, DisabledInstructions>,
+    
+    #[account(
+        mut,
+        seeds = [b"admin_audit"],
+        bump = audit_log.bump,
+    )]
+    pub audit_log: Account<'info, AdminAuditLog>,
+    
+    #[account(
+        mut,
+        seeds = [b"vault", vault.authority.as_ref()],
+        bump = vault.bump,
+    )]
+    pub vault: Account<'info, VaultSecure>,
+    
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(notes: Vec<u8>)]
+pub struct InitializeVaultV2<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = VaultV2::space(notes.len()),
+        seeds = [b"vault_v2", authority.key().as_ref()],
+        bump
+    )]
+    pub vault: Account<'info, VaultV2>,
+    
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct GrowVault<'info> {
+    #[account(
+        mut,
+        seeds = [b"vault_v2", authority.key().as_ref()],
+        bump = vault.bump,
+        has_one = authority,
+    )]
+    pub vault: Account<'info, VaultV2>,
+    
+    /// Pays the rent for the added space
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
 #[derive(Accounts)]
 pub struct Deposit<'info> {
     #[account(
@@ -330,6 +1151,9 @@ pub struct Deposit<'info> {
     )]
     pub vault: Account<'info, VaultSecure>,
     
+    #[account(seeds = [b"disabled_instructions"], bump = kill_switch.bump)]
+    pub kill_switch: Account<'info, DisabledInstructions>,
+    
     pub depositor: Signer<'info>,
 }
 
@@ -342,6 +1166,9 @@ pub struct Withdraw<'info> {
     )]
     pub vault: Account<'info, VaultSecure>,
     
+    #[account(seeds = [b"disabled_instructions"], bump = kill_switch.bump)]
+    pub kill_switch: Account<'info, DisabledInstructions>,
+    
     pub authority: Signer<'info>,
 }
 
@@ -360,6 +1187,11 @@ pub struct VaultVulnerable {
     pub is_initialized: bool,
 }
 
+impl VaultVulnerable {
+    /// Byte offset of `is_initialized`, discriminator included
+    pub const IS_INITIALIZED_OFFSET: usize = 8 + 32 + 8 + 8 + 8;
+}
+
 #[account]
 #[derive(InitSpace)]
 pub struct ConfigVulnerable {
@@ -388,21 +1220,172 @@ pub struct VaultSecure {
 pub struct ConfigSecure {
     pub admin: Pubkey,
     pub fee_bps: u16,
+    /// Nominated by `propose_admin_transfer`, until it accepts
+    pub pending_admin: Option<Pubkey>,
     pub bump: u8,
 }
 
+/// A vault with free-form notes; the account is resized as they change
+#[account]
+pub struct VaultV2 {
+    pub authority: Pubkey,
+    pub balance: u64,
+    pub bump: u8,
+    /// Variable length, so it comes last
+    pub notes: Vec<u8>,
+}
+
+impl VaultV2 {
+    /// Account length holding `notes_len` bytes of notes, discriminator included
+    pub const fn space(notes_len: usize) -> usize {
+        8 + 32 + 8 + 1 + 4 + notes_len
+    }
+}
+
+/// Permanent record that a vault was closed
+#[account]
+#[derive(InitSpace)]
+pub struct VaultTombstone {
+    pub record: Tombstone,
+    pub bump: u8,
+}
+
+/// How many times a vault address has been closed
+#[account]
+#[derive(InitSpace)]
+pub struct VaultCloses {
+    pub count: u64,
+    pub bump: u8,
+}
+
+/// Instructions the admin has switched off, by Anchor discriminator
+#[account]
+#[derive(InitSpace)]
+pub struct DisabledInstructions {
+    pub admin: Pubkey,
+    #[max_len(16)]
+    pub disabled: Vec<[u8; 8]>,
+    pub bump: u8,
+}
+
+impl DisabledInstructions {
+    /// Must match `max_len` on `disabled`
+    pub const MAX_DISABLED: usize = 16;
+    
+    /// Fails if the instruction with discriminator `ix_disc` is disabled.
+    pub fn require_enabled(&self, ix_disc: [u8; 8]) -> Result<()> {
+        require!(!self.disabled.contains(&ix_disc), ReinitError::InstructionDisabled);
+        Ok(())
+    }
+}
+
+/// Running record of every admin override
+#[account]
+#[derive(InitSpace)]
+pub struct AdminAuditLog {
+    pub admin: Pubkey,
+    /// Overrides recorded so far; the next one gets `override_count + 1`
+    pub override_count: u64,
+    /// Hash chain head - all zeroes before the first override
+    pub last_hash: [u8; 32],
+    pub bump: u8,
+}
+
+impl AdminAuditLog {
+    /// `sha256(prev_hash || sequence || action || admin || target)`
+    pub fn chain(
+        prev_hash: &[u8; 32],
+        sequence: u64,
+        action: OverrideAction,
+        admin: &Pubkey,
+        target: &Pubkey,
+    ) -> [u8; 32] {
+        hashv(&[
+            prev_hash,
+            &sequence.to_le_bytes(),
+            &[action as u8],
+            admin.as_ref(),
+            target.as_ref(),
+        ])
+        .to_bytes()
+    }
+}
+
+/// Privileged actions that bypass normal authorization
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OverrideAction {
+    Pause,
+    Unpause,
+    EmergencyWithdraw,
+    ResetVault,
+}
+
+// ============================================================================
+// EVENTS
+// ============================================================================
+
+/// Emitted for every admin override, in `sequence` order
+#[event]
+#[derive(Clone, Debug)]
+pub struct AdminOverride {
+    pub admin: Pubkey,
+    pub action: OverrideAction,
+    /// Account the override acted on
+    pub target: Pubkey,
+    pub sequence: u64,
+    /// `AdminAuditLog::last_hash` after this entry
+    pub hash: [u8; 32],
+}
+
+// ============================================================================
+// AUDIT FLAGS
+// ============================================================================
+
+/// Bits returned by `audit_vault`
+pub mod audit_flags {
+    /// `balance > total_deposits`
+    pub const BALANCE_EXCEEDS_DEPOSITS: u8 = 1 << 0;
+    /// `total_withdrawals > total_deposits`
+    pub const NEGATIVE_NET_BALANCE: u8 = 1 << 1;
+    /// `balance != total_deposits - total_withdrawals`
+    pub const BALANCE_MISMATCH: u8 = 1 << 2;
+    /// Address or stored bump is not the canonical `["vault", authority]` PDA
+    pub const NON_CANONICAL_PDA: u8 = 1 << 3;
+    /// `authority` is the default (all-zero) pubkey
+    pub const AUTHORITY_UNSET: u8 = 1 << 4;
+}
+
 // ============================================================================
 // ERRORS
 // ============================================================================
 
 #[error_code]
 pub enum ReinitError {
-    #[msg("Account is already initialized")]
-    AlreadyInitialized,
-    #[msg("Unauthorized")]
-    Unauthorized,
+    // 0-2: `AlreadyInitialized`, `Unauthorized`, `ZeroAmount` moved to `SecurityError`
+    #[msg("Address already holds lamports; create_account cannot use it - initialize with `init` instead")]
+    AddressPreFunded = 3,
+    #[msg("Instruction is disabled by the admin")]
+    InstructionDisabled,
+    #[msg("Too many disabled instructions")]
+    KillSwitchFull,
+    #[msg("Vault still holds a balance")]
+    VaultNotEmpty,
+    #[msg("New size must be larger than the account")]
+    InvalidSize,
+    #[msg("Account is allocated but was never initialized")]
+    NotInitialized,
+    #[msg("Account is not a VaultSecure")]
+    WrongAccountType,
+    #[msg("Signer is not the pending admin")]
+    NotPendingAdmin,
 }
 
+error_categories!(ReinitError {
+    Auth => [NotPendingAdmin],
+    Validation => [InvalidSize, WrongAccountType],
+    State => [AddressPreFunded, InstructionDisabled, KillSwitchFull, VaultNotEmpty, NotInitialized],
+});
+
 // ============================================================================
 // WHY ANCHOR'S `init` IS THE BEST SOLUTION
 // ============================================================================
@@ -19,6 +19,11 @@
 //! - Bypassed access controls
 
 use anchor_lang::prelude::*;
+use anchor_lang::system_program::{self, Transfer};
+use anchor_lang::Discriminator;
+use security_common::compute::require_compute_budget;
+use security_common::{error_categories, require_owned_by};
+pub use security_common::SecurityError;
 
 declare_id!("Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLnX");
 
@@ -94,6 +99,27 @@ pub mod type_cosplay {
         Ok(())
     }
 
+    /// SECURE (Manual): Takes a raw account, but type-checks it by hand.
+    /// 
+    /// ## What's Fixed?
+    /// `raw::try_deserialize_checked` makes the checks `Account<>` would:
+    /// the account is owned by this program and starts with
+    /// `AdminConfig::DISCRIMINATOR`. A UserAccount - or a forged layout
+    /// with no discriminator - is `TypeMismatch`, and an account of another
+    /// program `WrongOwnerProgram`, before any field is read.
+    pub fn admin_action_manual_secure(ctx: Context<AdminActionManualSecure>) -> Result<()> {
+        // SECURE: Owner and discriminator checked before deserializing
+        let config: AdminConfig = raw::try_deserialize_checked(&ctx.accounts.admin_config, ctx.program_id)?;
+        
+        require!(
+            ctx.accounts.signer.key() == config.admin,
+            TypeCosplayError::NotAdmin
+        );
+        
+        msg!("SECURE (manual): Admin action performed by verified admin");
+        Ok(())
+    }
+
     // ============================================================================
     // VULNERABILITY 2: SAME LAYOUT, DIFFERENT MEANING
     // ============================================================================
@@ -101,33 +127,60 @@ pub mod type_cosplay {
     /// VULNERABLE: Different account types with same layout.
     /// 
     /// ## What's Wrong?
-    /// UserVault and RewardVault have identical layouts.
+    /// UserAccount and RewardVault have identical layouts.
     /// Without discriminator checks, they're interchangeable.
     /// 
     /// ## Attack Scenario:
-    /// 1. Attacker creates UserVault with balance = 1000
-    /// 2. Attacker passes UserVault to claim_rewards_vulnerable
+    /// 1. Attacker creates UserAccount with balance = 1000
+    /// 2. Attacker passes UserAccount to claim_rewards_vulnerable
     /// 3. Program thinks it's a RewardVault with 1000 rewards available
     /// 4. Attacker claims 1000 tokens from reward pool
-    pub fn claim_rewards_vulnerable(ctx: Context<ClaimRewardsVulnerable>) -> Result<()> {
+    /// 
+    /// Returns the claimable amount through return data.
+    pub fn claim_rewards_vulnerable(ctx: Context<ClaimRewardsVulnerable>) -> Result<u64> {
         let data = ctx.accounts.vault.try_borrow_data()?;
         
-        // DANGER: No type check - could be UserVault or RewardVault!
+        // DANGER: No type check - could be UserAccount or RewardVault!
+        // The discriminator is skipped, never compared.
         // Both have: owner (32 bytes) + balance (8 bytes)
-        let _owner = Pubkey::try_from(&data[0..32]).unwrap();
-        let balance = u64::from_le_bytes(data[32..40].try_into().unwrap());
+        let _owner = Pubkey::try_from(&data[8..40]).unwrap();
+        let balance = u64::from_le_bytes(data[40..48].try_into().unwrap());
         
         msg!("VULNERABLE: Claiming {} rewards (but is this really a RewardVault?)", balance);
-        Ok(())
+        Ok(balance)
     }
 
     /// SECURE: Uses typed account that validates discriminator.
-    pub fn claim_rewards_secure(ctx: Context<ClaimRewardsSecure>) -> Result<()> {
+    /// 
+    /// Returns the claimable amount through return data.
+    pub fn claim_rewards_secure(ctx: Context<ClaimRewardsSecure>) -> Result<u64> {
         let vault = &ctx.accounts.reward_vault;
         
         // SECURE: This is definitely a RewardVault
         msg!("SECURE: Claiming {} rewards from verified RewardVault", vault.balance);
-        Ok(())
+        Ok(vault.balance)
+    }
+
+    /// SECURE: Checks by hand which program owns the vault before reading.
+    /// 
+    /// ## Why Both Checks?
+    /// A discriminator only names the struct: any program that declares a
+    /// `RewardVault` gets the same eight bytes. Another program - or the
+    /// attacker's own - can hold an account with this exact layout AND
+    /// this exact discriminator. Only the owner says whose `RewardVault`
+    /// it is; only the discriminator says it's a `RewardVault` rather
+    /// than a `UserAccount` of this program. Type confusion across
+    /// programs needs the first, within one program the second.
+    /// 
+    /// Returns the claimable amount through return data.
+    pub fn claim_rewards_owner_checked(ctx: Context<ClaimRewardsOwnerChecked>) -> Result<u64> {
+        // SECURE: Only this program can have written these bytes
+        // (`WrongOwnerProgram`), and it wrote them as a RewardVault
+        // (`TypeMismatch`)
+        let vault: RewardVault = raw::try_deserialize_checked(&ctx.accounts.vault, ctx.program_id)?;
+        
+        msg!("SECURE: Claiming {} rewards from a RewardVault this program owns", vault.balance);
+        Ok(vault.balance)
     }
 
     // ============================================================================
@@ -180,6 +233,143 @@ pub mod type_cosplay {
         Ok(())
     }
 
+    // ============================================================================
+    // VULNERABILITY 4: RESIZING WIPES THE DISCRIMINATOR
+    // ============================================================================
+
+    /// VULNERABLE: Grows a UserAccount by rebuilding its whole buffer.
+    /// 
+    /// ## What's Wrong?
+    /// After `realloc`, the buffer is zeroed "to start clean" and the old
+    /// state is written back with plain Borsh `serialize`. Borsh knows
+    /// nothing about discriminators, so the first 8 bytes stay zero and
+    /// the fields land where the discriminator used to be.
+    /// 
+    /// ## Result:
+    /// The account no longer loads as `UserAccount` (or anything else).
+    /// Every instruction taking `Account<'info, UserAccount>` now rejects
+    /// it, and any path that only checks the owner reads shifted garbage.
+    pub fn upgrade_account_vulnerable(ctx: Context<UpgradeAccountVulnerable>, new_len: u32) -> Result<()> {
+        let info = ctx.accounts.user_account.to_account_info();
+        let state = UserAccount::try_deserialize(&mut &info.try_borrow_data()?[..])?;
+        
+        upgrade::fund_rent(&info, new_len as usize, &ctx.accounts.owner, &ctx.accounts.system_program)?;
+        info.realloc(new_len as usize, false)?;
+        
+        // DANGER: Zeroes the discriminator along with everything else,
+        // then writes the fields back without one
+        let mut data = info.try_borrow_mut_data()?;
+        data.fill(0);
+        state.serialize(&mut &mut data[..])?;
+        
+        msg!("VULNERABLE: Grew account to {} bytes", new_len);
+        Ok(())
+    }
+
+    /// SECURE: Grows a UserAccount, keeping its discriminator in place.
+    /// 
+    /// ## What's Fixed?
+    /// `upgrade::grow_preserving_discriminator` only touches the bytes that
+    /// didn't exist before: `realloc` keeps the existing data, and only the
+    /// new tail is zeroed. The discriminator and existing fields are left
+    /// exactly where they were.
+    pub fn upgrade_account(ctx: Context<UpgradeAccount>, new_len: u32) -> Result<()> {
+        upgrade::grow_preserving_discriminator(
+            &ctx.accounts.user_account.to_account_info(),
+            new_len as usize,
+            &ctx.accounts.owner,
+            &ctx.accounts.system_program,
+        )?;
+        
+        msg!("SECURE: Grew account to {} bytes", new_len);
+        Ok(())
+    }
+
+    // ============================================================================
+    // VULNERABILITY 5: TAG BYTE READ FROM AN UNCHECKED ACCOUNT
+    // ============================================================================
+
+    /// VULNERABLE: Trusts the `kind` tag of a raw account.
+    /// 
+    /// ## What's Wrong?
+    /// `TaggedAccount` stores several logical types in one account type,
+    /// told apart by `kind`. Reading that tag from an `UncheckedAccount`
+    /// trusts whoever wrote the bytes - and nothing checks that it was
+    /// this program.
+    /// 
+    /// ## Attack Scenario:
+    /// 1. Attacker copies their own User `TaggedAccount`
+    /// 2. Flips the tag byte (offset 8) from `User` to `Admin`
+    /// 3. Stores the bytes in an account they own
+    /// 4. Passes it here with `expected = Admin` - admin access granted
+    pub fn process_tagged_vulnerable(ctx: Context<ProcessTaggedVulnerable>, expected: AccountKind) -> Result<()> {
+        let data = ctx.accounts.tagged.try_borrow_data()?;
+        
+        // DANGER: Tag and owner read from bytes anyone could have written
+        let kind = AccountKind::try_from_slice(&data[8..9])?;
+        let owner = Pubkey::try_from(&data[9..41]).unwrap();
+        
+        require_keys_eq!(owner, ctx.accounts.owner.key(), TypeCosplayError::NotAdmin);
+        require!(kind == expected, TypeCosplayError::UnexpectedKind);
+        
+        msg!("VULNERABLE: Processing tagged account as {:?}", kind);
+        Ok(())
+    }
+
+    /// SECURE: Matches on `kind` after `Account<>` has validated the account.
+    /// 
+    /// ## What's Fixed?
+    /// The owner and discriminator checks prove this program wrote the
+    /// account, so `kind` is the tag it was created with - a flipped byte
+    /// can only live in an account the attacker owns, and that is
+    /// rejected before the tag is read. A tag other than `expected` is
+    /// `UnexpectedKind`.
+    pub fn process_tagged_secure(ctx: Context<ProcessTaggedSecure>, expected: AccountKind) -> Result<()> {
+        let tagged = &ctx.accounts.tagged;
+        
+        // SECURE: Trusted tag, compared against what the caller needs
+        require!(tagged.kind == expected, TypeCosplayError::UnexpectedKind);
+        
+        match tagged.kind {
+            AccountKind::User => msg!("SECURE: Processing user {}", tagged.owner),
+            AccountKind::Admin => msg!("SECURE: Processing admin {}", tagged.owner),
+            AccountKind::Reward => msg!("SECURE: Processing reward account of {}", tagged.owner),
+        }
+        Ok(())
+    }
+
+    // ============================================================================
+    // BATCH MIGRATION
+    // ============================================================================
+
+    /// SECURE: Brings every StakeAccount in `remaining_accounts` up to
+    /// `StakeAccount::CURRENT_VERSION`.
+    /// 
+    /// Each account is loaded as `Account<'info, StakeAccount>`, so a
+    /// foreign account - wrong owner or wrong discriminator - fails the
+    /// whole batch instead of being rewritten. Accounts already at the
+    /// current version are left untouched, which makes the batch
+    /// idempotent: re-sending it, or overlapping it with another batch,
+    /// can't apply a step twice. Anyone may call it. Returns the number of
+    /// accounts migrated.
+    pub fn migrate_batch<'info>(ctx: Context<'_, '_, 'info, 'info, MigrateBatch>) -> Result<u32> {
+        require_compute_budget(ctx.remaining_accounts.len(), MIGRATE_UNITS_PER_ACCOUNT)?;
+        
+        let mut migrated = 0u32;
+        for info in ctx.remaining_accounts.iter() {
+            require!(info.is_writable, TypeCosplayError::InvalidAccountType);
+            let mut stake = Account::<StakeAccount>::try_from(info)?;
+            
+            if stake.migrate()? {
+                stake.exit(ctx.program_id)?;
+                migrated += 1;
+            }
+        }
+        
+        msg!("SECURE: Migrated {} of {} stake accounts", migrated, ctx.remaining_accounts.len());
+        Ok(migrated)
+    }
+
     // ============================================================================
     // INITIALIZATION
     // ============================================================================
@@ -199,6 +389,27 @@ pub mod type_cosplay {
         Ok(())
     }
 
+    /// Opens a stake account at the current version; `amount` is in base
+    /// units.
+    pub fn initialize_stake_account(ctx: Context<InitializeStakeAccount>, amount: u64) -> Result<()> {
+        let stake = &mut ctx.accounts.stake_account;
+        stake.owner = ctx.accounts.owner.key();
+        stake.version = StakeAccount::CURRENT_VERSION;
+        stake.amount = amount;
+        stake.bump = ctx.bumps.stake_account;
+        Ok(())
+    }
+
+    /// Opens the caller's `TaggedAccount` as a `User`; only the program
+    /// sets `kind`.
+    pub fn initialize_tagged_account(ctx: Context<InitializeTaggedAccount>) -> Result<()> {
+        let tagged = &mut ctx.accounts.tagged;
+        tagged.kind = AccountKind::User;
+        tagged.owner = ctx.accounts.owner.key();
+        tagged.bump = ctx.bumps.tagged;
+        Ok(())
+    }
+
     pub fn initialize_reward_vault(ctx: Context<InitializeRewardVault>, initial_balance: u64) -> Result<()> {
         let vault = &mut ctx.accounts.reward_vault;
         vault.authority = ctx.accounts.authority.key();
@@ -227,9 +438,17 @@ pub struct AdminActionVulnerable<'info> {
     pub signer: Signer<'info>,
 }
 
+#[derive(Accounts)]
+pub struct AdminActionManualSecure<'info> {
+    /// CHECK: Type-checked in the handler by `raw::try_deserialize_checked`
+    pub admin_config: UncheckedAccount<'info>,
+    
+    pub signer: Signer<'info>,
+}
+
 #[derive(Accounts)]
 pub struct ClaimRewardsVulnerable<'info> {
-    /// VULNERABLE: Could be UserVault or RewardVault
+    /// VULNERABLE: Could be UserAccount or RewardVault
     /// 
     /// Both types have identical layouts:
     /// - owner/authority: Pubkey (32 bytes)
@@ -241,6 +460,14 @@ pub struct ClaimRewardsVulnerable<'info> {
     pub user: Signer<'info>,
 }
 
+#[derive(Accounts)]
+pub struct ClaimRewardsOwnerChecked<'info> {
+    /// CHECK: Owner and discriminator checked in the handler
+    pub vault: UncheckedAccount<'info>,
+    
+    pub user: Signer<'info>,
+}
+
 #[derive(Accounts)]
 pub struct ProcessAccountVulnerable<'info> {
     /// VULNERABLE: Manual type field can be spoofed
@@ -249,6 +476,35 @@ pub struct ProcessAccountVulnerable<'info> {
     pub account: UncheckedAccount<'info>,
 }
 
+#[derive(Accounts)]
+pub struct UpgradeAccountVulnerable<'info> {
+    /// Raw so the handler can rewrite the whole buffer
+    /// 
+    /// CHECK: Intentionally insecure for demonstration
+    #[account(
+        mut,
+        owner = crate::ID,
+        seeds = [b"user", owner.key().as_ref()],
+        bump,
+    )]
+    pub user_account: UncheckedAccount<'info>,
+    
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ProcessTaggedVulnerable<'info> {
+    /// VULNERABLE: Tag byte read without owner or discriminator check
+    /// 
+    /// CHECK: Intentionally insecure for demonstration
+    pub tagged: UncheckedAccount<'info>,
+    
+    pub owner: Signer<'info>,
+}
+
 // ============================================================================
 // SECURE ACCOUNT STRUCTURES
 // ============================================================================
@@ -273,7 +529,7 @@ pub struct AdminActionSecure<'info> {
 
 #[derive(Accounts)]
 pub struct ClaimRewardsSecure<'info> {
-    /// SECURE: Specifically RewardVault, not UserVault
+    /// SECURE: Specifically RewardVault, not UserAccount
     #[account(
         seeds = [b"reward_vault", reward_vault.authority.as_ref()],
         bump = reward_vault.bump,
@@ -303,6 +559,41 @@ pub struct ProcessAdminSecure<'info> {
     pub admin_config: Account<'info, AdminConfig>,
 }
 
+#[derive(Accounts)]
+pub struct UpgradeAccount<'info> {
+    #[account(
+        mut,
+        seeds = [b"user", owner.key().as_ref()],
+        bump = user_account.bump,
+        has_one = owner,
+    )]
+    pub user_account: Account<'info, UserAccount>,
+    
+    /// Pays for the extra rent
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ProcessTaggedSecure<'info> {
+    /// SECURE: Owner and discriminator checked, so `kind` is trusted
+    #[account(
+        seeds = [b"tagged", owner.key().as_ref()],
+        bump = tagged.bump,
+        has_one = owner,
+    )]
+    pub tagged: Account<'info, TaggedAccount>,
+    
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateBatch {
+    // remaining_accounts: writable StakeAccounts, any version
+}
+
 #[derive(Accounts)]
 pub struct InitializeAdminConfig<'info> {
     #[account(
@@ -354,6 +645,40 @@ pub struct InitializeRewardVault<'info> {
     pub system_program: Program<'info, System>,
 }
 
+#[derive(Accounts)]
+pub struct InitializeTaggedAccount<'info> {
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + TaggedAccount::INIT_SPACE,
+        seeds = [b"tagged", owner.key().as_ref()],
+        bump
+    )]
+    pub tagged: Account<'info, TaggedAccount>,
+    
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeStakeAccount<'info> {
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + StakeAccount::INIT_SPACE,
+        seeds = [b"stake", owner.key().as_ref()],
+        bump
+    )]
+    pub stake_account: Account<'info, StakeAccount>,
+    
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
 // ============================================================================
 // STATE
 // ============================================================================
@@ -394,6 +719,66 @@ pub struct RewardVault {
     pub bump: u8,           // 1 byte
 }
 
+/// Logical type of a `TaggedAccount`
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AccountKind {
+    User,
+    Admin,
+    Reward,
+}
+
+/// One account type holding several logical types, told apart by `kind`
+/// 
+/// `kind` is the first field, so it is the byte right after the
+/// discriminator.
+#[account]
+#[derive(InitSpace)]
+pub struct TaggedAccount {
+    pub kind: AccountKind,   // 1 byte
+    pub owner: Pubkey,       // 32 bytes
+    pub bump: u8,            // 1 byte
+}
+
+/// Staked balance with a layout version
+/// 
+/// Version 1 stored `amount` in whole tokens; version 2 stores base units
+/// (`STAKE_BASE_UNITS` per token). Migrating an account twice would scale
+/// it twice, so a step only ever runs on accounts behind it.
+#[account]
+#[derive(InitSpace)]
+pub struct StakeAccount {
+    pub owner: Pubkey,
+    pub version: u8,
+    pub amount: u64,
+    pub bump: u8,
+}
+
+/// Base units per whole token, applied by the version 1 -> 2 migration
+pub const STAKE_BASE_UNITS: u64 = 1_000_000;
+
+/// Compute budgeted per `migrate_batch` account: deserialize, scale,
+/// serialize
+pub const MIGRATE_UNITS_PER_ACCOUNT: u64 = 5_000;
+
+impl StakeAccount {
+    pub const CURRENT_VERSION: u8 = 2;
+
+    /// Applies every migration step between the stored version and
+    /// `CURRENT_VERSION`. Returns whether anything changed.
+    pub fn migrate(&mut self) -> Result<bool> {
+        if self.version >= Self::CURRENT_VERSION {
+            return Ok(false);
+        }
+        if self.version < 2 {
+            self.amount = self.amount
+                .checked_mul(STAKE_BASE_UNITS)
+                .ok_or(TypeCosplayError::MigrationOverflow)?;
+        }
+        self.version = Self::CURRENT_VERSION;
+        Ok(true)
+    }
+}
+
 // ============================================================================
 // ERRORS
 // ============================================================================
@@ -406,6 +791,131 @@ pub enum TypeCosplayError {
     InvalidAccountType,
     #[msg("Account type mismatch")]
     TypeMismatch,
+    #[msg("Accounts can only grow, and must keep room for a discriminator")]
+    InvalidAccountSize,
+    #[msg("Migrated value does not fit the new layout")]
+    MigrationOverflow,
+    #[msg("Tagged account is not of the expected kind")]
+    UnexpectedKind,
+    #[msg("Account is not owned by the expected program")]
+    WrongOwnerProgram,
+}
+
+error_categories!(TypeCosplayError {
+    Auth => [NotAdmin],
+    Math => [MigrationOverflow],
+    Validation => [
+        InvalidAccountType, TypeMismatch, InvalidAccountSize, UnexpectedKind, WrongOwnerProgram,
+    ],
+});
+
+// ============================================================================
+// READING RAW ACCOUNTS
+// ============================================================================
+
+pub mod raw {
+    use super::*;
+
+    /// Deserializes `T` from a raw account with the checks
+    /// `Account<'info, T>` makes: owned by `program_id`, and starting with
+    /// `T::DISCRIMINATOR`.
+    /// 
+    /// The owner is checked by `security_common::require_owned_by`: the
+    /// wrong owner is `WrongOwnerProgram`, and an account too short for a
+    /// discriminator is `AccountDiscriminatorNotFound`. Another type's
+    /// discriminator, or bytes that don't deserialize, are `TypeMismatch`.
+    pub fn try_deserialize_checked<T>(info: &AccountInfo, program_id: &Pubkey) -> Result<T>
+    where
+        T: AccountDeserialize + Discriminator,
+    {
+        require_owned_by(info, program_id, TypeCosplayError::WrongOwnerProgram)?;
+        
+        let data = info.try_borrow_data()?;
+        require!(data[..8] == T::DISCRIMINATOR, TypeCosplayError::TypeMismatch);
+        
+        T::try_deserialize_unchecked(&mut &data[..]).map_err(|_| error!(TypeCosplayError::TypeMismatch))
+    }
+}
+
+// ============================================================================
+// READING OTHER PROGRAMS' ACCOUNTS
+// ============================================================================
+
+pub mod external {
+    use super::*;
+
+    /// Deserializes `T` from an account that belongs to another Anchor
+    /// program, after checking it is owned by `program_id` and starts with
+    /// `T`'s discriminator.
+    /// 
+    /// `Account<'info, T>` checks the owner against the program that
+    /// declared `T`. When a sibling program's type is mirrored locally
+    /// (same name, same fields - so the same discriminator), that owner
+    /// would be THIS program, and the sibling's real accounts would be
+    /// rejected. Pass the sibling's ID explicitly instead of trusting
+    /// whoever owns the account.
+    /// 
+    /// The checks are `raw::try_deserialize_checked`'s, errors included.
+    pub fn read_external_anchor_account<T>(info: &AccountInfo, program_id: &Pubkey) -> Result<T>
+    where
+        T: AccountDeserialize + Discriminator,
+    {
+        raw::try_deserialize_checked(info, program_id)
+    }
+}
+
+// ============================================================================
+// RESIZING ACCOUNTS
+// ============================================================================
+
+pub mod upgrade {
+    use super::*;
+
+    /// Grows `info` to `new_len` without disturbing what's already there.
+    /// 
+    /// `realloc` leaves the existing bytes, discriminator included, where
+    /// they are; only `[old_len..new_len]` is zeroed - the bytes that are
+    /// new to this instruction. `payer` covers any extra rent.
+    pub fn grow_preserving_discriminator<'info>(
+        info: &AccountInfo<'info>,
+        new_len: usize,
+        payer: &Signer<'info>,
+        system_program: &Program<'info, System>,
+    ) -> Result<()> {
+        let old_len = info.data_len();
+        require!(
+            old_len >= 8 && new_len >= old_len,
+            TypeCosplayError::InvalidAccountSize
+        );
+        
+        fund_rent(info, new_len, payer, system_program)?;
+        info.realloc(new_len, false)?;
+        
+        info.try_borrow_mut_data()?[old_len..].fill(0);
+        Ok(())
+    }
+
+    /// Tops `info` up to the rent-exempt minimum for `new_len` bytes.
+    pub fn fund_rent<'info>(
+        info: &AccountInfo<'info>,
+        new_len: usize,
+        payer: &Signer<'info>,
+        system_program: &Program<'info, System>,
+    ) -> Result<()> {
+        let shortfall = Rent::get()?
+            .minimum_balance(new_len)
+            .saturating_sub(info.lamports());
+        if shortfall > 0 {
+            system_program::transfer(
+                CpiContext::new(
+                    system_program.to_account_info(),
+                    Transfer { from: payer.to_account_info(), to: info.clone() },
+                ),
+                shortfall,
+            )?;
+        }
+        Ok(())
+    }
 }
 
 // ============================================================================
@@ -437,7 +947,7 @@ pub enum TypeCosplayError {
 //    - Gain admin privileges
 //
 // 2. ASSET THEFT
-//    - Pass UserVault as RewardVault
+//    - Pass UserAccount as RewardVault
 //    - Claim rewards you don't own
 //
 // 3. DATA MANIPULATION
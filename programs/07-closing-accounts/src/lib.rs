@@ -16,6 +16,11 @@
 //! - This creates a window for attacks
 
 use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Mint, MintTo, Token, TokenAccount};
+use security_common::compute::require_compute_budget;
+use security_common::ensure_authority;
+use security_common::{checked_close, error_categories, Tombstone};
+pub use security_common::SecurityError;
 
 declare_id!("Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLnY");
 
@@ -35,15 +40,16 @@ pub mod closing_accounts {
     /// 1. Close account (get lamports back)
     /// 2. Re-fund account in same transaction
     /// 3. Use the "closed" account again
-    /// 4. Repeat to drain funds or double-spend
+    /// 4. Close it again - the close never sticks
     /// 
     /// ## Attack Scenario:
     /// 1. User has UserAccount with 1000 token rewards accrued
-    /// 2. Attacker calls close_vulnerable to claim rewards
+    /// 2. Attacker calls close_vulnerable; the rent goes to the recipient
     /// 3. In same TX, attacker re-funds account to prevent garbage collection
     /// 4. Attacker calls claim_rewards (account still has rewards data!)
-    /// 5. Attacker claims rewards AGAIN
-    /// 6. Repeat until reward pool is drained
+    /// 5. Attacker closes it again and collects the rent again
+    /// 6. Repeat: the rent is paid out on every loop, and the "closed"
+    ///    account outlives every close with all of its data
     pub fn close_vulnerable(ctx: Context<CloseVulnerable>) -> Result<()> {
         let user_account = &ctx.accounts.user_account;
         let recipient = &ctx.accounts.recipient;
@@ -71,11 +77,37 @@ pub mod closing_accounts {
     /// Zeroing data prevents revival attacks because:
     /// - Even if account is re-funded, data is gone
     /// - Discriminator is zeroed, so deserialization fails
+    /// 
+    /// A tombstone records who performed the close so post-mortems can
+    /// attribute it, even after the account itself is gone. Tombstones
+    /// are numbered by `user_closes`, so an account the owner initializes
+    /// again can be closed again without touching the earlier record.
+    /// 
+    /// Each close also counts against the recipient's
+    /// `ClosesByRecipient` window, so one wallet can't farm rent by
+    /// sweeping many accounts in quick succession.
     pub fn close_secure(ctx: Context<CloseSecure>) -> Result<()> {
         // SECURE: Anchor's `close` constraint handles everything
         // - Lamports transferred to recipient
         // - Data zeroed
         // - Owner set to System Program
+        let now = Clock::get()?.unix_timestamp;
+        record_close(&mut ctx.accounts.closes, &ctx.accounts.config, now)?;
+        
+        // The `close` constraint runs after this handler, so the rent is
+        // still in the account here
+        let user_account = &ctx.accounts.user_account;
+        let tombstone = &mut ctx.accounts.tombstone;
+        tombstone.record = Tombstone {
+            account: user_account.key(),
+            original_owner: user_account.owner,
+            closed_by: ctx.accounts.owner.key(),
+            recipient: ctx.accounts.recipient.key(),
+            lamports: user_account.to_account_info().lamports(),
+            closed_at: now,
+        };
+        tombstone.bump = ctx.bumps.tombstone;
+        count_close(&mut ctx.accounts.user_closes, ctx.bumps.user_closes)?;
         
         msg!("SECURE: Account closed with data zeroed");
         Ok(())
@@ -110,8 +142,9 @@ pub mod closing_accounts {
 
     /// SECURE: Verifies signer is the account owner.
     pub fn close_with_auth_check(ctx: Context<CloseWithAuthCheck>) -> Result<()> {
-        // SECURE: `has_one = owner` constraint verifies ownership
-        // Only the owner can close their account
+        // SECURE: The closer must have signed AND be the stored owner.
+        // The `close` constraint only runs once the handler succeeds
+        ensure_authority!(ctx.accounts.user_account.owner, ctx.accounts.owner);
         
         msg!("SECURE: Account closed by verified owner");
         Ok(())
@@ -137,7 +170,7 @@ pub mod closing_accounts {
     pub fn read_config_vulnerable(ctx: Context<ReadConfigVulnerable>) -> Result<()> {
         // DANGER: Not checking if account has been defunded!
         let config_info = &ctx.accounts.config;
-        let data = config_info.try_borrow_data()?;
+        let _data = config_info.try_borrow_data()?;
         
         msg!("VULNERABLE: Reading config without rent check");
         Ok(())
@@ -168,7 +201,7 @@ pub mod closing_accounts {
     /// 2. User claims airdrop, admin closes their profile (marking as claimed)
     /// 3. User re-initializes profile PDA (same seeds still valid!)
     /// 4. User claims airdrop again
-    pub fn close_profile_vulnerable(ctx: Context<CloseProfileVulnerable>) -> Result<()> {
+    pub fn close_profile_vulnerable(_ctx: Context<CloseProfileVulnerable>) -> Result<()> {
         // DANGER: Just closing isn't enough for PDAs!
         // PDA can be recreated with same seeds
         
@@ -182,18 +215,123 @@ pub mod closing_accounts {
     /// - Set a tombstone flag before closing
     /// - Future init checks for tombstone in separate account
     /// - Or use unique seeds that include timestamp/nonce
+    /// 
+    /// The tombstone also keeps the profile's points, so `reopen` can
+    /// restore them if the close turns out to be a mistake.
     pub fn close_profile_secure(ctx: Context<CloseProfileSecure>) -> Result<()> {
         let profile = &mut ctx.accounts.profile;
         let tombstone = &mut ctx.accounts.tombstone;
         
-        // SECURE: Create permanent record that this profile was closed
+        // SECURE: Record that this profile was closed and who closed it.
+        // The tombstone is created with `init` and no instruction mutates
+        // it; `reopen` and `recreate_profile` delete it, so the close is
+        // attributable until the profile comes back.
         tombstone.original_owner = profile.owner;
+        tombstone.closed_by = ctx.accounts.owner.key();
         tombstone.closed_at = Clock::get()?.unix_timestamp;
+        tombstone.points = profile.points;
+        tombstone.bump = ctx.bumps.tombstone;
         
         msg!("SECURE: Profile closed with tombstone record");
         Ok(())
     }
 
+    /// SECURE: Recreates a closed profile once its cooldown has passed.
+    /// 
+    /// Models "one airdrop per period" rather than "one ever": the tombstone
+    /// blocks `initialize_profile` until `config.profile_cooldown_secs` have
+    /// elapsed since `closed_at`, then this instruction deletes it and opens
+    /// a fresh profile. Recreation is allowed exactly at the boundary.
+    pub fn recreate_profile(ctx: Context<RecreateProfile>) -> Result<()> {
+        let reopens_at = ctx.accounts.tombstone.closed_at
+            .checked_add(ctx.accounts.config.profile_cooldown_secs)
+            .ok_or(SecurityError::Overflow)?;
+        require!(
+            Clock::get()?.unix_timestamp >= reopens_at,
+            CloseError::CooldownActive
+        );
+        
+        // The tombstone itself is closed by the `close = owner` constraint
+        let profile = &mut ctx.accounts.profile;
+        profile.owner = ctx.accounts.owner.key();
+        profile.points = 0;
+        profile.bump = ctx.bumps.profile;
+        
+        msg!("SECURE: Profile recreated after cooldown, tombstone deleted");
+        Ok(())
+    }
+
+    /// SECURE: Undoes an accidental profile close within a grace period.
+    /// 
+    /// Inside `config.reopen_window_secs` of `closed_at`, the owner can get
+    /// their profile back as it was: the points saved on the tombstone are
+    /// restored and the tombstone is deleted. The window ends AT
+    /// `closed_at + reopen_window_secs` - from that second on the close is
+    /// final, and only `recreate_profile` (a fresh, empty profile after the
+    /// cooldown) remains. A window of 0 disables reopening.
+    /// 
+    /// Only the original owner can reopen: the tombstone is found through
+    /// the signer's own seeds.
+    pub fn reopen(ctx: Context<Reopen>) -> Result<()> {
+        let tombstone = &ctx.accounts.tombstone;
+        let window_ends_at = tombstone.closed_at
+            .checked_add(ctx.accounts.config.reopen_window_secs)
+            .ok_or(SecurityError::Overflow)?;
+        require!(
+            Clock::get()?.unix_timestamp < window_ends_at,
+            CloseError::ReopenWindowClosed
+        );
+        
+        // The tombstone itself is closed by the `close = owner` constraint
+        let profile = &mut ctx.accounts.profile;
+        profile.owner = tombstone.original_owner;
+        profile.points = tombstone.points;
+        profile.bump = ctx.bumps.profile;
+        
+        msg!("SECURE: Profile reopened with {} points, tombstone deleted", profile.points);
+        Ok(())
+    }
+    
+    // ============================================================================
+    // REFERENCE: VERIFIED CLOSE
+    // ============================================================================
+    
+    /// SECURE: The reference way to close a UserAccount.
+    /// 
+    /// Combines every lesson above:
+    /// 1. `has_one = owner` - only the owner can close
+    /// 2. `balance` and `rewards_accrued` must both be zero, so closing
+    ///    can't destroy funds the owner (or anyone else) is still owed
+    /// 3. The recipient's close window is charged, as in `close_secure`
+    /// 4. `checked_close` returns the rent, drops the data and verifies
+    ///    both, then emits `AccountClosed` so indexers can drop the account
+    /// 5. The tombstone it returns records who closed the account, where
+    ///    the rent went and when, numbered as in `close_secure`
+    /// 
+    /// Owners with rewards outstanding call `claim_rewards` first.
+    pub fn safe_close(ctx: Context<SafeClose>) -> Result<()> {
+        let user_account = &ctx.accounts.user_account;
+        require!(user_account.balance == 0, CloseError::BalanceNotEmpty);
+        require!(user_account.rewards_accrued == 0, CloseError::RewardsNotClaimed);
+        
+        let now = Clock::get()?.unix_timestamp;
+        record_close(&mut ctx.accounts.closes, &ctx.accounts.config, now)?;
+        
+        let record = checked_close(
+            &user_account.to_account_info(),
+            &ctx.accounts.recipient,
+            user_account.owner,
+            ctx.accounts.owner.key(),
+        )?;
+        let tombstone = &mut ctx.accounts.tombstone;
+        tombstone.record = record;
+        tombstone.bump = ctx.bumps.tombstone;
+        count_close(&mut ctx.accounts.user_closes, ctx.bumps.user_closes)?;
+        
+        msg!("SECURE: Empty account closed with tombstone");
+        Ok(())
+    }
+
     // ============================================================================
     // HELPER INSTRUCTIONS
     // ============================================================================
@@ -207,14 +345,44 @@ pub mod closing_accounts {
         Ok(())
     }
 
-    pub fn initialize_config(ctx: Context<InitializeConfig>, fee_bps: u16) -> Result<()> {
+    pub fn initialize_config(
+        ctx: Context<InitializeConfig>,
+        fee_bps: u16,
+        profile_cooldown_secs: i64,
+        reopen_window_secs: i64,
+        max_closes_per_window: u32,
+        close_window_secs: i64,
+    ) -> Result<()> {
+        require!(profile_cooldown_secs >= 0, CloseError::InvalidCooldown);
+        require!(reopen_window_secs >= 0, CloseError::InvalidReopenWindow);
+        require!(
+            max_closes_per_window > 0 && close_window_secs > 0,
+            CloseError::InvalidCloseLimit
+        );
+        
         let config = &mut ctx.accounts.config;
         config.admin = ctx.accounts.admin.key();
         config.fee_bps = fee_bps;
+        config.profile_cooldown_secs = profile_cooldown_secs;
+        config.reopen_window_secs = reopen_window_secs;
+        config.max_closes_per_window = max_closes_per_window;
+        config.close_window_secs = close_window_secs;
+        config.reward_mint = ctx.accounts.reward_mint.key();
+        config.reward_authority_bump = ctx.bumps.reward_authority;
         config.bump = ctx.bumps.config;
         Ok(())
     }
 
+    /// Opens the close counter for `recipient`. Anyone may pay for it.
+    pub fn initialize_close_counter(ctx: Context<InitializeCloseCounter>) -> Result<()> {
+        let closes = &mut ctx.accounts.closes;
+        closes.recipient = ctx.accounts.recipient.key();
+        closes.count = 0;
+        closes.window_start = 0;
+        closes.bump = ctx.bumps.closes;
+        Ok(())
+    }
+
     pub fn initialize_profile(ctx: Context<InitializeProfile>) -> Result<()> {
         let profile = &mut ctx.accounts.profile;
         profile.owner = ctx.accounts.owner.key();
@@ -223,21 +391,114 @@ pub mod closing_accounts {
         Ok(())
     }
 
-    pub fn accrue_rewards(ctx: Context<AccrueRewards>, amount: u64) -> Result<()> {
-        let user_account = &mut ctx.accounts.user_account;
-        user_account.rewards_accrued = user_account.rewards_accrued.checked_add(amount).unwrap();
+    /// SECURE: Admin-gated batch accrual.
+    /// 
+    /// Each entry is paired positionally with one `UserAccount` in
+    /// `remaining_accounts`. Every target is validated before it is touched:
+    /// 1. Owned by this program with the `UserAccount` discriminator
+    /// 2. Address is the canonical `["user", owner]` PDA
+    /// 3. Stored owner matches the entry (`has_one` equivalent)
+    /// 
+    /// Any failure - including a length mismatch between entries and
+    /// accounts - aborts the whole transaction, so no partial batch lands.
+    /// A batch too big for the remaining compute budget fails up front
+    /// with `ComputeBudgetLow` rather than running out part way.
+    pub fn accrue_rewards_batch<'info>(
+        ctx: Context<'_, '_, 'info, 'info, AccrueRewardsBatch<'info>>,
+        entries: Vec<AccrualEntry>,
+    ) -> Result<()> {
+        require!(
+            entries.len() == ctx.remaining_accounts.len(),
+            CloseError::BatchLengthMismatch
+        );
+        require_compute_budget(entries.len(), ACCRUE_UNITS_PER_ACCOUNT)?;
+        
+        for (entry, info) in entries.iter().zip(ctx.remaining_accounts.iter()) {
+            require!(info.is_writable, CloseError::InvalidUserAccount);
+            
+            // Owner + discriminator check
+            let mut user_account = Account::<UserAccount>::try_from(info)?;
+            
+            // PDA check
+            let expected = Pubkey::create_program_address(
+                &[b"user", user_account.owner.as_ref(), &[user_account.bump]],
+                ctx.program_id,
+            )
+            .map_err(|_| CloseError::InvalidUserAccount)?;
+            require_keys_eq!(expected, info.key(), CloseError::InvalidUserAccount);
+            
+            // Relationship check
+            require_keys_eq!(user_account.owner, entry.owner, CloseError::InvalidUserAccount);
+            
+            user_account.rewards_accrued = user_account.rewards_accrued
+                .checked_add(entry.amount)
+                .ok_or(SecurityError::Overflow)?;
+            user_account.exit(ctx.program_id)?;
+        }
+        
+        msg!("SECURE: Accrued rewards for {} accounts", entries.len());
         Ok(())
     }
 
+    /// Mints the accrued rewards to the owner's token account.
+    /// 
+    /// `rewards_accrued` is zeroed before the CPI so the state change can't be
+    /// skipped, and the mint authority is a PDA only this program can sign for.
     pub fn claim_rewards(ctx: Context<ClaimRewards>) -> Result<u64> {
         let user_account = &mut ctx.accounts.user_account;
         let rewards = user_account.rewards_accrued;
         user_account.rewards_accrued = 0;
+        
+        if rewards > 0 {
+            let seeds = &[
+                b"reward_authority".as_ref(),
+                &[ctx.accounts.config.reward_authority_bump],
+            ];
+            let signer_seeds = &[&seeds[..]];
+            
+            let cpi_ctx = CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                MintTo {
+                    mint: ctx.accounts.reward_mint.to_account_info(),
+                    to: ctx.accounts.user_token_account.to_account_info(),
+                    authority: ctx.accounts.reward_authority.to_account_info(),
+                },
+                signer_seeds,
+            );
+            token::mint_to(cpi_ctx, rewards)?;
+        }
+        
         msg!("Claimed {} rewards", rewards);
         Ok(rewards)
     }
 }
 
+/// Moves `user_closes` on to the next tombstone
+fn count_close(user_closes: &mut UserCloses, bump: u8) -> Result<()> {
+    user_closes.count = user_closes.count.checked_add(1).ok_or(SecurityError::Overflow)?;
+    user_closes.bump = bump;
+    Ok(())
+}
+
+/// Counts one close against `closes.recipient`, starting a fresh window
+/// once `config.close_window_secs` have passed since the last one began.
+fn record_close(closes: &mut ClosesByRecipient, config: &Config, now: i64) -> Result<()> {
+    let window_ends = closes.window_start
+        .checked_add(config.close_window_secs)
+        .ok_or(SecurityError::Overflow)?;
+    if now >= window_ends {
+        closes.window_start = now;
+        closes.count = 0;
+    }
+    
+    require!(
+        closes.count < config.max_closes_per_window,
+        CloseError::CloseRateLimited
+    );
+    closes.count += 1;
+    Ok(())
+}
+
 // ============================================================================
 // VULNERABLE ACCOUNT STRUCTURES
 // ============================================================================
@@ -317,22 +578,112 @@ pub struct CloseSecure<'info> {
     )]
     pub user_account: Account<'info, UserAccount>,
     
+    /// Numbers this close; it outlives the account, so a recreated
+    /// account continues the count
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = 8 + UserCloses::INIT_SPACE,
+        seeds = [b"user_closes", user_account.key().as_ref()],
+        bump
+    )]
+    pub user_closes: Account<'info, UserCloses>,
+    
+    /// SECURE: Records who closed the account
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + AccountTombstone::INIT_SPACE,
+        seeds = [b"user_tombstone", user_account.key().as_ref(), &user_closes.count.to_le_bytes()],
+        bump
+    )]
+    pub tombstone: Account<'info, AccountTombstone>,
+    
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, Config>,
+    
+    /// SECURE: Rate-limits closes paying out to `recipient`
+    #[account(
+        mut,
+        seeds = [b"closes", recipient.key().as_ref()],
+        bump = closes.bump,
+    )]
+    pub closes: Account<'info, ClosesByRecipient>,
+    
+    /// CHECK: Receives the rent lamports
+    #[account(mut)]
+    pub recipient: AccountInfo<'info>,
+    
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+/// Same accounts as `CloseSecure`; the emptiness checks and the close
+/// itself live in the handler
+#[derive(Accounts)]
+pub struct SafeClose<'info> {
+    #[account(
+        mut,
+        seeds = [b"user", user_account.owner.as_ref()],
+        bump = user_account.bump,
+        has_one = owner,
+    )]
+    pub user_account: Account<'info, UserAccount>,
+    
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = 8 + UserCloses::INIT_SPACE,
+        seeds = [b"user_closes", user_account.key().as_ref()],
+        bump
+    )]
+    pub user_closes: Account<'info, UserCloses>,
+    
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + AccountTombstone::INIT_SPACE,
+        seeds = [b"user_tombstone", user_account.key().as_ref(), &user_closes.count.to_le_bytes()],
+        bump
+    )]
+    pub tombstone: Account<'info, AccountTombstone>,
+    
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, Config>,
+    
+    #[account(
+        mut,
+        seeds = [b"closes", recipient.key().as_ref()],
+        bump = closes.bump,
+    )]
+    pub closes: Account<'info, ClosesByRecipient>,
+    
     /// CHECK: Receives the rent lamports
     #[account(mut)]
     pub recipient: AccountInfo<'info>,
     
+    #[account(mut)]
     pub owner: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
 pub struct CloseWithAuthCheck<'info> {
-    /// SECURE: has_one = owner ensures only owner can close
+    /// SECURE: The handler's `ensure_authority!` ensures only owner can close
     #[account(
         mut,
         close = recipient,
         seeds = [b"user", user_account.owner.as_ref()],
         bump = user_account.bump,
-        has_one = owner,  // Only owner can close
     )]
     pub user_account: Account<'info, UserAccount>,
     
@@ -417,12 +768,43 @@ pub struct InitializeConfig<'info> {
     )]
     pub config: Account<'info, Config>,
     
+    /// Reward mint - must already be controlled by the reward authority PDA
+    #[account(mint::authority = reward_authority)]
+    pub reward_mint: Account<'info, Mint>,
+    
+    /// CHECK: PDA that signs reward mints, validated by seeds
+    #[account(
+        seeds = [b"reward_authority"],
+        bump
+    )]
+    pub reward_authority: UncheckedAccount<'info>,
+    
     #[account(mut)]
     pub admin: Signer<'info>,
     
     pub system_program: Program<'info, System>,
 }
 
+#[derive(Accounts)]
+pub struct InitializeCloseCounter<'info> {
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + ClosesByRecipient::INIT_SPACE,
+        seeds = [b"closes", recipient.key().as_ref()],
+        bump
+    )]
+    pub closes: Account<'info, ClosesByRecipient>,
+    
+    /// CHECK: Only its key is recorded
+    pub recipient: UncheckedAccount<'info>,
+    
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
 #[derive(Accounts)]
 pub struct InitializeProfile<'info> {
     /// Check tombstone doesn't exist (prevents recreation)
@@ -435,6 +817,15 @@ pub struct InitializeProfile<'info> {
     )]
     pub profile: Account<'info, UserProfile>,
     
+    /// CHECK: Only its emptiness is read; a closed profile must go through
+    /// `recreate_profile` instead
+    #[account(
+        seeds = [b"tombstone", owner.key().as_ref()],
+        bump,
+        constraint = tombstone.data_is_empty() @ CloseError::ProfileTombstoneExists,
+    )]
+    pub tombstone: UncheckedAccount<'info>,
+    
     #[account(mut)]
     pub owner: Signer<'info>,
     
@@ -442,13 +833,81 @@ pub struct InitializeProfile<'info> {
 }
 
 #[derive(Accounts)]
-pub struct AccrueRewards<'info> {
+pub struct RecreateProfile<'info> {
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, Config>,
+    
+    /// Deleted on success so the next close starts a new cooldown
     #[account(
         mut,
-        seeds = [b"user", user_account.owner.as_ref()],
-        bump = user_account.bump,
+        close = owner,
+        seeds = [b"tombstone", owner.key().as_ref()],
+        bump = tombstone.bump,
     )]
-    pub user_account: Account<'info, UserAccount>,
+    pub tombstone: Account<'info, ProfileTombstone>,
+    
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + UserProfile::INIT_SPACE,
+        seeds = [b"profile", owner.key().as_ref()],
+        bump
+    )]
+    pub profile: Account<'info, UserProfile>,
+    
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct Reopen<'info> {
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, Config>,
+    
+    /// Holds the state to restore; deleted on success
+    #[account(
+        mut,
+        close = owner,
+        seeds = [b"tombstone", owner.key().as_ref()],
+        bump = tombstone.bump,
+    )]
+    pub tombstone: Account<'info, ProfileTombstone>,
+    
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + UserProfile::INIT_SPACE,
+        seeds = [b"profile", owner.key().as_ref()],
+        bump
+    )]
+    pub profile: Account<'info, UserProfile>,
+    
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct AccrueRewardsBatch<'info> {
+    /// Only the config admin may accrue rewards
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = admin,
+    )]
+    pub config: Account<'info, Config>,
+    
+    pub admin: Signer<'info>,
+    // remaining_accounts: one writable UserAccount per entry, in order
 }
 
 #[derive(Accounts)]
@@ -461,7 +920,32 @@ pub struct ClaimRewards<'info> {
     )]
     pub user_account: Account<'info, UserAccount>,
     
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = reward_mint,
+    )]
+    pub config: Account<'info, Config>,
+    
+    #[account(mut)]
+    pub reward_mint: Account<'info, Mint>,
+    
+    /// CHECK: PDA mint authority, validated by seeds
+    #[account(
+        seeds = [b"reward_authority"],
+        bump = config.reward_authority_bump,
+    )]
+    pub reward_authority: UncheckedAccount<'info>,
+    
+    #[account(
+        mut,
+        token::mint = reward_mint,
+    )]
+    pub user_token_account: Account<'info, TokenAccount>,
+    
     pub owner: Signer<'info>,
+    
+    pub token_program: Program<'info, Token>,
 }
 
 // ============================================================================
@@ -482,6 +966,16 @@ pub struct UserAccount {
 pub struct Config {
     pub admin: Pubkey,
     pub fee_bps: u16,
+    /// SPL mint that reward claims are paid in
+    pub reward_mint: Pubkey,
+    pub reward_authority_bump: u8,
+    /// How long a closed profile stays closed before it can be recreated
+    pub profile_cooldown_secs: i64,
+    /// How long after a close the owner can still `reopen` the profile
+    pub reopen_window_secs: i64,
+    /// Closes any one recipient may collect rent from per window
+    pub max_closes_per_window: u32,
+    pub close_window_secs: i64,
     pub bump: u8,
 }
 
@@ -493,12 +987,55 @@ pub struct UserProfile {
     pub bump: u8,
 }
 
-/// Permanent record that a profile was closed
+/// Compute budgeted per `accrue_rewards_batch` entry: deserialize, PDA
+/// check, serialize
+pub const ACCRUE_UNITS_PER_ACCOUNT: u64 = 6_000;
+
+/// One line of a batch accrual: credit `amount` to `owner`'s UserAccount
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct AccrualEntry {
+    pub owner: Pubkey,
+    pub amount: u64,
+}
+
+/// Record that a profile was closed, until it is reopened or recreated
 #[account]
 #[derive(InitSpace)]
 pub struct ProfileTombstone {
     pub original_owner: Pubkey,
+    /// Signer who performed the close - never mutated, but gone once
+    /// `reopen` or `recreate_profile` deletes the tombstone
+    pub closed_by: Pubkey,
     pub closed_at: i64,
+    /// The profile's points at close, restored by `reopen`
+    pub points: u64,
+    pub bump: u8,
+}
+
+/// Permanent record that a user account was closed
+#[account]
+#[derive(InitSpace)]
+pub struct AccountTombstone {
+    /// Set once, never mutated
+    pub record: Tombstone,
+    pub bump: u8,
+}
+
+/// How many times a user account address has been closed
+#[account]
+#[derive(InitSpace)]
+pub struct UserCloses {
+    pub count: u64,
+    pub bump: u8,
+}
+
+/// Closes paid out to one recipient in the current window
+#[account]
+#[derive(InitSpace)]
+pub struct ClosesByRecipient {
+    pub recipient: Pubkey,
+    pub count: u32,
+    pub window_start: i64,
     pub bump: u8,
 }
 
@@ -508,21 +1045,54 @@ pub struct ProfileTombstone {
 
 #[error_code]
 pub enum CloseError {
-    #[msg("Unauthorized to close this account")]
-    Unauthorized,
+    // 0: `Unauthorized` moved to `SecurityError`
     #[msg("Account already closed")]
-    AlreadyClosed,
+    AlreadyClosed = 1,
     #[msg("Cannot recreate closed profile")]
     ProfileTombstoneExists,
+    #[msg("Number of batch entries does not match the accounts provided")]
+    BatchLengthMismatch,
+    #[msg("Batch account is not a valid, writable UserAccount for its entry")]
+    InvalidUserAccount,
+    // 5: `Overflow` moved to `SecurityError`
+    #[msg("Profile cooldown has not elapsed since it was closed")]
+    CooldownActive = 6,
+    #[msg("Cooldown must not be negative")]
+    InvalidCooldown,
+    #[msg("Recipient has reached its close limit for this window")]
+    CloseRateLimited,
+    #[msg("Close limit and its window must be positive")]
+    InvalidCloseLimit,
+    #[msg("Account still holds a balance")]
+    BalanceNotEmpty,
+    #[msg("Account has unclaimed rewards")]
+    RewardsNotClaimed,
+    #[msg("Reopen window has passed since the profile was closed")]
+    ReopenWindowClosed,
+    #[msg("Reopen window must not be negative")]
+    InvalidReopenWindow,
 }
 
+error_categories!(CloseError {
+    Validation => [BatchLengthMismatch, InvalidUserAccount, InvalidCooldown, InvalidCloseLimit, InvalidReopenWindow],
+    State => [
+        AlreadyClosed, ProfileTombstoneExists, CooldownActive, CloseRateLimited, BalanceNotEmpty, RewardsNotClaimed,
+        ReopenWindowClosed,
+    ],
+});
+
 // ============================================================================
 // ACCOUNT CLOSING CHECKLIST
 // ============================================================================
 //
 // Use Anchor's `close` constraint (zeros data + transfers lamports)
 // Verify authority with `has_one` before closing
+// Refuse to close accounts that still hold balances or owed rewards
 // Consider tombstone records for PDA recreation prevention
+// Record `closed_by` on tombstones so closures can be attributed
+// Use a cooldown when recreation should be rate-limited, not forbidden
+// Allow undoing a close only within a bounded window, from saved state
+// Rate-limit closes per recipient so rent can't be farmed in bulk
 // Never just transfer lamports without zeroing data
 // Be aware of same-transaction revival attacks
 // Validate accounts haven't been defunded when reading
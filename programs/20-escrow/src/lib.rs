@@ -0,0 +1,268 @@
+//! # Escrow Release Conditions
+//! 
+//! ## Overview
+//! An escrow holds a depositor's funds until a condition is met, then pays
+//! a beneficiary. The whole security model is the release condition: if
+//! anyone can satisfy it, the escrow is just a slower wallet.
+//! 
+//! ## The Problem
+//! Release logic often trusts what the caller says about the world:
+//! - An `approved: bool` instruction argument
+//! - A "confirmed" flag any signer can set
+//! - A timeout checked against a caller-supplied timestamp
+//! 
+//! ## The Fix
+//! Every condition must come from something the caller can't fake: a
+//! signature from a key stored on the escrow (checked with `Signer` plus
+//! `has_one`/`address`), or the runtime `Clock`.
+
+use anchor_lang::prelude::*;
+use anchor_lang::system_program::{self, Transfer};
+pub use security_common::SecurityError;
+use security_common::{assert_value_conserved, error_categories, sum_balances};
+
+declare_id!("Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLnZ");
+
+#[program]
+pub mod escrow {
+    use super::*;
+    
+    // ============================================================================
+    // VULNERABILITY: CALLER-SUPPLIED RELEASE FLAG
+    // ============================================================================
+    
+    /// VULNERABLE: Releases funds whenever the caller says it's approved.
+    /// 
+    /// ## What's Wrong?
+    /// `approved` is an instruction argument - it's whatever the caller put
+    /// in the transaction. Nobody has to sign, no time has to pass. The only
+    /// thing checked is that the funds go to the recorded beneficiary.
+    /// 
+    /// ## Attack Scenario:
+    /// 1. Victim escrows payment for goods, to be released on delivery
+    /// 2. Beneficiary calls release_vulnerable(approved = true) right away
+    /// 3. Funds are released; the goods never ship
+    pub fn release_vulnerable(ctx: Context<ReleaseVulnerable>, approved: bool) -> Result<()> {
+        // DANGER: The caller vouches for themselves
+        require!(approved, EscrowError::ReleaseConditionsNotMet);
+        
+        pay_out(&mut ctx.accounts.escrow, &ctx.accounts.beneficiary)?;
+        
+        msg!("VULNERABLE: Released escrow on caller's say-so");
+        Ok(())
+    }
+    
+    // ============================================================================
+    // SECURE RELEASE
+    // ============================================================================
+    
+    /// SECURE: Releases only with the depositor's signature AND either the
+    /// timeout having passed or the arbiter co-signing.
+    /// 
+    /// ## What's Fixed?
+    /// - `depositor: Signer` + `has_one = depositor` - the depositor really
+    ///   signed, and it's THIS escrow's depositor
+    /// - `has_one = beneficiary` - funds can only go to the recorded payee
+    /// - The timeout comes from `Clock`, not from the caller
+    /// - `arbiter` is optional, but when present it must sign and must be
+    ///   `escrow.arbiter` (`address` constraint)
+    /// 
+    /// Release is allowed from `release_after` itself onwards.
+    pub fn release_secure(ctx: Context<ReleaseSecure>) -> Result<()> {
+        let timed_out = Clock::get()?.unix_timestamp >= ctx.accounts.escrow.release_after;
+        let arbiter_approved = ctx.accounts.arbiter.is_some();
+        require!(timed_out || arbiter_approved, EscrowError::ReleaseConditionsNotMet);
+        
+        pay_out(&mut ctx.accounts.escrow, &ctx.accounts.beneficiary)?;
+        
+        msg!(
+            "SECURE: Released escrow ({})",
+            if timed_out { "timeout" } else { "arbiter" }
+        );
+        Ok(())
+    }
+    
+    // ============================================================================
+    // INITIALIZATION
+    // ============================================================================
+    
+    /// Escrows `amount` lamports from the depositor until `release_after`.
+    pub fn initialize_escrow(
+        ctx: Context<InitializeEscrow>,
+        amount: u64,
+        release_after: i64,
+    ) -> Result<()> {
+        require!(amount > 0, SecurityError::ZeroAmount);
+        
+        system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.depositor.to_account_info(),
+                    to: ctx.accounts.escrow.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+        
+        let escrow = &mut ctx.accounts.escrow;
+        escrow.depositor = ctx.accounts.depositor.key();
+        escrow.beneficiary = ctx.accounts.beneficiary.key();
+        escrow.arbiter = ctx.accounts.arbiter.key();
+        escrow.amount = amount;
+        escrow.release_after = release_after;
+        escrow.released = false;
+        escrow.bump = ctx.bumps.escrow;
+        Ok(())
+    }
+}
+
+/// Moves the escrowed lamports to `beneficiary` and marks the escrow spent.
+/// 
+/// The escrow is owned by this program, so its lamports can be debited
+/// directly. Rent stays behind with the account, and the two accounts
+/// hold the same lamports between them afterwards.
+fn pay_out<'info>(escrow: &mut Account<'info, Escrow>, beneficiary: &AccountInfo<'info>) -> Result<()> {
+    require!(!escrow.released, EscrowError::AlreadyReleased);
+    
+    let amount = escrow.amount;
+    let escrow_info = escrow.to_account_info();
+    let before = sum_balances([escrow_info.lamports(), beneficiary.lamports()])?;
+    **escrow_info.try_borrow_mut_lamports()? = escrow_info
+        .lamports()
+        .checked_sub(amount)
+        .ok_or(SecurityError::InsufficientFunds)?;
+    **beneficiary.try_borrow_mut_lamports()? = beneficiary
+        .lamports()
+        .checked_add(amount)
+        .ok_or(SecurityError::Overflow)?;
+    assert_value_conserved(before, sum_balances([escrow_info.lamports(), beneficiary.lamports()])?)?;
+    
+    escrow.released = true;
+    escrow.amount = 0;
+    Ok(())
+}
+
+// ============================================================================
+// VULNERABLE ACCOUNT STRUCTURES
+// ============================================================================
+
+#[derive(Accounts)]
+pub struct ReleaseVulnerable<'info> {
+    #[account(
+        mut,
+        seeds = [b"escrow", escrow.depositor.as_ref()],
+        bump = escrow.bump,
+        has_one = beneficiary,
+    )]
+    pub escrow: Account<'info, Escrow>,
+    
+    /// CHECK: Must match escrow.beneficiary (has_one)
+    #[account(mut)]
+    pub beneficiary: AccountInfo<'info>,
+    
+    /// Anyone at all
+    pub caller: Signer<'info>,
+}
+
+// ============================================================================
+// SECURE ACCOUNT STRUCTURES
+// ============================================================================
+
+#[derive(Accounts)]
+pub struct ReleaseSecure<'info> {
+    #[account(
+        mut,
+        seeds = [b"escrow", depositor.key().as_ref()],
+        bump = escrow.bump,
+        has_one = depositor,
+        has_one = beneficiary,
+    )]
+    pub escrow: Account<'info, Escrow>,
+    
+    /// SECURE: The depositor must sign every release
+    pub depositor: Signer<'info>,
+    
+    /// CHECK: Must match escrow.beneficiary (has_one)
+    #[account(mut)]
+    pub beneficiary: AccountInfo<'info>,
+    
+    /// SECURE: Omit after the timeout; before it, the recorded arbiter
+    /// must co-sign
+    #[account(address = escrow.arbiter @ EscrowError::WrongArbiter)]
+    pub arbiter: Option<Signer<'info>>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeEscrow<'info> {
+    #[account(
+        init,
+        payer = depositor,
+        space = 8 + Escrow::INIT_SPACE,
+        seeds = [b"escrow", depositor.key().as_ref()],
+        bump
+    )]
+    pub escrow: Account<'info, Escrow>,
+    
+    #[account(mut)]
+    pub depositor: Signer<'info>,
+    
+    /// CHECK: Only its key is recorded
+    pub beneficiary: UncheckedAccount<'info>,
+    
+    /// CHECK: Only its key is recorded
+    pub arbiter: UncheckedAccount<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+// ============================================================================
+// STATE
+// ============================================================================
+
+#[account]
+#[derive(InitSpace)]
+pub struct Escrow {
+    pub depositor: Pubkey,
+    pub beneficiary: Pubkey,
+    /// May approve a release before `release_after`
+    pub arbiter: Pubkey,
+    /// Lamports held for the beneficiary, on top of rent
+    pub amount: u64,
+    /// Unix timestamp from which the depositor alone can release
+    pub release_after: i64,
+    pub released: bool,
+    pub bump: u8,
+}
+
+// ============================================================================
+// ERRORS
+// ============================================================================
+
+#[error_code]
+pub enum EscrowError {
+    #[msg("Release requires the timeout to have passed or the arbiter's signature")]
+    ReleaseConditionsNotMet,
+    #[msg("Escrow has already been released")]
+    AlreadyReleased,
+    #[msg("Signer is not this escrow's arbiter")]
+    WrongArbiter,
+}
+
+error_categories!(EscrowError {
+    Auth => [WrongArbiter],
+    State => [ReleaseConditionsNotMet, AlreadyReleased],
+});
+
+// ============================================================================
+// SECURITY CHECKLIST FOR ESCROWS
+// ============================================================================
+//
+// - Never take a release decision from an instruction argument
+// - Require a signature from every party whose consent the release needs
+// - Tie each signer to the escrow with has_one / address constraints
+// - Read time from Clock, never from the caller
+// - Pay only the recorded beneficiary
+// - Mark the escrow released so it can't pay out twice
+//
+// ============================================================================
@@ -0,0 +1,297 @@
+//! # Insecure Randomness
+//!
+//! ## Overview
+//! A program has no private state and no hidden inputs: everything it reads
+//! is visible to anyone who can see the transaction or simulate it. "Random"
+//! values derived from on-chain data are therefore known in advance.
+//!
+//! ## The Problem
+//! `Clock` is the usual source of fake randomness:
+//! - `slot` is public and advances predictably
+//! - `unix_timestamp` is set by the block producer, within bounds
+//! - Either can be read by a contract that calls in and reverts on a loss
+//!
+//! ## The Fix
+//! Take entropy from somewhere the participants can't see or choose when they
+//! act: a seed committed to before entries open and revealed afterwards
+//! (checked against its hash), or an external VRF whose proof the program
+//! verifies.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::hash::hashv;
+pub use security_common::SecurityError;
+use security_common::error_categories;
+
+declare_id!("Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLna");
+
+#[program]
+pub mod insecure_randomness {
+    use super::*;
+
+    // ============================================================================
+    // VULNERABILITY: CLOCK AS RANDOMNESS
+    // ============================================================================
+
+    /// VULNERABLE: Picks the winner from the current slot and timestamp.
+    ///
+    /// ## What's Wrong?
+    /// `slot + unix_timestamp` is the same number for everyone who looks.
+    /// Before sending, a caller reads the clock and knows who will win; a
+    /// block producer can nudge the timestamp until the result suits them.
+    ///
+    /// ## Attack Scenario:
+    /// 1. Attacker enters the lottery alongside honest players
+    /// 2. Attacker reads the clock and computes `clock_index` for upcoming slots
+    /// 3. Attacker calls pick_winner_vulnerable only when it lands on them
+    pub fn pick_winner_vulnerable(ctx: Context<PickWinnerVulnerable>) -> Result<()> {
+        let clock = &ctx.accounts.clock;
+        let lottery = &mut ctx.accounts.lottery;
+        require!(lottery.winner.is_none(), RandomnessError::WinnerAlreadyPicked);
+        require!(!lottery.entrants.is_empty(), RandomnessError::NoEntrants);
+
+        // DANGER: Every input here is public
+        let index = clock_index(clock.slot, clock.unix_timestamp, lottery.entrants.len());
+        lottery.winner = Some(lottery.entrants[index]);
+
+        msg!("VULNERABLE: Winner picked from slot {} / timestamp {}", clock.slot, clock.unix_timestamp);
+        Ok(())
+    }
+
+    // ============================================================================
+    // SECURE: COMMIT-REVEAL
+    // ============================================================================
+
+    /// SECURE: Picks the winner from a seed committed to at creation.
+    ///
+    /// ## What's Fixed?
+    /// - The authority fixed `commitment = sha256(seed)` before anyone
+    ///   entered, so the seed can't be chosen to favour an entrant
+    /// - The reveal must hash to the commitment, so the seed can't be
+    ///   swapped afterwards either
+    /// - Nobody but the authority knows the seed until it's revealed
+    /// - The draw depends on the entrant count, so entries must be closed
+    ///   (`close_entries`) first: otherwise whoever sees a pending reveal's
+    ///   seed could add entries to steer the index (`EntriesOpen`)
+    ///
+    /// Anyone may reveal; the hash check is what makes a reveal valid.
+    /// The authority can still refuse to reveal, and knowing the seed it
+    /// could stuff entries before closing them - for adversarial settings
+    /// use a VRF, or bond the authority so withholding costs them.
+    pub fn pick_winner_secure(ctx: Context<PickWinnerSecure>, seed: [u8; 32]) -> Result<()> {
+        let lottery_key = ctx.accounts.lottery.key();
+        let lottery = &mut ctx.accounts.lottery;
+        require!(lottery.winner.is_none(), RandomnessError::WinnerAlreadyPicked);
+        require!(lottery.entries_closed, RandomnessError::EntriesOpen);
+        require!(!lottery.entrants.is_empty(), RandomnessError::NoEntrants);
+        require!(
+            commitment_of(&seed) == lottery.commitment,
+            RandomnessError::CommitmentMismatch
+        );
+
+        let index = draw_index(&seed, &lottery_key, lottery.entrants.len());
+        lottery.winner = Some(lottery.entrants[index]);
+
+        msg!("SECURE: Winner picked from revealed seed");
+        Ok(())
+    }
+
+    // ============================================================================
+    // LOTTERY SETUP
+    // ============================================================================
+
+    /// Opens a lottery bound to `commitment = sha256(seed)`.
+    pub fn initialize_lottery(ctx: Context<InitializeLottery>, commitment: [u8; 32]) -> Result<()> {
+        let lottery = &mut ctx.accounts.lottery;
+        lottery.authority = ctx.accounts.authority.key();
+        lottery.commitment = commitment;
+        lottery.entrants = Vec::new();
+        lottery.entries_closed = false;
+        lottery.winner = None;
+        lottery.bump = ctx.bumps.lottery;
+        Ok(())
+    }
+
+    /// Adds the signer to the lottery while entries are open.
+    pub fn enter(ctx: Context<Enter>) -> Result<()> {
+        let lottery = &mut ctx.accounts.lottery;
+        require!(lottery.winner.is_none(), RandomnessError::WinnerAlreadyPicked);
+        require!(!lottery.entries_closed, RandomnessError::EntriesClosed);
+        require!(lottery.entrants.len() < Lottery::MAX_ENTRANTS, RandomnessError::LotteryFull);
+
+        lottery.entrants.push(ctx.accounts.entrant.key());
+        Ok(())
+    }
+
+    /// SECURE: Stops accepting entries, so the seed can be revealed.
+    ///
+    /// Only the lottery's authority can close entries, and they stay
+    /// closed.
+    pub fn close_entries(ctx: Context<CloseEntries>) -> Result<()> {
+        let lottery = &mut ctx.accounts.lottery;
+        require!(!lottery.entries_closed, RandomnessError::EntriesClosed);
+        lottery.entries_closed = true;
+        Ok(())
+    }
+}
+
+/// The entrant index `pick_winner_vulnerable` chooses - computable by
+/// anyone who can read the clock.
+pub fn clock_index(slot: u64, unix_timestamp: i64, entrants: usize) -> usize {
+    (slot.wrapping_add(unix_timestamp as u64) % entrants as u64) as usize
+}
+
+/// `sha256(seed)`, the value a lottery commits to.
+pub fn commitment_of(seed: &[u8; 32]) -> [u8; 32] {
+    hashv(&[seed]).to_bytes()
+}
+
+/// The entrant index a revealed `seed` draws for `lottery`.
+///
+/// Mixing in the lottery address keeps one seed from drawing the same
+/// index in every lottery it's reused for.
+pub fn draw_index(seed: &[u8; 32], lottery: &Pubkey, entrants: usize) -> usize {
+    let draw = hashv(&[seed, lottery.as_ref()]).to_bytes();
+    let mut bytes = [0u8; 8];
+    bytes.copy_from_slice(&draw[..8]);
+    (u64::from_le_bytes(bytes) % entrants as u64) as usize
+}
+
+// ============================================================================
+// VULNERABLE ACCOUNT STRUCTURES
+// ============================================================================
+
+#[derive(Accounts)]
+pub struct PickWinnerVulnerable<'info> {
+    #[account(
+        mut,
+        seeds = [b"lottery", lottery.authority.as_ref()],
+        bump = lottery.bump,
+    )]
+    pub lottery: Account<'info, Lottery>,
+
+    /// Anyone at all
+    pub caller: Signer<'info>,
+
+    /// VULNERABLE: Used as the source of randomness
+    pub clock: Sysvar<'info, Clock>,
+}
+
+// ============================================================================
+// SECURE ACCOUNT STRUCTURES
+// ============================================================================
+
+#[derive(Accounts)]
+pub struct PickWinnerSecure<'info> {
+    #[account(
+        mut,
+        seeds = [b"lottery", lottery.authority.as_ref()],
+        bump = lottery.bump,
+    )]
+    pub lottery: Account<'info, Lottery>,
+
+    /// Anyone holding the seed; the commitment check does the gating
+    pub caller: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeLottery<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + Lottery::INIT_SPACE,
+        seeds = [b"lottery", authority.key().as_ref()],
+        bump
+    )]
+    pub lottery: Account<'info, Lottery>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct Enter<'info> {
+    #[account(
+        mut,
+        seeds = [b"lottery", lottery.authority.as_ref()],
+        bump = lottery.bump,
+    )]
+    pub lottery: Account<'info, Lottery>,
+
+    pub entrant: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CloseEntries<'info> {
+    #[account(
+        mut,
+        seeds = [b"lottery", lottery.authority.as_ref()],
+        bump = lottery.bump,
+        has_one = authority,
+    )]
+    pub lottery: Account<'info, Lottery>,
+
+    pub authority: Signer<'info>,
+}
+
+// ============================================================================
+// STATE
+// ============================================================================
+
+#[account]
+#[derive(InitSpace)]
+pub struct Lottery {
+    pub authority: Pubkey,
+    /// `sha256(seed)`, fixed before the first entry
+    pub commitment: [u8; 32],
+    #[max_len(16)]
+    pub entrants: Vec<Pubkey>,
+    /// Set by `close_entries`; no entries after it, no reveal before it
+    pub entries_closed: bool,
+    pub winner: Option<Pubkey>,
+    pub bump: u8,
+}
+
+impl Lottery {
+    /// Must match the `max_len` on `entrants`
+    pub const MAX_ENTRANTS: usize = 16;
+}
+
+// ============================================================================
+// ERRORS
+// ============================================================================
+
+#[error_code]
+pub enum RandomnessError {
+    #[msg("Lottery has no entrants")]
+    NoEntrants,
+    #[msg("Lottery is full")]
+    LotteryFull,
+    #[msg("A winner has already been picked")]
+    WinnerAlreadyPicked,
+    #[msg("Revealed seed does not match the commitment")]
+    CommitmentMismatch,
+    #[msg("Lottery entries are closed")]
+    EntriesClosed,
+    #[msg("Lottery entries are still open")]
+    EntriesOpen,
+}
+
+error_categories!(RandomnessError {
+    Validation => [CommitmentMismatch],
+    State => [NoEntrants, LotteryFull, WinnerAlreadyPicked, EntriesClosed, EntriesOpen],
+});
+
+// ============================================================================
+// SECURITY CHECKLIST FOR RANDOMNESS
+// ============================================================================
+//
+// - Never derive outcomes from Clock, slot hashes, or other on-chain state
+// - Commit to the seed before participants can act, reveal after
+// - Check every reveal against its commitment
+// - Bind the draw to the account it's for, so seeds can't be replayed
+// - Plan for a committer who never reveals (VRF, bond, or timeout)
+// - Stop accepting entries once the outcome can be known
+//
+// ============================================================================
@@ -0,0 +1,245 @@
+//! # Native SOL Vault
+//!
+//! ## Overview
+//! A vault holding native SOL keeps it as the lamports of a program-owned
+//! PDA. Depositing is an ordinary System Program transfer; withdrawing is
+//! not, because the vault is no longer a System Program account.
+//!
+//! ## The Problem
+//! `system_program::transfer` only debits accounts the System Program owns
+//! and that carry no data. A vault PDA with state fails both checks, so
+//! signing the transfer with the PDA's seeds doesn't help - every
+//! withdrawal fails and the SOL is stuck.
+//!
+//! ## The Fix
+//! The owning program may debit its own accounts directly. Move the
+//! lamports by hand, and keep at least the rent-exempt minimum for the
+//! vault's data behind, or the runtime reaps the vault along with its
+//! state.
+
+use anchor_lang::prelude::*;
+use anchor_lang::system_program::{self, Transfer};
+pub use security_common::SecurityError;
+use security_common::{assert_value_conserved, error_categories, sum_balances};
+
+declare_id!("Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLnb");
+
+#[program]
+pub mod native_sol_vault {
+    use super::*;
+
+    // ============================================================================
+    // VULNERABILITY: SYSTEM TRANSFER FROM A PROGRAM-OWNED PDA
+    // ============================================================================
+
+    /// VULNERABLE: Withdraws with a System Program transfer signed by the
+    /// vault PDA.
+    ///
+    /// ## What's Wrong?
+    /// The System Program refuses to debit an account that carries data,
+    /// and the runtime refuses to let it debit one it doesn't own. The vault
+    /// is both, so this CPI fails every time - the signer seeds are right,
+    /// the account is simply the wrong kind.
+    ///
+    /// ## Attack Scenario:
+    /// No attacker needed:
+    /// 1. Users deposit SOL through `deposit`, which works
+    /// 2. Every withdrawal through this path fails with `InvalidArgument`
+    /// 3. The SOL is locked until the program is upgraded
+    pub fn withdraw_vulnerable(ctx: Context<WithdrawVulnerable>, amount: u64) -> Result<()> {
+        let authority = ctx.accounts.authority.key();
+        let seeds = &[b"sol_vault".as_ref(), authority.as_ref(), &[ctx.accounts.vault.bump]];
+
+        // DANGER: `from` is program-owned and carries data
+        system_program::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.system_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.vault.to_account_info(),
+                    to: ctx.accounts.recipient.to_account_info(),
+                },
+                &[&seeds[..]],
+            ),
+            amount,
+        )?;
+
+        msg!("VULNERABLE: Withdrew {} lamports", amount);
+        Ok(())
+    }
+
+    // ============================================================================
+    // SECURE: DIRECT LAMPORT ADJUSTMENT
+    // ============================================================================
+
+    /// SECURE: Moves lamports out of the vault directly.
+    ///
+    /// ## What's Fixed?
+    /// - The program owns the vault, so it may debit it without a CPI
+    /// - Only lamports above the rent-exempt minimum for the vault's data
+    ///   can leave, so the vault is never reaped
+    /// - Both sides of the move use checked arithmetic, and together hold
+    ///   the same lamports afterwards
+    pub fn withdraw_secure(ctx: Context<WithdrawSecure>, amount: u64) -> Result<()> {
+        require!(amount > 0, SecurityError::ZeroAmount);
+
+        let vault = ctx.accounts.vault.to_account_info();
+        let available = vault
+            .lamports()
+            .saturating_sub(Rent::get()?.minimum_balance(vault.data_len()));
+        require!(amount <= available, NativeVaultError::BelowRentExempt);
+
+        let recipient = &ctx.accounts.recipient;
+        let before = sum_balances([vault.lamports(), recipient.lamports()])?;
+        vault.sub_lamports(amount)?;
+        recipient.add_lamports(amount)?;
+        assert_value_conserved(before, sum_balances([vault.lamports(), recipient.lamports()])?)?;
+
+        msg!("SECURE: Withdrew {} lamports, {} left available", amount, available - amount);
+        Ok(())
+    }
+
+    // ============================================================================
+    // VAULT SETUP
+    // ============================================================================
+
+    pub fn initialize_sol_vault(ctx: Context<InitializeSolVault>) -> Result<()> {
+        let vault = &mut ctx.accounts.vault;
+        vault.authority = ctx.accounts.authority.key();
+        vault.bump = ctx.bumps.vault;
+        Ok(())
+    }
+
+    /// Adds `amount` lamports to the vault. The depositor is a System
+    /// Program account, so a System Program transfer is correct here.
+    pub fn deposit(ctx: Context<Deposit>, amount: u64) -> Result<()> {
+        require!(amount > 0, SecurityError::ZeroAmount);
+
+        system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.depositor.to_account_info(),
+                    to: ctx.accounts.vault.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+        Ok(())
+    }
+}
+
+// ============================================================================
+// VULNERABLE ACCOUNT STRUCTURES
+// ============================================================================
+
+#[derive(Accounts)]
+pub struct WithdrawVulnerable<'info> {
+    #[account(
+        mut,
+        seeds = [b"sol_vault", authority.key().as_ref()],
+        bump = vault.bump,
+        has_one = authority,
+    )]
+    pub vault: Account<'info, SolVault>,
+
+    /// CHECK: Receives the lamports
+    #[account(mut)]
+    pub recipient: UncheckedAccount<'info>,
+
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+// ============================================================================
+// SECURE ACCOUNT STRUCTURES
+// ============================================================================
+
+#[derive(Accounts)]
+pub struct WithdrawSecure<'info> {
+    #[account(
+        mut,
+        seeds = [b"sol_vault", vault.authority.as_ref()],
+        bump = vault.bump,
+        has_one = authority,
+    )]
+    pub vault: Account<'info, SolVault>,
+
+    /// CHECK: Receives the lamports
+    #[account(mut)]
+    pub recipient: UncheckedAccount<'info>,
+
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeSolVault<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + SolVault::INIT_SPACE,
+        seeds = [b"sol_vault", authority.key().as_ref()],
+        bump
+    )]
+    pub vault: Account<'info, SolVault>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct Deposit<'info> {
+    #[account(
+        mut,
+        seeds = [b"sol_vault", vault.authority.as_ref()],
+        bump = vault.bump,
+    )]
+    pub vault: Account<'info, SolVault>,
+
+    #[account(mut)]
+    pub depositor: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+// ============================================================================
+// STATE
+// ============================================================================
+
+/// Holds SOL as its own lamports; everything above rent is withdrawable
+#[account]
+#[derive(InitSpace)]
+pub struct SolVault {
+    pub authority: Pubkey,
+    pub bump: u8,
+}
+
+// ============================================================================
+// ERRORS
+// ============================================================================
+
+#[error_code]
+pub enum NativeVaultError {
+    // 0: `ZeroAmount` moved to `SecurityError`
+    #[msg("Withdrawal would leave the vault below rent exemption")]
+    BelowRentExempt = 1,
+}
+
+error_categories!(NativeVaultError {
+    State => [BelowRentExempt],
+});
+
+// ============================================================================
+// SECURITY CHECKLIST FOR NATIVE SOL VAULTS
+// ============================================================================
+//
+// - Deposit with `system_program::transfer` from the user's wallet
+// - Withdraw by adjusting lamports directly - the program owns the vault
+// - Never try `system_program::transfer` from a PDA that carries data
+// - Keep `Rent::minimum_balance(data_len)` in the vault at all times
+// - Check the withdrawing signer against the vault's stored authority
+// - Use checked arithmetic on both sides of every lamport move
+//
+// ============================================================================
@@ -0,0 +1,168 @@
+//! # Upgrade Authority
+//!
+//! ## Overview
+//! A freshly deployed program has no admin until someone writes one into its
+//! config. Whoever does that first controls every privileged instruction
+//! gated on `config.admin` from then on.
+//!
+//! ## The Problem
+//! An `initialize` that stores its caller as admin trusts whoever gets there
+//! first. Deployment and initialization are separate transactions, so an
+//! attacker watching for the deploy can slip their own `initialize` in
+//! between and own the protocol.
+//!
+//! ## The Fix
+//! The deployer already holds an authority the runtime enforces: the
+//! program's upgrade authority, recorded in its ProgramData account. Only
+//! let the bootstrap through when that account is the one the upgradeable
+//! loader derives for this program and its upgrade authority signed.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::bpf_loader_upgradeable;
+pub use security_common::SecurityError;
+use security_common::error_categories;
+
+declare_id!("Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLnc");
+
+#[program]
+pub mod upgrade_authority {
+    use super::*;
+
+    // ============================================================================
+    // VULNERABILITY: FIRST CALLER BECOMES ADMIN
+    // ============================================================================
+
+    /// VULNERABLE: Creates the config with whatever admin the first caller
+    /// names.
+    ///
+    /// ## What's Wrong?
+    /// `init` guarantees the config is only created once, not that the
+    /// right person creates it. Nothing ties `payer` to the deployer.
+    ///
+    /// ## Attack Scenario:
+    /// 1. The team deploys the program
+    /// 2. Attacker sees the deploy and calls this before the team does
+    /// 3. The config now names the attacker as admin, permanently
+    pub fn initialize_config_vulnerable(ctx: Context<InitializeConfigVulnerable>, admin: Pubkey) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        // DANGER: anyone who calls first picks the admin
+        config.admin = admin;
+        config.bump = ctx.bumps.config;
+
+        msg!("VULNERABLE: Admin set to {}", admin);
+        Ok(())
+    }
+
+    // ============================================================================
+    // SECURE: UPGRADE AUTHORITY BOOTSTRAPS THE ADMIN
+    // ============================================================================
+
+    /// SECURE: Creates the config only when the program's upgrade authority
+    /// signs.
+    ///
+    /// ## What's Fixed?
+    /// - `program_data` must sit at the address the upgradeable loader
+    ///   derives from this program's ID, so no other program's data fits
+    /// - `Account<ProgramData>` checks the loader owns it, so it can't be
+    ///   forged
+    /// - Its `upgrade_authority_address` must be the signer; a program
+    ///   already made immutable has none, so bootstrap before finalizing
+    pub fn bootstrap_admin(ctx: Context<BootstrapAdmin>, admin: Pubkey) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        config.admin = admin;
+        config.bump = ctx.bumps.config;
+
+        msg!("SECURE: Upgrade authority set admin to {}", admin);
+        Ok(())
+    }
+}
+
+// ============================================================================
+// VULNERABLE ACCOUNT STRUCTURES
+// ============================================================================
+
+#[derive(Accounts)]
+pub struct InitializeConfigVulnerable<'info> {
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + ProgramConfig::INIT_SPACE,
+        seeds = [b"config"],
+        bump
+    )]
+    pub config: Account<'info, ProgramConfig>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+// ============================================================================
+// SECURE ACCOUNT STRUCTURES
+// ============================================================================
+
+#[derive(Accounts)]
+pub struct BootstrapAdmin<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + ProgramConfig::INIT_SPACE,
+        seeds = [b"config"],
+        bump
+    )]
+    pub config: Account<'info, ProgramConfig>,
+
+    #[account(
+        seeds = [crate::ID.as_ref()],
+        bump,
+        seeds::program = bpf_loader_upgradeable::ID,
+        constraint = program_data.upgrade_authority_address == Some(authority.key())
+            @ UpgradeAuthorityError::NotUpgradeAuthority,
+    )]
+    pub program_data: Account<'info, ProgramData>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+// ============================================================================
+// STATE
+// ============================================================================
+
+/// Names the admin every privileged instruction checks against
+#[account]
+#[derive(InitSpace)]
+pub struct ProgramConfig {
+    pub admin: Pubkey,
+    pub bump: u8,
+}
+
+// ============================================================================
+// ERRORS
+// ============================================================================
+
+#[error_code]
+pub enum UpgradeAuthorityError {
+    #[msg("Signer is not the program's upgrade authority")]
+    NotUpgradeAuthority,
+}
+
+error_categories!(UpgradeAuthorityError {
+    Auth => [NotUpgradeAuthority],
+});
+
+// ============================================================================
+// SECURITY CHECKLIST FOR ADMIN BOOTSTRAP
+// ============================================================================
+//
+// - Never make whoever initializes first the admin
+// - Gate the bootstrap on the upgrade authority in the ProgramData account
+// - Derive ProgramData from the program ID under the upgradeable loader
+// - Let `Account<ProgramData>` check the loader owns it
+// - Bootstrap before making the program immutable - after, there is no
+//   upgrade authority left to check
+//
+// ============================================================================
@@ -0,0 +1,420 @@
+//! # Flash Loan Guard
+//!
+//! ## Overview
+//! A flash loan lends a pool's liquidity with no collateral, on the promise
+//! that it comes back before the transaction ends. Solana programs can't run
+//! code "at the end of the transaction", so the lender has to find the
+//! repayment itself: the instructions sysvar lists every top-level
+//! instruction in the transaction, including the ones that haven't run yet.
+//!
+//! ## The Problem
+//! Checking only that the pool's balance is back at the end says nothing
+//! about where the lamports came from. A borrower who `deposit`s the
+//! borrowed lamports straight back restores the balance - and walks away
+//! with a deposit position the pool never received new funds for.
+//!
+//! ## The Fix
+//! Track the loan itself. `borrow` records `loan_outstanding` and only lends
+//! if a `repay` of exactly that amount follows in the same transaction;
+//! `repay` is the only instruction that clears it, and `deposit` refuses to
+//! run while it is set. A transaction that ends with a loan outstanding
+//! can't land.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::solana_program::sysvar::instructions::{self as instructions_sysvar, load_current_index_checked, load_instruction_at_checked};
+use anchor_lang::system_program::{self, Transfer};
+use anchor_lang::Discriminator;
+pub use security_common::SecurityError;
+use security_common::{assert_value_conserved, error_categories, sum_balances};
+
+declare_id!("Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLnd");
+
+#[program]
+pub mod flash_loan_guard {
+    use super::*;
+
+    // ============================================================================
+    // VULNERABILITY: BALANCE-ONLY REPAYMENT CHECK
+    // ============================================================================
+
+    /// VULNERABLE: Lends `amount` and trusts a later balance check to catch
+    /// a missing repayment.
+    ///
+    /// ## What's Wrong?
+    /// `check_repaid_vulnerable` must follow in the transaction, but it only
+    /// compares the pool's lamports with `balance_before`. Any instruction
+    /// that sends lamports to the pool satisfies it - including `deposit`,
+    /// which also credits the sender.
+    ///
+    /// ## Attack Scenario:
+    /// 1. Attacker borrows the pool's whole balance
+    /// 2. Attacker deposits the borrowed lamports back, crediting a position
+    /// 3. The balance check passes: the lamports are back
+    /// 4. The pool now owes the attacker a deposit it was never paid
+    pub fn borrow_vulnerable(ctx: Context<BorrowVulnerable>, amount: u64) -> Result<()> {
+        let pool_key = ctx.accounts.pool.key();
+        require!(
+            find_later_instruction(&ctx.accounts.instructions, &instruction::CheckRepaidVulnerable::DISCRIMINATOR, &pool_key)?.is_some(),
+            FlashLoanError::MissingBalanceCheck
+        );
+
+        let pool = ctx.accounts.pool.to_account_info();
+        ctx.accounts.pool.balance_before = pool.lamports();
+        lend(&pool, &ctx.accounts.borrower, amount)?;
+
+        msg!("VULNERABLE: Lent {} lamports against a balance check", amount);
+        Ok(())
+    }
+
+    /// VULNERABLE: Passes whenever the pool holds at least what it did
+    /// before the borrow, however the lamports got there.
+    pub fn check_repaid_vulnerable(ctx: Context<CheckRepaidVulnerable>) -> Result<()> {
+        let pool = &mut ctx.accounts.pool;
+        // DANGER: a deposit of the borrowed lamports looks like a repayment
+        require!(pool.to_account_info().lamports() >= pool.balance_before, FlashLoanError::LoanNotRepaid);
+        pool.balance_before = 0;
+
+        msg!("VULNERABLE: Balance restored, loan considered repaid");
+        Ok(())
+    }
+
+    // ============================================================================
+    // SECURE: OUTSTANDING-LOAN COUNTER
+    // ============================================================================
+
+    /// SECURE: Lends `amount` only if this transaction repays it.
+    ///
+    /// ## What's Fixed?
+    /// - Must be a top-level instruction, so the repay found below follows
+    ///   this borrow rather than whatever instruction made the CPI
+    /// - One loan at a time: `loan_outstanding` must be zero on entry
+    /// - A later top-level `repay` of exactly `amount` on this pool must be in
+    ///   the transaction; if it fails, the whole transaction (loan included)
+    ///   is rolled back
+    /// - `loan_outstanding` blocks `deposit` until that repay runs, so the
+    ///   borrowed lamports can't be turned into a position
+    pub fn borrow(ctx: Context<Borrow>, amount: u64) -> Result<()> {
+        require!(amount > 0, SecurityError::ZeroAmount);
+        require!(ctx.accounts.pool.loan_outstanding == 0, FlashLoanError::LoanAlreadyOutstanding);
+
+        let instructions = &ctx.accounts.instructions;
+        let current = load_current_index_checked(instructions)?;
+        require_keys_eq!(
+            load_instruction_at_checked(current.into(), instructions)?.program_id,
+            crate::ID,
+            FlashLoanError::BorrowViaCpi
+        );
+
+        let pool_key = ctx.accounts.pool.key();
+        let repaid = find_later_instruction(instructions, &instruction::Repay::DISCRIMINATOR, &pool_key)?
+            .and_then(|repay| instruction::Repay::try_from_slice(&repay.data[8..]).ok())
+            .is_some_and(|repay| repay.amount == amount);
+        require!(repaid, FlashLoanError::MissingRepay);
+
+        ctx.accounts.pool.loan_outstanding = amount;
+        lend(&ctx.accounts.pool.to_account_info(), &ctx.accounts.borrower, amount)?;
+
+        msg!("SECURE: Lent {} lamports, repay found later in the transaction", amount);
+        Ok(())
+    }
+
+    /// SECURE: Returns the outstanding loan and clears the counter.
+    pub fn repay(ctx: Context<Repay>, amount: u64) -> Result<()> {
+        require!(amount > 0, SecurityError::ZeroAmount);
+        require!(amount == ctx.accounts.pool.loan_outstanding, FlashLoanError::RepayMismatch);
+
+        system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.borrower.to_account_info(),
+                    to: ctx.accounts.pool.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+        ctx.accounts.pool.loan_outstanding = 0;
+
+        msg!("SECURE: Repaid {} lamports", amount);
+        Ok(())
+    }
+
+    // ============================================================================
+    // POOL SETUP AND DEPOSITS
+    // ============================================================================
+
+    pub fn initialize_lending_pool(ctx: Context<InitializeLendingPool>) -> Result<()> {
+        let pool = &mut ctx.accounts.pool;
+        pool.authority = ctx.accounts.authority.key();
+        pool.bump = ctx.bumps.pool;
+        Ok(())
+    }
+
+    pub fn open_position(ctx: Context<OpenPosition>) -> Result<()> {
+        let position = &mut ctx.accounts.position;
+        position.owner = ctx.accounts.owner.key();
+        position.pool = ctx.accounts.pool.key();
+        position.bump = ctx.bumps.position;
+        Ok(())
+    }
+
+    /// Adds `amount` lamports to the pool and credits them to the
+    /// depositor's position. Refused while a secure loan is outstanding.
+    pub fn deposit(ctx: Context<Deposit>, amount: u64) -> Result<()> {
+        require!(amount > 0, SecurityError::ZeroAmount);
+        require!(ctx.accounts.pool.loan_outstanding == 0, FlashLoanError::FlashLoanActive);
+
+        system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.owner.to_account_info(),
+                    to: ctx.accounts.pool.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+
+        let pool = &mut ctx.accounts.pool;
+        pool.total_deposits = pool.total_deposits.checked_add(amount).ok_or(SecurityError::Overflow)?;
+        let position = &mut ctx.accounts.position;
+        position.amount = position.amount.checked_add(amount).ok_or(SecurityError::Overflow)?;
+        Ok(())
+    }
+}
+
+/// Moves `amount` lamports from the pool to the borrower, never touching
+/// the pool's rent reserve.
+fn lend(pool: &AccountInfo, borrower: &AccountInfo, amount: u64) -> Result<()> {
+    let available = pool.lamports().saturating_sub(Rent::get()?.minimum_balance(pool.data_len()));
+    require!(amount <= available, FlashLoanError::InsufficientLiquidity);
+
+    let before = sum_balances([pool.lamports(), borrower.lamports()])?;
+    pool.sub_lamports(amount)?;
+    borrower.add_lamports(amount)?;
+    assert_value_conserved(before, sum_balances([pool.lamports(), borrower.lamports()])?)
+}
+
+/// Finds the first top-level instruction after the current one that calls
+/// this program's `discriminator` instruction with `pool` as its first
+/// account.
+fn find_later_instruction(instructions: &AccountInfo, discriminator: &[u8], pool: &Pubkey) -> Result<Option<Instruction>> {
+    let mut index = usize::from(load_current_index_checked(instructions)?) + 1;
+    // Loading past the last instruction fails, which ends the scan
+    while let Ok(instruction) = load_instruction_at_checked(index, instructions) {
+        if instruction.program_id == crate::ID
+            && instruction.data.starts_with(discriminator)
+            && instruction.accounts.first().is_some_and(|meta| meta.pubkey == *pool)
+        {
+            return Ok(Some(instruction));
+        }
+        index += 1;
+    }
+    Ok(None)
+}
+
+// ============================================================================
+// VULNERABLE ACCOUNT STRUCTURES
+// ============================================================================
+
+#[derive(Accounts)]
+pub struct BorrowVulnerable<'info> {
+    #[account(
+        mut,
+        seeds = [b"lending_pool", pool.authority.as_ref()],
+        bump = pool.bump,
+    )]
+    pub pool: Account<'info, LendingPool>,
+
+    #[account(mut)]
+    pub borrower: Signer<'info>,
+
+    /// CHECK: The instructions sysvar, pinned by address
+    #[account(address = instructions_sysvar::ID)]
+    pub instructions: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CheckRepaidVulnerable<'info> {
+    #[account(
+        mut,
+        seeds = [b"lending_pool", pool.authority.as_ref()],
+        bump = pool.bump,
+    )]
+    pub pool: Account<'info, LendingPool>,
+}
+
+// ============================================================================
+// SECURE ACCOUNT STRUCTURES
+// ============================================================================
+
+#[derive(Accounts)]
+pub struct Borrow<'info> {
+    #[account(
+        mut,
+        seeds = [b"lending_pool", pool.authority.as_ref()],
+        bump = pool.bump,
+    )]
+    pub pool: Account<'info, LendingPool>,
+
+    #[account(mut)]
+    pub borrower: Signer<'info>,
+
+    /// CHECK: The instructions sysvar, pinned by address
+    #[account(address = instructions_sysvar::ID)]
+    pub instructions: UncheckedAccount<'info>,
+}
+
+/// `pool` must stay the first account: `borrow` matches repays on it
+#[derive(Accounts)]
+pub struct Repay<'info> {
+    #[account(
+        mut,
+        seeds = [b"lending_pool", pool.authority.as_ref()],
+        bump = pool.bump,
+    )]
+    pub pool: Account<'info, LendingPool>,
+
+    #[account(mut)]
+    pub borrower: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeLendingPool<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + LendingPool::INIT_SPACE,
+        seeds = [b"lending_pool", authority.key().as_ref()],
+        bump
+    )]
+    pub pool: Account<'info, LendingPool>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct OpenPosition<'info> {
+    #[account(
+        seeds = [b"lending_pool", pool.authority.as_ref()],
+        bump = pool.bump,
+    )]
+    pub pool: Account<'info, LendingPool>,
+
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + Position::INIT_SPACE,
+        seeds = [b"position", pool.key().as_ref(), owner.key().as_ref()],
+        bump
+    )]
+    pub position: Account<'info, Position>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct Deposit<'info> {
+    #[account(
+        mut,
+        seeds = [b"lending_pool", pool.authority.as_ref()],
+        bump = pool.bump,
+    )]
+    pub pool: Account<'info, LendingPool>,
+
+    #[account(
+        mut,
+        seeds = [b"position", pool.key().as_ref(), owner.key().as_ref()],
+        bump = position.bump,
+        has_one = owner,
+        has_one = pool,
+    )]
+    pub position: Account<'info, Position>,
+
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+// ============================================================================
+// STATE
+// ============================================================================
+
+/// Lends its lamports above rent; `total_deposits` is what it owes
+/// depositors
+#[account]
+#[derive(InitSpace)]
+pub struct LendingPool {
+    pub authority: Pubkey,
+    pub total_deposits: u64,
+    /// Lent by `borrow` and not yet repaid - nonzero only mid-transaction
+    pub loan_outstanding: u64,
+    /// Lamports held when `borrow_vulnerable` last lent
+    pub balance_before: u64,
+    pub bump: u8,
+}
+
+/// One depositor's claim on a pool
+#[account]
+#[derive(InitSpace)]
+pub struct Position {
+    pub owner: Pubkey,
+    pub pool: Pubkey,
+    pub amount: u64,
+    pub bump: u8,
+}
+
+// ============================================================================
+// ERRORS
+// ============================================================================
+
+#[error_code]
+pub enum FlashLoanError {
+    // 0: `ZeroAmount` moved to `SecurityError`
+    #[msg("Pool doesn't hold that much above its rent reserve")]
+    InsufficientLiquidity = 1,
+    #[msg("No balance check follows the borrow")]
+    MissingBalanceCheck,
+    #[msg("Pool balance is below what it held before the loan")]
+    LoanNotRepaid,
+    #[msg("Pool already has a loan outstanding")]
+    LoanAlreadyOutstanding,
+    #[msg("Borrow must be a top-level instruction")]
+    BorrowViaCpi,
+    #[msg("No repay of the borrowed amount follows the borrow")]
+    MissingRepay,
+    #[msg("Repay must match the outstanding loan")]
+    RepayMismatch,
+    #[msg("Deposits are closed while a flash loan is outstanding")]
+    FlashLoanActive,
+}
+
+error_categories!(FlashLoanError {
+    Validation => [MissingBalanceCheck, BorrowViaCpi, MissingRepay, RepayMismatch],
+    State => [InsufficientLiquidity, LoanNotRepaid, LoanAlreadyOutstanding, FlashLoanActive],
+});
+
+// ============================================================================
+// SECURITY CHECKLIST FOR FLASH LOANS
+// ============================================================================
+//
+// - Never treat a restored balance as proof of repayment
+// - Track the loan itself in state, and clear it only in `repay`
+// - Find the repay in the instructions sysvar before lending
+// - Pin the instructions sysvar by address
+// - Require the borrow to be top-level so "later" means later
+// - Block deposits (and anything else that credits the borrower) while a
+//   loan is outstanding
+// - Allow one loan at a time per pool
+//
+// ============================================================================
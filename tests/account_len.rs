@@ -0,0 +1,68 @@
+//! Exact-length checks on fixed-size accounts.
+//!
+//! `account_validation::guards::assert_exact_len` accepts a `UserDeposit`
+//! only if its data is exactly `8 + UserDeposit::INIT_SPACE` bytes.
+
+use account_validation::guards::assert_exact_len;
+use account_validation::{UserDeposit, ValidationError, ID};
+use anchor_lang::prelude::{AccountInfo, ProgramError, Pubkey};
+use anchor_lang::Space;
+use security_tests::anchor_account;
+
+fn user_deposit() -> Vec<u8> {
+    let state = UserDeposit {
+        pool: Pubkey::new_unique(),
+        owner: Pubkey::new_unique(),
+        amount: 1_000,
+        locked_until: 0,
+        deposited_at: 0,
+        permit_nonce: 0,
+        accrued_rewards: 0,
+        rewards_updated_at: 0,
+        bump: 255,
+    };
+    anchor_account(&state, ID).data
+}
+
+/// Checks `data`, as a program-owned account, against `UserDeposit`.
+fn check(mut data: Vec<u8>) -> anchor_lang::Result<()> {
+    let key = Pubkey::new_unique();
+    let mut lamports = 0;
+    let info = AccountInfo::new(&key, false, false, &mut lamports, &mut data, &ID, false, 0);
+    assert_exact_len::<UserDeposit>(&info)
+}
+
+fn rejected(result: anchor_lang::Result<()>) -> ProgramError {
+    result.expect_err("wrong length").into()
+}
+
+#[test]
+fn a_correctly_sized_account_is_accepted() {
+    let data = user_deposit();
+    assert_eq!(data.len(), 8 + UserDeposit::INIT_SPACE);
+
+    check(data).expect("exact size");
+}
+
+#[test]
+fn a_grown_account_is_rejected() {
+    // Still deserializes fine - the extra bytes are simply ignored
+    let mut data = user_deposit();
+    data.extend_from_slice(&[0xff; 32]);
+
+    assert_eq!(
+        rejected(check(data)),
+        ProgramError::Custom(ValidationError::UnexpectedAccountLength.into())
+    );
+}
+
+#[test]
+fn a_truncated_account_is_rejected() {
+    let mut data = user_deposit();
+    data.truncate(data.len() - 1);
+
+    assert_eq!(
+        rejected(check(data)),
+        ProgramError::Custom(ValidationError::UnexpectedAccountLength.into())
+    );
+}
@@ -0,0 +1,103 @@
+//! Growing an account with `realloc` in the type-cosplay program.
+//!
+//! `upgrade_account` keeps the discriminator and zeroes only the new tail,
+//! so the account still loads as `UserAccount`. The vulnerable
+//! zero-everything rewrite is the negative control: same growth, but the
+//! account no longer deserializes as anything.
+
+use anchor_lang::error::ErrorCode as AnchorError;
+use anchor_lang::{AccountDeserialize, Discriminator, InstructionData, ToAccountMetas};
+use security_tests::{custom_error, pda, program_test, send};
+use solana_program_test::BanksClient;
+use solana_sdk::{
+    instruction::Instruction,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    system_program,
+};
+use type_cosplay::{accounts, instruction, UserAccount, ID};
+
+const NEW_LEN: u32 = 256;
+
+fn ix(accounts: impl ToAccountMetas, data: impl InstructionData) -> Instruction {
+    Instruction::new_with_bytes(ID, &data.data(), accounts.to_account_metas(None))
+}
+
+/// A fresh UserAccount owned by the payer, and its original size.
+async fn user_account() -> (BanksClient, Keypair, Pubkey, usize) {
+    let (mut banks, payer, _) = program_test().start().await;
+    let user_account = pda(&[b"user", payer.pubkey().as_ref()], &ID);
+    send(
+        &mut banks,
+        &[ix(
+            accounts::InitializeUserAccount { user_account, owner: payer.pubkey(), system_program: system_program::ID },
+            instruction::InitializeUserAccount {},
+        )],
+        &[&payer],
+    )
+    .await
+    .expect("user account created");
+    let old_len = banks.get_account(user_account).await.unwrap().unwrap().data.len();
+    (banks, payer, user_account, old_len)
+}
+
+fn upgrade(user_account: Pubkey, owner: Pubkey, new_len: u32) -> Instruction {
+    ix(
+        accounts::UpgradeAccount { user_account, owner, system_program: system_program::ID },
+        instruction::UpgradeAccount { new_len },
+    )
+}
+
+#[tokio::test]
+async fn upgrade_keeps_the_discriminator_and_zeroes_the_tail() {
+    let (mut banks, payer, user_account, old_len) = user_account().await;
+    let before = banks.get_account(user_account).await.unwrap().unwrap();
+
+    send(&mut banks, &[upgrade(user_account, payer.pubkey(), NEW_LEN)], &[&payer])
+        .await
+        .expect("upgrade lands");
+
+    let after = banks.get_account(user_account).await.unwrap().unwrap();
+    assert_eq!(after.data.len(), NEW_LEN as usize);
+    assert_eq!(&after.data[..8], &UserAccount::DISCRIMINATOR);
+    assert_eq!(&after.data[..old_len], &before.data[..]);
+    assert!(after.data[old_len..].iter().all(|byte| *byte == 0));
+
+    let state = UserAccount::try_deserialize(&mut after.data.as_slice()).expect("still a UserAccount");
+    assert_eq!(state.owner, payer.pubkey());
+
+    // Loading it as Account<UserAccount> again proves the program agrees
+    send(&mut banks, &[upgrade(user_account, payer.pubkey(), NEW_LEN + 1)], &[&payer])
+        .await
+        .expect("upgraded account still loads");
+}
+
+#[tokio::test]
+async fn zero_everything_upgrade_breaks_the_account() {
+    let (mut banks, payer, user_account, _) = user_account().await;
+
+    send(
+        &mut banks,
+        &[ix(
+            accounts::UpgradeAccountVulnerable {
+                user_account,
+                owner: payer.pubkey(),
+                system_program: system_program::ID,
+            },
+            instruction::UpgradeAccountVulnerable { new_len: NEW_LEN },
+        )],
+        &[&payer],
+    )
+    .await
+    .expect("vulnerable upgrade lands");
+
+    let after = banks.get_account(user_account).await.unwrap().unwrap();
+    assert_eq!(after.data.len(), NEW_LEN as usize);
+    assert_ne!(&after.data[..8], &UserAccount::DISCRIMINATOR);
+    assert!(UserAccount::try_deserialize(&mut after.data.as_slice()).is_err());
+
+    let err = send(&mut banks, &[upgrade(user_account, payer.pubkey(), NEW_LEN + 1)], &[&payer])
+        .await
+        .expect_err("account no longer loads as UserAccount");
+    assert_eq!(err, custom_error(0, AnchorError::AccountDiscriminatorMismatch));
+}
@@ -0,0 +1,175 @@
+//! Admin override audit log in the reinitialization program.
+//!
+//! Pausing, unpausing, emergency withdrawals and vault resets each bump
+//! `AdminAuditLog::override_count`, extend its hash chain and emit an
+//! `AdminOverride` event naming the admin who did it.
+//!
+//! The builtin test harness does not surface `sol_log_data` in transaction
+//! logs, so each test reads the event back from the instruction's return
+//! data - the override instructions return the same struct they emit.
+
+use anchor_lang::{AnchorDeserialize, Discriminator, InstructionData, ToAccountMetas};
+use reinitialization::{accounts, instruction, AdminAuditLog, AdminOverride, OverrideAction, VaultSecure, ID};
+use security_tests::{anchor_account, custom_error, load, pda, program_test, system_account};
+use solana_program_test::ProgramTestContext;
+use solana_sdk::{
+    instruction::Instruction,
+    native_token::LAMPORTS_PER_SOL,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    system_program,
+    transaction::{Transaction, TransactionError},
+};
+
+const VAULT_BALANCE: u64 = 500;
+
+fn ix(accounts: impl ToAccountMetas, data: impl InstructionData) -> Instruction {
+    Instruction::new_with_bytes(ID, &data.data(), accounts.to_account_metas(None))
+}
+
+/// Sends `instruction` and returns its raw return data.
+async fn send(
+    context: &mut ProgramTestContext,
+    signer: &Keypair,
+    instruction: Instruction,
+) -> Result<Vec<u8>, TransactionError> {
+    let blockhash = context.banks_client.get_latest_blockhash().await.unwrap();
+    let tx = Transaction::new_signed_with_payer(&[instruction], Some(&signer.pubkey()), &[signer], blockhash);
+    let result = context.banks_client.process_transaction_with_metadata(tx).await.unwrap();
+    result.result?;
+
+    Ok(result
+        .metadata
+        .and_then(|metadata| metadata.return_data)
+        .map(|return_data| return_data.data)
+        .unwrap_or_default())
+}
+
+/// admin + action + target + sequence + hash
+const ENTRY_LEN: usize = 32 + 1 + 32 + 8 + 32;
+
+fn entry(mut data: Vec<u8>) -> AdminOverride {
+    // The runtime strips trailing zero bytes from return data
+    data.resize(ENTRY_LEN, 0);
+    AdminOverride::deserialize(&mut data.as_slice()).unwrap()
+}
+
+/// A kill switch and audit log owned by `admin`, someone else's vault, and
+/// a funded `intruder` who is not the admin.
+struct Fixture {
+    context: ProgramTestContext,
+    admin: Keypair,
+    intruder: Keypair,
+    kill_switch: Pubkey,
+    audit_log: Pubkey,
+    vault: Pubkey,
+}
+
+async fn audited() -> Fixture {
+    let admin = Keypair::new();
+    let intruder = Keypair::new();
+    let owner = Pubkey::new_unique();
+    let (vault, bump) = Pubkey::find_program_address(&[b"vault", owner.as_ref()], &ID);
+    let state = VaultSecure {
+        authority: owner,
+        balance: VAULT_BALANCE,
+        total_deposits: VAULT_BALANCE,
+        total_withdrawals: 0,
+        bump,
+    };
+
+    let mut test = program_test();
+    test.add_account(admin.pubkey(), system_account(LAMPORTS_PER_SOL));
+    test.add_account(intruder.pubkey(), system_account(LAMPORTS_PER_SOL));
+    test.add_account(vault, anchor_account(&state, ID));
+    let mut context = test.start_with_context().await;
+
+    let kill_switch = pda(&[b"disabled_instructions"], &ID);
+    let audit_log = pda(&[b"admin_audit"], &ID);
+    let setup = [
+        ix(
+            accounts::InitializeKillSwitch { kill_switch, admin: admin.pubkey(), system_program: system_program::ID },
+            instruction::InitializeKillSwitch {},
+        ),
+        ix(
+            accounts::InitializeAdminAuditLog {
+                kill_switch,
+                audit_log,
+                admin: admin.pubkey(),
+                system_program: system_program::ID,
+            },
+            instruction::InitializeAdminAuditLog {},
+        ),
+    ];
+    for instruction in setup {
+        send(&mut context, &admin, instruction).await.expect("setup");
+    }
+    Fixture { context, admin, intruder, kill_switch, audit_log, vault }
+}
+
+impl Fixture {
+    fn set_withdraw_enabled(&self, enabled: bool) -> Instruction {
+        ix(
+            accounts::SetInstructionEnabled { kill_switch: self.kill_switch, audit_log: self.audit_log, admin: self.admin.pubkey() },
+            instruction::SetInstructionEnabled { ix_disc: instruction::Withdraw::DISCRIMINATOR, enabled },
+        )
+    }
+
+    fn vault_override(&self, admin: Pubkey) -> accounts::AdminVaultOverride {
+        accounts::AdminVaultOverride { kill_switch: self.kill_switch, audit_log: self.audit_log, vault: self.vault, admin }
+    }
+
+    async fn log(&mut self) -> AdminAuditLog {
+        load(&mut self.context.banks_client, self.audit_log).await
+    }
+
+    async fn vault(&mut self) -> VaultSecure {
+        load(&mut self.context.banks_client, self.vault).await
+    }
+}
+
+#[tokio::test]
+async fn every_override_path_is_counted_and_emitted() {
+    let mut fixture = audited().await;
+    let admin = fixture.admin.pubkey();
+    let overrides = [
+        (fixture.set_withdraw_enabled(false), OverrideAction::Pause, fixture.kill_switch),
+        (fixture.set_withdraw_enabled(true), OverrideAction::Unpause, fixture.kill_switch),
+        (
+            ix(fixture.vault_override(admin), instruction::EmergencyWithdraw { amount: 200 }),
+            OverrideAction::EmergencyWithdraw,
+            fixture.vault,
+        ),
+        (ix(fixture.vault_override(admin), instruction::ResetVault {}), OverrideAction::ResetVault, fixture.vault),
+    ];
+
+    let mut prev_hash = [0u8; 32];
+    for (sequence, (instruction, action, target)) in (1..).zip(overrides) {
+        let data = send(&mut fixture.context, &fixture.admin, instruction).await.expect("admin override");
+        let event = entry(data);
+
+        let expected_hash = AdminAuditLog::chain(&prev_hash, sequence, action, &admin, &target);
+        assert_eq!((event.admin, event.action, event.target), (admin, action, target));
+        assert_eq!((event.sequence, event.hash), (sequence, expected_hash));
+
+        let log = fixture.log().await;
+        assert_eq!((log.override_count, log.last_hash), (sequence, expected_hash));
+        prev_hash = expected_hash;
+    }
+
+    let vault = fixture.vault().await;
+    assert_eq!((vault.balance, vault.total_deposits, vault.total_withdrawals), (0, 0, 0));
+}
+
+#[tokio::test]
+async fn non_admins_cannot_override_or_touch_the_log() {
+    let mut fixture = audited().await;
+    let intruder = fixture.intruder.pubkey();
+
+    let withdraw = ix(fixture.vault_override(intruder), instruction::EmergencyWithdraw { amount: VAULT_BALANCE });
+    let err = send(&mut fixture.context, &fixture.intruder, withdraw).await.expect_err("not the admin");
+
+    assert_eq!(err, custom_error(0, anchor_lang::error::ErrorCode::ConstraintHasOne));
+    assert_eq!(fixture.log().await.override_count, 0);
+    assert_eq!(fixture.vault().await.balance, VAULT_BALANCE);
+}
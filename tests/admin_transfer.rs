@@ -0,0 +1,142 @@
+//! Two-step admin transfer on the reinitialization program's config.
+//!
+//! The admin proposes, the nominee accepts; control never moves to a key
+//! that hasn't signed for it. `set_admin_vulnerable` hands it to anyone.
+
+use anchor_lang::error::ErrorCode as AnchorError;
+use anchor_lang::{AccountDeserialize, InstructionData, ToAccountMetas};
+use reinitialization::{accounts, instruction, ConfigSecure, ConfigVulnerable, ReinitError, ID};
+use security_tests::{anchor_account, custom_error, load, pda, program_test, send, system_account};
+use solana_program_test::BanksClient;
+use solana_sdk::{
+    instruction::{Instruction, InstructionError},
+    native_token::LAMPORTS_PER_SOL,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    system_program,
+    transaction::{Transaction, TransactionError},
+};
+
+/// A secure config created by `admin`, and two other funded keys.
+struct Fixture {
+    banks: BanksClient,
+    admin: Keypair,
+    nominee: Keypair,
+    stranger: Keypair,
+    config: Pubkey,
+}
+
+async fn fixture() -> Fixture {
+    let nominee = Keypair::new();
+    let stranger = Keypair::new();
+    let mut test = program_test();
+    test.add_account(nominee.pubkey(), system_account(LAMPORTS_PER_SOL));
+    test.add_account(stranger.pubkey(), system_account(LAMPORTS_PER_SOL));
+    let (banks, admin, _) = test.start().await;
+    let config = pda(&[b"config"], &ID);
+    let mut fixture = Fixture { banks, admin, nominee, stranger, config };
+
+    let init = ix(
+        accounts::InitializeConfigSecure { config, admin: fixture.admin.pubkey(), system_program: system_program::ID },
+        instruction::InitializeConfigSecure { fee_bps: 100 },
+    );
+    let admin = fixture.admin.insecure_clone();
+    fixture.send(init, &admin).await.expect("setup");
+    fixture
+}
+
+fn ix(accounts: impl ToAccountMetas, data: impl InstructionData) -> Instruction {
+    Instruction::new_with_bytes(ID, &data.data(), accounts.to_account_metas(None))
+}
+
+impl Fixture {
+    async fn send(&mut self, ix: Instruction, signer: &Keypair) -> Result<(), TransactionError> {
+        send(&mut self.banks, &[ix], &[signer]).await
+    }
+
+    async fn propose(&mut self, signer: &Keypair, new_admin: Pubkey) -> Result<(), TransactionError> {
+        let propose = ix(
+            accounts::ProposeAdminTransfer { config: self.config, admin: signer.pubkey() },
+            instruction::ProposeAdminTransfer { new_admin },
+        );
+        self.send(propose, signer).await
+    }
+
+    async fn accept(&mut self, signer: &Keypair) -> Result<(), TransactionError> {
+        let accept = ix(
+            accounts::AcceptAdminTransfer { config: self.config, new_admin: signer.pubkey() },
+            instruction::AcceptAdminTransfer {},
+        );
+        self.send(accept, signer).await
+    }
+
+    async fn config(&mut self) -> ConfigSecure {
+        load(&mut self.banks, self.config).await
+    }
+}
+
+#[tokio::test]
+async fn the_nominee_takes_over_once_it_accepts() {
+    let mut fixture = fixture().await;
+    let (admin, nominee) = (fixture.admin.insecure_clone(), fixture.nominee.insecure_clone());
+
+    fixture.propose(&admin, nominee.pubkey()).await.expect("the admin proposes");
+    let config = fixture.config().await;
+    assert_eq!((config.admin, config.pending_admin), (admin.pubkey(), Some(nominee.pubkey())));
+
+    fixture.accept(&nominee).await.expect("the nominee accepts");
+    let config = fixture.config().await;
+    assert_eq!((config.admin, config.pending_admin), (nominee.pubkey(), None));
+}
+
+#[tokio::test]
+async fn only_the_nominee_can_accept() {
+    let mut fixture = fixture().await;
+    let (admin, nominee, stranger) =
+        (fixture.admin.insecure_clone(), fixture.nominee.insecure_clone(), fixture.stranger.insecure_clone());
+    fixture.propose(&admin, nominee.pubkey()).await.expect("the admin proposes");
+
+    assert_eq!(fixture.accept(&stranger).await, Err(custom_error(0, ReinitError::NotPendingAdmin)));
+    assert_eq!(fixture.config().await.admin, admin.pubkey());
+}
+
+#[tokio::test]
+async fn nobody_can_accept_without_a_proposal() {
+    let mut fixture = fixture().await;
+    let (admin, nominee) = (fixture.admin.insecure_clone(), fixture.nominee.insecure_clone());
+
+    assert_eq!(fixture.accept(&nominee).await, Err(custom_error(0, ReinitError::NotPendingAdmin)));
+    // Not even the admin, accepting its own role
+    assert_eq!(fixture.accept(&admin).await, Err(custom_error(0, ReinitError::NotPendingAdmin)));
+}
+
+#[tokio::test]
+async fn only_the_admin_can_propose() {
+    let mut fixture = fixture().await;
+    let stranger = fixture.stranger.insecure_clone();
+
+    let err = fixture.propose(&stranger, stranger.pubkey()).await.expect_err("not the admin");
+
+    let has_one = InstructionError::Custom(AnchorError::ConstraintHasOne as u32);
+    assert_eq!(err, TransactionError::InstructionError(0, has_one));
+    assert_eq!(fixture.config().await.pending_admin, None);
+}
+
+#[tokio::test]
+async fn anyone_can_set_the_vulnerable_admin() {
+    let config = Pubkey::new_unique();
+    let state = ConfigVulnerable { admin: Pubkey::new_unique(), fee_bps: 100, is_initialized: true };
+    let mut test = program_test();
+    test.add_account(config, anchor_account(&state, ID));
+    let (mut banks, stranger, blockhash) = test.start().await;
+
+    let set_admin = ix(
+        accounts::SetAdminVulnerable { config, caller: stranger.pubkey() },
+        instruction::SetAdminVulnerable { new_admin: stranger.pubkey() },
+    );
+    let tx = Transaction::new_signed_with_payer(&[set_admin], Some(&stranger.pubkey()), &[&stranger], blockhash);
+    banks.process_transaction(tx).await.expect("no caller check");
+
+    let account = banks.get_account(config).await.unwrap().unwrap();
+    assert_eq!(ConfigVulnerable::try_deserialize(&mut account.data.as_slice()).unwrap().admin, stranger.pubkey());
+}
@@ -0,0 +1,983 @@
+//! Canonical bump audit.
+//!
+//! Runs every instruction that creates a PDA and checks the `bump` it stored
+//! is the one `find_program_address` returns. A PDA validated later with
+//! `bump = account.bump` is only unique if that stored bump is canonical; an
+//! instruction that saves a user-supplied or off-by-one bump quietly undoes
+//! the bump-canonicalization lesson for every account it creates.
+//!
+//! Adding a PDA-bearing account type means adding it to a row here -
+//! `every_pda_account_is_audited` fails until you do.
+
+use std::collections::BTreeSet;
+
+use anchor_lang::{AccountDeserialize, InstructionData, ToAccountMetas};
+use anchor_spl::token::spl_token;
+use security_tests::{account_types, anchor_account, mint_account, pda, program_test};
+use solana_sdk::{
+    account::Account,
+    instruction::Instruction,
+    pubkey::Pubkey,
+    signature::Signer,
+    system_program,
+    transaction::Transaction,
+};
+
+/// Account types that aren't PDAs and store no bump.
+const NOT_PDAS: &[(&str, &str)] = &[
+    ("reinitialization", "VaultVulnerable"),
+    ("reinitialization", "ConfigVulnerable"),
+];
+
+/// A bump field, and the PDA it must be canonical for.
+struct StoredBump {
+    /// `#[account]` type holding the field
+    account: &'static str,
+    field: &'static str,
+    /// Address of the account holding the field
+    holder: Pubkey,
+    read: fn(&[u8]) -> u8,
+    /// Seeds of the PDA the bump belongs to (usually `holder` itself)
+    seeds: Vec<Vec<u8>>,
+}
+
+/// Instructions that create PDAs, and the bumps they should have stored.
+///
+/// The payer signs for every role (authority, owner, admin, ...).
+struct Audit {
+    program_id: Pubkey,
+    accounts: Vec<(Pubkey, Account)>,
+    instructions: Vec<Instruction>,
+    bumps: Vec<StoredBump>,
+}
+
+/// `StoredBump` for `$field` of `$ty`, read from the account at `$holder`.
+macro_rules! stored_bump {
+    ($ty:ident . $field:ident at $holder:expr, seeds = [$($seed:expr),* $(,)?]) => {
+        StoredBump {
+            account: stringify!($ty),
+            field: stringify!($field),
+            holder: $holder,
+            read: |data: &[u8]| {
+                <$ty as AccountDeserialize>::try_deserialize(&mut &data[..])
+                    .expect(concat!("account deserializes as ", stringify!($ty)))
+                    .$field
+            },
+            seeds: vec![$(AsRef::<[u8]>::as_ref(&$seed).to_vec()),*],
+        }
+    };
+}
+
+fn ix(program_id: Pubkey, accounts: impl ToAccountMetas, data: impl InstructionData) -> Instruction {
+    Instruction::new_with_bytes(program_id, &data.data(), accounts.to_account_metas(None))
+}
+
+/// (program, what the row creates, audit)
+type Row = (&'static str, &'static str, fn(&Pubkey) -> Audit);
+
+fn audits() -> Vec<Row> {
+    vec![
+        ("missing_signer_check", "vault, multisig and delegate", signer::initialize),
+        ("account_validation", "config, pool, deposit, org chain", validation::initialize),
+        ("integer_overflow", "every init instruction", overflow::initialize),
+        ("arbitrary_cpi", "vault, treasury, swap commitment", cpi::initialize),
+        ("reinitialization", "init constraint", reinit::initialize_anchor),
+        ("reinitialization", "manual create_account", reinit::initialize_robust),
+        ("reinitialization", "vault tombstone", reinit::close_vault),
+        ("reinitialization", "init_if_needed, naive", reinit::deposit_or_init_vulnerable),
+        ("reinitialization", "init_if_needed, guarded", reinit::deposit_or_init_secure),
+        ("reinitialization", "vault with notes", reinit::initialize_vault_v2),
+        ("type_cosplay", "every init instruction", cosplay::initialize),
+        ("closing_accounts", "user account and profile", closing::initialize),
+        ("closing_accounts", "tombstones, config, close counter", closing::close_with_tombstones),
+        ("closing_accounts", "recreated profile", closing::recreate_profile),
+        ("closing_accounts", "reopened profile", closing::reopen),
+        ("escrow", "escrow", escrow::initialize),
+        ("insecure_randomness", "lottery", randomness::initialize),
+        ("native_sol_vault", "vault", sol_vault::initialize),
+        ("upgrade_authority", "config", upgrade::initialize_config),
+        ("flash_loan_guard", "lending pool and position", flash_loan::open_position),
+    ]
+}
+
+#[tokio::test]
+async fn stored_bumps_are_canonical() {
+    let mut failures = Vec::new();
+
+    for (program, creates, audit) in audits() {
+        // Preloaded accounts may depend on the payer, which only exists
+        // once the bank has started
+        let mut context = program_test().start_with_context().await;
+        let payer = context.payer.insecure_clone();
+        let audit = audit(&payer.pubkey());
+        for (key, account) in &audit.accounts {
+            context.set_account(key, &account.clone().into());
+        }
+        let banks = &mut context.banks_client;
+
+        for instruction in audit.instructions {
+            let blockhash = banks.get_latest_blockhash().await.unwrap();
+            let tx = Transaction::new_signed_with_payer(&[instruction], Some(&payer.pubkey()), &[&payer], blockhash);
+            banks
+                .process_transaction(tx)
+                .await
+                .unwrap_or_else(|err| panic!("{} ({}): setup failed: {}", program, creates, err));
+        }
+
+        for bump in audit.bumps {
+            let seeds: Vec<&[u8]> = bump.seeds.iter().map(Vec::as_slice).collect();
+            let (_, canonical) = Pubkey::find_program_address(&seeds, &audit.program_id);
+            let holder = banks
+                .get_account(bump.holder)
+                .await
+                .unwrap()
+                .unwrap_or_else(|| panic!("{}::{} was not created", program, bump.account));
+            let stored = (bump.read)(&holder.data);
+            if stored != canonical {
+                failures.push(format!(
+                    "{}::{}.{} is {}, canonical bump is {}",
+                    program, bump.account, bump.field, stored, canonical
+                ));
+            }
+        }
+    }
+
+    assert!(failures.is_empty(), "non-canonical bumps stored:\n  {}", failures.join("\n  "));
+}
+
+#[test]
+fn every_pda_account_is_audited() {
+    let audited: BTreeSet<(&str, &str)> = audits()
+        .into_iter()
+        .flat_map(|(program, _, audit)| {
+            audit(&Pubkey::default()).bumps.into_iter().map(move |bump| (program, bump.account))
+        })
+        .collect();
+    let expected: BTreeSet<(&str, &str)> = account_types()
+        .into_iter()
+        .map(|account| (account.program, account.name))
+        .filter(|account| !NOT_PDAS.contains(account))
+        .collect();
+
+    let missing: Vec<_> = expected.difference(&audited).collect();
+    assert!(missing.is_empty(), "account types with no audited bump: {:?}", missing);
+}
+
+// ============================================================================
+// AUDITS
+// ============================================================================
+
+mod signer {
+    use super::*;
+    use missing_signer_check::{accounts, instruction, Delegate, MultisigVault, Vault, ID};
+
+    pub fn initialize(payer: &Pubkey) -> Audit {
+        let vault = pda(&[b"vault", payer.as_ref()], &ID);
+        let multisig = pda(&[b"multisig", payer.as_ref()], &ID);
+        let delegate = Pubkey::new_unique();
+        let delegate_record = pda(&[b"delegate", vault.as_ref(), delegate.as_ref()], &ID);
+        Audit {
+            program_id: ID,
+            accounts: vec![],
+            instructions: vec![
+                ix(
+                    ID,
+                    accounts::InitializeVault { vault, authority: *payer, system_program: system_program::ID },
+                    instruction::InitializeVault { initial_balance: 1_000, withdrawal_limit: 100 },
+                ),
+                ix(
+                    ID,
+                    accounts::InitializeMultisigVault { multisig, creator: *payer, system_program: system_program::ID },
+                    instruction::InitializeMultisigVault {
+                        signers: [*payer, Pubkey::new_unique(), Pubkey::new_unique()],
+                        threshold: 2,
+                        timelock_secs: 0,
+                        initial_balance: 1_000,
+                    },
+                ),
+                ix(
+                    ID,
+                    accounts::ApproveDelegate { vault, delegate_record, authority: *payer, system_program: system_program::ID },
+                    instruction::ApproveDelegate { delegate, expires_at: i64::MAX },
+                ),
+            ],
+            bumps: vec![
+                stored_bump!(Vault.bump at vault, seeds = [b"vault", payer]),
+                stored_bump!(MultisigVault.bump at multisig, seeds = [b"multisig", payer]),
+                stored_bump!(Delegate.bump at delegate_record, seeds = [b"delegate", vault, delegate]),
+            ],
+        }
+    }
+}
+
+mod validation {
+    use super::*;
+    use account_validation::{accounts, instruction, Config, Member, Org, Pool, PoolVault, Team, UserAccount, UserDeposit, ID};
+
+    pub fn initialize(payer: &Pubkey) -> Audit {
+        let config = pda(&[b"config"], &ID);
+        let pool = pda(&[b"pool", payer.as_ref()], &ID);
+        let pool_vault = pda(&[b"pool_vault", pool.as_ref()], &ID);
+        let mint = Pubkey::new_from_array([0x02; 32]);
+        let user_account = pda(&[b"user", payer.as_ref()], &ID);
+        let user_deposit = pda(&[b"user_deposit", pool.as_ref(), payer.as_ref()], &ID);
+        let org = pda(&[b"org", payer.as_ref()], &ID);
+        let team = pda(&[b"team", org.as_ref(), payer.as_ref()], &ID);
+        let member = pda(&[b"member", team.as_ref(), payer.as_ref()], &ID);
+        Audit {
+            program_id: ID,
+            accounts: vec![(mint, mint_account(*payer, 6))],
+            instructions: vec![
+                ix(
+                    ID,
+                    accounts::InitializeConfig { config, admin: *payer, system_program: system_program::ID },
+                    instruction::InitializeConfig { fee_bps: 100, max_positions: 1, dust_threshold: 0 },
+                ),
+                ix(
+                    ID,
+                    accounts::InitializePool { config, pool, authority: *payer, system_program: system_program::ID },
+                    instruction::InitializePool {
+                        reward_rate: 10,
+                        global_tvl_cap: u64::MAX,
+                        lock_secs: 0,
+                        min_hold_secs: 0,
+                        early_exit_bps: 0,
+                        deposit_mint: Pubkey::default(),
+                        reward_mint: Pubkey::default(),
+                    },
+                ),
+                ix(
+                    ID,
+                    accounts::InitializePoolVault { pool, pool_vault, mint, authority: *payer, system_program: system_program::ID },
+                    instruction::InitializePoolVault {},
+                ),
+                ix(
+                    ID,
+                    accounts::InitializeUserAccount { user_account, user: *payer, system_program: system_program::ID },
+                    instruction::InitializeUserAccount {},
+                ),
+                ix(
+                    ID,
+                    accounts::OpenDeposit {
+                        config,
+                        pool,
+                        user_account,
+                        user_deposit,
+                        user: *payer,
+                        system_program: system_program::ID,
+                    },
+                    instruction::OpenDeposit {},
+                ),
+                ix(
+                    ID,
+                    accounts::InitializeOrg { org, authority: *payer, system_program: system_program::ID },
+                    instruction::InitializeOrg {},
+                ),
+                ix(
+                    ID,
+                    accounts::InitializeTeam {
+                        org,
+                        team,
+                        authority: *payer,
+                        lead: *payer,
+                        system_program: system_program::ID,
+                    },
+                    instruction::InitializeTeam {},
+                ),
+                ix(
+                    ID,
+                    accounts::AddMember {
+                        team,
+                        member,
+                        wallet: *payer,
+                        lead: *payer,
+                        system_program: system_program::ID,
+                    },
+                    instruction::AddMember {},
+                ),
+            ],
+            bumps: vec![
+                stored_bump!(Config.bump at config, seeds = [b"config"]),
+                stored_bump!(Pool.bump at pool, seeds = [b"pool", payer]),
+                stored_bump!(PoolVault.bump at pool_vault, seeds = [b"pool_vault", pool]),
+                stored_bump!(UserAccount.bump at user_account, seeds = [b"user", payer]),
+                stored_bump!(UserDeposit.bump at user_deposit, seeds = [b"user_deposit", pool, payer]),
+                stored_bump!(Org.bump at org, seeds = [b"org", payer]),
+                stored_bump!(Team.bump at team, seeds = [b"team", org, payer]),
+                stored_bump!(Member.bump at member, seeds = [b"member", team, payer]),
+            ],
+        }
+    }
+}
+
+mod overflow {
+    use super::*;
+    use integer_overflow::{
+        accounts, instruction, Config, FeePosition, InterestAccount, LpPool, LpPosition, ReferralAccount, RewardMinter,
+        UserAccount, Vault, WithdrawalRecord, ID,
+    };
+
+    pub fn initialize(payer: &Pubkey) -> Audit {
+        let vault = pda(&[b"vault", payer.as_ref()], &ID);
+        let user_account = pda(&[b"user", payer.as_ref()], &ID);
+        let config = pda(&[b"config"], &ID);
+        let interest_account = pda(&[b"interest", payer.as_ref()], &ID);
+        let record = pda(&[b"record", payer.as_ref()], &ID);
+        let lp_pool = pda(&[b"lp_pool", payer.as_ref()], &ID);
+        let lp_position = pda(&[b"lp_position", lp_pool.as_ref(), payer.as_ref()], &ID);
+        let referral_account = pda(&[b"referral", payer.as_ref()], &ID);
+        let fee_position = pda(&[b"fee_position", vault.as_ref(), payer.as_ref()], &ID);
+        let mint = Pubkey::new_from_array([0x03; 32]);
+        let minter = pda(&[b"mint_authority", mint.as_ref()], &ID);
+        Audit {
+            program_id: ID,
+            accounts: vec![(mint, mint_account(*payer, 6))],
+            instructions: vec![
+                ix(
+                    ID,
+                    accounts::InitializeVault { vault, authority: *payer, system_program: system_program::ID },
+                    instruction::InitializeVault {},
+                ),
+                ix(
+                    ID,
+                    accounts::InitializeUserAccount { user_account, owner: *payer, system_program: system_program::ID },
+                    instruction::InitializeUserAccount { initial_balance: 0 },
+                ),
+                ix(
+                    ID,
+                    accounts::InitializeConfig { config, admin: *payer, system_program: system_program::ID },
+                    instruction::InitializeConfig { price: 10, fee_bps: 100, referral_bps: 2_000 },
+                ),
+                ix(
+                    ID,
+                    accounts::InitializeInterestAccount {
+                        interest_account,
+                        owner: *payer,
+                        system_program: system_program::ID,
+                    },
+                    instruction::InitializeInterestAccount { principal: 1_000, rate_per_second: 1 },
+                ),
+                ix(
+                    ID,
+                    accounts::InitializeRecord { record, user: *payer, system_program: system_program::ID },
+                    instruction::InitializeRecord {},
+                ),
+                ix(
+                    ID,
+                    accounts::InitializeLpPool {
+                        pool: lp_pool,
+                        position: lp_position,
+                        authority: *payer,
+                        system_program: system_program::ID,
+                    },
+                    instruction::InitializeLpPool { reserve: 1_000, total_lp: 1_000 },
+                ),
+                ix(
+                    ID,
+                    accounts::InitializeReferralAccount {
+                        referral_account,
+                        referrer: *payer,
+                        payer: *payer,
+                        system_program: system_program::ID,
+                    },
+                    instruction::InitializeReferralAccount {},
+                ),
+                ix(
+                    ID,
+                    accounts::InitializeFeePosition {
+                        vault,
+                        position: fee_position,
+                        owner: *payer,
+                        system_program: system_program::ID,
+                    },
+                    instruction::InitializeFeePosition {},
+                ),
+                ix(
+                    ID,
+                    accounts::InitializeRewardMinter {
+                        minter,
+                        mint,
+                        admin: *payer,
+                        token_program: spl_token::ID,
+                        system_program: system_program::ID,
+                    },
+                    instruction::InitializeRewardMinter { max_supply: 1_000 },
+                ),
+            ],
+            bumps: vec![
+                stored_bump!(Vault.bump at vault, seeds = [b"vault", payer]),
+                stored_bump!(UserAccount.bump at user_account, seeds = [b"user", payer]),
+                stored_bump!(Config.bump at config, seeds = [b"config"]),
+                stored_bump!(InterestAccount.bump at interest_account, seeds = [b"interest", payer]),
+                stored_bump!(WithdrawalRecord.bump at record, seeds = [b"record", payer]),
+                stored_bump!(LpPool.bump at lp_pool, seeds = [b"lp_pool", payer]),
+                stored_bump!(LpPosition.bump at lp_position, seeds = [b"lp_position", lp_pool, payer]),
+                stored_bump!(ReferralAccount.bump at referral_account, seeds = [b"referral", payer]),
+                stored_bump!(FeePosition.bump at fee_position, seeds = [b"fee_position", vault, payer]),
+                stored_bump!(RewardMinter.bump at minter, seeds = [b"mint_authority", mint]),
+            ],
+        }
+    }
+}
+
+mod cpi {
+    use super::*;
+    use arbitrary_cpi::{accounts, instruction, SwapCommitment, Treasury, Vault, ID};
+
+    fn mint() -> Pubkey {
+        Pubkey::new_from_array([0x4d; 32])
+    }
+
+    pub fn initialize(payer: &Pubkey) -> Audit {
+        let vault = pda(&[b"vault", payer.as_ref()], &ID);
+        let vault_authority = pda(&[b"vault_authority"], &ID);
+        let treasury = pda(&[b"treasury"], &ID);
+        let swap_commitment = pda(&[b"swap_commitment", payer.as_ref()], &ID);
+        Audit {
+            program_id: ID,
+            accounts: vec![(mint(), mint_account(Pubkey::new_from_array([0x4e; 32]), 6))],
+            instructions: vec![
+                ix(
+                    ID,
+                    accounts::InitializeVault {
+                        vault,
+                        vault_authority,
+                        mint: mint(),
+                        authority: *payer,
+                        system_program: system_program::ID,
+                    },
+                    instruction::InitializeVault {},
+                ),
+                ix(
+                    ID,
+                    accounts::InitializeTreasury { treasury, admin: *payer, system_program: system_program::ID },
+                    instruction::InitializeTreasury { reward_amount: 100 },
+                ),
+                ix(
+                    ID,
+                    accounts::CommitSwap { swap_commitment, user: *payer, system_program: system_program::ID },
+                    instruction::CommitSwap { commitment: [0; 32] },
+                ),
+            ],
+            bumps: vec![
+                stored_bump!(Vault.bump at vault, seeds = [b"vault", payer]),
+                stored_bump!(Vault.vault_authority_bump at vault, seeds = [b"vault_authority"]),
+                stored_bump!(Treasury.bump at treasury, seeds = [b"treasury"]),
+                stored_bump!(SwapCommitment.bump at swap_commitment, seeds = [b"swap_commitment", payer]),
+            ],
+        }
+    }
+}
+
+mod reinit {
+    use super::*;
+    use reinitialization::{
+        accounts, instruction, AdminAuditLog, ConfigSecure, DisabledInstructions, VaultCloses, VaultSecure,
+        VaultTombstone, VaultV2, ID,
+    };
+
+    pub fn initialize_anchor(payer: &Pubkey) -> Audit {
+        let vault = pda(&[b"vault", payer.as_ref()], &ID);
+        let config = pda(&[b"config"], &ID);
+        let kill_switch = pda(&[b"disabled_instructions"], &ID);
+        let audit_log = pda(&[b"admin_audit"], &ID);
+        Audit {
+            program_id: ID,
+            accounts: vec![],
+            instructions: vec![
+                ix(
+                    ID,
+                    accounts::InitializeSecureAnchor { vault, authority: *payer, system_program: system_program::ID },
+                    instruction::InitializeSecureAnchor {},
+                ),
+                ix(
+                    ID,
+                    accounts::InitializeConfigSecure { config, admin: *payer, system_program: system_program::ID },
+                    instruction::InitializeConfigSecure { fee_bps: 100 },
+                ),
+                ix(
+                    ID,
+                    accounts::InitializeKillSwitch { kill_switch, admin: *payer, system_program: system_program::ID },
+                    instruction::InitializeKillSwitch {},
+                ),
+                ix(
+                    ID,
+                    accounts::InitializeAdminAuditLog {
+                        kill_switch,
+                        audit_log,
+                        admin: *payer,
+                        system_program: system_program::ID,
+                    },
+                    instruction::InitializeAdminAuditLog {},
+                ),
+            ],
+            bumps: vec![
+                stored_bump!(VaultSecure.bump at vault, seeds = [b"vault", payer]),
+                stored_bump!(ConfigSecure.bump at config, seeds = [b"config"]),
+                stored_bump!(DisabledInstructions.bump at kill_switch, seeds = [b"disabled_instructions"]),
+                stored_bump!(AdminAuditLog.bump at audit_log, seeds = [b"admin_audit"]),
+            ],
+        }
+    }
+
+    pub fn close_vault(payer: &Pubkey) -> Audit {
+        let vault = pda(&[b"vault", payer.as_ref()], &ID);
+        let closes = pda(&[b"vault_closes", vault.as_ref()], &ID);
+        let first = 0u64.to_le_bytes();
+        let tombstone = pda(&[b"vault_tombstone", vault.as_ref(), &first], &ID);
+        Audit {
+            program_id: ID,
+            accounts: vec![],
+            instructions: vec![
+                ix(
+                    ID,
+                    accounts::InitializeSecureAnchor { vault, authority: *payer, system_program: system_program::ID },
+                    instruction::InitializeSecureAnchor {},
+                ),
+                ix(
+                    ID,
+                    accounts::CloseVault {
+                        vault,
+                        closes,
+                        tombstone,
+                        recipient: *payer,
+                        authority: *payer,
+                        system_program: system_program::ID,
+                    },
+                    instruction::CloseVault {},
+                ),
+            ],
+            bumps: vec![
+                stored_bump!(VaultCloses.bump at closes, seeds = [b"vault_closes", vault]),
+                stored_bump!(VaultTombstone.bump at tombstone, seeds = [b"vault_tombstone", vault, first]),
+            ],
+        }
+    }
+
+    /// The first deposit creates the vault; the second must leave its bump alone
+    fn deposit_or_init(payer: &Pubkey, first: impl InstructionData, second: impl InstructionData) -> Audit {
+        let vault = pda(&[b"vault", payer.as_ref()], &ID);
+        let (kill_switch, bump) = Pubkey::find_program_address(&[b"disabled_instructions"], &ID);
+        let state = DisabledInstructions { admin: *payer, disabled: vec![], bump };
+        let accounts = || accounts::DepositOrInit {
+            vault,
+            owner: *payer,
+            kill_switch,
+            depositor: *payer,
+            system_program: system_program::ID,
+        };
+        Audit {
+            program_id: ID,
+            accounts: vec![(kill_switch, anchor_account(&state, ID))],
+            instructions: vec![ix(ID, accounts(), first), ix(ID, accounts(), second)],
+            bumps: vec![stored_bump!(VaultSecure.bump at vault, seeds = [b"vault", payer])],
+        }
+    }
+
+    pub fn deposit_or_init_vulnerable(payer: &Pubkey) -> Audit {
+        deposit_or_init(
+            payer,
+            instruction::DepositOrInitVulnerable { amount: 1 },
+            instruction::DepositOrInitVulnerable { amount: 2 },
+        )
+    }
+
+    pub fn deposit_or_init_secure(payer: &Pubkey) -> Audit {
+        deposit_or_init(
+            payer,
+            instruction::DepositOrInitSecure { amount: 1 },
+            instruction::DepositOrInitSecure { amount: 2 },
+        )
+    }
+
+    pub fn initialize_vault_v2(payer: &Pubkey) -> Audit {
+        let vault = pda(&[b"vault_v2", payer.as_ref()], &ID);
+        Audit {
+            program_id: ID,
+            accounts: vec![],
+            instructions: vec![ix(
+                ID,
+                accounts::InitializeVaultV2 { vault, authority: *payer, system_program: system_program::ID },
+                instruction::InitializeVaultV2 { notes: b"notes".to_vec() },
+            )],
+            bumps: vec![stored_bump!(VaultV2.bump at vault, seeds = [b"vault_v2", payer])],
+        }
+    }
+
+    /// Same address as `initialize_anchor`'s vault, so it needs its own bank
+    pub fn initialize_robust(payer: &Pubkey) -> Audit {
+        let vault = pda(&[b"vault", payer.as_ref()], &ID);
+        Audit {
+            program_id: ID,
+            accounts: vec![],
+            instructions: vec![ix(
+                ID,
+                accounts::InitializeRobust { vault, authority: *payer, system_program: system_program::ID },
+                instruction::InitializeRobust {},
+            )],
+            bumps: vec![stored_bump!(VaultSecure.bump at vault, seeds = [b"vault", payer])],
+        }
+    }
+}
+
+mod cosplay {
+    use super::*;
+    use type_cosplay::{accounts, instruction, AdminConfig, RewardVault, StakeAccount, TaggedAccount, UserAccount, ID};
+
+    pub fn initialize(payer: &Pubkey) -> Audit {
+        let admin_config = pda(&[b"admin_config"], &ID);
+        let user_account = pda(&[b"user", payer.as_ref()], &ID);
+        let reward_vault = pda(&[b"reward_vault", payer.as_ref()], &ID);
+        let stake_account = pda(&[b"stake", payer.as_ref()], &ID);
+        let tagged = pda(&[b"tagged", payer.as_ref()], &ID);
+        Audit {
+            program_id: ID,
+            accounts: vec![],
+            instructions: vec![
+                ix(
+                    ID,
+                    accounts::InitializeAdminConfig { admin_config, admin: *payer, system_program: system_program::ID },
+                    instruction::InitializeAdminConfig {},
+                ),
+                ix(
+                    ID,
+                    accounts::InitializeUserAccount { user_account, owner: *payer, system_program: system_program::ID },
+                    instruction::InitializeUserAccount {},
+                ),
+                ix(
+                    ID,
+                    accounts::InitializeRewardVault {
+                        reward_vault,
+                        authority: *payer,
+                        system_program: system_program::ID,
+                    },
+                    instruction::InitializeRewardVault { initial_balance: 1_000 },
+                ),
+                ix(
+                    ID,
+                    accounts::InitializeStakeAccount { stake_account, owner: *payer, system_program: system_program::ID },
+                    instruction::InitializeStakeAccount { amount: 1_000 },
+                ),
+                ix(
+                    ID,
+                    accounts::InitializeTaggedAccount { tagged, owner: *payer, system_program: system_program::ID },
+                    instruction::InitializeTaggedAccount {},
+                ),
+            ],
+            bumps: vec![
+                stored_bump!(AdminConfig.bump at admin_config, seeds = [b"admin_config"]),
+                stored_bump!(UserAccount.bump at user_account, seeds = [b"user", payer]),
+                stored_bump!(RewardVault.bump at reward_vault, seeds = [b"reward_vault", payer]),
+                stored_bump!(StakeAccount.bump at stake_account, seeds = [b"stake", payer]),
+                stored_bump!(TaggedAccount.bump at tagged, seeds = [b"tagged", payer]),
+            ],
+        }
+    }
+}
+
+mod closing {
+    use super::*;
+    use closing_accounts::{
+        accounts, instruction, AccountTombstone, ClosesByRecipient, Config, ProfileTombstone, UserAccount, UserCloses,
+        UserProfile, ID,
+    };
+
+    fn reward_mint() -> Pubkey {
+        Pubkey::new_from_array([0x52; 32])
+    }
+
+    /// Config with no profile cooldown, so a closed profile can be recreated at once
+    fn initialize_config(payer: &Pubkey) -> Instruction {
+        ix(
+            ID,
+            accounts::InitializeConfig {
+                config: pda(&[b"config"], &ID),
+                reward_mint: reward_mint(),
+                reward_authority: pda(&[b"reward_authority"], &ID),
+                admin: *payer,
+                system_program: system_program::ID,
+            },
+            instruction::InitializeConfig {
+                fee_bps: 100,
+                profile_cooldown_secs: 0,
+                reopen_window_secs: 3_600,
+                max_closes_per_window: 10,
+                close_window_secs: 3_600,
+            },
+        )
+    }
+
+    fn mint_accounts() -> Vec<(Pubkey, Account)> {
+        vec![(reward_mint(), mint_account(pda(&[b"reward_authority"], &ID), 6))]
+    }
+
+    fn initialize_user_account(payer: &Pubkey) -> Instruction {
+        let user_account = pda(&[b"user", payer.as_ref()], &ID);
+        ix(
+            ID,
+            accounts::InitializeUserAccount {
+                user_account,
+                owner: *payer,
+                system_program: system_program::ID,
+            },
+            instruction::InitializeUserAccount {},
+        )
+    }
+
+    fn initialize_profile(payer: &Pubkey) -> Instruction {
+        ix(
+            ID,
+            accounts::InitializeProfile {
+                profile: pda(&[b"profile", payer.as_ref()], &ID),
+                tombstone: pda(&[b"tombstone", payer.as_ref()], &ID),
+                owner: *payer,
+                system_program: system_program::ID,
+            },
+            instruction::InitializeProfile {},
+        )
+    }
+
+    fn close_profile(payer: &Pubkey) -> Instruction {
+        ix(
+            ID,
+            accounts::CloseProfileSecure {
+                profile: pda(&[b"profile", payer.as_ref()], &ID),
+                tombstone: pda(&[b"tombstone", payer.as_ref()], &ID),
+                recipient: *payer,
+                owner: *payer,
+                system_program: system_program::ID,
+            },
+            instruction::CloseProfileSecure {},
+        )
+    }
+
+    pub fn initialize(payer: &Pubkey) -> Audit {
+        let user_account = pda(&[b"user", payer.as_ref()], &ID);
+        let profile = pda(&[b"profile", payer.as_ref()], &ID);
+        Audit {
+            program_id: ID,
+            accounts: vec![],
+            instructions: vec![initialize_user_account(payer), initialize_profile(payer)],
+            bumps: vec![
+                stored_bump!(UserAccount.bump at user_account, seeds = [b"user", payer]),
+                stored_bump!(UserProfile.bump at profile, seeds = [b"profile", payer]),
+            ],
+        }
+    }
+
+    pub fn close_with_tombstones(payer: &Pubkey) -> Audit {
+        let config = pda(&[b"config"], &ID);
+        let user_account = pda(&[b"user", payer.as_ref()], &ID);
+        let user_closes = pda(&[b"user_closes", user_account.as_ref()], &ID);
+        let first = 0u64.to_le_bytes();
+        let user_tombstone = pda(&[b"user_tombstone", user_account.as_ref(), &first], &ID);
+        let profile_tombstone = pda(&[b"tombstone", payer.as_ref()], &ID);
+        let closes = pda(&[b"closes", payer.as_ref()], &ID);
+        Audit {
+            program_id: ID,
+            accounts: mint_accounts(),
+            instructions: vec![
+                initialize_config(payer),
+                initialize_user_account(payer),
+                ix(
+                    ID,
+                    accounts::InitializeCloseCounter {
+                        closes,
+                        recipient: *payer,
+                        payer: *payer,
+                        system_program: system_program::ID,
+                    },
+                    instruction::InitializeCloseCounter {},
+                ),
+                ix(
+                    ID,
+                    accounts::CloseSecure {
+                        user_account,
+                        user_closes,
+                        tombstone: user_tombstone,
+                        config,
+                        closes,
+                        recipient: *payer,
+                        owner: *payer,
+                        system_program: system_program::ID,
+                    },
+                    instruction::CloseSecure {},
+                ),
+                initialize_profile(payer),
+                close_profile(payer),
+            ],
+            bumps: vec![
+                stored_bump!(Config.bump at config, seeds = [b"config"]),
+                stored_bump!(Config.reward_authority_bump at config, seeds = [b"reward_authority"]),
+                stored_bump!(UserCloses.bump at user_closes, seeds = [b"user_closes", user_account]),
+                stored_bump!(AccountTombstone.bump at user_tombstone, seeds = [b"user_tombstone", user_account, first]),
+                stored_bump!(ClosesByRecipient.bump at closes, seeds = [b"closes", payer]),
+                stored_bump!(ProfileTombstone.bump at profile_tombstone, seeds = [b"tombstone", payer]),
+            ],
+        }
+    }
+
+    pub fn recreate_profile(payer: &Pubkey) -> Audit {
+        let profile = pda(&[b"profile", payer.as_ref()], &ID);
+        Audit {
+            program_id: ID,
+            accounts: mint_accounts(),
+            instructions: vec![
+                initialize_config(payer),
+                initialize_profile(payer),
+                close_profile(payer),
+                ix(
+                    ID,
+                    accounts::RecreateProfile {
+                        config: pda(&[b"config"], &ID),
+                        tombstone: pda(&[b"tombstone", payer.as_ref()], &ID),
+                        profile,
+                        owner: *payer,
+                        system_program: system_program::ID,
+                    },
+                    instruction::RecreateProfile {},
+                ),
+            ],
+            bumps: vec![stored_bump!(UserProfile.bump at profile, seeds = [b"profile", payer])],
+        }
+    }
+
+    pub fn reopen(payer: &Pubkey) -> Audit {
+        let profile = pda(&[b"profile", payer.as_ref()], &ID);
+        Audit {
+            program_id: ID,
+            accounts: mint_accounts(),
+            instructions: vec![
+                initialize_config(payer),
+                initialize_profile(payer),
+                close_profile(payer),
+                ix(
+                    ID,
+                    accounts::Reopen {
+                        config: pda(&[b"config"], &ID),
+                        tombstone: pda(&[b"tombstone", payer.as_ref()], &ID),
+                        profile,
+                        owner: *payer,
+                        system_program: system_program::ID,
+                    },
+                    instruction::Reopen {},
+                ),
+            ],
+            bumps: vec![stored_bump!(UserProfile.bump at profile, seeds = [b"profile", payer])],
+        }
+    }
+}
+
+mod escrow {
+    use super::*;
+    use ::escrow::{accounts, instruction, Escrow, ID};
+
+    pub fn initialize(payer: &Pubkey) -> Audit {
+        let escrow = pda(&[b"escrow", payer.as_ref()], &ID);
+        Audit {
+            program_id: ID,
+            accounts: vec![],
+            instructions: vec![ix(
+                ID,
+                accounts::InitializeEscrow {
+                    escrow,
+                    depositor: *payer,
+                    beneficiary: Pubkey::new_from_array([0x42; 32]),
+                    arbiter: Pubkey::new_from_array([0x41; 32]),
+                    system_program: system_program::ID,
+                },
+                instruction::InitializeEscrow { amount: 1_000, release_after: 0 },
+            )],
+            bumps: vec![stored_bump!(Escrow.bump at escrow, seeds = [b"escrow", payer])],
+        }
+    }
+}
+
+mod randomness {
+    use super::*;
+    use insecure_randomness::{accounts, instruction, Lottery, ID};
+
+    pub fn initialize(payer: &Pubkey) -> Audit {
+        let lottery = pda(&[b"lottery", payer.as_ref()], &ID);
+        Audit {
+            program_id: ID,
+            accounts: vec![],
+            instructions: vec![ix(
+                ID,
+                accounts::InitializeLottery { lottery, authority: *payer, system_program: system_program::ID },
+                instruction::InitializeLottery { commitment: [0x5e; 32] },
+            )],
+            bumps: vec![stored_bump!(Lottery.bump at lottery, seeds = [b"lottery", payer])],
+        }
+    }
+}
+
+mod sol_vault {
+    use super::*;
+    use native_sol_vault::{accounts, instruction, SolVault, ID};
+
+    pub fn initialize(payer: &Pubkey) -> Audit {
+        let vault = pda(&[b"sol_vault", payer.as_ref()], &ID);
+        Audit {
+            program_id: ID,
+            accounts: vec![],
+            instructions: vec![ix(
+                ID,
+                accounts::InitializeSolVault { vault, authority: *payer, system_program: system_program::ID },
+                instruction::InitializeSolVault {},
+            )],
+            bumps: vec![stored_bump!(SolVault.bump at vault, seeds = [b"sol_vault", payer])],
+        }
+    }
+}
+
+mod upgrade {
+    use super::*;
+    use upgrade_authority::{accounts, instruction, ProgramConfig, ID};
+
+    /// `bootstrap_admin` stores the same bump but needs the payer as upgrade
+    /// authority, which the preloaded ProgramData can't name
+    pub fn initialize_config(payer: &Pubkey) -> Audit {
+        let config = pda(&[b"config"], &ID);
+        Audit {
+            program_id: ID,
+            accounts: vec![],
+            instructions: vec![ix(
+                ID,
+                accounts::InitializeConfigVulnerable { config, payer: *payer, system_program: system_program::ID },
+                instruction::InitializeConfigVulnerable { admin: *payer },
+            )],
+            bumps: vec![stored_bump!(ProgramConfig.bump at config, seeds = [b"config"])],
+        }
+    }
+}
+
+mod flash_loan {
+    use super::*;
+    use flash_loan_guard::{accounts, instruction, LendingPool, Position, ID};
+
+    pub fn open_position(payer: &Pubkey) -> Audit {
+        let pool = pda(&[b"lending_pool", payer.as_ref()], &ID);
+        let position = pda(&[b"position", pool.as_ref(), payer.as_ref()], &ID);
+        Audit {
+            program_id: ID,
+            accounts: vec![],
+            instructions: vec![
+                ix(
+                    ID,
+                    accounts::InitializeLendingPool { pool, authority: *payer, system_program: system_program::ID },
+                    instruction::InitializeLendingPool {},
+                ),
+                ix(
+                    ID,
+                    accounts::OpenPosition { pool, position, owner: *payer, system_program: system_program::ID },
+                    instruction::OpenPosition {},
+                ),
+            ],
+            bumps: vec![
+                stored_bump!(LendingPool.bump at pool, seeds = [b"lending_pool", payer]),
+                stored_bump!(Position.bump at position, seeds = [b"position", pool, payer]),
+            ],
+        }
+    }
+}
@@ -0,0 +1,111 @@
+//! `audit_vault` in the reinitialization program.
+//!
+//! The audit never fails on a corrupted vault: it returns a bitmask of
+//! `audit_flags` through return data, 0 for a healthy vault. Only an
+//! account that isn't a `VaultSecure` at all is rejected.
+
+use anchor_lang::error::ErrorCode as AnchorError;
+use anchor_lang::{InstructionData, ToAccountMetas};
+use reinitialization::{accounts, audit_flags, instruction, VaultSecure, ID};
+use security_tests::{anchor_account, custom_error, program_test, send_for_u64, system_account};
+use solana_sdk::{
+    instruction::Instruction,
+    native_token::LAMPORTS_PER_SOL,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    transaction::TransactionError,
+};
+
+/// A vault at its canonical address whose books add up: 100 in, 40 out
+fn healthy() -> (Pubkey, VaultSecure) {
+    let authority = Pubkey::new_unique();
+    let (vault, bump) = Pubkey::find_program_address(&[b"vault", authority.as_ref()], &ID);
+    (vault, VaultSecure { authority, balance: 60, total_deposits: 100, total_withdrawals: 40, bump })
+}
+
+/// Audits `state` stored at `address`, owned by `owner`
+async fn audit_at(address: Pubkey, state: &VaultSecure, owner: Pubkey) -> Result<u64, TransactionError> {
+    let payer = Keypair::new();
+    let mut test = program_test();
+    test.add_account(payer.pubkey(), system_account(LAMPORTS_PER_SOL));
+    test.add_account(address, anchor_account(state, owner));
+    let (mut banks, _, _) = test.start().await;
+
+    let ix = Instruction::new_with_bytes(
+        ID,
+        &instruction::AuditVault {}.data(),
+        accounts::AuditVault { vault: address }.to_account_metas(None),
+    );
+    send_for_u64(&mut banks, &[ix], &[&payer]).await
+}
+
+async fn audit(address: Pubkey, state: VaultSecure) -> u8 {
+    let flags = audit_at(address, &state, ID).await.expect("the audit reports rather than fails");
+    u8::try_from(flags).unwrap()
+}
+
+#[tokio::test]
+async fn a_healthy_vault_passes() {
+    let (vault, state) = healthy();
+
+    assert_eq!(audit(vault, state).await, 0);
+}
+
+#[tokio::test]
+async fn a_balance_above_the_deposits_is_flagged() {
+    let (vault, state) = healthy();
+    let state = VaultSecure { balance: 101, ..state };
+
+    // Also no longer deposits - withdrawals
+    let expected = audit_flags::BALANCE_EXCEEDS_DEPOSITS | audit_flags::BALANCE_MISMATCH;
+    assert_eq!(audit(vault, state).await, expected);
+}
+
+#[tokio::test]
+async fn a_balance_off_the_books_is_flagged() {
+    let (vault, state) = healthy();
+    let state = VaultSecure { balance: 59, ..state };
+
+    assert_eq!(audit(vault, state).await, audit_flags::BALANCE_MISMATCH);
+}
+
+#[tokio::test]
+async fn more_withdrawn_than_deposited_is_flagged() {
+    let (vault, state) = healthy();
+    let state = VaultSecure { balance: 0, total_withdrawals: 101, ..state };
+
+    assert_eq!(audit(vault, state).await, audit_flags::NEGATIVE_NET_BALANCE);
+}
+
+#[tokio::test]
+async fn a_vault_away_from_its_pda_is_flagged() {
+    let (_, state) = healthy();
+
+    assert_eq!(audit(Pubkey::new_unique(), state).await, audit_flags::NON_CANONICAL_PDA);
+}
+
+#[tokio::test]
+async fn a_non_canonical_bump_is_flagged() {
+    let (vault, state) = healthy();
+    let state = VaultSecure { bump: state.bump.wrapping_sub(1), ..state };
+
+    assert_eq!(audit(vault, state).await, audit_flags::NON_CANONICAL_PDA);
+}
+
+#[tokio::test]
+async fn an_unset_authority_is_flagged() {
+    let authority = Pubkey::default();
+    let (vault, bump) = Pubkey::find_program_address(&[b"vault", authority.as_ref()], &ID);
+    let state = VaultSecure { authority, balance: 0, total_deposits: 0, total_withdrawals: 0, bump };
+
+    assert_eq!(audit(vault, state).await, audit_flags::AUTHORITY_UNSET);
+}
+
+#[tokio::test]
+async fn a_vault_owned_by_another_program_fails_the_audit() {
+    let (vault, state) = healthy();
+
+    let err = audit_at(vault, &state, Pubkey::new_unique()).await.expect_err("not this program's vault");
+
+    assert_eq!(err, custom_error(0, AnchorError::AccountOwnedByWrongProgram));
+}
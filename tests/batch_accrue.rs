@@ -0,0 +1,173 @@
+//! Admin-gated batch accrual in the closing-accounts program.
+//!
+//! `accrue_rewards_batch` pairs each entry with one `UserAccount` in
+//! `remaining_accounts` and checks the account's owner program, PDA and
+//! stored owner before accruing. Any failure - a length mismatch included -
+//! rejects the whole batch, so no entry lands on its own.
+
+use anchor_lang::error::ErrorCode as AnchorError;
+use anchor_lang::{InstructionData, ToAccountMetas};
+use closing_accounts::{accounts, instruction, AccrualEntry, CloseError, Config, UserAccount, ID};
+use security_tests::{anchor_account, custom_error, load, pda, program_test, send, system_account};
+use solana_program_test::BanksClient;
+use solana_sdk::{
+    instruction::{AccountMeta, Instruction},
+    native_token::LAMPORTS_PER_SOL,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    transaction::TransactionError,
+};
+
+/// Three user accounts, each with 10 accrued, behind a config whose admin
+/// is `admin`. `impostor` is a `UserAccount` for `owners[0]` stored away
+/// from its PDA.
+struct Fixture {
+    banks: BanksClient,
+    admin: Keypair,
+    stranger: Keypair,
+    owners: [Pubkey; 3],
+    impostor: Pubkey,
+}
+
+async fn users() -> Fixture {
+    let (admin, stranger) = (Keypair::new(), Keypair::new());
+    let (config, bump) = Pubkey::find_program_address(&[b"config"], &ID);
+    let state = Config {
+        admin: admin.pubkey(),
+        fee_bps: 0,
+        reward_mint: Pubkey::new_unique(),
+        reward_authority_bump: 0,
+        profile_cooldown_secs: 0,
+        reopen_window_secs: 0,
+        max_closes_per_window: 1,
+        close_window_secs: 0,
+        bump,
+    };
+
+    let mut test = program_test();
+    test.add_account(admin.pubkey(), system_account(LAMPORTS_PER_SOL));
+    test.add_account(stranger.pubkey(), system_account(LAMPORTS_PER_SOL));
+    test.add_account(config, anchor_account(&state, ID));
+    let owners = [Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique()];
+    for owner in &owners {
+        let (user_account, bump) = Pubkey::find_program_address(&[b"user", owner.as_ref()], &ID);
+        let state = UserAccount { owner: *owner, balance: 0, rewards_accrued: 10, bump };
+        test.add_account(user_account, anchor_account(&state, ID));
+    }
+    let impostor = Pubkey::new_unique();
+    let (_, bump) = Pubkey::find_program_address(&[b"user", owners[0].as_ref()], &ID);
+    let state = UserAccount { owner: owners[0], balance: 0, rewards_accrued: 10, bump };
+    test.add_account(impostor, anchor_account(&state, ID));
+    let (banks, _payer, _) = test.start().await;
+    Fixture { banks, admin, stranger, owners, impostor }
+}
+
+fn user(owner: &Pubkey) -> Pubkey {
+    pda(&[b"user", owner.as_ref()], &ID)
+}
+
+impl Fixture {
+    /// Accrues `entries`, passing `targets` as the remaining accounts
+    async fn accrue(
+        &mut self,
+        signer: &Keypair,
+        entries: &[(Pubkey, u64)],
+        targets: &[Pubkey],
+    ) -> Result<(), TransactionError> {
+        let mut ix = Instruction::new_with_bytes(
+            ID,
+            &instruction::AccrueRewardsBatch {
+                entries: entries.iter().map(|&(owner, amount)| AccrualEntry { owner, amount }).collect(),
+            }
+            .data(),
+            accounts::AccrueRewardsBatch { config: pda(&[b"config"], &ID), admin: signer.pubkey() }
+                .to_account_metas(None),
+        );
+        ix.accounts.extend(targets.iter().map(|target| AccountMeta::new(*target, false)));
+        send(&mut self.banks, &[ix], &[signer]).await
+    }
+
+    async fn rewards(&mut self) -> Vec<u64> {
+        let mut rewards = Vec::new();
+        for owner in self.owners {
+            rewards.push(load::<UserAccount>(&mut self.banks, user(&owner)).await.rewards_accrued);
+        }
+        rewards
+    }
+}
+
+#[tokio::test]
+async fn a_valid_batch_accrues_each_entry_to_its_account() {
+    let mut fixture = users().await;
+    let admin = fixture.admin.insecure_clone();
+    let [a, b, c] = fixture.owners;
+
+    fixture.accrue(&admin, &[(a, 5), (b, 0), (c, 90)], &[user(&a), user(&b), user(&c)]).await.expect("admin batch");
+
+    assert_eq!(fixture.rewards().await, vec![15, 10, 100]);
+}
+
+#[tokio::test]
+async fn more_entries_than_accounts_is_rejected() {
+    let mut fixture = users().await;
+    let admin = fixture.admin.insecure_clone();
+    let [a, b, _] = fixture.owners;
+
+    let err = fixture.accrue(&admin, &[(a, 5), (b, 5)], &[user(&a)]).await.expect_err("one account short");
+
+    assert_eq!(err, custom_error(0, CloseError::BatchLengthMismatch));
+    assert_eq!(fixture.rewards().await, vec![10; 3]);
+}
+
+#[tokio::test]
+async fn more_accounts_than_entries_is_rejected() {
+    let mut fixture = users().await;
+    let admin = fixture.admin.insecure_clone();
+    let [a, b, _] = fixture.owners;
+
+    let err = fixture.accrue(&admin, &[(a, 5)], &[user(&a), user(&b)]).await.expect_err("one entry short");
+
+    assert_eq!(err, custom_error(0, CloseError::BatchLengthMismatch));
+    assert_eq!(fixture.rewards().await, vec![10; 3]);
+}
+
+#[tokio::test]
+async fn a_mismatched_entry_rejects_the_entries_before_it_too() {
+    let mut fixture = users().await;
+    let admin = fixture.admin.insecure_clone();
+    let [a, b, c] = fixture.owners;
+
+    // The last two accounts are swapped relative to their entries
+    let err = fixture
+        .accrue(&admin, &[(a, 5), (b, 5), (c, 5)], &[user(&a), user(&c), user(&b)])
+        .await
+        .expect_err("entry for b paired with c's account");
+
+    assert_eq!(err, custom_error(0, CloseError::InvalidUserAccount));
+    assert_eq!(fixture.rewards().await, vec![10; 3], "a's accrual is rolled back");
+}
+
+#[tokio::test]
+async fn a_user_account_away_from_its_pda_is_rejected() {
+    let mut fixture = users().await;
+    let admin = fixture.admin.insecure_clone();
+    let [a, _, _] = fixture.owners;
+    let impostor = fixture.impostor;
+
+    let err = fixture.accrue(&admin, &[(a, 5)], &[impostor]).await.expect_err("not the canonical PDA");
+
+    assert_eq!(err, custom_error(0, CloseError::InvalidUserAccount));
+    assert_eq!(load::<UserAccount>(&mut fixture.banks, impostor).await.rewards_accrued, 10);
+}
+
+#[tokio::test]
+async fn only_the_admin_can_accrue() {
+    let mut fixture = users().await;
+    let stranger = fixture.stranger.insecure_clone();
+    let [a, _, _] = fixture.owners;
+
+    let err = fixture.accrue(&stranger, &[(a, 5)], &[user(&a)]).await.expect_err("not the admin");
+
+    assert_eq!(err, custom_error(0, AnchorError::ConstraintHasOne));
+    assert_eq!(fixture.rewards().await, vec![10; 3]);
+}
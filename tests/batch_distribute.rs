@@ -0,0 +1,161 @@
+//! Batched treasury payouts in the arbitrary-CPI program.
+//!
+//! Both instructions pay `reward_amount` to each `(token_account, program)`
+//! pair in `remaining_accounts`. `batch_distribute` validates the whole
+//! batch first: pairs only, allowlisted programs only, each recipient once.
+//! `batch_distribute_unchecked` does none of that.
+
+use anchor_lang::{InstructionData, ToAccountMetas};
+use anchor_spl::token::{spl_token, TokenAccount};
+use anchor_spl::token_2022;
+use arbitrary_cpi::{accounts, instruction, CpiError, Treasury, ID};
+use security_tests::{anchor_account, custom_error, load, program_test, send_for_u64, system_account, token_account};
+use solana_program_test::BanksClient;
+use solana_sdk::{
+    instruction::{AccountMeta, Instruction},
+    native_token::LAMPORTS_PER_SOL,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    transaction::TransactionError,
+};
+
+const TREASURY_BALANCE: u64 = 1_000;
+const REWARD_AMOUNT: u64 = 100;
+
+/// A treasury PDA holding `TREASURY_BALANCE` tokens, its admin, and two
+/// empty recipient token accounts of the same mint.
+struct Fixture {
+    banks: BanksClient,
+    admin: Keypair,
+    treasury: Pubkey,
+    treasury_tokens: Pubkey,
+    alice: Pubkey,
+    bob: Pubkey,
+}
+
+async fn funded_treasury() -> Fixture {
+    let admin = Keypair::new();
+    let mint = Pubkey::new_unique();
+    let (treasury, bump) = Pubkey::find_program_address(&[b"treasury"], &ID);
+    let (treasury_tokens, alice, bob) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+    let state = Treasury { admin: admin.pubkey(), reward_amount: REWARD_AMOUNT, bump };
+
+    let mut test = program_test();
+    test.add_account(admin.pubkey(), system_account(LAMPORTS_PER_SOL));
+    test.add_account(treasury, anchor_account(&state, ID));
+    test.add_account(treasury_tokens, token_account(mint, treasury, TREASURY_BALANCE));
+    test.add_account(alice, token_account(mint, Pubkey::new_unique(), 0));
+    test.add_account(bob, token_account(mint, Pubkey::new_unique(), 0));
+    let (banks, _payer, _) = test.start().await;
+    Fixture { banks, admin, treasury, treasury_tokens, alice, bob }
+}
+
+/// `remaining_accounts` for a batch of `(recipient, program)` pairs.
+fn batch(pairs: &[(Pubkey, Pubkey)]) -> Vec<AccountMeta> {
+    pairs
+        .iter()
+        .flat_map(|&(recipient, program)| [AccountMeta::new(recipient, false), AccountMeta::new_readonly(program, false)])
+        .collect()
+}
+
+impl Fixture {
+    /// Sends the instruction and returns the total it reports paying.
+    async fn send(&mut self, mut ix: Instruction, remaining: Vec<AccountMeta>) -> Result<u64, TransactionError> {
+        ix.accounts.extend(remaining);
+        send_for_u64(&mut self.banks, &[ix], &[&self.admin]).await
+    }
+
+    async fn batch_distribute(&mut self, remaining: Vec<AccountMeta>) -> Result<u64, TransactionError> {
+        let ix = Instruction::new_with_bytes(
+            ID,
+            &instruction::BatchDistribute {}.data(),
+            accounts::BatchDistribute {
+                treasury: self.treasury,
+                treasury_token_account: self.treasury_tokens,
+                admin: self.admin.pubkey(),
+            }
+            .to_account_metas(None),
+        );
+        self.send(ix, remaining).await
+    }
+
+    async fn batch_distribute_unchecked(&mut self, remaining: Vec<AccountMeta>) -> Result<u64, TransactionError> {
+        let ix = Instruction::new_with_bytes(
+            ID,
+            &instruction::BatchDistributeUnchecked {}.data(),
+            accounts::BatchDistributeUnchecked {
+                treasury: self.treasury,
+                treasury_token_account: self.treasury_tokens,
+                admin: self.admin.pubkey(),
+            }
+            .to_account_metas(None),
+        );
+        self.send(ix, remaining).await
+    }
+
+    async fn tokens(&mut self, key: Pubkey) -> u64 {
+        load::<TokenAccount>(&mut self.banks, key).await.amount
+    }
+}
+
+#[tokio::test]
+async fn each_recipient_is_paid_once() {
+    let mut fixture = funded_treasury().await;
+    let pairs = batch(&[(fixture.alice, spl_token::ID), (fixture.bob, spl_token::ID)]);
+
+    assert_eq!(fixture.batch_distribute(pairs).await, Ok(2 * REWARD_AMOUNT));
+
+    assert_eq!(fixture.tokens(fixture.alice).await, REWARD_AMOUNT);
+    assert_eq!(fixture.tokens(fixture.bob).await, REWARD_AMOUNT);
+    assert_eq!(fixture.tokens(fixture.treasury_tokens).await, TREASURY_BALANCE - 2 * REWARD_AMOUNT);
+}
+
+#[tokio::test]
+async fn a_program_off_the_allowlist_fails_the_whole_batch() {
+    let mut fixture = funded_treasury().await;
+    let pairs = batch(&[(fixture.alice, spl_token::ID), (fixture.bob, fake_token_program::ID)]);
+
+    assert_eq!(fixture.batch_distribute(pairs).await, Err(custom_error(0, CpiError::InvalidProgram)));
+    assert_eq!(fixture.tokens(fixture.alice).await, 0);
+}
+
+#[tokio::test]
+async fn token_2022_is_not_allowlisted_for_a_legacy_treasury() {
+    let mut fixture = funded_treasury().await;
+    let pairs = batch(&[(fixture.alice, token_2022::ID)]);
+
+    assert_eq!(fixture.batch_distribute(pairs).await, Err(custom_error(0, CpiError::InvalidProgram)));
+    assert_eq!(fixture.tokens(fixture.treasury_tokens).await, TREASURY_BALANCE);
+}
+
+#[tokio::test]
+async fn an_unpaired_account_is_rejected() {
+    let mut fixture = funded_treasury().await;
+    let mut pairs = batch(&[(fixture.alice, spl_token::ID)]);
+    pairs.push(AccountMeta::new(fixture.bob, false));
+
+    assert_eq!(fixture.batch_distribute(pairs).await, Err(custom_error(0, CpiError::UnpairedRemainingAccount)));
+}
+
+#[tokio::test]
+async fn a_duplicate_recipient_is_rejected() {
+    let mut fixture = funded_treasury().await;
+    let pairs = batch(&[(fixture.alice, spl_token::ID), (fixture.alice, spl_token::ID)]);
+
+    assert_eq!(fixture.batch_distribute(pairs).await, Err(custom_error(0, CpiError::DuplicateRecipient)));
+    assert_eq!(fixture.tokens(fixture.alice).await, 0);
+}
+
+#[tokio::test]
+async fn the_unchecked_batch_pays_a_duplicate_twice_and_trusts_any_program() {
+    let mut fixture = funded_treasury().await;
+    let mut pairs = batch(&[(fixture.alice, spl_token::ID), (fixture.alice, spl_token::ID), (fixture.bob, fake_token_program::ID)]);
+    // Dropped without an error
+    pairs.push(AccountMeta::new(fixture.bob, false));
+
+    // Bob is "paid" by a program that moves nothing
+    assert_eq!(fixture.batch_distribute_unchecked(pairs).await, Ok(3 * REWARD_AMOUNT));
+
+    assert_eq!(fixture.tokens(fixture.alice).await, 2 * REWARD_AMOUNT);
+    assert_eq!(fixture.tokens(fixture.bob).await, 0);
+}
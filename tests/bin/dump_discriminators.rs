@@ -0,0 +1,25 @@
+//! Prints the 8-byte discriminator of every `#[account]` type in the
+//! workspace so auditors can cross-reference raw on-chain account bytes.
+//!
+//! ```bash
+//! cargo run -p security-tests --bin dump-discriminators
+//! ```
+//!
+//! Note that discriminators only depend on the struct NAME. Types with the
+//! same name in different programs (e.g. `Vault`) share a discriminator,
+//! which is why an owner check is still required alongside it.
+
+use security_tests::{account_types, to_hex};
+
+fn main() {
+    println!("{:<22} {:<18} {:<18} BYTES", "PROGRAM", "ACCOUNT", "HEX");
+    for account in account_types() {
+        println!(
+            "{:<22} {:<18} {:<18} {:?}",
+            account.program,
+            account.name,
+            to_hex(&account.discriminator),
+            account.discriminator,
+        );
+    }
+}
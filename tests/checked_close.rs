@@ -0,0 +1,187 @@
+//! The shared `checked_close` path.
+//!
+//! `closing_accounts::safe_close` (a UserAccount) and
+//! `reinitialization::close_vault` (a VaultSecure) both close through
+//! `security_common::checked_close`. Each must remove the account, pay the
+//! whole rent to the recipient and store the same `Tombstone` record.
+//!
+//! The `AccountClosed` event carries that same record, but natively run
+//! programs print events to stdout instead of the transaction log, so the
+//! stored tombstones are what gets compared here.
+
+use anchor_lang::{AccountDeserialize, InstructionData, ToAccountMetas};
+use closing_accounts::{AccountTombstone, ClosesByRecipient, Config, UserAccount};
+use reinitialization::{VaultSecure, VaultTombstone};
+use security_common::{SecurityError, Tombstone};
+use security_tests::{anchor_account, custom_error, load, pda, program_test, send, system_account};
+use solana_program_test::ProgramTestContext;
+use solana_sdk::{
+    clock::Clock,
+    instruction::Instruction,
+    native_token::LAMPORTS_PER_SOL,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    system_program,
+    transaction::TransactionError,
+};
+
+const NOW: i64 = 1_000_000;
+
+fn ix(program_id: Pubkey, accounts: impl ToAccountMetas, data: impl InstructionData) -> Instruction {
+    Instruction::new_with_bytes(program_id, &data.data(), accounts.to_account_metas(None))
+}
+
+/// An owner with an empty UserAccount in the closing-accounts program and
+/// an empty vault in the reinitialization program, both closing to
+/// `recipient`.
+struct Fixture {
+    context: ProgramTestContext,
+    owner: Keypair,
+    recipient: Pubkey,
+    user_account: Pubkey,
+    vault: Pubkey,
+}
+
+async fn empty_accounts() -> Fixture {
+    let owner = Keypair::new();
+    let recipient = Pubkey::new_unique();
+    let mut test = program_test();
+    test.add_account(owner.pubkey(), system_account(LAMPORTS_PER_SOL));
+
+    let (user_account, bump) = Pubkey::find_program_address(&[b"user", owner.pubkey().as_ref()], &closing_accounts::ID);
+    let state = UserAccount { owner: owner.pubkey(), balance: 0, rewards_accrued: 0, bump };
+    test.add_account(user_account, anchor_account(&state, closing_accounts::ID));
+    let (config, bump) = Pubkey::find_program_address(&[b"config"], &closing_accounts::ID);
+    let config_state = Config {
+        admin: Pubkey::new_unique(),
+        fee_bps: 0,
+        reward_mint: Pubkey::new_unique(),
+        reward_authority_bump: 0,
+        profile_cooldown_secs: 0,
+        reopen_window_secs: 0,
+        max_closes_per_window: 1,
+        close_window_secs: 0,
+        bump,
+    };
+    test.add_account(config, anchor_account(&config_state, closing_accounts::ID));
+    let (closes, bump) = Pubkey::find_program_address(&[b"closes", recipient.as_ref()], &closing_accounts::ID);
+    let closes_state = ClosesByRecipient { recipient, count: 0, window_start: 0, bump };
+    test.add_account(closes, anchor_account(&closes_state, closing_accounts::ID));
+
+    let (vault, bump) = Pubkey::find_program_address(&[b"vault", owner.pubkey().as_ref()], &reinitialization::ID);
+    let vault_state = VaultSecure { authority: owner.pubkey(), balance: 0, total_deposits: 5, total_withdrawals: 5, bump };
+    test.add_account(vault, anchor_account(&vault_state, reinitialization::ID));
+
+    let mut context = test.start_with_context().await;
+    let clock: Clock = context.banks_client.get_sysvar().await.unwrap();
+    context.set_sysvar(&Clock { unix_timestamp: NOW, ..clock });
+    Fixture { context, owner, recipient, user_account, vault }
+}
+
+impl Fixture {
+    fn user_tombstone(&self) -> Pubkey {
+        pda(&[b"user_tombstone", self.user_account.as_ref(), &0u64.to_le_bytes()], &closing_accounts::ID)
+    }
+
+    fn vault_tombstone(&self) -> Pubkey {
+        pda(&[b"vault_tombstone", self.vault.as_ref(), &0u64.to_le_bytes()], &reinitialization::ID)
+    }
+
+    fn safe_close(&self) -> Instruction {
+        use closing_accounts::{accounts, instruction, ID};
+        ix(
+            ID,
+            accounts::SafeClose {
+                user_account: self.user_account,
+                user_closes: pda(&[b"user_closes", self.user_account.as_ref()], &ID),
+                tombstone: self.user_tombstone(),
+                config: pda(&[b"config"], &ID),
+                closes: pda(&[b"closes", self.recipient.as_ref()], &ID),
+                recipient: self.recipient,
+                owner: self.owner.pubkey(),
+                system_program: system_program::ID,
+            },
+            instruction::SafeClose {},
+        )
+    }
+
+    fn close_vault(&self, recipient: Pubkey) -> Instruction {
+        use reinitialization::{accounts, instruction, ID};
+        ix(
+            ID,
+            accounts::CloseVault {
+                vault: self.vault,
+                closes: pda(&[b"vault_closes", self.vault.as_ref()], &ID),
+                tombstone: self.vault_tombstone(),
+                recipient,
+                authority: self.owner.pubkey(),
+                system_program: system_program::ID,
+            },
+            instruction::CloseVault {},
+        )
+    }
+
+    async fn send(&mut self, instruction: Instruction) -> Result<(), TransactionError> {
+        send(&mut self.context.banks_client, &[instruction], &[&self.owner]).await
+    }
+
+    async fn rent(&mut self, key: Pubkey) -> u64 {
+        self.context.banks_client.get_account(key).await.unwrap().unwrap().lamports
+    }
+
+    async fn load<T: AccountDeserialize>(&mut self, key: Pubkey) -> T {
+        load(&mut self.context.banks_client, key).await
+    }
+
+    /// The tombstone a close of `account` into `recipient` should leave.
+    fn expected(&self, account: Pubkey, lamports: u64) -> Tombstone {
+        Tombstone {
+            account,
+            original_owner: self.owner.pubkey(),
+            closed_by: self.owner.pubkey(),
+            recipient: self.recipient,
+            lamports,
+            closed_at: NOW,
+        }
+    }
+}
+
+#[tokio::test]
+async fn both_programs_record_the_same_tombstone() {
+    let mut fixture = empty_accounts().await;
+    let user_rent = fixture.rent(fixture.user_account).await;
+    let vault_rent = fixture.rent(fixture.vault).await;
+
+    fixture.send(fixture.safe_close()).await.expect("user account closes");
+    fixture.send(fixture.close_vault(fixture.recipient)).await.expect("vault closes");
+
+    let user_record = fixture.expected(fixture.user_account, user_rent);
+    let vault_record = fixture.expected(fixture.vault, vault_rent);
+    assert_eq!(fixture.load::<AccountTombstone>(fixture.user_tombstone()).await.record, user_record);
+    assert_eq!(fixture.load::<VaultTombstone>(fixture.vault_tombstone()).await.record, vault_record);
+}
+
+#[tokio::test]
+async fn both_programs_pay_the_whole_rent_and_remove_the_account() {
+    let mut fixture = empty_accounts().await;
+    let rent = fixture.rent(fixture.user_account).await + fixture.rent(fixture.vault).await;
+
+    fixture.send(fixture.safe_close()).await.expect("user account closes");
+    fixture.send(fixture.close_vault(fixture.recipient)).await.expect("vault closes");
+
+    assert_eq!(fixture.context.banks_client.get_balance(fixture.recipient).await.unwrap(), rent);
+    for closed in [fixture.user_account, fixture.vault] {
+        assert_eq!(fixture.context.banks_client.get_account(closed).await.unwrap(), None);
+    }
+}
+
+#[tokio::test]
+async fn closing_an_account_into_itself_is_rejected() {
+    let mut fixture = empty_accounts().await;
+    let rent = fixture.rent(fixture.vault).await;
+
+    let err = fixture.send(fixture.close_vault(fixture.vault)).await.expect_err("the rent would be burned");
+
+    assert_eq!(err, custom_error(0, SecurityError::ConservationViolation));
+    assert_eq!(fixture.rent(fixture.vault).await, rent);
+}
@@ -0,0 +1,74 @@
+//! `raw::try_deserialize_checked` in the type-cosplay program.
+//!
+//! `admin_action_manual_secure` takes its config as a raw account, like
+//! `admin_action_vulnerable`, but type-checks it by hand: the wrong owner
+//! is `WrongOwnerProgram`, a wrong discriminator or none at all is
+//! `TypeMismatch`.
+
+use anchor_lang::{InstructionData, ToAccountMetas};
+use security_tests::{anchor_account, custom_error, program_test, raw_account};
+use solana_sdk::{
+    account::Account,
+    instruction::Instruction,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    transaction::{Transaction, TransactionError},
+};
+use type_cosplay::{accounts, instruction, AdminConfig, TypeCosplayError, UserAccount, ID};
+
+/// Sends `admin_action_manual_secure` with `account` as the config,
+/// signed by `signer`.
+async fn admin_action(account: Account, signer: &Keypair) -> Result<(), TransactionError> {
+    let admin_config = Pubkey::new_unique();
+    let mut test = program_test();
+    test.add_account(admin_config, account);
+    let (mut banks, payer, blockhash) = test.start().await;
+
+    let ix = Instruction::new_with_bytes(
+        ID,
+        &instruction::AdminActionManualSecure {}.data(),
+        accounts::AdminActionManualSecure { admin_config, signer: signer.pubkey() }.to_account_metas(None),
+    );
+    let tx = Transaction::new_signed_with_payer(&[ix], Some(&payer.pubkey()), &[&payer, signer], blockhash);
+    banks.process_transaction(tx).await.map_err(|err| err.unwrap())
+}
+
+fn type_mismatch() -> TransactionError {
+    custom_error(0, TypeCosplayError::TypeMismatch)
+}
+
+#[tokio::test]
+async fn the_admin_config_is_accepted() {
+    let admin = Keypair::new();
+    let config = AdminConfig { admin: admin.pubkey(), bump: 255 };
+
+    assert_eq!(admin_action(anchor_account(&config, ID), &admin).await, Ok(()));
+}
+
+#[tokio::test]
+async fn a_user_account_is_a_type_mismatch() {
+    let user = Keypair::new();
+    let state = UserAccount { owner: user.pubkey(), balance: 1, bump: 255 };
+
+    assert_eq!(admin_action(anchor_account(&state, ID), &user).await, Err(type_mismatch()));
+}
+
+#[tokio::test]
+async fn an_admin_config_owned_by_another_program_is_rejected() {
+    let admin = Keypair::new();
+    let config = AdminConfig { admin: admin.pubkey(), bump: 255 };
+
+    let result = admin_action(anchor_account(&config, Pubkey::new_unique()), &admin).await;
+
+    assert_eq!(result, Err(custom_error(0, TypeCosplayError::WrongOwnerProgram)));
+}
+
+#[tokio::test]
+async fn a_layout_without_a_discriminator_is_a_type_mismatch() {
+    // What admin_action_vulnerable accepts: admin, then is_admin = 1
+    let admin = Keypair::new();
+    let mut data = admin.pubkey().to_bytes().to_vec();
+    data.push(1);
+
+    assert_eq!(admin_action(raw_account(data, ID), &admin).await, Err(type_mismatch()));
+}
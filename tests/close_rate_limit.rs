@@ -0,0 +1,170 @@
+//! Per-recipient close limits in the closing-accounts program.
+//!
+//! `close_secure` counts every close against the rent recipient's
+//! `ClosesByRecipient` window; past `max_closes_per_window` further closes
+//! fail until the window rolls over.
+
+use anchor_lang::{InstructionData, ToAccountMetas};
+use closing_accounts::{accounts, instruction, CloseError, ClosesByRecipient, Config, UserAccount, ID};
+use security_tests::{
+    anchor_account, custom_error, load, mint_account, pda, program_test, send, set_time, system_account,
+};
+use solana_program_test::ProgramTestContext;
+use solana_sdk::{
+    instruction::Instruction,
+    native_token::LAMPORTS_PER_SOL,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    system_program,
+    transaction::TransactionError,
+};
+
+const MAX_CLOSES: u32 = 3;
+const WINDOW_SECS: i64 = 3_600;
+const START: i64 = 1_000_000;
+
+fn ix(accounts: impl ToAccountMetas, data: impl InstructionData) -> Instruction {
+    Instruction::new_with_bytes(ID, &data.data(), accounts.to_account_metas(None))
+}
+
+/// Owners with one UserAccount each, all closing to the same recipient.
+struct Fixture {
+    context: ProgramTestContext,
+    owners: Vec<Keypair>,
+    recipient: Pubkey,
+}
+
+async fn farm(accounts: usize) -> Fixture {
+    let owners: Vec<Keypair> = (0..accounts).map(|_| Keypair::new()).collect();
+    let recipient = Pubkey::new_unique();
+
+    let mut test = program_test();
+    let (config, bump) = Pubkey::find_program_address(&[b"config"], &ID);
+    let (_, reward_authority_bump) = Pubkey::find_program_address(&[b"reward_authority"], &ID);
+    let state = Config {
+        admin: Pubkey::new_unique(),
+        fee_bps: 100,
+        reward_mint: Pubkey::new_unique(),
+        reward_authority_bump,
+        profile_cooldown_secs: 0,
+        reopen_window_secs: 0,
+        max_closes_per_window: MAX_CLOSES,
+        close_window_secs: WINDOW_SECS,
+        bump,
+    };
+    test.add_account(config, anchor_account(&state, ID));
+    for owner in &owners {
+        let (user_account, bump) = Pubkey::find_program_address(&[b"user", owner.pubkey().as_ref()], &ID);
+        let state = UserAccount { owner: owner.pubkey(), balance: 0, rewards_accrued: 0, bump };
+        test.add_account(owner.pubkey(), system_account(LAMPORTS_PER_SOL));
+        test.add_account(user_account, anchor_account(&state, ID));
+    }
+    let mut context = test.start_with_context().await;
+    set_time(&mut context, START).await;
+
+    // Anyone can open the counter; the first owner pays
+    let open = ix(
+        accounts::InitializeCloseCounter {
+            closes: pda(&[b"closes", recipient.as_ref()], &ID),
+            recipient,
+            payer: owners[0].pubkey(),
+            system_program: system_program::ID,
+        },
+        instruction::InitializeCloseCounter {},
+    );
+    send(&mut context.banks_client, &[open], &[&owners[0]]).await.expect("counter opened");
+    Fixture { context, owners, recipient }
+}
+
+impl Fixture {
+    /// Closes the `index`th owner's account, paying its rent to the recipient.
+    async fn close(&mut self, index: usize) -> Result<(), TransactionError> {
+        let owner = &self.owners[index];
+        let user_account = pda(&[b"user", owner.pubkey().as_ref()], &ID);
+        let close = ix(
+            accounts::CloseSecure {
+                user_account,
+                user_closes: pda(&[b"user_closes", user_account.as_ref()], &ID),
+                tombstone: pda(&[b"user_tombstone", user_account.as_ref(), &0u64.to_le_bytes()], &ID),
+                config: pda(&[b"config"], &ID),
+                closes: pda(&[b"closes", self.recipient.as_ref()], &ID),
+                recipient: self.recipient,
+                owner: owner.pubkey(),
+                system_program: system_program::ID,
+            },
+            instruction::CloseSecure {},
+        );
+        send(&mut self.context.banks_client, &[close], &[owner]).await
+    }
+
+    async fn closes(&mut self) -> ClosesByRecipient {
+        let key = pda(&[b"closes", self.recipient.as_ref()], &ID);
+        load(&mut self.context.banks_client, key).await
+    }
+}
+
+#[tokio::test]
+async fn closes_past_the_limit_are_rejected_within_the_window() {
+    let mut fixture = farm(MAX_CLOSES as usize + 1).await;
+
+    for index in 0..MAX_CLOSES as usize {
+        fixture.close(index).await.expect("within the limit");
+    }
+    let closes = fixture.closes().await;
+    assert_eq!((closes.count, closes.window_start), (MAX_CLOSES, START));
+
+    set_time(&mut fixture.context, START + WINDOW_SECS - 1).await;
+    let err = fixture.close(MAX_CLOSES as usize).await.expect_err("limit reached");
+    assert_eq!(err, custom_error(0, CloseError::CloseRateLimited));
+}
+
+#[tokio::test]
+async fn the_limit_resets_when_the_window_ends() {
+    let mut fixture = farm(MAX_CLOSES as usize + 1).await;
+    for index in 0..MAX_CLOSES as usize {
+        fixture.close(index).await.expect("within the limit");
+    }
+
+    set_time(&mut fixture.context, START + WINDOW_SECS).await;
+    fixture.close(MAX_CLOSES as usize).await.expect("new window");
+
+    let closes = fixture.closes().await;
+    assert_eq!((closes.count, closes.window_start), (1, START + WINDOW_SECS));
+}
+
+#[tokio::test]
+async fn a_zero_length_window_is_rejected() {
+    let admin = Keypair::new();
+    let reward_mint = Pubkey::new_unique();
+    let reward_authority = pda(&[b"reward_authority"], &ID);
+
+    let mut test = program_test();
+    test.add_account(admin.pubkey(), system_account(LAMPORTS_PER_SOL));
+    test.add_account(reward_mint, mint_account(reward_authority, 6));
+    let (mut banks, _, _) = test.start().await;
+
+    let initialize = |close_window_secs| {
+        ix(
+            accounts::InitializeConfig {
+                config: pda(&[b"config"], &ID),
+                reward_mint,
+                reward_authority,
+                admin: admin.pubkey(),
+                system_program: system_program::ID,
+            },
+            instruction::InitializeConfig {
+                fee_bps: 100,
+                profile_cooldown_secs: 0,
+                reopen_window_secs: 0,
+                max_closes_per_window: MAX_CLOSES,
+                close_window_secs,
+            },
+        )
+    };
+    let err = send(&mut banks, &[initialize(0)], &[&admin]).await.expect_err("zero window");
+    assert_eq!(err, custom_error(0, CloseError::InvalidCloseLimit));
+
+    send(&mut banks, &[initialize(WINDOW_SECS)], &[&admin]).await.expect("positive window");
+    let config: Config = load(&mut banks, pda(&[b"config"], &ID)).await;
+    assert_eq!(config.close_window_secs, WINDOW_SECS);
+}
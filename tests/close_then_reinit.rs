@@ -0,0 +1,118 @@
+//! Closing a vault and initializing it again in the same transaction.
+//!
+//! `reinitialization::close_vault` closes through the same
+//! `security_common::checked_close` as `closing_accounts::safe_close`: every
+//! lamport moves out, the account goes back to the System Program and its
+//! data is dropped - right away, not at the end of the transaction. So an
+//! `init` later in the same transaction sees an empty System account and
+//! creates the vault again. The new vault starts empty, and closing it
+//! again leaves a second `["vault_tombstone", vault, n]` record, numbered
+//! by the `["vault_closes", vault]` counter, next to the first.
+
+use anchor_lang::{AccountDeserialize, InstructionData, ToAccountMetas};
+use reinitialization::{accounts, instruction, VaultCloses, VaultSecure, VaultTombstone, ID};
+use security_tests::{custom_error, pda, program_test, send};
+use solana_program_test::BanksClient;
+use solana_sdk::{
+    instruction::Instruction,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    system_program,
+    transaction::TransactionError,
+};
+
+struct Fixture {
+    banks: BanksClient,
+    authority: Keypair,
+    vault: Pubkey,
+    closes: Pubkey,
+}
+
+async fn fixture() -> Fixture {
+    let (banks, authority, _) = program_test().start().await;
+    let vault = pda(&[b"vault", authority.pubkey().as_ref()], &ID);
+    let closes = pda(&[b"vault_closes", vault.as_ref()], &ID);
+    Fixture { banks, authority, vault, closes }
+}
+
+impl Fixture {
+    fn init(&self) -> Instruction {
+        let accounts = accounts::InitializeSecureAnchor {
+            vault: self.vault,
+            authority: self.authority.pubkey(),
+            system_program: system_program::ID,
+        };
+        Instruction::new_with_bytes(ID, &instruction::InitializeSecureAnchor {}.data(), accounts.to_account_metas(None))
+    }
+
+    /// Tombstone of the vault's `n`th close, counting from 0
+    fn tombstone(&self, n: u64) -> Pubkey {
+        pda(&[b"vault_tombstone", self.vault.as_ref(), &n.to_le_bytes()], &ID)
+    }
+
+    fn close(&self, n: u64) -> Instruction {
+        let accounts = accounts::CloseVault {
+            vault: self.vault,
+            closes: self.closes,
+            tombstone: self.tombstone(n),
+            recipient: self.authority.pubkey(),
+            authority: self.authority.pubkey(),
+            system_program: system_program::ID,
+        };
+        Instruction::new_with_bytes(ID, &instruction::CloseVault {}.data(), accounts.to_account_metas(None))
+    }
+
+    async fn send(&mut self, instructions: &[Instruction]) -> Result<(), TransactionError> {
+        send(&mut self.banks, instructions, &[&self.authority]).await
+    }
+
+    async fn load<T: AccountDeserialize>(&mut self, key: Pubkey) -> T {
+        let account = self.banks.get_account(key).await.unwrap().unwrap();
+        assert_eq!(account.owner, ID);
+        T::try_deserialize(&mut account.data.as_slice()).unwrap()
+    }
+}
+
+#[tokio::test]
+async fn init_after_close_in_the_same_transaction_recreates_the_vault() {
+    let mut fixture = fixture().await;
+    let (init, close) = (fixture.init(), fixture.close(0));
+
+    fixture.send(&[init.clone(), close, init]).await.expect("the closed address is empty at once");
+
+    let vault = fixture.load::<VaultSecure>(fixture.vault).await;
+    assert_eq!(vault.authority, fixture.authority.pubkey());
+    assert_eq!((vault.balance, vault.total_deposits, vault.total_withdrawals), (0, 0, 0));
+    // The close that happened in between is still on record
+    let tombstone = fixture.load::<VaultTombstone>(fixture.tombstone(0)).await;
+    assert_eq!(tombstone.record.account, fixture.vault);
+}
+
+#[tokio::test]
+async fn the_recreated_vault_closes_into_a_new_tombstone() {
+    let mut fixture = fixture().await;
+    let init = fixture.init();
+    fixture.send(&[init.clone(), fixture.close(0), init]).await.expect("setup");
+    let first = fixture.load::<VaultTombstone>(fixture.tombstone(0)).await;
+
+    fixture.send(&[fixture.close(1)]).await.expect("a recreated vault can be closed");
+
+    assert!(fixture.banks.get_account(fixture.vault).await.unwrap().is_none());
+    assert_eq!(fixture.load::<VaultCloses>(fixture.closes).await.count, 2);
+    let second = fixture.load::<VaultTombstone>(fixture.tombstone(1)).await;
+    assert_eq!(second.record.account, fixture.vault);
+    // The first close's record is untouched
+    assert_eq!(fixture.load::<VaultTombstone>(fixture.tombstone(0)).await.record, first.record);
+}
+
+#[tokio::test]
+async fn a_close_cannot_reuse_an_earlier_tombstone() {
+    let mut fixture = fixture().await;
+    let init = fixture.init();
+    fixture.send(&[init.clone(), fixture.close(0), init]).await.expect("setup");
+
+    let err = fixture.send(&[fixture.close(0)]).await.expect_err("tombstone 0 is taken");
+
+    assert_eq!(err, custom_error(0, anchor_lang::error::ErrorCode::ConstraintSeeds));
+    fixture.load::<VaultSecure>(fixture.vault).await;
+}
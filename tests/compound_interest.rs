@@ -0,0 +1,72 @@
+//! Compound interest in the integer-overflow program.
+//!
+//! `accrue_compound_interest` compounds one period at a time in u128 and
+//! rounds each step up. The `pow` version overflows within a handful of
+//! periods however small the balance.
+
+use anchor_lang::{InstructionData, ToAccountMetas};
+use integer_overflow::{accounts, instruction, MathError, SecurityError, ID, MAX_COMPOUND_PERIODS};
+use security_tests::{custom_error, program_test};
+use solana_sdk::{
+    instruction::Instruction,
+    signature::Signer,
+    transaction::{Transaction, TransactionError},
+};
+
+fn compound_ix(data: impl InstructionData) -> Instruction {
+    Instruction::new_with_bytes(ID, &data.data(), accounts::CompoundInterest {}.to_account_metas(None))
+}
+
+/// Compounds through the secure instruction and returns the balance.
+async fn secure(principal: u64, rate_bps: u16, periods: u32) -> Result<u64, TransactionError> {
+    let (mut banks, payer, blockhash) = program_test().start().await;
+    let ix = compound_ix(instruction::AccrueCompoundInterest { principal, rate_bps, periods });
+    let tx = Transaction::new_signed_with_payer(&[ix], Some(&payer.pubkey()), &[&payer], blockhash);
+    let result = banks.process_transaction_with_metadata(tx).await.unwrap();
+    result.result?;
+
+    // The runtime strips trailing zero bytes from return data
+    let data = result
+        .metadata
+        .and_then(|metadata| metadata.return_data)
+        .map(|return_data| return_data.data)
+        .unwrap_or_default();
+    let mut bytes = [0u8; 8];
+    bytes[..data.len()].copy_from_slice(&data);
+    Ok(u64::from_le_bytes(bytes))
+}
+
+#[tokio::test]
+async fn interest_compounds_each_period_rounding_up() {
+    // 1_000_000 * 1.01^12 = 1_126_825.03, rounded up period by period
+    assert_eq!(secure(1_000_000, 100, 12).await, Ok(1_126_831));
+    assert_eq!(secure(1_000_000, 100, 0).await, Ok(1_000_000));
+}
+
+#[tokio::test]
+async fn a_balance_past_u64_max_overflows() {
+    // Doubling 1e18 fits four times: 1.6e19 < u64::MAX < 3.2e19
+    let quintillion = 1_000_000_000_000_000_000;
+
+    assert_eq!(secure(quintillion, 10_000, 4).await, Ok(16 * quintillion));
+    assert_eq!(secure(quintillion, 10_000, 5).await, Err(custom_error(0, SecurityError::Overflow)));
+}
+
+#[tokio::test]
+async fn periods_and_rate_are_capped() {
+    assert_eq!(secure(1, 1, MAX_COMPOUND_PERIODS + 1).await, Err(custom_error(0, MathError::TooManyPeriods)));
+    assert_eq!(secure(1, 10_001, 1).await, Err(custom_error(0, MathError::InvalidBps)));
+}
+
+#[tokio::test]
+async fn pow_overflows_where_the_loop_does_not() {
+    // 1% over 16 periods: 10_000^16 alone is past u64::MAX
+    assert_eq!(secure(1_000, 100, 16).await, Ok(1_181));
+
+    // With overflow-checks the pow panics natively; in release it wraps.
+    // Either way the vulnerable path cannot produce the right balance.
+    let (mut banks, payer, blockhash) = program_test().start().await;
+    let ix = compound_ix(instruction::AccrueCompoundInterestVulnerable { principal: 1_000, rate_bps: 100, periods: 16 });
+    let tx = Transaction::new_signed_with_payer(&[ix], Some(&payer.pubkey()), &[&payer], blockhash);
+    assert!(banks.process_transaction(tx).await.is_err(), "pow past u64::MAX");
+}
@@ -0,0 +1,111 @@
+//! Compute budget guard on batch instructions.
+//!
+//! `accrue_rewards_batch` checks up front that every entry fits in the
+//! remaining compute budget, and fails with `ComputeBudgetLow` - before
+//! writing anything - when it doesn't. The native harness has no compute
+//! meter, so the budget is set with `compute::set_native_budget`; every
+//! test here uses the same `BATCH_LIMIT`, since the setting is
+//! process-wide.
+
+use anchor_lang::{AccountDeserialize, InstructionData, ToAccountMetas};
+use closing_accounts::{accounts, instruction, AccrualEntry, Config, UserAccount, ACCRUE_UNITS_PER_ACCOUNT, ID};
+use security_common::compute::{set_native_budget, COMPUTE_RESERVE};
+use security_common::SecurityError;
+use security_tests::{anchor_account, custom_error, pda, program_test, send, system_account};
+use solana_program_test::BanksClient;
+use solana_sdk::{
+    instruction::{AccountMeta, Instruction},
+    native_token::LAMPORTS_PER_SOL,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    transaction::TransactionError,
+};
+
+/// Entries the budget has room for.
+const BATCH_LIMIT: usize = 10;
+
+/// `BATCH_LIMIT + 1` user accounts behind a config whose admin is `admin`.
+struct Fixture {
+    banks: BanksClient,
+    admin: Keypair,
+    owners: Vec<Pubkey>,
+}
+
+async fn users() -> Fixture {
+    set_native_budget(COMPUTE_RESERVE + BATCH_LIMIT as u64 * ACCRUE_UNITS_PER_ACCOUNT);
+
+    let admin = Keypair::new();
+    let (config, bump) = Pubkey::find_program_address(&[b"config"], &ID);
+    let state = Config {
+        admin: admin.pubkey(),
+        fee_bps: 0,
+        reward_mint: Pubkey::new_unique(),
+        reward_authority_bump: 0,
+        profile_cooldown_secs: 0,
+        reopen_window_secs: 0,
+        max_closes_per_window: 1,
+        close_window_secs: 0,
+        bump,
+    };
+
+    let mut test = program_test();
+    test.add_account(admin.pubkey(), system_account(LAMPORTS_PER_SOL));
+    test.add_account(config, anchor_account(&state, ID));
+    let owners: Vec<Pubkey> = (0..=BATCH_LIMIT).map(|_| Pubkey::new_unique()).collect();
+    for owner in &owners {
+        let (user_account, bump) = Pubkey::find_program_address(&[b"user", owner.as_ref()], &ID);
+        let state = UserAccount { owner: *owner, balance: 0, rewards_accrued: 0, bump };
+        test.add_account(user_account, anchor_account(&state, ID));
+    }
+    let (banks, _payer, _) = test.start().await;
+    Fixture { banks, admin, owners }
+}
+
+impl Fixture {
+    /// Accrues 100 to each of the first `count` owners in one batch.
+    async fn accrue(&mut self, count: usize) -> Result<(), TransactionError> {
+        let owners = &self.owners[..count];
+        let mut ix = Instruction::new_with_bytes(
+            ID,
+            &instruction::AccrueRewardsBatch {
+                entries: owners.iter().map(|owner| AccrualEntry { owner: *owner, amount: 100 }).collect(),
+            }
+            .data(),
+            accounts::AccrueRewardsBatch { config: pda(&[b"config"], &ID), admin: self.admin.pubkey() }
+                .to_account_metas(None),
+        );
+        ix.accounts.extend(owners.iter().map(|owner| AccountMeta::new(pda(&[b"user", owner.as_ref()], &ID), false)));
+
+        send(&mut self.banks, &[ix], &[&self.admin]).await
+    }
+
+    async fn rewards(&mut self) -> Vec<u64> {
+        let mut rewards = Vec::new();
+        for owner in self.owners.clone() {
+            let account = self.banks.get_account(pda(&[b"user", owner.as_ref()], &ID)).await.unwrap().unwrap();
+            rewards.push(UserAccount::try_deserialize(&mut account.data.as_slice()).unwrap().rewards_accrued);
+        }
+        rewards
+    }
+}
+
+#[tokio::test]
+async fn a_batch_within_the_budget_lands_in_full() {
+    let mut fixture = users().await;
+
+    fixture.accrue(BATCH_LIMIT).await.expect("fits the budget");
+
+    let mut expected = vec![100; BATCH_LIMIT];
+    expected.push(0);
+    assert_eq!(fixture.rewards().await, expected);
+}
+
+#[tokio::test]
+async fn an_oversized_batch_bails_out_before_writing() {
+    let mut fixture = users().await;
+
+    let err = fixture.accrue(BATCH_LIMIT + 1).await.expect_err("one entry too many");
+
+    assert_eq!(err, custom_error(0, SecurityError::ComputeBudgetLow));
+    assert_eq!(fixture.rewards().await, vec![0; BATCH_LIMIT + 1]);
+}
@@ -0,0 +1,81 @@
+//! Reading the account-validation config from a raw account.
+//!
+//! `read_config_rent_checked` only trusts the config at the `["config"]`
+//! PDA, owned by the program, and still rent exempt. Each check has its own
+//! error, and the config's `fee_bps` comes back as return data.
+
+use account_validation::{accounts, instruction, Config, ValidationError, ID};
+use anchor_lang::{InstructionData, ToAccountMetas};
+use security_tests::{anchor_account, custom_error, program_test};
+use solana_sdk::{
+    account::Account,
+    instruction::Instruction,
+    pubkey::Pubkey,
+    signature::Signer,
+    system_program,
+    transaction::{Transaction, TransactionError},
+};
+
+const FEE_BPS: u16 = 250;
+
+fn config_account(owner: Pubkey) -> Account {
+    let (_, bump) = Pubkey::find_program_address(&[b"config"], &ID);
+    let state = Config { admin: Pubkey::new_unique(), fee_bps: FEE_BPS, fee_holiday_until: 0, max_positions: 1, dust_threshold: 0, bump };
+    anchor_account(&state, owner)
+}
+
+/// Puts `account` at `address` and reads it as the config.
+async fn read(address: Pubkey, account: Account) -> Result<u16, TransactionError> {
+    let mut test = program_test();
+    test.add_account(address, account);
+    let (mut banks, payer, blockhash) = test.start().await;
+
+    let ix = Instruction::new_with_bytes(
+        ID,
+        &instruction::ReadConfigRentChecked {}.data(),
+        accounts::ReadConfigRentChecked { config: address }.to_account_metas(None),
+    );
+    let tx = Transaction::new_signed_with_payer(&[ix], Some(&payer.pubkey()), &[&payer], blockhash);
+    let result = banks.process_transaction_with_metadata(tx).await.unwrap();
+    result.result?;
+
+    let data = result
+        .metadata
+        .and_then(|metadata| metadata.return_data)
+        .map(|return_data| return_data.data)
+        .unwrap_or_default();
+    let mut bytes = [0u8; 2];
+    bytes[..data.len()].copy_from_slice(&data);
+    Ok(u16::from_le_bytes(bytes))
+}
+
+fn canonical() -> Pubkey {
+    Pubkey::find_program_address(&[b"config"], &ID).0
+}
+
+#[tokio::test]
+async fn the_canonical_config_is_read() {
+    assert_eq!(read(canonical(), config_account(ID)).await, Ok(FEE_BPS));
+}
+
+#[tokio::test]
+async fn a_config_at_another_address_is_rejected() {
+    let lookalike = read(Pubkey::new_unique(), config_account(ID)).await;
+
+    assert_eq!(lookalike, Err(custom_error(0, ValidationError::InvalidPDA)));
+}
+
+#[tokio::test]
+async fn a_config_owned_by_another_program_is_rejected() {
+    let foreign = read(canonical(), config_account(system_program::ID)).await;
+
+    assert_eq!(foreign, Err(custom_error(0, ValidationError::InvalidOwner)));
+}
+
+#[tokio::test]
+async fn a_defunded_config_is_rejected() {
+    let mut defunded = config_account(ID);
+    defunded.lamports = 1;
+
+    assert_eq!(read(canonical(), defunded).await, Err(custom_error(0, ValidationError::NotRentExempt)));
+}
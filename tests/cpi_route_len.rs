@@ -0,0 +1,73 @@
+//! Minimum data length for accounts forwarded on the swap route.
+//!
+//! `swap_secure` checks each `remaining_accounts` entry against the length
+//! `SWAP_ROUTE_MIN_LENS` expects at its position before it would reach the
+//! callee. The source and destination token accounts are checked; the
+//! mints and other accounts after them aren't held to a token account's
+//! size.
+
+use anchor_lang::{InstructionData, ToAccountMetas};
+use anchor_spl::token::spl_token::{self, solana_program::program_pack::Pack};
+use arbitrary_cpi::{accounts, instruction, CpiError, ID, SWAP_ROUTE_MIN_LENS};
+use security_tests::{custom_error, mock_swap, program_test, raw_account};
+use solana_sdk::{
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+    signature::Signer,
+    system_program,
+    transaction::{Transaction, TransactionError},
+};
+
+const TOKEN_ACCOUNT_LEN: usize = SWAP_ROUTE_MIN_LENS[0];
+
+/// Sends `swap_secure` with one route account per entry of `lens`, each
+/// that many bytes long.
+async fn swap_with_route_lens(lens: &[usize]) -> Result<(), TransactionError> {
+    let route: Vec<Pubkey> = lens.iter().map(|_| Pubkey::new_unique()).collect();
+    let mut test = program_test();
+    for (key, len) in route.iter().zip(lens) {
+        test.add_account(*key, raw_account(vec![0; *len], system_program::ID));
+    }
+    let (mut banks, payer, blockhash) = test.start().await;
+
+    let mut metas = accounts::SwapSecure { swap_program: mock_swap::ID, user: payer.pubkey() }
+        .to_account_metas(None);
+    metas.extend(route.iter().map(|key| AccountMeta::new_readonly(*key, false)));
+    let swap = Instruction::new_with_bytes(ID, &instruction::SwapSecure { amount: 100 }.data(), metas);
+
+    let tx = Transaction::new_signed_with_payer(&[swap], Some(&payer.pubkey()), &[&payer], blockhash);
+    banks.process_transaction(tx).await.map_err(|err| err.unwrap())
+}
+
+#[tokio::test]
+async fn too_short_route_account_is_rejected() {
+    let err = swap_with_route_lens(&[TOKEN_ACCOUNT_LEN - 1])
+        .await
+        .expect_err("truncated account must not be forwarded");
+
+    assert_eq!(err, custom_error(0, CpiError::AccountDataTooSmall));
+}
+
+#[tokio::test]
+async fn a_short_destination_is_rejected_too() {
+    let err = swap_with_route_lens(&[TOKEN_ACCOUNT_LEN, TOKEN_ACCOUNT_LEN - 1])
+        .await
+        .expect_err("every token account position is checked");
+
+    assert_eq!(err, custom_error(0, CpiError::AccountDataTooSmall));
+}
+
+#[tokio::test]
+async fn correctly_sized_route_account_is_accepted() {
+    swap_with_route_lens(&[TOKEN_ACCOUNT_LEN])
+        .await
+        .expect("minimum-length account is forwarded");
+}
+
+#[tokio::test]
+async fn a_mint_after_the_token_accounts_is_accepted() {
+    // A mint is 82 bytes and a program account 36; neither is a token account
+    swap_with_route_lens(&[TOKEN_ACCOUNT_LEN, TOKEN_ACCOUNT_LEN, spl_token::state::Mint::LEN, 36])
+        .await
+        .expect("only the token account positions are held to a token account's size");
+}
@@ -0,0 +1,181 @@
+//! Delegated withdrawals from the missing-signer-check vault.
+//!
+//! The vault authority approves a delegate until `expires_at` and can
+//! revoke it earlier. `withdraw_as_delegate` honours the grant only while
+//! it is unexpired; `withdraw_as_delegate_vulnerable` never looks at the
+//! expiry.
+
+use anchor_lang::error::ErrorCode as AnchorError;
+use anchor_lang::{InstructionData, ToAccountMetas};
+use missing_signer_check::{accounts, instruction, CustomError, Vault, ID};
+use security_tests::{anchor_account, custom_error, load, pda, program_test, send, set_time, system_account};
+use solana_program_test::ProgramTestContext;
+use solana_sdk::{
+    instruction::Instruction,
+    native_token::LAMPORTS_PER_SOL,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    system_program, system_transaction,
+    transaction::TransactionError,
+};
+
+const NOW: i64 = 1_000_000;
+const EXPIRY: i64 = NOW + 3_600;
+const BALANCE: u64 = LAMPORTS_PER_SOL;
+
+fn ix(accounts: impl ToAccountMetas, data: impl InstructionData) -> Instruction {
+    Instruction::new_with_bytes(ID, &data.data(), accounts.to_account_metas(None))
+}
+
+fn error(code: impl Into<u32>) -> TransactionError {
+    custom_error(0, code)
+}
+
+/// A vault holding `BALANCE` above its rent, with `delegate` approved by
+/// its authority until `EXPIRY`.
+struct Fixture {
+    context: ProgramTestContext,
+    authority: Keypair,
+    delegate: Keypair,
+    vault: Pubkey,
+    recipient: Pubkey,
+}
+
+async fn approved() -> Fixture {
+    let authority = Keypair::new();
+    let delegate = Keypair::new();
+    let (vault, bump) = Pubkey::find_program_address(&[b"vault", authority.pubkey().as_ref()], &ID);
+    let state = Vault { authority: authority.pubkey(), balance: BALANCE, total_withdrawn: 0, withdrawal_limit: BALANCE, bump };
+    let mut account = anchor_account(&state, ID);
+    account.lamports += BALANCE;
+
+    let mut test = program_test();
+    test.add_account(authority.pubkey(), system_account(LAMPORTS_PER_SOL));
+    test.add_account(delegate.pubkey(), system_account(LAMPORTS_PER_SOL));
+    test.add_account(vault, account);
+    let mut context = test.start_with_context().await;
+    set_time(&mut context, NOW).await;
+
+    let mut fixture = Fixture { context, authority, delegate, vault, recipient: Pubkey::new_unique() };
+    let approve = fixture.approve(&fixture.authority, EXPIRY);
+    let authority = fixture.authority.insecure_clone();
+    send(&mut fixture.context.banks_client, &[approve], &[&authority]).await.expect("authority approves");
+    fixture
+}
+
+impl Fixture {
+    fn delegate_record(&self) -> Pubkey {
+        pda(&[b"delegate", self.vault.as_ref(), self.delegate.pubkey().as_ref()], &ID)
+    }
+
+    /// `signer` approving the fixture's delegate on the fixture's vault.
+    fn approve(&self, signer: &Keypair, expires_at: i64) -> Instruction {
+        ix(
+            accounts::ApproveDelegate {
+                vault: self.vault,
+                delegate_record: self.delegate_record(),
+                authority: signer.pubkey(),
+                system_program: system_program::ID,
+            },
+            instruction::ApproveDelegate { delegate: self.delegate.pubkey(), expires_at },
+        )
+    }
+
+    /// The delegate withdrawing the whole balance to `recipient`.
+    async fn withdraw(&mut self, data: impl InstructionData) -> Result<(), TransactionError> {
+        let withdraw = ix(
+            accounts::WithdrawAsDelegate {
+                vault: self.vault,
+                delegate_record: self.delegate_record(),
+                delegate: self.delegate.pubkey(),
+                recipient: self.recipient,
+            },
+            data,
+        );
+        let delegate = self.delegate.insecure_clone();
+        send(&mut self.context.banks_client, &[withdraw], &[&delegate]).await
+    }
+
+    async fn balance(&mut self) -> u64 {
+        load::<Vault>(&mut self.context.banks_client, self.vault).await.balance
+    }
+}
+
+#[tokio::test]
+async fn a_delegate_withdraws_before_expiry() {
+    let mut fixture = approved().await;
+    set_time(&mut fixture.context, EXPIRY - 1).await;
+
+    fixture.withdraw(instruction::WithdrawAsDelegate { amount: BALANCE }).await.expect("last valid second");
+
+    assert_eq!(fixture.balance().await, 0);
+    assert_eq!(fixture.context.banks_client.get_balance(fixture.recipient).await.unwrap(), BALANCE);
+}
+
+#[tokio::test]
+async fn an_expired_delegate_is_rejected() {
+    let mut fixture = approved().await;
+    set_time(&mut fixture.context, EXPIRY).await;
+
+    let err = fixture.withdraw(instruction::WithdrawAsDelegate { amount: BALANCE }).await.expect_err("grant expired");
+
+    assert_eq!(err, error(CustomError::DelegateExpired));
+    assert_eq!(fixture.balance().await, BALANCE);
+}
+
+#[tokio::test]
+async fn without_the_expiry_check_an_expired_delegate_drains_the_vault() {
+    let mut fixture = approved().await;
+    set_time(&mut fixture.context, EXPIRY + 86_400).await;
+
+    fixture
+        .withdraw(instruction::WithdrawAsDelegateVulnerable { amount: BALANCE })
+        .await
+        .expect("expiry never checked");
+
+    assert_eq!(fixture.balance().await, 0);
+}
+
+#[tokio::test]
+async fn a_revoked_delegate_cannot_withdraw() {
+    let mut fixture = approved().await;
+    let revoke = ix(
+        accounts::RevokeDelegate {
+            vault: fixture.vault,
+            delegate_record: fixture.delegate_record(),
+            authority: fixture.authority.pubkey(),
+        },
+        instruction::RevokeDelegate {},
+    );
+    let authority = fixture.authority.insecure_clone();
+    send(&mut fixture.context.banks_client, &[revoke], &[&authority]).await.expect("authority revokes");
+
+    let err = fixture.withdraw(instruction::WithdrawAsDelegate { amount: BALANCE }).await.expect_err("record closed");
+
+    assert_eq!(err, error(AnchorError::AccountNotInitialized));
+    assert_eq!(fixture.balance().await, BALANCE);
+}
+
+#[tokio::test]
+async fn only_the_authority_approves_a_future_expiry() {
+    let mut fixture = approved().await;
+    // A fresh delegate, so the record address is free
+    fixture.delegate = Keypair::new();
+    let stranger = Keypair::new();
+    let fund = system_transaction::transfer(
+        &fixture.context.payer,
+        &stranger.pubkey(),
+        LAMPORTS_PER_SOL,
+        fixture.context.last_blockhash,
+    );
+    fixture.context.banks_client.process_transaction(fund).await.unwrap();
+
+    let approve = fixture.approve(&stranger, EXPIRY);
+    let err = send(&mut fixture.context.banks_client, &[approve], &[&stranger]).await.expect_err("not the vault's authority");
+    assert_eq!(err, error(AnchorError::ConstraintSeeds));
+
+    let approve = fixture.approve(&fixture.authority, NOW);
+    let authority = fixture.authority.insecure_clone();
+    let err = send(&mut fixture.context.banks_client, &[approve], &[&authority]).await.expect_err("already expired");
+    assert_eq!(err, error(CustomError::InvalidExpiry));
+}
@@ -0,0 +1,159 @@
+//! Time-locked deposits in the account-validation pool.
+//!
+//! Each deposit locks the depositor's balance for the pool's `lock_secs`, so
+//! borrowed funds can't be deposited and pulled back in the same slot.
+
+use account_validation::{accounts, instruction, UserDeposit, ValidationError, ID};
+use anchor_lang::{InstructionData, ToAccountMetas};
+use anchor_spl::token::{spl_token, TokenAccount};
+use security_tests::{custom_error, load, pda, program_test, send, set_time, system_account, token_account};
+use solana_program_test::ProgramTestContext;
+use solana_sdk::{
+    instruction::Instruction,
+    native_token::LAMPORTS_PER_SOL,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    system_program,
+    transaction::TransactionError,
+};
+
+const MINT: Pubkey = Pubkey::new_from_array([0x71; 32]);
+const LOCK_SECS: i64 = 24 * 60 * 60;
+const DEPOSIT_AT: i64 = 1_000_000;
+
+fn ix(accounts: impl ToAccountMetas, data: impl InstructionData) -> Instruction {
+    Instruction::new_with_bytes(ID, &data.data(), accounts.to_account_metas(None))
+}
+
+/// A depositor's pool and position, their token account and the pool's,
+/// with the clock under test control.
+struct Fixture {
+    context: ProgramTestContext,
+    user: Keypair,
+    pool: Pubkey,
+    user_deposit: Pubkey,
+    token: Pubkey,
+    pool_tokens: Pubkey,
+}
+
+/// A pool locking deposits for `lock_secs`, with the user holding 1_000
+/// deposited at `DEPOSIT_AT`.
+async fn deposited(lock_secs: i64) -> Fixture {
+    let user = Keypair::new();
+    let (token, pool_tokens) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let config = pda(&[b"config"], &ID);
+    let pool = pda(&[b"pool", user.pubkey().as_ref()], &ID);
+    let mut test = program_test();
+    test.add_account(user.pubkey(), system_account(LAMPORTS_PER_SOL));
+    test.add_account(token, token_account(MINT, user.pubkey(), 1_000));
+    test.add_account(pool_tokens, token_account(MINT, pool, 0));
+    let mut context = test.start_with_context().await;
+    set_time(&mut context, DEPOSIT_AT).await;
+
+    let user_account = pda(&[b"user", user.pubkey().as_ref()], &ID);
+    let user_deposit = pda(&[b"user_deposit", pool.as_ref(), user.pubkey().as_ref()], &ID);
+    let setup = [
+        ix(
+            accounts::InitializeConfig { config, admin: user.pubkey(), system_program: system_program::ID },
+            instruction::InitializeConfig { fee_bps: 100, max_positions: 1, dust_threshold: 0 },
+        ),
+        ix(
+            accounts::InitializePool { config, pool, authority: user.pubkey(), system_program: system_program::ID },
+            instruction::InitializePool {
+                reward_rate: 10,
+                global_tvl_cap: u64::MAX,
+                lock_secs,
+                min_hold_secs: 0,
+                early_exit_bps: 0,
+                deposit_mint: MINT,
+                reward_mint: Pubkey::default(),
+            },
+        ),
+        ix(
+            accounts::InitializeUserAccount { user_account, user: user.pubkey(), system_program: system_program::ID },
+            instruction::InitializeUserAccount {},
+        ),
+        ix(
+            accounts::OpenDeposit {
+                config,
+                pool,
+                user_account,
+                user_deposit,
+                user: user.pubkey(),
+                system_program: system_program::ID,
+            },
+            instruction::OpenDeposit {},
+        ),
+        ix(
+            accounts::DepositSecure {
+                config,
+                pool,
+                user_deposit,
+                user_token_account: token,
+                pool_token_account: pool_tokens,
+                user: user.pubkey(),
+                token_program: spl_token::ID,
+            },
+            instruction::DepositSecure { amount: 1_000 },
+        ),
+    ];
+    send(&mut context.banks_client, &setup, &[&user]).await.expect("setup");
+    Fixture { context, user, pool, user_deposit, token, pool_tokens }
+}
+
+impl Fixture {
+    async fn withdraw(&mut self, amount: u64) -> Result<(), TransactionError> {
+        let withdraw = ix(
+            accounts::Withdraw {
+                pool: self.pool,
+                user_deposit: self.user_deposit,
+                pool_token_account: self.pool_tokens,
+                user_token_account: self.token,
+                user: self.user.pubkey(),
+                token_program: spl_token::ID,
+            },
+            instruction::Withdraw { amount },
+        );
+        send(&mut self.context.banks_client, &[withdraw], &[&self.user]).await
+    }
+
+    async fn user_deposit(&mut self) -> UserDeposit {
+        load(&mut self.context.banks_client, self.user_deposit).await
+    }
+}
+
+#[tokio::test]
+async fn deposit_sets_the_lock() {
+    let mut fixture = deposited(LOCK_SECS).await;
+
+    let state = fixture.user_deposit().await;
+    assert_eq!(state.amount, 1_000);
+    assert_eq!(state.locked_until, DEPOSIT_AT + LOCK_SECS);
+}
+
+#[tokio::test]
+async fn withdrawing_before_the_lock_expires_is_rejected() {
+    let mut fixture = deposited(LOCK_SECS).await;
+    set_time(&mut fixture.context, DEPOSIT_AT + LOCK_SECS - 1).await;
+
+    let err = fixture.withdraw(1_000).await.expect_err("deposit is still locked");
+    assert_eq!(err, custom_error(0, ValidationError::DepositLocked));
+}
+
+#[tokio::test]
+async fn withdrawing_after_the_lock_expires_succeeds() {
+    let mut fixture = deposited(LOCK_SECS).await;
+    set_time(&mut fixture.context, DEPOSIT_AT + LOCK_SECS).await;
+
+    fixture.withdraw(1_000).await.expect("lock has expired");
+    assert_eq!(fixture.user_deposit().await.amount, 0);
+    assert_eq!(load::<TokenAccount>(&mut fixture.context.banks_client, fixture.token).await.amount, 1_000);
+    assert_eq!(load::<TokenAccount>(&mut fixture.context.banks_client, fixture.pool_tokens).await.amount, 0);
+}
+
+#[tokio::test]
+async fn zero_lock_allows_an_immediate_withdrawal() {
+    let mut fixture = deposited(0).await;
+
+    fixture.withdraw(1_000).await.expect("no lock to wait out");
+}
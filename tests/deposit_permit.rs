@@ -0,0 +1,248 @@
+//! Deposits on a user's behalf in the account-validation pool.
+//!
+//! A relayer submits `deposit_with_permit` along with an Ed25519 program
+//! instruction proving the user signed a `DepositPermit` for exactly that
+//! deposit. A permit is rejected after its expiry and once its nonce has
+//! been used. The tokens move under the delegate approval the user gave the
+//! pool's `permit_delegate` PDA.
+
+use account_validation::{accounts, instruction, DepositPermit, UserDeposit, ValidationError, ID};
+use anchor_lang::solana_program::sysvar;
+use anchor_lang::{InstructionData, ToAccountMetas};
+use anchor_spl::token::{spl_token, TokenAccount};
+use security_tests::{custom_error, delegated_token_account, ed25519_instruction, load, pda, program_test, send, set_time, system_account, token_account};
+use solana_program_test::ProgramTestContext;
+use solana_sdk::{
+    instruction::Instruction,
+    native_token::LAMPORTS_PER_SOL,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    system_program, system_transaction,
+    transaction::TransactionError,
+};
+
+const MINT: Pubkey = Pubkey::new_from_array([0x74; 32]);
+const NOW: i64 = 1_000_000;
+const EXPIRY: i64 = NOW + 600;
+const AMOUNT: u64 = 500;
+
+fn ix(accounts: impl ToAccountMetas, data: impl InstructionData) -> Instruction {
+    Instruction::new_with_bytes(ID, &data.data(), accounts.to_account_metas(None))
+}
+
+/// A user with an open, empty position and twice `AMOUNT` of `MINT`, all
+/// approved to the permit delegate, and a relayer with fees to spend.
+struct Fixture {
+    context: ProgramTestContext,
+    user: Keypair,
+    relayer: Keypair,
+    pool: Pubkey,
+    user_deposit: Pubkey,
+    user_tokens: Pubkey,
+    pool_tokens: Pubkey,
+}
+
+async fn opened() -> Fixture {
+    let user = Keypair::new();
+    let relayer = Keypair::new();
+    let config = pda(&[b"config"], &ID);
+    let pool = pda(&[b"pool", user.pubkey().as_ref()], &ID);
+    let delegate = pda(&[b"permit_delegate", pool.as_ref()], &ID);
+    let (user_tokens, pool_tokens) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let mut test = program_test();
+    test.add_account(user.pubkey(), system_account(LAMPORTS_PER_SOL));
+    test.add_account(relayer.pubkey(), system_account(LAMPORTS_PER_SOL));
+    test.add_account(user_tokens, delegated_token_account(MINT, user.pubkey(), 2 * AMOUNT, delegate));
+    test.add_account(pool_tokens, token_account(MINT, pool, 0));
+    let mut context = test.start_with_context().await;
+    set_time(&mut context, NOW).await;
+
+    let user_account = pda(&[b"user", user.pubkey().as_ref()], &ID);
+    let user_deposit = pda(&[b"user_deposit", pool.as_ref(), user.pubkey().as_ref()], &ID);
+    let setup = [
+        ix(
+            accounts::InitializeConfig { config, admin: user.pubkey(), system_program: system_program::ID },
+            instruction::InitializeConfig { fee_bps: 100, max_positions: 1, dust_threshold: 0 },
+        ),
+        ix(
+            accounts::InitializePool { config, pool, authority: user.pubkey(), system_program: system_program::ID },
+            instruction::InitializePool {
+                reward_rate: 10,
+                global_tvl_cap: u64::MAX,
+                lock_secs: 0,
+                min_hold_secs: 0,
+                early_exit_bps: 0,
+                deposit_mint: MINT,
+                reward_mint: Pubkey::default(),
+            },
+        ),
+        ix(
+            accounts::InitializeUserAccount { user_account, user: user.pubkey(), system_program: system_program::ID },
+            instruction::InitializeUserAccount {},
+        ),
+        ix(
+            accounts::OpenDeposit {
+                config,
+                pool,
+                user_account,
+                user_deposit,
+                user: user.pubkey(),
+                system_program: system_program::ID,
+            },
+            instruction::OpenDeposit {},
+        ),
+    ];
+    send(&mut context.banks_client, &setup, &[&user]).await.expect("setup");
+    Fixture { context, user, relayer, pool, user_deposit, user_tokens, pool_tokens }
+}
+
+impl Fixture {
+    fn permit(&self, nonce: u64) -> DepositPermit {
+        DepositPermit { pool: self.pool, owner: self.user.pubkey(), amount: AMOUNT, expiry: EXPIRY, nonce }
+    }
+
+    /// The Ed25519 check of `signer`'s signature over `permit`, then the
+    /// deposit that relies on it.
+    fn deposit_with(&self, signer: &Keypair, permit: &DepositPermit) -> [Instruction; 2] {
+        let (verify, signature) = ed25519_instruction(signer, &permit.message());
+        let deposit = ix(
+            accounts::DepositWithPermit {
+                config: pda(&[b"config"], &ID),
+                pool: self.pool,
+                user_deposit: self.user_deposit,
+                user: self.user.pubkey(),
+                user_token_account: self.user_tokens,
+                pool_token_account: self.pool_tokens,
+                permit_delegate: pda(&[b"permit_delegate", self.pool.as_ref()], &ID),
+                relayer: self.relayer.pubkey(),
+                instructions: sysvar::instructions::ID,
+                token_program: spl_token::ID,
+            },
+            instruction::DepositWithPermit { amount: permit.amount, expiry: permit.expiry, signature },
+        );
+        [verify, deposit]
+    }
+
+    async fn relay(&mut self, instructions: &[Instruction]) -> Result<(), TransactionError> {
+        let relayer = self.relayer.insecure_clone();
+        send(&mut self.context.banks_client, instructions, &[&relayer]).await
+    }
+
+    async fn user_deposit(&mut self) -> UserDeposit {
+        load(&mut self.context.banks_client, self.user_deposit).await
+    }
+
+    async fn tokens(&mut self, key: Pubkey) -> u64 {
+        load::<TokenAccount>(&mut self.context.banks_client, key).await.amount
+    }
+}
+
+fn error(code: ValidationError) -> TransactionError {
+    // The Ed25519 check is instruction 0, the deposit instruction 1
+    custom_error(1, code)
+}
+
+#[tokio::test]
+async fn a_valid_permit_deposits_for_the_user() {
+    let mut fixture = opened().await;
+    let instructions = fixture.deposit_with(&fixture.user, &fixture.permit(0));
+
+    fixture.relay(&instructions).await.expect("user signed the permit");
+
+    let deposit = fixture.user_deposit().await;
+    assert_eq!((deposit.amount, deposit.permit_nonce, deposit.deposited_at), (AMOUNT, 1, NOW));
+    assert_eq!(fixture.tokens(fixture.pool_tokens).await, AMOUNT);
+    assert_eq!(fixture.tokens(fixture.user_tokens).await, AMOUNT);
+}
+
+#[tokio::test]
+async fn a_permit_without_a_delegate_approval_moves_nothing() {
+    let mut fixture = opened().await;
+    let unapproved = token_account(MINT, fixture.user.pubkey(), 2 * AMOUNT);
+    fixture.context.set_account(&fixture.user_tokens, &unapproved.into());
+    let instructions = fixture.deposit_with(&fixture.user, &fixture.permit(0));
+
+    let err = fixture.relay(&instructions).await.expect_err("the delegate can't move the tokens");
+
+    assert_eq!(err, custom_error(1, spl_token::error::TokenError::OwnerMismatch as u32));
+    assert_eq!(fixture.user_deposit().await.amount, 0);
+}
+
+#[tokio::test]
+async fn a_permit_is_usable_up_to_its_expiry() {
+    let mut fixture = opened().await;
+    set_time(&mut fixture.context, EXPIRY).await;
+    let instructions = fixture.deposit_with(&fixture.user, &fixture.permit(0));
+
+    fixture.relay(&instructions).await.expect("last valid second");
+
+    assert_eq!(fixture.user_deposit().await.amount, AMOUNT);
+}
+
+#[tokio::test]
+async fn an_expired_permit_is_rejected() {
+    let mut fixture = opened().await;
+    set_time(&mut fixture.context, EXPIRY + 1).await;
+    let instructions = fixture.deposit_with(&fixture.user, &fixture.permit(0));
+
+    let err = fixture.relay(&instructions).await.expect_err("permit expired");
+
+    assert_eq!(err, error(ValidationError::PermitExpired));
+    assert_eq!(fixture.user_deposit().await.amount, 0);
+}
+
+#[tokio::test]
+async fn a_replayed_permit_is_rejected() {
+    let mut fixture = opened().await;
+    let permit = fixture.permit(0);
+    let instructions = fixture.deposit_with(&fixture.user, &permit);
+    fixture.relay(&instructions).await.expect("first use");
+
+    // Another relayer resubmits the same signed permit
+    let copycat = Keypair::new();
+    let blockhash = fixture.context.banks_client.get_latest_blockhash().await.unwrap();
+    let fund = system_transaction::transfer(&fixture.relayer, &copycat.pubkey(), LAMPORTS_PER_SOL / 2, blockhash);
+    fixture.context.banks_client.process_transaction(fund).await.unwrap();
+    fixture.relayer = copycat;
+    let instructions = fixture.deposit_with(&fixture.user, &permit);
+    let err = fixture.relay(&instructions).await.expect_err("nonce already used");
+
+    assert_eq!(err, error(ValidationError::PermitReplayed));
+    let deposit = fixture.user_deposit().await;
+    assert_eq!((deposit.amount, deposit.permit_nonce), (AMOUNT, 1));
+}
+
+#[tokio::test]
+async fn a_permit_signed_by_someone_else_is_rejected() {
+    let mut fixture = opened().await;
+    let instructions = fixture.deposit_with(&Keypair::new(), &fixture.permit(0));
+
+    let err = fixture.relay(&instructions).await.expect_err("not the user's signature");
+
+    assert_eq!(err, error(ValidationError::InvalidPermit));
+}
+
+#[tokio::test]
+async fn the_unsigned_flag_lets_anyone_deposit_for_the_user() {
+    let mut fixture = opened().await;
+    let deposit = ix(
+        accounts::DepositWithPermitVulnerable {
+            config: pda(&[b"config"], &ID),
+            pool: fixture.pool,
+            user_deposit: fixture.user_deposit,
+            user: fixture.user.pubkey(),
+            user_token_account: fixture.user_tokens,
+            pool_token_account: fixture.pool_tokens,
+            permit_delegate: pda(&[b"permit_delegate", fixture.pool.as_ref()], &ID),
+            relayer: fixture.relayer.pubkey(),
+            token_program: spl_token::ID,
+        },
+        instruction::DepositWithPermitVulnerable { amount: AMOUNT, approved: true },
+    );
+
+    fixture.relay(&[deposit]).await.expect("nothing from the user is checked");
+
+    // The user's approval was meant for their own permits
+    assert_eq!(fixture.user_deposit().await.amount, AMOUNT);
+    assert_eq!(fixture.tokens(fixture.user_tokens).await, AMOUNT);
+}
@@ -0,0 +1,91 @@
+//! Discriminator snapshot.
+//!
+//! Pins the 8-byte discriminator of every `#[account]` type. Renaming a struct
+//! changes its discriminator and orphans every existing on-chain account of
+//! that type, so a rename must show up here as a deliberate snapshot update.
+
+use security_tests::{account_types, compute_discriminator, to_hex};
+
+/// (program, account, discriminator hex)
+const SNAPSHOT: &[(&str, &str, &str)] = &[
+    ("missing_signer_check", "Vault", "d308e82b02987577"),
+    ("missing_signer_check", "MultisigVault", "62969cb49efeaa8c"),
+    ("missing_signer_check", "Delegate", "5c91a66f0b2626f7"),
+    ("account_validation", "Pool", "f19a6d0411b16dbc"),
+    ("account_validation", "PoolVault", "09b8cc45e752fc9a"),
+    ("account_validation", "Config", "9b0caae01efacc82"),
+    ("account_validation", "Org", "215880da2556276b"),
+    ("account_validation", "Team", "8cdab18cc1f1c76a"),
+    ("account_validation", "Member", "3613a2151da611c6"),
+    ("account_validation", "UserDeposit", "45ee17d9ff89b923"),
+    ("account_validation", "UserAccount", "d3218810ba6ef27f"),
+    ("integer_overflow", "Vault", "d308e82b02987577"),
+    ("integer_overflow", "UserAccount", "d3218810ba6ef27f"),
+    ("integer_overflow", "Config", "9b0caae01efacc82"),
+    ("integer_overflow", "WithdrawalRecord", "583b9acad8d2d3ed"),
+    ("integer_overflow", "InterestAccount", "d583bc692c6732fd"),
+    ("integer_overflow", "LpPool", "b97f838dc5c6aa93"),
+    ("integer_overflow", "LpPosition", "69f125c8e002fc5a"),
+    ("integer_overflow", "ReferralAccount", "eda2504ec4e95b02"),
+    ("integer_overflow", "FeePosition", "ae9231447af2a605"),
+    ("integer_overflow", "RewardMinter", "d022738ad4c3522f"),
+    ("arbitrary_cpi", "Vault", "d308e82b02987577"),
+    ("arbitrary_cpi", "Treasury", "eeef7bee5901a8fd"),
+    ("arbitrary_cpi", "SwapCommitment", "544edfc294ff7597"),
+    ("reinitialization", "VaultVulnerable", "bb2aeba5dda2bb41"),
+    ("reinitialization", "ConfigVulnerable", "171c26c412d8d995"),
+    ("reinitialization", "VaultSecure", "2d3cea08833757b5"),
+    ("reinitialization", "ConfigSecure", "4709d4f86c55bb2a"),
+    ("reinitialization", "DisabledInstructions", "67cab080d8bf591e"),
+    ("reinitialization", "AdminAuditLog", "f5042b8640a824c0"),
+    ("reinitialization", "VaultTombstone", "3fb2b0a86d51d389"),
+    ("reinitialization", "VaultCloses", "6dd4c8316e31d4b0"),
+    ("reinitialization", "VaultV2", "1a48504625585a27"),
+    ("type_cosplay", "AdminConfig", "9c0a4fa147093e4d"),
+    ("type_cosplay", "UserAccount", "d3218810ba6ef27f"),
+    ("type_cosplay", "RewardVault", "c916dda7d010d221"),
+    ("type_cosplay", "StakeAccount", "509e437c32bdc0ff"),
+    ("type_cosplay", "TaggedAccount", "71871b6381b5699c"),
+    ("closing_accounts", "UserAccount", "d3218810ba6ef27f"),
+    ("closing_accounts", "Config", "9b0caae01efacc82"),
+    ("closing_accounts", "UserProfile", "202577cdb3b40dc2"),
+    ("closing_accounts", "ProfileTombstone", "9ae569cecd7d5870"),
+    ("closing_accounts", "AccountTombstone", "31a951b0a299fb61"),
+    ("closing_accounts", "ClosesByRecipient", "5c2049fab427ac09"),
+    ("closing_accounts", "UserCloses", "ee6c520621dcca52"),
+    ("escrow", "Escrow", "1fd57bbbba16da9b"),
+    ("insecure_randomness", "Lottery", "a2b61a0ca4d67003"),
+    ("native_sol_vault", "SolVault", "1584e66713d181f8"),
+    ("upgrade_authority", "ProgramConfig", "c4d25ae790958c3f"),
+    ("flash_loan_guard", "LendingPool", "d028f252ba124b24"),
+    ("flash_loan_guard", "Position", "aabc8fe47a40f7d0"),
+];
+
+#[test]
+fn discriminators_match_anchor_derivation() {
+    for account in account_types() {
+        assert_eq!(
+            account.discriminator,
+            compute_discriminator(account.name),
+            "{}::{} does not match sha256(\"account:{}\")[0..8]",
+            account.program,
+            account.name,
+            account.name,
+        );
+    }
+}
+
+#[test]
+fn discriminators_match_snapshot() {
+    let actual: Vec<(&str, &str, String)> = account_types()
+        .iter()
+        .map(|a| (a.program, a.name, to_hex(&a.discriminator)))
+        .collect();
+
+    let expected: Vec<(&str, &str, String)> = SNAPSHOT
+        .iter()
+        .map(|(program, name, hex)| (*program, *name, hex.to_string()))
+        .collect();
+
+    assert_eq!(actual, expected, "account set or discriminators changed - update SNAPSHOT");
+}
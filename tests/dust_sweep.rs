@@ -0,0 +1,239 @@
+//! Dust sweeping in the account-validation pool.
+//!
+//! `sweep_dust` lets a position's owner or the pool authority close a
+//! position holding less than `config.dust_threshold`. The dust goes to the
+//! pool and the rent goes back to the owner, never to whoever swept it. A
+//! locked position can't be swept.
+
+use account_validation::{accounts, instruction, Config, Pool, UserAccount, UserDeposit, ValidationError, ID};
+use anchor_lang::error::ErrorCode as AnchorError;
+use anchor_lang::{InstructionData, ToAccountMetas};
+use anchor_spl::token::TokenAccount;
+use security_tests::{anchor_account, custom_error, load, program_test, send, system_account, token_account};
+use solana_program_test::BanksClient;
+use solana_sdk::{
+    instruction::Instruction,
+    native_token::LAMPORTS_PER_SOL,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    transaction::TransactionError,
+};
+
+const DUST_THRESHOLD: u64 = 100;
+const MINT: Pubkey = Pubkey::new_from_array([0x71; 32]);
+
+/// An owner with a single position holding `amount` and locked until
+/// `locked_until`, the pool's tokens backing it, and a stranger with no
+/// relationship to either.
+struct Fixture {
+    banks: BanksClient,
+    owner: Keypair,
+    authority: Keypair,
+    stranger: Keypair,
+    config: Pubkey,
+    pool: Pubkey,
+    user_account: Pubkey,
+    user_deposit: Pubkey,
+    pool_tokens: Pubkey,
+}
+
+async fn position_holding(amount: u64) -> Fixture {
+    position_locked_until(amount, 0).await
+}
+
+async fn position_locked_until(amount: u64, locked_until: i64) -> Fixture {
+    let (owner_keypair, authority_keypair, stranger) = (Keypair::new(), Keypair::new(), Keypair::new());
+    let owner = owner_keypair.pubkey();
+    let (config, config_bump) = Pubkey::find_program_address(&[b"config"], &ID);
+    let config_state = Config {
+        admin: Pubkey::new_unique(),
+        fee_bps: 100,
+        fee_holiday_until: 0,
+        max_positions: 1,
+        dust_threshold: DUST_THRESHOLD,
+        bump: config_bump,
+    };
+    let authority = authority_keypair.pubkey();
+    let (pool, pool_bump) = Pubkey::find_program_address(&[b"pool", authority.as_ref()], &ID);
+    let pool_state = Pool {
+        authority,
+        total_deposited: amount,
+        reward_rate: 10,
+        config,
+        total_accrued: 0,
+        global_tvl_cap: u64::MAX,
+        lock_secs: 0,
+        dust_swept: 0,
+        min_hold_secs: 0,
+        early_exit_bps: 0,
+        early_exit_fees: 0,
+        deposit_mint: MINT,
+        reward_mint: Pubkey::default(),
+        vault: Pubkey::default(),
+        bump: pool_bump,
+    };
+    let (user_account, user_bump) = Pubkey::find_program_address(&[b"user", owner.as_ref()], &ID);
+    let user_state = UserAccount { owner, open_positions: 1, referrer: None, referrals: 0, bump: user_bump };
+    let (user_deposit, deposit_bump) =
+        Pubkey::find_program_address(&[b"user_deposit", pool.as_ref(), owner.as_ref()], &ID);
+    let deposit_state = UserDeposit {
+        pool,
+        owner,
+        amount,
+        locked_until,
+        deposited_at: 0,
+        permit_nonce: 0,
+        accrued_rewards: 0,
+        rewards_updated_at: 0,
+        bump: deposit_bump,
+    };
+
+    let pool_tokens = Pubkey::new_unique();
+
+    let mut test = program_test();
+    for signer in [owner, authority, stranger.pubkey()] {
+        test.add_account(signer, system_account(LAMPORTS_PER_SOL));
+    }
+    test.add_account(pool_tokens, token_account(MINT, pool, amount));
+    test.add_account(config, anchor_account(&config_state, ID));
+    test.add_account(pool, anchor_account(&pool_state, ID));
+    test.add_account(user_account, anchor_account(&user_state, ID));
+    test.add_account(user_deposit, anchor_account(&deposit_state, ID));
+    let (banks, _payer, _) = test.start().await;
+    Fixture {
+        banks,
+        owner: owner_keypair,
+        authority: authority_keypair,
+        stranger,
+        config,
+        pool,
+        user_account,
+        user_deposit,
+        pool_tokens,
+    }
+}
+
+impl Fixture {
+    /// Every account the pool authority would pass to sweep the position.
+    fn accounts(&self) -> accounts::SweepDust {
+        accounts::SweepDust {
+            config: self.config,
+            pool: self.pool,
+            user_account: self.user_account,
+            user_deposit: self.user_deposit,
+            owner: self.owner.pubkey(),
+            sweeper: self.authority.pubkey(),
+        }
+    }
+
+    async fn sweep_with(&mut self, accounts: accounts::SweepDust, sweeper: &Keypair) -> Result<(), TransactionError> {
+        let ix = Instruction::new_with_bytes(ID, &instruction::SweepDust {}.data(), accounts.to_account_metas(None));
+        send(&mut self.banks, &[ix], &[sweeper]).await
+    }
+
+    async fn sweep(&mut self) -> Result<(), TransactionError> {
+        let authority = self.authority.insecure_clone();
+        self.sweep_with(self.accounts(), &authority).await
+    }
+
+    async fn tokens(&mut self, address: Pubkey) -> u64 {
+        load::<TokenAccount>(&mut self.banks, address).await.amount
+    }
+
+    async fn lamports(&mut self, address: Pubkey) -> u64 {
+        self.banks.get_balance(address).await.unwrap()
+    }
+
+    async fn pool(&mut self) -> Pool {
+        load(&mut self.banks, self.pool).await
+    }
+
+    async fn open_positions(&mut self) -> u16 {
+        load::<UserAccount>(&mut self.banks, self.user_account).await.open_positions
+    }
+}
+
+#[tokio::test]
+async fn a_position_below_the_threshold_is_swept_into_the_pool() {
+    let dust = DUST_THRESHOLD - 1;
+    let mut fixture = position_holding(dust).await;
+    let owner = fixture.owner.pubkey();
+    let owner_before = fixture.lamports(owner).await;
+    let rent = fixture.lamports(fixture.user_deposit).await;
+
+    fixture.sweep().await.expect("dust swept");
+
+    let user_deposit = fixture.user_deposit;
+    assert!(fixture.banks.get_account(user_deposit).await.unwrap().is_none());
+    assert_eq!(fixture.lamports(owner).await, owner_before + rent, "rent goes back to the owner");
+    assert_eq!(fixture.tokens(fixture.pool_tokens).await, dust, "the dust stays with the pool");
+    let pool = fixture.pool().await;
+    assert_eq!((pool.total_deposited, pool.dust_swept), (0, dust));
+    assert_eq!(fixture.open_positions().await, 0, "the position's slot is freed");
+}
+
+#[tokio::test]
+async fn the_owner_may_sweep_their_own_position() {
+    let mut fixture = position_holding(DUST_THRESHOLD - 1).await;
+    let accounts = accounts::SweepDust { sweeper: fixture.owner.pubkey(), ..fixture.accounts() };
+    let owner = fixture.owner.insecure_clone();
+
+    fixture.sweep_with(accounts, &owner).await.expect("own position");
+
+    assert_eq!(fixture.pool().await.dust_swept, DUST_THRESHOLD - 1);
+}
+
+#[tokio::test]
+async fn a_stranger_cannot_sweep() {
+    let mut fixture = position_holding(DUST_THRESHOLD - 1).await;
+    let accounts = accounts::SweepDust { sweeper: fixture.stranger.pubkey(), ..fixture.accounts() };
+    let stranger = fixture.stranger.insecure_clone();
+
+    let err = fixture.sweep_with(accounts, &stranger).await.expect_err("neither owner nor authority");
+
+    assert_eq!(err, custom_error(0, ValidationError::UnauthorizedSweeper));
+    assert_eq!(fixture.open_positions().await, 1);
+}
+
+#[tokio::test]
+async fn a_position_above_the_threshold_is_not_swept() {
+    let mut fixture = position_holding(DUST_THRESHOLD + 1).await;
+
+    let err = fixture.sweep().await.expect_err("not dust");
+
+    assert_eq!(err, custom_error(0, ValidationError::NotDust));
+    assert_eq!(fixture.pool().await.total_deposited, DUST_THRESHOLD + 1);
+    assert_eq!(fixture.open_positions().await, 1);
+}
+
+#[tokio::test]
+async fn a_position_exactly_at_the_threshold_is_not_swept() {
+    let mut fixture = position_holding(DUST_THRESHOLD).await;
+
+    let err = fixture.sweep().await.expect_err("the threshold is exclusive");
+
+    assert_eq!(err, custom_error(0, ValidationError::NotDust));
+}
+
+#[tokio::test]
+async fn the_sweeper_cannot_take_the_rent() {
+    let mut fixture = position_holding(0).await;
+    let accounts = accounts::SweepDust { owner: fixture.authority.pubkey(), ..fixture.accounts() };
+    let authority = fixture.authority.insecure_clone();
+
+    let err = fixture.sweep_with(accounts, &authority).await.expect_err("rent redirected");
+
+    assert_eq!(err, custom_error(0, AnchorError::ConstraintSeeds));
+}
+
+#[tokio::test]
+async fn a_locked_position_is_not_swept() {
+    // Sweeping would otherwise be a way out of the lock for a small deposit
+    let mut fixture = position_locked_until(DUST_THRESHOLD - 1, i64::MAX).await;
+
+    let err = fixture.sweep().await.expect_err("still locked");
+
+    assert_eq!(err, custom_error(0, ValidationError::DepositLocked));
+    assert_eq!(fixture.pool().await.total_deposited, DUST_THRESHOLD - 1);
+    assert_eq!(fixture.open_positions().await, 1);
+}
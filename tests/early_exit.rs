@@ -0,0 +1,188 @@
+//! Early-exit penalties in the account-validation pool.
+//!
+//! A withdrawal inside the pool's `min_hold_secs` forfeits `early_exit_bps`
+//! of the amount (rounded up) to the pool; one at or after the end of the
+//! hold is paid out in full.
+
+use account_validation::{accounts, instruction, Pool, UserDeposit, ID};
+use anchor_lang::{InstructionData, ToAccountMetas};
+use anchor_spl::token::{spl_token, TokenAccount};
+use security_tests::{load, pda, program_test, send, send_for_u64, set_time, system_account, token_account};
+use solana_program_test::ProgramTestContext;
+use solana_sdk::{
+    instruction::Instruction,
+    native_token::LAMPORTS_PER_SOL,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    system_program,
+};
+
+const MINT: Pubkey = Pubkey::new_from_array([0x72; 32]);
+const MIN_HOLD_SECS: i64 = 60 * 60;
+const EARLY_EXIT_BPS: u16 = 50;
+const DEPOSIT_AT: i64 = 1_000_000;
+const DEPOSIT: u64 = 10_000;
+
+fn ix(accounts: impl ToAccountMetas, data: impl InstructionData) -> Instruction {
+    Instruction::new_with_bytes(ID, &data.data(), accounts.to_account_metas(None))
+}
+
+/// A pool with a minimum hold and no lock, with the user holding `DEPOSIT`
+/// deposited at `DEPOSIT_AT` from `token`.
+struct Fixture {
+    context: ProgramTestContext,
+    user: Keypair,
+    pool: Pubkey,
+    user_deposit: Pubkey,
+    token: Pubkey,
+    pool_tokens: Pubkey,
+}
+
+async fn deposited() -> Fixture {
+    let user = Keypair::new();
+    let (token, pool_tokens) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let config = pda(&[b"config"], &ID);
+    let pool = pda(&[b"pool", user.pubkey().as_ref()], &ID);
+    let mut test = program_test();
+    test.add_account(user.pubkey(), system_account(LAMPORTS_PER_SOL));
+    test.add_account(token, token_account(MINT, user.pubkey(), DEPOSIT));
+    test.add_account(pool_tokens, token_account(MINT, pool, 0));
+    let mut context = test.start_with_context().await;
+    set_time(&mut context, DEPOSIT_AT).await;
+
+    let user_account = pda(&[b"user", user.pubkey().as_ref()], &ID);
+    let user_deposit = pda(&[b"user_deposit", pool.as_ref(), user.pubkey().as_ref()], &ID);
+    let setup = [
+        ix(
+            accounts::InitializeConfig { config, admin: user.pubkey(), system_program: system_program::ID },
+            instruction::InitializeConfig { fee_bps: 100, max_positions: 1, dust_threshold: 0 },
+        ),
+        ix(
+            accounts::InitializePool { config, pool, authority: user.pubkey(), system_program: system_program::ID },
+            instruction::InitializePool {
+                reward_rate: 10,
+                global_tvl_cap: u64::MAX,
+                lock_secs: 0,
+                min_hold_secs: MIN_HOLD_SECS,
+                early_exit_bps: EARLY_EXIT_BPS,
+                deposit_mint: MINT,
+                reward_mint: Pubkey::default(),
+            },
+        ),
+        ix(
+            accounts::InitializeUserAccount { user_account, user: user.pubkey(), system_program: system_program::ID },
+            instruction::InitializeUserAccount {},
+        ),
+        ix(
+            accounts::OpenDeposit {
+                config,
+                pool,
+                user_account,
+                user_deposit,
+                user: user.pubkey(),
+                system_program: system_program::ID,
+            },
+            instruction::OpenDeposit {},
+        ),
+        ix(
+            accounts::DepositSecure {
+                config,
+                pool,
+                user_deposit,
+                user_token_account: token,
+                pool_token_account: pool_tokens,
+                user: user.pubkey(),
+                token_program: spl_token::ID,
+            },
+            instruction::DepositSecure { amount: DEPOSIT },
+        ),
+    ];
+    send(&mut context.banks_client, &setup, &[&user]).await.expect("setup");
+    Fixture { context, user, pool, user_deposit, token, pool_tokens }
+}
+
+impl Fixture {
+    /// Withdraws `amount` at `unix_timestamp`; returns what was paid out.
+    async fn withdraw_at(&mut self, unix_timestamp: i64, amount: u64) -> u64 {
+        set_time(&mut self.context, unix_timestamp).await;
+        let withdraw = ix(
+            accounts::Withdraw {
+                pool: self.pool,
+                user_deposit: self.user_deposit,
+                pool_token_account: self.pool_tokens,
+                user_token_account: self.token,
+                user: self.user.pubkey(),
+                token_program: spl_token::ID,
+            },
+            instruction::Withdraw { amount },
+        );
+        send_for_u64(&mut self.context.banks_client, &[withdraw], &[&self.user]).await.expect("withdrawal")
+    }
+
+    async fn tokens(&mut self, key: Pubkey) -> u64 {
+        load::<TokenAccount>(&mut self.context.banks_client, key).await.amount
+    }
+
+    async fn pool(&mut self) -> Pool {
+        load(&mut self.context.banks_client, self.pool).await
+    }
+
+    async fn user_deposit(&mut self) -> UserDeposit {
+        load(&mut self.context.banks_client, self.user_deposit).await
+    }
+}
+
+#[tokio::test]
+async fn deposit_starts_the_hold() {
+    let mut fixture = deposited().await;
+
+    assert_eq!(fixture.user_deposit().await.deposited_at, DEPOSIT_AT);
+}
+
+#[tokio::test]
+async fn withdrawing_inside_the_hold_pays_the_penalty() {
+    let mut fixture = deposited().await;
+
+    let paid_out = fixture.withdraw_at(DEPOSIT_AT + MIN_HOLD_SECS - 1, DEPOSIT).await;
+
+    // 10_000 * 50 / 10_000
+    assert_eq!(paid_out, DEPOSIT - 50);
+    let pool = fixture.pool().await;
+    assert_eq!((pool.total_deposited, pool.early_exit_fees), (0, 50));
+    assert_eq!(fixture.user_deposit().await.amount, 0);
+    // The penalty stays in the pool's tokens
+    assert_eq!(fixture.tokens(fixture.token).await, DEPOSIT - 50);
+    assert_eq!(fixture.tokens(fixture.pool_tokens).await, 50);
+}
+
+#[tokio::test]
+async fn the_penalty_rounds_up() {
+    let mut fixture = deposited().await;
+
+    // 199 * 50 / 10_000 = 0.995 - a piece this small still pays 1
+    let paid_out = fixture.withdraw_at(DEPOSIT_AT, 199).await;
+
+    assert_eq!(paid_out, 198);
+    assert_eq!(fixture.pool().await.early_exit_fees, 1);
+}
+
+#[tokio::test]
+async fn withdrawing_exactly_at_the_end_of_the_hold_is_free() {
+    let mut fixture = deposited().await;
+
+    let paid_out = fixture.withdraw_at(DEPOSIT_AT + MIN_HOLD_SECS, DEPOSIT).await;
+
+    assert_eq!(paid_out, DEPOSIT);
+    assert_eq!(fixture.pool().await.early_exit_fees, 0);
+}
+
+#[tokio::test]
+async fn withdrawing_after_the_hold_is_free() {
+    let mut fixture = deposited().await;
+
+    let paid_out = fixture.withdraw_at(DEPOSIT_AT + MIN_HOLD_SECS + 1, DEPOSIT).await;
+
+    assert_eq!(paid_out, DEPOSIT);
+    let pool = fixture.pool().await;
+    assert_eq!((pool.total_deposited, pool.early_exit_fees), (0, 0));
+}
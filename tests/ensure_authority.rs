@@ -0,0 +1,65 @@
+//! The shared `ensure_authority!` check.
+//!
+//! An authority passes only if it both signed and is the stored key;
+//! either half on its own is rejected.
+
+use anchor_lang::error::ErrorCode as AnchorError;
+use anchor_lang::prelude::{AccountInfo, ProgramError, Pubkey, Result};
+use escrow::EscrowError;
+use security_common::{ensure_authority, SecurityError};
+
+fn check(expected: Pubkey, signer: &AccountInfo) -> Result<()> {
+    ensure_authority!(expected, signer, EscrowError::WrongArbiter);
+    Ok(())
+}
+
+fn check_default(expected: Pubkey, signer: &AccountInfo) -> Result<()> {
+    ensure_authority!(expected, signer);
+    Ok(())
+}
+
+/// Runs `f` against an account at `key` that did or didn't sign
+fn with_account<T>(key: Pubkey, is_signer: bool, f: impl FnOnce(&AccountInfo) -> T) -> T {
+    let owner = Pubkey::default();
+    let mut lamports = 0;
+    let info = AccountInfo::new(&key, is_signer, false, &mut lamports, &mut [], &owner, false, 0);
+    f(&info)
+}
+
+fn code(result: Result<()>) -> ProgramError {
+    result.expect_err("authority rejected").into()
+}
+
+#[test]
+fn the_stored_authority_signing_passes() {
+    let authority = Pubkey::new_unique();
+
+    with_account(authority, true, |signer| check(authority, signer)).expect("authority signed");
+}
+
+#[test]
+fn a_non_signing_authority_is_rejected() {
+    let authority = Pubkey::new_unique();
+
+    let err = with_account(authority, false, |signer| check(authority, signer));
+
+    assert_eq!(code(err), ProgramError::Custom(AnchorError::AccountNotSigner.into()));
+}
+
+#[test]
+fn a_signer_with_the_wrong_key_is_rejected() {
+    let authority = Pubkey::new_unique();
+
+    let err = with_account(Pubkey::new_unique(), true, |signer| check(authority, signer));
+
+    assert_eq!(code(err), ProgramError::Custom(EscrowError::WrongArbiter.into()));
+}
+
+#[test]
+fn the_error_defaults_to_security_error_unauthorized() {
+    let authority = Pubkey::new_unique();
+
+    let err = with_account(Pubkey::new_unique(), true, |signer| check_default(authority, signer));
+
+    assert_eq!(code(err), ProgramError::Custom(SecurityError::Unauthorized.into()));
+}
@@ -0,0 +1,114 @@
+//! Error categories for client tooling.
+//!
+//! Each program categorizes its own error enum with `error_categories!`,
+//! and `program_error_category` looks a code up in the failing program's
+//! enum. These rows go through the real enums and their codes, so a
+//! variant that moves category is caught here.
+
+use anchor_lang::error::ErrorCode as AnchorError;
+use security_common::{
+    error_category, program_error_category, CategorizedError, ErrorCategory, LessonId, SecurityError,
+};
+
+use ErrorCategory::{Auth, Math, State, Validation};
+
+/// (lesson, code, categorized as, expected)
+type Row = (LessonId, u32, ErrorCategory, ErrorCategory);
+
+/// Classifies `error`'s code against its own program's enum
+fn row<E: CategorizedError>(lesson: LessonId, error: E, expected: ErrorCategory) -> Row {
+    let code = error.into();
+    (lesson, code, program_error_category::<E>(code), expected)
+}
+
+fn representative_errors() -> Vec<Row> {
+    vec![
+        row(LessonId::MissingSignerCheck, missing_signer_check::CustomError::WithdrawalLimitReached, State),
+        row(LessonId::MissingSignerCheck, missing_signer_check::CustomError::DelegateExpired, Auth),
+        row(LessonId::MissingSignerCheck, missing_signer_check::CustomError::InvalidExpiry, Validation),
+        row(LessonId::MissingSignerCheck, missing_signer_check::MultisigError::DuplicateSigner, Auth),
+        row(LessonId::MissingSignerCheck, missing_signer_check::MultisigError::TimelockNotElapsed, State),
+        row(LessonId::AccountValidation, account_validation::ValidationError::InvalidPDA, Validation),
+        row(LessonId::AccountValidation, account_validation::ValidationError::TvlCapReached, State),
+        row(LessonId::AccountValidation, account_validation::ValidationError::InvalidPenalty, Validation),
+        row(LessonId::AccountValidation, account_validation::ValidationError::InvalidPermit, Auth),
+        row(LessonId::AccountValidation, account_validation::ValidationError::PermitReplayed, State),
+        row(LessonId::AccountValidation, account_validation::ValidationError::DeadlinePassed, State),
+        row(LessonId::AccountValidation, account_validation::ValidationError::NotRentExempt, State),
+        row(LessonId::AccountValidation, account_validation::ValidationError::BrokenAccountLink, Validation),
+        row(LessonId::AccountValidation, account_validation::ValidationError::UnauthorizedSweeper, Auth),
+        row(LessonId::IntegerOverflow, integer_overflow::MathError::CastOverflow, Math),
+        row(LessonId::IntegerOverflow, integer_overflow::MathError::InvalidBps, Validation),
+        row(LessonId::IntegerOverflow, integer_overflow::MathError::TooManyPeriods, Validation),
+        row(LessonId::IntegerOverflow, integer_overflow::MathError::MaxSupplyExceeded, State),
+        row(LessonId::ArbitraryCpi, arbitrary_cpi::CpiError::InvalidProgram, Validation),
+        row(LessonId::ArbitraryCpi, arbitrary_cpi::CpiError::RevealTooEarly, State),
+        row(LessonId::ArbitraryCpi, arbitrary_cpi::CpiError::UnexpectedFollowingInstruction, Validation),
+        row(LessonId::ArbitraryCpi, arbitrary_cpi::CpiError::DuplicateRecipient, Validation),
+        row(LessonId::ArbitraryCpi, arbitrary_cpi::CpiError::SlippageExceeded, Validation),
+        row(LessonId::Reinitialization, reinitialization::ReinitError::VaultNotEmpty, State),
+        row(LessonId::Reinitialization, reinitialization::ReinitError::InvalidSize, Validation),
+        row(LessonId::Reinitialization, reinitialization::ReinitError::NotInitialized, State),
+        row(LessonId::Reinitialization, reinitialization::ReinitError::NotPendingAdmin, Auth),
+        row(LessonId::TypeCosplay, type_cosplay::TypeCosplayError::NotAdmin, Auth),
+        row(LessonId::TypeCosplay, type_cosplay::TypeCosplayError::MigrationOverflow, Math),
+        row(LessonId::TypeCosplay, type_cosplay::TypeCosplayError::UnexpectedKind, Validation),
+        row(LessonId::TypeCosplay, type_cosplay::TypeCosplayError::WrongOwnerProgram, Validation),
+        row(LessonId::ClosingAccounts, closing_accounts::CloseError::CooldownActive, State),
+        row(LessonId::ClosingAccounts, closing_accounts::CloseError::InvalidReopenWindow, Validation),
+        row(LessonId::EscrowRelease, escrow::EscrowError::WrongArbiter, Auth),
+        row(LessonId::EscrowRelease, escrow::EscrowError::AlreadyReleased, State),
+        row(LessonId::InsecureRandomness, insecure_randomness::RandomnessError::WinnerAlreadyPicked, State),
+        row(LessonId::InsecureRandomness, insecure_randomness::RandomnessError::CommitmentMismatch, Validation),
+        row(LessonId::NativeSolVault, native_sol_vault::NativeVaultError::BelowRentExempt, State),
+        row(LessonId::UpgradeAuthority, upgrade_authority::UpgradeAuthorityError::NotUpgradeAuthority, Auth),
+        row(LessonId::FlashLoanGuard, flash_loan_guard::FlashLoanError::MissingRepay, Validation),
+        row(LessonId::FlashLoanGuard, flash_loan_guard::FlashLoanError::FlashLoanActive, State),
+    ]
+}
+
+#[test]
+fn program_errors_map_to_their_category() {
+    let wrong: Vec<_> = representative_errors()
+        .into_iter()
+        .filter(|(_, _, actual, expected)| actual != expected)
+        .collect();
+    assert!(wrong.is_empty(), "miscategorized (lesson, code, expected): {:?}", wrong);
+}
+
+#[test]
+fn every_lesson_has_a_representative() {
+    let covered: Vec<LessonId> = representative_errors().iter().map(|row| row.0).collect();
+    let missing: Vec<_> = LessonId::ALL.iter().filter(|lesson| !covered.contains(lesson)).collect();
+    assert!(missing.is_empty(), "no representative errors for {:?}", missing);
+}
+
+#[test]
+fn shared_errors_keep_their_category_in_every_program() {
+    assert_eq!(error_category(SecurityError::Unauthorized.into()), Auth);
+    assert_eq!(error_category(SecurityError::ConservationViolation.into()), Math);
+    assert_eq!(error_category(SecurityError::ZeroAmount.into()), Validation);
+    assert_eq!(error_category(SecurityError::AlreadyInitialized.into()), State);
+
+    // The re-exported enum is the shared one, whichever program returns it
+    let overflow: u32 = flash_loan_guard::SecurityError::Overflow.into();
+    assert_eq!(program_error_category::<flash_loan_guard::FlashLoanError>(overflow), Math);
+    assert_eq!(program_error_category::<account_validation::ValidationError>(overflow), Math);
+    assert_eq!(program_error_category::<closing_accounts::CloseError>(overflow), Math);
+}
+
+#[test]
+fn anchor_errors_are_categorized() {
+    assert_eq!(error_category(AnchorError::ConstraintHasOne.into()), Auth);
+    assert_eq!(error_category(AnchorError::AccountNotSigner.into()), Auth);
+    assert_eq!(error_category(AnchorError::AccountDiscriminatorAlreadySet.into()), State);
+    assert_eq!(error_category(AnchorError::ConstraintSeeds.into()), Validation);
+    assert_eq!(error_category(AnchorError::AccountOwnedByWrongProgram.into()), Validation);
+}
+
+#[test]
+fn unknown_codes_are_validation() {
+    assert_eq!(error_category(6000), Validation);
+    assert_eq!(error_category(7999), Validation);
+    assert_eq!(program_error_category::<upgrade_authority::UpgradeAuthorityError>(6001), Validation);
+}
@@ -0,0 +1,184 @@
+//! Error code snapshots.
+//!
+//! Clients decode failures by number, so a variant must keep its code
+//! forever - a `SecurityError` one and every program's own. Reordering an
+//! enum, changing its offset or deleting a variant ahead of others shows up
+//! here.
+
+use anchor_lang::error::Error;
+use security_common::{CategorizedError, SecurityError, SECURITY_ERROR_OFFSET};
+
+/// (variant, code)
+const SNAPSHOT: &[(SecurityError, u32)] = &[
+    (SecurityError::Unauthorized, 7000),
+    (SecurityError::ZeroAmount, 7001),
+    (SecurityError::Overflow, 7002),
+    (SecurityError::InsufficientFunds, 7003),
+    (SecurityError::DivisionByZero, 7004),
+    (SecurityError::AlreadyInitialized, 7005),
+    (SecurityError::ComputeBudgetLow, 7006),
+    (SecurityError::ConservationViolation, 7007),
+    (SecurityError::AccountNotClosed, 7008),
+];
+
+#[test]
+fn shared_variants_keep_their_codes() {
+    assert_eq!(SECURITY_ERROR_OFFSET, 7000);
+    for (variant, code) in SNAPSHOT {
+        assert_eq!(u32::from(*variant), *code, "{} moved", variant.name());
+    }
+}
+
+#[test]
+fn anchor_errors_carry_the_shared_code() {
+    for (variant, code) in SNAPSHOT {
+        match Error::from(*variant) {
+            Error::AnchorError(e) => {
+                assert_eq!(e.error_code_number, *code);
+                assert_eq!(e.error_name, variant.name());
+            }
+            other => panic!("{} became {:?}", variant.name(), other),
+        }
+    }
+}
+
+#[test]
+fn every_program_reexports_the_same_codes() {
+    let reexported: [u32; 12] = [
+        missing_signer_check::SecurityError::Overflow.into(),
+        account_validation::SecurityError::Overflow.into(),
+        integer_overflow::SecurityError::Overflow.into(),
+        arbitrary_cpi::SecurityError::Overflow.into(),
+        reinitialization::SecurityError::Overflow.into(),
+        type_cosplay::SecurityError::Overflow.into(),
+        closing_accounts::SecurityError::Overflow.into(),
+        escrow::SecurityError::Overflow.into(),
+        insecure_randomness::SecurityError::Overflow.into(),
+        native_sol_vault::SecurityError::Overflow.into(),
+        upgrade_authority::SecurityError::Overflow.into(),
+        flash_loan_guard::SecurityError::Overflow.into(),
+    ];
+    assert!(reexported.iter().all(|code| *code == 7002), "{:?}", reexported);
+}
+
+#[test]
+fn shared_codes_stay_clear_of_program_errors() {
+    // Program enums use the default 6000 offset (01's multisig enum uses 6100);
+    // none of them is anywhere near 1000 variants long.
+    assert!(u32::from(integer_overflow::MathError::CastOverflow) < SECURITY_ERROR_OFFSET);
+    assert!(u32::from(missing_signer_check::MultisigError::ThresholdNotMet) < SECURITY_ERROR_OFFSET);
+}
+
+/// Names of `E`'s variants that are also `SecurityError` variants
+fn shadowed<E: CategorizedError + Into<Error>>() -> Vec<String> {
+    let shared: Vec<String> = SNAPSHOT.iter().map(|(variant, _)| variant.name()).collect();
+    E::ALL
+        .iter()
+        .filter_map(|variant| match Into::<Error>::into(*variant) {
+            Error::AnchorError(e) if shared.contains(&e.error_name) => Some(e.error_name),
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn programs_return_shared_failures_as_security_error() {
+    let shadowed = [
+        shadowed::<missing_signer_check::CustomError>(),
+        shadowed::<missing_signer_check::MultisigError>(),
+        shadowed::<account_validation::ValidationError>(),
+        shadowed::<integer_overflow::MathError>(),
+        shadowed::<arbitrary_cpi::CpiError>(),
+        shadowed::<reinitialization::ReinitError>(),
+        shadowed::<type_cosplay::TypeCosplayError>(),
+        shadowed::<closing_accounts::CloseError>(),
+        shadowed::<escrow::EscrowError>(),
+        shadowed::<insecure_randomness::RandomnessError>(),
+        shadowed::<native_sol_vault::NativeVaultError>(),
+        shadowed::<upgrade_authority::UpgradeAuthorityError>(),
+        shadowed::<flash_loan_guard::FlashLoanError>(),
+    ]
+    .concat();
+    assert!(shadowed.is_empty(), "local copies of shared errors: {:?}", shadowed);
+}
+
+/// Asserts `E`'s variants carry exactly the `(name, code)` pairs in
+/// `expected`, in code order.
+fn assert_codes<E: CategorizedError + Into<Error>>(expected: &[(&str, u32)]) {
+    let mut actual: Vec<(String, u32)> = E::ALL
+        .iter()
+        .map(|variant| match Into::<Error>::into(*variant) {
+            Error::AnchorError(e) => (e.error_name, e.error_code_number),
+            Error::ProgramError(e) => panic!("not an Anchor error: {:?}", e),
+        })
+        .collect();
+    actual.sort_by_key(|(_, code)| *code);
+    let expected: Vec<(String, u32)> = expected.iter().map(|(name, code)| (name.to_string(), *code)).collect();
+    assert_eq!(actual, expected);
+}
+
+#[test]
+fn program_error_codes_never_move() {
+    // Gaps are variants that moved to `SecurityError`; their codes stay unused
+    assert_codes::<missing_signer_check::CustomError>(&[
+        ("WithdrawalLimitReached", 6001), ("BelowRentExempt", 6003), ("DelegateExpired", 6004), ("InvalidExpiry", 6005),
+    ]);
+    assert_codes::<missing_signer_check::MultisigError>(&[
+        ("ThresholdNotMet", 6100), ("InvalidThreshold", 6101), ("DuplicateSigner", 6102), ("InvalidTimelock", 6103),
+        ("NoPendingChange", 6104), ("TimelockNotElapsed", 6105),
+    ]);
+    assert_codes::<account_validation::ValidationError>(&[
+        ("InvalidOwner", 6000), ("InvalidPDA", 6001), ("TokenAccountOwnerMismatch", 6002),
+        ("ProgramAccountNotAllowed", 6004), ("PoolInsolvent", 6005), ("TvlCapReached", 6006), ("DepositLocked", 6007),
+        ("InvalidLockPeriod", 6008), ("InvalidSplit", 6009), ("RecipientCountMismatch", 6010),
+        ("TokenMintMismatch", 6011), ("InvalidSeeds", 6012), ("TooManyPositions", 6013), ("DepositNotEmpty", 6014),
+        ("ReferrerAlreadySet", 6015), ("SelfReferral", 6016), ("NotDust", 6017), ("UnexpectedAccountLength", 6018),
+        ("InvalidPenalty", 6019), ("PermitNotApproved", 6020), ("InvalidPermit", 6021), ("PermitExpired", 6022),
+        ("PermitReplayed", 6023), ("SlippageExceeded", 6024), ("DeadlinePassed", 6025), ("NotRentExempt", 6026),
+        ("BrokenAccountLink", 6027), ("UnauthorizedSweeper", 6028),
+    ]);
+    assert_codes::<integer_overflow::MathError>(&[
+        ("CastOverflow", 6003), ("SelfReferral", 6005), ("ReferrerMismatch", 6006), ("InvalidReferralShare", 6007),
+        ("FeePositionMismatch", 6008), ("DuplicatePosition", 6009), ("InvalidBps", 6010), ("TooManyPeriods", 6011),
+        ("MaxSupplyExceeded", 6012),
+    ]);
+    assert_codes::<arbitrary_cpi::CpiError>(&[
+        ("InvalidProgram", 6000), ("NotExecutable", 6001), ("InvalidOracle", 6002), ("DuplicateAccount", 6004),
+        ("AccountDataTooSmall", 6006), ("DecimalsMismatch", 6007), ("InvalidReturnData", 6008),
+        ("RewardBelowMinimum", 6009), ("CommitmentMismatch", 6010), ("RevealTooEarly", 6011),
+        ("UnexpectedFollowingInstruction", 6012), ("UnpairedRemainingAccount", 6013), ("DuplicateRecipient", 6014),
+        ("SlippageExceeded", 6015),
+    ]);
+    assert_codes::<reinitialization::ReinitError>(&[
+        ("AddressPreFunded", 6003), ("InstructionDisabled", 6004), ("KillSwitchFull", 6005), ("VaultNotEmpty", 6006),
+        ("InvalidSize", 6007), ("NotInitialized", 6008), ("WrongAccountType", 6009), ("NotPendingAdmin", 6010),
+    ]);
+    assert_codes::<type_cosplay::TypeCosplayError>(&[
+        ("NotAdmin", 6000), ("InvalidAccountType", 6001), ("TypeMismatch", 6002), ("InvalidAccountSize", 6003),
+        ("MigrationOverflow", 6004), ("UnexpectedKind", 6005), ("WrongOwnerProgram", 6006),
+    ]);
+    assert_codes::<closing_accounts::CloseError>(&[
+        ("AlreadyClosed", 6001), ("ProfileTombstoneExists", 6002), ("BatchLengthMismatch", 6003),
+        ("InvalidUserAccount", 6004), ("CooldownActive", 6006), ("InvalidCooldown", 6007), ("CloseRateLimited", 6008),
+        ("InvalidCloseLimit", 6009), ("BalanceNotEmpty", 6010), ("RewardsNotClaimed", 6011),
+        ("ReopenWindowClosed", 6012), ("InvalidReopenWindow", 6013),
+    ]);
+    assert_codes::<escrow::EscrowError>(&[
+        ("ReleaseConditionsNotMet", 6000), ("AlreadyReleased", 6001), ("WrongArbiter", 6002),
+    ]);
+    assert_codes::<insecure_randomness::RandomnessError>(&[
+        ("NoEntrants", 6000), ("LotteryFull", 6001), ("WinnerAlreadyPicked", 6002), ("CommitmentMismatch", 6003),
+        ("EntriesClosed", 6004), ("EntriesOpen", 6005),
+    ]);
+    assert_codes::<native_sol_vault::NativeVaultError>(&[
+        ("BelowRentExempt", 6001),
+    ]);
+    assert_codes::<upgrade_authority::UpgradeAuthorityError>(&[
+        ("NotUpgradeAuthority", 6000),
+    ]);
+    assert_codes::<flash_loan_guard::FlashLoanError>(&[
+        ("InsufficientLiquidity", 6001), ("MissingBalanceCheck", 6002), ("LoanNotRepaid", 6003),
+        ("LoanAlreadyOutstanding", 6004), ("BorrowViaCpi", 6005), ("MissingRepay", 6006), ("RepayMismatch", 6007),
+        ("FlashLoanActive", 6008),
+    ]);
+}
@@ -0,0 +1,149 @@
+//! Escrow release conditions.
+//!
+//! `release_secure` needs the depositor's signature plus one of: the clock
+//! reaching `release_after`, or the recorded arbiter co-signing.
+
+use anchor_lang::error::ErrorCode as AnchorError;
+use anchor_lang::{InstructionData, ToAccountMetas};
+use escrow::{accounts, instruction, Escrow, EscrowError, ID};
+use security_tests::{custom_error, load, pda, program_test, send, set_time, system_account};
+use solana_program_test::ProgramTestContext;
+use solana_sdk::{
+    instruction::Instruction,
+    native_token::LAMPORTS_PER_SOL,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    system_program,
+};
+
+const AMOUNT: u64 = LAMPORTS_PER_SOL;
+const RELEASE_AFTER: i64 = 1_000_000;
+
+fn ix(accounts: impl ToAccountMetas, data: impl InstructionData) -> Instruction {
+    Instruction::new_with_bytes(ID, &data.data(), accounts.to_account_metas(None))
+}
+
+/// An escrow of `AMOUNT`, funded by the depositor, with the clock under
+/// test control.
+struct Fixture {
+    context: ProgramTestContext,
+    depositor: Keypair,
+    arbiter: Keypair,
+    beneficiary: Pubkey,
+    escrow: Pubkey,
+}
+
+impl Fixture {
+    fn release(&self, depositor: Pubkey, arbiter: Option<Pubkey>) -> Instruction {
+        ix(
+            accounts::ReleaseSecure { escrow: self.escrow, depositor, beneficiary: self.beneficiary, arbiter },
+            instruction::ReleaseSecure {},
+        )
+    }
+
+    async fn beneficiary_balance(&mut self) -> u64 {
+        self.context.banks_client.get_balance(self.beneficiary).await.unwrap()
+    }
+
+    async fn state(&mut self) -> Escrow {
+        load(&mut self.context.banks_client, self.escrow).await
+    }
+}
+
+async fn escrowed() -> Fixture {
+    let depositor = Keypair::new();
+    let arbiter = Keypair::new();
+    let beneficiary = Pubkey::new_unique();
+    let mut test = program_test();
+    test.add_account(depositor.pubkey(), system_account(10 * LAMPORTS_PER_SOL));
+    test.add_account(arbiter.pubkey(), system_account(LAMPORTS_PER_SOL));
+    test.add_account(beneficiary, system_account(LAMPORTS_PER_SOL));
+    let context = test.start_with_context().await;
+
+    let escrow = pda(&[b"escrow", depositor.pubkey().as_ref()], &ID);
+    let mut fixture = Fixture { context, depositor, arbiter, beneficiary, escrow };
+    let initialize = ix(
+        accounts::InitializeEscrow {
+            escrow,
+            depositor: fixture.depositor.pubkey(),
+            beneficiary,
+            arbiter: fixture.arbiter.pubkey(),
+            system_program: system_program::ID,
+        },
+        instruction::InitializeEscrow { amount: AMOUNT, release_after: RELEASE_AFTER },
+    );
+    send(&mut fixture.context.banks_client, &[initialize], &[&fixture.depositor]).await.expect("escrow funded");
+    fixture
+}
+
+#[tokio::test]
+async fn depositor_alone_releases_after_the_timeout() {
+    let mut fixture = escrowed().await;
+    let before = fixture.beneficiary_balance().await;
+    set_time(&mut fixture.context, RELEASE_AFTER).await;
+
+    let release = fixture.release(fixture.depositor.pubkey(), None);
+    send(&mut fixture.context.banks_client, &[release], &[&fixture.depositor]).await.expect("timed out");
+
+    assert_eq!(fixture.beneficiary_balance().await, before + AMOUNT);
+    let state = fixture.state().await;
+    assert!(state.released);
+    assert_eq!(state.amount, 0);
+}
+
+#[tokio::test]
+async fn arbiter_cosigns_an_early_release() {
+    let mut fixture = escrowed().await;
+    let before = fixture.beneficiary_balance().await;
+    set_time(&mut fixture.context, RELEASE_AFTER - 1).await;
+
+    let release = fixture.release(fixture.depositor.pubkey(), Some(fixture.arbiter.pubkey()));
+    send(&mut fixture.context.banks_client, &[release], &[&fixture.depositor, &fixture.arbiter])
+        .await
+        .expect("arbiter approved");
+
+    assert_eq!(fixture.beneficiary_balance().await, before + AMOUNT);
+    assert!(fixture.state().await.released);
+}
+
+#[tokio::test]
+async fn early_release_without_the_arbiter_is_rejected() {
+    let mut fixture = escrowed().await;
+    set_time(&mut fixture.context, RELEASE_AFTER - 1).await;
+
+    let release = fixture.release(fixture.depositor.pubkey(), None);
+    let err = send(&mut fixture.context.banks_client, &[release], &[&fixture.depositor]).await.expect_err("still locked");
+
+    assert_eq!(err, custom_error(0, EscrowError::ReleaseConditionsNotMet));
+    assert!(!fixture.state().await.released);
+}
+
+#[tokio::test]
+async fn someone_elses_cosignature_is_not_the_arbiters() {
+    let mut fixture = escrowed().await;
+    set_time(&mut fixture.context, RELEASE_AFTER - 1).await;
+    let impostor = Keypair::new();
+
+    let release = fixture.release(fixture.depositor.pubkey(), Some(impostor.pubkey()));
+    let err = send(&mut fixture.context.banks_client, &[release], &[&fixture.depositor, &impostor])
+        .await
+        .expect_err("wrong arbiter");
+
+    assert_eq!(err, custom_error(0, EscrowError::WrongArbiter));
+}
+
+#[tokio::test]
+async fn release_without_the_depositors_signature_is_rejected() {
+    let mut fixture = escrowed().await;
+    set_time(&mut fixture.context, RELEASE_AFTER).await;
+
+    // The arbiter pays, and claims the release the depositor never signed
+    let mut release = fixture.release(fixture.depositor.pubkey(), Some(fixture.arbiter.pubkey()));
+    for meta in release.accounts.iter_mut().filter(|meta| meta.pubkey == fixture.depositor.pubkey()) {
+        meta.is_signer = false;
+    }
+    let err = send(&mut fixture.context.banks_client, &[release], &[&fixture.arbiter]).await.expect_err("unsigned");
+
+    assert_eq!(err, custom_error(0, AnchorError::AccountNotSigner));
+    assert!(!fixture.state().await.released);
+}
@@ -0,0 +1,2858 @@
+//! Exploit regression matrix.
+//!
+//! Every attack the lessons describe, as one table of
+//! `(program, instruction, attack, expected)`. Each row runs its attack
+//! through the real program entrypoints and compares what actually happened
+//! with what the lesson promises: a VULNERABLE path that stops being
+//! exploitable is as much a regression as a SECURE path that starts letting
+//! the attack through.
+//!
+//! Adding a lesson means adding rows here - `every_program_has_rows` fails
+//! until you do.
+
+use std::collections::{BTreeSet, HashMap};
+
+use anchor_lang::error::ErrorCode as AnchorError;
+use anchor_lang::{AccountDeserialize, InstructionData, ToAccountMetas};
+use anchor_spl::token::{spl_token, TokenAccount};
+use security_tests::{
+    account_types, anchor_account, delegated_token_account, ed25519_instruction, mint_account, mock_reward, mock_swap, pda,
+    program_data_account, program_test, raw_account, system_account, token_account,
+};
+use solana_sdk::{
+    account::Account,
+    instruction::{AccountMeta, Instruction, InstructionError},
+    native_token::LAMPORTS_PER_SOL,
+    pubkey::Pubkey,
+    rent::Rent,
+    signature::{Keypair, Signer},
+    system_instruction::{self, SystemError},
+    system_program,
+    transaction::{Transaction, TransactionError},
+};
+use Outcome::{Exploited, Neutralized, Rejected};
+
+// ============================================================================
+// RUNNER
+// ============================================================================
+
+/// What an attack did when it ran.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Outcome {
+    /// The transaction landed and the attack took effect
+    Exploited,
+    /// The transaction landed but the attack had no effect
+    Neutralized,
+    /// The transaction failed with this error code
+    Rejected(u32),
+}
+
+/// State to preload and instructions to send, built around the attacker.
+///
+/// The attacker pays for and signs the transaction; nobody else signs.
+struct Scenario {
+    accounts: Vec<(Pubkey, Account)>,
+    instructions: Vec<Instruction>,
+    /// Decides, from post-state, whether a landed transaction did damage
+    exploited: fn(&Effects) -> bool,
+}
+
+/// Post-transaction state of every account the instructions touched.
+struct Effects {
+    accounts: HashMap<Pubkey, Account>,
+    return_data: Vec<u8>,
+}
+
+impl Effects {
+    fn exists(&self, key: &Pubkey) -> bool {
+        self.accounts.contains_key(key)
+    }
+
+    /// `None` if the account is gone or no longer deserializes as `T`.
+    fn load<T: AccountDeserialize>(&self, key: &Pubkey) -> Option<T> {
+        let account = self.accounts.get(key)?;
+        T::try_deserialize(&mut account.data.as_slice()).ok()
+    }
+
+    /// The runtime strips trailing zero bytes from return data, so pad it back.
+    fn returned_u64(&self) -> u64 {
+        let mut bytes = [0u8; 8];
+        bytes[..self.return_data.len()].copy_from_slice(&self.return_data);
+        u64::from_le_bytes(bytes)
+    }
+}
+
+/// Row of the matrix: `(program, instruction, attack, expected, scenario)`.
+type Row = (&'static str, &'static str, &'static str, Outcome, fn(&Pubkey) -> Scenario);
+
+async fn run(scenario: fn(&Pubkey) -> Scenario) -> Outcome {
+    let attacker = Keypair::new();
+    let scenario = scenario(&attacker.pubkey());
+
+    let mut test = program_test();
+    test.add_account(attacker.pubkey(), system_account(10 * LAMPORTS_PER_SOL));
+    for (key, account) in &scenario.accounts {
+        test.add_account(*key, account.clone());
+    }
+    let (mut banks, _payer, blockhash) = test.start().await;
+
+    let tx = Transaction::new_signed_with_payer(
+        &scenario.instructions,
+        Some(&attacker.pubkey()),
+        &[&attacker],
+        blockhash,
+    );
+    let result = banks
+        .process_transaction_with_metadata(tx)
+        .await
+        .expect("banks server unreachable");
+
+    match result.result {
+        Ok(()) => {
+            let mut effects = Effects {
+                accounts: HashMap::new(),
+                return_data: result
+                    .metadata
+                    .and_then(|metadata| metadata.return_data)
+                    .map(|return_data| return_data.data)
+                    .unwrap_or_default(),
+            };
+            for ix in &scenario.instructions {
+                for meta in &ix.accounts {
+                    if let Some(account) = banks.get_account(meta.pubkey).await.unwrap() {
+                        effects.accounts.insert(meta.pubkey, account);
+                    }
+                }
+            }
+            if (scenario.exploited)(&effects) {
+                Exploited
+            } else {
+                Neutralized
+            }
+        }
+        Err(TransactionError::InstructionError(_, InstructionError::Custom(code))) => Rejected(code),
+        Err(err) => panic!("transaction failed outside the program: {:?}", err),
+    }
+}
+
+// ============================================================================
+// THE MATRIX
+// ============================================================================
+
+fn matrix() -> Vec<Row> {
+    vec![
+        // 01 - missing signer check
+        ("missing_signer_check", "withdraw_vulnerable", "drain victim's vault without their signature", Exploited, missing_signer::withdraw_vulnerable),
+        ("missing_signer_check", "withdraw_secure", "drain victim's vault without their signature", Rejected(AnchorError::AccountNotSigner as u32), missing_signer::withdraw_secure),
+        ("missing_signer_check", "withdraw_manual_missing_is_signer", "drain victim's vault naming them as a raw AccountInfo", Exploited, missing_signer::withdraw_manual_missing_is_signer),
+        ("missing_signer_check", "withdraw_manual_signer_check", "drain victim's vault naming them as a raw AccountInfo", Rejected(code(missing_signer_check::SecurityError::Unauthorized)), missing_signer::withdraw_manual_signer_check),
+        ("missing_signer_check", "withdraw_secure", "withdraw the rent reserve so the vault is reaped", Rejected(code(missing_signer_check::CustomError::BelowRentExempt)), missing_signer::withdraw_rent_reserve),
+        ("missing_signer_check", "withdraw_multisig", "one signer passed twice to meet 2-of-3", Rejected(code(missing_signer_check::MultisigError::ThresholdNotMet)), missing_signer::withdraw_multisig_repeated_signer),
+        ("missing_signer_check", "withdraw_as_delegate_vulnerable", "expired delegate drains the vault", Exploited, missing_signer::withdraw_as_delegate_vulnerable),
+        ("missing_signer_check", "withdraw_as_delegate", "expired delegate drains the vault", Rejected(code(missing_signer_check::CustomError::DelegateExpired)), missing_signer::withdraw_as_delegate),
+        ("missing_signer_check", "propose_threshold_change", "one signer passed three times to meet 2-of-3", Rejected(code(missing_signer_check::MultisigError::ThresholdNotMet)), missing_signer::propose_with_repeated_signer),
+        // 02 - account validation
+        ("account_validation", "claim_rewards_vulnerable", "Pool-shaped data in an attacker-owned account drains the real reward vault", Exploited, validation::claim_rewards_vulnerable),
+        ("account_validation", "claim_rewards_secure", "Pool-shaped data in an attacker-owned account drains the real reward vault", Rejected(AnchorError::AccountOwnedByWrongProgram as u32), validation::claim_rewards_secure),
+        ("account_validation", "claim_rewards_manual_owner_check", "Pool-shaped data in an attacker-owned account drains the real reward vault", Rejected(code(account_validation::ValidationError::InvalidOwner)), validation::claim_rewards_manual_owner_check),
+        ("account_validation", "claim_rewards_secure", "claim ahead of others from an under-funded pool", Rejected(code(account_validation::ValidationError::PoolInsolvent)), validation::claim_rewards_insolvent),
+        ("account_validation", "swap_vulnerable", "zero-fee config at a non-canonical address", Exploited, validation::swap_vulnerable),
+        ("account_validation", "swap_secure", "zero-fee config at a non-canonical address", Rejected(AnchorError::ConstraintSeeds as u32), validation::swap_secure),
+        ("account_validation", "swap_secure", "pool created under an unrelated config", Rejected(AnchorError::ConstraintHasOne as u32), validation::swap_secure_mismatched_pool),
+        ("account_validation", "read_config_rent_checked", "zero-fee config at a non-canonical address", Rejected(code(account_validation::ValidationError::InvalidPDA)), validation::read_config_rent_checked),
+        ("account_validation", "deposit_vulnerable", "deposit from victim's token account", Exploited, validation::deposit_vulnerable),
+        ("account_validation", "deposit_secure", "deposit from victim's token account", Rejected(AnchorError::ConstraintTokenOwner as u32), validation::deposit_secure),
+        ("account_validation", "deposit_secure", "pool created under an unrelated config", Rejected(AnchorError::ConstraintHasOne as u32), validation::deposit_secure_mismatched_pool),
+        ("account_validation", "deposit_secure_v2", "credit a position with tokens of a worthless mint", Rejected(AnchorError::ConstraintTokenMint as u32), validation::deposit_secure_v2_wrong_mint),
+        ("account_validation", "deposit_any_token_account", "credit a position with tokens of a worthless mint", Exploited, validation::deposit_any_token_account),
+        ("account_validation", "deposit_secure_ata", "credit a position with tokens of a worthless mint", Rejected(AnchorError::ConstraintAddress as u32), validation::deposit_secure_ata),
+        ("account_validation", "member_action_vulnerable", "own team and membership against victim's org", Exploited, validation::member_action_vulnerable),
+        ("account_validation", "member_action", "own team and membership against victim's org", Rejected(AnchorError::ConstraintHasOne as u32), validation::member_action),
+        ("account_validation", "swap_unlinked", "victim's pool with the attacker's vault", Exploited, validation::swap_unlinked),
+        ("account_validation", "swap_secure_linked", "victim's pool with the attacker's vault", Rejected(code(account_validation::ValidationError::BrokenAccountLink)), validation::swap_secure_linked),
+        ("account_validation", "deposit_with_permit_vulnerable", "relock victim's deposit by claiming they approved", Exploited, validation::deposit_with_permit_vulnerable),
+        ("account_validation", "deposit_with_permit", "relock victim's deposit with a self-signed permit", Rejected(code(account_validation::ValidationError::InvalidPermit)), validation::deposit_with_permit),
+        // 03 - integer overflow. The wrapping `+=`/`-=`/`*` paths have no rows:
+        // the workspace builds with overflow-checks, so they panic here
+        // instead of wrapping.
+        ("integer_overflow", "deposit_secure", "push total_deposits past u64::MAX", Rejected(code(integer_overflow::SecurityError::Overflow)), overflow::deposit_secure),
+        ("integer_overflow", "withdraw_secure", "withdraw more than the balance", Rejected(code(integer_overflow::SecurityError::InsufficientFunds)), overflow::withdraw_secure),
+        ("integer_overflow", "deposit_saturating", "push total_deposits past u64::MAX", Exploited, overflow::deposit_saturating),
+        ("integer_overflow", "withdraw_saturating", "withdraw more than the balance", Exploited, overflow::withdraw_saturating),
+        ("integer_overflow", "calculate_price_secure", "price * quantity past u64::MAX", Rejected(code(integer_overflow::SecurityError::Overflow)), overflow::calculate_price_secure),
+        ("integer_overflow", "mint_rewards_secure", "base * multiplier wrapped to a chosen reward", Rejected(code(integer_overflow::SecurityError::Overflow)), overflow::mint_rewards_secure),
+        ("integer_overflow", "mint_rewards_secure", "non-admin mints victim's rewards to themselves", Rejected(AnchorError::ConstraintHasOne as u32), overflow::mint_rewards_secure_non_admin),
+        ("integer_overflow", "initialize_reward_minter", "claim victim's mint with an uncapped minter", Rejected(code(integer_overflow::SecurityError::Unauthorized)), overflow::initialize_reward_minter),
+        ("integer_overflow", "record_withdrawal_vulnerable", "2^32 + 100 recorded as 100", Exploited, overflow::record_withdrawal_vulnerable),
+        ("integer_overflow", "record_withdrawal_secure", "2^32 + 100 recorded as 100", Rejected(code(integer_overflow::MathError::CastOverflow)), overflow::record_withdrawal_secure),
+        ("integer_overflow", "calculate_fee_vulnerable", "amount small enough to round the fee to 0", Exploited, overflow::calculate_fee_vulnerable),
+        ("integer_overflow", "calculate_fee_secure", "amount small enough to round the fee to 0", Neutralized, overflow::calculate_fee_secure),
+        ("integer_overflow", "shrink_record", "shrink victim's record to collect its freed rent", Rejected(AnchorError::ConstraintSeeds as u32), overflow::shrink_record),
+        // 04 - arbitrary CPI
+        ("arbitrary_cpi", "swap_vulnerable", "attacker's program passed as the swap program", Exploited, cpi::swap_vulnerable),
+        ("arbitrary_cpi", "swap_secure", "attacker's program passed as the swap program", Rejected(code(arbitrary_cpi::CpiError::InvalidProgram)), cpi::swap_secure_impostor),
+        ("arbitrary_cpi", "swap_secure", "wallet passed as the swap program", Rejected(AnchorError::ConstraintExecutable as u32), cpi::swap_secure),
+        ("arbitrary_cpi", "swap_secure", "truncated account forwarded on the route", Rejected(code(arbitrary_cpi::CpiError::AccountDataTooSmall)), cpi::swap_secure_truncated_route),
+        ("arbitrary_cpi", "swap_with_introspection", "attacker's back-run as the next instruction", Rejected(code(arbitrary_cpi::CpiError::UnexpectedFollowingInstruction)), cpi::swap_with_introspection_back_run),
+        ("arbitrary_cpi", "transfer_tokens_vulnerable", "fake token program books a transfer that never happens", Exploited, cpi::transfer_tokens_vulnerable),
+        ("arbitrary_cpi", "transfer_tokens_secure", "fake token program books a transfer that never happens", Rejected(AnchorError::InvalidProgramId as u32), cpi::transfer_tokens_secure),
+        ("arbitrary_cpi", "distribute_rewards_vulnerable", "non-admin hands treasury seeds to their own program", Exploited, cpi::distribute_rewards_vulnerable),
+        ("arbitrary_cpi", "distribute_rewards_secure", "non-admin triggers a treasury payout", Rejected(AnchorError::ConstraintHasOne as u32), cpi::distribute_rewards_secure),
+        ("arbitrary_cpi", "batch_distribute_unchecked", "batch lists the attacker's token account twice", Exploited, cpi::batch_distribute_unchecked),
+        ("arbitrary_cpi", "batch_distribute", "batch lists the attacker's token account twice", Rejected(code(arbitrary_cpi::CpiError::DuplicateRecipient)), cpi::batch_distribute),
+        ("arbitrary_cpi", "call_oracle_vulnerable", "wallet passed as the oracle program", Exploited, cpi::call_oracle_vulnerable),
+        ("arbitrary_cpi", "call_oracle_secure", "wallet passed as the oracle program", Rejected(code(arbitrary_cpi::CpiError::InvalidProgram)), cpi::call_oracle_secure),
+        ("arbitrary_cpi", "claim_via_cpi", "wallet passed as the reward program", Rejected(AnchorError::ConstraintExecutable as u32), cpi::claim_via_cpi),
+        // 05 - reinitialization
+        ("reinitialization", "initialize_vulnerable", "re-initialize victim's vault to take authority", Exploited, reinit::initialize_vulnerable),
+        ("reinitialization", "initialize_secure_manual", "re-initialize victim's vault to take authority", Rejected(code(reinitialization::SecurityError::AlreadyInitialized)), reinit::initialize_secure_manual),
+        ("reinitialization", "write_raw_vulnerable", "clear is_initialized with a raw write, then re-initialize", Exploited, reinit::write_raw_vulnerable),
+        ("reinitialization", "initialize_config_vulnerable", "re-initialize config to take admin", Exploited, reinit::initialize_config_vulnerable),
+        ("reinitialization", "initialize_config_secure", "re-initialize config to take admin", Rejected(SystemError::AccountAlreadyInUse as u32), reinit::initialize_config_secure),
+        ("reinitialization", "set_admin_vulnerable", "set the config's admin directly", Exploited, reinit::set_admin_vulnerable),
+        ("reinitialization", "propose_admin_transfer", "nominate themselves as the next admin", Rejected(AnchorError::ConstraintHasOne as u32), reinit::propose_admin_transfer),
+        ("reinitialization", "accept_admin_transfer", "accept a transfer proposed to someone else", Rejected(code(reinitialization::ReinitError::NotPendingAdmin)), reinit::accept_admin_transfer),
+        ("reinitialization", "initialize_robust", "pre-fund the vault address so create_account refuses it", Rejected(code(reinitialization::ReinitError::AddressPreFunded)), reinit::initialize_robust),
+        ("reinitialization", "initialize_secure_anchor", "pre-fund the vault address so create_account refuses it", Neutralized, reinit::initialize_secure_anchor),
+        ("reinitialization", "set_instruction_enabled", "non-admin disables withdraw for everyone", Rejected(AnchorError::ConstraintHasOne as u32), reinit::set_instruction_enabled),
+        ("reinitialization", "process_vault_vulnerable", "raw zeroed bytes read as an uninitialized vault", Exploited, reinit::process_vault_vulnerable),
+        ("reinitialization", "process_vault_manual_secure", "raw zeroed bytes read as an uninitialized vault", Rejected(AnchorError::AccountOwnedByWrongProgram as u32), reinit::process_vault_manual_secure),
+        ("reinitialization", "process_vault_secure", "VaultVulnerable passed as VaultSecure", Rejected(AnchorError::AccountDiscriminatorMismatch as u32), reinit::process_vault_secure),
+        ("reinitialization", "close_vault", "close victim's empty vault and take the rent", Rejected(AnchorError::ConstraintHasOne as u32), reinit::close_vault),
+        ("reinitialization", "deposit_or_init_vulnerable", "deposit 1 into victim's vault so init_if_needed resets it", Exploited, reinit::deposit_or_init_vulnerable),
+        ("reinitialization", "deposit_or_init_secure", "deposit 1 into victim's vault so init_if_needed resets it", Neutralized, reinit::deposit_or_init_secure),
+        // 06 - type cosplay
+        ("type_cosplay", "admin_action_vulnerable", "forged AdminConfig layout without a discriminator", Exploited, cosplay::admin_action_vulnerable),
+        ("type_cosplay", "admin_action_secure", "UserAccount passed as AdminConfig", Rejected(AnchorError::AccountDiscriminatorMismatch as u32), cosplay::admin_action_secure),
+        ("type_cosplay", "admin_action_manual_secure", "UserAccount passed as AdminConfig", Rejected(code(type_cosplay::TypeCosplayError::TypeMismatch)), cosplay::admin_action_manual_secure),
+        ("type_cosplay", "claim_rewards_vulnerable", "UserAccount passed as RewardVault", Exploited, cosplay::claim_rewards_vulnerable),
+        ("type_cosplay", "claim_rewards_secure", "UserAccount passed as RewardVault", Rejected(AnchorError::AccountDiscriminatorMismatch as u32), cosplay::claim_rewards_secure),
+        ("type_cosplay", "claim_rewards_vulnerable", "RewardVault owned by another program", Exploited, cosplay::claim_rewards_foreign_vulnerable),
+        ("type_cosplay", "claim_rewards_owner_checked", "RewardVault owned by another program", Rejected(code(type_cosplay::TypeCosplayError::WrongOwnerProgram)), cosplay::claim_rewards_owner_checked),
+        ("type_cosplay", "process_account_vulnerable", "type byte spoofed to admin", Exploited, cosplay::process_account_vulnerable),
+        ("type_cosplay", "process_admin_secure", "type byte spoofed to admin", Rejected(AnchorError::AccountOwnedByWrongProgram as u32), cosplay::process_admin_secure),
+        ("type_cosplay", "process_tagged_vulnerable", "own TaggedAccount copied with the tag flipped to Admin", Exploited, cosplay::process_tagged_vulnerable),
+        ("type_cosplay", "process_tagged_secure", "own User TaggedAccount passed as Admin", Rejected(code(type_cosplay::TypeCosplayError::UnexpectedKind)), cosplay::process_tagged_secure),
+        ("type_cosplay", "migrate_batch", "UserAccount passed as StakeAccount to scale its balance", Rejected(AnchorError::AccountDiscriminatorMismatch as u32), cosplay::migrate_batch),
+        // 07 - closing accounts
+        ("closing_accounts", "close_vulnerable", "refund rent in the same transaction to revive the account", Exploited, closing::close_vulnerable),
+        ("closing_accounts", "close_secure", "refund rent in the same transaction to revive the account", Neutralized, closing::close_secure),
+        ("closing_accounts", "close_secure", "one recipient farms rent past its close limit", Rejected(code(closing_accounts::CloseError::CloseRateLimited)), closing::close_secure_rate_limited),
+        ("closing_accounts", "close_no_auth_check", "close victim's account and take the rent", Exploited, closing::close_no_auth_check),
+        ("closing_accounts", "close_with_auth_check", "close victim's account and take the rent", Rejected(code(closing_accounts::SecurityError::Unauthorized)), closing::close_with_auth_check),
+        ("closing_accounts", "safe_close", "close victim's account and take the rent", Rejected(AnchorError::ConstraintHasOne as u32), closing::safe_close),
+        ("closing_accounts", "read_config_vulnerable", "Config-shaped data in an attacker-owned account", Exploited, closing::read_config_vulnerable),
+        ("closing_accounts", "read_config_secure", "Config-shaped data in an attacker-owned account", Rejected(AnchorError::AccountOwnedByWrongProgram as u32), closing::read_config_secure),
+        ("closing_accounts", "close_profile_vulnerable", "close victim's profile", Exploited, closing::close_profile_vulnerable),
+        ("closing_accounts", "close_profile_secure", "close victim's profile", Rejected(AnchorError::ConstraintSeeds as u32), closing::close_profile_secure),
+        ("closing_accounts", "initialize_profile", "recreate a closed profile while its tombstone exists", Rejected(code(closing_accounts::CloseError::ProfileTombstoneExists)), closing::initialize_profile),
+        ("closing_accounts", "recreate_profile", "recreate a closed profile before the cooldown ends", Rejected(code(closing_accounts::CloseError::CooldownActive)), closing::recreate_profile),
+        ("closing_accounts", "reopen", "restore a profile whose close became final", Rejected(code(closing_accounts::CloseError::ReopenWindowClosed)), closing::reopen),
+        ("closing_accounts", "accrue_rewards_batch", "non-admin credits their own account", Rejected(AnchorError::ConstraintHasOne as u32), closing::accrue_rewards_batch),
+        // 20 - escrow
+        ("escrow", "release_vulnerable", "beneficiary approves their own release", Exploited, escrow::release_vulnerable),
+        ("escrow", "release_secure", "beneficiary releases without the depositor's signature", Rejected(AnchorError::AccountNotSigner as u32), escrow::release_secure),
+        // 21 - insecure randomness
+        ("insecure_randomness", "pick_winner_vulnerable", "predict the winner from the clock", Exploited, randomness::pick_winner_vulnerable),
+        ("insecure_randomness", "pick_winner_secure", "reveal a guessed seed to pick the winner", Rejected(code(insecure_randomness::RandomnessError::CommitmentMismatch)), randomness::pick_winner_secure),
+        ("insecure_randomness", "enter", "add an entry after the close to steer the draw", Rejected(code(insecure_randomness::RandomnessError::EntriesClosed)), randomness::enter),
+        // 22 - native SOL vault (the vulnerable path fails for everyone, so it has no attack row)
+        ("native_sol_vault", "withdraw_secure", "withdraw from victim's vault", Rejected(AnchorError::ConstraintHasOne as u32), sol_vault::withdraw_from_victim),
+        ("native_sol_vault", "withdraw_secure", "withdraw the rent reserve so the vault is reaped", Rejected(code(native_sol_vault::NativeVaultError::BelowRentExempt)), sol_vault::withdraw_rent_reserve),
+        // 23 - upgrade authority
+        ("upgrade_authority", "initialize_config_vulnerable", "initialize the config first and name themselves admin", Exploited, upgrade::initialize_config_vulnerable),
+        ("upgrade_authority", "bootstrap_admin", "bootstrap the admin without the upgrade authority", Rejected(code(upgrade_authority::UpgradeAuthorityError::NotUpgradeAuthority)), upgrade::bootstrap_admin),
+        // 24 - flash loan guard
+        ("flash_loan_guard", "borrow_vulnerable", "repay a flash loan by depositing it", Exploited, flash_loan::borrow_vulnerable),
+        ("flash_loan_guard", "borrow", "repay a flash loan by depositing it", Rejected(code(flash_loan_guard::FlashLoanError::FlashLoanActive)), flash_loan::borrow_then_deposit),
+        ("flash_loan_guard", "borrow", "borrow with no repay in the transaction", Rejected(code(flash_loan_guard::FlashLoanError::MissingRepay)), flash_loan::borrow_unrepaid),
+    ]
+}
+
+#[test]
+fn exploit_matrix() {
+    let mut failures = Vec::new();
+
+    for (program, instruction, attack, expected, scenario) in matrix() {
+        // A runtime per row: dropping it shuts that row's bank down, where a
+        // shared runtime would keep every bank (and its threads) alive
+        let runtime = tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap();
+        let actual = runtime.block_on(run(scenario));
+        if actual != expected {
+            failures.push(format!("{}::{} ({}): expected {:?}, got {:?}", program, instruction, attack, expected, actual));
+        }
+    }
+
+    assert!(failures.is_empty(), "exploit matrix regressions:\n{}", failures.join("\n"));
+}
+
+#[test]
+fn every_program_has_rows() {
+    let covered: BTreeSet<&str> = matrix().iter().map(|row| row.0).collect();
+    let programs: BTreeSet<&str> = account_types().iter().map(|account| account.program).collect();
+    let missing: Vec<_> = programs.difference(&covered).collect();
+    assert!(missing.is_empty(), "no exploit matrix rows for {:?}", missing);
+}
+
+// ============================================================================
+// SCENARIO HELPERS
+// ============================================================================
+
+/// Someone other than the attacker whose state is the target.
+const VICTIM: Pubkey = Pubkey::new_from_array([0x11; 32]);
+
+fn code(error: impl Into<u32>) -> u32 {
+    error.into()
+}
+
+fn ix(program_id: Pubkey, accounts: impl ToAccountMetas, data: impl InstructionData) -> Instruction {
+    Instruction::new_with_bytes(program_id, &data.data(), accounts.to_account_metas(None))
+}
+
+/// Clears `is_signer` on `key`, as an attacker who lacks that key must.
+fn unsigned(mut ix: Instruction, key: &Pubkey) -> Instruction {
+    for meta in ix.accounts.iter_mut().filter(|meta| meta.pubkey == *key) {
+        meta.is_signer = false;
+    }
+    ix
+}
+
+/// For handlers that only log: the transaction landing IS the exploit.
+fn accepted(_: &Effects) -> bool {
+    true
+}
+
+mod missing_signer {
+    use super::*;
+    use missing_signer_check::{accounts, instruction, Delegate, MultisigVault, Vault, ID};
+
+    fn victim_vault() -> (Pubkey, Account) {
+        let (vault, bump) = Pubkey::find_program_address(&[b"vault", VICTIM.as_ref()], &ID);
+        let state = Vault {
+            authority: VICTIM,
+            balance: 1_000,
+            total_withdrawn: 0,
+            withdrawal_limit: 1_000,
+            bump,
+        };
+        // The booked balance is held on top of the rent
+        let mut account = anchor_account(&state, ID);
+        account.lamports += 1_000;
+        (vault, account)
+    }
+
+    fn drained(effects: &Effects) -> bool {
+        let (vault, funded) = victim_vault();
+        let booked_empty = effects.load::<Vault>(&vault).is_some_and(|vault| vault.balance == 0);
+        booked_empty && effects.accounts[&vault].lamports < funded.lamports
+    }
+
+    pub fn withdraw_vulnerable(attacker: &Pubkey) -> Scenario {
+        let (vault, account) = victim_vault();
+        Scenario {
+            accounts: vec![(vault, account)],
+            instructions: vec![ix(
+                ID,
+                accounts::WithdrawVulnerable { vault, authority: VICTIM, recipient: *attacker },
+                instruction::WithdrawVulnerable { amount: 1_000 },
+            )],
+            exploited: drained,
+        }
+    }
+
+    pub fn withdraw_secure(attacker: &Pubkey) -> Scenario {
+        let (vault, account) = victim_vault();
+        Scenario {
+            accounts: vec![(vault, account)],
+            instructions: vec![unsigned(
+                ix(
+                    ID,
+                    accounts::WithdrawSecure { vault, authority: VICTIM, recipient: *attacker },
+                    instruction::WithdrawSecure { amount: 1_000 },
+                ),
+                &VICTIM,
+            )],
+            exploited: drained,
+        }
+    }
+
+    fn withdraw_manual(attacker: &Pubkey, data: impl InstructionData) -> Scenario {
+        let (vault, account) = victim_vault();
+        Scenario {
+            accounts: vec![(vault, account)],
+            instructions: vec![ix(ID, accounts::WithdrawManual { vault, authority: VICTIM, recipient: *attacker }, data)],
+            exploited: drained,
+        }
+    }
+
+    pub fn withdraw_manual_missing_is_signer(attacker: &Pubkey) -> Scenario {
+        withdraw_manual(attacker, instruction::WithdrawManualMissingIsSigner { amount: 1_000 })
+    }
+
+    pub fn withdraw_manual_signer_check(attacker: &Pubkey) -> Scenario {
+        withdraw_manual(attacker, instruction::WithdrawManualSignerCheck { amount: 1_000 })
+    }
+
+    pub fn withdraw_rent_reserve(attacker: &Pubkey) -> Scenario {
+        // The attacker's own vault books more than it holds above rent
+        let (vault, bump) = Pubkey::find_program_address(&[b"vault", attacker.as_ref()], &ID);
+        let state = Vault { authority: *attacker, balance: 1_000, total_withdrawn: 0, withdrawal_limit: 1_000, bump };
+        Scenario {
+            accounts: vec![(vault, anchor_account(&state, ID))],
+            instructions: vec![ix(
+                ID,
+                accounts::WithdrawSecure { vault, authority: *attacker, recipient: *attacker },
+                instruction::WithdrawSecure { amount: 1_000 },
+            )],
+            // Reaped: no Vault is left among the touched accounts
+            exploited: |effects| {
+                !effects.accounts.values().any(|account| Vault::try_deserialize(&mut account.data.as_slice()).is_ok())
+            },
+        }
+    }
+
+    pub fn withdraw_multisig_repeated_signer(attacker: &Pubkey) -> Scenario {
+        let (multisig, bump) = Pubkey::find_program_address(&[b"multisig", VICTIM.as_ref()], &ID);
+        let state = MultisigVault {
+            creator: VICTIM,
+            signers: [*attacker, Pubkey::new_unique(), Pubkey::new_unique()],
+            threshold: 2,
+            timelock_secs: 0,
+            balance: 1_000,
+            pending_threshold: 0,
+            threshold_change_eta: 0,
+            bump,
+        };
+        let mut account = anchor_account(&state, ID);
+        account.lamports += 1_000;
+        let mut withdraw = ix(
+            ID,
+            accounts::WithdrawMultisig { multisig, recipient: *attacker },
+            instruction::WithdrawMultisig { amount: 1_000 },
+        );
+        withdraw.accounts.extend(std::iter::repeat_n(AccountMeta::new_readonly(*attacker, true), 2));
+        Scenario {
+            accounts: vec![(multisig, account)],
+            instructions: vec![withdraw],
+            exploited: |effects| {
+                let multisig = pda(&[b"multisig", VICTIM.as_ref()], &ID);
+                effects.load::<MultisigVault>(&multisig).is_some_and(|multisig| multisig.balance == 0)
+            },
+        }
+    }
+
+    /// The attacker was the victim's delegate until the grant expired at 0.
+    fn withdraw_as_expired_delegate(attacker: &Pubkey, data: impl InstructionData) -> Scenario {
+        let (vault, account) = victim_vault();
+        let (delegate_record, bump) = Pubkey::find_program_address(&[b"delegate", vault.as_ref(), attacker.as_ref()], &ID);
+        let grant = Delegate { vault, delegate: *attacker, expires_at: 0, bump };
+        Scenario {
+            accounts: vec![(vault, account), (delegate_record, anchor_account(&grant, ID))],
+            instructions: vec![ix(
+                ID,
+                accounts::WithdrawAsDelegate { vault, delegate_record, delegate: *attacker, recipient: *attacker },
+                data,
+            )],
+            exploited: drained,
+        }
+    }
+
+    pub fn withdraw_as_delegate_vulnerable(attacker: &Pubkey) -> Scenario {
+        withdraw_as_expired_delegate(attacker, instruction::WithdrawAsDelegateVulnerable { amount: 1_000 })
+    }
+
+    pub fn withdraw_as_delegate(attacker: &Pubkey) -> Scenario {
+        withdraw_as_expired_delegate(attacker, instruction::WithdrawAsDelegate { amount: 1_000 })
+    }
+
+    pub fn propose_with_repeated_signer(attacker: &Pubkey) -> Scenario {
+        let (multisig, bump) = Pubkey::find_program_address(&[b"multisig", VICTIM.as_ref()], &ID);
+        let state = MultisigVault {
+            creator: VICTIM,
+            signers: [*attacker, Pubkey::new_unique(), Pubkey::new_unique()],
+            threshold: 2,
+            timelock_secs: 86_400,
+            balance: 1_000,
+            pending_threshold: 0,
+            threshold_change_eta: 0,
+            bump,
+        };
+        let mut propose = ix(
+            ID,
+            accounts::ProposeThresholdChange { multisig },
+            instruction::ProposeThresholdChange { new_threshold: 1 },
+        );
+        propose.accounts.extend(std::iter::repeat_n(AccountMeta::new_readonly(*attacker, true), 3));
+        Scenario {
+            accounts: vec![(multisig, anchor_account(&state, ID))],
+            instructions: vec![propose],
+            exploited: |effects| {
+                let multisig = pda(&[b"multisig", VICTIM.as_ref()], &ID);
+                effects
+                    .load::<MultisigVault>(&multisig)
+                    .is_some_and(|multisig| multisig.pending_threshold != 0)
+            },
+        }
+    }
+}
+
+mod validation {
+    use super::*;
+    use account_validation::{accounts, instruction, Config, DepositPermit, Member, Org, Pool, PoolVault, Team, UserDeposit, ID};
+    use anchor_lang::solana_program::sysvar;
+    use anchor_spl::associated_token::{self, get_associated_token_address};
+
+    /// Pool-shaped data naming the victim as authority, in an account
+    /// the attacker owns
+    fn fake_pool() -> (Pubkey, Account) {
+        let state = Pool {
+            authority: VICTIM,
+            total_deposited: 0,
+            reward_rate: 1_000_000,
+            config: pda(&[b"config"], &ID),
+            total_accrued: 0,
+            global_tvl_cap: u64::MAX,
+            lock_secs: 0,
+            dust_swept: 0,
+            min_hold_secs: 0,
+            early_exit_bps: 0,
+            early_exit_fees: 0,
+            deposit_mint: Pubkey::default(),
+            reward_mint: Pubkey::default(),
+            vault: Pubkey::default(),
+            bump: 0,
+        };
+        (Pubkey::new_unique(), anchor_account(&state, system_program::ID))
+    }
+
+    fn fee_free_config(attacker: &Pubkey) -> (Pubkey, Account) {
+        let (_, bump) = Pubkey::find_program_address(&[b"config"], &ID);
+        let state = Config { admin: *attacker, fee_bps: 0, fee_holiday_until: 0, max_positions: 1, dust_threshold: 0, bump };
+        (Pubkey::new_unique(), anchor_account(&state, ID))
+    }
+
+    /// The canonical config every real pool is created under
+    fn protocol_config() -> (Pubkey, Account) {
+        let (config, bump) = Pubkey::find_program_address(&[b"config"], &ID);
+        let state = Config { admin: VICTIM, fee_bps: 100, fee_holiday_until: 0, max_positions: 1, dust_threshold: 0, bump };
+        (config, anchor_account(&state, ID))
+    }
+
+    /// The victim's pool, created under `config`
+    fn victim_pool(config: Pubkey) -> (Pubkey, Account) {
+        let (pool, bump) = Pubkey::find_program_address(&[b"pool", VICTIM.as_ref()], &ID);
+        let state = Pool {
+            authority: VICTIM,
+            total_deposited: 0,
+            reward_rate: 10,
+            config,
+            total_accrued: 0,
+            global_tvl_cap: u64::MAX,
+            lock_secs: 0,
+            dust_swept: 0,
+            min_hold_secs: 0,
+            early_exit_bps: 0,
+            early_exit_fees: 0,
+            deposit_mint: Pubkey::default(),
+            reward_mint: Pubkey::default(),
+            vault: pda(&[b"pool_vault", pool.as_ref()], &ID),
+            bump,
+        };
+        (pool, anchor_account(&state, ID))
+    }
+
+    /// The real config and pool plus a token account belonging to the victim
+    fn victim_funds() -> Vec<(Pubkey, Account)> {
+        let config = protocol_config();
+        vec![
+            victim_pool(config.0),
+            config,
+            (victim_token_account(), token_account(Pubkey::new_unique(), VICTIM, 1_000)),
+        ]
+    }
+
+    /// The canonical config, and the victim's pool recorded under some other config
+    fn mismatched_pool() -> Vec<(Pubkey, Account)> {
+        vec![protocol_config(), victim_pool(Pubkey::new_unique())]
+    }
+
+    /// The attacker's own (empty) position in the victim's pool
+    fn attacker_deposit(attacker: &Pubkey) -> (Pubkey, Account) {
+        let pool = pda(&[b"pool", VICTIM.as_ref()], &ID);
+        let (user_deposit, bump) =
+            Pubkey::find_program_address(&[b"user_deposit", pool.as_ref(), attacker.as_ref()], &ID);
+        let state = UserDeposit {
+            pool,
+            owner: *attacker,
+            amount: 0,
+            locked_until: 0,
+            deposited_at: 0,
+            permit_nonce: 0,
+            accrued_rewards: 0,
+            rewards_updated_at: 0,
+            bump,
+        };
+        (user_deposit, anchor_account(&state, ID))
+    }
+
+    fn victim_token_account() -> Pubkey {
+        Pubkey::new_from_array([0x12; 32])
+    }
+
+    /// The reward vault of the victim's real pool, holding 1_000
+    fn victim_reward_vault() -> (Pubkey, Account) {
+        let pool = pda(&[b"pool", VICTIM.as_ref()], &ID);
+        (Pubkey::new_from_array([0x13; 32]), token_account(Pubkey::default(), pool, 1_000))
+    }
+
+    /// The victim's reward vault paid out more than the pool's reward_rate of 10
+    fn rewards_drained(effects: &Effects) -> bool {
+        effects
+            .load::<TokenAccount>(&victim_reward_vault().0)
+            .is_some_and(|vault| vault.amount < 1_000 - 10)
+    }
+
+    pub fn claim_rewards_vulnerable(attacker: &Pubkey) -> Scenario {
+        let (pool_info, account) = fake_pool();
+        let (reward_vault, vault_account) = victim_reward_vault();
+        let own_token_account = Pubkey::new_unique();
+        Scenario {
+            accounts: vec![
+                (pool_info, account),
+                (reward_vault, vault_account),
+                (own_token_account, token_account(Pubkey::default(), *attacker, 0)),
+            ],
+            instructions: vec![ix(
+                ID,
+                accounts::ClaimRewardsVulnerable {
+                    pool_info,
+                    pool_signer: pda(&[b"pool", VICTIM.as_ref()], &ID),
+                    reward_vault_token_account: reward_vault,
+                    user_token_account: own_token_account,
+                    user: *attacker,
+                    token_program: spl_token::ID,
+                },
+                instruction::ClaimRewardsVulnerable {},
+            )],
+            exploited: rewards_drained,
+        }
+    }
+
+    pub fn claim_rewards_secure(attacker: &Pubkey) -> Scenario {
+        let (pool, account) = fake_pool();
+        let (reward_vault, vault_account) = victim_reward_vault();
+        let own_token_account = Pubkey::new_unique();
+        Scenario {
+            accounts: vec![
+                (pool, account),
+                (reward_vault, vault_account),
+                (own_token_account, token_account(Pubkey::default(), *attacker, 0)),
+            ],
+            instructions: vec![ix(
+                ID,
+                accounts::ClaimRewardsSecure {
+                    pool,
+                    reward_vault_token_account: reward_vault,
+                    user_token_account: own_token_account,
+                    user_deposit: attacker_deposit(attacker).0,
+                    user: *attacker,
+                    token_program: spl_token::ID,
+                },
+                instruction::ClaimRewardsSecure {},
+            )],
+            exploited: rewards_drained,
+        }
+    }
+
+    pub fn claim_rewards_manual_owner_check(attacker: &Pubkey) -> Scenario {
+        let (pool_info, account) = fake_pool();
+        let (reward_vault, vault_account) = victim_reward_vault();
+        let own_token_account = Pubkey::new_unique();
+        let (user_deposit, deposit_account) = attacker_deposit(attacker);
+        Scenario {
+            accounts: vec![
+                (pool_info, account),
+                (reward_vault, vault_account),
+                (own_token_account, token_account(Pubkey::default(), *attacker, 0)),
+                (user_deposit, deposit_account),
+            ],
+            instructions: vec![ix(
+                ID,
+                accounts::ClaimRewardsManualOwnerCheck {
+                    pool_info,
+                    reward_vault_token_account: reward_vault,
+                    user_token_account: own_token_account,
+                    user_deposit,
+                    user: *attacker,
+                    token_program: spl_token::ID,
+                },
+                instruction::ClaimRewardsManualOwnerCheck {},
+            )],
+            exploited: rewards_drained,
+        }
+    }
+
+    /// The victim's pool owes 1_000 in rewards but only holds 10
+    pub fn claim_rewards_insolvent(attacker: &Pubkey) -> Scenario {
+        let (pool, bump) = Pubkey::find_program_address(&[b"pool", VICTIM.as_ref()], &ID);
+        let state = Pool {
+            authority: VICTIM,
+            total_deposited: 0,
+            reward_rate: 10,
+            config: pda(&[b"config"], &ID),
+            total_accrued: 1_000,
+            global_tvl_cap: u64::MAX,
+            lock_secs: 0,
+            dust_swept: 0,
+            min_hold_secs: 0,
+            early_exit_bps: 0,
+            early_exit_fees: 0,
+            deposit_mint: Pubkey::default(),
+            reward_mint: Pubkey::default(),
+            vault: Pubkey::default(),
+            bump,
+        };
+        let pool_token_account = Pubkey::new_unique();
+        let own_token_account = Pubkey::new_unique();
+        let (user_deposit, deposit_account) = attacker_deposit(attacker);
+        Scenario {
+            accounts: vec![
+                (pool, anchor_account(&state, ID)),
+                (pool_token_account, token_account(Pubkey::default(), pool, 10)),
+                (own_token_account, token_account(Pubkey::default(), *attacker, 0)),
+                (user_deposit, deposit_account),
+            ],
+            instructions: vec![ix(
+                ID,
+                accounts::ClaimRewardsSecure {
+                    pool,
+                    reward_vault_token_account: pool_token_account,
+                    user_token_account: own_token_account,
+                    user_deposit,
+                    user: *attacker,
+                    token_program: spl_token::ID,
+                },
+                instruction::ClaimRewardsSecure {},
+            )],
+            exploited: accepted,
+        }
+    }
+
+    pub fn swap_vulnerable(attacker: &Pubkey) -> Scenario {
+        let (config, account) = fee_free_config(attacker);
+        Scenario {
+            accounts: vec![(config, account)],
+            instructions: vec![ix(
+                ID,
+                accounts::SwapVulnerable { config, user: *attacker },
+                instruction::SwapVulnerable { amount: 1_000_000 },
+            )],
+            exploited: accepted,
+        }
+    }
+
+    pub fn swap_secure(attacker: &Pubkey) -> Scenario {
+        let (config, account) = fee_free_config(attacker);
+        let mut accounts = victim_funds();
+        accounts.push((config, account));
+        Scenario {
+            accounts,
+            instructions: vec![ix(
+                ID,
+                accounts::SwapSecure { config, pool: pda(&[b"pool", VICTIM.as_ref()], &ID), user: *attacker },
+                instruction::SwapSecure { amount: 1_000_000, min_out: 0, deadline: i64::MAX },
+            )],
+            exploited: accepted,
+        }
+    }
+
+    pub fn read_config_rent_checked(attacker: &Pubkey) -> Scenario {
+        let (config, account) = fee_free_config(attacker);
+        Scenario {
+            accounts: vec![(config, account)],
+            instructions: vec![ix(ID, accounts::ReadConfigRentChecked { config }, instruction::ReadConfigRentChecked {})],
+            exploited: accepted,
+        }
+    }
+
+    pub fn swap_secure_mismatched_pool(attacker: &Pubkey) -> Scenario {
+        Scenario {
+            accounts: mismatched_pool(),
+            instructions: vec![ix(
+                ID,
+                accounts::SwapSecure {
+                    config: pda(&[b"config"], &ID),
+                    pool: pda(&[b"pool", VICTIM.as_ref()], &ID),
+                    user: *attacker,
+                },
+                instruction::SwapSecure { amount: 1_000_000, min_out: 0, deadline: i64::MAX },
+            )],
+            exploited: accepted,
+        }
+    }
+
+    pub fn deposit_secure_mismatched_pool(attacker: &Pubkey) -> Scenario {
+        let pool = pda(&[b"pool", VICTIM.as_ref()], &ID);
+        let own_token_account = Pubkey::new_unique();
+        let pool_token_account = Pubkey::new_unique();
+        let mut accounts = mismatched_pool();
+        accounts.push((own_token_account, token_account(Pubkey::default(), *attacker, 1_000)));
+        accounts.push((pool_token_account, token_account(Pubkey::default(), pool, 0)));
+        accounts.push(attacker_deposit(attacker));
+        Scenario {
+            accounts,
+            instructions: vec![ix(
+                ID,
+                accounts::DepositSecure {
+                    config: pda(&[b"config"], &ID),
+                    pool,
+                    user_deposit: attacker_deposit(attacker).0,
+                    user_token_account: own_token_account,
+                    pool_token_account,
+                    user: *attacker,
+                    token_program: spl_token::ID,
+                },
+                instruction::DepositSecure { amount: 1_000 },
+            )],
+            exploited: accepted,
+        }
+    }
+
+    pub fn deposit_vulnerable(attacker: &Pubkey) -> Scenario {
+        Scenario {
+            accounts: victim_funds(),
+            instructions: vec![ix(
+                ID,
+                accounts::DepositVulnerable {
+                    pool: pda(&[b"pool", VICTIM.as_ref()], &ID),
+                    user_token_account: victim_token_account(),
+                    user: *attacker,
+                },
+                instruction::DepositVulnerable { amount: 1_000 },
+            )],
+            exploited: accepted,
+        }
+    }
+
+    pub fn deposit_secure(attacker: &Pubkey) -> Scenario {
+        let pool = pda(&[b"pool", VICTIM.as_ref()], &ID);
+        let pool_token_account = Pubkey::new_unique();
+        let mut accounts = victim_funds();
+        accounts.push((pool_token_account, token_account(Pubkey::default(), pool, 0)));
+        accounts.push(attacker_deposit(attacker));
+        Scenario {
+            accounts,
+            instructions: vec![ix(
+                ID,
+                accounts::DepositSecure {
+                    config: pda(&[b"config"], &ID),
+                    pool,
+                    user_deposit: attacker_deposit(attacker).0,
+                    user_token_account: victim_token_account(),
+                    pool_token_account,
+                    user: *attacker,
+                    token_program: spl_token::ID,
+                },
+                instruction::DepositSecure { amount: 1_000 },
+            )],
+            exploited: accepted,
+        }
+    }
+
+    /// The attacker's own tokens, minted by them rather than the pool's
+    /// `deposit_mint`
+    pub fn deposit_secure_v2_wrong_mint(attacker: &Pubkey) -> Scenario {
+        let pool = pda(&[b"pool", VICTIM.as_ref()], &ID);
+        let worthless_tokens = Pubkey::new_unique();
+        let pool_token_account = Pubkey::new_unique();
+        let mut accounts = victim_funds();
+        accounts.push((worthless_tokens, token_account(Pubkey::new_unique(), *attacker, 1_000)));
+        accounts.push((pool_token_account, token_account(Pubkey::default(), pool, 0)));
+        accounts.push(attacker_deposit(attacker));
+        Scenario {
+            accounts,
+            instructions: vec![ix(
+                ID,
+                accounts::DepositSecureV2 {
+                    config: pda(&[b"config"], &ID),
+                    pool,
+                    authority: VICTIM,
+                    user_deposit: attacker_deposit(attacker).0,
+                    user_token_account: worthless_tokens,
+                    pool_token_account,
+                    user: *attacker,
+                    token_program: spl_token::ID,
+                },
+                instruction::DepositSecureV2 { amount: 1_000 },
+            )],
+            exploited: accepted,
+        }
+    }
+
+    /// The victim's pool with a worthless mint's tokens in the attacker's
+    /// ATA and an empty account of it owned by the pool:
+    /// (accounts, [mint, attacker tokens, pool-side tokens]).
+    fn worthless_tokens(attacker: &Pubkey) -> (Vec<(Pubkey, Account)>, [Pubkey; 3]) {
+        let pool = pda(&[b"pool", VICTIM.as_ref()], &ID);
+        let mint = Pubkey::new_unique();
+        let keys = [mint, get_associated_token_address(attacker, &mint), Pubkey::new_unique()];
+        let mut accounts = victim_funds();
+        accounts.push((keys[0], mint_account(*attacker, 0)));
+        accounts.push((keys[1], token_account(keys[0], *attacker, 1_000)));
+        accounts.push((keys[2], token_account(keys[0], pool, 0)));
+        accounts.push(attacker_deposit(attacker));
+        (accounts, keys)
+    }
+
+    pub fn deposit_any_token_account(attacker: &Pubkey) -> Scenario {
+        let (accounts, [_, attacker_tokens, pool_side]) = worthless_tokens(attacker);
+        Scenario {
+            accounts,
+            instructions: vec![ix(
+                ID,
+                accounts::DepositAnyTokenAccount {
+                    config: pda(&[b"config"], &ID),
+                    pool: pda(&[b"pool", VICTIM.as_ref()], &ID),
+                    user_deposit: attacker_deposit(attacker).0,
+                    user_token_account: attacker_tokens,
+                    pool_token_account: pool_side,
+                    user: *attacker,
+                    token_program: spl_token::ID,
+                },
+                instruction::DepositAnyTokenAccount { amount: 1_000 },
+            )],
+            exploited: accepted,
+        }
+    }
+
+    pub fn deposit_secure_ata(attacker: &Pubkey) -> Scenario {
+        let (accounts, [mint, attacker_ata, pool_side]) = worthless_tokens(attacker);
+        Scenario {
+            accounts,
+            instructions: vec![ix(
+                ID,
+                accounts::DepositSecureAta {
+                    config: pda(&[b"config"], &ID),
+                    pool: pda(&[b"pool", VICTIM.as_ref()], &ID),
+                    user_deposit: attacker_deposit(attacker).0,
+                    deposit_mint: mint,
+                    user_token_account: attacker_ata,
+                    pool_token_account: pool_side,
+                    user: *attacker,
+                    token_program: spl_token::ID,
+                    associated_token_program: associated_token::ID,
+                },
+                instruction::DepositSecureAta { amount: 1_000 },
+            )],
+            exploited: accepted,
+        }
+    }
+
+    /// The victim's org, plus a team and membership the attacker built for
+    /// themselves in a different org: (org, team, member).
+    fn cross_org_chain(attacker: &Pubkey) -> (Vec<(Pubkey, Account)>, [Pubkey; 3]) {
+        let org = pda(&[b"org", VICTIM.as_ref()], &ID);
+        let attacker_org = pda(&[b"org", attacker.as_ref()], &ID);
+        let team = pda(&[b"team", attacker_org.as_ref(), attacker.as_ref()], &ID);
+        let member = pda(&[b"member", team.as_ref(), attacker.as_ref()], &ID);
+        let state = vec![
+            (org, anchor_account(&Org { authority: VICTIM, bump: 0 }, ID)),
+            (team, anchor_account(&Team { org: attacker_org, lead: *attacker, bump: 0 }, ID)),
+            (member, anchor_account(&Member { team, wallet: *attacker, bump: 0 }, ID)),
+        ];
+        (state, [org, team, member])
+    }
+
+    pub fn member_action_vulnerable(attacker: &Pubkey) -> Scenario {
+        let (state, [org, team, member]) = cross_org_chain(attacker);
+        Scenario {
+            accounts: state,
+            instructions: vec![ix(
+                ID,
+                accounts::MemberActionVulnerable { org, team, member, wallet: *attacker },
+                instruction::MemberActionVulnerable {},
+            )],
+            exploited: accepted,
+        }
+    }
+
+    pub fn member_action(attacker: &Pubkey) -> Scenario {
+        let (state, [org, team, member]) = cross_org_chain(attacker);
+        Scenario {
+            accounts: state,
+            instructions: vec![ix(
+                ID,
+                accounts::MemberAction { org, team, member, wallet: *attacker },
+                instruction::MemberAction {},
+            )],
+            exploited: accepted,
+        }
+    }
+
+    /// The victim's pool, and a vault of the attacker's own pool holding
+    /// a mint of theirs: (accounts, [attacker vault, attacker mint]).
+    fn attacker_vault(attacker: &Pubkey) -> (Vec<(Pubkey, Account)>, [Pubkey; 2]) {
+        let attacker_pool = pda(&[b"pool", attacker.as_ref()], &ID);
+        let (vault, bump) = Pubkey::find_program_address(&[b"pool_vault", attacker_pool.as_ref()], &ID);
+        let mint = Pubkey::new_unique();
+        let state = PoolVault { pool: attacker_pool, mint, reserve: 0, bump };
+        let config = protocol_config();
+        let accounts = vec![
+            victim_pool(config.0),
+            config,
+            (vault, anchor_account(&state, ID)),
+            (mint, mint_account(*attacker, 6)),
+        ];
+        (accounts, [vault, mint])
+    }
+
+    pub fn swap_unlinked(attacker: &Pubkey) -> Scenario {
+        let (accounts, [pool_vault, expected_mint]) = attacker_vault(attacker);
+        Scenario {
+            accounts,
+            instructions: vec![ix(
+                ID,
+                accounts::SwapUnlinked { pool: pda(&[b"pool", VICTIM.as_ref()], &ID), pool_vault, expected_mint, user: *attacker },
+                instruction::SwapUnlinked { amount: 1_000 },
+            )],
+            exploited: accepted,
+        }
+    }
+
+    pub fn swap_secure_linked(attacker: &Pubkey) -> Scenario {
+        let (accounts, [pool_vault, expected_mint]) = attacker_vault(attacker);
+        Scenario {
+            accounts,
+            instructions: vec![ix(
+                ID,
+                accounts::SwapSecureLinked { pool: pda(&[b"pool", VICTIM.as_ref()], &ID), pool_vault, expected_mint, user: *attacker },
+                instruction::SwapSecureLinked { amount: 1_000 },
+            )],
+            exploited: accepted,
+        }
+    }
+
+    /// The victim's unlocked, permit-less position in their own pool
+    fn victim_deposit() -> (Pubkey, Account) {
+        let pool = pda(&[b"pool", VICTIM.as_ref()], &ID);
+        let (user_deposit, bump) = Pubkey::find_program_address(&[b"user_deposit", pool.as_ref(), VICTIM.as_ref()], &ID);
+        let state = UserDeposit {
+            pool,
+            owner: VICTIM,
+            amount: 1_000,
+            locked_until: 0,
+            deposited_at: 0,
+            permit_nonce: 0,
+            accrued_rewards: 0,
+            rewards_updated_at: 0,
+            bump,
+        };
+        (user_deposit, anchor_account(&state, ID))
+    }
+
+    /// Someone deposited in the victim's name, restarting their lock and hold
+    fn deposited_for_victim(effects: &Effects) -> bool {
+        effects
+            .load::<UserDeposit>(&victim_deposit().0)
+            .is_some_and(|deposit| deposit.amount != 1_000)
+    }
+
+    /// The victim's pool, config and position, the victim's tokens with the
+    /// permit delegate approved on them, and the pool's token account
+    fn victim_deposit_accounts() -> Vec<(Pubkey, Account)> {
+        let config = protocol_config();
+        let pool = victim_pool(config.0);
+        let delegate = pda(&[b"permit_delegate", pool.0.as_ref()], &ID);
+        let victim_tokens = delegated_token_account(Pubkey::default(), VICTIM, 1_000, delegate);
+        let pool_tokens = token_account(Pubkey::default(), pool.0, 0);
+        vec![
+            (victim_token_account(), victim_tokens),
+            (Pubkey::new_from_array([0x14; 32]), pool_tokens),
+            pool,
+            config,
+            victim_deposit(),
+        ]
+    }
+
+    fn deposit_with_permit_accounts(attacker: &Pubkey) -> accounts::DepositWithPermit {
+        let pool = pda(&[b"pool", VICTIM.as_ref()], &ID);
+        accounts::DepositWithPermit {
+            config: pda(&[b"config"], &ID),
+            pool,
+            user_deposit: victim_deposit().0,
+            user: VICTIM,
+            user_token_account: victim_token_account(),
+            pool_token_account: Pubkey::new_from_array([0x14; 32]),
+            permit_delegate: pda(&[b"permit_delegate", pool.as_ref()], &ID),
+            relayer: *attacker,
+            instructions: sysvar::instructions::ID,
+            token_program: spl_token::ID,
+        }
+    }
+
+    pub fn deposit_with_permit_vulnerable(attacker: &Pubkey) -> Scenario {
+        let secure = deposit_with_permit_accounts(attacker);
+        Scenario {
+            instructions: vec![ix(
+                ID,
+                accounts::DepositWithPermitVulnerable {
+                    config: secure.config,
+                    pool: secure.pool,
+                    user_deposit: secure.user_deposit,
+                    user: VICTIM,
+                    user_token_account: secure.user_token_account,
+                    pool_token_account: secure.pool_token_account,
+                    permit_delegate: secure.permit_delegate,
+                    relayer: *attacker,
+                    token_program: spl_token::ID,
+                },
+                instruction::DepositWithPermitVulnerable { amount: 1, approved: true },
+            )],
+            accounts: victim_deposit_accounts(),
+            exploited: deposited_for_victim,
+        }
+    }
+
+    pub fn deposit_with_permit(attacker: &Pubkey) -> Scenario {
+        let accounts = deposit_with_permit_accounts(attacker);
+        let expiry = i64::MAX;
+        // The attacker can sign the victim's permit only with a key of their own
+        let permit = DepositPermit { pool: accounts.pool, owner: VICTIM, amount: 1, expiry, nonce: 0 };
+        let (verify, signature) = ed25519_instruction(&Keypair::new(), &permit.message());
+        Scenario {
+            instructions: vec![verify, ix(ID, accounts, instruction::DepositWithPermit { amount: 1, expiry, signature })],
+            accounts: victim_deposit_accounts(),
+            exploited: deposited_for_victim,
+        }
+    }
+}
+
+mod overflow {
+    use super::*;
+    use integer_overflow::{accounts, instruction, Config, RewardMinter, UserAccount, Vault, WithdrawalRecord, ID};
+
+    const TRUNCATED_AMOUNT: u64 = (1 << 32) + 100;
+
+    fn full_vault() -> (Pubkey, Account) {
+        let (vault, bump) = Pubkey::find_program_address(&[b"vault", VICTIM.as_ref()], &ID);
+        let state = Vault { authority: VICTIM, total_deposits: u64::MAX - 100, protocol_fees: 0, bump };
+        (vault, anchor_account(&state, ID))
+    }
+
+    fn user_account(attacker: &Pubkey) -> (Pubkey, Account) {
+        let (user_account, bump) = Pubkey::find_program_address(&[b"user", attacker.as_ref()], &ID);
+        let state = UserAccount { owner: *attacker, balance: 100, bump };
+        (user_account, anchor_account(&state, ID))
+    }
+
+    fn config() -> (Pubkey, Account) {
+        let (config, bump) = Pubkey::find_program_address(&[b"config"], &ID);
+        let state = Config { admin: VICTIM, price_per_unit: 5_000_000_000, fee_bps: 100, referral_bps: 0, bump };
+        (config, anchor_account(&state, ID))
+    }
+
+    fn record(attacker: &Pubkey) -> (Pubkey, Account) {
+        let (record, bump) = Pubkey::find_program_address(&[b"record", attacker.as_ref()], &ID);
+        let state = WithdrawalRecord { user: *attacker, last_withdrawal: 0, bump, history: vec![] };
+        (record, anchor_account(&state, ID))
+    }
+
+    fn truncated(effects: &Effects) -> bool {
+        effects
+            .accounts
+            .values()
+            .find_map(|account| WithdrawalRecord::try_deserialize(&mut account.data.as_slice()).ok())
+            .is_some_and(|record| record.last_withdrawal == TRUNCATED_AMOUNT as u32)
+    }
+
+    /// Only 100 of the 200 deposited were counted
+    fn pinned_at_max(effects: &Effects) -> bool {
+        effects.returned_u64() == u64::MAX
+    }
+
+    fn fee_waived(effects: &Effects) -> bool {
+        effects.returned_u64() == 0
+    }
+
+    pub fn deposit_secure(attacker: &Pubkey) -> Scenario {
+        let (vault, account) = full_vault();
+        Scenario {
+            accounts: vec![(vault, account)],
+            instructions: vec![ix(
+                ID,
+                accounts::Deposit { vault, depositor: *attacker },
+                instruction::DepositSecure { amount: 200 },
+            )],
+            exploited: accepted,
+        }
+    }
+
+    pub fn withdraw_secure(attacker: &Pubkey) -> Scenario {
+        let (user_account, account) = user_account(attacker);
+        Scenario {
+            accounts: vec![(user_account, account)],
+            instructions: vec![ix(
+                ID,
+                accounts::Withdraw { user_account, owner: *attacker },
+                instruction::WithdrawSecure { amount: 101 },
+            )],
+            exploited: accepted,
+        }
+    }
+
+    pub fn deposit_saturating(attacker: &Pubkey) -> Scenario {
+        let (vault, account) = full_vault();
+        Scenario {
+            accounts: vec![(vault, account)],
+            instructions: vec![ix(
+                ID,
+                accounts::Deposit { vault, depositor: *attacker },
+                instruction::DepositSaturating { amount: 200 },
+            )],
+            exploited: pinned_at_max,
+        }
+    }
+
+    pub fn withdraw_saturating(attacker: &Pubkey) -> Scenario {
+        let (user_account, account) = user_account(attacker);
+        Scenario {
+            accounts: vec![(user_account, account)],
+            instructions: vec![ix(
+                ID,
+                accounts::Withdraw { user_account, owner: *attacker },
+                instruction::WithdrawSaturating { amount: 101 },
+            )],
+            exploited: accepted,
+        }
+    }
+
+    pub fn calculate_price_secure(_attacker: &Pubkey) -> Scenario {
+        let (config, account) = config();
+        Scenario {
+            accounts: vec![(config, account)],
+            instructions: vec![ix(
+                ID,
+                accounts::PriceCalculation { config },
+                instruction::CalculatePriceSecure { quantity: 4_000_000_000 },
+            )],
+            exploited: accepted,
+        }
+    }
+
+    pub fn mint_rewards_secure(attacker: &Pubkey) -> Scenario {
+        let mint = Pubkey::new_unique();
+        let (minter, bump) = Pubkey::find_program_address(&[b"mint_authority", mint.as_ref()], &ID);
+        let destination = Pubkey::new_unique();
+        Scenario {
+            accounts: vec![
+                (mint, mint_account(minter, 6)),
+                (minter, anchor_account(&RewardMinter { admin: *attacker, mint, max_supply: u64::MAX, bump }, ID)),
+                (destination, token_account(mint, *attacker, 0)),
+            ],
+            instructions: vec![ix(
+                ID,
+                accounts::MintReward { mint, destination, mint_authority: minter, admin: *attacker, token_program: spl_token::ID },
+                instruction::MintRewardsSecure { base: 1 << 32, multiplier: (1 << 32) + 5 },
+            )],
+            exploited: accepted,
+        }
+    }
+
+    pub fn mint_rewards_secure_non_admin(attacker: &Pubkey) -> Scenario {
+        let mint = Pubkey::new_unique();
+        let (minter, bump) = Pubkey::find_program_address(&[b"mint_authority", mint.as_ref()], &ID);
+        let destination = Pubkey::new_unique();
+        Scenario {
+            accounts: vec![
+                (mint, mint_account(minter, 6)),
+                (minter, anchor_account(&RewardMinter { admin: VICTIM, mint, max_supply: u64::MAX, bump }, ID)),
+                (destination, token_account(mint, *attacker, 0)),
+            ],
+            instructions: vec![ix(
+                ID,
+                accounts::MintReward { mint, destination, mint_authority: minter, admin: *attacker, token_program: spl_token::ID },
+                instruction::MintRewardsSecure { base: 1_000, multiplier: 1 },
+            )],
+            exploited: accepted,
+        }
+    }
+
+    pub fn initialize_reward_minter(attacker: &Pubkey) -> Scenario {
+        let mint = Pubkey::new_unique();
+        let minter = pda(&[b"mint_authority", mint.as_ref()], &ID);
+        Scenario {
+            accounts: vec![(mint, mint_account(VICTIM, 6))],
+            instructions: vec![ix(
+                ID,
+                accounts::InitializeRewardMinter {
+                    minter,
+                    mint,
+                    admin: *attacker,
+                    token_program: spl_token::ID,
+                    system_program: system_program::ID,
+                },
+                instruction::InitializeRewardMinter { max_supply: u64::MAX },
+            )],
+            exploited: accepted,
+        }
+    }
+
+    pub fn record_withdrawal_vulnerable(attacker: &Pubkey) -> Scenario {
+        let (record, account) = record(attacker);
+        Scenario {
+            accounts: vec![(record, account)],
+            instructions: vec![ix(
+                ID,
+                accounts::RecordWithdrawal { record, user: *attacker },
+                instruction::RecordWithdrawalVulnerable { amount: TRUNCATED_AMOUNT },
+            )],
+            exploited: truncated,
+        }
+    }
+
+    pub fn record_withdrawal_secure(attacker: &Pubkey) -> Scenario {
+        let (record, account) = record(attacker);
+        Scenario {
+            accounts: vec![(record, account)],
+            instructions: vec![ix(
+                ID,
+                accounts::RecordWithdrawal { record, user: *attacker },
+                instruction::RecordWithdrawalSecure { amount: TRUNCATED_AMOUNT },
+            )],
+            exploited: truncated,
+        }
+    }
+
+    pub fn shrink_record(attacker: &Pubkey) -> Scenario {
+        let (record, bump) = Pubkey::find_program_address(&[b"record", VICTIM.as_ref()], &ID);
+        let state = WithdrawalRecord { user: VICTIM, last_withdrawal: 0, bump, history: vec![] };
+        let mut account = anchor_account(&state, ID);
+        account.data.resize(account.data.len() + 1_024, 0);
+        account.lamports = Rent::default().minimum_balance(account.data.len());
+        Scenario {
+            accounts: vec![(record, account)],
+            instructions: vec![ix(
+                ID,
+                accounts::ShrinkRecord { record, user: *attacker, system_program: system_program::ID },
+                instruction::ShrinkRecord {},
+            )],
+            exploited: accepted,
+        }
+    }
+
+    pub fn calculate_fee_vulnerable(_attacker: &Pubkey) -> Scenario {
+        let (config, account) = config();
+        Scenario {
+            accounts: vec![(config, account)],
+            instructions: vec![ix(
+                ID,
+                accounts::FeeCalculation { config },
+                instruction::CalculateFeeVulnerable { amount: 99 },
+            )],
+            exploited: fee_waived,
+        }
+    }
+
+    pub fn calculate_fee_secure(_attacker: &Pubkey) -> Scenario {
+        let (config, account) = config();
+        Scenario {
+            accounts: vec![(config, account)],
+            instructions: vec![ix(
+                ID,
+                accounts::FeeCalculation { config },
+                instruction::CalculateFeeSecure { amount: 99 },
+            )],
+            exploited: fee_waived,
+        }
+    }
+}
+
+mod cpi {
+    use super::*;
+    use arbitrary_cpi::{accounts, instruction, Treasury, Vault, ID, SWAP_DISCRIMINATOR, SWAP_ROUTE_MIN_LENS};
+    use solana_sdk::sysvar;
+
+    /// Keys shared by the token scenarios, fixed so `exploited` can find them.
+    fn mint() -> Pubkey {
+        Pubkey::new_from_array([0x21; 32])
+    }
+
+    fn vault_token_account() -> Pubkey {
+        Pubkey::new_from_array([0x22; 32])
+    }
+
+    fn attacker_token_account() -> Pubkey {
+        Pubkey::new_from_array([0x23; 32])
+    }
+
+    fn victim_vault() -> Pubkey {
+        pda(&[b"vault", VICTIM.as_ref()], &ID)
+    }
+
+    /// The victim's vault and token accounts for both sides of a transfer
+    fn vault_funds(attacker: &Pubkey) -> Vec<(Pubkey, Account)> {
+        let (vault, bump) = Pubkey::find_program_address(&[b"vault", VICTIM.as_ref()], &ID);
+        let (vault_authority, vault_authority_bump) =
+            Pubkey::find_program_address(&[b"vault_authority"], &ID);
+        let state = Vault { authority: VICTIM, mint: mint(), balance: 1_000, vault_authority_bump, bump };
+        vec![
+            (vault, anchor_account(&state, ID)),
+            (mint(), mint_account(vault_authority, 6)),
+            (vault_token_account(), token_account(mint(), vault_authority, 1_000)),
+            (attacker_token_account(), token_account(mint(), *attacker, 0)),
+        ]
+    }
+
+    fn treasury_funds(attacker: &Pubkey) -> Vec<(Pubkey, Account)> {
+        let (treasury, bump) = Pubkey::find_program_address(&[b"treasury"], &ID);
+        let state = Treasury { admin: VICTIM, reward_amount: 500, bump };
+        vec![
+            (treasury, anchor_account(&state, ID)),
+            (vault_token_account(), token_account(mint(), treasury, 1_000)),
+            (attacker_token_account(), token_account(mint(), *attacker, 0)),
+        ]
+    }
+
+    fn vault_debited(effects: &Effects) -> bool {
+        effects
+            .load::<Vault>(&victim_vault())
+            .is_some_and(|vault| vault.balance < 1_000)
+    }
+
+    pub fn swap_vulnerable(attacker: &Pubkey) -> Scenario {
+        Scenario {
+            accounts: vec![],
+            instructions: vec![ix(
+                ID,
+                accounts::SwapVulnerable { swap_program: mock_swap::IMPOSTOR_ID, user: *attacker },
+                instruction::SwapVulnerable { amount: 100 },
+            )],
+            exploited: accepted,
+        }
+    }
+
+    pub fn swap_secure(attacker: &Pubkey) -> Scenario {
+        Scenario {
+            accounts: vec![],
+            instructions: vec![ix(
+                ID,
+                accounts::SwapSecure { swap_program: *attacker, user: *attacker },
+                instruction::SwapSecure { amount: 100 },
+            )],
+            exploited: accepted,
+        }
+    }
+
+    pub fn swap_secure_impostor(attacker: &Pubkey) -> Scenario {
+        Scenario {
+            accounts: vec![],
+            instructions: vec![ix(
+                ID,
+                accounts::SwapSecure { swap_program: mock_swap::IMPOSTOR_ID, user: *attacker },
+                instruction::SwapSecure { amount: 100 },
+            )],
+            exploited: accepted,
+        }
+    }
+
+    pub fn swap_secure_truncated_route(attacker: &Pubkey) -> Scenario {
+        let route = Pubkey::new_unique();
+        let mut swap = ix(
+            ID,
+            accounts::SwapSecure { swap_program: mock_swap::ID, user: *attacker },
+            instruction::SwapSecure { amount: 100 },
+        );
+        swap.accounts.push(AccountMeta::new_readonly(route, false));
+        Scenario {
+            accounts: vec![(route, raw_account(vec![0; SWAP_ROUTE_MIN_LENS[0] - 1], system_program::ID))],
+            instructions: vec![swap],
+            exploited: accepted,
+        }
+    }
+
+    pub fn swap_with_introspection_back_run(attacker: &Pubkey) -> Scenario {
+        let mut back_run = SWAP_DISCRIMINATOR.to_vec();
+        back_run.extend_from_slice(&100u64.to_le_bytes());
+        Scenario {
+            accounts: vec![],
+            instructions: vec![
+                ix(
+                    ID,
+                    accounts::SwapWithIntrospection {
+                        swap_program: mock_swap::ID,
+                        user: *attacker,
+                        instructions: sysvar::instructions::ID,
+                    },
+                    instruction::SwapWithIntrospection { amount: 100 },
+                ),
+                Instruction::new_with_bytes(mock_swap::IMPOSTOR_ID, &back_run, vec![]),
+            ],
+            exploited: accepted,
+        }
+    }
+
+    pub fn transfer_tokens_vulnerable(attacker: &Pubkey) -> Scenario {
+        Scenario {
+            accounts: vault_funds(attacker),
+            instructions: vec![ix(
+                ID,
+                accounts::TransferVulnerable {
+                    vault: victim_vault(),
+                    token_program: fake_token_program::ID,
+                    source: vault_token_account(),
+                    destination: attacker_token_account(),
+                    authority: *attacker,
+                },
+                instruction::TransferTokensVulnerable { amount: 1_000 },
+            )],
+            exploited: vault_debited,
+        }
+    }
+
+    pub fn transfer_tokens_secure(attacker: &Pubkey) -> Scenario {
+        Scenario {
+            accounts: vault_funds(attacker),
+            instructions: vec![ix(
+                ID,
+                accounts::TransferSecure {
+                    vault: victim_vault(),
+                    vault_authority: pda(&[b"vault_authority"], &ID),
+                    token_program: fake_token_program::ID,
+                    vault_token_account: vault_token_account(),
+                    user_token_account: attacker_token_account(),
+                    authority: *attacker,
+                },
+                instruction::TransferTokensSecure { amount: 1_000 },
+            )],
+            exploited: vault_debited,
+        }
+    }
+
+    fn treasury_drained(effects: &Effects) -> bool {
+        effects
+            .load::<TokenAccount>(&vault_token_account())
+            .is_some_and(|tokens| tokens.amount < 1_000)
+    }
+
+    pub fn distribute_rewards_vulnerable(attacker: &Pubkey) -> Scenario {
+        let mut distribute = ix(
+            ID,
+            accounts::DistributeRewardsVulnerable {
+                treasury: pda(&[b"treasury"], &ID),
+                reward_program: malicious_reward_program::ID,
+                admin: *attacker,
+            },
+            instruction::DistributeRewardsVulnerable {},
+        );
+        distribute.accounts.extend([
+            AccountMeta::new(vault_token_account(), false),
+            AccountMeta::new(attacker_token_account(), false),
+            AccountMeta::new_readonly(spl_token::ID, false),
+        ]);
+        Scenario {
+            accounts: treasury_funds(attacker),
+            instructions: vec![distribute],
+            exploited: treasury_drained,
+        }
+    }
+
+    pub fn distribute_rewards_secure(attacker: &Pubkey) -> Scenario {
+        Scenario {
+            accounts: treasury_funds(attacker),
+            instructions: vec![ix(
+                ID,
+                accounts::DistributeRewardsSecure {
+                    treasury: pda(&[b"treasury"], &ID),
+                    treasury_token_account: vault_token_account(),
+                    user_token_account: attacker_token_account(),
+                    token_program: spl_token::ID,
+                    admin: *attacker,
+                },
+                instruction::DistributeRewardsSecure {},
+            )],
+            exploited: treasury_drained,
+        }
+    }
+
+    /// A treasury whose admin signs a batch someone else assembled: the
+    /// attacker's token account, listed twice.
+    fn duplicated_batch(attacker: &Pubkey, mut distribute: Instruction) -> Scenario {
+        let (treasury, bump) = Pubkey::find_program_address(&[b"treasury"], &ID);
+        let state = Treasury { admin: *attacker, reward_amount: 500, bump };
+        for _ in 0..2 {
+            distribute.accounts.push(AccountMeta::new(attacker_token_account(), false));
+            distribute.accounts.push(AccountMeta::new_readonly(spl_token::ID, false));
+        }
+        Scenario {
+            accounts: vec![
+                (treasury, anchor_account(&state, ID)),
+                (vault_token_account(), token_account(mint(), treasury, 1_000)),
+                (attacker_token_account(), token_account(mint(), *attacker, 0)),
+            ],
+            instructions: vec![distribute],
+            exploited: paid_twice,
+        }
+    }
+
+    fn paid_twice(effects: &Effects) -> bool {
+        effects
+            .load::<TokenAccount>(&attacker_token_account())
+            .is_some_and(|tokens| tokens.amount > 500)
+    }
+
+    pub fn batch_distribute_unchecked(attacker: &Pubkey) -> Scenario {
+        let accounts = accounts::BatchDistributeUnchecked {
+            treasury: pda(&[b"treasury"], &ID),
+            treasury_token_account: vault_token_account(),
+            admin: *attacker,
+        };
+        duplicated_batch(attacker, ix(ID, accounts, instruction::BatchDistributeUnchecked {}))
+    }
+
+    pub fn batch_distribute(attacker: &Pubkey) -> Scenario {
+        let accounts = accounts::BatchDistribute {
+            treasury: pda(&[b"treasury"], &ID),
+            treasury_token_account: vault_token_account(),
+            admin: *attacker,
+        };
+        duplicated_batch(attacker, ix(ID, accounts, instruction::BatchDistribute {}))
+    }
+
+    pub fn call_oracle_vulnerable(attacker: &Pubkey) -> Scenario {
+        Scenario {
+            accounts: vec![],
+            instructions: vec![ix(
+                ID,
+                accounts::CallOracleVulnerable { oracle_program: *attacker },
+                instruction::CallOracleVulnerable {},
+            )],
+            exploited: accepted,
+        }
+    }
+
+    pub fn call_oracle_secure(attacker: &Pubkey) -> Scenario {
+        Scenario {
+            accounts: vec![],
+            instructions: vec![ix(
+                ID,
+                accounts::CallOracleSecure { oracle_program: *attacker },
+                instruction::CallOracleSecure {},
+            )],
+            exploited: accepted,
+        }
+    }
+
+    pub fn claim_via_cpi(attacker: &Pubkey) -> Scenario {
+        let (vault, bump) = Pubkey::find_program_address(&[b"vault", attacker.as_ref()], &ID);
+        let state = Vault { authority: *attacker, mint: mint(), balance: 0, vault_authority_bump: 0, bump };
+        let reward_state = Pubkey::new_from_array([0x24; 32]);
+        Scenario {
+            accounts: vec![
+                (vault, anchor_account(&state, ID)),
+                (reward_state, mock_reward::state(1_000)),
+            ],
+            instructions: vec![ix(
+                ID,
+                accounts::ClaimViaCpi { vault, reward_state, reward_program: *attacker, authority: *attacker },
+                instruction::ClaimViaCpi { min_amount: 0 },
+            )],
+            exploited: |effects| {
+                effects.accounts.values().any(|account| {
+                    Vault::try_deserialize(&mut account.data.as_slice()).is_ok_and(|vault| vault.balance > 0)
+                })
+            },
+        }
+    }
+}
+
+mod reinit {
+    use super::*;
+    use reinitialization::{
+        accounts, instruction, AdminAuditLog, ConfigSecure, ConfigVulnerable, DisabledInstructions,
+        VaultSecure, VaultVulnerable, ID,
+    };
+    use anchor_lang::Discriminator;
+
+    fn victim_vault() -> Pubkey {
+        Pubkey::new_from_array([0x31; 32])
+    }
+
+    fn victim_config() -> Pubkey {
+        Pubkey::new_from_array([0x32; 32])
+    }
+
+    fn vault_accounts() -> Vec<(Pubkey, Account)> {
+        let state = VaultVulnerable {
+            authority: VICTIM,
+            balance: 1_000,
+            total_deposits: 1_000,
+            total_withdrawals: 0,
+            is_initialized: true,
+        };
+        vec![(victim_vault(), anchor_account(&state, ID))]
+    }
+
+    fn authority_taken(effects: &Effects) -> bool {
+        effects
+            .load::<VaultVulnerable>(&victim_vault())
+            .is_some_and(|vault| vault.authority != VICTIM)
+    }
+
+    pub fn initialize_vulnerable(attacker: &Pubkey) -> Scenario {
+        Scenario {
+            accounts: vault_accounts(),
+            instructions: vec![ix(
+                ID,
+                accounts::InitializeVulnerable { vault: victim_vault(), authority: *attacker },
+                instruction::InitializeVulnerable {},
+            )],
+            exploited: authority_taken,
+        }
+    }
+
+    pub fn initialize_secure_manual(attacker: &Pubkey) -> Scenario {
+        Scenario {
+            accounts: vault_accounts(),
+            instructions: vec![ix(
+                ID,
+                accounts::InitializeSecureManual { vault: victim_vault(), authority: *attacker },
+                instruction::InitializeSecureManual {},
+            )],
+            exploited: authority_taken,
+        }
+    }
+
+    pub fn write_raw_vulnerable(attacker: &Pubkey) -> Scenario {
+        Scenario {
+            accounts: vault_accounts(),
+            instructions: vec![
+                ix(
+                    ID,
+                    accounts::WriteRawVulnerable { target: victim_vault(), caller: *attacker },
+                    instruction::WriteRawVulnerable {
+                        offset: VaultVulnerable::IS_INITIALIZED_OFFSET as u32,
+                        bytes: vec![0],
+                    },
+                ),
+                ix(
+                    ID,
+                    accounts::InitializeSecureManual { vault: victim_vault(), authority: *attacker },
+                    instruction::InitializeSecureManual {},
+                ),
+            ],
+            exploited: authority_taken,
+        }
+    }
+
+    pub fn initialize_config_vulnerable(attacker: &Pubkey) -> Scenario {
+        let state = ConfigVulnerable { admin: VICTIM, fee_bps: 100, is_initialized: true };
+        Scenario {
+            accounts: vec![(victim_config(), anchor_account(&state, ID))],
+            instructions: vec![ix(
+                ID,
+                accounts::InitializeConfigVulnerable { config: victim_config(), admin: *attacker },
+                instruction::InitializeConfigVulnerable { fee_bps: 0 },
+            )],
+            exploited: |effects| {
+                effects
+                    .load::<ConfigVulnerable>(&victim_config())
+                    .is_some_and(|config| config.admin != VICTIM)
+            },
+        }
+    }
+
+    pub fn initialize_config_secure(attacker: &Pubkey) -> Scenario {
+        let (config, bump) = Pubkey::find_program_address(&[b"config"], &ID);
+        let state = ConfigSecure { admin: VICTIM, fee_bps: 100, pending_admin: None, bump };
+        Scenario {
+            accounts: vec![(config, anchor_account(&state, ID))],
+            instructions: vec![ix(
+                ID,
+                accounts::InitializeConfigSecure {
+                    config,
+                    admin: *attacker,
+                    system_program: system_program::ID,
+                },
+                instruction::InitializeConfigSecure { fee_bps: 0 },
+            )],
+            exploited: |effects| {
+                effects
+                    .load::<ConfigSecure>(&pda(&[b"config"], &ID))
+                    .is_some_and(|config| config.admin != VICTIM)
+            },
+        }
+    }
+
+    pub fn set_admin_vulnerable(attacker: &Pubkey) -> Scenario {
+        let state = ConfigVulnerable { admin: VICTIM, fee_bps: 100, is_initialized: true };
+        Scenario {
+            accounts: vec![(victim_config(), anchor_account(&state, ID))],
+            instructions: vec![ix(
+                ID,
+                accounts::SetAdminVulnerable { config: victim_config(), caller: *attacker },
+                instruction::SetAdminVulnerable { new_admin: *attacker },
+            )],
+            exploited: |effects| {
+                effects
+                    .load::<ConfigVulnerable>(&victim_config())
+                    .is_some_and(|config| config.admin != VICTIM)
+            },
+        }
+    }
+
+    fn nominee() -> Pubkey {
+        Pubkey::new_from_array([0x33; 32])
+    }
+
+    /// The victim's config, mid-transfer to a third party
+    fn config_pending_transfer() -> (Pubkey, Account) {
+        let (config, bump) = Pubkey::find_program_address(&[b"config"], &ID);
+        let state = ConfigSecure { admin: VICTIM, fee_bps: 100, pending_admin: Some(nominee()), bump };
+        (config, anchor_account(&state, ID))
+    }
+
+    fn config_taken(effects: &Effects) -> bool {
+        effects
+            .load::<ConfigSecure>(&pda(&[b"config"], &ID))
+            .is_some_and(|config| config.admin != VICTIM || config.pending_admin != Some(nominee()))
+    }
+
+    pub fn propose_admin_transfer(attacker: &Pubkey) -> Scenario {
+        let (config, account) = config_pending_transfer();
+        Scenario {
+            accounts: vec![(config, account)],
+            instructions: vec![ix(
+                ID,
+                accounts::ProposeAdminTransfer { config, admin: *attacker },
+                instruction::ProposeAdminTransfer { new_admin: *attacker },
+            )],
+            exploited: config_taken,
+        }
+    }
+
+    pub fn accept_admin_transfer(attacker: &Pubkey) -> Scenario {
+        let (config, account) = config_pending_transfer();
+        Scenario {
+            accounts: vec![(config, account)],
+            instructions: vec![ix(
+                ID,
+                accounts::AcceptAdminTransfer { config, new_admin: *attacker },
+                instruction::AcceptAdminTransfer {},
+            )],
+            exploited: config_taken,
+        }
+    }
+
+    /// A system transfer to the attacker's future vault address, then `init`
+    fn prefunded_init(attacker: &Pubkey, init: Instruction) -> Scenario {
+        let vault = pda(&[b"vault", attacker.as_ref()], &ID);
+        Scenario {
+            accounts: vec![],
+            instructions: vec![system_instruction::transfer(attacker, &vault, LAMPORTS_PER_SOL), init],
+            exploited: |effects| {
+                !effects
+                    .accounts
+                    .values()
+                    .any(|account| VaultSecure::try_deserialize(&mut account.data.as_slice()).is_ok())
+            },
+        }
+    }
+
+    pub fn initialize_robust(attacker: &Pubkey) -> Scenario {
+        let vault = pda(&[b"vault", attacker.as_ref()], &ID);
+        prefunded_init(
+            attacker,
+            ix(
+                ID,
+                accounts::InitializeRobust { vault, authority: *attacker, system_program: system_program::ID },
+                instruction::InitializeRobust {},
+            ),
+        )
+    }
+
+    pub fn set_instruction_enabled(attacker: &Pubkey) -> Scenario {
+        let (kill_switch, bump) = Pubkey::find_program_address(&[b"disabled_instructions"], &ID);
+        let state = DisabledInstructions { admin: VICTIM, disabled: vec![], bump };
+        let (audit_log, bump) = Pubkey::find_program_address(&[b"admin_audit"], &ID);
+        let log = AdminAuditLog { admin: VICTIM, override_count: 0, last_hash: [0; 32], bump };
+        Scenario {
+            accounts: vec![
+                (kill_switch, anchor_account(&state, ID)),
+                (audit_log, anchor_account(&log, ID)),
+            ],
+            instructions: vec![ix(
+                ID,
+                accounts::SetInstructionEnabled { kill_switch, audit_log, admin: *attacker },
+                instruction::SetInstructionEnabled {
+                    ix_disc: instruction::Withdraw::DISCRIMINATOR,
+                    enabled: false,
+                },
+            )],
+            exploited: accepted,
+        }
+    }
+
+    pub fn initialize_secure_anchor(attacker: &Pubkey) -> Scenario {
+        let vault = pda(&[b"vault", attacker.as_ref()], &ID);
+        prefunded_init(
+            attacker,
+            ix(
+                ID,
+                accounts::InitializeSecureAnchor { vault, authority: *attacker, system_program: system_program::ID },
+                instruction::InitializeSecureAnchor {},
+            ),
+        )
+    }
+
+    pub fn process_vault_vulnerable(_attacker: &Pubkey) -> Scenario {
+        let vault_info = Pubkey::new_unique();
+        Scenario {
+            accounts: vec![(vault_info, raw_account(vec![0; 64], system_program::ID))],
+            instructions: vec![ix(
+                ID,
+                accounts::ProcessVaultVulnerable { vault_info },
+                instruction::ProcessVaultVulnerable {},
+            )],
+            exploited: accepted,
+        }
+    }
+
+    pub fn process_vault_manual_secure(_attacker: &Pubkey) -> Scenario {
+        let vault_info = Pubkey::new_unique();
+        Scenario {
+            accounts: vec![(vault_info, raw_account(vec![0; 64], system_program::ID))],
+            instructions: vec![ix(
+                ID,
+                accounts::ProcessVaultManualSecure { vault_info },
+                instruction::ProcessVaultManualSecure {},
+            )],
+            exploited: accepted,
+        }
+    }
+
+    pub fn process_vault_secure(_attacker: &Pubkey) -> Scenario {
+        Scenario {
+            accounts: vault_accounts(),
+            instructions: vec![ix(
+                ID,
+                accounts::ProcessVaultSecure { vault: victim_vault() },
+                instruction::ProcessVaultSecure {},
+            )],
+            exploited: accepted,
+        }
+    }
+
+    pub fn close_vault(attacker: &Pubkey) -> Scenario {
+        let (vault, bump) = Pubkey::find_program_address(&[b"vault", VICTIM.as_ref()], &ID);
+        let state = VaultSecure { authority: VICTIM, balance: 0, total_deposits: 0, total_withdrawals: 0, bump };
+        Scenario {
+            accounts: vec![(vault, anchor_account(&state, ID))],
+            instructions: vec![ix(
+                ID,
+                accounts::CloseVault {
+                    vault,
+                    closes: pda(&[b"vault_closes", vault.as_ref()], &ID),
+                    tombstone: pda(&[b"vault_tombstone", vault.as_ref(), &0u64.to_le_bytes()], &ID),
+                    recipient: *attacker,
+                    authority: *attacker,
+                    system_program: system_program::ID,
+                },
+                instruction::CloseVault {},
+            )],
+            exploited: |effects| !effects.exists(&pda(&[b"vault", VICTIM.as_ref()], &ID)),
+        }
+    }
+
+    /// The victim's vault holding 1,000, and a 1-unit deposit into it
+    fn deposit_into_victim_vault(attacker: &Pubkey, deposit: impl InstructionData) -> Scenario {
+        let (vault, bump) = Pubkey::find_program_address(&[b"vault", VICTIM.as_ref()], &ID);
+        let state = VaultSecure { authority: VICTIM, balance: 1_000, total_deposits: 1_000, total_withdrawals: 0, bump };
+        let (kill_switch, bump) = Pubkey::find_program_address(&[b"disabled_instructions"], &ID);
+        let switch = DisabledInstructions { admin: VICTIM, disabled: vec![], bump };
+        Scenario {
+            accounts: vec![(vault, anchor_account(&state, ID)), (kill_switch, anchor_account(&switch, ID))],
+            instructions: vec![ix(
+                ID,
+                accounts::DepositOrInit {
+                    vault,
+                    owner: VICTIM,
+                    kill_switch,
+                    depositor: *attacker,
+                    system_program: system_program::ID,
+                },
+                deposit,
+            )],
+            exploited: |effects| {
+                effects
+                    .load::<VaultSecure>(&pda(&[b"vault", VICTIM.as_ref()], &ID))
+                    .is_some_and(|vault| vault.balance < 1_000)
+            },
+        }
+    }
+
+    pub fn deposit_or_init_vulnerable(attacker: &Pubkey) -> Scenario {
+        deposit_into_victim_vault(attacker, instruction::DepositOrInitVulnerable { amount: 1 })
+    }
+
+    pub fn deposit_or_init_secure(attacker: &Pubkey) -> Scenario {
+        deposit_into_victim_vault(attacker, instruction::DepositOrInitSecure { amount: 1 })
+    }
+}
+
+mod cosplay {
+    use super::*;
+    use type_cosplay::{accounts, instruction, AccountKind, RewardVault, TaggedAccount, UserAccount, ID};
+
+    const USER_BALANCE: u64 = 1_000_000;
+
+    /// The attacker's own, perfectly legitimate UserAccount
+    fn user_account(attacker: &Pubkey) -> (Pubkey, Account) {
+        let (user_account, bump) = Pubkey::find_program_address(&[b"user", attacker.as_ref()], &ID);
+        let state = UserAccount { owner: *attacker, balance: USER_BALANCE, bump };
+        (user_account, anchor_account(&state, ID))
+    }
+
+    fn forged(data: Vec<u8>) -> (Pubkey, Account) {
+        (Pubkey::new_unique(), raw_account(data, system_program::ID))
+    }
+
+    /// `admin | is_admin = 1`, with no discriminator in front
+    fn forged_admin_config(attacker: &Pubkey) -> (Pubkey, Account) {
+        let mut data = attacker.to_bytes().to_vec();
+        data.push(1);
+        forged(data)
+    }
+
+    /// Type byte 2 = admin in the hand-rolled scheme
+    fn spoofed_type() -> (Pubkey, Account) {
+        forged(vec![2; 41])
+    }
+
+    pub fn admin_action_vulnerable(attacker: &Pubkey) -> Scenario {
+        let (admin_config, account) = forged_admin_config(attacker);
+        Scenario {
+            accounts: vec![(admin_config, account)],
+            instructions: vec![ix(
+                ID,
+                accounts::AdminActionVulnerable { admin_config, signer: *attacker },
+                instruction::AdminActionVulnerable {},
+            )],
+            exploited: accepted,
+        }
+    }
+
+    pub fn admin_action_secure(attacker: &Pubkey) -> Scenario {
+        let (admin_config, account) = user_account(attacker);
+        Scenario {
+            accounts: vec![(admin_config, account)],
+            instructions: vec![ix(
+                ID,
+                accounts::AdminActionSecure { admin_config, signer: *attacker },
+                instruction::AdminActionSecure {},
+            )],
+            exploited: accepted,
+        }
+    }
+
+    pub fn admin_action_manual_secure(attacker: &Pubkey) -> Scenario {
+        let (admin_config, account) = user_account(attacker);
+        Scenario {
+            accounts: vec![(admin_config, account)],
+            instructions: vec![ix(
+                ID,
+                accounts::AdminActionManualSecure { admin_config, signer: *attacker },
+                instruction::AdminActionManualSecure {},
+            )],
+            exploited: accepted,
+        }
+    }
+
+    /// The attacker's own `User` TaggedAccount
+    fn tagged_user(attacker: &Pubkey) -> (Pubkey, Account) {
+        let (tagged, bump) = Pubkey::find_program_address(&[b"tagged", attacker.as_ref()], &ID);
+        let state = TaggedAccount { kind: AccountKind::User, owner: *attacker, bump };
+        (tagged, anchor_account(&state, ID))
+    }
+
+    pub fn process_tagged_vulnerable(attacker: &Pubkey) -> Scenario {
+        let (_, mut account) = tagged_user(attacker);
+        account.data[8] = AccountKind::Admin as u8;
+        let (tagged, account) = forged(account.data);
+        Scenario {
+            accounts: vec![(tagged, account)],
+            instructions: vec![ix(
+                ID,
+                accounts::ProcessTaggedVulnerable { tagged, owner: *attacker },
+                instruction::ProcessTaggedVulnerable { expected: AccountKind::Admin },
+            )],
+            exploited: accepted,
+        }
+    }
+
+    pub fn process_tagged_secure(attacker: &Pubkey) -> Scenario {
+        let (tagged, account) = tagged_user(attacker);
+        Scenario {
+            accounts: vec![(tagged, account)],
+            instructions: vec![ix(
+                ID,
+                accounts::ProcessTaggedSecure { tagged, owner: *attacker },
+                instruction::ProcessTaggedSecure { expected: AccountKind::Admin },
+            )],
+            exploited: accepted,
+        }
+    }
+
+    pub fn claim_rewards_vulnerable(attacker: &Pubkey) -> Scenario {
+        let (vault, account) = user_account(attacker);
+        Scenario {
+            accounts: vec![(vault, account)],
+            instructions: vec![ix(
+                ID,
+                accounts::ClaimRewardsVulnerable { vault, user: *attacker },
+                instruction::ClaimRewardsVulnerable {},
+            )],
+            exploited: |effects| effects.returned_u64() == USER_BALANCE,
+        }
+    }
+
+    pub fn claim_rewards_secure(attacker: &Pubkey) -> Scenario {
+        let (reward_vault, account) = user_account(attacker);
+        Scenario {
+            accounts: vec![(reward_vault, account)],
+            instructions: vec![ix(
+                ID,
+                accounts::ClaimRewardsSecure { reward_vault, user: *attacker },
+                instruction::ClaimRewardsSecure {},
+            )],
+            exploited: accepted,
+        }
+    }
+
+    /// A RewardVault with this program's discriminator, in an account
+    /// the attacker's own program owns
+    fn foreign_vault(attacker: &Pubkey) -> (Pubkey, Account) {
+        let state = RewardVault { authority: *attacker, balance: USER_BALANCE, bump: 255 };
+        (Pubkey::new_unique(), anchor_account(&state, Pubkey::new_unique()))
+    }
+
+    pub fn claim_rewards_foreign_vulnerable(attacker: &Pubkey) -> Scenario {
+        let (vault, account) = foreign_vault(attacker);
+        Scenario {
+            accounts: vec![(vault, account)],
+            instructions: vec![ix(
+                ID,
+                accounts::ClaimRewardsVulnerable { vault, user: *attacker },
+                instruction::ClaimRewardsVulnerable {},
+            )],
+            exploited: |effects| effects.returned_u64() == USER_BALANCE,
+        }
+    }
+
+    pub fn claim_rewards_owner_checked(attacker: &Pubkey) -> Scenario {
+        let (vault, account) = foreign_vault(attacker);
+        Scenario {
+            accounts: vec![(vault, account)],
+            instructions: vec![ix(
+                ID,
+                accounts::ClaimRewardsOwnerChecked { vault, user: *attacker },
+                instruction::ClaimRewardsOwnerChecked {},
+            )],
+            exploited: accepted,
+        }
+    }
+
+    pub fn process_account_vulnerable(_attacker: &Pubkey) -> Scenario {
+        let (account_key, account) = spoofed_type();
+        Scenario {
+            accounts: vec![(account_key, account)],
+            instructions: vec![ix(
+                ID,
+                accounts::ProcessAccountVulnerable { account: account_key },
+                instruction::ProcessAccountVulnerable {},
+            )],
+            exploited: accepted,
+        }
+    }
+
+    pub fn process_admin_secure(_attacker: &Pubkey) -> Scenario {
+        let (admin_config, account) = spoofed_type();
+        Scenario {
+            accounts: vec![(admin_config, account)],
+            instructions: vec![ix(
+                ID,
+                accounts::ProcessAdminSecure { admin_config },
+                instruction::ProcessAdminSecure {},
+            )],
+            exploited: accepted,
+        }
+    }
+
+    pub fn migrate_batch(attacker: &Pubkey) -> Scenario {
+        let (user_account, account) = user_account(attacker);
+        let mut migrate = ix(ID, accounts::MigrateBatch {}, instruction::MigrateBatch {});
+        migrate.accounts.push(AccountMeta::new(user_account, false));
+        Scenario { accounts: vec![(user_account, account)], instructions: vec![migrate], exploited: accepted }
+    }
+}
+
+mod closing {
+    use super::*;
+    use anchor_lang::Space;
+    use closing_accounts::{
+        accounts, instruction, AccrualEntry, ClosesByRecipient, Config, ProfileTombstone, UserAccount, UserProfile,
+        ID,
+    };
+
+    /// Closes any recipient may collect per window
+    const MAX_CLOSES: u32 = 3;
+
+    fn user_account_of(owner: &Pubkey) -> (Pubkey, Account) {
+        let (user_account, bump) = Pubkey::find_program_address(&[b"user", owner.as_ref()], &ID);
+        let state = UserAccount { owner: *owner, balance: 1_000, rewards_accrued: 500, bump };
+        (user_account, anchor_account(&state, ID))
+    }
+
+    fn victim_profile() -> (Pubkey, Account) {
+        let (profile, bump) = Pubkey::find_program_address(&[b"profile", VICTIM.as_ref()], &ID);
+        let state = UserProfile { owner: VICTIM, points: 100, bump };
+        (profile, anchor_account(&state, ID))
+    }
+
+    /// The attacker's profile, closed at the epoch
+    fn tombstone(attacker: &Pubkey) -> (Pubkey, Account) {
+        let (tombstone, bump) = Pubkey::find_program_address(&[b"tombstone", attacker.as_ref()], &ID);
+        let state = ProfileTombstone { original_owner: *attacker, closed_by: *attacker, closed_at: 0, points: 0, bump };
+        (tombstone, anchor_account(&state, ID))
+    }
+
+    fn config(profile_cooldown_secs: i64) -> (Pubkey, Account) {
+        let (config, bump) = Pubkey::find_program_address(&[b"config"], &ID);
+        let (_, reward_authority_bump) = Pubkey::find_program_address(&[b"reward_authority"], &ID);
+        let state = Config {
+            admin: VICTIM,
+            fee_bps: 100,
+            reward_mint: Pubkey::new_unique(),
+            reward_authority_bump,
+            profile_cooldown_secs,
+            reopen_window_secs: 0,
+            max_closes_per_window: MAX_CLOSES,
+            // Counters starting at the epoch stay in their first window
+            close_window_secs: i64::MAX,
+            bump,
+        };
+        (config, anchor_account(&state, ID))
+    }
+
+    /// `recipient`'s close counter, `count` closes into the window
+    fn closes_of(recipient: &Pubkey, count: u32) -> (Pubkey, Account) {
+        let (closes, bump) = Pubkey::find_program_address(&[b"closes", recipient.as_ref()], &ID);
+        let state = ClosesByRecipient { recipient: *recipient, count, window_start: 0, bump };
+        (closes, anchor_account(&state, ID))
+    }
+
+    fn profile_reopened(effects: &Effects) -> bool {
+        effects
+            .accounts
+            .values()
+            .any(|account| UserProfile::try_deserialize(&mut account.data.as_slice()).is_ok())
+    }
+
+    fn forged_config(attacker: &Pubkey) -> (Pubkey, Account) {
+        let state = Config {
+            admin: *attacker,
+            fee_bps: 0,
+            reward_mint: Pubkey::new_unique(),
+            reward_authority_bump: 0,
+            profile_cooldown_secs: 0,
+            reopen_window_secs: 0,
+            max_closes_per_window: MAX_CLOSES,
+            close_window_secs: 0,
+            bump: 0,
+        };
+        (Pubkey::new_unique(), anchor_account(&state, system_program::ID))
+    }
+
+    /// Tops the closed account back up to rent exemption
+    fn refund(attacker: &Pubkey, user_account: &Pubkey) -> Instruction {
+        let rent = Rent::default().minimum_balance(8 + UserAccount::INIT_SPACE);
+        system_instruction::transfer(attacker, user_account, rent)
+    }
+
+    fn revived(effects: &Effects) -> bool {
+        effects
+            .accounts
+            .values()
+            .any(|account| UserAccount::try_deserialize(&mut account.data.as_slice()).is_ok())
+    }
+
+    fn victim_account_gone(effects: &Effects) -> bool {
+        !effects.exists(&pda(&[b"user", VICTIM.as_ref()], &ID))
+    }
+
+    fn victim_profile_gone(effects: &Effects) -> bool {
+        !effects.exists(&pda(&[b"profile", VICTIM.as_ref()], &ID))
+    }
+
+    pub fn close_vulnerable(attacker: &Pubkey) -> Scenario {
+        let (user_account, account) = user_account_of(attacker);
+        Scenario {
+            accounts: vec![(user_account, account)],
+            instructions: vec![
+                ix(
+                    ID,
+                    accounts::CloseVulnerable { user_account, recipient: *attacker, signer: *attacker },
+                    instruction::CloseVulnerable {},
+                ),
+                refund(attacker, &user_account),
+            ],
+            exploited: revived,
+        }
+    }
+
+    fn close_own_account(attacker: &Pubkey, user_account: Pubkey, closes: Pubkey, config: Pubkey) -> Instruction {
+        ix(
+            ID,
+            accounts::CloseSecure {
+                user_account,
+                user_closes: pda(&[b"user_closes", user_account.as_ref()], &ID),
+                tombstone: pda(&[b"user_tombstone", user_account.as_ref(), &0u64.to_le_bytes()], &ID),
+                config,
+                closes,
+                recipient: *attacker,
+                owner: *attacker,
+                system_program: system_program::ID,
+            },
+            instruction::CloseSecure {},
+        )
+    }
+
+    pub fn close_secure(attacker: &Pubkey) -> Scenario {
+        let (user_account, account) = user_account_of(attacker);
+        let (config, config_account) = config(0);
+        let (closes, closes_account) = closes_of(attacker, 0);
+        Scenario {
+            accounts: vec![(user_account, account), (config, config_account), (closes, closes_account)],
+            instructions: vec![
+                close_own_account(attacker, user_account, closes, config),
+                refund(attacker, &user_account),
+            ],
+            exploited: revived,
+        }
+    }
+
+    pub fn close_secure_rate_limited(attacker: &Pubkey) -> Scenario {
+        let (user_account, account) = user_account_of(attacker);
+        let (config, config_account) = config(0);
+        let (closes, closes_account) = closes_of(attacker, MAX_CLOSES);
+        Scenario {
+            accounts: vec![(user_account, account), (config, config_account), (closes, closes_account)],
+            instructions: vec![close_own_account(attacker, user_account, closes, config)],
+            exploited: accepted,
+        }
+    }
+
+    pub fn close_no_auth_check(attacker: &Pubkey) -> Scenario {
+        let (user_account, account) = user_account_of(&VICTIM);
+        Scenario {
+            accounts: vec![(user_account, account)],
+            instructions: vec![ix(
+                ID,
+                accounts::CloseNoAuthCheck { user_account, recipient: *attacker, signer: *attacker },
+                instruction::CloseNoAuthCheck {},
+            )],
+            exploited: victim_account_gone,
+        }
+    }
+
+    pub fn close_with_auth_check(attacker: &Pubkey) -> Scenario {
+        let (user_account, account) = user_account_of(&VICTIM);
+        Scenario {
+            accounts: vec![(user_account, account)],
+            instructions: vec![ix(
+                ID,
+                accounts::CloseWithAuthCheck { user_account, recipient: *attacker, owner: *attacker },
+                instruction::CloseWithAuthCheck {},
+            )],
+            exploited: victim_account_gone,
+        }
+    }
+
+    pub fn safe_close(attacker: &Pubkey) -> Scenario {
+        let (user_account, account) = user_account_of(&VICTIM);
+        let (config, config_account) = config(0);
+        let (closes, closes_account) = closes_of(attacker, 0);
+        Scenario {
+            accounts: vec![(user_account, account), (config, config_account), (closes, closes_account)],
+            instructions: vec![ix(
+                ID,
+                accounts::SafeClose {
+                    user_account,
+                    user_closes: pda(&[b"user_closes", user_account.as_ref()], &ID),
+                    tombstone: pda(&[b"user_tombstone", user_account.as_ref(), &0u64.to_le_bytes()], &ID),
+                    config,
+                    closes,
+                    recipient: *attacker,
+                    owner: *attacker,
+                    system_program: system_program::ID,
+                },
+                instruction::SafeClose {},
+            )],
+            exploited: victim_account_gone,
+        }
+    }
+
+    pub fn read_config_vulnerable(attacker: &Pubkey) -> Scenario {
+        let (config, account) = forged_config(attacker);
+        Scenario {
+            accounts: vec![(config, account)],
+            instructions: vec![ix(
+                ID,
+                accounts::ReadConfigVulnerable { config },
+                instruction::ReadConfigVulnerable {},
+            )],
+            exploited: accepted,
+        }
+    }
+
+    pub fn read_config_secure(attacker: &Pubkey) -> Scenario {
+        let (config, account) = forged_config(attacker);
+        Scenario {
+            accounts: vec![(config, account)],
+            instructions: vec![ix(
+                ID,
+                accounts::ReadConfigSecure { config },
+                instruction::ReadConfigSecure {},
+            )],
+            exploited: accepted,
+        }
+    }
+
+    pub fn close_profile_vulnerable(attacker: &Pubkey) -> Scenario {
+        let (profile, account) = victim_profile();
+        Scenario {
+            accounts: vec![(profile, account)],
+            instructions: vec![ix(
+                ID,
+                accounts::CloseProfileVulnerable { profile, recipient: *attacker, owner: *attacker },
+                instruction::CloseProfileVulnerable {},
+            )],
+            exploited: victim_profile_gone,
+        }
+    }
+
+    pub fn close_profile_secure(attacker: &Pubkey) -> Scenario {
+        let (profile, account) = victim_profile();
+        Scenario {
+            accounts: vec![(profile, account)],
+            instructions: vec![ix(
+                ID,
+                accounts::CloseProfileSecure {
+                    profile,
+                    tombstone: pda(&[b"tombstone", attacker.as_ref()], &ID),
+                    recipient: *attacker,
+                    owner: *attacker,
+                    system_program: system_program::ID,
+                },
+                instruction::CloseProfileSecure {},
+            )],
+            exploited: victim_profile_gone,
+        }
+    }
+
+    pub fn initialize_profile(attacker: &Pubkey) -> Scenario {
+        let (tombstone, account) = tombstone(attacker);
+        Scenario {
+            accounts: vec![(tombstone, account)],
+            instructions: vec![ix(
+                ID,
+                accounts::InitializeProfile {
+                    profile: pda(&[b"profile", attacker.as_ref()], &ID),
+                    tombstone,
+                    owner: *attacker,
+                    system_program: system_program::ID,
+                },
+                instruction::InitializeProfile {},
+            )],
+            exploited: profile_reopened,
+        }
+    }
+
+    pub fn recreate_profile(attacker: &Pubkey) -> Scenario {
+        const CENTURY_SECS: i64 = 100 * 365 * 24 * 60 * 60;
+        let (config, config_account) = config(CENTURY_SECS);
+        let (tombstone, tombstone_account) = tombstone(attacker);
+        Scenario {
+            accounts: vec![(config, config_account), (tombstone, tombstone_account)],
+            instructions: vec![ix(
+                ID,
+                accounts::RecreateProfile {
+                    config,
+                    tombstone,
+                    profile: pda(&[b"profile", attacker.as_ref()], &ID),
+                    owner: *attacker,
+                    system_program: system_program::ID,
+                },
+                instruction::RecreateProfile {},
+            )],
+            exploited: profile_reopened,
+        }
+    }
+
+    pub fn reopen(attacker: &Pubkey) -> Scenario {
+        // Closed at the epoch, with no grace period configured
+        let (config, config_account) = config(0);
+        let (tombstone, tombstone_account) = tombstone(attacker);
+        Scenario {
+            accounts: vec![(config, config_account), (tombstone, tombstone_account)],
+            instructions: vec![ix(
+                ID,
+                accounts::Reopen {
+                    config,
+                    tombstone,
+                    profile: pda(&[b"profile", attacker.as_ref()], &ID),
+                    owner: *attacker,
+                    system_program: system_program::ID,
+                },
+                instruction::Reopen {},
+            )],
+            exploited: profile_reopened,
+        }
+    }
+
+    pub fn accrue_rewards_batch(attacker: &Pubkey) -> Scenario {
+        let (config, config_account) = config(0);
+        let (user_account, account) = user_account_of(attacker);
+        let mut accrue = ix(
+            ID,
+            accounts::AccrueRewardsBatch { config, admin: *attacker },
+            instruction::AccrueRewardsBatch {
+                entries: vec![AccrualEntry { owner: *attacker, amount: 1_000_000 }],
+            },
+        );
+        accrue.accounts.push(AccountMeta::new(user_account, false));
+        Scenario {
+            accounts: vec![(config, config_account), (user_account, account)],
+            instructions: vec![accrue],
+            exploited: accepted,
+        }
+    }
+}
+
+mod escrow {
+    use super::*;
+    use ::escrow::{accounts, instruction, Escrow, ID};
+
+    const AMOUNT: u64 = LAMPORTS_PER_SOL;
+
+    /// The victim's escrow, paying the attacker once released
+    fn victim_escrow(attacker: &Pubkey) -> (Pubkey, Account) {
+        let (escrow, bump) = Pubkey::find_program_address(&[b"escrow", VICTIM.as_ref()], &ID);
+        let state = Escrow {
+            depositor: VICTIM,
+            beneficiary: *attacker,
+            arbiter: Pubkey::new_unique(),
+            amount: AMOUNT,
+            release_after: i64::MAX,
+            released: false,
+            bump,
+        };
+        let mut account = anchor_account(&state, ID);
+        account.lamports += AMOUNT;
+        (escrow, account)
+    }
+
+    fn released(effects: &Effects) -> bool {
+        let escrow = pda(&[b"escrow", VICTIM.as_ref()], &ID);
+        effects.load::<Escrow>(&escrow).is_some_and(|escrow| escrow.released)
+    }
+
+    pub fn release_vulnerable(attacker: &Pubkey) -> Scenario {
+        let (escrow, account) = victim_escrow(attacker);
+        Scenario {
+            accounts: vec![(escrow, account)],
+            instructions: vec![ix(
+                ID,
+                accounts::ReleaseVulnerable { escrow, beneficiary: *attacker, caller: *attacker },
+                instruction::ReleaseVulnerable { approved: true },
+            )],
+            exploited: released,
+        }
+    }
+
+    pub fn release_secure(attacker: &Pubkey) -> Scenario {
+        let (escrow, account) = victim_escrow(attacker);
+        let release = ix(
+            ID,
+            accounts::ReleaseSecure { escrow, depositor: VICTIM, beneficiary: *attacker, arbiter: None },
+            instruction::ReleaseSecure {},
+        );
+        Scenario {
+            accounts: vec![(escrow, account)],
+            instructions: vec![unsigned(release, &VICTIM)],
+            exploited: released,
+        }
+    }
+}
+
+mod randomness {
+    use super::*;
+    use anchor_lang::Space;
+    use insecure_randomness::{accounts, clock_index, commitment_of, instruction, Lottery, ID};
+    use solana_sdk::{clock::Clock, sysvar};
+
+    /// The victim's lottery, committed to a seed the attacker doesn't know,
+    /// with entries closed and waiting for the reveal
+    fn victim_lottery(attacker: &Pubkey) -> (Pubkey, Account) {
+        let (lottery, bump) = Pubkey::find_program_address(&[b"lottery", VICTIM.as_ref()], &ID);
+        let state = Lottery {
+            authority: VICTIM,
+            commitment: commitment_of(&[0x5e; 32]),
+            entrants: vec![VICTIM, *attacker],
+            entries_closed: true,
+            winner: None,
+            bump,
+        };
+        // Allocated at full size, as `initialize_lottery` would
+        let mut account = anchor_account(&state, ID);
+        account.data.resize(8 + Lottery::INIT_SPACE, 0);
+        account.lamports = Rent::default().minimum_balance(account.data.len());
+        (lottery, account)
+    }
+
+    /// The winner is exactly who the clock said it would be
+    fn predicted(effects: &Effects) -> bool {
+        let lottery = pda(&[b"lottery", VICTIM.as_ref()], &ID);
+        let Some(lottery) = effects.load::<Lottery>(&lottery) else {
+            return false;
+        };
+        let clock: Clock = solana_sdk::account::from_account(&effects.accounts[&sysvar::clock::ID]).unwrap();
+        let index = clock_index(clock.slot, clock.unix_timestamp, lottery.entrants.len());
+        lottery.winner == Some(lottery.entrants[index])
+    }
+
+    fn picked(effects: &Effects) -> bool {
+        let lottery = pda(&[b"lottery", VICTIM.as_ref()], &ID);
+        effects.load::<Lottery>(&lottery).is_some_and(|lottery| lottery.winner.is_some())
+    }
+
+    pub fn pick_winner_vulnerable(attacker: &Pubkey) -> Scenario {
+        let (lottery, account) = victim_lottery(attacker);
+        Scenario {
+            accounts: vec![(lottery, account)],
+            instructions: vec![ix(
+                ID,
+                accounts::PickWinnerVulnerable { lottery, caller: *attacker, clock: sysvar::clock::ID },
+                instruction::PickWinnerVulnerable {},
+            )],
+            exploited: predicted,
+        }
+    }
+
+    pub fn enter(attacker: &Pubkey) -> Scenario {
+        let (lottery, account) = victim_lottery(attacker);
+        Scenario {
+            accounts: vec![(lottery, account)],
+            instructions: vec![ix(ID, accounts::Enter { lottery, entrant: *attacker }, instruction::Enter {})],
+            exploited: |effects| {
+                let lottery = pda(&[b"lottery", VICTIM.as_ref()], &ID);
+                effects.load::<Lottery>(&lottery).is_some_and(|lottery| lottery.entrants.len() > 2)
+            },
+        }
+    }
+
+    pub fn pick_winner_secure(attacker: &Pubkey) -> Scenario {
+        let (lottery, account) = victim_lottery(attacker);
+        Scenario {
+            accounts: vec![(lottery, account)],
+            instructions: vec![ix(
+                ID,
+                accounts::PickWinnerSecure { lottery, caller: *attacker },
+                instruction::PickWinnerSecure { seed: [0u8; 32] },
+            )],
+            exploited: picked,
+        }
+    }
+}
+
+mod sol_vault {
+    use super::*;
+    use native_sol_vault::{accounts, instruction, SolVault, ID};
+
+    /// `authority`'s vault holding one SOL above its rent reserve
+    fn vault_of(authority: &Pubkey) -> (Pubkey, Account) {
+        let (vault, bump) = Pubkey::find_program_address(&[b"sol_vault", authority.as_ref()], &ID);
+        let mut account = anchor_account(&SolVault { authority: *authority, bump }, ID);
+        account.lamports += LAMPORTS_PER_SOL;
+        (vault, account)
+    }
+
+    /// No vault survived the transaction
+    fn reaped(effects: &Effects) -> bool {
+        !effects
+            .accounts
+            .values()
+            .any(|account| SolVault::try_deserialize(&mut account.data.as_slice()).is_ok())
+    }
+
+    fn withdraw(vault: Pubkey, attacker: &Pubkey, amount: u64) -> Instruction {
+        ix(
+            ID,
+            accounts::WithdrawSecure { vault, recipient: *attacker, authority: *attacker },
+            instruction::WithdrawSecure { amount },
+        )
+    }
+
+    pub fn withdraw_from_victim(attacker: &Pubkey) -> Scenario {
+        let (vault, account) = vault_of(&VICTIM);
+        Scenario {
+            accounts: vec![(vault, account)],
+            instructions: vec![withdraw(vault, attacker, LAMPORTS_PER_SOL)],
+            exploited: |effects| {
+                let (vault, before) = vault_of(&VICTIM);
+                effects.accounts.get(&vault).is_none_or(|account| account.lamports != before.lamports)
+            },
+        }
+    }
+
+    pub fn withdraw_rent_reserve(attacker: &Pubkey) -> Scenario {
+        let (vault, account) = vault_of(attacker);
+        Scenario {
+            accounts: vec![(vault, account)],
+            instructions: vec![withdraw(vault, attacker, LAMPORTS_PER_SOL + 1)],
+            exploited: reaped,
+        }
+    }
+}
+
+mod upgrade {
+    use super::*;
+    use anchor_lang::solana_program::bpf_loader_upgradeable;
+    use upgrade_authority::{accounts, instruction, ProgramConfig, ID};
+
+    fn config() -> Pubkey {
+        pda(&[b"config"], &ID)
+    }
+
+    /// Someone other than the deployer became admin
+    fn admin_taken(effects: &Effects) -> bool {
+        effects.load::<ProgramConfig>(&config()).is_some_and(|config| config.admin != VICTIM)
+    }
+
+    pub fn initialize_config_vulnerable(attacker: &Pubkey) -> Scenario {
+        Scenario {
+            accounts: vec![],
+            instructions: vec![ix(
+                ID,
+                accounts::InitializeConfigVulnerable { config: config(), payer: *attacker, system_program: system_program::ID },
+                instruction::InitializeConfigVulnerable { admin: *attacker },
+            )],
+            exploited: admin_taken,
+        }
+    }
+
+    pub fn bootstrap_admin(attacker: &Pubkey) -> Scenario {
+        // The victim deployed the program and holds its upgrade authority
+        let program_data = pda(&[ID.as_ref()], &bpf_loader_upgradeable::ID);
+        Scenario {
+            accounts: vec![(program_data, program_data_account(Some(VICTIM)))],
+            instructions: vec![ix(
+                ID,
+                accounts::BootstrapAdmin { config: config(), program_data, authority: *attacker, system_program: system_program::ID },
+                instruction::BootstrapAdmin { admin: *attacker },
+            )],
+            exploited: admin_taken,
+        }
+    }
+}
+
+mod flash_loan {
+    use super::*;
+    use anchor_lang::solana_program::sysvar::instructions;
+    use flash_loan_guard::{accounts, instruction, LendingPool, Position, ID};
+
+    /// The victim's pool, holding one SOL of the victim's deposits
+    fn victim_pool() -> (Pubkey, Account) {
+        let (pool, bump) = Pubkey::find_program_address(&[b"lending_pool", VICTIM.as_ref()], &ID);
+        let state = LendingPool { authority: VICTIM, total_deposits: LAMPORTS_PER_SOL, loan_outstanding: 0, balance_before: 0, bump };
+        let mut account = anchor_account(&state, ID);
+        account.lamports += LAMPORTS_PER_SOL;
+        (pool, account)
+    }
+
+    /// The attacker's empty position in the victim's pool
+    fn position(attacker: &Pubkey) -> (Pubkey, Account) {
+        let pool = victim_pool().0;
+        let (position, bump) = Pubkey::find_program_address(&[b"position", pool.as_ref(), attacker.as_ref()], &ID);
+        let state = Position { owner: *attacker, pool, amount: 0, bump };
+        (position, anchor_account(&state, ID))
+    }
+
+    /// The pool owes depositors more than it holds above rent
+    fn insolvent(effects: &Effects) -> bool {
+        let (pool, before) = victim_pool();
+        effects.load::<LendingPool>(&pool).is_some_and(|state| {
+            let rent = before.lamports - LAMPORTS_PER_SOL;
+            effects.accounts[&pool].lamports - rent < state.total_deposits
+        })
+    }
+
+    fn deposit(attacker: &Pubkey) -> Instruction {
+        ix(
+            ID,
+            accounts::Deposit { pool: victim_pool().0, position: position(attacker).0, owner: *attacker, system_program: system_program::ID },
+            instruction::Deposit { amount: LAMPORTS_PER_SOL },
+        )
+    }
+
+    fn borrow(attacker: &Pubkey) -> Instruction {
+        ix(
+            ID,
+            accounts::Borrow { pool: victim_pool().0, borrower: *attacker, instructions: instructions::ID },
+            instruction::Borrow { amount: LAMPORTS_PER_SOL },
+        )
+    }
+
+    fn repay(attacker: &Pubkey) -> Instruction {
+        ix(
+            ID,
+            accounts::Repay { pool: victim_pool().0, borrower: *attacker, system_program: system_program::ID },
+            instruction::Repay { amount: LAMPORTS_PER_SOL },
+        )
+    }
+
+    pub fn borrow_vulnerable(attacker: &Pubkey) -> Scenario {
+        let pool = victim_pool();
+        Scenario {
+            accounts: vec![pool.clone(), position(attacker)],
+            instructions: vec![
+                ix(
+                    ID,
+                    accounts::BorrowVulnerable { pool: pool.0, borrower: *attacker, instructions: instructions::ID },
+                    instruction::BorrowVulnerable { amount: LAMPORTS_PER_SOL },
+                ),
+                deposit(attacker),
+                ix(ID, accounts::CheckRepaidVulnerable { pool: pool.0 }, instruction::CheckRepaidVulnerable {}),
+            ],
+            exploited: insolvent,
+        }
+    }
+
+    pub fn borrow_then_deposit(attacker: &Pubkey) -> Scenario {
+        Scenario {
+            accounts: vec![victim_pool(), position(attacker)],
+            instructions: vec![borrow(attacker), deposit(attacker), repay(attacker)],
+            exploited: insolvent,
+        }
+    }
+
+    pub fn borrow_unrepaid(attacker: &Pubkey) -> Scenario {
+        Scenario {
+            accounts: vec![victim_pool()],
+            instructions: vec![borrow(attacker)],
+            exploited: |effects| {
+                let (pool, before) = victim_pool();
+                effects.accounts.get(&pool).is_none_or(|account| account.lamports < before.lamports)
+            },
+        }
+    }
+}
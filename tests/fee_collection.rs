@@ -0,0 +1,114 @@
+//! Batch fee collection in the integer-overflow program.
+//!
+//! `collect_fees` sums the pending fees of every position it's handed with
+//! checked addition and only then zeroes them, so a batch whose total
+//! doesn't fit in u64 fails without collecting anything.
+
+use anchor_lang::{AccountDeserialize, InstructionData, ToAccountMetas};
+use integer_overflow::{accounts, instruction, FeePosition, MathError, SecurityError, Vault, ID};
+use security_tests::{anchor_account, custom_error, load, program_test, send, system_account};
+use solana_program_test::BanksClient;
+use solana_sdk::{
+    instruction::{AccountMeta, Instruction},
+    native_token::LAMPORTS_PER_SOL,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    transaction::TransactionError,
+};
+
+const EXISTING_FEES: u64 = 1_000;
+
+/// A vault already holding `EXISTING_FEES`, with one position per entry
+/// of `pending`.
+struct Fixture {
+    banks: BanksClient,
+    authority: Keypair,
+    vault: Pubkey,
+    positions: Vec<Pubkey>,
+}
+
+async fn positions_owing(pending: &[u64]) -> Fixture {
+    let authority = Keypair::new();
+    let mut test = program_test();
+    test.add_account(authority.pubkey(), system_account(LAMPORTS_PER_SOL));
+    let (vault, bump) = Pubkey::find_program_address(&[b"vault", authority.pubkey().as_ref()], &ID);
+    let state = Vault { authority: authority.pubkey(), total_deposits: 0, protocol_fees: EXISTING_FEES, bump };
+    test.add_account(vault, anchor_account(&state, ID));
+
+    let mut positions = Vec::new();
+    for &pending_fees in pending {
+        let owner = Pubkey::new_unique();
+        let (position, bump) = Pubkey::find_program_address(&[b"fee_position", vault.as_ref(), owner.as_ref()], &ID);
+        test.add_account(position, anchor_account(&FeePosition { vault, owner, pending_fees, bump }, ID));
+        positions.push(position);
+    }
+    let (banks, _payer, _) = test.start().await;
+    Fixture { banks, authority, vault, positions }
+}
+
+impl Fixture {
+    async fn collect(&mut self, positions: &[Pubkey]) -> Result<(), TransactionError> {
+        let mut metas = accounts::CollectFees { vault: self.vault, authority: self.authority.pubkey() }.to_account_metas(None);
+        metas.extend(positions.iter().map(|position| AccountMeta::new(*position, false)));
+        let ix = Instruction::new_with_bytes(ID, &instruction::CollectFees {}.data(), metas);
+        send(&mut self.banks, &[ix], &[&self.authority]).await
+    }
+
+    async fn protocol_fees(&mut self) -> u64 {
+        load::<Vault>(&mut self.banks, self.vault).await.protocol_fees
+    }
+
+    async fn pending(&mut self) -> Vec<u64> {
+        let mut pending = Vec::new();
+        for position in self.positions.clone() {
+            let account = self.banks.get_account(position).await.unwrap().unwrap();
+            pending.push(FeePosition::try_deserialize(&mut account.data.as_slice()).unwrap().pending_fees);
+        }
+        pending
+    }
+}
+
+#[tokio::test]
+async fn fees_from_every_position_are_collected() {
+    let mut fixture = positions_owing(&[100, 250, 0, 4_650]).await;
+    let positions = fixture.positions.clone();
+
+    fixture.collect(&positions).await.expect("fees collected");
+
+    assert_eq!(fixture.protocol_fees().await, EXISTING_FEES + 5_000);
+    assert_eq!(fixture.pending().await, vec![0; 4]);
+}
+
+#[tokio::test]
+async fn an_empty_batch_is_a_no_op() {
+    let mut fixture = positions_owing(&[100]).await;
+
+    fixture.collect(&[]).await.expect("nothing to collect");
+
+    assert_eq!(fixture.protocol_fees().await, EXISTING_FEES);
+    assert_eq!(fixture.pending().await, vec![100]);
+}
+
+#[tokio::test]
+async fn an_overflowing_total_is_rejected_before_anything_moves() {
+    // Each position fits; the sum does not
+    let mut fixture = positions_owing(&[u64::MAX / 2, u64::MAX / 2, 2]).await;
+    let positions = fixture.positions.clone();
+
+    let err = fixture.collect(&positions).await.expect_err("aggregate past u64::MAX");
+
+    assert_eq!(err, custom_error(0, SecurityError::Overflow));
+    assert_eq!(fixture.protocol_fees().await, EXISTING_FEES);
+    assert_eq!(fixture.pending().await, vec![u64::MAX / 2, u64::MAX / 2, 2]);
+}
+
+#[tokio::test]
+async fn a_position_passed_twice_is_rejected() {
+    let mut fixture = positions_owing(&[100]).await;
+    let position = fixture.positions[0];
+
+    let err = fixture.collect(&[position, position]).await.expect_err("duplicate position");
+
+    assert_eq!(err, custom_error(0, MathError::DuplicatePosition));
+    assert_eq!(fixture.protocol_fees().await, EXISTING_FEES);
+}
@@ -0,0 +1,106 @@
+//! Fee holidays on account-validation swaps.
+//!
+//! Swaps before `config.fee_holiday_until` pay nothing; from that second
+//! on, the configured `fee_bps` applies again.
+
+use account_validation::{accounts, instruction, ID};
+use anchor_lang::error::ErrorCode as AnchorError;
+use anchor_lang::{InstructionData, ToAccountMetas};
+use security_tests::{custom_error, pda, program_test, send, send_for_u64, set_time};
+use solana_program_test::ProgramTestContext;
+use solana_sdk::{
+    instruction::Instruction,
+    native_token::LAMPORTS_PER_SOL,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    system_instruction, system_program,
+};
+
+const FEE_BPS: u16 = 100;
+const HOLIDAY_ENDS: i64 = 2_000_000;
+const AMOUNT: u64 = 1_000_000;
+/// 1% of `AMOUNT`
+const FULL_FEE: u64 = 10_000;
+
+fn ix(accounts: impl ToAccountMetas, data: impl InstructionData) -> Instruction {
+    Instruction::new_with_bytes(ID, &data.data(), accounts.to_account_metas(None))
+}
+
+/// A config charging `FEE_BPS` with a holiday until `HOLIDAY_ENDS`, and a
+/// pool under it. The payer is the admin.
+async fn promotion() -> ProgramTestContext {
+    let mut context = program_test().start_with_context().await;
+    let admin = context.payer.pubkey();
+    let config = pda(&[b"config"], &ID);
+    let setup = [
+        ix(
+            accounts::InitializeConfig { config, admin, system_program: system_program::ID },
+            instruction::InitializeConfig { fee_bps: FEE_BPS, max_positions: 1, dust_threshold: 0 },
+        ),
+        ix(
+            accounts::InitializePool { config, pool: pool(&context), authority: admin, system_program: system_program::ID },
+            instruction::InitializePool {
+                reward_rate: 10,
+                global_tvl_cap: u64::MAX,
+                lock_secs: 0,
+                min_hold_secs: 0,
+                early_exit_bps: 0,
+                deposit_mint: Pubkey::default(),
+                reward_mint: Pubkey::default(),
+            },
+        ),
+        ix(
+            accounts::SetFeeHoliday { config, admin },
+            instruction::SetFeeHoliday { fee_holiday_until: HOLIDAY_ENDS },
+        ),
+    ];
+    send(&mut context.banks_client, &setup, &[&context.payer]).await.expect("setup");
+    context
+}
+
+fn pool(context: &ProgramTestContext) -> Pubkey {
+    pda(&[b"pool", context.payer.pubkey().as_ref()], &ID)
+}
+
+/// Swaps `amount` at `unix_timestamp` and returns the fee charged.
+async fn swap_at(context: &mut ProgramTestContext, unix_timestamp: i64, amount: u64) -> u64 {
+    set_time(context, unix_timestamp).await;
+    let swap = ix(
+        accounts::SwapSecure { config: pda(&[b"config"], &ID), pool: pool(context), user: context.payer.pubkey() },
+        instruction::SwapSecure { amount, min_out: 0, deadline: i64::MAX },
+    );
+    send_for_u64(&mut context.banks_client, &[swap], &[&context.payer]).await.expect("swap lands")
+}
+
+#[tokio::test]
+async fn swaps_during_the_holiday_are_free() {
+    let mut context = promotion().await;
+
+    assert_eq!(swap_at(&mut context, 0, AMOUNT).await, 0);
+    assert_eq!(swap_at(&mut context, HOLIDAY_ENDS - 1, AMOUNT + 1).await, 0);
+}
+
+#[tokio::test]
+async fn fees_resume_exactly_at_the_boundary() {
+    let mut context = promotion().await;
+
+    // Amounts differ so identical transactions aren't deduplicated
+    assert_eq!(swap_at(&mut context, HOLIDAY_ENDS - 1, AMOUNT + 1).await, 0);
+    assert_eq!(swap_at(&mut context, HOLIDAY_ENDS, AMOUNT).await, FULL_FEE);
+    assert_eq!(swap_at(&mut context, HOLIDAY_ENDS + 1, AMOUNT + 100).await, FULL_FEE + 1);
+}
+
+#[tokio::test]
+async fn only_the_admin_can_declare_a_holiday() {
+    let mut context = promotion().await;
+    let intruder = Keypair::new();
+    let fund = system_instruction::transfer(&context.payer.pubkey(), &intruder.pubkey(), LAMPORTS_PER_SOL);
+    send(&mut context.banks_client, &[fund], &[&context.payer]).await.expect("fund intruder");
+
+    let holiday = ix(
+        accounts::SetFeeHoliday { config: pda(&[b"config"], &ID), admin: intruder.pubkey() },
+        instruction::SetFeeHoliday { fee_holiday_until: i64::MAX },
+    );
+    let err = send(&mut context.banks_client, &[holiday], &[&intruder]).await.expect_err("not the admin");
+    assert_eq!(err, custom_error(0, AnchorError::ConstraintHasOne));
+}
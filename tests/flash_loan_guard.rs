@@ -0,0 +1,182 @@
+//! Flash loans from the flash-loan-guard pool.
+//!
+//! `borrow` only lends when a `repay` of the same amount follows in the
+//! transaction, and deposits wait until it has run.
+//! `borrow_vulnerable` only checks the pool's balance afterwards, which a
+//! deposit of the borrowed lamports satisfies.
+
+use anchor_lang::solana_program::sysvar::instructions;
+use anchor_lang::{InstructionData, ToAccountMetas};
+use flash_loan_guard::{accounts, instruction, FlashLoanError, LendingPool, Position, ID};
+use security_tests::{load, pda, program_test, send, system_account};
+use solana_program_test::BanksClient;
+use solana_sdk::{
+    instruction::{Instruction, InstructionError},
+    native_token::LAMPORTS_PER_SOL,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    system_program,
+    transaction::TransactionError,
+};
+
+const LIQUIDITY: u64 = 5 * LAMPORTS_PER_SOL;
+
+fn ix(accounts: impl ToAccountMetas, data: impl InstructionData) -> Instruction {
+    Instruction::new_with_bytes(ID, &data.data(), accounts.to_account_metas(None))
+}
+
+/// A pool holding `LIQUIDITY` from its authority's deposit, and a borrower
+/// with an empty position in it.
+struct Fixture {
+    banks: BanksClient,
+    borrower: Keypair,
+    pool: Pubkey,
+    position: Pubkey,
+}
+
+async fn funded_pool() -> Fixture {
+    let lender = Keypair::new();
+    let borrower = Keypair::new();
+    let mut test = program_test();
+    test.add_account(lender.pubkey(), system_account(2 * LIQUIDITY));
+    test.add_account(borrower.pubkey(), system_account(LAMPORTS_PER_SOL));
+    let (mut banks, _payer, _) = test.start().await;
+
+    let pool = pda(&[b"lending_pool", lender.pubkey().as_ref()], &ID);
+    let lender_position = pda(&[b"position", pool.as_ref(), lender.pubkey().as_ref()], &ID);
+    let setup = [
+        ix(
+            accounts::InitializeLendingPool { pool, authority: lender.pubkey(), system_program: system_program::ID },
+            instruction::InitializeLendingPool {},
+        ),
+        ix(
+            accounts::OpenPosition { pool, position: lender_position, owner: lender.pubkey(), system_program: system_program::ID },
+            instruction::OpenPosition {},
+        ),
+        ix(
+            accounts::Deposit { pool, position: lender_position, owner: lender.pubkey(), system_program: system_program::ID },
+            instruction::Deposit { amount: LIQUIDITY },
+        ),
+    ];
+    send(&mut banks, &setup, &[&lender]).await.expect("pool funded");
+
+    let position = pda(&[b"position", pool.as_ref(), borrower.pubkey().as_ref()], &ID);
+    let open = ix(
+        accounts::OpenPosition { pool, position, owner: borrower.pubkey(), system_program: system_program::ID },
+        instruction::OpenPosition {},
+    );
+    send(&mut banks, &[open], &[&borrower]).await.expect("position opened");
+    Fixture { banks, borrower, pool, position }
+}
+
+impl Fixture {
+    async fn send(&mut self, instructions: &[Instruction]) -> Result<(), TransactionError> {
+        let borrower = self.borrower.insecure_clone();
+        send(&mut self.banks, instructions, &[&borrower]).await
+    }
+
+    fn borrow(&self, amount: u64) -> Instruction {
+        ix(
+            accounts::Borrow { pool: self.pool, borrower: self.borrower.pubkey(), instructions: instructions::ID },
+            instruction::Borrow { amount },
+        )
+    }
+
+    fn repay(&self, amount: u64) -> Instruction {
+        ix(
+            accounts::Repay { pool: self.pool, borrower: self.borrower.pubkey(), system_program: system_program::ID },
+            instruction::Repay { amount },
+        )
+    }
+
+    fn deposit(&self, amount: u64) -> Instruction {
+        ix(
+            accounts::Deposit {
+                pool: self.pool,
+                position: self.position,
+                owner: self.borrower.pubkey(),
+                system_program: system_program::ID,
+            },
+            instruction::Deposit { amount },
+        )
+    }
+
+    async fn pool_lamports(&mut self) -> u64 {
+        self.banks.get_balance(self.pool).await.unwrap()
+    }
+
+    async fn pool(&mut self) -> LendingPool {
+        load(&mut self.banks, self.pool).await
+    }
+
+    async fn position(&mut self) -> Position {
+        load(&mut self.banks, self.position).await
+    }
+}
+
+fn error(index: u8, code: FlashLoanError) -> TransactionError {
+    TransactionError::InstructionError(index, InstructionError::Custom(code.into()))
+}
+
+#[tokio::test]
+async fn a_loan_repaid_in_the_same_transaction_lands() {
+    let mut fixture = funded_pool().await;
+    let before = fixture.pool_lamports().await;
+
+    fixture.send(&[fixture.borrow(LIQUIDITY), fixture.repay(LIQUIDITY)]).await.expect("borrowed and repaid");
+
+    assert_eq!(fixture.pool_lamports().await, before);
+    assert_eq!(fixture.pool().await.loan_outstanding, 0);
+}
+
+#[tokio::test]
+async fn an_unrepaid_loan_is_rejected() {
+    let mut fixture = funded_pool().await;
+    let before = fixture.pool_lamports().await;
+
+    let err = fixture.send(&[fixture.borrow(LIQUIDITY)]).await.expect_err("nothing repays it");
+
+    assert_eq!(err, error(0, FlashLoanError::MissingRepay));
+    assert_eq!(fixture.pool_lamports().await, before);
+}
+
+#[tokio::test]
+async fn repaying_less_than_the_loan_is_rejected() {
+    let mut fixture = funded_pool().await;
+
+    let err = fixture.send(&[fixture.borrow(LIQUIDITY), fixture.repay(LIQUIDITY - 1)]).await.expect_err("short repay");
+
+    assert_eq!(err, error(0, FlashLoanError::MissingRepay));
+}
+
+#[tokio::test]
+async fn depositing_the_loan_is_rejected() {
+    let mut fixture = funded_pool().await;
+
+    let instructions = [fixture.borrow(LIQUIDITY), fixture.deposit(LIQUIDITY), fixture.repay(LIQUIDITY)];
+    let err = fixture.send(&instructions).await.expect_err("deposit during the loan");
+
+    assert_eq!(err, error(1, FlashLoanError::FlashLoanActive));
+    assert_eq!(fixture.position().await.amount, 0);
+}
+
+#[tokio::test]
+async fn the_balance_check_accepts_a_deposit_as_repayment() {
+    let mut fixture = funded_pool().await;
+    let before = fixture.pool_lamports().await;
+
+    let instructions = [
+        ix(
+            accounts::BorrowVulnerable { pool: fixture.pool, borrower: fixture.borrower.pubkey(), instructions: instructions::ID },
+            instruction::BorrowVulnerable { amount: LIQUIDITY },
+        ),
+        fixture.deposit(LIQUIDITY),
+        ix(accounts::CheckRepaidVulnerable { pool: fixture.pool }, instruction::CheckRepaidVulnerable {}),
+    ];
+    fixture.send(&instructions).await.expect("balance restored");
+
+    // The borrower now owns a position the pool was never paid for
+    assert_eq!(fixture.pool_lamports().await, before);
+    assert_eq!(fixture.position().await.amount, LIQUIDITY);
+    assert_eq!(fixture.pool().await.total_deposits, 2 * LIQUIDITY);
+}
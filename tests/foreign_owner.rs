@@ -0,0 +1,88 @@
+//! The owner half of type confusion in the type-cosplay program.
+//!
+//! A `RewardVault` with this program's discriminator and layout, but owned
+//! by another program, is not this program's vault. Only the owner check
+//! in `claim_rewards_owner_checked` tells the two apart; the discriminator
+//! check then tells a `RewardVault` from a `UserAccount`.
+
+use anchor_lang::{InstructionData, ToAccountMetas};
+use security_tests::{anchor_account, custom_error, program_test};
+use solana_sdk::{
+    account::Account,
+    instruction::Instruction,
+    pubkey::Pubkey,
+    signature::Signer,
+    transaction::{Transaction, TransactionError},
+};
+use type_cosplay::{accounts, instruction, RewardVault, TypeCosplayError, UserAccount, ID};
+
+const BALANCE: u64 = 1_000;
+
+/// Preloads `vault`, sends the instruction `build` makes for it and the
+/// payer, and returns the claimable amount.
+async fn claim(vault: Account, build: impl FnOnce(Pubkey, Pubkey) -> Instruction) -> Result<u64, TransactionError> {
+    let key = Pubkey::new_unique();
+    let mut test = program_test();
+    test.add_account(key, vault);
+    let (mut banks, payer, blockhash) = test.start().await;
+    let tx = Transaction::new_signed_with_payer(&[build(key, payer.pubkey())], Some(&payer.pubkey()), &[&payer], blockhash);
+    let result = banks.process_transaction_with_metadata(tx).await.unwrap();
+    result.result?;
+
+    // The runtime strips trailing zero bytes from return data
+    let data = result
+        .metadata
+        .and_then(|metadata| metadata.return_data)
+        .map(|return_data| return_data.data)
+        .unwrap_or_default();
+    let mut bytes = [0u8; 8];
+    bytes[..data.len()].copy_from_slice(&data);
+    Ok(u64::from_le_bytes(bytes))
+}
+
+fn owner_checked(vault: Pubkey, user: Pubkey) -> Instruction {
+    Instruction::new_with_bytes(
+        ID,
+        &instruction::ClaimRewardsOwnerChecked {}.data(),
+        accounts::ClaimRewardsOwnerChecked { vault, user }.to_account_metas(None),
+    )
+}
+
+fn vulnerable(vault: Pubkey, user: Pubkey) -> Instruction {
+    Instruction::new_with_bytes(
+        ID,
+        &instruction::ClaimRewardsVulnerable {}.data(),
+        accounts::ClaimRewardsVulnerable { vault, user }.to_account_metas(None),
+    )
+}
+
+/// A `RewardVault` holding `BALANCE`, discriminator and all, owned by `owner`
+fn reward_vault(owner: Pubkey) -> Account {
+    anchor_account(&RewardVault { authority: Pubkey::new_unique(), balance: BALANCE, bump: 255 }, owner)
+}
+
+#[tokio::test]
+async fn this_programs_reward_vault_is_claimed() {
+    assert_eq!(claim(reward_vault(ID), owner_checked).await, Ok(BALANCE));
+}
+
+#[tokio::test]
+async fn a_matching_vault_owned_by_another_program_is_rejected() {
+    // Same discriminator, same layout: only the owner differs
+    for owner in [closing_accounts::ID, Pubkey::new_unique()] {
+        let result = claim(reward_vault(owner), owner_checked).await;
+        assert_eq!(result, Err(custom_error(0, TypeCosplayError::WrongOwnerProgram)), "{owner}");
+    }
+}
+
+#[tokio::test]
+async fn the_vulnerable_claim_pays_out_another_programs_vault() {
+    assert_eq!(claim(reward_vault(closing_accounts::ID), vulnerable).await, Ok(BALANCE));
+}
+
+#[tokio::test]
+async fn the_owner_alone_does_not_tell_user_accounts_from_vaults() {
+    let user = anchor_account(&UserAccount { owner: Pubkey::new_unique(), balance: BALANCE, bump: 255 }, ID);
+
+    assert_eq!(claim(user, owner_checked).await, Err(custom_error(0, TypeCosplayError::TypeMismatch)));
+}
@@ -0,0 +1,107 @@
+//! Token-program-aware transfers in the arbitrary-CPI program.
+//!
+//! `transfer_with_hook_awareness` routes Token-2022 mints through
+//! `transfer_checked` and legacy mints through `transfer`, after checking
+//! the mint's decimals against what the caller expects.
+
+use anchor_lang::{InstructionData, ToAccountMetas};
+use anchor_spl::token::spl_token;
+use anchor_spl::token_2022;
+use arbitrary_cpi::{accounts, instruction, CpiError, ID};
+use security_tests::{custom_error, mint_account, program_test, send, system_account, token_account};
+use solana_program_test::BanksClient;
+use solana_sdk::{
+    account::Account,
+    instruction::Instruction,
+    native_token::LAMPORTS_PER_SOL,
+    program_pack::Pack,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    transaction::TransactionError,
+};
+
+const DECIMALS: u8 = 6;
+
+/// A source holding 1_000 and an empty destination, both of one mint
+/// owned by `token_program`.
+struct Fixture {
+    banks: BanksClient,
+    authority: Keypair,
+    token_program: Pubkey,
+    mint: Pubkey,
+    source: Pubkey,
+    destination: Pubkey,
+}
+
+fn owned_by(mut account: Account, token_program: Pubkey) -> Account {
+    account.owner = token_program;
+    account
+}
+
+async fn funded(token_program: Pubkey) -> Fixture {
+    let authority = Keypair::new();
+    let (mint, source, destination) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+    let mut test = program_test();
+    test.add_account(authority.pubkey(), system_account(LAMPORTS_PER_SOL));
+    test.add_account(mint, owned_by(mint_account(Pubkey::new_unique(), DECIMALS), token_program));
+    test.add_account(source, owned_by(token_account(mint, authority.pubkey(), 1_000), token_program));
+    test.add_account(destination, owned_by(token_account(mint, Pubkey::new_unique(), 0), token_program));
+    let (banks, _payer, _) = test.start().await;
+    Fixture { banks, authority, token_program, mint, source, destination }
+}
+
+impl Fixture {
+    async fn transfer(&mut self, amount: u64, expected_decimals: u8) -> Result<(), TransactionError> {
+        let ix = Instruction::new_with_bytes(
+            ID,
+            &instruction::TransferWithHookAwareness { amount, expected_decimals }.data(),
+            accounts::TransferHookAware {
+                source: self.source,
+                mint: self.mint,
+                destination: self.destination,
+                authority: self.authority.pubkey(),
+                token_program: self.token_program,
+            }
+            .to_account_metas(None),
+        );
+        send(&mut self.banks, &[ix], &[&self.authority]).await
+    }
+
+    /// Both layouts share the legacy base account encoding
+    async fn balance(&mut self, key: Pubkey) -> u64 {
+        let account = self.banks.get_account(key).await.unwrap().unwrap();
+        spl_token::state::Account::unpack(&account.data[..spl_token::state::Account::LEN]).unwrap().amount
+    }
+}
+
+#[tokio::test]
+async fn legacy_mints_transfer_through_the_token_program() {
+    let mut fixture = funded(spl_token::ID).await;
+
+    fixture.transfer(400, DECIMALS).await.expect("legacy transfer");
+
+    assert_eq!(fixture.balance(fixture.source).await, 600);
+    assert_eq!(fixture.balance(fixture.destination).await, 400);
+}
+
+#[tokio::test]
+async fn token_2022_mints_transfer_checked() {
+    let mut fixture = funded(token_2022::ID).await;
+
+    fixture.transfer(400, DECIMALS).await.expect("Token-2022 transfer_checked");
+
+    assert_eq!(fixture.balance(fixture.source).await, 600);
+    assert_eq!(fixture.balance(fixture.destination).await, 400);
+}
+
+#[tokio::test]
+async fn unexpected_decimals_are_rejected_on_either_program() {
+    for token_program in [spl_token::ID, token_2022::ID] {
+        let mut fixture = funded(token_program).await;
+
+        let err = fixture.transfer(400, DECIMALS + 3).await.expect_err("wrong decimals");
+
+        assert_eq!(err, custom_error(0, CpiError::DecimalsMismatch));
+        assert_eq!(fixture.balance(fixture.source).await, 1_000);
+    }
+}
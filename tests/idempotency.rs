@@ -0,0 +1,167 @@
+//! Instructions that promise to be idempotent.
+//!
+//! Re-sending `migrate_batch` or pausing an already-paused instruction must
+//! leave the same state as sending it once, without failing. Each repeat
+//! lands in a later slot so it is a new transaction, not a replay the
+//! runtime would drop.
+
+use anchor_lang::{AccountDeserialize, Discriminator, InstructionData, Space, ToAccountMetas};
+use reinitialization::{AdminAuditLog, DisabledInstructions};
+use security_tests::{anchor_account, program_test, raw_account};
+use solana_program_test::ProgramTestContext;
+use solana_sdk::{
+    clock::Clock,
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    transaction::{Transaction, TransactionError},
+};
+use type_cosplay::StakeAccount;
+
+async fn send(context: &mut ProgramTestContext, instruction: Instruction, admin: Option<&Keypair>) -> Result<(), TransactionError> {
+    // A fresh slot means a fresh blockhash, so a repeat isn't deduplicated
+    let clock: Clock = context.banks_client.get_sysvar().await.unwrap();
+    context.warp_to_slot(clock.slot + 1).unwrap();
+    let blockhash = context.banks_client.get_latest_blockhash().await.unwrap();
+    let mut signers = vec![&context.payer];
+    signers.extend(admin);
+    let tx = Transaction::new_signed_with_payer(&[instruction], Some(&context.payer.pubkey()), &signers, blockhash);
+    context.banks_client.process_transaction(tx).await.map_err(|err| err.unwrap())
+}
+
+async fn data(context: &mut ProgramTestContext, key: Pubkey) -> Vec<u8> {
+    context.banks_client.get_account(key).await.unwrap().unwrap().data
+}
+
+// ============================================================================
+// MIGRATION
+// ============================================================================
+
+fn migrate_batch(batch: &[Pubkey]) -> Instruction {
+    let mut metas = type_cosplay::accounts::MigrateBatch {}.to_account_metas(None);
+    metas.extend(batch.iter().map(|key| AccountMeta::new(*key, false)));
+    Instruction::new_with_bytes(type_cosplay::ID, &type_cosplay::instruction::MigrateBatch {}.data(), metas)
+}
+
+#[tokio::test]
+async fn migrating_a_batch_twice_matches_migrating_it_once() {
+    let mut test = program_test();
+    let mut stakes = Vec::new();
+    for (version, amount) in [(1, 5), (2, 7_000_000), (1, 3)] {
+        let owner = Pubkey::new_unique();
+        let (stake, bump) = Pubkey::find_program_address(&[b"stake", owner.as_ref()], &type_cosplay::ID);
+        test.add_account(stake, anchor_account(&StakeAccount { owner, version, amount, bump }, type_cosplay::ID));
+        stakes.push(stake);
+    }
+    let mut context = test.start_with_context().await;
+
+    send(&mut context, migrate_batch(&stakes), None).await.expect("first migration");
+    let mut once = Vec::new();
+    for stake in &stakes {
+        once.push(data(&mut context, *stake).await);
+    }
+
+    send(&mut context, migrate_batch(&stakes), None).await.expect("second migration is a no-op");
+
+    for (stake, once) in stakes.iter().zip(once) {
+        let twice = data(&mut context, *stake).await;
+        assert_eq!(twice, once, "a step was applied twice");
+        let stake = StakeAccount::try_deserialize(&mut twice.as_slice()).unwrap();
+        assert_eq!(stake.version, StakeAccount::CURRENT_VERSION);
+    }
+}
+
+// ============================================================================
+// PAUSE
+// ============================================================================
+
+/// A kill switch already holding `disabled`, padded to its full size,
+/// and an empty audit log.
+struct Paused {
+    context: ProgramTestContext,
+    admin: Keypair,
+    kill_switch: Pubkey,
+    audit_log: Pubkey,
+}
+
+async fn kill_switch_with(disabled: Vec<[u8; 8]>) -> Paused {
+    use reinitialization::ID;
+
+    let admin = Keypair::new();
+    let mut test = program_test();
+    let (kill_switch, bump) = Pubkey::find_program_address(&[b"disabled_instructions"], &ID);
+    let state = DisabledInstructions { admin: admin.pubkey(), disabled, bump };
+    let mut data = anchor_account(&state, ID).data;
+    data.resize(8 + DisabledInstructions::INIT_SPACE, 0);
+    test.add_account(kill_switch, raw_account(data, ID));
+    let (audit_log, bump) = Pubkey::find_program_address(&[b"admin_audit"], &ID);
+    let log = AdminAuditLog { admin: admin.pubkey(), override_count: 0, last_hash: [0; 32], bump };
+    test.add_account(audit_log, anchor_account(&log, ID));
+
+    let context = test.start_with_context().await;
+    Paused { context, admin, kill_switch, audit_log }
+}
+
+impl Paused {
+    async fn set_withdraw_enabled(&mut self, enabled: bool) -> Result<(), TransactionError> {
+        use reinitialization::{accounts, instruction, ID};
+
+        let ix = Instruction::new_with_bytes(
+            ID,
+            &instruction::SetInstructionEnabled { ix_disc: WITHDRAW, enabled }.data(),
+            accounts::SetInstructionEnabled {
+                kill_switch: self.kill_switch,
+                audit_log: self.audit_log,
+                admin: self.admin.pubkey(),
+            }
+            .to_account_metas(None),
+        );
+        send(&mut self.context, ix, Some(&self.admin)).await
+    }
+
+    async fn disabled(&mut self) -> Vec<[u8; 8]> {
+        let data = data(&mut self.context, self.kill_switch).await;
+        DisabledInstructions::try_deserialize(&mut data.as_slice()).unwrap().disabled
+    }
+
+    async fn overrides_recorded(&mut self) -> u64 {
+        let data = data(&mut self.context, self.audit_log).await;
+        AdminAuditLog::try_deserialize(&mut data.as_slice()).unwrap().override_count
+    }
+}
+
+const WITHDRAW: [u8; 8] = reinitialization::instruction::Withdraw::DISCRIMINATOR;
+
+#[tokio::test]
+async fn pausing_twice_disables_the_instruction_once() {
+    let mut paused = kill_switch_with(Vec::new()).await;
+
+    paused.set_withdraw_enabled(false).await.expect("first pause");
+    paused.set_withdraw_enabled(false).await.expect("already paused");
+
+    assert_eq!(paused.disabled().await, vec![WITHDRAW]);
+    // The state is idempotent; the audit trail deliberately is not
+    assert_eq!(paused.overrides_recorded().await, 2);
+}
+
+#[tokio::test]
+async fn unpausing_twice_leaves_the_instruction_enabled() {
+    let mut paused = kill_switch_with(vec![WITHDRAW]).await;
+
+    paused.set_withdraw_enabled(true).await.expect("first unpause");
+    paused.set_withdraw_enabled(true).await.expect("already enabled");
+
+    assert!(paused.disabled().await.is_empty());
+}
+
+#[tokio::test]
+async fn re_pausing_with_a_full_kill_switch_is_not_an_error() {
+    // Already paused, so there's nothing to add even with no room left
+    let mut full: Vec<[u8; 8]> = (1..DisabledInstructions::MAX_DISABLED as u8).map(|n| [n; 8]).collect();
+    full.push(WITHDRAW);
+    let mut paused = kill_switch_with(full.clone()).await;
+
+    paused.set_withdraw_enabled(false).await.expect("already paused");
+
+    assert_eq!(paused.disabled().await, full);
+}
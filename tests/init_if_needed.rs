@@ -0,0 +1,108 @@
+//! `init_if_needed` in the reinitialization program.
+//!
+//! `deposit_or_init_*` create the owner's vault on the first deposit and
+//! load it on every later one. The handler runs both times, so the naive
+//! version re-initializes the vault on each deposit; the secure one only
+//! writes the initial values when the vault is new.
+
+use anchor_lang::{AccountDeserialize, InstructionData, ToAccountMetas};
+use reinitialization::{accounts, instruction, DisabledInstructions, VaultSecure, ID};
+use security_tests::{anchor_account, pda, program_test, send, system_account};
+use solana_program_test::BanksClient;
+use solana_sdk::{
+    instruction::Instruction,
+    native_token::LAMPORTS_PER_SOL,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    system_program,
+    transaction::TransactionError,
+};
+
+/// A bank with an empty kill switch, and the owner whose vault is used.
+struct Fixture {
+    banks: BanksClient,
+    payer: Keypair,
+    attacker: Keypair,
+    owner: Pubkey,
+}
+
+async fn fixture() -> Fixture {
+    let attacker = Keypair::new();
+    let (kill_switch, bump) = Pubkey::find_program_address(&[b"disabled_instructions"], &ID);
+    let state = DisabledInstructions { admin: Pubkey::new_unique(), disabled: vec![], bump };
+
+    let mut test = program_test();
+    test.add_account(kill_switch, anchor_account(&state, ID));
+    test.add_account(attacker.pubkey(), system_account(LAMPORTS_PER_SOL));
+    let (banks, payer, _) = test.start().await;
+    Fixture { banks, owner: payer.pubkey(), payer, attacker }
+}
+
+impl Fixture {
+    fn vault(&self) -> Pubkey {
+        pda(&[b"vault", self.owner.as_ref()], &ID)
+    }
+
+    async fn send(&mut self, ix: Instruction, signer: &Keypair) -> Result<(), TransactionError> {
+        send(&mut self.banks, &[ix], &[signer]).await
+    }
+
+    fn deposit_ix(&self, depositor: Pubkey, data: impl InstructionData) -> Instruction {
+        let accounts = accounts::DepositOrInit {
+            vault: self.vault(),
+            owner: self.owner,
+            kill_switch: pda(&[b"disabled_instructions"], &ID),
+            depositor,
+            system_program: system_program::ID,
+        };
+        Instruction::new_with_bytes(ID, &data.data(), accounts.to_account_metas(None))
+    }
+
+    async fn load_vault(&mut self) -> VaultSecure {
+        let account = self.banks.get_account(self.vault()).await.unwrap().unwrap();
+        VaultSecure::try_deserialize(&mut account.data.as_slice()).unwrap()
+    }
+}
+
+#[tokio::test]
+async fn the_first_deposit_creates_the_vault() {
+    let mut fixture = fixture().await;
+    let payer = fixture.payer.insecure_clone();
+    let ix = fixture.deposit_ix(payer.pubkey(), instruction::DepositOrInitSecure { amount: 1_000 });
+
+    fixture.send(ix, &payer).await.expect("init_if_needed creates it");
+
+    let vault = fixture.load_vault().await;
+    let (_, bump) = Pubkey::find_program_address(&[b"vault", fixture.owner.as_ref()], &ID);
+    assert_eq!((vault.authority, vault.balance, vault.total_deposits, vault.bump), (fixture.owner, 1_000, 1_000, bump));
+}
+
+#[tokio::test]
+async fn the_secure_version_keeps_the_balance_on_the_second_call() {
+    let mut fixture = fixture().await;
+    let (payer, attacker) = (fixture.payer.insecure_clone(), fixture.attacker.insecure_clone());
+
+    let first = fixture.deposit_ix(payer.pubkey(), instruction::DepositOrInitSecure { amount: 1_000 });
+    fixture.send(first, &payer).await.expect("creates the vault");
+    let second = fixture.deposit_ix(attacker.pubkey(), instruction::DepositOrInitSecure { amount: 1 });
+    fixture.send(second, &attacker).await.expect("loads the vault");
+
+    let vault = fixture.load_vault().await;
+    assert_eq!(vault.authority, fixture.owner);
+    assert_eq!((vault.balance, vault.total_deposits), (1_001, 1_001));
+}
+
+#[tokio::test]
+async fn the_naive_version_clobbers_the_balance_on_the_second_call() {
+    let mut fixture = fixture().await;
+    let (payer, attacker) = (fixture.payer.insecure_clone(), fixture.attacker.insecure_clone());
+
+    let first = fixture.deposit_ix(payer.pubkey(), instruction::DepositOrInitVulnerable { amount: 1_000 });
+    fixture.send(first, &payer).await.expect("creates the vault");
+    // Anyone may deposit, and every deposit re-initializes
+    let second = fixture.deposit_ix(attacker.pubkey(), instruction::DepositOrInitVulnerable { amount: 1 });
+    fixture.send(second, &attacker).await.expect("loads the vault");
+
+    let vault = fixture.load_vault().await;
+    assert_eq!((vault.balance, vault.total_deposits), (1, 1), "the first 1,000 is gone");
+}
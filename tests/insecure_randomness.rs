@@ -0,0 +1,157 @@
+//! Lottery draws in the insecure-randomness program.
+//!
+//! `pick_winner_vulnerable` draws from the clock, so its result is known
+//! before the transaction is sent. `pick_winner_secure` draws from a seed
+//! the lottery committed to up front and rejects any other reveal. The draw
+//! depends on the entrant count, so the seed may only be revealed once
+//! `close_entries` has fixed it, and nobody can enter after that.
+
+use anchor_lang::{InstructionData, ToAccountMetas};
+use insecure_randomness::{accounts, clock_index, commitment_of, draw_index, instruction, Lottery, RandomnessError, ID};
+use security_tests::{custom_error, load, pda, program_test, send, system_account};
+use solana_program_test::ProgramTestContext;
+use solana_sdk::{
+    clock::Clock,
+    instruction::Instruction,
+    native_token::LAMPORTS_PER_SOL,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    system_program, sysvar,
+    transaction::TransactionError,
+};
+
+const SEED: [u8; 32] = [0x5e; 32];
+const ENTRANTS: usize = 3;
+
+fn ix(accounts: impl ToAccountMetas, data: impl InstructionData) -> Instruction {
+    Instruction::new_with_bytes(ID, &data.data(), accounts.to_account_metas(None))
+}
+
+/// A lottery committed to `SEED` with `ENTRANTS` entries, still open; the
+/// last entrant is the attacker.
+struct Fixture {
+    context: ProgramTestContext,
+    authority: Keypair,
+    entrants: Vec<Keypair>,
+    lottery: Pubkey,
+}
+
+async fn lottery() -> Fixture {
+    let authority = Keypair::new();
+    let entrants: Vec<Keypair> = (0..ENTRANTS).map(|_| Keypair::new()).collect();
+    let mut test = program_test();
+    test.add_account(authority.pubkey(), system_account(LAMPORTS_PER_SOL));
+    for entrant in &entrants {
+        test.add_account(entrant.pubkey(), system_account(LAMPORTS_PER_SOL));
+    }
+    let mut context = test.start_with_context().await;
+
+    let lottery = pda(&[b"lottery", authority.pubkey().as_ref()], &ID);
+    let open = ix(
+        accounts::InitializeLottery { lottery, authority: authority.pubkey(), system_program: system_program::ID },
+        instruction::InitializeLottery { commitment: commitment_of(&SEED) },
+    );
+    send(&mut context.banks_client, &[open], &[&authority]).await.expect("lottery opened");
+    for entrant in &entrants {
+        let enter = ix(accounts::Enter { lottery, entrant: entrant.pubkey() }, instruction::Enter {});
+        send(&mut context.banks_client, &[enter], &[entrant]).await.expect("entered");
+    }
+    Fixture { context, authority, entrants, lottery }
+}
+
+/// `lottery`, with entries closed by its authority.
+async fn closed() -> Fixture {
+    let mut fixture = lottery().await;
+    let authority = fixture.authority.insecure_clone();
+    let close = ix(
+        accounts::CloseEntries { lottery: fixture.lottery, authority: authority.pubkey() },
+        instruction::CloseEntries {},
+    );
+    send(&mut fixture.context.banks_client, &[close], &[&authority]).await.expect("entries closed");
+    fixture
+}
+
+impl Fixture {
+    fn attacker(&self) -> &Keypair {
+        &self.entrants[ENTRANTS - 1]
+    }
+
+    async fn winner(&mut self) -> Option<Pubkey> {
+        load::<Lottery>(&mut self.context.banks_client, self.lottery).await.winner
+    }
+
+    /// The attacker reveals `seed`.
+    async fn reveal(&mut self, seed: [u8; 32]) -> Result<(), TransactionError> {
+        let attacker = self.attacker().insecure_clone();
+        let reveal = ix(
+            accounts::PickWinnerSecure { lottery: self.lottery, caller: attacker.pubkey() },
+            instruction::PickWinnerSecure { seed },
+        );
+        send(&mut self.context.banks_client, &[reveal], &[&attacker]).await
+    }
+}
+
+#[tokio::test]
+async fn clock_draws_are_known_before_they_are_made() {
+    let mut fixture = lottery().await;
+    let clock: Clock = fixture.context.banks_client.get_sysvar().await.unwrap();
+
+    // A block producer nudges the timestamp until the draw lands on them
+    let unix_timestamp = (clock.unix_timestamp..)
+        .find(|ts| clock_index(clock.slot, *ts, ENTRANTS) == ENTRANTS - 1)
+        .unwrap();
+    fixture.context.set_sysvar(&Clock { unix_timestamp, ..clock });
+
+    let attacker = fixture.attacker().pubkey();
+    let pick = ix(
+        accounts::PickWinnerVulnerable { lottery: fixture.lottery, caller: attacker, clock: sysvar::clock::ID },
+        instruction::PickWinnerVulnerable {},
+    );
+    send(&mut fixture.context.banks_client, &[pick], &[&fixture.entrants[ENTRANTS - 1]]).await.expect("pick lands");
+
+    assert_eq!(fixture.winner().await, Some(attacker));
+}
+
+#[tokio::test]
+async fn the_committed_seed_draws_the_winner() {
+    let mut fixture = closed().await;
+
+    fixture.reveal(SEED).await.expect("valid reveal");
+
+    let index = draw_index(&SEED, &fixture.lottery, ENTRANTS);
+    assert_eq!(fixture.winner().await, Some(fixture.entrants[index].pubkey()));
+}
+
+#[tokio::test]
+async fn a_mismatched_reveal_is_rejected() {
+    let mut fixture = closed().await;
+
+    let err = fixture.reveal([0u8; 32]).await.expect_err("seed does not match the commitment");
+
+    assert_eq!(err, custom_error(0, RandomnessError::CommitmentMismatch));
+    assert_eq!(fixture.winner().await, None);
+}
+
+#[tokio::test]
+async fn the_seed_cannot_be_revealed_while_entries_are_open() {
+    let mut fixture = lottery().await;
+
+    let err = fixture.reveal(SEED).await.expect_err("entries still open");
+
+    assert_eq!(err, custom_error(0, RandomnessError::EntriesOpen));
+    assert_eq!(fixture.winner().await, None);
+}
+
+#[tokio::test]
+async fn nobody_can_enter_once_entries_are_closed() {
+    let mut fixture = closed().await;
+    let late = Keypair::new();
+    let enter = ix(accounts::Enter { lottery: fixture.lottery, entrant: late.pubkey() }, instruction::Enter {});
+    let payer = fixture.context.payer.insecure_clone();
+
+    let err = send(&mut fixture.context.banks_client, &[enter], &[&payer, &late]).await.expect_err("entries closed");
+
+    assert_eq!(err, custom_error(0, RandomnessError::EntriesClosed));
+    let lottery = load::<Lottery>(&mut fixture.context.banks_client, fixture.lottery).await;
+    assert_eq!(lottery.entrants.len(), ENTRANTS);
+}
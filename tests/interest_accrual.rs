@@ -0,0 +1,119 @@
+//! Per-second interest accrual in the integer-overflow program.
+//!
+//! `principal * rate_per_second * elapsed` is formed in u128, so a long gap
+//! between accruals overflows nothing as long as the interest itself fits.
+
+use anchor_lang::{AccountDeserialize, InstructionData, ToAccountMetas};
+use integer_overflow::{accounts, instruction, InterestAccount, ID, INTEREST_SCALE};
+use security_tests::{anchor_account, program_test};
+use solana_sdk::{
+    clock::Clock,
+    instruction::Instruction,
+    pubkey::Pubkey,
+    signature::Signer,
+    transaction::Transaction,
+};
+
+/// 100 years, in seconds
+const CENTURY: i64 = 100 * 365 * 24 * 60 * 60;
+
+struct Accrual {
+    /// Interest reported through return data
+    returned: u64,
+    /// `accrued_interest` stored afterwards
+    stored: u64,
+}
+
+/// Accrues on an account last touched at t = 0, with the clock at `elapsed`.
+async fn accrue(principal: u64, rate_per_second: u64, elapsed: i64) -> Accrual {
+    let owner = Pubkey::new_unique();
+    let (interest_account, bump) = Pubkey::find_program_address(&[b"interest", owner.as_ref()], &ID);
+    let state = InterestAccount {
+        owner,
+        principal,
+        rate_per_second,
+        accrued_interest: 0,
+        last_accrued_at: 0,
+        bump,
+    };
+
+    let mut test = program_test();
+    test.add_account(interest_account, anchor_account(&state, ID));
+    let mut context = test.start_with_context().await;
+    let clock: Clock = context.banks_client.get_sysvar().await.unwrap();
+    context.set_sysvar(&Clock { unix_timestamp: elapsed, ..clock });
+
+    let ix = Instruction::new_with_bytes(
+        ID,
+        &instruction::AccrueInterest {}.data(),
+        accounts::AccrueInterest { interest_account }.to_account_metas(None),
+    );
+    let tx = Transaction::new_signed_with_payer(
+        &[ix],
+        Some(&context.payer.pubkey()),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+    let result = context.banks_client.process_transaction_with_metadata(tx).await.unwrap();
+    result.result.expect("accrual succeeds");
+
+    // The runtime strips trailing zero bytes from return data
+    let data = result
+        .metadata
+        .and_then(|metadata| metadata.return_data)
+        .map(|return_data| return_data.data)
+        .unwrap_or_default();
+    let mut bytes = [0u8; 8];
+    bytes[..data.len()].copy_from_slice(&data);
+
+    let account = context.banks_client.get_account(interest_account).await.unwrap().unwrap();
+    let stored = InterestAccount::try_deserialize(&mut account.data.as_slice()).unwrap();
+    assert_eq!(stored.last_accrued_at, elapsed);
+
+    Accrual { returned: u64::from_le_bytes(bytes), stored: stored.accrued_interest }
+}
+
+#[tokio::test]
+async fn accrues_interest_per_second() {
+    // 1_000_000_000 at 0.0001% per second for 1_000s = 1_000_000
+    let accrual = accrue(1_000_000_000, INTEREST_SCALE / 1_000_000, 1_000).await;
+
+    assert_eq!(accrual.returned, 1_000_000);
+    assert_eq!(accrual.stored, 1_000_000);
+}
+
+#[tokio::test]
+async fn long_gap_stays_within_bounds() {
+    let principal: u64 = 1_000_000_000_000_000_000;
+    let rate_per_second: u64 = 1_000;
+    // principal * rate alone is already past u64::MAX
+    assert!(principal.checked_mul(rate_per_second).is_none());
+
+    let accrual = accrue(principal, rate_per_second, CENTURY).await;
+
+    let expected = (principal as u128 * rate_per_second as u128 * CENTURY as u128
+        / INTEREST_SCALE as u128) as u64;
+    assert_eq!(accrual.returned, expected);
+    assert_eq!(accrual.stored, expected);
+}
+
+#[tokio::test]
+async fn rate_times_a_long_gap_past_u64_still_accrues() {
+    // 100% per second for a century: rate * elapsed alone is past u64::MAX,
+    // but with a principal of 1 the interest is just the gap in seconds
+    let rate_per_second = INTEREST_SCALE;
+    assert!(rate_per_second.checked_mul(CENTURY as u64).is_none());
+
+    let accrual = accrue(1, rate_per_second, CENTURY).await;
+
+    assert_eq!(accrual.returned, CENTURY as u64);
+    assert_eq!(accrual.stored, CENTURY as u64);
+}
+
+#[tokio::test]
+async fn zero_principal_accrues_nothing() {
+    let accrual = accrue(0, INTEREST_SCALE / 1_000_000, CENTURY).await;
+
+    assert_eq!(accrual.returned, 0);
+    assert_eq!(accrual.stored, 0);
+}
@@ -0,0 +1,41 @@
+//! Lesson registry.
+//!
+//! `security_common::LessonId` is how tooling enumerates the lessons, so
+//! every program has to appear in it, and every registered lesson has to
+//! point at a real program.
+
+use std::collections::BTreeSet;
+
+use security_common::{describe_lesson, LessonId};
+use security_tests::account_types;
+
+fn programs() -> BTreeSet<&'static str> {
+    account_types().iter().map(|account| account.program).collect()
+}
+
+#[test]
+fn every_program_has_a_lesson() {
+    let registered: BTreeSet<&str> = LessonId::ALL.iter().map(|lesson| lesson.program()).collect();
+    let missing: Vec<_> = programs().difference(&registered).copied().collect();
+    assert!(missing.is_empty(), "no LessonId for {:?}", missing);
+}
+
+#[test]
+fn every_lesson_names_a_program() {
+    let programs = programs();
+    for lesson in LessonId::ALL {
+        assert!(programs.contains(lesson.program()), "{:?} names unknown program {}", lesson, lesson.program());
+    }
+}
+
+#[test]
+fn descriptions_are_unique_slugged_lines() {
+    let mut slugs = BTreeSet::new();
+    for lesson in LessonId::ALL {
+        let description = describe_lesson(*lesson);
+        let (slug, summary) = description.split_once(": ").expect("`<slug>: <summary>`");
+        assert!(slug.chars().all(|c| c.is_ascii_lowercase() || c == '-'), "bad slug {:?}", slug);
+        assert!(!summary.is_empty() && !summary.contains('\n'), "bad summary for {:?}", lesson);
+        assert!(slugs.insert(slug), "duplicate slug {:?}", slug);
+    }
+}
@@ -0,0 +1,426 @@
+//! # Security Pattern Test Harness
+//!
+//! Shared helpers for the integration tests and audit tooling in this
+//! directory. Every example program is linked as a library (with the
+//! `no-entrypoint` feature) so tests can reference its account types,
+//! instruction builders and program IDs directly.
+
+use anchor_lang::prelude::{AccountInfo, Pubkey};
+use anchor_lang::solana_program::bpf_loader_upgradeable::{self, UpgradeableLoaderState};
+use anchor_lang::solana_program::entrypoint::ProgramResult;
+use anchor_lang::solana_program::hash::hash;
+use anchor_lang::solana_program::program_pack::Pack;
+use anchor_lang::{AccountDeserialize, AccountSerialize, Discriminator};
+use anchor_spl::token::spl_token;
+use solana_program_test::{processor, BanksClient, ProgramTest, ProgramTestContext};
+use solana_sdk::{
+    account::Account,
+    clock::Clock,
+    ed25519_instruction::new_ed25519_instruction,
+    instruction::{Instruction, InstructionError},
+    rent::Rent,
+    signature::{Keypair, Signer},
+    system_program,
+    transaction::{Transaction, TransactionError},
+};
+
+// ============================================================================
+// ACCOUNT DISCRIMINATORS
+// ============================================================================
+
+/// An `#[account]` type and the 8-byte discriminator Anchor generated for it.
+#[derive(Debug, Clone, Copy)]
+pub struct AccountType {
+    /// Crate the type is defined in
+    pub program: &'static str,
+    /// Struct name, as hashed by Anchor
+    pub name: &'static str,
+    /// `T::DISCRIMINATOR` as compiled into the program
+    pub discriminator: [u8; 8],
+}
+
+macro_rules! account_types {
+    ($($program:ident :: $name:ident),* $(,)?) => {
+        vec![$(
+            AccountType {
+                program: stringify!($program),
+                name: stringify!($name),
+                discriminator: <$program::$name as Discriminator>::DISCRIMINATOR,
+            }
+        ),*]
+    };
+}
+
+/// Every `#[account]` type in the workspace.
+///
+/// Add new account types here so the discriminator snapshot catches renames.
+pub fn account_types() -> Vec<AccountType> {
+    account_types![
+        missing_signer_check::Vault,
+        missing_signer_check::MultisigVault,
+        missing_signer_check::Delegate,
+        account_validation::Pool,
+        account_validation::PoolVault,
+        account_validation::Config,
+        account_validation::Org,
+        account_validation::Team,
+        account_validation::Member,
+        account_validation::UserDeposit,
+        account_validation::UserAccount,
+        integer_overflow::Vault,
+        integer_overflow::UserAccount,
+        integer_overflow::Config,
+        integer_overflow::WithdrawalRecord,
+        integer_overflow::InterestAccount,
+        integer_overflow::LpPool,
+        integer_overflow::LpPosition,
+        integer_overflow::ReferralAccount,
+        integer_overflow::FeePosition,
+        integer_overflow::RewardMinter,
+        arbitrary_cpi::Vault,
+        arbitrary_cpi::Treasury,
+        arbitrary_cpi::SwapCommitment,
+        reinitialization::VaultVulnerable,
+        reinitialization::ConfigVulnerable,
+        reinitialization::VaultSecure,
+        reinitialization::ConfigSecure,
+        reinitialization::DisabledInstructions,
+        reinitialization::AdminAuditLog,
+        reinitialization::VaultTombstone,
+        reinitialization::VaultCloses,
+        reinitialization::VaultV2,
+        type_cosplay::AdminConfig,
+        type_cosplay::UserAccount,
+        type_cosplay::RewardVault,
+        type_cosplay::StakeAccount,
+        type_cosplay::TaggedAccount,
+        closing_accounts::UserAccount,
+        closing_accounts::Config,
+        closing_accounts::UserProfile,
+        closing_accounts::ProfileTombstone,
+        closing_accounts::AccountTombstone,
+        closing_accounts::ClosesByRecipient,
+        closing_accounts::UserCloses,
+        escrow::Escrow,
+        insecure_randomness::Lottery,
+        native_sol_vault::SolVault,
+        upgrade_authority::ProgramConfig,
+        flash_loan_guard::LendingPool,
+        flash_loan_guard::Position,
+    ]
+}
+
+/// Recomputes a discriminator the way Anchor does:
+/// `sha256("account:<Name>")[0..8]`.
+pub fn compute_discriminator(name: &str) -> [u8; 8] {
+    let digest = hash(format!("account:{}", name).as_bytes()).to_bytes();
+    let mut discriminator = [0u8; 8];
+    discriminator.copy_from_slice(&digest[..8]);
+    discriminator
+}
+
+/// Lowercase hex encoding, matching how explorers display raw account bytes.
+pub fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+// ============================================================================
+// PROGRAM TEST HARNESS
+// ============================================================================
+
+/// Adapts an Anchor `entry` to `processor!`.
+///
+/// Anchor 0.30 ties the account slice to the `AccountInfo` lifetime, which
+/// the native runtime can't supply, so the slice is leaked for the rest of
+/// the test process.
+macro_rules! anchor_processor {
+    ($program:ident) => {{
+        fn entry<'a, 'b, 'c, 'info>(
+            program_id: &'a Pubkey,
+            accounts: &'b [AccountInfo<'info>],
+            data: &'c [u8],
+        ) -> ProgramResult {
+            let accounts: &'info [AccountInfo<'info>] =
+                Box::leak(accounts.to_vec().into_boxed_slice());
+            $program::entry(program_id, accounts, data)
+        }
+        processor!(entry)
+    }};
+}
+
+/// A `ProgramTest` with every program in the workspace loaded natively.
+///
+/// The SPL Token program is part of the default genesis.
+pub fn program_test() -> ProgramTest {
+    let mut test = ProgramTest::default();
+    test.prefer_bpf(false);
+    test.add_program("missing_signer_check", missing_signer_check::ID, anchor_processor!(missing_signer_check));
+    test.add_program("account_validation", account_validation::ID, anchor_processor!(account_validation));
+    test.add_program("integer_overflow", integer_overflow::ID, anchor_processor!(integer_overflow));
+    test.add_program("arbitrary_cpi", arbitrary_cpi::ID, anchor_processor!(arbitrary_cpi));
+    test.add_program("reinitialization", reinitialization::ID, anchor_processor!(reinitialization));
+    test.add_program("type_cosplay", type_cosplay::ID, anchor_processor!(type_cosplay));
+    test.add_program("closing_accounts", closing_accounts::ID, anchor_processor!(closing_accounts));
+    test.add_program("escrow", escrow::ID, anchor_processor!(escrow));
+    test.add_program("insecure_randomness", insecure_randomness::ID, anchor_processor!(insecure_randomness));
+    test.add_program("native_sol_vault", native_sol_vault::ID, anchor_processor!(native_sol_vault));
+    test.add_program("upgrade_authority", upgrade_authority::ID, anchor_processor!(upgrade_authority));
+    test.add_program("flash_loan_guard", flash_loan_guard::ID, anchor_processor!(flash_loan_guard));
+    test.add_program("malicious_reward_program", malicious_reward_program::ID, anchor_processor!(malicious_reward_program));
+    test.add_program("fake_token_program", fake_token_program::ID, processor!(fake_token_program::process_instruction));
+    test.add_program("mock_reward", mock_reward::ID, processor!(mock_reward::process_instruction));
+    test.add_program("mock_swap", mock_swap::ID, processor!(mock_swap::process_instruction));
+    test.add_program("impostor_swap", mock_swap::IMPOSTOR_ID, processor!(mock_swap::process_instruction));
+    test
+}
+
+/// Stand-in for the reward program `arbitrary_cpi::claim_via_cpi` calls.
+///
+/// `distribute` returns, as a little-endian `u64`, whatever the first
+/// account's data holds - set it with `mock_reward::state(amount)` - and
+/// zeroes it, as a reward program marks a reward paid.
+pub mod mock_reward {
+    use super::*;
+    use anchor_lang::solana_program::program::set_return_data;
+    use anchor_lang::solana_program::program_error::ProgramError;
+
+    pub const ID: Pubkey = arbitrary_cpi::REWARD_PROGRAM_ID;
+
+    pub fn process_instruction(_program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+        if data != arbitrary_cpi::DISTRIBUTE_DISCRIMINATOR {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+        let state = accounts.first().ok_or(ProgramError::NotEnoughAccountKeys)?;
+        let mut data = state.try_borrow_mut_data()?;
+        set_return_data(&data);
+        data.fill(0);
+        Ok(())
+    }
+
+    /// Reward state reporting `amount` for every user.
+    pub fn state(amount: u64) -> Account {
+        raw_account(amount.to_le_bytes().to_vec(), ID)
+    }
+}
+
+/// Stand-in for the swap program `arbitrary_cpi::swap_secure` routes through.
+///
+/// `swap` echoes the amount back as return data, so a test can tell which
+/// program answered. The same code is also deployed at `IMPOSTOR_ID`, an
+/// address the attacker controls.
+pub mod mock_swap {
+    use super::*;
+    use anchor_lang::solana_program::program::set_return_data;
+    use anchor_lang::solana_program::program_error::ProgramError;
+
+    pub const ID: Pubkey = arbitrary_cpi::EXPECTED_SWAP_PROGRAM;
+    pub const IMPOSTOR_ID: Pubkey = Pubkey::new_from_array([0x5a; 32]);
+
+    pub fn process_instruction(_program_id: &Pubkey, _accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+        match data.split_at_checked(8) {
+            Some((discriminator, amount)) if discriminator == arbitrary_cpi::SWAP_DISCRIMINATOR && amount.len() == 8 => {
+                set_return_data(amount);
+                Ok(())
+            }
+            _ => Err(ProgramError::InvalidInstructionData),
+        }
+    }
+}
+
+/// A rent-exempt account holding `data` verbatim.
+///
+/// Use this for forged layouts; `anchor_account` for genuine state.
+pub fn raw_account(data: Vec<u8>, owner: Pubkey) -> Account {
+    Account {
+        lamports: Rent::default().minimum_balance(data.len()),
+        data,
+        owner,
+        executable: false,
+        rent_epoch: 0,
+    }
+}
+
+/// A rent-exempt account holding `value` exactly as Anchor would store it,
+/// discriminator included.
+pub fn anchor_account<T: AccountSerialize>(value: &T, owner: Pubkey) -> Account {
+    let mut data = Vec::new();
+    value.try_serialize(&mut data).expect("account serializes");
+    raw_account(data, owner)
+}
+
+/// A plain wallet.
+pub fn system_account(lamports: u64) -> Account {
+    Account::new(lamports, 0, &system_program::ID)
+}
+
+/// An initialized SPL mint with `authority` as its mint authority.
+pub fn mint_account(authority: Pubkey, decimals: u8) -> Account {
+    let mint = spl_token::state::Mint {
+        mint_authority: Some(authority).into(),
+        supply: 0,
+        decimals,
+        is_initialized: true,
+        freeze_authority: None.into(),
+    };
+    let mut data = vec![0u8; spl_token::state::Mint::LEN];
+    mint.pack_into_slice(&mut data);
+    raw_account(data, spl_token::ID)
+}
+
+/// An initialized SPL token account of `mint` owned by `authority`.
+pub fn token_account(mint: Pubkey, authority: Pubkey, amount: u64) -> Account {
+    let account = spl_token::state::Account {
+        mint,
+        owner: authority,
+        amount,
+        state: spl_token::state::AccountState::Initialized,
+        ..Default::default()
+    };
+    let mut data = vec![0u8; spl_token::state::Account::LEN];
+    account.pack_into_slice(&mut data);
+    raw_account(data, spl_token::ID)
+}
+
+/// `token_account`, with `delegate` approved to move all of `amount`.
+pub fn delegated_token_account(mint: Pubkey, authority: Pubkey, amount: u64, delegate: Pubkey) -> Account {
+    let account = spl_token::state::Account {
+        mint,
+        owner: authority,
+        amount,
+        delegate: Some(delegate).into(),
+        delegated_amount: amount,
+        state: spl_token::state::AccountState::Initialized,
+        ..Default::default()
+    };
+    let mut data = vec![0u8; spl_token::state::Account::LEN];
+    account.pack_into_slice(&mut data);
+    raw_account(data, spl_token::ID)
+}
+
+/// A ProgramData account, as the upgradeable loader keeps it, recording
+/// `upgrade_authority` (`None` once the program is immutable).
+///
+/// Lives at `pda(&[program_id.as_ref()], &bpf_loader_upgradeable::ID)`.
+pub fn program_data_account(upgrade_authority: Option<Pubkey>) -> Account {
+    let state = UpgradeableLoaderState::ProgramData { slot: 0, upgrade_authority_address: upgrade_authority };
+    let data = bincode::serialize(&state).expect("program data serializes");
+    raw_account(data, bpf_loader_upgradeable::ID)
+}
+
+/// The executable account the upgradeable loader keeps at `program_id`,
+/// pointing at its ProgramData.
+///
+/// Enough for checks on the program account; there is no code to run.
+pub fn upgradeable_program_account(program_id: &Pubkey) -> Account {
+    let programdata_address = pda(&[program_id.as_ref()], &bpf_loader_upgradeable::ID);
+    let state = UpgradeableLoaderState::Program { programdata_address };
+    let data = bincode::serialize(&state).expect("program account serializes");
+    Account { executable: true, ..raw_account(data, bpf_loader_upgradeable::ID) }
+}
+
+/// An Ed25519 program instruction proving `signer` signed `message`, and
+/// the signature it checks.
+///
+/// Put it in the same transaction as the instruction that looks for it in
+/// the instructions sysvar.
+pub fn ed25519_instruction(signer: &Keypair, message: &[u8]) -> (Instruction, [u8; 64]) {
+    let keypair = ed25519_dalek::Keypair::from_bytes(&signer.to_bytes()).expect("valid ed25519 keypair");
+    let signature = signer.sign_message(message).into();
+    (new_ed25519_instruction(&keypair, message), signature)
+}
+
+/// `find_program_address` for call sites that only need the address.
+pub fn pda(seeds: &[&[u8]], program_id: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(seeds, program_id).0
+}
+
+// ============================================================================
+// TRANSACTIONS
+// ============================================================================
+
+/// Sends `instructions` as one transaction against a fresh blockhash. The
+/// first signer pays.
+///
+/// Transport failures panic; the transaction's own error is returned.
+pub async fn send(banks: &mut BanksClient, instructions: &[Instruction], signers: &[&Keypair]) -> Result<(), TransactionError> {
+    let tx = signed(banks, instructions, signers).await;
+    banks.process_transaction(tx).await.map_err(|err| err.unwrap())
+}
+
+/// `send`, returning the `u64` the last instruction set as return data.
+///
+/// The runtime strips trailing zero bytes from return data, so they are
+/// put back here; no return data at all reads as 0.
+pub async fn send_for_u64(
+    banks: &mut BanksClient,
+    instructions: &[Instruction],
+    signers: &[&Keypair],
+) -> Result<u64, TransactionError> {
+    let tx = signed(banks, instructions, signers).await;
+    let result = banks.process_transaction_with_metadata(tx).await.unwrap();
+    result.result?;
+
+    let data = result
+        .metadata
+        .and_then(|metadata| metadata.return_data)
+        .map(|return_data| return_data.data)
+        .unwrap_or_default();
+    let mut bytes = [0u8; 8];
+    bytes[..data.len()].copy_from_slice(&data);
+    Ok(u64::from_le_bytes(bytes))
+}
+
+async fn signed(banks: &mut BanksClient, instructions: &[Instruction], signers: &[&Keypair]) -> Transaction {
+    let payer = signers.first().expect("a fee payer").pubkey();
+    let blockhash = banks.get_latest_blockhash().await.unwrap();
+    Transaction::new_signed_with_payer(instructions, Some(&payer), signers, blockhash)
+}
+
+/// Loads the account at `key` as `T`, discriminator checked.
+///
+/// Panics if the account doesn't exist.
+pub async fn load<T: AccountDeserialize>(banks: &mut BanksClient, key: Pubkey) -> T {
+    let account = banks.get_account(key).await.unwrap().expect("account exists");
+    T::try_deserialize(&mut account.data.as_slice()).unwrap()
+}
+
+/// The error a transaction fails with when instruction `index` returns
+/// `code` - a program's error enum or Anchor's `ErrorCode`.
+pub fn custom_error(index: u8, code: impl Into<u32>) -> TransactionError {
+    TransactionError::InstructionError(index, InstructionError::Custom(code.into()))
+}
+
+/// Sets the clock to `unix_timestamp`, one slot after the current one.
+///
+/// Moving to a new slot first means a transaction sent after the jump never
+/// shares a slot - or a blockhash - with one sent before it, so it can't be
+/// rejected as a duplicate or held up behind the earlier transaction's
+/// account locks.
+pub async fn set_time(context: &mut ProgramTestContext, unix_timestamp: i64) {
+    let clock: Clock = context.banks_client.get_sysvar().await.unwrap();
+    context.warp_to_slot(clock.slot + 1).unwrap();
+    let clock: Clock = context.banks_client.get_sysvar().await.unwrap();
+    context.set_sysvar(&Clock { unix_timestamp, ..clock });
+}
+
+/// PDA derivations for the missing-signer-check program, with the same
+/// seeds as its `seeds` constraints.
+pub mod missing_signer_pdas {
+    use super::*;
+    use missing_signer_check::ID;
+
+    /// `[b"vault", authority]`
+    pub fn vault(authority: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[b"vault", authority.as_ref()], &ID)
+    }
+
+    /// `[b"multisig", creator]`
+    pub fn multisig(creator: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[b"multisig", creator.as_ref()], &ID)
+    }
+
+    /// `[b"delegate", vault, delegate]`
+    pub fn delegate(vault: &Pubkey, delegate: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[b"delegate", vault.as_ref(), delegate.as_ref()], &ID)
+    }
+}
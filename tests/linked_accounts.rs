@@ -0,0 +1,161 @@
+//! The `Pool -> PoolVault -> Mint` chain of the account-validation program.
+//!
+//! `swap_secure_linked` follows every link: the pool's `vault`, the vault's
+//! `pool` and the vault's `mint`. `swap_unlinked` takes the three accounts
+//! as given, so the victim's pool can be credited with liquidity that went
+//! into the attacker's vault.
+
+use account_validation::{accounts, instruction, Pool, PoolVault, ValidationError, ID};
+use anchor_lang::{AccountDeserialize, InstructionData, ToAccountMetas};
+use security_tests::{custom_error, load, mint_account, pda, program_test, send, system_account};
+use solana_program_test::ProgramTestContext;
+use solana_sdk::{
+    instruction::Instruction,
+    native_token::LAMPORTS_PER_SOL,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    system_program,
+    transaction::TransactionError,
+};
+
+const AMOUNT: u64 = 1_000;
+
+fn ix(accounts: impl ToAccountMetas, data: impl InstructionData) -> Instruction {
+    Instruction::new_with_bytes(ID, &data.data(), accounts.to_account_metas(None))
+}
+
+/// A pool, its vault and the vault's mint.
+struct Chain {
+    pool: Pubkey,
+    vault: Pubkey,
+    mint: Pubkey,
+}
+
+/// A victim's and an attacker's pool, each with a vault of its own mint.
+struct Fixture {
+    context: ProgramTestContext,
+    attacker: Keypair,
+    victim: Chain,
+    attacker_chain: Chain,
+}
+
+async fn create_chain(context: &mut ProgramTestContext, owner: &Keypair) -> Chain {
+    let config = pda(&[b"config"], &ID);
+    let pool = pda(&[b"pool", owner.pubkey().as_ref()], &ID);
+    let vault = pda(&[b"pool_vault", pool.as_ref()], &ID);
+    let mint = Pubkey::new_unique();
+    context.set_account(&mint, &mint_account(owner.pubkey(), 6).into());
+    let setup = [
+        ix(
+            accounts::InitializePool { config, pool, authority: owner.pubkey(), system_program: system_program::ID },
+            instruction::InitializePool {
+                reward_rate: 10,
+                global_tvl_cap: u64::MAX,
+                lock_secs: 0,
+                min_hold_secs: 0,
+                early_exit_bps: 0,
+                deposit_mint: Pubkey::default(),
+                reward_mint: Pubkey::default(),
+            },
+        ),
+        ix(
+            accounts::InitializePoolVault { pool, pool_vault: vault, mint, authority: owner.pubkey(), system_program: system_program::ID },
+            instruction::InitializePoolVault {},
+        ),
+    ];
+    send(&mut context.banks_client, &setup, &[owner]).await.expect("setup");
+    Chain { pool, vault, mint }
+}
+
+async fn two_pools() -> Fixture {
+    let victim = Keypair::new();
+    let attacker = Keypair::new();
+    let mut test = program_test();
+    test.add_account(victim.pubkey(), system_account(LAMPORTS_PER_SOL));
+    test.add_account(attacker.pubkey(), system_account(LAMPORTS_PER_SOL));
+    let mut context = test.start_with_context().await;
+
+    let config = ix(
+        accounts::InitializeConfig { config: pda(&[b"config"], &ID), admin: victim.pubkey(), system_program: system_program::ID },
+        instruction::InitializeConfig { fee_bps: 0, max_positions: 1, dust_threshold: 0 },
+    );
+    send(&mut context.banks_client, &[config], &[&victim]).await.expect("config");
+    let victim_chain = create_chain(&mut context, &victim).await;
+    let attacker_chain = create_chain(&mut context, &attacker).await;
+    Fixture { context, attacker, victim: victim_chain, attacker_chain }
+}
+
+impl Fixture {
+    /// Swaps `AMOUNT` into the victim's pool, through `pool_vault` of `mint`.
+    async fn swap_linked(&mut self, pool_vault: Pubkey, expected_mint: Pubkey) -> Result<(), TransactionError> {
+        let swap = ix(
+            accounts::SwapSecureLinked { pool: self.victim.pool, pool_vault, expected_mint, user: self.attacker.pubkey() },
+            instruction::SwapSecureLinked { amount: AMOUNT },
+        );
+        let attacker = self.attacker.insecure_clone();
+        send(&mut self.context.banks_client, &[swap], &[&attacker]).await
+    }
+
+    async fn load<T: AccountDeserialize>(&mut self, key: Pubkey) -> T {
+        load(&mut self.context.banks_client, key).await
+    }
+}
+
+fn broken_link() -> TransactionError {
+    custom_error(0, ValidationError::BrokenAccountLink)
+}
+
+#[tokio::test]
+async fn the_linked_chain_is_credited() {
+    let mut fixture = two_pools().await;
+    let (vault, mint) = (fixture.victim.vault, fixture.victim.mint);
+
+    fixture.swap_linked(vault, mint).await.expect("pool, vault and mint all agree");
+
+    assert_eq!(fixture.load::<Pool>(fixture.victim.pool).await.total_deposited, AMOUNT);
+    assert_eq!(fixture.load::<PoolVault>(vault).await.reserve, AMOUNT);
+}
+
+#[tokio::test]
+async fn another_pools_vault_is_rejected() {
+    let mut fixture = two_pools().await;
+    let (vault, mint) = (fixture.attacker_chain.vault, fixture.attacker_chain.mint);
+
+    let err = fixture.swap_linked(vault, mint).await.expect_err("not the victim pool's vault");
+
+    assert_eq!(err, broken_link());
+    assert_eq!(fixture.load::<Pool>(fixture.victim.pool).await.total_deposited, 0);
+}
+
+#[tokio::test]
+async fn another_mint_is_rejected() {
+    let mut fixture = two_pools().await;
+    let (vault, mint) = (fixture.victim.vault, fixture.attacker_chain.mint);
+
+    let err = fixture.swap_linked(vault, mint).await.expect_err("not the vault's mint");
+
+    assert_eq!(err, broken_link());
+    assert_eq!(fixture.load::<PoolVault>(vault).await.reserve, 0);
+}
+
+#[tokio::test]
+async fn an_unlinked_swap_credits_the_victim_with_the_attackers_vault() {
+    let mut fixture = two_pools().await;
+    let swap = ix(
+        accounts::SwapUnlinked {
+            pool: fixture.victim.pool,
+            pool_vault: fixture.attacker_chain.vault,
+            expected_mint: fixture.attacker_chain.mint,
+            user: fixture.attacker.pubkey(),
+        },
+        instruction::SwapUnlinked { amount: AMOUNT },
+    );
+    let attacker = fixture.attacker.insecure_clone();
+
+    send(&mut fixture.context.banks_client, &[swap], &[&attacker]).await.expect("no link is checked");
+
+    // The victim's pool counts liquidity its own vault never received
+    assert_eq!(fixture.load::<Pool>(fixture.victim.pool).await.total_deposited, AMOUNT);
+    assert_eq!(fixture.load::<PoolVault>(fixture.victim.vault).await.reserve, 0);
+    assert_eq!(fixture.load::<PoolVault>(fixture.attacker_chain.vault).await.reserve, AMOUNT);
+}
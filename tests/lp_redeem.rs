@@ -0,0 +1,95 @@
+//! LP redemption in the integer-overflow program.
+//!
+//! `lp_amount * reserve / total_lp` is formed in u128 and rounded down, so
+//! redemptions never pay out more than the reserve holds, and the reserve
+//! paid out plus the reserve left always equals the reserve before.
+
+use anchor_lang::{InstructionData, ToAccountMetas};
+use integer_overflow::{accounts, instruction, LpPool, LpPosition, ID};
+use security_tests::{anchor_account, load, program_test, send_for_u64, system_account};
+use solana_program_test::ProgramTestContext;
+use solana_sdk::{
+    instruction::Instruction,
+    native_token::LAMPORTS_PER_SOL,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+};
+
+/// A pool whose whole LP supply sits in one owner's position.
+struct Fixture {
+    context: ProgramTestContext,
+    owner: Keypair,
+    pool: Pubkey,
+    position: Pubkey,
+}
+
+async fn pool_of(reserve: u64, total_lp: u64) -> Fixture {
+    let owner = Keypair::new();
+    let authority = Pubkey::new_unique();
+    let (pool, pool_bump) = Pubkey::find_program_address(&[b"lp_pool", authority.as_ref()], &ID);
+    let (position, position_bump) =
+        Pubkey::find_program_address(&[b"lp_position", pool.as_ref(), owner.pubkey().as_ref()], &ID);
+
+    let mut test = program_test();
+    test.add_account(owner.pubkey(), system_account(LAMPORTS_PER_SOL));
+    test.add_account(pool, anchor_account(&LpPool { authority, reserve, total_lp, bump: pool_bump }, ID));
+    test.add_account(
+        position,
+        anchor_account(&LpPosition { pool, owner: owner.pubkey(), lp_amount: total_lp, bump: position_bump }, ID),
+    );
+    let context = test.start_with_context().await;
+    Fixture { context, owner, pool, position }
+}
+
+impl Fixture {
+    /// Redeems `lp_amount` and returns the reserve paid out.
+    async fn redeem(&mut self, lp_amount: u64) -> u64 {
+        let ix = Instruction::new_with_bytes(
+            ID,
+            &instruction::Redeem { lp_amount }.data(),
+            accounts::Redeem { pool: self.pool, position: self.position, owner: self.owner.pubkey() }
+                .to_account_metas(None),
+        );
+        send_for_u64(&mut self.context.banks_client, &[ix], &[&self.owner]).await.expect("redemption succeeds")
+    }
+
+    async fn pool(&mut self) -> LpPool {
+        load(&mut self.context.banks_client, self.pool).await
+    }
+
+    async fn lp_held(&mut self) -> u64 {
+        load::<LpPosition>(&mut self.context.banks_client, self.position).await.lp_amount
+    }
+}
+
+#[tokio::test]
+async fn redeeming_the_whole_supply_returns_the_whole_reserve() {
+    // lp_amount * reserve is far past u64::MAX
+    let reserve = u64::MAX / 3;
+    let total_lp = 1 << 40;
+    let mut fixture = pool_of(reserve, total_lp).await;
+
+    let owed = fixture.redeem(total_lp).await;
+
+    assert_eq!(owed, reserve);
+    let pool = fixture.pool().await;
+    assert_eq!((pool.reserve, pool.total_lp), (0, 0));
+    assert_eq!(fixture.lp_held().await, 0);
+}
+
+#[tokio::test]
+async fn dust_redemptions_round_down_and_conserve_the_reserve() {
+    let reserve = 999;
+    let total_lp = 1_000;
+    let mut fixture = pool_of(reserve, total_lp).await;
+
+    // 1 * 999 / 1000 rounds to nothing; the burn still happens
+    let dust = fixture.redeem(1).await;
+    assert_eq!(dust, 0);
+    let pool = fixture.pool().await;
+    assert_eq!((pool.reserve, pool.total_lp), (reserve, total_lp - 1));
+
+    let rest = fixture.redeem(total_lp - 1).await;
+    assert_eq!(dust + rest, reserve);
+    assert_eq!(fixture.pool().await.reserve, 0);
+}
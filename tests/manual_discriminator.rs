@@ -0,0 +1,78 @@
+//! The manual initialization check in the reinitialization program.
+//!
+//! `process_vault_manual_secure` takes a raw account and checks what
+//! `Account<VaultSecure>` would: this program owns it, and its first 8 bytes
+//! are `VaultSecure::DISCRIMINATOR`. All-zero bytes there mean "allocated,
+//! never initialized" - unlike `data[0]`, which is just the first byte of
+//! whatever was written.
+
+use anchor_lang::error::ErrorCode as AnchorError;
+use anchor_lang::{InstructionData, ToAccountMetas};
+use reinitialization::{accounts, instruction, ReinitError, VaultSecure, VaultVulnerable, ID};
+use security_tests::{anchor_account, custom_error, program_test, raw_account};
+use solana_sdk::{
+    account::Account,
+    instruction::Instruction,
+    pubkey::Pubkey,
+    signature::Signer,
+    transaction::{Transaction, TransactionError},
+};
+
+const LEN: usize = 8 + <VaultSecure as anchor_lang::Space>::INIT_SPACE;
+
+fn vault() -> VaultSecure {
+    VaultSecure { authority: Pubkey::new_unique(), balance: 1_000, total_deposits: 1_000, total_withdrawals: 0, bump: 255 }
+}
+
+/// Runs `process_vault_manual_secure` against `account`.
+async fn process(account: Account) -> Result<(), TransactionError> {
+    let vault_info = Pubkey::new_unique();
+    let mut test = program_test();
+    test.add_account(vault_info, account);
+    let (mut banks, payer, blockhash) = test.start().await;
+
+    let ix = Instruction::new_with_bytes(
+        ID,
+        &instruction::ProcessVaultManualSecure {}.data(),
+        accounts::ProcessVaultManualSecure { vault_info }.to_account_metas(None),
+    );
+    let tx = Transaction::new_signed_with_payer(&[ix], Some(&payer.pubkey()), &[&payer], blockhash);
+    banks.process_transaction(tx).await.map_err(|err| err.unwrap())
+}
+
+#[tokio::test]
+async fn an_initialized_vault_is_accepted() {
+    assert_eq!(process(anchor_account(&vault(), ID)).await, Ok(()));
+}
+
+#[tokio::test]
+async fn zeroed_data_is_not_initialized() {
+    assert_eq!(process(raw_account(vec![0; LEN], ID)).await, Err(custom_error(0, ReinitError::NotInitialized)));
+}
+
+#[tokio::test]
+async fn another_discriminator_is_the_wrong_type() {
+    let state = VaultVulnerable {
+        authority: Pubkey::new_unique(),
+        balance: 1_000,
+        total_deposits: 1_000,
+        total_withdrawals: 0,
+        is_initialized: true,
+    };
+
+    assert_eq!(process(anchor_account(&state, ID)).await, Err(custom_error(0, ReinitError::WrongAccountType)));
+}
+
+#[tokio::test]
+async fn fewer_than_8_bytes_hold_no_discriminator() {
+    let err = process(raw_account(vec![1; 4], ID)).await;
+
+    assert_eq!(err, Err(custom_error(0, AnchorError::AccountDiscriminatorNotFound)));
+}
+
+#[tokio::test]
+async fn a_valid_vault_owned_by_another_program_is_rejected() {
+    let forged = anchor_account(&vault(), Pubkey::new_unique());
+
+    assert_eq!(process(forged).await, Err(custom_error(0, AnchorError::AccountOwnedByWrongProgram)));
+}
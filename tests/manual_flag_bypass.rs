@@ -0,0 +1,99 @@
+//! Manual `is_initialized` flags vs. Anchor's `init`, under raw writes.
+//!
+//! `write_raw_vulnerable` lets anyone overwrite bytes in an account the
+//! reinitialization program owns. That is enough to defeat a manual flag,
+//! and not enough to defeat `init`.
+
+use anchor_lang::{AccountDeserialize, InstructionData, ToAccountMetas};
+use reinitialization::{accounts, instruction, VaultSecure, VaultVulnerable, ID};
+use security_tests::{anchor_account, custom_error, pda, program_test, system_account};
+use solana_sdk::{
+    instruction::Instruction,
+    native_token::LAMPORTS_PER_SOL,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    system_instruction::SystemError,
+    system_program,
+    transaction::Transaction,
+};
+
+fn ix(accounts: impl ToAccountMetas, data: impl InstructionData) -> Instruction {
+    Instruction::new_with_bytes(ID, &data.data(), accounts.to_account_metas(None))
+}
+
+fn write_raw(target: Pubkey, caller: Pubkey, offset: usize, bytes: Vec<u8>) -> Instruction {
+    ix(
+        accounts::WriteRawVulnerable { target, caller },
+        instruction::WriteRawVulnerable { offset: offset as u32, bytes },
+    )
+}
+
+#[tokio::test]
+async fn raw_write_resets_manual_flag_and_hands_over_the_vault() {
+    let victim = Pubkey::new_unique();
+    let attacker = Keypair::new();
+    let vault = Pubkey::new_unique();
+    let state = VaultVulnerable {
+        authority: victim,
+        balance: 1_000,
+        total_deposits: 1_000,
+        total_withdrawals: 0,
+        is_initialized: true,
+    };
+
+    let mut test = program_test();
+    test.add_account(vault, anchor_account(&state, ID));
+    test.add_account(attacker.pubkey(), system_account(LAMPORTS_PER_SOL));
+    let (mut banks, _, blockhash) = test.start().await;
+
+    let reinit = ix(
+        accounts::InitializeSecureManual { vault, authority: attacker.pubkey() },
+        instruction::InitializeSecureManual {},
+    );
+
+    // The flag check holds on its own...
+    let tx = Transaction::new_signed_with_payer(std::slice::from_ref(&reinit), Some(&attacker.pubkey()), &[&attacker], blockhash);
+    assert!(banks.process_transaction(tx).await.is_err(), "flag blocks a plain re-init");
+
+    // ...until one raw byte clears it
+    let tx = Transaction::new_signed_with_payer(
+        &[
+            write_raw(vault, attacker.pubkey(), VaultVulnerable::IS_INITIALIZED_OFFSET, vec![0]),
+            reinit,
+        ],
+        Some(&attacker.pubkey()),
+        &[&attacker],
+        blockhash,
+    );
+    banks.process_transaction(tx).await.expect("raw write bypasses the flag");
+
+    let account = banks.get_account(vault).await.unwrap().unwrap();
+    let taken = VaultVulnerable::try_deserialize(&mut account.data.as_slice()).unwrap();
+    assert_eq!(taken.authority, attacker.pubkey());
+    assert_eq!(taken.balance, 0, "victim's balance is wiped");
+}
+
+#[tokio::test]
+async fn raw_write_cannot_make_init_run_twice() {
+    let (mut banks, authority, blockhash) = program_test().start().await;
+    let vault = pda(&[b"vault", authority.pubkey().as_ref()], &ID);
+    let init = ix(
+        accounts::InitializeSecureAnchor { vault, authority: authority.pubkey(), system_program: system_program::ID },
+        instruction::InitializeSecureAnchor {},
+    );
+
+    let tx = Transaction::new_signed_with_payer(std::slice::from_ref(&init), Some(&authority.pubkey()), &[&authority], blockhash);
+    banks.process_transaction(tx).await.expect("first init");
+
+    // Zero every byte, discriminator included, then try init again
+    let len = 8 + <VaultSecure as anchor_lang::Space>::INIT_SPACE;
+    let tx = Transaction::new_signed_with_payer(
+        &[write_raw(vault, authority.pubkey(), 0, vec![0; len]), init],
+        Some(&authority.pubkey()),
+        &[&authority],
+        blockhash,
+    );
+    let err = banks.process_transaction(tx).await.expect_err("init refuses an existing address").unwrap();
+
+    assert_eq!(err, custom_error(1, SystemError::AccountAlreadyInUse as u32));
+}
@@ -0,0 +1,122 @@
+//! Hand-rolled signer checks on a raw `AccountInfo` authority.
+//!
+//! `withdraw_manual_signer_check` needs the authority to both sign and be
+//! the vault's; `withdraw_manual_missing_is_signer` only compares the key,
+//! so naming the authority is enough to withdraw.
+
+use anchor_lang::{InstructionData, ToAccountMetas};
+use missing_signer_check::{accounts, instruction, SecurityError, Vault, ID};
+use security_tests::{anchor_account, custom_error, load, program_test, send, system_account};
+use solana_program_test::BanksClient;
+use solana_sdk::{
+    instruction::Instruction,
+    native_token::LAMPORTS_PER_SOL,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    transaction::TransactionError,
+};
+
+const BALANCE: u64 = LAMPORTS_PER_SOL;
+
+/// A vault booking and holding `BALANCE` above its rent, and someone who
+/// isn't its authority.
+struct Fixture {
+    banks: BanksClient,
+    authority: Keypair,
+    stranger: Keypair,
+    vault: Pubkey,
+}
+
+async fn funded_vault() -> Fixture {
+    let authority = Keypair::new();
+    let stranger = Keypair::new();
+    let (vault, bump) = Pubkey::find_program_address(&[b"vault", authority.pubkey().as_ref()], &ID);
+    let state = Vault { authority: authority.pubkey(), balance: BALANCE, total_withdrawn: 0, withdrawal_limit: BALANCE, bump };
+    let mut account = anchor_account(&state, ID);
+    account.lamports += BALANCE;
+
+    let mut test = program_test();
+    test.add_account(authority.pubkey(), system_account(LAMPORTS_PER_SOL));
+    test.add_account(stranger.pubkey(), system_account(LAMPORTS_PER_SOL));
+    test.add_account(vault, account);
+    let (banks, _payer, _) = test.start().await;
+    Fixture { banks, authority, stranger, vault }
+}
+
+impl Fixture {
+    /// A withdrawal to `recipient` naming `authority`, which is not marked
+    /// as a signer until the caller sets `is_signer` on it.
+    fn withdraw(&self, data: impl InstructionData, authority: &Keypair, recipient: Pubkey) -> Instruction {
+        let accounts = accounts::WithdrawManual { vault: self.vault, authority: authority.pubkey(), recipient };
+        Instruction::new_with_bytes(ID, &data.data(), accounts.to_account_metas(None))
+    }
+
+    async fn send(&mut self, signers: &[&Keypair], instruction: Instruction) -> Result<(), TransactionError> {
+        send(&mut self.banks, &[instruction], signers).await
+    }
+
+    async fn vault(&mut self) -> Vault {
+        load(&mut self.banks, self.vault).await
+    }
+}
+
+#[tokio::test]
+async fn the_signing_authority_withdraws() {
+    let mut fixture = funded_vault().await;
+    let recipient = Pubkey::new_unique();
+    let mut withdraw = fixture.withdraw(instruction::WithdrawManualSignerCheck { amount: BALANCE }, &fixture.authority, recipient);
+    withdraw.accounts[1].is_signer = true;
+
+    let authority = fixture.authority.insecure_clone();
+    fixture.send(&[&authority], withdraw).await.expect("authority signed");
+
+    assert_eq!(fixture.vault().await.balance, 0);
+    assert_eq!(fixture.banks.get_balance(recipient).await.unwrap(), BALANCE);
+}
+
+#[tokio::test]
+async fn naming_the_authority_without_its_signature_is_rejected() {
+    let mut fixture = funded_vault().await;
+    let withdraw = fixture.withdraw(
+        instruction::WithdrawManualSignerCheck { amount: BALANCE },
+        &fixture.authority,
+        fixture.stranger.pubkey(),
+    );
+
+    let stranger = fixture.stranger.insecure_clone();
+    let err = fixture.send(&[&stranger], withdraw).await.expect_err("is_signer is false");
+
+    assert_eq!(err, custom_error(0, SecurityError::Unauthorized));
+    assert_eq!(fixture.vault().await.balance, BALANCE);
+}
+
+#[tokio::test]
+async fn a_signer_that_is_not_the_authority_is_rejected() {
+    let mut fixture = funded_vault().await;
+    let mut withdraw = fixture.withdraw(
+        instruction::WithdrawManualSignerCheck { amount: BALANCE },
+        &fixture.stranger,
+        fixture.stranger.pubkey(),
+    );
+    withdraw.accounts[1].is_signer = true;
+
+    let stranger = fixture.stranger.insecure_clone();
+    let err = fixture.send(&[&stranger], withdraw).await.expect_err("wrong key");
+
+    assert_eq!(err, custom_error(0, SecurityError::Unauthorized));
+}
+
+#[tokio::test]
+async fn without_is_signer_naming_the_authority_is_enough() {
+    let mut fixture = funded_vault().await;
+    let withdraw = fixture.withdraw(
+        instruction::WithdrawManualMissingIsSigner { amount: BALANCE },
+        &fixture.authority,
+        fixture.stranger.pubkey(),
+    );
+
+    let stranger = fixture.stranger.insecure_clone();
+    fixture.send(&[&stranger], withdraw).await.expect("only the key is compared");
+
+    assert_eq!(fixture.vault().await.balance, 0);
+}
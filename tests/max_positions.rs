@@ -0,0 +1,167 @@
+//! Per-user position limits in the account-validation pool.
+//!
+//! `open_deposit` refuses a user's position beyond `config.max_positions`,
+//! so nobody can bloat the program's state with empty accounts. Closing a
+//! position with `close_deposit` frees its slot.
+
+use account_validation::{accounts, instruction, Pool, UserAccount, ValidationError, ID};
+use anchor_lang::{InstructionData, ToAccountMetas};
+use security_tests::{anchor_account, custom_error, load, pda, program_test, send, system_account};
+use solana_program_test::BanksClient;
+use solana_sdk::{
+    instruction::Instruction,
+    native_token::LAMPORTS_PER_SOL,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    system_program,
+    transaction::TransactionError,
+};
+
+const MAX_POSITIONS: u16 = 3;
+
+fn ix(accounts: impl ToAccountMetas, data: impl InstructionData) -> Instruction {
+    Instruction::new_with_bytes(ID, &data.data(), accounts.to_account_metas(None))
+}
+
+/// A user with no positions yet and one more pool than they may join.
+struct Fixture {
+    banks: BanksClient,
+    user: Keypair,
+    config: Pubkey,
+    user_account: Pubkey,
+    pools: Vec<Pubkey>,
+}
+
+async fn one_pool_too_many() -> Fixture {
+    let user = Keypair::new();
+    let config = pda(&[b"config"], &ID);
+    let mut test = program_test();
+    test.add_account(user.pubkey(), system_account(LAMPORTS_PER_SOL));
+    let pools: Vec<Pubkey> = (0..=MAX_POSITIONS).map(|_| Pubkey::new_unique()).collect();
+    for pool in &pools {
+        let state = Pool {
+            authority: Pubkey::new_unique(),
+            total_deposited: 0,
+            reward_rate: 10,
+            config,
+            total_accrued: 0,
+            global_tvl_cap: u64::MAX,
+            lock_secs: 0,
+            dust_swept: 0,
+            min_hold_secs: 0,
+            early_exit_bps: 0,
+            early_exit_fees: 0,
+            deposit_mint: Pubkey::default(),
+            reward_mint: Pubkey::default(),
+            vault: Pubkey::default(),
+            bump: 0,
+        };
+        test.add_account(*pool, anchor_account(&state, ID));
+    }
+    let (banks, _payer, _) = test.start().await;
+
+    let user_account = pda(&[b"user", user.pubkey().as_ref()], &ID);
+    let mut fixture = Fixture { banks, user, config, user_account, pools };
+    let setup = [
+        ix(
+            accounts::InitializeConfig { config, admin: fixture.user.pubkey(), system_program: system_program::ID },
+            instruction::InitializeConfig { fee_bps: 100, max_positions: MAX_POSITIONS, dust_threshold: 0 },
+        ),
+        ix(
+            accounts::InitializeUserAccount {
+                user_account,
+                user: fixture.user.pubkey(),
+                system_program: system_program::ID,
+            },
+            instruction::InitializeUserAccount {},
+        ),
+    ];
+    for instruction in setup {
+        fixture.send(instruction).await.expect("setup");
+    }
+    fixture
+}
+
+impl Fixture {
+    async fn send(&mut self, instruction: Instruction) -> Result<(), TransactionError> {
+        send(&mut self.banks, &[instruction], &[&self.user]).await
+    }
+
+    fn user_deposit(&self, index: usize) -> Pubkey {
+        pda(&[b"user_deposit", self.pools[index].as_ref(), self.user.pubkey().as_ref()], &ID)
+    }
+
+    async fn open(&mut self, index: usize) -> Result<(), TransactionError> {
+        let open = ix(
+            accounts::OpenDeposit {
+                config: self.config,
+                pool: self.pools[index],
+                user_account: self.user_account,
+                user_deposit: self.user_deposit(index),
+                user: self.user.pubkey(),
+                system_program: system_program::ID,
+            },
+            instruction::OpenDeposit {},
+        );
+        self.send(open).await
+    }
+
+    async fn close(&mut self, index: usize) -> Result<(), TransactionError> {
+        let close = ix(
+            accounts::CloseDeposit {
+                user_account: self.user_account,
+                user_deposit: self.user_deposit(index),
+                user: self.user.pubkey(),
+            },
+            instruction::CloseDeposit {},
+        );
+        self.send(close).await
+    }
+
+    async fn open_positions(&mut self) -> u16 {
+        load::<UserAccount>(&mut self.banks, self.user_account).await.open_positions
+    }
+}
+
+#[tokio::test]
+async fn positions_can_be_opened_up_to_the_cap() {
+    let mut fixture = one_pool_too_many().await;
+
+    for index in 0..usize::from(MAX_POSITIONS) {
+        fixture.open(index).await.expect("under the cap");
+    }
+
+    assert_eq!(fixture.open_positions().await, MAX_POSITIONS);
+}
+
+#[tokio::test]
+async fn one_position_over_the_cap_is_rejected() {
+    let mut fixture = one_pool_too_many().await;
+    for index in 0..usize::from(MAX_POSITIONS) {
+        fixture.open(index).await.expect("under the cap");
+    }
+
+    let over = usize::from(MAX_POSITIONS);
+    let err = fixture.open(over).await.expect_err("cap reached");
+
+    assert_eq!(err, custom_error(0, ValidationError::TooManyPositions));
+    assert_eq!(fixture.open_positions().await, MAX_POSITIONS);
+    let user_deposit = fixture.user_deposit(over);
+    assert!(fixture.banks.get_account(user_deposit).await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn closing_a_position_frees_its_slot() {
+    let mut fixture = one_pool_too_many().await;
+    for index in 0..usize::from(MAX_POSITIONS) {
+        fixture.open(index).await.expect("under the cap");
+    }
+
+    fixture.close(0).await.expect("empty position closes");
+    assert_eq!(fixture.open_positions().await, MAX_POSITIONS - 1);
+    let closed = fixture.user_deposit(0);
+    assert!(fixture.banks.get_account(closed).await.unwrap().is_none());
+
+    fixture.open(usize::from(MAX_POSITIONS)).await.expect("slot was freed");
+    assert_eq!(fixture.open_positions().await, MAX_POSITIONS);
+}
@@ -0,0 +1,117 @@
+//! The missing-signer attack, executed.
+//!
+//! A victim opens and funds a vault. An attacker who only knows the
+//! victim's pubkey names it as `authority` and signs the transaction alone:
+//! `withdraw_vulnerable` pays out, `withdraw_secure` refuses because the
+//! authority never signed.
+
+use anchor_lang::error::ErrorCode as AnchorError;
+use anchor_lang::{InstructionData, ToAccountMetas};
+use missing_signer_check::{accounts, instruction, Vault, ID};
+use security_tests::{custom_error, load, missing_signer_pdas, program_test, send, system_account};
+use solana_program_test::BanksClient;
+use solana_sdk::{
+    instruction::Instruction,
+    native_token::LAMPORTS_PER_SOL,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    system_program,
+};
+
+const BALANCE: u64 = LAMPORTS_PER_SOL;
+
+fn ix(accounts: impl ToAccountMetas, data: impl InstructionData) -> Instruction {
+    Instruction::new_with_bytes(ID, &data.data(), accounts.to_account_metas(None))
+}
+
+/// A victim's vault funded with `BALANCE`, and an attacker with a wallet
+/// to receive what they take.
+struct Fixture {
+    banks: BanksClient,
+    victim: Keypair,
+    attacker: Keypair,
+    vault: Pubkey,
+    loot: Pubkey,
+}
+
+async fn victim_vault() -> Fixture {
+    let victim = Keypair::new();
+    let attacker = Keypair::new();
+    let mut test = program_test();
+    test.add_account(victim.pubkey(), system_account(2 * BALANCE));
+    test.add_account(attacker.pubkey(), system_account(LAMPORTS_PER_SOL));
+    let (mut banks, _payer, _) = test.start().await;
+
+    let (vault, _) = missing_signer_pdas::vault(&victim.pubkey());
+    let initialize = ix(
+        accounts::InitializeVault { vault, authority: victim.pubkey(), system_program: system_program::ID },
+        instruction::InitializeVault { initial_balance: BALANCE, withdrawal_limit: BALANCE },
+    );
+    send(&mut banks, &[initialize], &[&victim]).await.expect("victim opens the vault");
+    Fixture { banks, victim, attacker, vault, loot: Pubkey::new_unique() }
+}
+
+impl Fixture {
+    /// Clears `is_signer` on the victim, as an attacker without their key
+    /// must.
+    fn unsigned(&self, mut withdraw: Instruction) -> Instruction {
+        for meta in withdraw.accounts.iter_mut().filter(|meta| meta.pubkey == self.victim.pubkey()) {
+            meta.is_signer = false;
+        }
+        withdraw
+    }
+
+    async fn vault(&mut self) -> Vault {
+        load(&mut self.banks, self.vault).await
+    }
+
+    async fn loot(&mut self) -> u64 {
+        self.banks.get_balance(self.loot).await.unwrap()
+    }
+}
+
+#[tokio::test]
+async fn the_vulnerable_withdrawal_drains_the_vault_without_the_victims_signature() {
+    let mut fixture = victim_vault().await;
+    let withdraw = fixture.unsigned(ix(
+        accounts::WithdrawVulnerable { vault: fixture.vault, authority: fixture.victim.pubkey(), recipient: fixture.loot },
+        instruction::WithdrawVulnerable { amount: BALANCE },
+    ));
+
+    let attacker = fixture.attacker.insecure_clone();
+    send(&mut fixture.banks, &[withdraw], &[&attacker]).await.expect("only the attacker signed");
+
+    assert_eq!(fixture.vault().await.balance, 0);
+    assert_eq!(fixture.loot().await, BALANCE);
+}
+
+#[tokio::test]
+async fn the_secure_withdrawal_requires_the_victims_signature() {
+    let mut fixture = victim_vault().await;
+    let withdraw = fixture.unsigned(ix(
+        accounts::WithdrawSecure { vault: fixture.vault, authority: fixture.victim.pubkey(), recipient: fixture.loot },
+        instruction::WithdrawSecure { amount: BALANCE },
+    ));
+
+    let attacker = fixture.attacker.insecure_clone();
+    let err = send(&mut fixture.banks, &[withdraw], &[&attacker]).await.expect_err("the victim never signed");
+
+    assert_eq!(err, custom_error(0, AnchorError::AccountNotSigner));
+    assert_eq!(fixture.vault().await.balance, BALANCE);
+    assert_eq!(fixture.loot().await, 0);
+}
+
+#[tokio::test]
+async fn the_victim_can_still_withdraw_securely() {
+    let mut fixture = victim_vault().await;
+    let withdraw = ix(
+        accounts::WithdrawSecure { vault: fixture.vault, authority: fixture.victim.pubkey(), recipient: fixture.loot },
+        instruction::WithdrawSecure { amount: BALANCE },
+    );
+
+    let victim = fixture.victim.insecure_clone();
+    send(&mut fixture.banks, &[withdraw], &[&victim]).await.expect("the victim signed");
+
+    assert_eq!(fixture.vault().await.balance, 0);
+    assert_eq!(fixture.loot().await, BALANCE);
+}
@@ -0,0 +1,118 @@
+//! Two-of-three withdrawals from the missing-signer-check multisig.
+//!
+//! Approvals come in as `remaining_accounts`. Only distinct configured keys
+//! that actually signed count towards the threshold.
+
+use anchor_lang::{InstructionData, ToAccountMetas};
+use missing_signer_check::{accounts, instruction, MultisigError, MultisigVault, ID};
+use security_tests::{anchor_account, custom_error, load, program_test, system_account};
+use solana_program_test::BanksClient;
+use solana_sdk::{
+    instruction::{AccountMeta, Instruction},
+    native_token::LAMPORTS_PER_SOL,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    transaction::{Transaction, TransactionError},
+};
+
+const BALANCE: u64 = LAMPORTS_PER_SOL;
+
+/// A 2-of-3 multisig holding `BALANCE` above its rent.
+struct Fixture {
+    banks: BanksClient,
+    signers: [Keypair; 3],
+    multisig: Pubkey,
+    recipient: Pubkey,
+}
+
+async fn funded_multisig() -> Fixture {
+    let signers = [Keypair::new(), Keypair::new(), Keypair::new()];
+    let creator = Pubkey::new_unique();
+    let (multisig, bump) = Pubkey::find_program_address(&[b"multisig", creator.as_ref()], &ID);
+    let state = MultisigVault {
+        creator,
+        signers: [signers[0].pubkey(), signers[1].pubkey(), signers[2].pubkey()],
+        threshold: 2,
+        timelock_secs: 0,
+        balance: BALANCE,
+        pending_threshold: 0,
+        threshold_change_eta: 0,
+        bump,
+    };
+    let mut account = anchor_account(&state, ID);
+    account.lamports += BALANCE;
+
+    let mut test = program_test();
+    for signer in &signers {
+        test.add_account(signer.pubkey(), system_account(LAMPORTS_PER_SOL));
+    }
+    test.add_account(multisig, account);
+    let (banks, _payer, _) = test.start().await;
+    Fixture { banks, signers, multisig, recipient: Pubkey::new_unique() }
+}
+
+impl Fixture {
+    /// Withdraws `BALANCE` with `approvals` as `(signer index, signed)`
+    /// remaining accounts. Signer 0 pays, and every approval that signed
+    /// signs the transaction.
+    async fn withdraw(&mut self, approvals: &[(usize, bool)]) -> Result<(), TransactionError> {
+        let mut withdraw = Instruction::new_with_bytes(
+            ID,
+            &instruction::WithdrawMultisig { amount: BALANCE }.data(),
+            accounts::WithdrawMultisig { multisig: self.multisig, recipient: self.recipient }.to_account_metas(None),
+        );
+        withdraw.accounts.extend(
+            approvals
+                .iter()
+                .map(|(index, signed)| AccountMeta::new_readonly(self.signers[*index].pubkey(), *signed)),
+        );
+
+        let mut keypairs = vec![&self.signers[0]];
+        for (index, signed) in approvals {
+            if *signed && *index != 0 {
+                keypairs.push(&self.signers[*index]);
+            }
+        }
+        let blockhash = self.banks.get_latest_blockhash().await.unwrap();
+        let tx = Transaction::new_signed_with_payer(&[withdraw], Some(&self.signers[0].pubkey()), &keypairs, blockhash);
+        self.banks.process_transaction(tx).await.map_err(|err| err.unwrap())
+    }
+
+    async fn balance(&mut self) -> u64 {
+        load::<MultisigVault>(&mut self.banks, self.multisig).await.balance
+    }
+}
+
+fn threshold_not_met() -> TransactionError {
+    custom_error(0, MultisigError::ThresholdNotMet)
+}
+
+#[tokio::test]
+async fn two_distinct_signers_withdraw() {
+    let mut fixture = funded_multisig().await;
+
+    fixture.withdraw(&[(0, true), (2, true)]).await.expect("2 of 3 signed");
+
+    assert_eq!(fixture.balance().await, 0);
+    assert_eq!(fixture.banks.get_balance(fixture.recipient).await.unwrap(), BALANCE);
+}
+
+#[tokio::test]
+async fn one_signer_passed_twice_counts_once() {
+    let mut fixture = funded_multisig().await;
+
+    let err = fixture.withdraw(&[(0, true), (0, true)]).await.expect_err("the same key twice");
+
+    assert_eq!(err, threshold_not_met());
+    assert_eq!(fixture.balance().await, BALANCE);
+}
+
+#[tokio::test]
+async fn a_listed_signer_who_did_not_sign_does_not_count() {
+    let mut fixture = funded_multisig().await;
+
+    let err = fixture.withdraw(&[(0, true), (1, false)]).await.expect_err("signer 1 never signed");
+
+    assert_eq!(err, threshold_not_met());
+    assert_eq!(fixture.balance().await, BALANCE);
+}
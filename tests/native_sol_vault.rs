@@ -0,0 +1,126 @@
+//! Withdrawals from the native-SOL vault.
+//!
+//! `withdraw_secure` debits the program-owned vault directly and never
+//! dips into its rent reserve. `withdraw_vulnerable` goes through the
+//! System Program, which refuses to debit an account that carries data.
+
+use anchor_lang::{AccountDeserialize, InstructionData, ToAccountMetas};
+use native_sol_vault::{accounts, instruction, NativeVaultError, SolVault, ID};
+use security_tests::{custom_error, pda, program_test, send, system_account};
+use solana_program_test::BanksClient;
+use solana_sdk::{
+    instruction::{Instruction, InstructionError},
+    native_token::LAMPORTS_PER_SOL,
+    pubkey::Pubkey,
+    rent::Rent,
+    signature::{Keypair, Signer},
+    system_program,
+    transaction::TransactionError,
+};
+
+const DEPOSIT: u64 = 2 * LAMPORTS_PER_SOL;
+
+fn ix(accounts: impl ToAccountMetas, data: impl InstructionData) -> Instruction {
+    Instruction::new_with_bytes(ID, &data.data(), accounts.to_account_metas(None))
+}
+
+/// A vault opened by `authority` holding `DEPOSIT` above its rent reserve.
+struct Fixture {
+    banks: BanksClient,
+    authority: Keypair,
+    vault: Pubkey,
+    recipient: Pubkey,
+}
+
+async fn funded_vault() -> Fixture {
+    let authority = Keypair::new();
+    let mut test = program_test();
+    test.add_account(authority.pubkey(), system_account(10 * LAMPORTS_PER_SOL));
+    let (banks, _payer, _) = test.start().await;
+
+    let vault = pda(&[b"sol_vault", authority.pubkey().as_ref()], &ID);
+    let mut fixture = Fixture { banks, authority, vault, recipient: Pubkey::new_unique() };
+    let open = ix(
+        accounts::InitializeSolVault { vault, authority: fixture.authority.pubkey(), system_program: system_program::ID },
+        instruction::InitializeSolVault {},
+    );
+    fixture.send(open).await.expect("vault opened");
+    let deposit = ix(
+        accounts::Deposit { vault, depositor: fixture.authority.pubkey(), system_program: system_program::ID },
+        instruction::Deposit { amount: DEPOSIT },
+    );
+    fixture.send(deposit).await.expect("deposited");
+    fixture
+}
+
+impl Fixture {
+    async fn send(&mut self, instruction: Instruction) -> Result<(), TransactionError> {
+        send(&mut self.banks, &[instruction], &[&self.authority]).await
+    }
+
+    async fn withdraw(&mut self, amount: u64) -> Result<(), TransactionError> {
+        let withdraw = ix(
+            accounts::WithdrawSecure { vault: self.vault, recipient: self.recipient, authority: self.authority.pubkey() },
+            instruction::WithdrawSecure { amount },
+        );
+        self.send(withdraw).await
+    }
+
+    async fn balance(&mut self, key: Pubkey) -> u64 {
+        self.banks.get_balance(key).await.unwrap()
+    }
+
+    /// Rent-exempt minimum for the vault's current size
+    async fn reserve(&mut self) -> u64 {
+        let account = self.banks.get_account(self.vault).await.unwrap().unwrap();
+        Rent::default().minimum_balance(account.data.len())
+    }
+}
+
+#[tokio::test]
+async fn secure_withdrawals_move_lamports_directly() {
+    let mut fixture = funded_vault().await;
+    let before = fixture.balance(fixture.vault).await;
+
+    fixture.withdraw(LAMPORTS_PER_SOL).await.expect("withdrawal");
+
+    assert_eq!(fixture.balance(fixture.vault).await, before - LAMPORTS_PER_SOL);
+    assert_eq!(fixture.balance(fixture.recipient).await, LAMPORTS_PER_SOL);
+    let account = fixture.banks.get_account(fixture.vault).await.unwrap().unwrap();
+    let vault = SolVault::try_deserialize(&mut account.data.as_slice()).unwrap();
+    assert_eq!(vault.authority, fixture.authority.pubkey());
+}
+
+#[tokio::test]
+async fn the_rent_reserve_stays_behind() {
+    let mut fixture = funded_vault().await;
+    let reserve = fixture.reserve().await;
+
+    let err = fixture.withdraw(DEPOSIT + 1).await.expect_err("would dip into rent");
+    assert_eq!(err, custom_error(0, NativeVaultError::BelowRentExempt));
+
+    // Everything above the reserve can leave, and the vault survives
+    fixture.withdraw(DEPOSIT).await.expect("withdraw down to the reserve");
+    assert_eq!(fixture.balance(fixture.vault).await, reserve);
+    assert!(fixture.banks.get_account(fixture.vault).await.unwrap().is_some());
+}
+
+#[tokio::test]
+async fn system_transfers_from_the_vault_always_fail() {
+    let mut fixture = funded_vault().await;
+    let before = fixture.balance(fixture.vault).await;
+
+    let withdraw = ix(
+        accounts::WithdrawVulnerable {
+            vault: fixture.vault,
+            recipient: fixture.recipient,
+            authority: fixture.authority.pubkey(),
+            system_program: system_program::ID,
+        },
+        instruction::WithdrawVulnerable { amount: 1 },
+    );
+    let err = fixture.send(withdraw).await.expect_err("vault carries data");
+
+    assert_eq!(err, TransactionError::InstructionError(0, InstructionError::InvalidArgument));
+    assert_eq!(fixture.balance(fixture.vault).await, before);
+}
@@ -0,0 +1,144 @@
+//! Optional referrer accounts in the account-validation program.
+//!
+//! `set_referrer` treats a missing referrer as a normal call and validates
+//! the referrer only when one is passed. `set_referrer_vulnerable` unwraps
+//! the option, so the no-referrer call fails outright.
+
+use account_validation::{accounts, instruction, UserAccount, ValidationError, ID};
+use anchor_lang::{AccountDeserialize, InstructionData, ToAccountMetas};
+use security_tests::{custom_error, pda, program_test, send, system_account};
+use solana_program_test::BanksClient;
+use solana_sdk::{
+    instruction::Instruction,
+    native_token::LAMPORTS_PER_SOL,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    system_program,
+    transaction::{Transaction, TransactionError},
+};
+
+fn ix(accounts: impl ToAccountMetas, data: impl InstructionData) -> Instruction {
+    Instruction::new_with_bytes(ID, &data.data(), accounts.to_account_metas(None))
+}
+
+/// A new user and a would-be referrer, each with a UserAccount.
+struct Fixture {
+    banks: BanksClient,
+    user: Keypair,
+    referrer: Keypair,
+}
+
+async fn two_users() -> Fixture {
+    let user = Keypair::new();
+    let referrer = Keypair::new();
+    let mut test = program_test();
+    test.add_account(user.pubkey(), system_account(LAMPORTS_PER_SOL));
+    test.add_account(referrer.pubkey(), system_account(LAMPORTS_PER_SOL));
+    let (banks, _payer, _) = test.start().await;
+
+    let mut fixture = Fixture { banks, user, referrer };
+    for signer in [&fixture.user, &fixture.referrer] {
+        let open = ix(
+            accounts::InitializeUserAccount {
+                user_account: user_account(&signer.pubkey()),
+                user: signer.pubkey(),
+                system_program: system_program::ID,
+            },
+            instruction::InitializeUserAccount {},
+        );
+        let blockhash = fixture.banks.get_latest_blockhash().await.unwrap();
+        let tx = Transaction::new_signed_with_payer(&[open], Some(&signer.pubkey()), &[signer], blockhash);
+        fixture.banks.process_transaction(tx).await.expect("user account opened");
+    }
+    fixture
+}
+
+fn user_account(owner: &Pubkey) -> Pubkey {
+    pda(&[b"user", owner.as_ref()], &ID)
+}
+
+impl Fixture {
+    async fn send(&mut self, instruction: Instruction) -> Result<(), TransactionError> {
+        send(&mut self.banks, &[instruction], &[&self.user]).await
+    }
+
+    async fn set_referrer(&mut self, referrer_account: Option<Pubkey>) -> Result<(), TransactionError> {
+        let set = ix(
+            accounts::SetReferrer {
+                user_account: user_account(&self.user.pubkey()),
+                referrer_account,
+                user: self.user.pubkey(),
+            },
+            instruction::SetReferrer {},
+        );
+        self.send(set).await
+    }
+
+    async fn state(&mut self, owner: Pubkey) -> UserAccount {
+        let account = self.banks.get_account(user_account(&owner)).await.unwrap().unwrap();
+        UserAccount::try_deserialize(&mut account.data.as_slice()).unwrap()
+    }
+}
+
+#[tokio::test]
+async fn a_missing_referrer_is_a_normal_call() {
+    let mut fixture = two_users().await;
+
+    fixture.set_referrer(None).await.expect("no referrer needed");
+
+    assert_eq!(fixture.state(fixture.user.pubkey()).await.referrer, None);
+    assert_eq!(fixture.state(fixture.referrer.pubkey()).await.referrals, 0);
+}
+
+#[tokio::test]
+async fn a_present_referrer_is_validated_and_credited() {
+    let mut fixture = two_users().await;
+    let referrer = fixture.referrer.pubkey();
+
+    fixture.set_referrer(Some(user_account(&referrer))).await.expect("referred");
+
+    assert_eq!(fixture.state(fixture.user.pubkey()).await.referrer, Some(referrer));
+    assert_eq!(fixture.state(referrer).await.referrals, 1);
+
+    // Naming a referrer is one-shot
+    let err = fixture.set_referrer(None).await.expect_err("already set");
+    assert_eq!(err, custom_error(0, ValidationError::ReferrerAlreadySet));
+}
+
+#[tokio::test]
+async fn a_user_cannot_refer_themselves() {
+    let mut fixture = two_users().await;
+    let own = user_account(&fixture.user.pubkey());
+
+    let err = fixture.set_referrer(Some(own)).await.expect_err("self-referral");
+
+    assert_eq!(err, custom_error(0, ValidationError::SelfReferral));
+}
+
+#[tokio::test]
+async fn unwrapping_the_option_locks_out_users_without_a_referrer() {
+    let mut fixture = two_users().await;
+    let referrer = fixture.referrer.pubkey();
+
+    let set = |referrer_account| {
+        ix(
+            accounts::SetReferrerVulnerable {
+                user_account: user_account(&fixture.user.pubkey()),
+                referrer_account,
+                user: fixture.user.pubkey(),
+            },
+            instruction::SetReferrerVulnerable {},
+        )
+    };
+    let with = set(Some(user_account(&referrer)));
+    let without = set(None);
+
+    fixture.send(with).await.expect("works with a referrer");
+    assert_eq!(fixture.state(fixture.user.pubkey()).await.referrer, Some(referrer));
+
+    // Natively the unwrap panics inside the bank, so the call never returns
+    // a result; on-chain it aborts the program. Either way it cannot succeed.
+    let blockhash = fixture.banks.get_latest_blockhash().await.unwrap();
+    let tx = Transaction::new_signed_with_payer(&[without], Some(&fixture.user.pubkey()), &[&fixture.user], blockhash);
+    assert!(fixture.banks.process_transaction(tx).await.is_err(), "unwrap on None");
+}
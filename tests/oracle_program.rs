@@ -0,0 +1,63 @@
+//! `call_oracle_secure` in the arbitrary-CPI program.
+//!
+//! The oracle has to be `ORACLE_PROGRAM_ID`, executable, and owned by a BPF
+//! loader - `guards::require_valid_program`. Being executable is not enough
+//! on its own.
+
+use anchor_lang::{InstructionData, ToAccountMetas};
+use anchor_spl::token::spl_token;
+use arbitrary_cpi::{accounts, instruction, CpiError, ID, ORACLE_PROGRAM_ID};
+use security_tests::{custom_error, program_test, raw_account, upgradeable_program_account};
+use solana_sdk::{
+    account::Account,
+    instruction::Instruction,
+    pubkey::Pubkey,
+    signature::Signer,
+    system_program,
+    transaction::{Transaction, TransactionError},
+};
+
+/// Calls `call_oracle_secure` with `oracle_program`, after storing
+/// `account` at `ORACLE_PROGRAM_ID`.
+async fn call_oracle(account: Account, oracle_program: Pubkey) -> Result<(), TransactionError> {
+    let mut test = program_test();
+    test.add_account(ORACLE_PROGRAM_ID, account);
+    let (mut banks, payer, blockhash) = test.start().await;
+
+    let ix = Instruction::new_with_bytes(
+        ID,
+        &instruction::CallOracleSecure {}.data(),
+        accounts::CallOracleSecure { oracle_program }.to_account_metas(None),
+    );
+    let tx = Transaction::new_signed_with_payer(&[ix], Some(&payer.pubkey()), &[&payer], blockhash);
+    banks.process_transaction(tx).await.map_err(|err| err.unwrap())
+}
+
+#[tokio::test]
+async fn the_deployed_oracle_is_accepted() {
+    let oracle = upgradeable_program_account(&ORACLE_PROGRAM_ID);
+
+    assert_eq!(call_oracle(oracle, ORACLE_PROGRAM_ID).await, Ok(()));
+}
+
+#[tokio::test]
+async fn a_non_executable_account_is_rejected() {
+    let oracle = Account { executable: false, ..upgradeable_program_account(&ORACLE_PROGRAM_ID) };
+
+    assert_eq!(call_oracle(oracle, ORACLE_PROGRAM_ID).await, Err(custom_error(0, CpiError::NotExecutable)));
+}
+
+#[tokio::test]
+async fn an_executable_outside_the_bpf_loaders_is_rejected() {
+    let oracle = Account { executable: true, ..raw_account(vec![0; 36], system_program::ID) };
+
+    assert_eq!(call_oracle(oracle, ORACLE_PROGRAM_ID).await, Err(custom_error(0, CpiError::InvalidProgram)));
+}
+
+#[tokio::test]
+async fn another_deployed_program_is_rejected() {
+    let oracle = upgradeable_program_account(&ORACLE_PROGRAM_ID);
+
+    // A real program, just not the oracle
+    assert_eq!(call_oracle(oracle, spl_token::ID).await, Err(custom_error(0, CpiError::InvalidProgram)));
+}
@@ -0,0 +1,217 @@
+//! Manual owner and discriminator checks for raw accounts.
+//!
+//! `security_common::require_owned_by` is the owner check `Account<>`
+//! makes, plus a length check so the discriminator can be read.
+//! `claim_rewards_manual_owner_check` uses it on an `UncheckedAccount` pool
+//! and must turn away the same accounts `claim_rewards_secure` does.
+
+use account_validation::{accounts, instruction, Config, Pool, UserDeposit, ValidationError, ID};
+use anchor_lang::error::ErrorCode as AnchorError;
+use anchor_lang::prelude::{AccountInfo, ProgramError};
+use anchor_lang::{InstructionData, ToAccountMetas};
+use anchor_spl::token::{spl_token, TokenAccount};
+use security_common::require_owned_by;
+use security_tests::{anchor_account, custom_error, load, program_test, send, token_account};
+use solana_program_test::ProgramTestContext;
+use solana_sdk::{
+    account::Account,
+    clock::Clock,
+    instruction::Instruction,
+    pubkey::Pubkey,
+    signature::Signer,
+    system_program,
+    transaction::TransactionError,
+};
+
+const MINT: Pubkey = Pubkey::new_from_array([0x51; 32]);
+const RATE: u64 = 10;
+const VAULT: u64 = 1_000;
+const ACCRUED: u64 = 25;
+
+/// Runs `require_owned_by(_, ID, InvalidOwner)` on an account owned by
+/// `owner`.
+fn check(owner: Pubkey, mut data: Vec<u8>) -> Result<(), ProgramError> {
+    let key = Pubkey::new_unique();
+    let mut lamports = 0;
+    let info = AccountInfo::new(&key, false, false, &mut lamports, &mut data, &owner, false, 0);
+    require_owned_by(&info, &ID, ValidationError::InvalidOwner).map_err(Into::into)
+}
+
+#[test]
+fn an_owned_account_with_a_discriminator_is_accepted() {
+    check(ID, vec![0; 8]).expect("owned, 8 bytes");
+}
+
+#[test]
+fn an_account_of_another_owner_is_rejected() {
+    let err = check(system_program::ID, vec![0; 64]).expect_err("not ours");
+
+    assert_eq!(err, ProgramError::Custom(ValidationError::InvalidOwner.into()));
+}
+
+#[test]
+fn an_account_too_short_for_a_discriminator_is_rejected() {
+    let err = check(ID, vec![0; 7]).expect_err("7 bytes");
+
+    assert_eq!(err, ProgramError::Custom(AnchorError::AccountDiscriminatorNotFound.into()));
+}
+
+fn pool_state(authority: Pubkey, bump: u8) -> Pool {
+    Pool {
+        authority,
+        total_deposited: 0,
+        reward_rate: RATE,
+        config: Pubkey::new_unique(),
+        total_accrued: ACCRUED,
+        global_tvl_cap: u64::MAX,
+        lock_secs: 0,
+        dust_swept: 0,
+        min_hold_secs: 0,
+        early_exit_bps: 0,
+        early_exit_fees: 0,
+        deposit_mint: Pubkey::default(),
+        reward_mint: MINT,
+        vault: Pubkey::default(),
+        bump,
+    }
+}
+
+/// An empty position of `owner`'s in `pool` with `ACCRUED` rewards to
+/// claim.
+fn position(pool: Pubkey, owner: Pubkey) -> (Pubkey, Account) {
+    let (key, bump) = Pubkey::find_program_address(&[b"user_deposit", pool.as_ref(), owner.as_ref()], &ID);
+    let state = UserDeposit {
+        pool,
+        owner,
+        amount: 0,
+        locked_until: 0,
+        deposited_at: 0,
+        permit_nonce: 0,
+        accrued_rewards: ACCRUED,
+        rewards_updated_at: 0,
+        bump,
+    };
+    (key, anchor_account(&state, ID))
+}
+
+/// A real pool paying `RATE` of `MINT` and owing `ACCRUED`, its reward vault
+/// holding `VAULT`, and the payer's empty `MINT` account and position in the
+/// pool.
+struct Fixture {
+    context: ProgramTestContext,
+    pool: Pubkey,
+    reward_vault: Pubkey,
+    user_tokens: Pubkey,
+    user_deposit: Pubkey,
+}
+
+async fn funded() -> Fixture {
+    let authority = Pubkey::new_unique();
+    let (pool, bump) = Pubkey::find_program_address(&[b"pool", authority.as_ref()], &ID);
+    let (reward_vault, user_tokens) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let mut test = program_test();
+    test.add_account(pool, anchor_account(&pool_state(authority, bump), ID));
+    test.add_account(reward_vault, token_account(MINT, pool, VAULT));
+    let mut context = test.start_with_context().await;
+    let payer = context.payer.pubkey();
+    let (user_deposit, deposit_account) = position(pool, payer);
+    context.set_account(&user_deposit, &deposit_account.into());
+    context.set_account(&user_tokens, &token_account(MINT, payer, 0).into());
+    Fixture { context, pool, reward_vault, user_tokens, user_deposit }
+}
+
+impl Fixture {
+    /// Claims through the manual check, passing `pool_info` as the pool, in
+    /// a new slot so a repeated claim isn't deduplicated.
+    async fn claim(&mut self, pool_info: Pubkey) -> Result<(), TransactionError> {
+        let slot = self.context.banks_client.get_sysvar::<Clock>().await.unwrap().slot;
+        self.context.warp_to_slot(slot + 1).unwrap();
+        let claim = Instruction::new_with_bytes(
+            ID,
+            &instruction::ClaimRewardsManualOwnerCheck {}.data(),
+            accounts::ClaimRewardsManualOwnerCheck {
+                pool_info,
+                reward_vault_token_account: self.reward_vault,
+                user_token_account: self.user_tokens,
+                user_deposit: self.user_deposit,
+                user: self.context.payer.pubkey(),
+                token_program: spl_token::ID,
+            }
+            .to_account_metas(None),
+        );
+        let payer = self.context.payer.insecure_clone();
+        send(&mut self.context.banks_client, &[claim], &[&payer]).await
+    }
+
+    /// Claims with `account` at a fresh address in place of the pool.
+    async fn claim_with(&mut self, account: Account) -> Result<(), TransactionError> {
+        let key = Pubkey::new_unique();
+        self.context.set_account(&key, &account.into());
+        self.claim(key).await
+    }
+
+    async fn tokens(&mut self, key: Pubkey) -> u64 {
+        load::<TokenAccount>(&mut self.context.banks_client, key).await.amount
+    }
+}
+
+fn error(code: impl Into<u32>) -> TransactionError {
+    custom_error(0, code)
+}
+
+#[tokio::test]
+async fn the_real_pool_pays_what_the_position_accrued() {
+    let mut fixture = funded().await;
+
+    fixture.claim(fixture.pool).await.expect("owned by the program, a Pool");
+
+    assert_eq!(fixture.tokens(fixture.user_tokens).await, ACCRUED);
+    assert_eq!(fixture.tokens(fixture.reward_vault).await, VAULT - ACCRUED);
+    assert_eq!(load::<UserDeposit>(&mut fixture.context.banks_client, fixture.user_deposit).await.accrued_rewards, 0);
+    assert_eq!(load::<Pool>(&mut fixture.context.banks_client, fixture.pool).await.total_accrued, 0);
+}
+
+#[tokio::test]
+async fn a_second_claim_pays_nothing_already_paid() {
+    let mut fixture = funded().await;
+    fixture.claim(fixture.pool).await.expect("first claim");
+
+    fixture.claim(fixture.pool).await.expect("nothing left, but not an error");
+
+    assert_eq!(fixture.tokens(fixture.user_tokens).await, ACCRUED);
+    assert_eq!(fixture.tokens(fixture.reward_vault).await, VAULT - ACCRUED);
+}
+
+#[tokio::test]
+async fn another_users_position_is_rejected() {
+    let mut fixture = funded().await;
+    let (theirs, account) = position(fixture.pool, Pubkey::new_unique());
+    fixture.context.set_account(&theirs, &account.into());
+    fixture.user_deposit = theirs;
+
+    let err = fixture.claim(fixture.pool).await.expect_err("not the claimant's position");
+
+    assert_eq!(err, error(ValidationError::InvalidPDA));
+    assert_eq!(fixture.tokens(fixture.reward_vault).await, VAULT);
+}
+
+#[tokio::test]
+async fn pool_data_in_someone_elses_account_is_rejected() {
+    let mut fixture = funded().await;
+    let copy = anchor_account(&Pool { reward_rate: u64::MAX, ..pool_state(Pubkey::new_unique(), 0) }, system_program::ID);
+
+    let err = fixture.claim_with(copy).await.expect_err("not owned by the program");
+
+    assert_eq!(err, error(ValidationError::InvalidOwner));
+    assert_eq!(fixture.tokens(fixture.reward_vault).await, VAULT);
+}
+
+#[tokio::test]
+async fn another_account_type_of_the_program_is_rejected() {
+    let mut fixture = funded().await;
+    let config = Config { admin: Pubkey::new_unique(), fee_bps: 0, fee_holiday_until: 0, max_positions: 1, dust_threshold: 0, bump: 0 };
+
+    let err = fixture.claim_with(anchor_account(&config, ID)).await.expect_err("a Config, not a Pool");
+
+    assert_eq!(err, error(AnchorError::AccountDiscriminatorMismatch));
+}
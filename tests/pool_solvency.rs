@@ -0,0 +1,241 @@
+//! Pool solvency check on `claim_rewards_secure`.
+//!
+//! `pool.total_accrued` grows whenever a position accrues and shrinks by
+//! what each claim pays. A claim goes through only while the pool's reward
+//! vault covers it, including the payout being claimed.
+
+use account_validation::{accounts, instruction, Pool, ValidationError, ID};
+use anchor_lang::{InstructionData, ToAccountMetas};
+use anchor_spl::token::spl_token;
+use security_tests::{custom_error, load, pda, program_test, send_for_u64, system_account, token_account};
+use solana_program_test::ProgramTestContext;
+use solana_sdk::{
+    clock::Clock,
+    instruction::Instruction,
+    native_token::LAMPORTS_PER_SOL,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    system_program,
+    transaction::TransactionError,
+};
+
+const REWARD_MINT: Pubkey = Pubkey::new_from_array([0x61; 32]);
+const DEPOSIT_MINT: Pubkey = Pubkey::new_from_array([0x62; 32]);
+/// 0.1% of the position per second: 1 a second on `DEPOSIT`
+const RATE: u64 = 10;
+const DEPOSIT: u64 = 1_000;
+const START: i64 = 1_000_000;
+
+fn ix(accounts: impl ToAccountMetas, data: impl InstructionData) -> Instruction {
+    Instruction::new_with_bytes(ID, &data.data(), accounts.to_account_metas(None))
+}
+
+/// A depositor's keys, position and token accounts.
+struct Depositor {
+    keypair: Keypair,
+    user_deposit: Pubkey,
+    deposit_tokens: Pubkey,
+    reward_tokens: Pubkey,
+}
+
+/// A pool earning `RATE`, its reward vault, and `depositors` positions of
+/// `DEPOSIT` each opened at `START`.
+struct Fixture {
+    context: ProgramTestContext,
+    authority: Keypair,
+    pool: Pubkey,
+    pool_deposits: Pubkey,
+    reward_vault: Pubkey,
+    depositors: Vec<Depositor>,
+}
+
+async fn pool_with(depositors: usize, vault_balance: u64) -> Fixture {
+    let authority = Keypair::new();
+    let config = pda(&[b"config"], &ID);
+    let pool = pda(&[b"pool", authority.pubkey().as_ref()], &ID);
+    let (pool_deposits, reward_vault) = (Pubkey::new_unique(), Pubkey::new_unique());
+
+    let mut test = program_test();
+    test.add_account(authority.pubkey(), system_account(LAMPORTS_PER_SOL));
+    test.add_account(pool_deposits, token_account(DEPOSIT_MINT, pool, 0));
+    test.add_account(reward_vault, token_account(REWARD_MINT, pool, vault_balance));
+    let depositors: Vec<Depositor> = (0..depositors)
+        .map(|_| {
+            let keypair = Keypair::new();
+            let depositor = Depositor {
+                user_deposit: pda(&[b"user_deposit", pool.as_ref(), keypair.pubkey().as_ref()], &ID),
+                deposit_tokens: Pubkey::new_unique(),
+                reward_tokens: Pubkey::new_unique(),
+                keypair,
+            };
+            let owner = depositor.keypair.pubkey();
+            test.add_account(owner, system_account(LAMPORTS_PER_SOL));
+            test.add_account(depositor.deposit_tokens, token_account(DEPOSIT_MINT, owner, 2 * DEPOSIT));
+            test.add_account(depositor.reward_tokens, token_account(REWARD_MINT, owner, 0));
+            depositor
+        })
+        .collect();
+    let context = test.start_with_context().await;
+
+    let mut fixture = Fixture { context, authority, pool, pool_deposits, reward_vault, depositors };
+    fixture.at(START).await;
+    let setup = [
+        ix(
+            accounts::InitializeConfig { config, admin: fixture.authority.pubkey(), system_program: system_program::ID },
+            instruction::InitializeConfig { fee_bps: 0, max_positions: 1, dust_threshold: 0 },
+        ),
+        ix(
+            accounts::InitializePool {
+                config,
+                pool,
+                authority: fixture.authority.pubkey(),
+                system_program: system_program::ID,
+            },
+            instruction::InitializePool {
+                reward_rate: RATE,
+                global_tvl_cap: u64::MAX,
+                lock_secs: 0,
+                min_hold_secs: 0,
+                early_exit_bps: 0,
+                deposit_mint: DEPOSIT_MINT,
+                reward_mint: REWARD_MINT,
+            },
+        ),
+    ];
+    let authority = fixture.authority.insecure_clone();
+    send_for_u64(&mut fixture.context.banks_client, &setup, &[&authority]).await.expect("pool setup");
+    for depositor in 0..fixture.depositors.len() {
+        fixture.open(depositor).await;
+    }
+    fixture
+}
+
+impl Fixture {
+    /// Moves to a new slot at `unix_timestamp`, so a repeated transaction
+    /// isn't deduplicated.
+    async fn at(&mut self, unix_timestamp: i64) {
+        let clock: Clock = self.context.banks_client.get_sysvar().await.unwrap();
+        self.context.warp_to_slot(clock.slot + 1).unwrap();
+        let clock: Clock = self.context.banks_client.get_sysvar().await.unwrap();
+        self.context.set_sysvar(&Clock { unix_timestamp, ..clock });
+    }
+
+    async fn send_as(&mut self, depositor: usize, ixs: &[Instruction]) -> Result<u64, TransactionError> {
+        let signer = self.depositors[depositor].keypair.insecure_clone();
+        send_for_u64(&mut self.context.banks_client, ixs, &[&signer]).await
+    }
+
+    fn deposit_ix(&self, depositor: usize, amount: u64) -> Instruction {
+        let depositor = &self.depositors[depositor];
+        ix(
+            accounts::DepositSecureV2 {
+                config: pda(&[b"config"], &ID),
+                pool: self.pool,
+                authority: self.authority.pubkey(),
+                user_deposit: depositor.user_deposit,
+                user_token_account: depositor.deposit_tokens,
+                pool_token_account: self.pool_deposits,
+                user: depositor.keypair.pubkey(),
+                token_program: spl_token::ID,
+            },
+            instruction::DepositSecureV2 { amount },
+        )
+    }
+
+    async fn open(&mut self, depositor: usize) {
+        let user = self.depositors[depositor].keypair.pubkey();
+        let user_account = pda(&[b"user", user.as_ref()], &ID);
+        let open = [
+            ix(
+                accounts::InitializeUserAccount { user_account, user, system_program: system_program::ID },
+                instruction::InitializeUserAccount {},
+            ),
+            ix(
+                accounts::OpenDeposit {
+                    config: pda(&[b"config"], &ID),
+                    pool: self.pool,
+                    user_account,
+                    user_deposit: self.depositors[depositor].user_deposit,
+                    user,
+                    system_program: system_program::ID,
+                },
+                instruction::OpenDeposit {},
+            ),
+            self.deposit_ix(depositor, DEPOSIT),
+        ];
+        self.send_as(depositor, &open).await.expect("position setup");
+    }
+
+    /// Tops up a position at `secs` after `START`, which accrues it.
+    async fn top_up_after(&mut self, secs: i64, depositor: usize) {
+        self.at(START + secs).await;
+        let deposit = self.deposit_ix(depositor, 1);
+        self.send_as(depositor, &[deposit]).await.expect("top-up");
+    }
+
+    async fn claim_after(&mut self, secs: i64, depositor: usize) -> Result<u64, TransactionError> {
+        self.at(START + secs).await;
+        let claim = ix(
+            accounts::ClaimRewardsSecure {
+                pool: self.pool,
+                reward_vault_token_account: self.reward_vault,
+                user_token_account: self.depositors[depositor].reward_tokens,
+                user_deposit: self.depositors[depositor].user_deposit,
+                user: self.depositors[depositor].keypair.pubkey(),
+                token_program: spl_token::ID,
+            },
+            instruction::ClaimRewardsSecure {},
+        );
+        self.send_as(depositor, &[claim]).await
+    }
+
+    async fn total_accrued(&mut self) -> u64 {
+        load::<Pool>(&mut self.context.banks_client, self.pool).await.total_accrued
+    }
+}
+
+#[tokio::test]
+async fn accrual_and_payout_are_booked() {
+    let mut fixture = pool_with(1, 50).await;
+    fixture.top_up_after(20, 0).await;
+    assert_eq!(fixture.total_accrued().await, 20);
+
+    let paid = fixture.claim_after(50, 0).await.expect("vault covers the payout");
+
+    assert_eq!(paid, 50);
+    assert_eq!(fixture.total_accrued().await, 0);
+}
+
+#[tokio::test]
+async fn a_vault_short_of_the_pending_payout_rejects_the_claim() {
+    // Nothing has accrued before the claim itself
+    let mut fixture = pool_with(1, 49).await;
+    assert_eq!(fixture.total_accrued().await, 0);
+
+    let err = fixture.claim_after(50, 0).await.expect_err("50 owed, 49 held");
+
+    assert_eq!(err, custom_error(0, ValidationError::PoolInsolvent));
+}
+
+#[tokio::test]
+async fn rewards_owed_to_other_positions_count() {
+    let mut fixture = pool_with(2, 60).await;
+    fixture.top_up_after(50, 1).await;
+
+    // The vault covers this claim's 50, but not that and the other position's 50
+    let err = fixture.claim_after(50, 0).await.expect_err("the second position's rewards are owed too");
+
+    assert_eq!(err, custom_error(0, ValidationError::PoolInsolvent));
+    assert_eq!(fixture.total_accrued().await, 50);
+}
+
+#[tokio::test]
+async fn a_vault_covering_every_position_pays() {
+    let mut fixture = pool_with(2, 100).await;
+    fixture.top_up_after(50, 1).await;
+
+    assert_eq!(fixture.claim_after(50, 0).await, Ok(50));
+
+    // Only the second position's rewards are still owed
+    assert_eq!(fixture.total_accrued().await, 50);
+}
@@ -0,0 +1,159 @@
+//! Recreating a closed profile in the closing-accounts program.
+//!
+//! `recreate_profile` opens a fresh, empty profile once
+//! `profile_cooldown_secs` have passed since the tombstone's `closed_at` -
+//! exactly at the boundary included - and deletes the tombstone, so a
+//! later close starts a new cooldown.
+
+use anchor_lang::{AccountDeserialize, InstructionData, ToAccountMetas};
+use closing_accounts::{accounts, instruction, CloseError, Config, ProfileTombstone, UserProfile, ID};
+use security_tests::{anchor_account, custom_error, load, pda, program_test, send, set_time, system_account};
+use solana_program_test::ProgramTestContext;
+use solana_sdk::{
+    instruction::Instruction,
+    native_token::LAMPORTS_PER_SOL,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    system_program,
+    transaction::TransactionError,
+};
+
+const COOLDOWN_SECS: i64 = 86_400;
+const CLOSED_AT: i64 = 1_000_000;
+const POINTS: u64 = 750;
+
+fn ix(accounts: impl ToAccountMetas, data: impl InstructionData) -> Instruction {
+    Instruction::new_with_bytes(ID, &data.data(), accounts.to_account_metas(None))
+}
+
+/// An owner whose profile, holding `POINTS`, was closed at `CLOSED_AT`.
+struct Fixture {
+    context: ProgramTestContext,
+    owner: Keypair,
+    profile: Pubkey,
+    tombstone: Pubkey,
+}
+
+async fn closed() -> Fixture {
+    let owner = Keypair::new();
+    let (config, config_bump) = Pubkey::find_program_address(&[b"config"], &ID);
+    let (profile, profile_bump) = Pubkey::find_program_address(&[b"profile", owner.pubkey().as_ref()], &ID);
+
+    let mut test = program_test();
+    test.add_account(owner.pubkey(), system_account(LAMPORTS_PER_SOL));
+    let state = Config {
+        admin: Pubkey::new_unique(),
+        fee_bps: 100,
+        reward_mint: Pubkey::new_unique(),
+        reward_authority_bump: 0,
+        profile_cooldown_secs: COOLDOWN_SECS,
+        reopen_window_secs: 0,
+        max_closes_per_window: 10,
+        close_window_secs: 3_600,
+        bump: config_bump,
+    };
+    test.add_account(config, anchor_account(&state, ID));
+    let state = UserProfile { owner: owner.pubkey(), points: POINTS, bump: profile_bump };
+    test.add_account(profile, anchor_account(&state, ID));
+    let context = test.start_with_context().await;
+
+    let tombstone = pda(&[b"tombstone", owner.pubkey().as_ref()], &ID);
+    let mut fixture = Fixture { context, owner, profile, tombstone };
+    fixture.close_at(CLOSED_AT).await.expect("profile closed");
+    fixture
+}
+
+impl Fixture {
+    async fn close_at(&mut self, unix_timestamp: i64) -> Result<(), TransactionError> {
+        set_time(&mut self.context, unix_timestamp).await;
+        let owner = self.owner.insecure_clone();
+        let close = ix(
+            accounts::CloseProfileSecure {
+                profile: self.profile,
+                tombstone: self.tombstone,
+                recipient: owner.pubkey(),
+                owner: owner.pubkey(),
+                system_program: system_program::ID,
+            },
+            instruction::CloseProfileSecure {},
+        );
+        send(&mut self.context.banks_client, &[close], &[&owner]).await
+    }
+
+    async fn recreate_at(&mut self, unix_timestamp: i64) -> Result<(), TransactionError> {
+        set_time(&mut self.context, unix_timestamp).await;
+        let owner = self.owner.insecure_clone();
+        let recreate = ix(
+            accounts::RecreateProfile {
+                config: pda(&[b"config"], &ID),
+                tombstone: self.tombstone,
+                profile: self.profile,
+                owner: owner.pubkey(),
+                system_program: system_program::ID,
+            },
+            instruction::RecreateProfile {},
+        );
+        send(&mut self.context.banks_client, &[recreate], &[&owner]).await
+    }
+
+    async fn profile(&mut self) -> Option<UserProfile> {
+        let account = self.context.banks_client.get_account(self.profile).await.unwrap()?;
+        Some(UserProfile::try_deserialize(&mut account.data.as_slice()).unwrap())
+    }
+
+    async fn tombstone_exists(&mut self) -> bool {
+        self.context.banks_client.get_account(self.tombstone).await.unwrap().is_some()
+    }
+}
+
+fn cooldown_active() -> TransactionError {
+    custom_error(0, CloseError::CooldownActive)
+}
+
+#[tokio::test]
+async fn recreating_before_the_cooldown_is_rejected() {
+    let mut fixture = closed().await;
+
+    let err = fixture.recreate_at(CLOSED_AT + COOLDOWN_SECS - 1).await.expect_err("one second early");
+
+    assert_eq!(err, cooldown_active());
+    assert!(fixture.profile().await.is_none());
+    assert!(fixture.tombstone_exists().await);
+}
+
+#[tokio::test]
+async fn recreating_exactly_at_the_end_of_the_cooldown_is_allowed() {
+    let mut fixture = closed().await;
+
+    fixture.recreate_at(CLOSED_AT + COOLDOWN_SECS).await.expect("at the boundary");
+
+    let profile = fixture.profile().await.expect("profile recreated");
+    assert_eq!((profile.owner, profile.points), (fixture.owner.pubkey(), 0), "a fresh profile, not the old one");
+    assert!(!fixture.tombstone_exists().await);
+}
+
+#[tokio::test]
+async fn recreating_after_the_cooldown_deletes_the_tombstone() {
+    let mut fixture = closed().await;
+    let tombstone: ProfileTombstone = load(&mut fixture.context.banks_client, fixture.tombstone).await;
+    assert_eq!(tombstone.closed_at, CLOSED_AT);
+
+    fixture.recreate_at(CLOSED_AT + 2 * COOLDOWN_SECS).await.expect("well after the cooldown");
+
+    assert!(fixture.profile().await.is_some());
+    assert!(!fixture.tombstone_exists().await);
+}
+
+#[tokio::test]
+async fn the_next_close_starts_a_new_cooldown() {
+    let mut fixture = closed().await;
+    let recreated_at = CLOSED_AT + COOLDOWN_SECS;
+    fixture.recreate_at(recreated_at).await.expect("recreated");
+
+    // Only possible because the old tombstone is gone
+    fixture.close_at(recreated_at + 10).await.expect("closed again");
+
+    let err = fixture.recreate_at(recreated_at + COOLDOWN_SECS).await.expect_err("the new close counts");
+    assert_eq!(err, cooldown_active());
+    fixture.recreate_at(recreated_at + 10 + COOLDOWN_SECS).await.expect("a cooldown after the second close");
+}
@@ -0,0 +1,149 @@
+//! Reopening a closed profile in the closing-accounts program.
+//!
+//! `close_profile_secure` saves the profile's points on its tombstone;
+//! `reopen` restores them until `reopen_window_secs` after `closed_at`, and
+//! is rejected from the end of the window on. Until then the tombstone
+//! also records who closed the profile; reopening deletes that record.
+
+use anchor_lang::{AccountDeserialize, InstructionData, ToAccountMetas};
+use closing_accounts::{accounts, instruction, CloseError, Config, ProfileTombstone, UserProfile, ID};
+use security_tests::{anchor_account, custom_error, load, pda, program_test, send, set_time, system_account};
+use solana_program_test::ProgramTestContext;
+use solana_sdk::{
+    instruction::Instruction,
+    native_token::LAMPORTS_PER_SOL,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    system_program,
+    transaction::TransactionError,
+};
+
+const REOPEN_WINDOW_SECS: i64 = 3_600;
+const CLOSED_AT: i64 = 1_000_000;
+const POINTS: u64 = 750;
+
+fn ix(accounts: impl ToAccountMetas, data: impl InstructionData) -> Instruction {
+    Instruction::new_with_bytes(ID, &data.data(), accounts.to_account_metas(None))
+}
+
+/// An owner whose profile, holding `POINTS`, was closed at `CLOSED_AT`.
+struct Fixture {
+    context: ProgramTestContext,
+    owner: Keypair,
+    profile: Pubkey,
+    tombstone: Pubkey,
+}
+
+async fn closed() -> Fixture {
+    let owner = Keypair::new();
+    let (config, config_bump) = Pubkey::find_program_address(&[b"config"], &ID);
+    let (profile, profile_bump) = Pubkey::find_program_address(&[b"profile", owner.pubkey().as_ref()], &ID);
+    let (_, reward_authority_bump) = Pubkey::find_program_address(&[b"reward_authority"], &ID);
+
+    let mut test = program_test();
+    test.add_account(owner.pubkey(), system_account(LAMPORTS_PER_SOL));
+    let state = Config {
+        admin: Pubkey::new_unique(),
+        fee_bps: 100,
+        reward_mint: Pubkey::new_unique(),
+        reward_authority_bump,
+        profile_cooldown_secs: 2 * REOPEN_WINDOW_SECS,
+        reopen_window_secs: REOPEN_WINDOW_SECS,
+        max_closes_per_window: 10,
+        close_window_secs: 3_600,
+        bump: config_bump,
+    };
+    test.add_account(config, anchor_account(&state, ID));
+    let state = UserProfile { owner: owner.pubkey(), points: POINTS, bump: profile_bump };
+    test.add_account(profile, anchor_account(&state, ID));
+    let mut context = test.start_with_context().await;
+    set_time(&mut context, CLOSED_AT).await;
+
+    let tombstone = pda(&[b"tombstone", owner.pubkey().as_ref()], &ID);
+    let close = ix(
+        accounts::CloseProfileSecure {
+            profile,
+            tombstone,
+            recipient: owner.pubkey(),
+            owner: owner.pubkey(),
+            system_program: system_program::ID,
+        },
+        instruction::CloseProfileSecure {},
+    );
+    send(&mut context.banks_client, &[close], &[&owner]).await.expect("profile closed");
+    Fixture { context, owner, profile, tombstone }
+}
+
+impl Fixture {
+    async fn reopen_at(&mut self, unix_timestamp: i64) -> Result<(), TransactionError> {
+        set_time(&mut self.context, unix_timestamp).await;
+        let reopen = ix(
+            accounts::Reopen {
+                config: pda(&[b"config"], &ID),
+                tombstone: self.tombstone,
+                profile: self.profile,
+                owner: self.owner.pubkey(),
+                system_program: system_program::ID,
+            },
+            instruction::Reopen {},
+        );
+        let owner = self.owner.insecure_clone();
+        send(&mut self.context.banks_client, &[reopen], &[&owner]).await
+    }
+
+    async fn profile(&mut self) -> Option<UserProfile> {
+        let account = self.context.banks_client.get_account(self.profile).await.unwrap()?;
+        Some(UserProfile::try_deserialize(&mut account.data.as_slice()).unwrap())
+    }
+
+    async fn tombstone_exists(&mut self) -> bool {
+        self.context.banks_client.get_account(self.tombstone).await.unwrap().is_some()
+    }
+}
+
+fn window_closed() -> TransactionError {
+    custom_error(0, CloseError::ReopenWindowClosed)
+}
+
+#[tokio::test]
+async fn reopening_inside_the_window_restores_the_profile() {
+    let mut fixture = closed().await;
+
+    fixture.reopen_at(CLOSED_AT + REOPEN_WINDOW_SECS - 1).await.expect("inside the window");
+
+    let profile = fixture.profile().await.expect("profile restored");
+    assert_eq!((profile.owner, profile.points), (fixture.owner.pubkey(), POINTS));
+    assert!(!fixture.tombstone_exists().await);
+}
+
+#[tokio::test]
+async fn the_tombstone_attributes_the_close_until_reopened() {
+    let mut fixture = closed().await;
+    let tombstone: ProfileTombstone = load(&mut fixture.context.banks_client, fixture.tombstone).await;
+    assert_eq!((tombstone.closed_by, tombstone.closed_at), (fixture.owner.pubkey(), CLOSED_AT));
+
+    fixture.reopen_at(CLOSED_AT + 1).await.expect("inside the window");
+
+    assert!(!fixture.tombstone_exists().await, "the attribution goes with it");
+}
+
+#[tokio::test]
+async fn reopening_exactly_at_the_end_of_the_window_is_rejected() {
+    let mut fixture = closed().await;
+
+    let err = fixture.reopen_at(CLOSED_AT + REOPEN_WINDOW_SECS).await.expect_err("window over");
+
+    assert_eq!(err, window_closed());
+    assert!(fixture.profile().await.is_none());
+    assert!(fixture.tombstone_exists().await);
+}
+
+#[tokio::test]
+async fn reopening_after_the_window_is_rejected() {
+    let mut fixture = closed().await;
+
+    let err = fixture.reopen_at(CLOSED_AT + REOPEN_WINDOW_SECS + 1).await.expect_err("window over");
+
+    assert_eq!(err, window_closed());
+    assert!(fixture.profile().await.is_none());
+}
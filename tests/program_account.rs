@@ -0,0 +1,50 @@
+//! Program accounts passed where state is expected.
+//!
+//! `account_validation::guards::assert_not_program_account` rejects any
+//! account that is executable or owned by one of the BPF loaders, and lets
+//! an ordinary data account through.
+
+use account_validation::guards::assert_not_program_account;
+use account_validation::{ValidationError, ID};
+use anchor_lang::prelude::{AccountInfo, ProgramError, Pubkey};
+use anchor_lang::solana_program::{bpf_loader, bpf_loader_deprecated, bpf_loader_upgradeable};
+
+/// Checks an account owned by `owner`, with some data, against the guard.
+fn check(owner: Pubkey, executable: bool) -> anchor_lang::Result<()> {
+    let key = Pubkey::new_unique();
+    let mut lamports = 1;
+    let mut data = vec![0xab; 64];
+    let info = AccountInfo::new(&key, false, false, &mut lamports, &mut data, &owner, executable, 0);
+    assert_not_program_account(&info)
+}
+
+fn rejected(result: anchor_lang::Result<()>) -> ProgramError {
+    result.expect_err("a program account").into()
+}
+
+fn not_allowed() -> ProgramError {
+    ProgramError::Custom(ValidationError::ProgramAccountNotAllowed.into())
+}
+
+#[test]
+fn a_data_account_is_accepted() {
+    check(ID, false).expect("plain state");
+}
+
+#[test]
+fn an_executable_program_account_is_rejected() {
+    assert_eq!(rejected(check(bpf_loader_upgradeable::ID, true)), not_allowed());
+}
+
+#[test]
+fn an_executable_account_of_any_owner_is_rejected() {
+    assert_eq!(rejected(check(ID, true)), not_allowed());
+}
+
+#[test]
+fn loader_owned_accounts_are_rejected_even_if_not_executable() {
+    // e.g. an upgradeable program's program-data account
+    for loader in [bpf_loader::ID, bpf_loader_deprecated::ID, bpf_loader_upgradeable::ID] {
+        assert_eq!(rejected(check(loader, false)), not_allowed(), "{}", loader);
+    }
+}
@@ -0,0 +1,110 @@
+//! Growing an account with `realloc` in the reinitialization program.
+//!
+//! `grow_vault` drops a `VaultV2`'s notes and grows it to a larger size in
+//! the same instruction. A shrink leaves the bytes in place, so with
+//! `zero_init = false` the old note is still there past the new, empty
+//! notes. `grow_vault_secure` keeps the notes, zeroes what it grows into
+//! and refuses any size that isn't a grow.
+
+use anchor_lang::{AccountDeserialize, InstructionData, ToAccountMetas};
+use reinitialization::{accounts, instruction, ReinitError, VaultV2, ID};
+use security_tests::{pda, program_test, send};
+use solana_program_test::BanksClient;
+use solana_sdk::{
+    instruction::{Instruction, InstructionError},
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    system_program,
+    transaction::TransactionError,
+};
+
+const NOTE: [u8; 64] = [0xab; 64];
+const GROWN_LEN: usize = VaultV2::space(0) + 128;
+
+struct Fixture {
+    banks: BanksClient,
+    authority: Keypair,
+    vault: Pubkey,
+}
+
+/// A vault holding `NOTE`, sized to fit it exactly.
+async fn vault_with_note() -> Fixture {
+    let (banks, authority, _) = program_test().start().await;
+    let vault = pda(&[b"vault_v2", authority.pubkey().as_ref()], &ID);
+    let mut fixture = Fixture { banks, authority, vault };
+    let init = fixture.ix(
+        accounts::InitializeVaultV2 { vault, authority: fixture.authority.pubkey(), system_program: system_program::ID },
+        instruction::InitializeVaultV2 { notes: NOTE.to_vec() },
+    );
+    fixture.send(init).await.expect("setup");
+    fixture
+}
+
+impl Fixture {
+    fn ix(&self, accounts: impl ToAccountMetas, data: impl InstructionData) -> Instruction {
+        Instruction::new_with_bytes(ID, &data.data(), accounts.to_account_metas(None))
+    }
+
+    async fn send(&mut self, ix: Instruction) -> Result<(), TransactionError> {
+        send(&mut self.banks, &[ix], &[&self.authority]).await
+    }
+
+    async fn grow(&mut self, data: impl InstructionData) -> Result<(), TransactionError> {
+        let accounts = accounts::GrowVault {
+            vault: self.vault,
+            authority: self.authority.pubkey(),
+            system_program: system_program::ID,
+        };
+        self.send(self.ix(accounts, data)).await
+    }
+
+    async fn data(&mut self) -> Vec<u8> {
+        self.banks.get_account(self.vault).await.unwrap().unwrap().data
+    }
+}
+
+#[tokio::test]
+async fn growing_without_zero_init_hands_back_the_dropped_note() {
+    let mut fixture = vault_with_note().await;
+
+    fixture.grow(instruction::GrowVault { new_len: GROWN_LEN as u32 }).await.expect("grows");
+
+    let data = fixture.data().await;
+    assert_eq!(data.len(), GROWN_LEN);
+    assert!(VaultV2::try_deserialize(&mut data.as_slice()).unwrap().notes.is_empty());
+    // The "new" region starts with the note that was just dropped
+    let grown = &data[VaultV2::space(0)..];
+    assert_eq!(grown[..NOTE.len()], NOTE);
+    assert!(grown[NOTE.len()..].iter().all(|byte| *byte == 0));
+}
+
+#[tokio::test]
+async fn the_secure_grow_keeps_the_notes_and_zeroes_the_new_space() {
+    let mut fixture = vault_with_note().await;
+
+    fixture.grow(instruction::GrowVaultSecure { new_len: GROWN_LEN as u32 }).await.expect("grows");
+
+    let data = fixture.data().await;
+    assert_eq!(data.len(), GROWN_LEN);
+    assert_eq!(VaultV2::try_deserialize(&mut data.as_slice()).unwrap().notes, NOTE);
+    assert!(data[VaultV2::space(NOTE.len())..].iter().all(|byte| *byte == 0));
+}
+
+#[tokio::test]
+async fn the_secure_grow_refuses_anything_but_a_grow() {
+    let mut fixture = vault_with_note().await;
+    let current = VaultV2::space(NOTE.len());
+    let invalid_size = TransactionError::InstructionError(0, InstructionError::Custom(ReinitError::InvalidSize.into()));
+
+    for new_len in [current - 1, current] {
+        let err = fixture
+            .grow(instruction::GrowVaultSecure { new_len: new_len as u32 })
+            .await
+            .expect_err("not larger than the account");
+        assert_eq!(err, invalid_size, "new_len {}", new_len);
+    }
+
+    let data = fixture.data().await;
+    assert_eq!(data.len(), current);
+    assert_eq!(VaultV2::try_deserialize(&mut data.as_slice()).unwrap().notes, NOTE);
+}
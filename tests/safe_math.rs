@@ -0,0 +1,147 @@
+//! The integer-overflow program's `safe_math` helpers at the edges of u64.
+//!
+//! `mul_div` and `mul_div_ceil` multiply in u128, so an intermediate past
+//! u64::MAX is fine as long as the result fits. `calculate_price_secure`
+//! uses them to add a fee to a subtotal too large for `subtotal * fee_bps`,
+//! and `Bps` builds its floor and ceiling rates on them. `ceil_div` and
+//! `calculate_bps_fee` used to overflow on their way to a result that fits.
+
+use anchor_lang::error::Error;
+use anchor_lang::{InstructionData, ToAccountMetas};
+use integer_overflow::safe_math::{calculate_bps_fee, ceil_div, mul_div, mul_div_ceil, Bps};
+use integer_overflow::{accounts, instruction, Config, MathError, SecurityError, ID};
+use security_tests::{anchor_account, program_test};
+use solana_sdk::{instruction::Instruction, pubkey::Pubkey, signature::Signer, transaction::Transaction};
+use std::fmt::Debug;
+
+fn rejects_with<E: Copy + Debug + Into<u32>>(result: anchor_lang::Result<u64>, expected: E) {
+    match result {
+        Err(Error::AnchorError(e)) => assert_eq!(e.error_code_number, expected.into()),
+        other => panic!("expected {:?}, got {:?}", expected, other),
+    }
+}
+
+#[test]
+fn a_product_past_u64_max_is_fine_if_the_result_fits() {
+    assert_eq!(mul_div(u64::MAX, u64::MAX, u64::MAX).unwrap(), u64::MAX);
+    assert_eq!(mul_div(u64::MAX, 10_000, 10_000).unwrap(), u64::MAX);
+    assert_eq!(mul_div(u64::MAX, 2, 4).unwrap(), u64::MAX / 2);
+}
+
+#[test]
+fn a_result_past_u64_max_overflows() {
+    rejects_with(mul_div(u64::MAX, 2, 1), SecurityError::Overflow);
+    rejects_with(mul_div(u64::MAX, u64::MAX, u64::MAX - 1), SecurityError::Overflow);
+    rejects_with(mul_div_ceil(u64::MAX, u64::MAX, u64::MAX - 1), SecurityError::Overflow);
+}
+
+#[test]
+fn a_zero_denominator_is_rejected() {
+    rejects_with(mul_div(1, 1, 0), SecurityError::DivisionByZero);
+    rejects_with(mul_div_ceil(1, 1, 0), SecurityError::DivisionByZero);
+}
+
+#[test]
+fn mul_div_ceil_rounds_up_only_when_inexact() {
+    assert_eq!(mul_div(1, 1, 10_000).unwrap(), 0);
+    assert_eq!(mul_div_ceil(1, 1, 10_000).unwrap(), 1);
+    assert_eq!(mul_div_ceil(10_000, 3, 10_000).unwrap(), 3);
+    assert_eq!(mul_div_ceil(u64::MAX, 1, 2).unwrap(), u64::MAX / 2 + 1);
+    assert_eq!(mul_div_ceil(u64::MAX, u64::MAX, u64::MAX).unwrap(), u64::MAX);
+}
+
+#[test]
+fn rounding_up_past_u64_max_overflows() {
+    // a * 31 == 2^65 - 1, so a * 31 / 2 is u64::MAX with a remainder of 1
+    let a = ((1u128 << 65) - 1) / 31;
+    let a = u64::try_from(a).unwrap();
+
+    assert_eq!(mul_div(a, 31, 2).unwrap(), u64::MAX);
+    rejects_with(mul_div_ceil(a, 31, 2), SecurityError::Overflow);
+}
+
+#[test]
+fn bps_of_nothing_is_nothing() {
+    assert_eq!(Bps(100).apply_floor(0).unwrap(), 0);
+    assert_eq!(Bps(100).apply_ceil(0).unwrap(), 0);
+}
+
+#[test]
+fn a_ceiling_fee_on_99_at_1_percent_is_not_zero() {
+    // 99 * 100 / 10_000 = 0.99
+    assert_eq!(Bps(100).apply_floor(99).unwrap(), 0);
+    assert_eq!(Bps(100).apply_ceil(99).unwrap(), 1);
+}
+
+#[test]
+fn bps_work_across_the_whole_u64_range() {
+    assert_eq!(Bps(10_000).apply_floor(u64::MAX).unwrap(), u64::MAX);
+    assert_eq!(Bps(10_000).apply_ceil(u64::MAX).unwrap(), u64::MAX);
+    assert_eq!(Bps(5_000).apply_ceil(u64::MAX).unwrap(), u64::MAX / 2 + 1);
+    assert_eq!(Bps(0).apply_ceil(u64::MAX).unwrap(), 0);
+}
+
+#[test]
+fn more_than_10000_bps_is_rejected() {
+    rejects_with(Bps(10_001).apply_floor(100), MathError::InvalidBps);
+    rejects_with(Bps(10_001).apply_ceil(0), MathError::InvalidBps);
+}
+
+#[test]
+fn ceiling_division_of_u64_max_fits() {
+    assert_eq!(ceil_div(u64::MAX, 1).unwrap(), u64::MAX);
+    assert_eq!(ceil_div(u64::MAX, 10_000).unwrap(), u64::MAX / 10_000 + 1);
+}
+
+#[test]
+fn a_full_fee_on_u64_max_is_u64_max() {
+    assert_eq!(calculate_bps_fee(u64::MAX, 10_000).unwrap(), u64::MAX);
+    assert_eq!(calculate_bps_fee(u64::MAX, 1).unwrap(), u64::MAX / 10_000 + 1);
+}
+
+fn config(price_per_unit: u64, fee_bps: u16) -> (Pubkey, Config) {
+    let (config, bump) = Pubkey::find_program_address(&[b"config"], &ID);
+    (config, Config { admin: Pubkey::new_unique(), price_per_unit, fee_bps, referral_bps: 0, bump })
+}
+
+/// Sends `ix` against `config` and returns its u64 return data.
+async fn returned((config, state): (Pubkey, Config), ix: Instruction) -> u64 {
+    let mut test = program_test();
+    test.add_account(config, anchor_account(&state, ID));
+    let (mut banks, payer, blockhash) = test.start().await;
+
+    let tx = Transaction::new_signed_with_payer(&[ix], Some(&payer.pubkey()), &[&payer], blockhash);
+    let result = banks.process_transaction_with_metadata(tx).await.unwrap();
+    result.result.expect("the result fits in u64");
+
+    // The runtime strips trailing zero bytes from return data
+    let data = result.metadata.and_then(|metadata| metadata.return_data).unwrap().data;
+    let mut bytes = [0u8; 8];
+    bytes[..data.len()].copy_from_slice(&data);
+    u64::from_le_bytes(bytes)
+}
+
+#[tokio::test]
+async fn a_large_price_gets_its_fee_without_overflowing() {
+    let config = config(5_000_000_000, 100);
+    let ix = Instruction::new_with_bytes(
+        ID,
+        &instruction::CalculatePriceSecure { quantity: 3_000_000_000 }.data(),
+        accounts::PriceCalculation { config: config.0 }.to_account_metas(None),
+    );
+
+    // 1.5e19 subtotal, whose 1% fee would need 1.5e21 as a u64 intermediate
+    assert_eq!(returned(config, ix).await, 15_000_000_000_000_000_000 + 150_000_000_000_000_000);
+}
+
+#[tokio::test]
+async fn a_full_fee_on_u64_max_is_charged_in_full() {
+    let config = config(0, 10_000);
+    let ix = Instruction::new_with_bytes(
+        ID,
+        &instruction::CalculateFeeSecure { amount: u64::MAX }.data(),
+        accounts::FeeCalculation { config: config.0 }.to_account_metas(None),
+    );
+
+    assert_eq!(returned(config, ix).await, u64::MAX);
+}
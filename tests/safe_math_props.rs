@@ -0,0 +1,83 @@
+//! Property tests for the integer-overflow program's `safe_math` helpers.
+//!
+//! Each helper is checked against the same computation in u128, where none
+//! of these inputs can overflow: either the helper returns the reference
+//! result, or the reference result doesn't fit and the helper returns the
+//! matching error.
+
+use anchor_lang::error::Error;
+use integer_overflow::safe_math::{calculate_bps_fee, ceil_div, safe_add, safe_mul, safe_sub};
+use integer_overflow::SecurityError;
+use proptest::prelude::*;
+
+fn code(result: anchor_lang::Result<u64>) -> Option<u32> {
+    match result {
+        Err(Error::AnchorError(e)) => Some(e.error_code_number),
+        _ => None,
+    }
+}
+
+fn error(expected: SecurityError) -> Option<u32> {
+    Some(expected.into())
+}
+
+/// What `result` should be, given the exact value in u128.
+fn fits(result: anchor_lang::Result<u64>, exact: u128, otherwise: SecurityError) -> Result<(), TestCaseError> {
+    match u64::try_from(exact) {
+        Ok(expected) => prop_assert_eq!(result.ok(), Some(expected)),
+        Err(_) => prop_assert_eq!(code(result), error(otherwise)),
+    }
+    Ok(())
+}
+
+#[test]
+fn ceil_div_of_u64_max_does_not_overflow() {
+    // `(a + b - 1) / b` would overflow here before dividing
+    assert_eq!(ceil_div(u64::MAX, 2).unwrap(), 1 << 63);
+    assert_eq!(ceil_div(u64::MAX, u64::MAX).unwrap(), 1);
+    assert_eq!(ceil_div(u64::MAX - 1, u64::MAX).unwrap(), 1);
+}
+
+proptest! {
+    #[test]
+    fn ceil_div_is_the_exact_ceiling(a: u64, b in 1..=u64::MAX) {
+        let exact = (a as u128).div_ceil(b as u128);
+        prop_assert_eq!(ceil_div(a, b).unwrap() as u128, exact);
+    }
+
+    #[test]
+    fn ceil_div_by_zero_is_rejected(a: u64) {
+        prop_assert_eq!(code(ceil_div(a, 0)), error(SecurityError::DivisionByZero));
+    }
+
+    #[test]
+    fn safe_add_matches_u128(a: u64, b: u64) {
+        fits(safe_add(a, b), a as u128 + b as u128, SecurityError::Overflow)?;
+    }
+
+    #[test]
+    fn safe_sub_matches_u128(a: u64, b: u64) {
+        match (a as u128).checked_sub(b as u128) {
+            Some(exact) => prop_assert_eq!(safe_sub(a, b).ok(), Some(exact as u64)),
+            None => prop_assert_eq!(code(safe_sub(a, b)), error(SecurityError::InsufficientFunds)),
+        }
+    }
+
+    #[test]
+    fn safe_mul_matches_u128(a: u64, b: u64) {
+        fits(safe_mul(a, b), a as u128 * b as u128, SecurityError::Overflow)?;
+    }
+
+    #[test]
+    fn small_operands_never_overflow(a in 0..=u32::MAX as u64, b in 0..=u32::MAX as u64) {
+        prop_assert!(safe_add(a, b).is_ok());
+        prop_assert!(safe_mul(a, b).is_ok());
+    }
+
+    #[test]
+    fn a_bps_fee_is_the_exact_ceiling_and_never_zero(amount in 1..=u64::MAX, bps in 1..=10_000u16) {
+        let fee = calculate_bps_fee(amount, bps).unwrap();
+        prop_assert!(fee > 0);
+        prop_assert_eq!(fee as u128, (amount as u128 * bps as u128).div_ceil(10_000));
+    }
+}
@@ -0,0 +1,226 @@
+//! The `safe_transfer` capstone in the arbitrary-CPI program, one test per
+//! guard.
+//!
+//! Two vaults hold the same mint. Each vault's tokens sit in its own
+//! `[b"vault_token", vault]` account with the vault PDA as token authority,
+//! so a vault's signer seeds can't move another vault's tokens.
+
+use anchor_lang::error::ErrorCode as AnchorError;
+use anchor_lang::{InstructionData, ToAccountMetas};
+use anchor_spl::token::spl_token;
+use arbitrary_cpi::{accounts, instruction, CpiError, SecurityError, Vault, ID};
+use security_tests::{
+    anchor_account, custom_error, load, mint_account, pda, program_test, send, system_account, token_account,
+};
+use solana_program_test::{BanksClient, ProgramTest};
+use solana_sdk::{
+    instruction::Instruction,
+    native_token::LAMPORTS_PER_SOL,
+    program_pack::Pack,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    system_program,
+    transaction::TransactionError,
+};
+
+const BALANCE: u64 = 1_000;
+
+/// A vault of `MINT`'s tokens, its token account and a wallet of its
+/// authority's to pay out to.
+struct Holder {
+    authority: Keypair,
+    vault: Pubkey,
+    vault_token_account: Pubkey,
+    wallet: Pubkey,
+}
+
+fn open_vault(test: &mut ProgramTest, mint: Pubkey) -> Holder {
+    let authority = Keypair::new();
+    let (vault, bump) = Pubkey::find_program_address(&[b"vault", authority.pubkey().as_ref()], &ID);
+    let vault_authority_bump = Pubkey::find_program_address(&[b"vault_authority"], &ID).1;
+    let state = Vault { authority: authority.pubkey(), mint, balance: BALANCE, vault_authority_bump, bump };
+    let vault_token_account = pda(&[b"vault_token", vault.as_ref()], &ID);
+    let wallet = Pubkey::new_unique();
+
+    test.add_account(authority.pubkey(), system_account(LAMPORTS_PER_SOL));
+    test.add_account(vault, anchor_account(&state, ID));
+    test.add_account(vault_token_account, token_account(mint, vault, BALANCE));
+    test.add_account(wallet, token_account(mint, authority.pubkey(), 0));
+    Holder { authority, vault, vault_token_account, wallet }
+}
+
+/// The victim's and the attacker's vaults for one mint, plus the attacker's
+/// wallet for a second mint and a token account the attacker's vault
+/// controls that isn't its `vault_token` account.
+struct Fixture {
+    banks: BanksClient,
+    mint: Pubkey,
+    victim: Holder,
+    attacker: Holder,
+    other_mint_wallet: Pubkey,
+    stray_token_account: Pubkey,
+}
+
+async fn two_vaults() -> Fixture {
+    let mint = Pubkey::new_unique();
+    let other_mint = Pubkey::new_unique();
+    let mut test = program_test();
+    test.add_account(mint, mint_account(Pubkey::new_unique(), 6));
+    test.add_account(other_mint, mint_account(Pubkey::new_unique(), 6));
+    let victim = open_vault(&mut test, mint);
+    let attacker = open_vault(&mut test, mint);
+
+    let other_mint_wallet = Pubkey::new_unique();
+    test.add_account(other_mint_wallet, token_account(other_mint, attacker.authority.pubkey(), 0));
+    let stray_token_account = Pubkey::new_unique();
+    test.add_account(stray_token_account, token_account(mint, attacker.vault, BALANCE));
+
+    let (banks, _payer, _) = test.start().await;
+    Fixture { banks, mint, victim, attacker, other_mint_wallet, stray_token_account }
+}
+
+/// Every account the attacker would pass for their own vault.
+fn attackers(fixture: &Fixture) -> accounts::SafeTransfer {
+    accounts::SafeTransfer {
+        vault: fixture.attacker.vault,
+        mint: fixture.mint,
+        vault_token_account: fixture.attacker.vault_token_account,
+        user_token_account: fixture.attacker.wallet,
+        token_program: spl_token::ID,
+        authority: fixture.attacker.authority.pubkey(),
+    }
+}
+
+fn safe_transfer(accounts: accounts::SafeTransfer, amount: u64) -> Instruction {
+    Instruction::new_with_bytes(ID, &instruction::SafeTransfer { amount }.data(), accounts.to_account_metas(None))
+}
+
+impl Fixture {
+    async fn send_as_attacker(&mut self, ix: Instruction) -> Result<(), TransactionError> {
+        let attacker = self.attacker.authority.insecure_clone();
+        send(&mut self.banks, &[ix], &[&attacker]).await
+    }
+
+    async fn tokens(&mut self, key: Pubkey) -> u64 {
+        let account = self.banks.get_account(key).await.unwrap().unwrap();
+        spl_token::state::Account::unpack(&account.data).unwrap().amount
+    }
+}
+
+#[tokio::test]
+async fn the_vaults_own_tokens_move_and_the_books_follow() {
+    let mut fixture = two_vaults().await;
+    let accounts = attackers(&fixture);
+
+    fixture.send_as_attacker(safe_transfer(accounts, 400)).await.expect("own vault");
+
+    assert_eq!(fixture.tokens(fixture.attacker.wallet).await, 400);
+    assert_eq!(fixture.tokens(fixture.attacker.vault_token_account).await, BALANCE - 400);
+    assert_eq!(load::<Vault>(&mut fixture.banks, fixture.attacker.vault).await.balance, BALANCE - 400);
+}
+
+#[tokio::test]
+async fn another_vaults_token_account_is_rejected() {
+    // Same mint, same program: under a shared authority PDA, the attacker's
+    // vault seeds would have signed for this account too
+    let mut fixture = two_vaults().await;
+    let accounts = accounts::SafeTransfer { vault_token_account: fixture.victim.vault_token_account, ..attackers(&fixture) };
+
+    let err = fixture.send_as_attacker(safe_transfer(accounts, BALANCE)).await.expect_err("victim's tokens");
+
+    assert_eq!(err, custom_error(0, AnchorError::ConstraintSeeds));
+    assert_eq!(fixture.tokens(fixture.victim.vault_token_account).await, BALANCE);
+}
+
+#[tokio::test]
+async fn a_token_account_the_vault_controls_elsewhere_is_rejected() {
+    let mut fixture = two_vaults().await;
+    let accounts = accounts::SafeTransfer { vault_token_account: fixture.stray_token_account, ..attackers(&fixture) };
+
+    let err = fixture.send_as_attacker(safe_transfer(accounts, 1)).await.expect_err("not the vault_token PDA");
+
+    assert_eq!(err, custom_error(0, AnchorError::ConstraintSeeds));
+}
+
+#[tokio::test]
+async fn another_authoritys_vault_is_rejected() {
+    let mut fixture = two_vaults().await;
+    let accounts = accounts::SafeTransfer {
+        vault: fixture.victim.vault,
+        vault_token_account: fixture.victim.vault_token_account,
+        ..attackers(&fixture)
+    };
+
+    let err = fixture.send_as_attacker(safe_transfer(accounts, BALANCE)).await.expect_err("victim's vault");
+
+    assert_eq!(err, custom_error(0, AnchorError::ConstraintSeeds));
+}
+
+#[tokio::test]
+async fn the_vault_authority_must_sign() {
+    let mut fixture = two_vaults().await;
+    let accounts = accounts::SafeTransfer {
+        vault: fixture.victim.vault,
+        vault_token_account: fixture.victim.vault_token_account,
+        authority: fixture.victim.authority.pubkey(),
+        ..attackers(&fixture)
+    };
+    let mut ix = safe_transfer(accounts, BALANCE);
+    let authority = ix.accounts.iter_mut().find(|meta| meta.pubkey == fixture.victim.authority.pubkey()).unwrap();
+    authority.is_signer = false;
+
+    let err = fixture.send_as_attacker(ix).await.expect_err("victim never signed");
+
+    assert_eq!(err, custom_error(0, AnchorError::AccountNotSigner));
+}
+
+#[tokio::test]
+async fn a_destination_of_another_mint_is_rejected() {
+    let mut fixture = two_vaults().await;
+    let accounts = accounts::SafeTransfer { user_token_account: fixture.other_mint_wallet, ..attackers(&fixture) };
+
+    let err = fixture.send_as_attacker(safe_transfer(accounts, 1)).await.expect_err("wrong mint");
+
+    assert_eq!(err, custom_error(0, AnchorError::ConstraintTokenMint));
+}
+
+#[tokio::test]
+async fn the_source_as_destination_is_rejected() {
+    let mut fixture = two_vaults().await;
+    let accounts =
+        accounts::SafeTransfer { user_token_account: fixture.attacker.vault_token_account, ..attackers(&fixture) };
+
+    let err = fixture.send_as_attacker(safe_transfer(accounts, 1)).await.expect_err("aliased accounts");
+
+    assert_eq!(err, custom_error(0, CpiError::DuplicateAccount));
+}
+
+#[tokio::test]
+async fn a_zero_amount_is_rejected() {
+    let mut fixture = two_vaults().await;
+    let accounts = attackers(&fixture);
+
+    let err = fixture.send_as_attacker(safe_transfer(accounts, 0)).await.expect_err("zero");
+
+    assert_eq!(err, custom_error(0, SecurityError::ZeroAmount));
+}
+
+#[tokio::test]
+async fn more_than_the_books_hold_is_rejected() {
+    let mut fixture = two_vaults().await;
+    let accounts = attackers(&fixture);
+
+    let err = fixture.send_as_attacker(safe_transfer(accounts, BALANCE + 1)).await.expect_err("overdraw");
+
+    assert_eq!(err, custom_error(0, SecurityError::InsufficientFunds));
+}
+
+#[tokio::test]
+async fn a_program_other_than_token_is_rejected() {
+    let mut fixture = two_vaults().await;
+    let accounts = accounts::SafeTransfer { token_program: system_program::ID, ..attackers(&fixture) };
+
+    let err = fixture.send_as_attacker(safe_transfer(accounts, 1)).await.expect_err("not the Token Program");
+
+    assert_eq!(err, custom_error(0, AnchorError::InvalidProgramId));
+}
@@ -0,0 +1,92 @@
+//! Saturating deposits and withdrawals in the integer-overflow program.
+//!
+//! Neither instruction ever fails on the bound: a total past u64::MAX sticks
+//! at MAX and an overdraft leaves zero. The tests pin down what that hides.
+
+use anchor_lang::{InstructionData, ToAccountMetas};
+use integer_overflow::{accounts, instruction, UserAccount, Vault, ID};
+use security_tests::{anchor_account, load, program_test, send_for_u64};
+use solana_program_test::ProgramTestContext;
+use solana_sdk::{
+    instruction::Instruction,
+    pubkey::Pubkey,
+    signature::Signer,
+};
+
+/// A vault holding `total_deposits`, and the payer's account holding `balance`.
+struct Fixture {
+    context: ProgramTestContext,
+    vault: Pubkey,
+    user_account: Pubkey,
+}
+
+async fn holding(total_deposits: u64, balance: u64) -> Fixture {
+    let authority = Pubkey::new_unique();
+    let (vault, vault_bump) = Pubkey::find_program_address(&[b"vault", authority.as_ref()], &ID);
+    let mut test = program_test();
+    test.add_account(vault, anchor_account(&Vault { authority, total_deposits, protocol_fees: 0, bump: vault_bump }, ID));
+    let context = test.start_with_context().await;
+
+    let payer = context.payer.pubkey();
+    let (user_account, bump) = Pubkey::find_program_address(&[b"user", payer.as_ref()], &ID);
+    let mut fixture = Fixture { context, vault, user_account };
+    let state = UserAccount { owner: payer, balance, bump };
+    fixture.context.set_account(&user_account, &anchor_account(&state, ID).into());
+    fixture
+}
+
+impl Fixture {
+    async fn send(&mut self, ix: Instruction) -> u64 {
+        let payer = self.context.payer.insecure_clone();
+        send_for_u64(&mut self.context.banks_client, &[ix], &[&payer]).await.expect("saturating ops never fail on the bound")
+    }
+
+    async fn deposit(&mut self, amount: u64) -> u64 {
+        let depositor = self.context.payer.pubkey();
+        self.send(Instruction::new_with_bytes(
+            ID,
+            &instruction::DepositSaturating { amount }.data(),
+            accounts::Deposit { vault: self.vault, depositor }.to_account_metas(None),
+        ))
+        .await
+    }
+
+    async fn withdraw(&mut self, amount: u64) -> u64 {
+        let owner = self.context.payer.pubkey();
+        self.send(Instruction::new_with_bytes(
+            ID,
+            &instruction::WithdrawSaturating { amount }.data(),
+            accounts::Withdraw { user_account: self.user_account, owner }.to_account_metas(None),
+        ))
+        .await
+    }
+
+    async fn total_deposits(&mut self) -> u64 {
+        load::<Vault>(&mut self.context.banks_client, self.vault).await.total_deposits
+    }
+}
+
+#[tokio::test]
+async fn deposits_below_the_bound_are_counted_in_full() {
+    let mut fixture = holding(1_000, 0).await;
+
+    assert_eq!(fixture.deposit(500).await, 1_500);
+    assert_eq!(fixture.total_deposits().await, 1_500);
+}
+
+#[tokio::test]
+async fn a_total_pinned_at_max_drops_every_later_deposit() {
+    let mut fixture = holding(u64::MAX - 100, 0).await;
+
+    assert_eq!(fixture.deposit(200).await, u64::MAX);
+    // The next depositor's funds vanish from the books without an error
+    assert_eq!(fixture.deposit(1_000).await, u64::MAX);
+    assert_eq!(fixture.total_deposits().await, u64::MAX);
+}
+
+#[tokio::test]
+async fn an_overdraft_leaves_zero_instead_of_failing() {
+    let mut fixture = holding(0, 100).await;
+
+    assert_eq!(fixture.withdraw(101).await, 0);
+}
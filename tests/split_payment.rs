@@ -0,0 +1,98 @@
+//! Basis-point split payments in the account-validation program.
+//!
+//! Shares round down and the remainder goes to the first recipient, so the
+//! recipients always receive exactly `total` between them.
+
+use account_validation::{accounts, instruction, payments::split_amounts, ValidationError, ID};
+use anchor_lang::{InstructionData, ToAccountMetas};
+use anchor_spl::token::spl_token;
+use anchor_spl::token::spl_token::solana_program::program_pack::Pack;
+use security_tests::{custom_error, program_test, send, system_account, token_account};
+use solana_sdk::{
+    instruction::{AccountMeta, Instruction},
+    native_token::LAMPORTS_PER_SOL,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    transaction::TransactionError,
+};
+
+fn code(error: ValidationError) -> u32 {
+    error.into()
+}
+
+fn rejects_with(result: anchor_lang::Result<Vec<u64>>, expected: ValidationError) {
+    match result {
+        Err(anchor_lang::error::Error::AnchorError(e)) => assert_eq!(e.error_code_number, code(expected)),
+        other => panic!("expected {:?}, got {:?}", expected, other),
+    }
+}
+
+#[test]
+fn uneven_splits_give_the_remainder_to_the_first_recipient() {
+    // 1_001 * 3_333 / 10_000 = 333.6..., 1_001 * 3_334 / 10_000 = 333.7...
+    let amounts = split_amounts(1_001, &[3_333, 3_333, 3_334]).unwrap();
+    assert_eq!(amounts, vec![335, 333, 333]);
+    assert_eq!(amounts.iter().sum::<u64>(), 1_001);
+}
+
+#[test]
+fn splits_conserve_the_total() {
+    let splits: [&[u16]; 4] = [&[10_000], &[1, 9_999], &[2_500; 4], &[7, 3, 9_990]];
+    for total in [1, 7, 999, 1_000_003, u64::MAX] {
+        for bps in splits {
+            let amounts = split_amounts(total, bps).unwrap();
+            let sum = amounts.iter().try_fold(0u64, |sum, amount| sum.checked_add(*amount));
+            assert_eq!(sum, Some(total), "{} split by {:?}", total, bps);
+        }
+    }
+}
+
+#[test]
+fn weights_must_sum_to_10000() {
+    rejects_with(split_amounts(100, &[5_000, 4_999]), ValidationError::InvalidSplit);
+    rejects_with(split_amounts(100, &[10_000, 1]), ValidationError::InvalidSplit);
+    rejects_with(split_amounts(100, &[]), ValidationError::InvalidSplit);
+}
+
+/// Pays `total` split by `bps` into fresh token accounts and returns their
+/// balances afterwards.
+async fn pay(total: u64, bps: Vec<u16>) -> Result<Vec<u64>, TransactionError> {
+    let payer = Keypair::new();
+    let mint = Pubkey::new_unique();
+    let source = Pubkey::new_unique();
+    let recipients: Vec<Pubkey> = bps.iter().map(|_| Pubkey::new_unique()).collect();
+
+    let mut test = program_test();
+    test.add_account(payer.pubkey(), system_account(LAMPORTS_PER_SOL));
+    test.add_account(source, token_account(mint, payer.pubkey(), total));
+    for recipient in &recipients {
+        test.add_account(*recipient, token_account(mint, Pubkey::new_unique(), 0));
+    }
+    let (mut banks, _, _) = test.start().await;
+
+    let mut metas = accounts::SplitPayment { source, payer: payer.pubkey(), token_program: spl_token::ID }
+        .to_account_metas(None);
+    metas.extend(recipients.iter().map(|recipient| AccountMeta::new(*recipient, false)));
+    let ix = Instruction::new_with_bytes(ID, &instruction::SplitPayment { total, bps_splits: bps, min_out: 0, deadline: i64::MAX }.data(), metas);
+    send(&mut banks, &[ix], &[&payer]).await?;
+
+    let mut balances = Vec::new();
+    for recipient in recipients {
+        let account = banks.get_account(recipient).await.unwrap().unwrap();
+        balances.push(spl_token::state::Account::unpack(&account.data).unwrap().amount);
+    }
+    Ok(balances)
+}
+
+#[tokio::test]
+async fn split_payment_moves_exactly_the_total() {
+    let balances = pay(1_001, vec![3_333, 3_333, 3_334]).await.expect("split lands");
+    assert_eq!(balances, vec![335, 333, 333]);
+    assert_eq!(balances.iter().sum::<u64>(), 1_001);
+}
+
+#[tokio::test]
+async fn split_payment_rejects_weights_not_summing_to_10000() {
+    let err = pay(1_000, vec![5_000, 4_000]).await.expect_err("weights sum to 9000");
+    assert_eq!(err, custom_error(0, ValidationError::InvalidSplit));
+}
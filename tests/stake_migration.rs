@@ -0,0 +1,110 @@
+//! Batch migration of versioned accounts in the type-cosplay program.
+//!
+//! `migrate_batch` advances every StakeAccount behind
+//! `StakeAccount::CURRENT_VERSION` and leaves current ones byte-for-byte
+//! alone. Anything in the batch that isn't a StakeAccount fails the whole
+//! batch.
+
+use anchor_lang::error::ErrorCode as AnchorError;
+use anchor_lang::{AccountDeserialize, InstructionData, ToAccountMetas};
+use security_tests::{anchor_account, custom_error, program_test};
+use solana_program_test::BanksClient;
+use solana_sdk::{
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    transaction::{Transaction, TransactionError},
+};
+use type_cosplay::{accounts, instruction, StakeAccount, UserAccount, ID, STAKE_BASE_UNITS};
+
+/// (version, amount) of each preloaded stake account
+const STAKES: [(u8, u64); 3] = [(1, 5), (2, 7_000_000), (1, 3)];
+
+/// Stake accounts at the `STAKES` versions, plus a UserAccount.
+struct Fixture {
+    banks: BanksClient,
+    payer: Keypair,
+    stakes: Vec<Pubkey>,
+    user_account: Pubkey,
+}
+
+async fn mixed_versions() -> Fixture {
+    let mut test = program_test();
+    let mut stakes = Vec::new();
+    for (version, amount) in STAKES {
+        let owner = Pubkey::new_unique();
+        let (stake, bump) = Pubkey::find_program_address(&[b"stake", owner.as_ref()], &ID);
+        test.add_account(stake, anchor_account(&StakeAccount { owner, version, amount, bump }, ID));
+        stakes.push(stake);
+    }
+    let owner = Pubkey::new_unique();
+    let (user_account, bump) = Pubkey::find_program_address(&[b"user", owner.as_ref()], &ID);
+    test.add_account(user_account, anchor_account(&UserAccount { owner, balance: 5, bump }, ID));
+    let (banks, payer, _) = test.start().await;
+    Fixture { banks, payer, stakes, user_account }
+}
+
+impl Fixture {
+    /// Migrates `batch` and returns how many accounts advanced.
+    async fn migrate(&mut self, batch: &[Pubkey]) -> Result<u32, TransactionError> {
+        let mut ix = Instruction::new_with_bytes(
+            ID,
+            &instruction::MigrateBatch {}.data(),
+            accounts::MigrateBatch {}.to_account_metas(None),
+        );
+        ix.accounts.extend(batch.iter().map(|key| AccountMeta::new(*key, false)));
+        let blockhash = self.banks.get_latest_blockhash().await.unwrap();
+        let tx = Transaction::new_signed_with_payer(&[ix], Some(&self.payer.pubkey()), &[&self.payer], blockhash);
+        let result = self.banks.process_transaction_with_metadata(tx).await.unwrap();
+        result.result?;
+
+        // The runtime strips trailing zero bytes from return data
+        let data = result
+            .metadata
+            .and_then(|metadata| metadata.return_data)
+            .map(|return_data| return_data.data)
+            .unwrap_or_default();
+        let mut bytes = [0u8; 4];
+        bytes[..data.len()].copy_from_slice(&data);
+        Ok(u32::from_le_bytes(bytes))
+    }
+
+    async fn data(&mut self, key: Pubkey) -> Vec<u8> {
+        self.banks.get_account(key).await.unwrap().unwrap().data
+    }
+
+    async fn stake(&mut self, index: usize) -> StakeAccount {
+        let data = self.data(self.stakes[index]).await;
+        StakeAccount::try_deserialize(&mut data.as_slice()).unwrap()
+    }
+}
+
+#[tokio::test]
+async fn stale_accounts_advance_and_current_ones_are_untouched() {
+    let mut fixture = mixed_versions().await;
+    let current = fixture.data(fixture.stakes[1]).await;
+    let batch = fixture.stakes.clone();
+
+    assert_eq!(fixture.migrate(&batch).await, Ok(2));
+
+    for (index, (_, amount)) in STAKES.iter().enumerate() {
+        let stake = fixture.stake(index).await;
+        assert_eq!(stake.version, StakeAccount::CURRENT_VERSION);
+        if index != 1 {
+            assert_eq!(stake.amount, amount * STAKE_BASE_UNITS);
+        }
+    }
+    assert_eq!(fixture.data(fixture.stakes[1]).await, current);
+}
+
+#[tokio::test]
+async fn a_foreign_account_fails_the_whole_batch() {
+    let mut fixture = mixed_versions().await;
+    let before = fixture.data(fixture.stakes[0]).await;
+    let batch = [fixture.stakes[0], fixture.user_account];
+
+    let err = fixture.migrate(&batch).await.expect_err("UserAccount is not a StakeAccount");
+
+    assert_eq!(err, custom_error(0, AnchorError::AccountDiscriminatorMismatch));
+    assert_eq!(fixture.data(fixture.stakes[0]).await, before);
+}
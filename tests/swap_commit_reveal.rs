@@ -0,0 +1,133 @@
+//! Commit-reveal swaps in the arbitrary-CPI program.
+//!
+//! `commit_swap` stores only a hash of `(amount, min_out, nonce)`;
+//! `reveal_swap` runs the swap only if a later reveal matches it exactly,
+//! and keeps it only if the swap paid out at least `min_out`. The mock swap
+//! program pays out exactly the amount in.
+
+use anchor_lang::error::ErrorCode as AnchorError;
+use anchor_lang::{InstructionData, ToAccountMetas};
+use arbitrary_cpi::{accounts, instruction, swap_commitment_of, CpiError, ID};
+use security_tests::{custom_error, mock_swap, pda, program_test, send, system_account};
+use solana_program_test::ProgramTestContext;
+use solana_sdk::{
+    clock::Clock,
+    instruction::Instruction,
+    native_token::LAMPORTS_PER_SOL,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    system_program,
+    transaction::TransactionError,
+};
+
+const AMOUNT: u64 = 1_000;
+const MIN_OUT: u64 = 990;
+const NONCE: [u8; 32] = [0x6e; 32];
+
+fn ix(accounts: impl ToAccountMetas, data: impl InstructionData) -> Instruction {
+    Instruction::new_with_bytes(ID, &data.data(), accounts.to_account_metas(None))
+}
+
+/// A user with `(AMOUNT, min_out, NONCE)` committed.
+struct Fixture {
+    context: ProgramTestContext,
+    user: Keypair,
+    swap_commitment: Pubkey,
+}
+
+async fn committed() -> Fixture {
+    committed_to(MIN_OUT).await
+}
+
+async fn committed_to(min_out: u64) -> Fixture {
+    let user = Keypair::new();
+    let mut test = program_test();
+    test.add_account(user.pubkey(), system_account(LAMPORTS_PER_SOL));
+    let context = test.start_with_context().await;
+
+    let swap_commitment = pda(&[b"swap_commitment", user.pubkey().as_ref()], &ID);
+    let mut fixture = Fixture { context, user, swap_commitment };
+    let commit = ix(
+        accounts::CommitSwap {
+            swap_commitment,
+            user: fixture.user.pubkey(),
+            system_program: system_program::ID,
+        },
+        instruction::CommitSwap { commitment: swap_commitment_of(AMOUNT, min_out, &NONCE) },
+    );
+    fixture.send(commit).await.expect("swap committed");
+    fixture
+}
+
+impl Fixture {
+    async fn send(&mut self, instruction: Instruction) -> Result<(), TransactionError> {
+        send(&mut self.context.banks_client, &[instruction], &[&self.user]).await
+    }
+
+    async fn next_slot(&mut self) {
+        let clock: Clock = self.context.banks_client.get_sysvar().await.unwrap();
+        self.context.warp_to_slot(clock.slot + 1).unwrap();
+    }
+
+    async fn reveal(&mut self, amount: u64, min_out: u64, nonce: [u8; 32]) -> Result<(), TransactionError> {
+        let reveal = ix(
+            accounts::RevealSwap {
+                swap_commitment: self.swap_commitment,
+                swap_program: mock_swap::ID,
+                user: self.user.pubkey(),
+            },
+            instruction::RevealSwap { amount, min_out, nonce },
+        );
+        self.send(reveal).await
+    }
+
+    async fn committed(&mut self) -> bool {
+        self.context.banks_client.get_account(self.swap_commitment).await.unwrap().is_some()
+    }
+}
+
+#[tokio::test]
+async fn a_matching_reveal_swaps_and_closes_the_commitment() {
+    let mut fixture = committed().await;
+    fixture.next_slot().await;
+
+    fixture.reveal(AMOUNT, MIN_OUT, NONCE).await.expect("reveal matches");
+
+    assert!(!fixture.committed().await, "commitment is closed once revealed");
+    let err = fixture.reveal(AMOUNT, MIN_OUT, NONCE).await.expect_err("a reveal can't be replayed");
+    assert_eq!(err, custom_error(0, AnchorError::AccountNotInitialized));
+}
+
+#[tokio::test]
+async fn a_tampered_reveal_is_rejected() {
+    let mut fixture = committed().await;
+    fixture.next_slot().await;
+
+    // Loosened slippage, a larger size, or a guessed nonce all miss the hash
+    for (amount, min_out, nonce) in [(AMOUNT, 0, NONCE), (AMOUNT * 10, MIN_OUT, NONCE), (AMOUNT, MIN_OUT, [0; 32])] {
+        let err = fixture.reveal(amount, min_out, nonce).await.expect_err("tampered reveal");
+        assert_eq!(err, custom_error(0, CpiError::CommitmentMismatch));
+    }
+    assert!(fixture.committed().await, "a failed reveal leaves the commitment in place");
+}
+
+#[tokio::test]
+async fn a_swap_paying_out_less_than_min_out_is_rejected() {
+    // The user committed to receiving more than the swap will pay
+    let mut fixture = committed_to(AMOUNT + 1).await;
+    fixture.next_slot().await;
+
+    let err = fixture.reveal(AMOUNT, AMOUNT + 1, NONCE).await.expect_err("slippage exceeded");
+
+    assert_eq!(err, custom_error(0, CpiError::SlippageExceeded));
+    assert!(fixture.committed().await, "a failed reveal leaves the commitment in place");
+}
+
+#[tokio::test]
+async fn a_reveal_in_the_commit_slot_is_rejected() {
+    let mut fixture = committed().await;
+
+    let err = fixture.reveal(AMOUNT, MIN_OUT, NONCE).await.expect_err("same slot");
+
+    assert_eq!(err, custom_error(0, CpiError::RevealTooEarly));
+}
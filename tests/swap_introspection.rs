@@ -0,0 +1,95 @@
+//! `swap_with_introspection` in the arbitrary-CPI program.
+//!
+//! The swap reads the instructions sysvar and refuses to run when any later
+//! instruction in the transaction targets a program outside
+//! `FOLLOWING_PROGRAM_ALLOWLIST` - the attacker's back-run in a sandwich.
+
+use anchor_lang::{InstructionData, ToAccountMetas};
+use arbitrary_cpi::{accounts, instruction, CpiError, ID, SWAP_DISCRIMINATOR};
+use security_tests::{mock_swap, program_test};
+use solana_sdk::{
+    instruction::{AccountMeta, Instruction, InstructionError},
+    pubkey::Pubkey,
+    signature::Signer,
+    sysvar,
+    transaction::{Transaction, TransactionError},
+};
+
+const AMOUNT: u64 = 100;
+
+/// Sends the instructions `build` makes for the payer.
+async fn send(build: impl FnOnce(Pubkey) -> Vec<Instruction>) -> Result<(), TransactionError> {
+    let (mut banks, payer, blockhash) = program_test().start().await;
+    let tx = Transaction::new_signed_with_payer(&build(payer.pubkey()), Some(&payer.pubkey()), &[&payer], blockhash);
+    banks.process_transaction(tx).await.map_err(|err| err.unwrap())
+}
+
+fn swap(user: Pubkey) -> Instruction {
+    Instruction::new_with_bytes(
+        ID,
+        &instruction::SwapWithIntrospection { amount: AMOUNT }.data(),
+        accounts::SwapWithIntrospection { swap_program: mock_swap::ID, user, instructions: sysvar::instructions::ID }
+            .to_account_metas(None),
+    )
+}
+
+/// A swap straight into `program`, the shape of a sandwich's back-run.
+fn direct_swap(program: Pubkey, user: Pubkey) -> Instruction {
+    let mut data = SWAP_DISCRIMINATOR.to_vec();
+    data.extend_from_slice(&AMOUNT.to_le_bytes());
+    Instruction::new_with_bytes(program, &data, vec![AccountMeta::new_readonly(user, true)])
+}
+
+#[tokio::test]
+async fn a_swap_with_nothing_after_it_runs() {
+    assert_eq!(send(|user| vec![swap(user)]).await, Ok(()));
+}
+
+#[tokio::test]
+async fn an_allowlisted_program_may_follow() {
+    assert_eq!(send(|user| vec![swap(user), direct_swap(mock_swap::ID, user)]).await, Ok(()));
+}
+
+#[tokio::test]
+async fn an_unknown_program_following_the_swap_is_rejected() {
+    let result = send(|user| vec![swap(user), direct_swap(mock_swap::IMPOSTOR_ID, user)]).await;
+
+    let unexpected = InstructionError::Custom(CpiError::UnexpectedFollowingInstruction.into());
+    assert_eq!(result, Err(TransactionError::InstructionError(0, unexpected)));
+}
+
+#[tokio::test]
+async fn an_unknown_program_further_down_is_rejected_too() {
+    // An allowlisted instruction in between doesn't hide the back-run
+    let result = send(|user| {
+        vec![swap(user), direct_swap(mock_swap::ID, user), direct_swap(mock_swap::IMPOSTOR_ID, user)]
+    })
+    .await;
+
+    let unexpected = InstructionError::Custom(CpiError::UnexpectedFollowingInstruction.into());
+    assert_eq!(result, Err(TransactionError::InstructionError(0, unexpected)));
+}
+
+#[tokio::test]
+async fn a_back_run_through_this_programs_own_swap_is_rejected() {
+    // `swap_vulnerable` invokes whatever program it is given, so allowing
+    // this program would let the back-run ride through it
+    let result = send(|user| {
+        let back_run = Instruction::new_with_bytes(
+            ID,
+            &instruction::SwapVulnerable { amount: AMOUNT }.data(),
+            accounts::SwapVulnerable { swap_program: mock_swap::IMPOSTOR_ID, user }.to_account_metas(None),
+        );
+        vec![swap(user), back_run]
+    })
+    .await;
+
+    let unexpected = InstructionError::Custom(CpiError::UnexpectedFollowingInstruction.into());
+    assert_eq!(result, Err(TransactionError::InstructionError(0, unexpected)));
+}
+
+#[tokio::test]
+async fn only_the_following_instructions_are_checked() {
+    // The front-run half of a sandwich comes first and is out of scope
+    assert_eq!(send(|user| vec![direct_swap(mock_swap::IMPOSTOR_ID, user), swap(user)]).await, Ok(()));
+}
@@ -0,0 +1,159 @@
+//! Deadlines and minimum outputs on account-validation swaps and splits.
+//!
+//! A trade only lands while `now <= deadline`, and only if what comes out
+//! after fees - for a split, what the recipients received between them -
+//! is at least the caller's `min_out`.
+
+use account_validation::{accounts, instruction, ValidationError, ID};
+use anchor_lang::{InstructionData, ToAccountMetas};
+use anchor_spl::token::spl_token;
+use security_tests::{custom_error, pda, program_test, send_for_u64, set_time, token_account};
+use solana_program_test::ProgramTestContext;
+use solana_sdk::{
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+    signature::Signer,
+    system_program,
+    transaction::TransactionError,
+};
+
+const FEE_BPS: u16 = 100;
+const NOW: i64 = 1_000_000;
+const AMOUNT: u64 = 1_000_000;
+/// `AMOUNT` less the 1% fee
+const OUT: u64 = 990_000;
+const SOURCE: Pubkey = Pubkey::new_from_array([0x21; 32]);
+const RECIPIENTS: [Pubkey; 2] = [Pubkey::new_from_array([0x22; 32]), Pubkey::new_from_array([0x23; 32])];
+
+fn ix(accounts: impl ToAccountMetas, data: impl InstructionData) -> Instruction {
+    Instruction::new_with_bytes(ID, &data.data(), accounts.to_account_metas(None))
+}
+
+/// Sends `instruction` signed by `signer` and returns its u64 return data.
+/// A config charging `FEE_BPS` and a pool under it, at `NOW`.
+async fn market() -> ProgramTestContext {
+    let mut context = program_test().start_with_context().await;
+    set_time(&mut context, NOW).await;
+    let admin = context.payer.insecure_clone();
+    let config = pda(&[b"config"], &ID);
+    let setup = [
+        ix(
+            accounts::InitializeConfig { config, admin: admin.pubkey(), system_program: system_program::ID },
+            instruction::InitializeConfig { fee_bps: FEE_BPS, max_positions: 1, dust_threshold: 0 },
+        ),
+        ix(
+            accounts::InitializePool { config, pool: pool(&context), authority: admin.pubkey(), system_program: system_program::ID },
+            instruction::InitializePool {
+                reward_rate: 10,
+                global_tvl_cap: u64::MAX,
+                lock_secs: 0,
+                min_hold_secs: 0,
+                early_exit_bps: 0,
+                deposit_mint: Pubkey::default(),
+                reward_mint: Pubkey::default(),
+            },
+        ),
+    ];
+    for instruction in setup {
+        send_for_u64(&mut context.banks_client, &[instruction], &[&admin]).await.expect("setup");
+    }
+    context
+}
+
+fn pool(context: &ProgramTestContext) -> Pubkey {
+    pda(&[b"pool", context.payer.pubkey().as_ref()], &ID)
+}
+
+/// Swaps `AMOUNT` and returns the fee charged.
+async fn swap(context: &mut ProgramTestContext, min_out: u64, deadline: i64) -> Result<u64, TransactionError> {
+    let swap = ix(
+        accounts::SwapSecure { config: pda(&[b"config"], &ID), pool: pool(context), user: context.payer.pubkey() },
+        instruction::SwapSecure { amount: AMOUNT, min_out, deadline },
+    );
+    let payer = context.payer.insecure_clone();
+    send_for_u64(&mut context.banks_client, &[swap], &[&payer]).await
+}
+
+#[tokio::test]
+async fn a_timely_swap_within_slippage_lands() {
+    let mut context = market().await;
+
+    let fee = swap(&mut context, OUT, NOW).await.expect("exactly min_out, at the deadline");
+
+    assert_eq!(fee, AMOUNT - OUT);
+}
+
+#[tokio::test]
+async fn a_swap_after_its_deadline_is_rejected() {
+    let mut context = market().await;
+
+    let err = swap(&mut context, OUT, NOW - 1).await.expect_err("one second late");
+
+    assert_eq!(err, custom_error(0, ValidationError::DeadlinePassed));
+}
+
+#[tokio::test]
+async fn a_swap_below_min_out_is_rejected() {
+    let mut context = market().await;
+
+    let err = swap(&mut context, OUT + 1, NOW).await.expect_err("the fee leaves one token short");
+
+    assert_eq!(err, custom_error(0, ValidationError::SlippageExceeded));
+}
+
+/// Splits `total` evenly between two fresh token accounts.
+async fn split(context: &mut ProgramTestContext, total: u64, min_out: u64, deadline: i64) -> Result<u64, TransactionError> {
+    split_to(context, RECIPIENTS, total, min_out, deadline).await
+}
+
+/// Splits `total` evenly between `recipients`
+async fn split_to(
+    context: &mut ProgramTestContext,
+    recipients: [Pubkey; 2],
+    total: u64,
+    min_out: u64,
+    deadline: i64,
+) -> Result<u64, TransactionError> {
+    let payer = context.payer.insecure_clone();
+    let mut metas = accounts::SplitPayment { source: SOURCE, payer: payer.pubkey(), token_program: spl_token::ID }
+        .to_account_metas(None);
+    metas.extend(recipients.iter().map(|recipient| AccountMeta::new(*recipient, false)));
+    let data = instruction::SplitPayment { total, bps_splits: vec![5_000, 5_000], min_out, deadline }.data();
+    send_for_u64(&mut context.banks_client, &[Instruction::new_with_bytes(ID, &data, metas)], &[&payer]).await
+}
+
+/// `AMOUNT` tokens in `SOURCE`, owned by the payer, and empty accounts of
+/// the same mint at `RECIPIENTS`.
+async fn funded_source() -> ProgramTestContext {
+    let mint = Pubkey::new_unique();
+    let mut context = program_test().start_with_context().await;
+    let payer = context.payer.pubkey();
+    context.set_account(&SOURCE, &token_account(mint, payer, AMOUNT).into());
+    for recipient in RECIPIENTS {
+        context.set_account(&recipient, &token_account(mint, Pubkey::new_unique(), 0).into());
+    }
+    set_time(&mut context, NOW).await;
+    context
+}
+
+#[tokio::test]
+async fn a_split_honours_its_deadline_and_min_out() {
+    let mut context = funded_source().await;
+
+    assert_eq!(split(&mut context, 1_000, 500, NOW - 1).await, Err(custom_error(0, ValidationError::DeadlinePassed)));
+    // 1_001 splits as 501 + 500; `min_out` is the total received
+    assert_eq!(split(&mut context, 1_001, 1_002, NOW).await, Err(custom_error(0, ValidationError::SlippageExceeded)));
+    split(&mut context, 1_001, 1_001, NOW).await.expect("the recipients received 1_001");
+}
+
+#[tokio::test]
+async fn min_out_counts_only_what_left_the_source() {
+    let mut context = funded_source().await;
+    // Half of the split is paid back into the source itself
+    let recipients = [SOURCE, RECIPIENTS[1]];
+
+    let result = split_to(&mut context, recipients, 1_000, 1_000, NOW).await;
+
+    assert_eq!(result, Err(custom_error(0, ValidationError::SlippageExceeded)));
+    split_to(&mut context, recipients, 1_000, 500, NOW).await.expect("500 reached the other recipient");
+}
@@ -0,0 +1,73 @@
+//! The swap program in the arbitrary-CPI program is pinned by address.
+//!
+//! `swap_secure` only accepts `EXPECTED_SWAP_PROGRAM`; any other executable,
+//! even one running the very same code, is `InvalidProgram`.
+//! `swap_vulnerable` invokes whatever it was handed.
+
+use anchor_lang::{InstructionData, ToAccountMetas};
+use anchor_spl::token::spl_token;
+use arbitrary_cpi::{accounts, instruction, CpiError, ID};
+use security_tests::{custom_error, mock_swap, program_test};
+use solana_sdk::{
+    instruction::Instruction,
+    pubkey::Pubkey,
+    signature::Signer,
+    transaction::{Transaction, TransactionError},
+};
+
+const AMOUNT: u64 = 100;
+
+/// Sends the instruction `build` makes for the payer, and returns the
+/// program that answered with the amount.
+async fn swap(build: impl FnOnce(Pubkey) -> Instruction) -> Result<Pubkey, TransactionError> {
+    let (mut banks, payer, blockhash) = program_test().start().await;
+    let tx = Transaction::new_signed_with_payer(&[build(payer.pubkey())], Some(&payer.pubkey()), &[&payer], blockhash);
+    let result = banks.process_transaction_with_metadata(tx).await.unwrap();
+    result.result?;
+
+    let return_data = result.metadata.and_then(|metadata| metadata.return_data).expect("the swap answered");
+    // The runtime strips trailing zero bytes from return data
+    let mut amount = [0u8; 8];
+    amount[..return_data.data.len()].copy_from_slice(&return_data.data);
+    assert_eq!(u64::from_le_bytes(amount), AMOUNT);
+    Ok(return_data.program_id)
+}
+
+fn swap_secure(swap_program: Pubkey, user: Pubkey) -> Instruction {
+    Instruction::new_with_bytes(
+        ID,
+        &instruction::SwapSecure { amount: AMOUNT }.data(),
+        accounts::SwapSecure { swap_program, user }.to_account_metas(None),
+    )
+}
+
+fn swap_vulnerable(swap_program: Pubkey, user: Pubkey) -> Instruction {
+    Instruction::new_with_bytes(
+        ID,
+        &instruction::SwapVulnerable { amount: AMOUNT }.data(),
+        accounts::SwapVulnerable { swap_program, user }.to_account_metas(None),
+    )
+}
+
+fn invalid_program() -> TransactionError {
+    custom_error(0, CpiError::InvalidProgram)
+}
+
+#[tokio::test]
+async fn the_expected_program_carries_the_swap() {
+    assert_eq!(swap(|user| swap_secure(mock_swap::ID, user)).await, Ok(mock_swap::ID));
+}
+
+#[tokio::test]
+async fn any_other_program_is_rejected() {
+    for program in [mock_swap::IMPOSTOR_ID, spl_token::ID] {
+        let result = swap(|user| swap_secure(program, user)).await;
+        assert_eq!(result, Err(invalid_program()), "{program}");
+    }
+}
+
+#[tokio::test]
+async fn the_vulnerable_swap_goes_through_the_impostor() {
+    // Same code as the real swap program, at the attacker's address
+    assert_eq!(swap(|user| swap_vulnerable(mock_swap::IMPOSTOR_ID, user)).await, Ok(mock_swap::IMPOSTOR_ID));
+}
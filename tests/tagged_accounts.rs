@@ -0,0 +1,116 @@
+//! One account type, several logical types: `TaggedAccount` and its `kind`.
+//!
+//! The tag is only as trustworthy as the account it is read from. The
+//! vulnerable instruction reads it from an unchecked account, so a copy
+//! of a User account with one byte flipped passes as Admin. The secure
+//! instruction reads it through `Account<>`, where only the program could
+//! have written it.
+
+use anchor_lang::error::ErrorCode as AnchorError;
+use anchor_lang::{AccountDeserialize, InstructionData, ToAccountMetas};
+use security_tests::{anchor_account, custom_error, pda, program_test, raw_account};
+use solana_sdk::{
+    account::Account,
+    instruction::Instruction,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    system_program,
+    transaction::{Transaction, TransactionError},
+};
+use type_cosplay::{accounts, instruction, AccountKind, TaggedAccount, TypeCosplayError, ID};
+
+/// Sends `ix` with `tagged` preloaded at `key`, signed by `owner`.
+async fn send(key: Pubkey, tagged: Account, owner: &Keypair, ix: Instruction) -> Result<(), TransactionError> {
+    let mut test = program_test();
+    test.add_account(key, tagged);
+    let (mut banks, payer, blockhash) = test.start().await;
+    let tx = Transaction::new_signed_with_payer(&[ix], Some(&payer.pubkey()), &[&payer, owner], blockhash);
+    banks.process_transaction(tx).await.map_err(|err| err.unwrap())
+}
+
+/// `owner`'s User account, at its PDA
+fn user(owner: &Keypair) -> (Pubkey, Account) {
+    let (tagged, bump) = Pubkey::find_program_address(&[b"tagged", owner.pubkey().as_ref()], &ID);
+    let state = TaggedAccount { kind: AccountKind::User, owner: owner.pubkey(), bump };
+    (tagged, anchor_account(&state, ID))
+}
+
+/// `owner`'s User account with the tag flipped to Admin, in an account
+/// the attacker controls
+fn flipped(owner: &Keypair) -> (Pubkey, Account) {
+    let (_, mut account) = user(owner);
+    account.data[8] = AccountKind::Admin as u8;
+    (Pubkey::new_unique(), raw_account(account.data, system_program::ID))
+}
+
+fn vulnerable(tagged: Pubkey, owner: Pubkey, expected: AccountKind) -> Instruction {
+    Instruction::new_with_bytes(
+        ID,
+        &instruction::ProcessTaggedVulnerable { expected }.data(),
+        accounts::ProcessTaggedVulnerable { tagged, owner }.to_account_metas(None),
+    )
+}
+
+fn secure(tagged: Pubkey, owner: Pubkey, expected: AccountKind) -> Instruction {
+    Instruction::new_with_bytes(
+        ID,
+        &instruction::ProcessTaggedSecure { expected }.data(),
+        accounts::ProcessTaggedSecure { tagged, owner }.to_account_metas(None),
+    )
+}
+
+#[tokio::test]
+async fn the_tag_is_the_byte_after_the_discriminator() {
+    let owner = Keypair::new();
+    let (_, account) = flipped(&owner);
+
+    let forged = TaggedAccount::try_deserialize(&mut account.data.as_slice()).unwrap();
+    assert_eq!((forged.kind, forged.owner), (AccountKind::Admin, owner.pubkey()));
+}
+
+#[tokio::test]
+async fn a_flipped_byte_makes_the_vulnerable_version_see_an_admin() {
+    let attacker = Keypair::new();
+    let (tagged, account) = flipped(&attacker);
+
+    let result = send(tagged, account, &attacker, vulnerable(tagged, attacker.pubkey(), AccountKind::Admin)).await;
+    assert_eq!(result, Ok(()), "attacker-written tag trusted");
+}
+
+#[tokio::test]
+async fn the_secure_version_checks_the_kind() {
+    let owner = Keypair::new();
+    let (tagged, account) = user(&owner);
+
+    let as_user = send(tagged, account.clone(), &owner, secure(tagged, owner.pubkey(), AccountKind::User)).await;
+    assert_eq!(as_user, Ok(()));
+
+    let as_admin = send(tagged, account, &owner, secure(tagged, owner.pubkey(), AccountKind::Admin)).await;
+    assert_eq!(as_admin, Err(custom_error(0, TypeCosplayError::UnexpectedKind)));
+}
+
+#[tokio::test]
+async fn the_secure_version_never_reads_a_flipped_copy() {
+    let attacker = Keypair::new();
+    let (tagged, account) = flipped(&attacker);
+
+    let result = send(tagged, account, &attacker, secure(tagged, attacker.pubkey(), AccountKind::Admin)).await;
+    assert_eq!(result, Err(custom_error(0, AnchorError::AccountOwnedByWrongProgram as u32)));
+}
+
+#[tokio::test]
+async fn new_tagged_accounts_are_users() {
+    let (mut banks, owner, blockhash) = program_test().start().await;
+    let tagged = pda(&[b"tagged", owner.pubkey().as_ref()], &ID);
+    let init = Instruction::new_with_bytes(
+        ID,
+        &instruction::InitializeTaggedAccount {}.data(),
+        accounts::InitializeTaggedAccount { tagged, owner: owner.pubkey(), system_program: system_program::ID }
+            .to_account_metas(None),
+    );
+    let tx = Transaction::new_signed_with_payer(&[init], Some(&owner.pubkey()), &[&owner], blockhash);
+    banks.process_transaction(tx).await.expect("init");
+
+    let account = banks.get_account(tagged).await.unwrap().unwrap();
+    assert_eq!(TaggedAccount::try_deserialize(&mut account.data.as_slice()).unwrap().kind, AccountKind::User);
+}
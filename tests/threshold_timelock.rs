@@ -0,0 +1,118 @@
+//! Timelocked threshold changes on the missing-signer-check multisig.
+//!
+//! `propose_threshold_change` needs the current threshold's approval and
+//! sets `threshold_change_eta` to `timelock_secs` from now.
+//! `apply_threshold_change` is permissionless, but only from that second on.
+
+use anchor_lang::{InstructionData, ToAccountMetas};
+use missing_signer_check::{accounts, instruction, MultisigError, MultisigVault, ID};
+use security_tests::{anchor_account, custom_error, load, program_test, send, set_time, system_account};
+use solana_program_test::ProgramTestContext;
+use solana_sdk::{
+    instruction::{AccountMeta, Instruction},
+    native_token::LAMPORTS_PER_SOL,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    transaction::TransactionError,
+};
+
+const TIMELOCK_SECS: i64 = 3_600;
+const PROPOSED_AT: i64 = 1_000_000;
+
+/// A 2-of-3 multisig with a change to 1-of-3 proposed at `PROPOSED_AT`,
+/// and a stranger to crank it through.
+struct Fixture {
+    context: ProgramTestContext,
+    stranger: Keypair,
+    multisig: Pubkey,
+}
+
+async fn change_proposed() -> Fixture {
+    let signers = [Keypair::new(), Keypair::new(), Keypair::new()];
+    let stranger = Keypair::new();
+    let creator = Pubkey::new_unique();
+    let (multisig, bump) = Pubkey::find_program_address(&[b"multisig", creator.as_ref()], &ID);
+    let state = MultisigVault {
+        creator,
+        signers: [signers[0].pubkey(), signers[1].pubkey(), signers[2].pubkey()],
+        threshold: 2,
+        timelock_secs: TIMELOCK_SECS,
+        balance: 0,
+        pending_threshold: 0,
+        threshold_change_eta: 0,
+        bump,
+    };
+
+    let mut test = program_test();
+    test.add_account(signers[0].pubkey(), system_account(LAMPORTS_PER_SOL));
+    test.add_account(stranger.pubkey(), system_account(LAMPORTS_PER_SOL));
+    test.add_account(multisig, anchor_account(&state, ID));
+    let mut context = test.start_with_context().await;
+    set_time(&mut context, PROPOSED_AT).await;
+
+    let mut propose = Instruction::new_with_bytes(
+        ID,
+        &instruction::ProposeThresholdChange { new_threshold: 1 }.data(),
+        accounts::ProposeThresholdChange { multisig }.to_account_metas(None),
+    );
+    propose.accounts.extend(signers[..2].iter().map(|signer| AccountMeta::new_readonly(signer.pubkey(), true)));
+    send(&mut context.banks_client, &[propose], &[&signers[0], &signers[1]]).await.expect("2 of 3 proposed");
+
+    Fixture { context, stranger, multisig }
+}
+
+impl Fixture {
+    async fn apply_at(&mut self, unix_timestamp: i64) -> Result<(), TransactionError> {
+        set_time(&mut self.context, unix_timestamp).await;
+        let apply = Instruction::new_with_bytes(
+            ID,
+            &instruction::ApplyThresholdChange {}.data(),
+            accounts::ApplyThresholdChange { multisig: self.multisig }.to_account_metas(None),
+        );
+        let stranger = self.stranger.insecure_clone();
+        send(&mut self.context.banks_client, &[apply], &[&stranger]).await
+    }
+
+    async fn multisig(&mut self) -> MultisigVault {
+        load(&mut self.context.banks_client, self.multisig).await
+    }
+}
+
+#[tokio::test]
+async fn the_proposal_waits_for_the_timelock() {
+    let mut fixture = change_proposed().await;
+
+    let multisig = fixture.multisig().await;
+    assert_eq!((multisig.threshold, multisig.pending_threshold), (2, 1));
+    assert_eq!(multisig.threshold_change_eta, PROPOSED_AT + TIMELOCK_SECS);
+}
+
+#[tokio::test]
+async fn applying_before_the_timelock_is_rejected() {
+    let mut fixture = change_proposed().await;
+
+    let err = fixture.apply_at(PROPOSED_AT + TIMELOCK_SECS - 1).await.expect_err("one second early");
+
+    assert_eq!(err, custom_error(0, MultisigError::TimelockNotElapsed));
+    assert_eq!(fixture.multisig().await.threshold, 2);
+}
+
+#[tokio::test]
+async fn anyone_can_apply_once_the_timelock_has_elapsed() {
+    let mut fixture = change_proposed().await;
+
+    fixture.apply_at(PROPOSED_AT + TIMELOCK_SECS).await.expect("exactly at the eta");
+
+    let multisig = fixture.multisig().await;
+    assert_eq!((multisig.threshold, multisig.pending_threshold, multisig.threshold_change_eta), (1, 0, 0));
+}
+
+#[tokio::test]
+async fn a_change_applies_only_once() {
+    let mut fixture = change_proposed().await;
+    fixture.apply_at(PROPOSED_AT + TIMELOCK_SECS).await.expect("applied");
+
+    let err = fixture.apply_at(PROPOSED_AT + TIMELOCK_SECS + 1).await.expect_err("nothing pending");
+
+    assert_eq!(err, custom_error(0, MultisigError::NoPendingChange));
+}
@@ -0,0 +1,234 @@
+//! Token deposits through `deposit_secure_v2` and `deposit_secure_ata`.
+//!
+//! The pool accepts a single `deposit_mint`. A deposit moves real tokens
+//! from the user's token account into the pool's before the position is
+//! credited, and a token account of any other mint fails its constraint.
+//! `deposit_secure_ata` also insists on the user's associated token
+//! account; `deposit_any_token_account` checks neither mint nor address.
+
+use account_validation::{accounts, instruction, Pool, UserDeposit, ID};
+use anchor_lang::error::ErrorCode as AnchorError;
+use anchor_lang::{AccountDeserialize, InstructionData, ToAccountMetas};
+use anchor_spl::associated_token::{self, get_associated_token_address};
+use anchor_spl::token::{spl_token, TokenAccount};
+use security_tests::{custom_error, load, mint_account, pda, program_test, send, system_account, token_account};
+use solana_program_test::ProgramTestContext;
+use solana_sdk::{
+    instruction::Instruction,
+    native_token::LAMPORTS_PER_SOL,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    system_program,
+    transaction::TransactionError,
+};
+
+const MINT: Pubkey = Pubkey::new_from_array([0x31; 32]);
+const BALANCE: u64 = 1_000;
+const AMOUNT: u64 = 400;
+
+fn ix(accounts: impl ToAccountMetas, data: impl InstructionData) -> Instruction {
+    Instruction::new_with_bytes(ID, &data.data(), accounts.to_account_metas(None))
+}
+
+/// A pool taking `MINT`, a user with an open position and `BALANCE` tokens
+/// of `MINT` in their associated token account, and the pool's empty token
+/// account.
+struct Fixture {
+    context: ProgramTestContext,
+    user: Keypair,
+    pool: Pubkey,
+    user_deposit: Pubkey,
+    user_tokens: Pubkey,
+    pool_tokens: Pubkey,
+}
+
+async fn opened() -> Fixture {
+    let user = Keypair::new();
+    let mut test = program_test();
+    test.add_account(user.pubkey(), system_account(LAMPORTS_PER_SOL));
+    let mut context = test.start_with_context().await;
+
+    let config = pda(&[b"config"], &ID);
+    let pool = pda(&[b"pool", user.pubkey().as_ref()], &ID);
+    let user_account = pda(&[b"user", user.pubkey().as_ref()], &ID);
+    let user_deposit = pda(&[b"user_deposit", pool.as_ref(), user.pubkey().as_ref()], &ID);
+    let setup = [
+        ix(
+            accounts::InitializeConfig { config, admin: user.pubkey(), system_program: system_program::ID },
+            instruction::InitializeConfig { fee_bps: 0, max_positions: 1, dust_threshold: 0 },
+        ),
+        ix(
+            accounts::InitializePool { config, pool, authority: user.pubkey(), system_program: system_program::ID },
+            instruction::InitializePool {
+                reward_rate: 10,
+                global_tvl_cap: u64::MAX,
+                lock_secs: 0,
+                min_hold_secs: 0,
+                early_exit_bps: 0,
+                deposit_mint: MINT,
+                reward_mint: Pubkey::default(),
+            },
+        ),
+        ix(
+            accounts::InitializeUserAccount { user_account, user: user.pubkey(), system_program: system_program::ID },
+            instruction::InitializeUserAccount {},
+        ),
+        ix(
+            accounts::OpenDeposit {
+                config,
+                pool,
+                user_account,
+                user_deposit,
+                user: user.pubkey(),
+                system_program: system_program::ID,
+            },
+            instruction::OpenDeposit {},
+        ),
+    ];
+    send(&mut context.banks_client, &setup, &[&user]).await.expect("setup");
+
+    let user_tokens = get_associated_token_address(&user.pubkey(), &MINT);
+    let pool_tokens = Pubkey::new_unique();
+    context.set_account(&MINT, &mint_account(Pubkey::new_unique(), 6).into());
+    context.set_account(&user_tokens, &token_account(MINT, user.pubkey(), BALANCE).into());
+    context.set_account(&pool_tokens, &token_account(MINT, pool, 0).into());
+    Fixture { context, user, pool, user_deposit, user_tokens, pool_tokens }
+}
+
+impl Fixture {
+    async fn deposit(&mut self, user_tokens: Pubkey, pool_tokens: Pubkey) -> Result<(), TransactionError> {
+        let deposit = ix(
+            accounts::DepositSecureV2 {
+                config: pda(&[b"config"], &ID),
+                pool: self.pool,
+                authority: self.user.pubkey(),
+                user_deposit: self.user_deposit,
+                user_token_account: user_tokens,
+                pool_token_account: pool_tokens,
+                user: self.user.pubkey(),
+                token_program: spl_token::ID,
+            },
+            instruction::DepositSecureV2 { amount: AMOUNT },
+        );
+        let user = self.user.insecure_clone();
+        send(&mut self.context.banks_client, &[deposit], &[&user]).await
+    }
+
+    async fn deposit_from_ata(&mut self, user_tokens: Pubkey) -> Result<(), TransactionError> {
+        let deposit = ix(
+            accounts::DepositSecureAta {
+                config: pda(&[b"config"], &ID),
+                pool: self.pool,
+                user_deposit: self.user_deposit,
+                deposit_mint: MINT,
+                user_token_account: user_tokens,
+                pool_token_account: self.pool_tokens,
+                user: self.user.pubkey(),
+                token_program: spl_token::ID,
+                associated_token_program: associated_token::ID,
+            },
+            instruction::DepositSecureAta { amount: AMOUNT },
+        );
+        let user = self.user.insecure_clone();
+        send(&mut self.context.banks_client, &[deposit], &[&user]).await
+    }
+
+    /// A fresh token account of `mint` owned by `authority`, holding `amount`.
+    fn add_token_account(&mut self, mint: Pubkey, authority: Pubkey, amount: u64) -> Pubkey {
+        let key = Pubkey::new_unique();
+        self.context.set_account(&key, &token_account(mint, authority, amount).into());
+        key
+    }
+
+    async fn load<T: AccountDeserialize>(&mut self, key: Pubkey) -> T {
+        load(&mut self.context.banks_client, key).await
+    }
+
+    async fn tokens(&mut self, key: Pubkey) -> u64 {
+        self.load::<TokenAccount>(key).await.amount
+    }
+}
+
+#[tokio::test]
+async fn a_deposit_moves_the_tokens_and_credits_the_position() {
+    let mut fixture = opened().await;
+
+    fixture.deposit(fixture.user_tokens, fixture.pool_tokens).await.expect("right mint, own tokens");
+
+    assert_eq!(fixture.tokens(fixture.user_tokens).await, BALANCE - AMOUNT);
+    assert_eq!(fixture.tokens(fixture.pool_tokens).await, AMOUNT);
+    assert_eq!(fixture.load::<Pool>(fixture.pool).await.total_deposited, AMOUNT);
+    assert_eq!(fixture.load::<UserDeposit>(fixture.user_deposit).await.amount, AMOUNT);
+}
+
+#[tokio::test]
+async fn tokens_of_another_mint_are_rejected() {
+    let mut fixture = opened().await;
+    let other_tokens = fixture.add_token_account(Pubkey::new_unique(), fixture.user.pubkey(), BALANCE);
+
+    let err = fixture.deposit(other_tokens, fixture.pool_tokens).await.expect_err("not the pool's mint");
+
+    assert_eq!(err, custom_error(0, AnchorError::ConstraintTokenMint));
+    assert_eq!(fixture.tokens(other_tokens).await, BALANCE);
+    assert_eq!(fixture.load::<UserDeposit>(fixture.user_deposit).await.amount, 0);
+}
+
+#[tokio::test]
+async fn a_pool_token_account_of_another_mint_is_rejected() {
+    let mut fixture = opened().await;
+    let other_tokens = fixture.add_token_account(Pubkey::new_unique(), fixture.pool, 0);
+
+    let err = fixture.deposit(fixture.user_tokens, other_tokens).await.expect_err("not the pool's mint");
+
+    assert_eq!(err, custom_error(0, AnchorError::ConstraintTokenMint));
+    assert_eq!(fixture.tokens(fixture.user_tokens).await, BALANCE);
+}
+
+#[tokio::test]
+async fn a_deposit_from_the_users_ata_is_credited() {
+    let mut fixture = opened().await;
+
+    fixture.deposit_from_ata(fixture.user_tokens).await.expect("the canonical account");
+
+    assert_eq!(fixture.tokens(fixture.pool_tokens).await, AMOUNT);
+    assert_eq!(fixture.load::<UserDeposit>(fixture.user_deposit).await.amount, AMOUNT);
+}
+
+#[tokio::test]
+async fn another_account_of_the_deposit_mint_is_not_the_ata() {
+    let mut fixture = opened().await;
+    let side_account = fixture.add_token_account(MINT, fixture.user.pubkey(), BALANCE);
+
+    let err = fixture.deposit_from_ata(side_account).await.expect_err("right owner and mint, wrong address");
+
+    assert_eq!(err, custom_error(0, AnchorError::ConstraintAssociated));
+    assert_eq!(fixture.tokens(side_account).await, BALANCE);
+}
+
+#[tokio::test]
+async fn any_token_account_lets_worthless_tokens_be_credited() {
+    let mut fixture = opened().await;
+    let worthless = Pubkey::new_unique();
+    let attacker_tokens = fixture.add_token_account(worthless, fixture.user.pubkey(), BALANCE);
+    let pool_side = fixture.add_token_account(worthless, fixture.pool, 0);
+    let deposit = ix(
+        accounts::DepositAnyTokenAccount {
+            config: pda(&[b"config"], &ID),
+            pool: fixture.pool,
+            user_deposit: fixture.user_deposit,
+            user_token_account: attacker_tokens,
+            pool_token_account: pool_side,
+            user: fixture.user.pubkey(),
+            token_program: spl_token::ID,
+        },
+        instruction::DepositAnyTokenAccount { amount: AMOUNT },
+    );
+    let user = fixture.user.insecure_clone();
+
+    send(&mut fixture.context.banks_client, &[deposit], &[&user]).await.expect("only token::authority is checked");
+
+    // Credited in full, while the pool's real token account got nothing
+    assert_eq!(fixture.load::<UserDeposit>(fixture.user_deposit).await.amount, AMOUNT);
+    assert_eq!(fixture.load::<Pool>(fixture.pool).await.total_deposited, AMOUNT);
+    assert_eq!(fixture.tokens(fixture.pool_tokens).await, 0);
+}
@@ -0,0 +1,97 @@
+//! Decimals on token transfers in the arbitrary-CPI program.
+//!
+//! The mint has 6 decimals; every transfer here quotes 9. Plain `transfer`
+//! moves the amount anyway, `transfer_checked` refuses it.
+
+use anchor_lang::{InstructionData, ToAccountMetas};
+use anchor_spl::token::spl_token;
+use arbitrary_cpi::{accounts, instruction, ID};
+use security_tests::{custom_error, mint_account, program_test, send, system_account, token_account};
+use solana_program_test::BanksClient;
+use solana_sdk::{
+    instruction::Instruction,
+    native_token::LAMPORTS_PER_SOL,
+    program_pack::Pack,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    transaction::TransactionError,
+};
+
+const MINT_DECIMALS: u8 = 6;
+const QUOTED_DECIMALS: u8 = 9;
+/// "1 token" at `QUOTED_DECIMALS` - 1000 tokens at `MINT_DECIMALS`
+const ONE_QUOTED_TOKEN: u64 = 1_000_000_000;
+
+struct Fixture {
+    banks: BanksClient,
+    authority: Keypair,
+    mint: Pubkey,
+    source: Pubkey,
+    destination: Pubkey,
+}
+
+async fn funded() -> Fixture {
+    let authority = Keypair::new();
+    let (mint, source, destination) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+    let mut test = program_test();
+    test.add_account(authority.pubkey(), system_account(LAMPORTS_PER_SOL));
+    test.add_account(mint, mint_account(Pubkey::new_unique(), MINT_DECIMALS));
+    test.add_account(source, token_account(mint, authority.pubkey(), 10 * ONE_QUOTED_TOKEN));
+    test.add_account(destination, token_account(mint, Pubkey::new_unique(), 0));
+    let (banks, _payer, _) = test.start().await;
+    Fixture { banks, authority, mint, source, destination }
+}
+
+impl Fixture {
+    async fn send(&mut self, ix: Instruction) -> Result<(), TransactionError> {
+        send(&mut self.banks, &[ix], &[&self.authority]).await
+    }
+
+    async fn received(&mut self) -> u64 {
+        let account = self.banks.get_account(self.destination).await.unwrap().unwrap();
+        spl_token::state::Account::unpack(&account.data).unwrap().amount
+    }
+}
+
+#[tokio::test]
+async fn transfer_checked_rejects_mismatched_decimals() {
+    let mut fixture = funded().await;
+    let transfer = Instruction::new_with_bytes(
+        ID,
+        &instruction::TransferCheckedSecure { amount: ONE_QUOTED_TOKEN, decimals: QUOTED_DECIMALS }.data(),
+        accounts::TransferCheckedSecure {
+            source: fixture.source,
+            mint: fixture.mint,
+            destination: fixture.destination,
+            authority: fixture.authority.pubkey(),
+            token_program: spl_token::ID,
+        }
+        .to_account_metas(None),
+    );
+
+    let err = fixture.send(transfer).await.expect_err("decimals mismatch");
+
+    assert_eq!(err, custom_error(0, spl_token::error::TokenError::MintDecimalsMismatch as u32));
+    assert_eq!(fixture.received().await, 0);
+}
+
+#[tokio::test]
+async fn plain_transfer_silently_moves_the_misquoted_amount() {
+    let mut fixture = funded().await;
+    let transfer = Instruction::new_with_bytes(
+        ID,
+        &instruction::TransferUncheckedVulnerable { amount: ONE_QUOTED_TOKEN, decimals: QUOTED_DECIMALS }.data(),
+        accounts::TransferUncheckedVulnerable {
+            source: fixture.source,
+            destination: fixture.destination,
+            authority: fixture.authority.pubkey(),
+            token_program: spl_token::ID,
+        }
+        .to_account_metas(None),
+    );
+
+    fixture.send(transfer).await.expect("nothing checks the decimals");
+
+    // Meant as one token; the recipient got a thousand
+    assert_eq!(fixture.received().await, 1_000 * 10u64.pow(MINT_DECIMALS as u32));
+}
@@ -0,0 +1,159 @@
+//! The confused deputy in the arbitrary-CPI program, end to end.
+//!
+//! `distribute_rewards_vulnerable` signs for the treasury PDA and invokes
+//! whatever `reward_program` it is given. Handed
+//! `programs/malicious-reward-program`, that signature moves the treasury's
+//! tokens to the attacker. `distribute_rewards_secure` only signs for the
+//! Token program and only pays out `reward_amount`.
+
+use anchor_lang::error::ErrorCode as AnchorError;
+use anchor_lang::{InstructionData, ToAccountMetas};
+use anchor_spl::token::{spl_token, TokenAccount};
+use arbitrary_cpi::{accounts, instruction, Treasury, ID};
+use security_tests::{anchor_account, load, program_test, send, system_account, token_account};
+use solana_program_test::BanksClient;
+use solana_sdk::{
+    instruction::{AccountMeta, Instruction, InstructionError},
+    native_token::LAMPORTS_PER_SOL,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    transaction::TransactionError,
+};
+
+const TREASURY_BALANCE: u64 = 1_000;
+const REWARD_AMOUNT: u64 = 100;
+
+/// A treasury PDA holding `TREASURY_BALANCE` tokens, its admin, and an
+/// attacker with an empty token account of the same mint.
+struct Fixture {
+    banks: BanksClient,
+    admin: Keypair,
+    attacker: Keypair,
+    treasury: Pubkey,
+    treasury_tokens: Pubkey,
+    attacker_tokens: Pubkey,
+}
+
+async fn funded_treasury() -> Fixture {
+    let admin = Keypair::new();
+    let attacker = Keypair::new();
+    let mint = Pubkey::new_unique();
+    let (treasury, bump) = Pubkey::find_program_address(&[b"treasury"], &ID);
+    let treasury_tokens = Pubkey::new_unique();
+    let attacker_tokens = Pubkey::new_unique();
+    let state = Treasury { admin: admin.pubkey(), reward_amount: REWARD_AMOUNT, bump };
+
+    let mut test = program_test();
+    test.add_account(admin.pubkey(), system_account(LAMPORTS_PER_SOL));
+    test.add_account(attacker.pubkey(), system_account(LAMPORTS_PER_SOL));
+    test.add_account(treasury, anchor_account(&state, ID));
+    test.add_account(treasury_tokens, token_account(mint, treasury, TREASURY_BALANCE));
+    test.add_account(attacker_tokens, token_account(mint, attacker.pubkey(), 0));
+    let (banks, _payer, _) = test.start().await;
+    Fixture { banks, admin, attacker, treasury, treasury_tokens, attacker_tokens }
+}
+
+impl Fixture {
+    async fn send(&mut self, ix: Instruction, signer: &Keypair) -> Result<(), TransactionError> {
+        send(&mut self.banks, &[ix], &[signer]).await
+    }
+
+    /// `distribute_rewards_vulnerable`, called by the attacker with their
+    /// own program and the accounts it wants forwarded.
+    fn vulnerable_ix(&self) -> Instruction {
+        let mut ix = Instruction::new_with_bytes(
+            ID,
+            &instruction::DistributeRewardsVulnerable {}.data(),
+            accounts::DistributeRewardsVulnerable {
+                treasury: self.treasury,
+                reward_program: malicious_reward_program::ID,
+                admin: self.attacker.pubkey(),
+            }
+            .to_account_metas(None),
+        );
+        ix.accounts.extend([
+            AccountMeta::new(self.treasury_tokens, false),
+            AccountMeta::new(self.attacker_tokens, false),
+            AccountMeta::new_readonly(spl_token::ID, false),
+        ]);
+        ix
+    }
+
+    fn secure_ix(&self, token_program: Pubkey) -> Instruction {
+        Instruction::new_with_bytes(
+            ID,
+            &instruction::DistributeRewardsSecure {}.data(),
+            accounts::DistributeRewardsSecure {
+                treasury: self.treasury,
+                treasury_token_account: self.treasury_tokens,
+                user_token_account: self.attacker_tokens,
+                token_program,
+                admin: self.admin.pubkey(),
+            }
+            .to_account_metas(None),
+        )
+    }
+
+    async fn balance(&mut self, key: Pubkey) -> u64 {
+        load::<TokenAccount>(&mut self.banks, key).await.amount
+    }
+}
+
+#[tokio::test]
+async fn the_malicious_program_drains_the_treasury() {
+    let mut fixture = funded_treasury().await;
+    let (ix, attacker) = (fixture.vulnerable_ix(), fixture.attacker.insecure_clone());
+
+    fixture.send(ix, &attacker).await.expect("the treasury signs for the attacker's program");
+
+    assert_eq!(fixture.balance(fixture.treasury_tokens).await, 0);
+    assert_eq!(fixture.balance(fixture.attacker_tokens).await, TREASURY_BALANCE);
+}
+
+#[tokio::test]
+async fn the_malicious_program_cannot_drain_without_the_treasury_signature() {
+    let mut fixture = funded_treasury().await;
+    let mut ix = Instruction::new_with_bytes(
+        malicious_reward_program::ID,
+        &malicious_reward_program::instruction::Distribute {}.data(),
+        malicious_reward_program::accounts::Distribute {
+            authority: fixture.treasury,
+            source: fixture.treasury_tokens,
+            destination: fixture.attacker_tokens,
+            token_program: spl_token::ID,
+        }
+        .to_account_metas(None),
+    );
+    // Only the treasury program can sign for its PDA
+    ix.accounts[0].is_signer = false;
+    let attacker = fixture.attacker.insecure_clone();
+
+    let err = fixture.send(ix, &attacker).await.expect_err("nobody signed for the treasury");
+
+    let not_signed = InstructionError::Custom(AnchorError::AccountNotSigner as u32);
+    assert_eq!(err, TransactionError::InstructionError(0, not_signed));
+    assert_eq!(fixture.balance(fixture.treasury_tokens).await, TREASURY_BALANCE);
+}
+
+#[tokio::test]
+async fn the_secure_distribution_pays_only_the_reward() {
+    let mut fixture = funded_treasury().await;
+    let (ix, admin) = (fixture.secure_ix(spl_token::ID), fixture.admin.insecure_clone());
+
+    fixture.send(ix, &admin).await.expect("the Token program pays the reward");
+
+    assert_eq!(fixture.balance(fixture.treasury_tokens).await, TREASURY_BALANCE - REWARD_AMOUNT);
+    assert_eq!(fixture.balance(fixture.attacker_tokens).await, REWARD_AMOUNT);
+}
+
+#[tokio::test]
+async fn the_secure_distribution_refuses_the_malicious_program() {
+    let mut fixture = funded_treasury().await;
+    let (ix, admin) = (fixture.secure_ix(malicious_reward_program::ID), fixture.admin.insecure_clone());
+
+    let err = fixture.send(ix, &admin).await.expect_err("not the Token program");
+
+    let wrong_program = InstructionError::Custom(AnchorError::InvalidProgramId as u32);
+    assert_eq!(err, TransactionError::InstructionError(0, wrong_program));
+    assert_eq!(fixture.balance(fixture.treasury_tokens).await, TREASURY_BALANCE);
+}
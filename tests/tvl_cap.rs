@@ -0,0 +1,128 @@
+//! Global TVL cap on the account-validation pool.
+//!
+//! The cap is protocol-wide: deposits from every user add up against it,
+//! and each one moves its tokens into the pool.
+
+use account_validation::{accounts, instruction, ValidationError, ID};
+use anchor_lang::{InstructionData, ToAccountMetas};
+use anchor_spl::token::{spl_token, TokenAccount};
+use security_tests::{custom_error, load, pda, program_test, send, system_account, token_account};
+use solana_sdk::{
+    instruction::Instruction,
+    native_token::LAMPORTS_PER_SOL,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    system_program,
+};
+
+const MINT: Pubkey = Pubkey::new_from_array([0x73; 32]);
+const CAP: u64 = 1_000;
+
+fn ix(accounts: impl ToAccountMetas, data: impl InstructionData) -> Instruction {
+    Instruction::new_with_bytes(ID, &data.data(), accounts.to_account_metas(None))
+}
+
+/// A depositor with SOL for fees and a token account of `MINT` they own.
+struct User {
+    keypair: Keypair,
+    token_account: Pubkey,
+}
+
+fn deposit(pool: Pubkey, pool_tokens: Pubkey, user: &User, amount: u64) -> Instruction {
+    ix(
+        accounts::DepositSecure {
+            config: pda(&[b"config"], &ID),
+            pool,
+            user_deposit: pda(&[b"user_deposit", pool.as_ref(), user.keypair.pubkey().as_ref()], &ID),
+            user_token_account: user.token_account,
+            pool_token_account: pool_tokens,
+            user: user.keypair.pubkey(),
+            token_program: spl_token::ID,
+        },
+        instruction::DepositSecure { amount },
+    )
+}
+
+#[tokio::test]
+async fn deposits_across_users_stop_at_the_cap() {
+    let users: Vec<User> = (0..2)
+        .map(|_| User { keypair: Keypair::new(), token_account: Pubkey::new_unique() })
+        .collect();
+
+    let payer = Keypair::new();
+    let config = pda(&[b"config"], &ID);
+    let pool = pda(&[b"pool", payer.pubkey().as_ref()], &ID);
+    let pool_tokens = Pubkey::new_unique();
+    let mut test = program_test();
+    test.add_account(payer.pubkey(), system_account(LAMPORTS_PER_SOL));
+    test.add_account(pool_tokens, token_account(MINT, pool, 0));
+    for user in &users {
+        test.add_account(user.keypair.pubkey(), system_account(LAMPORTS_PER_SOL));
+        test.add_account(user.token_account, token_account(MINT, user.keypair.pubkey(), CAP));
+    }
+    let (mut banks, _, _) = test.start().await;
+
+    let setup = [
+        ix(
+            accounts::InitializeConfig { config, admin: payer.pubkey(), system_program: system_program::ID },
+            instruction::InitializeConfig { fee_bps: 100, max_positions: 1, dust_threshold: 0 },
+        ),
+        ix(
+            accounts::InitializePool { config, pool, authority: payer.pubkey(), system_program: system_program::ID },
+            instruction::InitializePool {
+                reward_rate: 10,
+                global_tvl_cap: CAP,
+                lock_secs: 0,
+                min_hold_secs: 0,
+                early_exit_bps: 0,
+                deposit_mint: MINT,
+                reward_mint: Pubkey::default(),
+            },
+        ),
+    ];
+    for instruction in setup {
+        send(&mut banks, &[instruction], &[&payer]).await.expect("setup");
+    }
+    for user in &users {
+        let user_account = pda(&[b"user", user.keypair.pubkey().as_ref()], &ID);
+        let user_deposit = pda(&[b"user_deposit", pool.as_ref(), user.keypair.pubkey().as_ref()], &ID);
+        let open = [
+            ix(
+                accounts::InitializeUserAccount {
+                    user_account,
+                    user: user.keypair.pubkey(),
+                    system_program: system_program::ID,
+                },
+                instruction::InitializeUserAccount {},
+            ),
+            ix(
+                accounts::OpenDeposit {
+                    config,
+                    pool,
+                    user_account,
+                    user_deposit,
+                    user: user.keypair.pubkey(),
+                    system_program: system_program::ID,
+                },
+                instruction::OpenDeposit {},
+            ),
+        ];
+        for instruction in open {
+            send(&mut banks, &[instruction], &[&user.keypair]).await.expect("open deposit");
+        }
+    }
+
+    // 600 + 400 fills the cap exactly
+    send(&mut banks, &[deposit(pool, pool_tokens, &users[0], 600)], &[&users[0].keypair])
+        .await
+        .expect("first user deposits under the cap");
+    send(&mut banks, &[deposit(pool, pool_tokens, &users[1], CAP - 600)], &[&users[1].keypair])
+        .await
+        .expect("second user deposits up to the cap");
+    assert_eq!(load::<TokenAccount>(&mut banks, pool_tokens).await.amount, CAP);
+
+    let err = send(&mut banks, &[deposit(pool, pool_tokens, &users[0], 1)], &[&users[0].keypair])
+        .await
+        .expect_err("pool is full");
+    assert_eq!(err, custom_error(0, ValidationError::TvlCapReached));
+}
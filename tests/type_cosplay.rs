@@ -0,0 +1,178 @@
+//! A `UserAccount` cosplaying as a `RewardVault` or an `AdminConfig`.
+//!
+//! `UserAccount` and `RewardVault` are both `Pubkey + u64 + u8`, so only the
+//! discriminator tells them apart. The vulnerable claim reads a user's
+//! deposit balance as claimable rewards; the secure claim refuses the
+//! account outright. A balance of 1 puts 0x01 at byte 32, right where the
+//! vulnerable admin check looks for its `is_admin` flag.
+
+use anchor_lang::error::ErrorCode as AnchorError;
+use anchor_lang::{AccountDeserialize, AnchorSerialize, Discriminator, InstructionData, ToAccountMetas};
+use security_tests::{anchor_account, custom_error, pda, program_test, raw_account};
+use solana_sdk::{
+    account::Account,
+    instruction::Instruction,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    system_program,
+    transaction::{Transaction, TransactionError},
+};
+use type_cosplay::{accounts, instruction, AdminConfig, RewardVault, UserAccount, ID};
+
+const BALANCE: u64 = 1_000;
+
+/// Sends `claim` against a `UserAccount` holding `BALANCE` and returns the
+/// claimable amount the program reported.
+async fn claim_with_user_account(claim: fn(vault: Pubkey, user: Pubkey) -> Instruction) -> Result<u64, TransactionError> {
+    let user = Keypair::new();
+    let (vault, bump) = Pubkey::find_program_address(&[b"user", user.pubkey().as_ref()], &ID);
+    let state = UserAccount { owner: user.pubkey(), balance: BALANCE, bump };
+
+    let mut test = program_test();
+    test.add_account(vault, anchor_account(&state, ID));
+    let (mut banks, payer, blockhash) = test.start().await;
+
+    let tx = Transaction::new_signed_with_payer(
+        &[claim(vault, user.pubkey())],
+        Some(&payer.pubkey()),
+        &[&payer, &user],
+        blockhash,
+    );
+    let result = banks.process_transaction_with_metadata(tx).await.unwrap();
+    result.result?;
+
+    // The runtime strips trailing zero bytes from return data
+    let data = result
+        .metadata
+        .and_then(|metadata| metadata.return_data)
+        .map(|return_data| return_data.data)
+        .unwrap_or_default();
+    let mut bytes = [0u8; 8];
+    bytes[..data.len()].copy_from_slice(&data);
+    Ok(u64::from_le_bytes(bytes))
+}
+
+#[tokio::test]
+async fn vulnerable_claim_reads_user_balance_as_rewards() {
+    let claimable = claim_with_user_account(|vault, user| {
+        Instruction::new_with_bytes(
+            ID,
+            &instruction::ClaimRewardsVulnerable {}.data(),
+            accounts::ClaimRewardsVulnerable { vault, user }.to_account_metas(None),
+        )
+    })
+    .await
+    .expect("vulnerable claim accepts any layout-compatible account");
+
+    assert_eq!(claimable, BALANCE, "deposit balance reported as claimable rewards");
+}
+
+#[tokio::test]
+async fn secure_claim_rejects_user_account() {
+    let err = claim_with_user_account(|reward_vault, user| {
+        Instruction::new_with_bytes(
+            ID,
+            &instruction::ClaimRewardsSecure {}.data(),
+            accounts::ClaimRewardsSecure { reward_vault, user }.to_account_metas(None),
+        )
+    })
+    .await
+    .expect_err("UserAccount is not a RewardVault");
+
+    assert_eq!(err, custom_error(0, AnchorError::AccountDiscriminatorMismatch));
+}
+
+/// Sends `action` for `signer` against `account`, loaded at the
+/// AdminConfig address.
+async fn admin_action(
+    signer: &Keypair,
+    account: Account,
+    action: fn(admin_config: Pubkey, signer: Pubkey) -> Instruction,
+) -> Result<(), TransactionError> {
+    let admin_config = pda(&[b"admin_config"], &ID);
+    let mut test = program_test();
+    test.add_account(admin_config, account);
+    let (mut banks, payer, blockhash) = test.start().await;
+
+    let tx = Transaction::new_signed_with_payer(
+        &[action(admin_config, signer.pubkey())],
+        Some(&payer.pubkey()),
+        &[&payer, signer],
+        blockhash,
+    );
+    banks.process_transaction(tx).await.map_err(|err| err.unwrap())
+}
+
+fn admin_action_vulnerable(admin_config: Pubkey, signer: Pubkey) -> Instruction {
+    Instruction::new_with_bytes(
+        ID,
+        &instruction::AdminActionVulnerable {}.data(),
+        accounts::AdminActionVulnerable { admin_config, signer }.to_account_metas(None),
+    )
+}
+
+fn admin_action_secure(admin_config: Pubkey, signer: Pubkey) -> Instruction {
+    Instruction::new_with_bytes(
+        ID,
+        &instruction::AdminActionSecure {}.data(),
+        accounts::AdminActionSecure { admin_config, signer }.to_account_metas(None),
+    )
+}
+
+/// The attacker's UserAccount with `balance = 1`
+fn balance_of_one(attacker: &Keypair) -> UserAccount {
+    UserAccount { owner: attacker.pubkey(), balance: 1, bump: 255 }
+}
+
+#[test]
+fn discriminators_are_pairwise_distinct() {
+    let discriminators = [
+        ("AdminConfig", AdminConfig::DISCRIMINATOR),
+        ("UserAccount", UserAccount::DISCRIMINATOR),
+        ("RewardVault", RewardVault::DISCRIMINATOR),
+    ];
+    for (i, (a, first)) in discriminators.iter().enumerate() {
+        for (b, second) in &discriminators[i + 1..] {
+            assert_ne!(first, second, "{a} and {b} share a discriminator");
+        }
+    }
+}
+
+#[test]
+fn a_user_account_does_not_deserialize_as_admin_config() {
+    let attacker = Keypair::new();
+    let account = anchor_account(&balance_of_one(&attacker), ID);
+
+    let result = AdminConfig::try_deserialize(&mut account.data.as_slice());
+
+    assert_eq!(result.err(), Some(AnchorError::AccountDiscriminatorMismatch.into()));
+}
+
+#[tokio::test]
+async fn a_balance_of_one_passes_the_vulnerable_admin_check() {
+    let attacker = Keypair::new();
+    // The documented layout: UserAccount's fields with no discriminator in
+    // front, so `owner` is bytes 0..32 and balance's low byte is byte 32.
+    // Behind a discriminator the owner would sit at 8..40 and the check
+    // would read the wrong key.
+    let data = balance_of_one(&attacker).try_to_vec().unwrap();
+    assert_eq!(data[32], 0x01);
+
+    let result = admin_action(&attacker, raw_account(data, system_program::ID), admin_action_vulnerable).await;
+
+    assert_eq!(result, Ok(()), "user balance read as is_admin");
+}
+
+#[tokio::test]
+async fn a_balance_of_one_is_not_an_admin_config() {
+    let attacker = Keypair::new();
+
+    // The same bytes the vulnerable check accepts: not this program's
+    let data = balance_of_one(&attacker).try_to_vec().unwrap();
+    let forged = admin_action(&attacker, raw_account(data, system_program::ID), admin_action_secure).await;
+    assert_eq!(forged, Err(custom_error(0, AnchorError::AccountOwnedByWrongProgram)));
+
+    // A real UserAccount of this program: the wrong type
+    let user = admin_action(&attacker, anchor_account(&balance_of_one(&attacker), ID), admin_action_secure).await;
+    assert_eq!(user, Err(custom_error(0, AnchorError::AccountDiscriminatorMismatch)));
+}
@@ -0,0 +1,137 @@
+//! Bootstrapping the admin of the upgrade-authority program.
+//!
+//! `bootstrap_admin` only creates the config when the signer is the upgrade
+//! authority recorded in this program's ProgramData account.
+//! `initialize_config_vulnerable` lets anyone who calls first pick the admin.
+
+use anchor_lang::error::ErrorCode as AnchorError;
+use anchor_lang::solana_program::bpf_loader_upgradeable;
+use anchor_lang::{AccountDeserialize, InstructionData, ToAccountMetas};
+use security_tests::{custom_error, pda, program_data_account, program_test, send, system_account};
+use solana_program_test::BanksClient;
+use solana_sdk::{
+    instruction::Instruction,
+    native_token::LAMPORTS_PER_SOL,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    system_program,
+    transaction::TransactionError,
+};
+use upgrade_authority::{accounts, instruction, ProgramConfig, UpgradeAuthorityError, ID};
+
+/// A deployed program whose upgrade authority is `deployer`, no config yet,
+/// and an `attacker` who is the upgrade authority of some other program.
+struct Fixture {
+    banks: BanksClient,
+    deployer: Keypair,
+    attacker: Keypair,
+    config: Pubkey,
+    program_data: Pubkey,
+    attackers_program_data: Pubkey,
+}
+
+async fn deployed_with(upgrade_authority: impl FnOnce(&Keypair) -> Option<Pubkey>) -> Fixture {
+    let deployer = Keypair::new();
+    let attacker = Keypair::new();
+    let program_data = pda(&[ID.as_ref()], &bpf_loader_upgradeable::ID);
+    let attackers_program_data = pda(&[Pubkey::new_unique().as_ref()], &bpf_loader_upgradeable::ID);
+
+    let mut test = program_test();
+    test.add_account(deployer.pubkey(), system_account(LAMPORTS_PER_SOL));
+    test.add_account(attacker.pubkey(), system_account(LAMPORTS_PER_SOL));
+    test.add_account(program_data, program_data_account(upgrade_authority(&deployer)));
+    test.add_account(attackers_program_data, program_data_account(Some(attacker.pubkey())));
+    let (banks, _payer, _) = test.start().await;
+    let config = pda(&[b"config"], &ID);
+    Fixture { banks, deployer, attacker, config, program_data, attackers_program_data }
+}
+
+async fn deployed() -> Fixture {
+    deployed_with(|deployer| Some(deployer.pubkey())).await
+}
+
+impl Fixture {
+    async fn send(&mut self, signer: &Keypair, ix: Instruction) -> Result<(), TransactionError> {
+        send(&mut self.banks, &[ix], &[signer]).await
+    }
+
+    async fn bootstrap(&mut self, signer: &Keypair, program_data: Pubkey) -> Result<(), TransactionError> {
+        let metas = accounts::BootstrapAdmin {
+            config: self.config,
+            program_data,
+            authority: signer.pubkey(),
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None);
+        let ix = Instruction::new_with_bytes(ID, &instruction::BootstrapAdmin { admin: signer.pubkey() }.data(), metas);
+        self.send(signer, ix).await
+    }
+
+    async fn admin(&mut self) -> Option<Pubkey> {
+        let account = self.banks.get_account(self.config).await.unwrap()?;
+        Some(ProgramConfig::try_deserialize(&mut account.data.as_slice()).unwrap().admin)
+    }
+}
+
+#[tokio::test]
+async fn the_upgrade_authority_bootstraps_the_admin() {
+    let mut fixture = deployed().await;
+    let (deployer, program_data) = (fixture.deployer.insecure_clone(), fixture.program_data);
+
+    fixture.bootstrap(&deployer, program_data).await.expect("upgrade authority signed");
+
+    assert_eq!(fixture.admin().await, Some(deployer.pubkey()));
+}
+
+#[tokio::test]
+async fn a_non_authority_cannot_bootstrap() {
+    let mut fixture = deployed().await;
+    let (attacker, program_data) = (fixture.attacker.insecure_clone(), fixture.program_data);
+
+    let err = fixture.bootstrap(&attacker, program_data).await.expect_err("not the upgrade authority");
+
+    assert_eq!(err, custom_error(0, UpgradeAuthorityError::NotUpgradeAuthority));
+    assert_eq!(fixture.admin().await, None);
+}
+
+#[tokio::test]
+async fn another_programs_data_account_is_rejected() {
+    // Genuine ProgramData naming the attacker, but for a different program
+    let mut fixture = deployed().await;
+    let (attacker, theirs) = (fixture.attacker.insecure_clone(), fixture.attackers_program_data);
+
+    let err = fixture.bootstrap(&attacker, theirs).await.expect_err("wrong program data");
+
+    assert_eq!(err, custom_error(0, AnchorError::ConstraintSeeds));
+}
+
+#[tokio::test]
+async fn an_immutable_program_cannot_bootstrap() {
+    let mut fixture = deployed_with(|_| None).await;
+    let (deployer, program_data) = (fixture.deployer.insecure_clone(), fixture.program_data);
+
+    let err = fixture.bootstrap(&deployer, program_data).await.expect_err("no upgrade authority left");
+
+    assert_eq!(err, custom_error(0, UpgradeAuthorityError::NotUpgradeAuthority));
+}
+
+#[tokio::test]
+async fn anyone_can_claim_the_vulnerable_config_first() {
+    let mut fixture = deployed().await;
+    let attacker = fixture.attacker.insecure_clone();
+    let metas = accounts::InitializeConfigVulnerable {
+        config: fixture.config,
+        payer: attacker.pubkey(),
+        system_program: system_program::ID,
+    }
+    .to_account_metas(None);
+    let ix = Instruction::new_with_bytes(
+        ID,
+        &instruction::InitializeConfigVulnerable { admin: attacker.pubkey() }.data(),
+        metas,
+    );
+
+    fixture.send(&attacker, ix).await.expect("nothing checks the caller");
+
+    assert_eq!(fixture.admin().await, Some(attacker.pubkey()));
+}
@@ -0,0 +1,201 @@
+//! The shared `assert_value_conserved` invariant.
+//!
+//! Instructions that move value sum every balance they touch before and
+//! after, re-reading the accounts once the transfer is done; the sums must
+//! match. Real token transfers pass it. `withdraw` run against a token
+//! program that loses a token on every transfer does not.
+
+use account_validation::{accounts, instruction, Pool, UserDeposit, ID};
+use anchor_lang::prelude::{AccountInfo, ProgramError, Result};
+use anchor_lang::solana_program::entrypoint::ProgramResult;
+use anchor_lang::{InstructionData, ToAccountMetas};
+use anchor_spl::token::spl_token;
+use anchor_spl::token::spl_token::instruction::TokenInstruction;
+use anchor_spl::token::spl_token::solana_program::program_pack::Pack;
+use security_common::{assert_value_conserved, sum_balances, SecurityError};
+use security_tests::{anchor_account, custom_error, pda, program_test, send, system_account, token_account};
+use solana_program_test::{processor, BanksClient};
+use solana_sdk::{
+    instruction::{AccountMeta, Instruction},
+    native_token::LAMPORTS_PER_SOL,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    transaction::{Transaction, TransactionError},
+};
+
+fn code<T: std::fmt::Debug>(result: Result<T>) -> ProgramError {
+    result.expect_err("invariant tripped").into()
+}
+
+#[test]
+fn equal_sums_pass() {
+    assert_value_conserved(1_000, 1_000).expect("nothing created or destroyed");
+}
+
+#[test]
+fn unequal_sums_are_a_violation() {
+    let violation = ProgramError::Custom(SecurityError::ConservationViolation.into());
+
+    assert_eq!(code(assert_value_conserved(1_000, 999)), violation);
+    assert_eq!(code(assert_value_conserved(1_000, 1_001)), violation);
+}
+
+#[test]
+fn summing_past_u64_is_an_overflow() {
+    assert_eq!(code(sum_balances([u64::MAX, 1])), ProgramError::Custom(SecurityError::Overflow.into()));
+}
+
+/// Runs `split_payment` of `total` from a source holding `total`, paying
+/// the source itself when `self_pay` is set, and returns the sum of every
+/// touched token balance before and after.
+async fn split_payment_sums(total: u64, bps: Vec<u16>, self_pay: bool) -> (u64, u64) {
+    let payer = Keypair::new();
+    let mint = Pubkey::new_unique();
+    let source = Pubkey::new_unique();
+    let mut recipients: Vec<Pubkey> = bps.iter().map(|_| Pubkey::new_unique()).collect();
+    if self_pay {
+        recipients[0] = source;
+    }
+
+    let mut test = program_test();
+    test.add_account(payer.pubkey(), system_account(LAMPORTS_PER_SOL));
+    test.add_account(source, token_account(mint, payer.pubkey(), total));
+    for recipient in recipients.iter().filter(|recipient| **recipient != source) {
+        test.add_account(*recipient, token_account(mint, Pubkey::new_unique(), 0));
+    }
+    let (mut banks, _, blockhash) = test.start().await;
+
+    let mut touched = vec![source];
+    touched.extend(recipients.iter().filter(|recipient| **recipient != source));
+    let before = token_sum(&mut banks, &touched).await;
+
+    let mut metas = accounts::SplitPayment { source, payer: payer.pubkey(), token_program: spl_token::ID }
+        .to_account_metas(None);
+    metas.extend(recipients.iter().map(|recipient| AccountMeta::new(*recipient, false)));
+    let ix = Instruction::new_with_bytes(ID, &instruction::SplitPayment { total, bps_splits: bps, min_out: 0, deadline: i64::MAX }.data(), metas);
+    let tx = Transaction::new_signed_with_payer(&[ix], Some(&payer.pubkey()), &[&payer], blockhash);
+    banks.process_transaction(tx).await.expect("split conserves value");
+
+    (before, token_sum(&mut banks, &touched).await)
+}
+
+async fn token_sum(banks: &mut BanksClient, keys: &[Pubkey]) -> u64 {
+    let mut sum = 0;
+    for key in keys {
+        let account = banks.get_account(*key).await.unwrap().unwrap();
+        sum += spl_token::state::Account::unpack(&account.data).unwrap().amount;
+    }
+    sum
+}
+
+#[tokio::test]
+async fn split_payment_conserves_the_touched_balances() {
+    let (before, after) = split_payment_sums(1_001, vec![3_333, 3_333, 3_334], false).await;
+
+    assert_eq!((before, after), (1_001, 1_001));
+}
+
+#[tokio::test]
+async fn split_payment_back_to_the_source_still_conserves() {
+    // The source is also a recipient, so it is counted on both sides
+    let (before, after) = split_payment_sums(1_000, vec![5_000, 5_000], true).await;
+
+    assert_eq!((before, after), (1_000, 1_000));
+}
+
+const MINT: Pubkey = Pubkey::new_from_array([0x73; 32]);
+const DEPOSIT: u64 = 1_000;
+
+/// Stands in for SPL Token: a `Transfer` debits the full amount from the
+/// source but credits the destination one token less.
+fn leaky_token(_program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    let TokenInstruction::Transfer { amount } = TokenInstruction::unpack(data)? else {
+        return Err(ProgramError::InvalidInstructionData);
+    };
+    let (source, destination) = (&accounts[0], &accounts[1]);
+    let mut from = spl_token::state::Account::unpack(&source.try_borrow_data()?)?;
+    from.amount -= amount;
+    spl_token::state::Account::pack(from, &mut source.try_borrow_mut_data()?)?;
+    let mut to = spl_token::state::Account::unpack(&destination.try_borrow_data()?)?;
+    to.amount += amount - 1;
+    spl_token::state::Account::pack(to, &mut destination.try_borrow_mut_data()?)?;
+    Ok(())
+}
+
+/// Withdraws a user's whole `DEPOSIT` from a pool with no lock, hold or
+/// rewards, with `leaky_token` in place of SPL Token when `leaky` is set.
+async fn withdraw_everything(leaky: bool) -> std::result::Result<(), TransactionError> {
+    let user = Keypair::new();
+    let (pool_tokens, user_tokens) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let (pool, pool_bump) = Pubkey::find_program_address(&[b"pool", user.pubkey().as_ref()], &ID);
+    let (user_deposit, deposit_bump) =
+        Pubkey::find_program_address(&[b"user_deposit", pool.as_ref(), user.pubkey().as_ref()], &ID);
+    let pool_state = Pool {
+        authority: user.pubkey(),
+        total_deposited: DEPOSIT,
+        reward_rate: 0,
+        config: pda(&[b"config"], &ID),
+        total_accrued: 0,
+        global_tvl_cap: u64::MAX,
+        lock_secs: 0,
+        dust_swept: 0,
+        min_hold_secs: 0,
+        early_exit_bps: 0,
+        early_exit_fees: 0,
+        deposit_mint: MINT,
+        reward_mint: Pubkey::default(),
+        vault: Pubkey::default(),
+        bump: pool_bump,
+    };
+    let deposit_state = UserDeposit {
+        pool,
+        owner: user.pubkey(),
+        amount: DEPOSIT,
+        locked_until: 0,
+        deposited_at: 0,
+        permit_nonce: 0,
+        accrued_rewards: 0,
+        rewards_updated_at: 0,
+        bump: deposit_bump,
+    };
+
+    let mut test = program_test();
+    if leaky {
+        test.add_program("leaky_token", spl_token::ID, processor!(leaky_token));
+    }
+    test.add_account(user.pubkey(), system_account(LAMPORTS_PER_SOL));
+    test.add_account(pool, anchor_account(&pool_state, ID));
+    test.add_account(user_deposit, anchor_account(&deposit_state, ID));
+    test.add_account(pool_tokens, token_account(MINT, pool, DEPOSIT));
+    test.add_account(user_tokens, token_account(MINT, user.pubkey(), 0));
+    let (mut banks, _, _) = test.start().await;
+
+    let withdraw = Instruction::new_with_bytes(
+        ID,
+        &instruction::Withdraw { amount: DEPOSIT }.data(),
+        accounts::Withdraw {
+            pool,
+            user_deposit,
+            pool_token_account: pool_tokens,
+            user_token_account: user_tokens,
+            user: user.pubkey(),
+            token_program: spl_token::ID,
+        }
+        .to_account_metas(None),
+    );
+    send(&mut banks, &[withdraw], &[&user]).await
+}
+
+#[tokio::test]
+async fn withdraw_conserves_the_touched_balances() {
+    assert_eq!(withdraw_everything(false).await, Ok(()));
+}
+
+#[tokio::test]
+async fn withdraw_through_a_leaky_transfer_trips_the_invariant() {
+    // The pool's account drops by the full payout, so the payout check
+    // passes; only the re-read user balance shows the missing token
+    let err = withdraw_everything(true).await.expect_err("a token went missing");
+
+    assert_eq!(err, custom_error(0, SecurityError::ConservationViolation));
+}
@@ -0,0 +1,109 @@
+//! Creating the vault's token account in the arbitrary-CPI program.
+//!
+//! `init_token_account` creates `[b"vault_token", vault]` through the Token
+//! Program for the vault's own mint, with the vault PDA itself as its
+//! token authority.
+
+use anchor_lang::error::ErrorCode as AnchorError;
+use anchor_lang::solana_program::program_pack::Pack;
+use anchor_lang::{InstructionData, ToAccountMetas};
+use anchor_spl::token::spl_token;
+use arbitrary_cpi::{accounts, instruction, Vault, ID};
+use security_tests::{anchor_account, custom_error, mint_account, pda, program_test, send, system_account};
+use solana_program_test::BanksClient;
+use solana_sdk::{
+    instruction::Instruction,
+    native_token::LAMPORTS_PER_SOL,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    system_program,
+    transaction::TransactionError,
+};
+
+/// A vault for `mint`, a second unrelated mint, and no token account yet.
+struct Fixture {
+    banks: BanksClient,
+    authority: Keypair,
+    vault: Pubkey,
+    mint: Pubkey,
+    other_mint: Pubkey,
+    vault_token_account: Pubkey,
+}
+
+/// `prefund` lamports are sent to the token account's address beforehand.
+async fn vault_without_token_account(prefund: u64) -> Fixture {
+    let authority = Keypair::new();
+    let mint = Pubkey::new_unique();
+    let other_mint = Pubkey::new_unique();
+    let (vault, bump) = Pubkey::find_program_address(&[b"vault", authority.pubkey().as_ref()], &ID);
+    let vault_authority_bump = Pubkey::find_program_address(&[b"vault_authority"], &ID).1;
+    let state = Vault { authority: authority.pubkey(), mint, balance: 0, vault_authority_bump, bump };
+    let vault_token_account = pda(&[b"vault_token", vault.as_ref()], &ID);
+
+    let mut test = program_test();
+    test.add_account(authority.pubkey(), system_account(LAMPORTS_PER_SOL));
+    test.add_account(vault, anchor_account(&state, ID));
+    test.add_account(mint, mint_account(Pubkey::new_unique(), 6));
+    test.add_account(other_mint, mint_account(Pubkey::new_unique(), 6));
+    if prefund > 0 {
+        test.add_account(vault_token_account, system_account(prefund));
+    }
+    let (banks, _payer, _) = test.start().await;
+    Fixture { banks, authority, vault, mint, other_mint, vault_token_account }
+}
+
+impl Fixture {
+    async fn init(&mut self, mint: Pubkey) -> Result<(), TransactionError> {
+        let metas = accounts::InitTokenAccount {
+            vault: self.vault,
+            mint,
+            vault_token_account: self.vault_token_account,
+            authority: self.authority.pubkey(),
+            token_program: spl_token::ID,
+            system_program: system_program::ID,
+        }
+        .to_account_metas(None);
+        let ix = Instruction::new_with_bytes(ID, &instruction::InitTokenAccount {}.data(), metas);
+        send(&mut self.banks, &[ix], &[&self.authority]).await
+    }
+
+    async fn token_account(&mut self) -> spl_token::state::Account {
+        let account = self.banks.get_account(self.vault_token_account).await.unwrap().unwrap();
+        assert_eq!(account.owner, spl_token::ID);
+        spl_token::state::Account::unpack(&account.data).unwrap()
+    }
+}
+
+#[tokio::test]
+async fn the_token_account_holds_the_vault_mint_and_is_owned_by_the_vault() {
+    let mut fixture = vault_without_token_account(0).await;
+    let mint = fixture.mint;
+
+    fixture.init(mint).await.expect("token account created");
+
+    let token_account = fixture.token_account().await;
+    assert_eq!(token_account.mint, fixture.mint);
+    assert_eq!(token_account.owner, fixture.vault);
+    assert_eq!(token_account.amount, 0);
+}
+
+#[tokio::test]
+async fn a_mint_other_than_the_vaults_is_rejected() {
+    let mut fixture = vault_without_token_account(0).await;
+    let other_mint = fixture.other_mint;
+
+    let err = fixture.init(other_mint).await.expect_err("wrong mint");
+
+    assert_eq!(err, custom_error(0, AnchorError::ConstraintHasOne));
+}
+
+#[tokio::test]
+async fn a_pre_funded_address_is_still_initialized() {
+    // A griefer's transfer must not block the vault from ever getting one
+    let mut fixture = vault_without_token_account(1_000).await;
+    let mint = fixture.mint;
+
+    fixture.init(mint).await.expect("token account created");
+
+    assert_eq!(fixture.token_account().await.owner, fixture.vault);
+}
@@ -0,0 +1,68 @@
+//! PDA verification in the account-validation program.
+//!
+//! `verify_pda` recomputes the PDA of the given seeds and returns its
+//! canonical bump when the passed account matches, and fails otherwise.
+
+use account_validation::{accounts, instruction, ValidationError, ID};
+use anchor_lang::{InstructionData, ToAccountMetas};
+use security_tests::{custom_error, program_test};
+use solana_program_test::BanksClient;
+use solana_sdk::{
+    instruction::Instruction,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    transaction::{Transaction, TransactionError},
+};
+
+/// Runs `verify_pda` against `account` and returns the bump it reported.
+async fn verify(banks: &mut BanksClient, payer: &Keypair, account: Pubkey, seeds: &[&[u8]]) -> Result<u8, TransactionError> {
+    let ix = Instruction::new_with_bytes(
+        ID,
+        &instruction::VerifyPda { seeds: seeds.iter().map(|seed| seed.to_vec()).collect() }.data(),
+        accounts::VerifyPda { account }.to_account_metas(None),
+    );
+    let blockhash = banks.get_latest_blockhash().await.unwrap();
+    let tx = Transaction::new_signed_with_payer(&[ix], Some(&payer.pubkey()), &[payer], blockhash);
+    let result = banks.process_transaction_with_metadata(tx).await.unwrap();
+    result.result?;
+
+    // The runtime strips trailing zero bytes, so a bump of 0 comes back empty
+    let data = result
+        .metadata
+        .and_then(|metadata| metadata.return_data)
+        .map(|return_data| return_data.data)
+        .unwrap_or_default();
+    Ok(data.first().copied().unwrap_or(0))
+}
+
+#[tokio::test]
+async fn matching_seeds_return_the_canonical_bump() {
+    let (mut banks, payer, _) = program_test().start().await;
+    let team = Pubkey::new_unique();
+    let wallet = Pubkey::new_unique();
+
+    let (config, config_bump) = Pubkey::find_program_address(&[b"config"], &ID);
+    assert_eq!(verify(&mut banks, &payer, config, &[b"config"]).await, Ok(config_bump));
+
+    let seeds: &[&[u8]] = &[b"member", team.as_ref(), wallet.as_ref()];
+    let (member, member_bump) = Pubkey::find_program_address(seeds, &ID);
+    assert_eq!(verify(&mut banks, &payer, member, seeds).await, Ok(member_bump));
+}
+
+#[tokio::test]
+async fn mismatched_seeds_are_rejected() {
+    let (mut banks, payer, _) = program_test().start().await;
+    let config = Pubkey::find_program_address(&[b"config"], &ID).0;
+
+    // Right account, wrong seeds
+    let err = verify(&mut banks, &payer, config, &[b"pool"]).await.unwrap_err();
+    assert_eq!(err, custom_error(0, ValidationError::InvalidPDA));
+
+    // Right seeds, lookalike account
+    let err = verify(&mut banks, &payer, Pubkey::new_unique(), &[b"config"]).await.unwrap_err();
+    assert_eq!(err, custom_error(0, ValidationError::InvalidPDA));
+
+    // A seed longer than 32 bytes can never derive a PDA
+    let err = verify(&mut banks, &payer, config, &[&[7u8; 33]]).await.unwrap_err();
+    assert_eq!(err, custom_error(0, ValidationError::InvalidSeeds));
+}
@@ -0,0 +1,128 @@
+//! Withdrawal events in the missing-signer-check vault.
+//!
+//! `withdraw_vulnerable` and `withdraw_secure` emit a `WithdrawEvent` with
+//! the amount actually moved - clamped to the vault's balance - and what is
+//! left. `withdraw_secure` emits `WithdrawDenied` for a signer that isn't
+//! the vault's authority.
+//!
+//! The builtin test harness does not surface `sol_log_data` in transaction
+//! logs, so each test reads the event back from the instruction's return
+//! data - both withdrawals return the struct they emit. A denied withdrawal
+//! fails, so only its error is checked here.
+
+use anchor_lang::{AnchorDeserialize, InstructionData, ToAccountMetas};
+use missing_signer_check::{accounts, instruction, SecurityError, Vault, WithdrawEvent, ID};
+use security_tests::{anchor_account, custom_error, program_test, system_account};
+use solana_program_test::BanksClient;
+use solana_sdk::{
+    instruction::Instruction,
+    native_token::LAMPORTS_PER_SOL,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    transaction::{Transaction, TransactionError},
+};
+
+const BALANCE: u64 = LAMPORTS_PER_SOL;
+
+/// vault + authority + amount + remaining_balance
+const EVENT_LEN: usize = 32 + 32 + 8 + 8;
+
+/// A vault booking and holding `BALANCE` above its rent, and someone who
+/// isn't its authority.
+struct Fixture {
+    banks: BanksClient,
+    authority: Keypair,
+    stranger: Keypair,
+    vault: Pubkey,
+}
+
+async fn funded_vault() -> Fixture {
+    let authority = Keypair::new();
+    let stranger = Keypair::new();
+    let (vault, bump) = Pubkey::find_program_address(&[b"vault", authority.pubkey().as_ref()], &ID);
+    let state = Vault { authority: authority.pubkey(), balance: BALANCE, total_withdrawn: 0, withdrawal_limit: u64::MAX, bump };
+    let mut account = anchor_account(&state, ID);
+    account.lamports += BALANCE;
+
+    let mut test = program_test();
+    test.add_account(authority.pubkey(), system_account(LAMPORTS_PER_SOL));
+    test.add_account(stranger.pubkey(), system_account(LAMPORTS_PER_SOL));
+    test.add_account(vault, account);
+    let (banks, _payer, _) = test.start().await;
+    Fixture { banks, authority, stranger, vault }
+}
+
+impl Fixture {
+    /// Sends `instruction` signed by `signer` and decodes the event it returns.
+    async fn send(&mut self, signer: &Keypair, instruction: Instruction) -> Result<WithdrawEvent, TransactionError> {
+        let blockhash = self.banks.get_latest_blockhash().await.unwrap();
+        let tx = Transaction::new_signed_with_payer(&[instruction], Some(&signer.pubkey()), &[signer], blockhash);
+        let result = self.banks.process_transaction_with_metadata(tx).await.unwrap();
+        result.result?;
+
+        let mut data = result.metadata.and_then(|metadata| metadata.return_data).unwrap().data;
+        // The runtime strips trailing zero bytes from return data
+        data.resize(EVENT_LEN, 0);
+        Ok(WithdrawEvent::deserialize(&mut data.as_slice()).unwrap())
+    }
+
+    async fn withdraw_secure(&mut self, signer: &Keypair, amount: u64) -> Result<WithdrawEvent, TransactionError> {
+        let withdraw = Instruction::new_with_bytes(
+            ID,
+            &instruction::WithdrawSecure { amount }.data(),
+            accounts::WithdrawSecure { vault: self.vault, authority: signer.pubkey(), recipient: signer.pubkey() }
+                .to_account_metas(None),
+        );
+        self.send(signer, withdraw).await
+    }
+
+    fn event(&self, amount: u64, remaining_balance: u64) -> WithdrawEvent {
+        WithdrawEvent { vault: self.vault, authority: self.authority.pubkey(), amount, remaining_balance }
+    }
+}
+
+#[tokio::test]
+async fn a_secure_withdrawal_reports_the_amount_and_what_is_left() {
+    let mut fixture = funded_vault().await;
+    let authority = fixture.authority.insecure_clone();
+
+    let event = fixture.withdraw_secure(&authority, 400).await.expect("authority signed");
+
+    assert_eq!(event, fixture.event(400, BALANCE - 400));
+}
+
+#[tokio::test]
+async fn an_oversized_withdrawal_reports_the_clamped_amount() {
+    let mut fixture = funded_vault().await;
+    let authority = fixture.authority.insecure_clone();
+
+    let event = fixture.withdraw_secure(&authority, BALANCE * 5).await.expect("clamped to the balance");
+
+    assert_eq!(event, fixture.event(BALANCE, 0));
+}
+
+#[tokio::test]
+async fn a_vulnerable_withdrawal_reports_the_authority_it_was_never_signed_by() {
+    let mut fixture = funded_vault().await;
+    let stranger = fixture.stranger.insecure_clone();
+    let withdraw = Instruction::new_with_bytes(
+        ID,
+        &instruction::WithdrawVulnerable { amount: BALANCE * 5 }.data(),
+        accounts::WithdrawVulnerable { vault: fixture.vault, authority: fixture.authority.pubkey(), recipient: stranger.pubkey() }
+            .to_account_metas(None),
+    );
+
+    let event = fixture.send(&stranger, withdraw).await.expect("no signature needed");
+
+    assert_eq!(event, fixture.event(BALANCE, 0));
+}
+
+#[tokio::test]
+async fn a_signer_that_is_not_the_authority_is_denied() {
+    let mut fixture = funded_vault().await;
+    let stranger = fixture.stranger.insecure_clone();
+
+    let err = fixture.withdraw_secure(&stranger, BALANCE).await.expect_err("wrong key");
+
+    assert_eq!(err, custom_error(0, SecurityError::Unauthorized));
+}
@@ -0,0 +1,119 @@
+//! The lifetime withdrawal cap on the missing-signer-check vault.
+//!
+//! `Vault::book_withdrawal` caps `total_withdrawn` at `withdrawal_limit`
+//! across every withdrawal, so splitting a large withdrawal into small ones
+//! gets no further than the cap. Both withdrawal paths book through it.
+
+use anchor_lang::{InstructionData, ToAccountMetas};
+use missing_signer_check::{accounts, instruction, CustomError, Vault, ID};
+use security_tests::{anchor_account, custom_error, load, program_test, send, system_account};
+use solana_program_test::BanksClient;
+use solana_sdk::{
+    instruction::Instruction,
+    native_token::LAMPORTS_PER_SOL,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    transaction::TransactionError,
+};
+
+const BALANCE: u64 = LAMPORTS_PER_SOL;
+const LIMIT: u64 = 1_000;
+
+/// A vault holding `BALANCE` above its rent with a lifetime cap of
+/// `LIMIT`, and someone who isn't its authority.
+struct Fixture {
+    banks: BanksClient,
+    authority: Keypair,
+    stranger: Keypair,
+    vault: Pubkey,
+}
+
+async fn capped_vault() -> Fixture {
+    let (authority, stranger) = (Keypair::new(), Keypair::new());
+    let (vault, bump) = Pubkey::find_program_address(&[b"vault", authority.pubkey().as_ref()], &ID);
+    let state = Vault { authority: authority.pubkey(), balance: BALANCE, total_withdrawn: 0, withdrawal_limit: LIMIT, bump };
+    let mut account = anchor_account(&state, ID);
+    account.lamports += BALANCE;
+
+    let mut test = program_test();
+    test.add_account(authority.pubkey(), system_account(LAMPORTS_PER_SOL));
+    test.add_account(stranger.pubkey(), system_account(LAMPORTS_PER_SOL));
+    test.add_account(vault, account);
+    let (banks, _payer, _) = test.start().await;
+    Fixture { banks, authority, stranger, vault }
+}
+
+impl Fixture {
+    async fn withdraw_secure(&mut self, amount: u64) -> Result<(), TransactionError> {
+        let authority = self.authority.insecure_clone();
+        let withdraw = Instruction::new_with_bytes(
+            ID,
+            &instruction::WithdrawSecure { amount }.data(),
+            accounts::WithdrawSecure { vault: self.vault, authority: authority.pubkey(), recipient: authority.pubkey() }
+                .to_account_metas(None),
+        );
+        send(&mut self.banks, &[withdraw], &[&authority]).await
+    }
+
+    /// The stranger withdraws to themselves, naming the real authority
+    async fn withdraw_vulnerable(&mut self, amount: u64) -> Result<(), TransactionError> {
+        let stranger = self.stranger.insecure_clone();
+        let withdraw = Instruction::new_with_bytes(
+            ID,
+            &instruction::WithdrawVulnerable { amount }.data(),
+            accounts::WithdrawVulnerable {
+                vault: self.vault,
+                authority: self.authority.pubkey(),
+                recipient: stranger.pubkey(),
+            }
+            .to_account_metas(None),
+        );
+        send(&mut self.banks, &[withdraw], &[&stranger]).await
+    }
+
+    async fn vault(&mut self) -> Vault {
+        load(&mut self.banks, self.vault).await
+    }
+}
+
+fn limit_reached() -> TransactionError {
+    custom_error(0, CustomError::WithdrawalLimitReached)
+}
+
+#[tokio::test]
+async fn withdrawals_add_up_to_the_cap() {
+    let mut fixture = capped_vault().await;
+    fixture.withdraw_secure(400).await.expect("400 of 1000");
+    fixture.withdraw_secure(350).await.expect("750 of 1000");
+
+    // Each one alone is under the cap; together they aren't
+    let err = fixture.withdraw_secure(251).await.expect_err("1001 of 1000");
+    assert_eq!(err, limit_reached());
+
+    fixture.withdraw_secure(250).await.expect("exactly the cap");
+    let vault = fixture.vault().await;
+    assert_eq!((vault.total_withdrawn, vault.balance), (LIMIT, BALANCE - LIMIT));
+}
+
+#[tokio::test]
+async fn nothing_more_leaves_once_the_cap_is_reached() {
+    let mut fixture = capped_vault().await;
+    fixture.withdraw_secure(LIMIT).await.expect("the whole cap at once");
+
+    let err = fixture.withdraw_secure(1).await.expect_err("cap spent");
+
+    assert_eq!(err, limit_reached());
+    assert_eq!(fixture.vault().await.balance, BALANCE - LIMIT);
+}
+
+#[tokio::test]
+async fn the_cap_bounds_the_unsigned_drain_too() {
+    let mut fixture = capped_vault().await;
+    fixture.withdraw_vulnerable(600).await.expect("no signature needed");
+
+    let err = fixture.withdraw_vulnerable(401).await.expect_err("over the cap");
+    assert_eq!(err, limit_reached());
+
+    fixture.withdraw_vulnerable(400).await.expect("up to the cap");
+    assert_eq!(fixture.vault().await.total_withdrawn, LIMIT);
+}
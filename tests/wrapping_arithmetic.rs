@@ -0,0 +1,154 @@
+//! What the integer-overflow program's unchecked arithmetic really does.
+//!
+//! The workspace builds with overflow-checks, so `deposit_vulnerable` and
+//! `withdraw_vulnerable` panic here instead of wrapping, and so does
+//! `mint_rewards_vulnerable`. Under the
+//! `wrapping` profile (release, overflow-checks off) they wrap the way a
+//! program deployed without overflow-checks would:
+//!
+//! ```text
+//! cargo test --profile wrapping -p security-tests --test wrapping_arithmetic -- --include-ignored
+//! ```
+//!
+//! The secure counterparts fail with the same error under either profile.
+
+use anchor_lang::{AccountDeserialize, InstructionData, ToAccountMetas};
+use anchor_spl::token::{spl_token, TokenAccount};
+use integer_overflow::{accounts, instruction, RewardMinter, SecurityError, UserAccount, Vault, ID};
+use security_tests::{anchor_account, custom_error, load, mint_account, program_test, system_account, token_account};
+use solana_program_test::BanksClient;
+use solana_sdk::{
+    hash::Hash,
+    instruction::Instruction,
+    native_token::LAMPORTS_PER_SOL,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    transaction::{Transaction, TransactionError},
+};
+use std::hint::black_box;
+
+/// Overflow-checks are set per profile, so if this crate wraps, so does
+/// the program under test.
+fn assert_wrapping_profile() {
+    let wrapped = std::panic::catch_unwind(|| black_box(u64::MAX) + black_box(1)).is_ok();
+    assert!(wrapped, "built with overflow-checks; run under --profile wrapping");
+}
+
+/// The vault holding `u64::MAX - 100`, and the payer's account holding 100.
+struct Fixture {
+    banks: BanksClient,
+    payer: Keypair,
+    blockhash: Hash,
+    vault: Pubkey,
+    user_account: Pubkey,
+}
+
+async fn nearly_full() -> Fixture {
+    let payer = Keypair::new();
+    let authority = Pubkey::new_unique();
+    let (vault, vault_bump) = Pubkey::find_program_address(&[b"vault", authority.as_ref()], &ID);
+    let (user_account, bump) = Pubkey::find_program_address(&[b"user", payer.pubkey().as_ref()], &ID);
+    let mut test = program_test();
+    let vault_state = Vault { authority, total_deposits: u64::MAX - 100, protocol_fees: 0, bump: vault_bump };
+    test.add_account(vault, anchor_account(&vault_state, ID));
+    test.add_account(user_account, anchor_account(&UserAccount { owner: payer.pubkey(), balance: 100, bump }, ID));
+    test.add_account(payer.pubkey(), system_account(LAMPORTS_PER_SOL));
+    let (banks, _, blockhash) = test.start().await;
+    Fixture { banks, payer, blockhash, vault, user_account }
+}
+
+impl Fixture {
+    async fn send(&mut self, data: impl InstructionData, accounts: impl ToAccountMetas) -> Result<(), TransactionError> {
+        let ix = Instruction::new_with_bytes(ID, &data.data(), accounts.to_account_metas(None));
+        let tx = Transaction::new_signed_with_payer(&[ix], Some(&self.payer.pubkey()), &[&self.payer], self.blockhash);
+        self.banks.process_transaction(tx).await.map_err(|err| err.unwrap())
+    }
+
+    async fn deposit(&mut self, data: impl InstructionData) -> Result<(), TransactionError> {
+        let accounts = accounts::Deposit { vault: self.vault, depositor: self.payer.pubkey() };
+        self.send(data, accounts).await
+    }
+
+    async fn withdraw(&mut self, data: impl InstructionData) -> Result<(), TransactionError> {
+        let accounts = accounts::Withdraw { user_account: self.user_account, owner: self.payer.pubkey() };
+        self.send(data, accounts).await
+    }
+
+    async fn load<T: AccountDeserialize>(&mut self, key: Pubkey) -> T {
+        load(&mut self.banks, key).await
+    }
+}
+
+#[tokio::test]
+#[ignore = "needs the wrapping profile"]
+async fn an_unchecked_deposit_wraps_the_total() {
+    assert_wrapping_profile();
+    let mut fixture = nearly_full().await;
+
+    fixture.deposit(instruction::DepositVulnerable { amount: 200 }).await.expect("nothing is checked");
+
+    // u64::MAX - 100 + 200 = u64::MAX + 100, which wraps to 99
+    assert_eq!(fixture.load::<Vault>(fixture.vault).await.total_deposits, 99);
+}
+
+#[tokio::test]
+#[ignore = "needs the wrapping profile"]
+async fn an_unchecked_withdrawal_wraps_the_balance() {
+    assert_wrapping_profile();
+    let mut fixture = nearly_full().await;
+
+    fixture.withdraw(instruction::WithdrawVulnerable { amount: 101 }).await.expect("nothing is checked");
+
+    // 100 - 101 wraps to u64::MAX
+    assert_eq!(fixture.load::<UserAccount>(fixture.user_account).await.balance, u64::MAX);
+}
+
+#[tokio::test]
+#[ignore = "needs the wrapping profile"]
+async fn an_unchecked_reward_mints_the_wrapped_product() {
+    assert_wrapping_profile();
+    let mint = Pubkey::new_unique();
+    let (minter, bump) = Pubkey::find_program_address(&[b"mint_authority", mint.as_ref()], &ID);
+    let destination = Pubkey::new_unique();
+    let admin = Keypair::new();
+    let mut test = program_test();
+    test.add_account(mint, mint_account(minter, 6));
+    let state = RewardMinter { admin: admin.pubkey(), mint, max_supply: u64::MAX, bump };
+    test.add_account(minter, anchor_account(&state, ID));
+    test.add_account(destination, token_account(mint, Pubkey::new_unique(), 0));
+    let (mut banks, payer, blockhash) = test.start().await;
+
+    let ix = Instruction::new_with_bytes(
+        ID,
+        &instruction::MintRewardsVulnerable { base: 1 << 32, multiplier: (1 << 32) + 5 }.data(),
+        accounts::MintReward { mint, destination, mint_authority: minter, admin: admin.pubkey(), token_program: spl_token::ID }
+            .to_account_metas(None),
+    );
+    let tx = Transaction::new_signed_with_payer(&[ix], Some(&payer.pubkey()), &[&payer, &admin], blockhash);
+    banks.process_transaction(tx).await.expect("nothing is checked");
+
+    // 2^32 * (2^32 + 5) = 2^64 + 5 * 2^32, which wraps to 5 * 2^32
+    let account = banks.get_account(destination).await.unwrap().unwrap();
+    let minted = TokenAccount::try_deserialize(&mut account.data.as_slice()).unwrap().amount;
+    assert_eq!(minted, 5 << 32);
+}
+
+#[tokio::test]
+async fn a_checked_deposit_fails_with_overflow() {
+    let mut fixture = nearly_full().await;
+
+    let err = fixture.deposit(instruction::DepositSecure { amount: 200 }).await.expect_err("past u64::MAX");
+
+    assert_eq!(err, custom_error(0, SecurityError::Overflow));
+    assert_eq!(fixture.load::<Vault>(fixture.vault).await.total_deposits, u64::MAX - 100);
+}
+
+#[tokio::test]
+async fn a_checked_withdrawal_fails_with_insufficient_funds() {
+    let mut fixture = nearly_full().await;
+
+    let err = fixture.withdraw(instruction::WithdrawSecure { amount: 101 }).await.expect_err("below zero");
+
+    assert_eq!(err, custom_error(0, SecurityError::InsufficientFunds));
+    assert_eq!(fixture.load::<UserAccount>(fixture.user_account).await.balance, 100);
+}
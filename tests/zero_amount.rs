@@ -0,0 +1,164 @@
+//! Zero amounts on the secure value-moving instructions.
+//!
+//! Every lesson program that moves value rejects `amount = 0` up front
+//! with the shared `SecurityError::ZeroAmount`, rather than succeeding as
+//! a no-op.
+//! One instruction per program, each otherwise valid, so the amount is the
+//! only thing wrong.
+
+use anchor_lang::{InstructionData, ToAccountMetas};
+use anchor_spl::token;
+use security_common::SecurityError;
+use security_tests::{anchor_account, custom_error, mint_account, pda, program_test, send, system_account, token_account};
+use solana_sdk::{
+    account::Account,
+    instruction::Instruction,
+    native_token::LAMPORTS_PER_SOL,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    transaction::TransactionError,
+};
+
+/// Sends `ix`, signed by `signer`, against `accounts`; it must fail
+async fn rejected(accounts: Vec<(Pubkey, Account)>, ix: Instruction, signer: &Keypair) -> TransactionError {
+    let mut test = program_test();
+    test.add_account(signer.pubkey(), system_account(LAMPORTS_PER_SOL));
+    for (address, account) in accounts {
+        test.add_account(address, account);
+    }
+    let (mut banks, _, _) = test.start().await;
+    send(&mut banks, &[ix], &[signer]).await.expect_err("zero amount")
+}
+
+#[tokio::test]
+async fn missing_signer_check_rejects_a_zero_withdrawal() {
+    use missing_signer_check::{accounts, instruction, Vault, ID};
+
+    let authority = Keypair::new();
+    let (vault, bump) = Pubkey::find_program_address(&[b"vault", authority.pubkey().as_ref()], &ID);
+    let state = Vault { authority: authority.pubkey(), balance: 1_000, total_withdrawn: 0, withdrawal_limit: u64::MAX, bump };
+    let ix = Instruction::new_with_bytes(
+        ID,
+        &instruction::WithdrawSecure { amount: 0 }.data(),
+        accounts::WithdrawSecure { vault, authority: authority.pubkey(), recipient: authority.pubkey() }
+            .to_account_metas(None),
+    );
+
+    let err = rejected(vec![(vault, anchor_account(&state, ID))], ix, &authority).await;
+
+    assert_eq!(err, custom_error(0, SecurityError::ZeroAmount));
+}
+
+#[tokio::test]
+async fn account_validation_rejects_a_zero_swap() {
+    use account_validation::{accounts, instruction, Config, Pool, ID};
+
+    let user = Keypair::new();
+    let (config, config_bump) = Pubkey::find_program_address(&[b"config"], &ID);
+    let authority = Pubkey::new_unique();
+    let (pool, pool_bump) = Pubkey::find_program_address(&[b"pool", authority.as_ref()], &ID);
+    let config_state = Config { admin: authority, fee_bps: 100, fee_holiday_until: 0, max_positions: 1, dust_threshold: 0, bump: config_bump };
+    let pool_state = Pool {
+        authority,
+        total_deposited: 0,
+        reward_rate: 10,
+        config,
+        total_accrued: 0,
+        global_tvl_cap: u64::MAX,
+        lock_secs: 0,
+        dust_swept: 0,
+        min_hold_secs: 0,
+        early_exit_bps: 0,
+        early_exit_fees: 0,
+        deposit_mint: Pubkey::default(),
+        reward_mint: Pubkey::default(),
+        vault: Pubkey::default(),
+        bump: pool_bump,
+    };
+    let ix = Instruction::new_with_bytes(
+        ID,
+        &instruction::SwapSecure { amount: 0, min_out: 0, deadline: i64::MAX }.data(),
+        accounts::SwapSecure { config, pool, user: user.pubkey() }.to_account_metas(None),
+    );
+
+    let accounts = vec![(config, anchor_account(&config_state, ID)), (pool, anchor_account(&pool_state, ID))];
+    let err = rejected(accounts, ix, &user).await;
+
+    assert_eq!(err, custom_error(0, SecurityError::ZeroAmount));
+}
+
+#[tokio::test]
+async fn integer_overflow_rejects_a_zero_deposit() {
+    use integer_overflow::{accounts, instruction, Vault, ID};
+
+    let depositor = Keypair::new();
+    let authority = Pubkey::new_unique();
+    let (vault, bump) = Pubkey::find_program_address(&[b"vault", authority.as_ref()], &ID);
+    let state = Vault { authority, total_deposits: 0, protocol_fees: 0, bump };
+    let ix = Instruction::new_with_bytes(
+        ID,
+        &instruction::DepositSecure { amount: 0 }.data(),
+        accounts::Deposit { vault, depositor: depositor.pubkey() }.to_account_metas(None),
+    );
+
+    let err = rejected(vec![(vault, anchor_account(&state, ID))], ix, &depositor).await;
+
+    assert_eq!(err, custom_error(0, SecurityError::ZeroAmount));
+}
+
+#[tokio::test]
+async fn arbitrary_cpi_rejects_a_zero_transfer() {
+    use arbitrary_cpi::{accounts, instruction, Vault, ID};
+
+    let authority = Keypair::new();
+    let mint = Pubkey::new_unique();
+    let (vault, bump) = Pubkey::find_program_address(&[b"vault", authority.pubkey().as_ref()], &ID);
+    let vault_token_account = pda(&[b"vault_token", vault.as_ref()], &ID);
+    let user_token_account = Pubkey::new_unique();
+    let state = Vault { authority: authority.pubkey(), mint, balance: 1_000, vault_authority_bump: 0, bump };
+    let ix = Instruction::new_with_bytes(
+        ID,
+        &instruction::SafeTransfer { amount: 0 }.data(),
+        accounts::SafeTransfer {
+            vault,
+            mint,
+            vault_token_account,
+            user_token_account,
+            token_program: token::ID,
+            authority: authority.pubkey(),
+        }
+        .to_account_metas(None),
+    );
+
+    let accounts = vec![
+        (vault, anchor_account(&state, ID)),
+        (mint, mint_account(Pubkey::new_unique(), 6)),
+        (vault_token_account, token_account(mint, vault, 1_000)),
+        (user_token_account, token_account(mint, authority.pubkey(), 0)),
+    ];
+    let err = rejected(accounts, ix, &authority).await;
+
+    assert_eq!(err, custom_error(0, SecurityError::ZeroAmount));
+}
+
+#[tokio::test]
+async fn reinitialization_rejects_a_zero_deposit() {
+    use reinitialization::{accounts, instruction, DisabledInstructions, VaultSecure, ID};
+
+    let depositor = Keypair::new();
+    let authority = Pubkey::new_unique();
+    let (vault, vault_bump) = Pubkey::find_program_address(&[b"vault", authority.as_ref()], &ID);
+    let (kill_switch, switch_bump) = Pubkey::find_program_address(&[b"disabled_instructions"], &ID);
+    let vault_state = VaultSecure { authority, balance: 0, total_deposits: 0, total_withdrawals: 0, bump: vault_bump };
+    let switch_state = DisabledInstructions { admin: authority, disabled: vec![], bump: switch_bump };
+    let ix = Instruction::new_with_bytes(
+        ID,
+        &instruction::Deposit { amount: 0 }.data(),
+        accounts::Deposit { vault, kill_switch, depositor: depositor.pubkey() }.to_account_metas(None),
+    );
+
+    let accounts = vec![(vault, anchor_account(&vault_state, ID)), (kill_switch, anchor_account(&switch_state, ID))];
+    let err = rejected(accounts, ix, &depositor).await;
+
+    assert_eq!(err, custom_error(0, SecurityError::ZeroAmount));
+}